@@ -105,14 +105,18 @@ struct BallistaBenchmarkOpt {
 
 #[derive(Debug, StructOpt, Clone)]
 struct DataFusionBenchmarkOpt {
-    /// Query number
+    /// Query number. All queries are run if not set
     #[structopt(short, long)]
-    query: usize,
+    query: Option<usize>,
 
     /// Activate debug mode to see query results
     #[structopt(short, long)]
     debug: bool,
 
+    /// Print the metrics of each operator of the last iteration of each query
+    #[structopt(long = "metrics")]
+    metrics: bool,
+
     /// Number of iterations of each test run
     #[structopt(short = "i", long = "iterations", default_value = "3")]
     iterations: usize,
@@ -238,20 +242,38 @@ async fn benchmark_datafusion(opt: DataFusionBenchmarkOpt) -> Result<Vec<RecordB
         }
     }
 
-    let mut millis = vec![];
-    // run benchmark
+    let queries = match opt.query {
+        Some(query) => vec![query],
+        None => (1..=22).collect(),
+    };
     let mut result: Vec<RecordBatch> = Vec::with_capacity(1);
-    for i in 0..opt.iterations {
-        let start = Instant::now();
-        let plan = create_logical_plan(&mut ctx, opt.query)?;
-        result = execute_query(&mut ctx, &plan, opt.debug).await?;
-        let elapsed = start.elapsed().as_secs_f64() * 1000.0;
-        millis.push(elapsed as f64);
-        println!("Query {} iteration {} took {:.1} ms", opt.query, i, elapsed);
+    let mut summary = vec![];
+    for query in queries {
+        let mut millis = vec![];
+        // run benchmark
+        for i in 0..opt.iterations {
+            let start = Instant::now();
+            let plan = create_logical_plan(&mut ctx, query)?;
+            let print_metrics = opt.metrics && i + 1 == opt.iterations;
+            result = execute_query(&mut ctx, &plan, opt.debug, print_metrics).await?;
+            let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+            millis.push(elapsed as f64);
+            println!("Query {} iteration {} took {:.1} ms", query, i, elapsed);
+        }
+
+        let avg = millis.iter().sum::<f64>() / millis.len() as f64;
+        println!("Query {} avg time: {:.2} ms", query, avg);
+        summary.push((query, avg));
     }
 
-    let avg = millis.iter().sum::<f64>() / millis.len() as f64;
-    println!("Query {} avg time: {:.2} ms", opt.query, avg);
+    if summary.len() > 1 {
+        println!("Query avg time (ms)");
+        for (query, avg) in &summary {
+            println!("Q{:<4} {:>10.2}", query, avg);
+        }
+        let total = summary.iter().map(|(_, avg)| avg).sum::<f64>();
+        println!("Total {:>10.2}", total);
+    }
 
     Ok(result)
 }
@@ -358,6 +380,7 @@ async fn execute_query(
     ctx: &mut ExecutionContext,
     plan: &LogicalPlan,
     debug: bool,
+    print_metrics: bool,
 ) -> Result<Vec<RecordBatch>> {
     if debug {
         println!("=== Logical plan ===\n{:?}\n", plan);
@@ -374,13 +397,15 @@ async fn execute_query(
         );
     }
     let result = collect(physical_plan.clone()).await?;
-    if debug {
+    if debug || print_metrics {
         println!(
             "=== Physical plan with metrics ===\n{}\n",
             DisplayableExecutionPlan::with_metrics(physical_plan.as_ref())
                 .indent()
                 .to_string()
         );
+    }
+    if debug {
         pretty::print_batches(&result)?;
     }
     Ok(result)
@@ -1007,7 +1032,7 @@ mod tests {
         }
 
         let plan = create_logical_plan(&mut ctx, n)?;
-        execute_query(&mut ctx, &plan, false).await?;
+        execute_query(&mut ctx, &plan, false, false).await?;
 
         Ok(())
     }
@@ -1043,8 +1068,9 @@ mod tests {
 
             // run the query to compute actual results of the query
             let opt = DataFusionBenchmarkOpt {
-                query: n,
+                query: Some(n),
                 debug: false,
+                metrics: false,
                 iterations: 1,
                 concurrency: 2,
                 batch_size: 8192,
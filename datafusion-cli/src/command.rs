@@ -0,0 +1,175 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Backslash commands of the CLI, such as `\timing`
+use crate::print_format::PrintFormat;
+use crate::PrintOptions;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::ExecutionContext;
+use datafusion::prelude::CsvReadOptions;
+use std::str::FromStr;
+
+/// A command of the CLI, a line starting with a backslash
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Command {
+    /// `\q`, exit the CLI
+    Quit,
+    /// `\?`, describe the commands
+    Help,
+    /// `\d`, list the tables
+    ListTables,
+    /// `\timing [on|off]`, print the time taken by queries, toggled without argument
+    Timing(Option<bool>),
+    /// `\format <format>`, the output format of the results
+    Format(PrintFormat),
+    /// `\csv <name> <path>`, register the CSV files of `path`, with a header, as a table
+    RegisterCsv { name: String, path: String },
+    /// `\parquet <name> <path>`, register the Parquet files of `path` as a table
+    RegisterParquet { name: String, path: String },
+}
+
+const HELP: &str = "\
+\\q                      exit
+\\?                      show this help
+\\d                      list the tables
+\\timing [on|off]        print the time taken by queries, toggled without argument
+\\format <format>        output format: csv, tsv, table, json or ndjson
+\\csv <name> <path>      register the CSV files, with a header, of path as a table
+\\parquet <name> <path>  register the Parquet files of path as a table";
+
+impl FromStr for Command {
+    type Err = DataFusionError;
+
+    fn from_str(line: &str) -> Result<Self> {
+        let mut words = line.trim_end().trim_end_matches(';').split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let args = words.collect::<Vec<_>>();
+        let table = |args: &[&str]| match args {
+            [name, path] => Ok((name.to_string(), path.to_string())),
+            _ => Err(DataFusionError::Plan(format!(
+                "{} expects a table name and a path",
+                command
+            ))),
+        };
+        Ok(match (command, args.as_slice()) {
+            ("\\q", []) => Self::Quit,
+            ("\\?", []) => Self::Help,
+            ("\\d", []) => Self::ListTables,
+            ("\\timing", []) => Self::Timing(None),
+            ("\\timing", ["on"]) => Self::Timing(Some(true)),
+            ("\\timing", ["off"]) => Self::Timing(Some(false)),
+            ("\\format", [format]) => {
+                Self::Format(format.parse::<PrintFormat>().map_err(|_| {
+                    DataFusionError::Plan(format!("Invalid format '{}'", format))
+                })?)
+            }
+            ("\\csv", args) => {
+                let (name, path) = table(args)?;
+                Self::RegisterCsv { name, path }
+            }
+            ("\\parquet", args) => {
+                let (name, path) = table(args)?;
+                Self::RegisterParquet { name, path }
+            }
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "Invalid command '{}', \\? lists the commands",
+                    line.trim()
+                )))
+            }
+        })
+    }
+}
+
+impl Command {
+    /// Whether `line` is a command rather than a part of a query
+    pub fn is_command(line: &str) -> bool {
+        line.trim_start().starts_with('\\')
+    }
+
+    /// Execute the command, except `\q` which is handled by the caller
+    pub fn execute(
+        &self,
+        ctx: &mut ExecutionContext,
+        print_options: &mut PrintOptions,
+    ) -> Result<()> {
+        match self {
+            Self::Quit => {}
+            Self::Help => println!("{}", HELP),
+            Self::ListTables => {
+                let mut tables = ctx.tables()?.into_iter().collect::<Vec<_>>();
+                tables.sort();
+                for table in tables {
+                    println!("{}", table);
+                }
+            }
+            Self::Timing(timing) => {
+                let timing = timing.unwrap_or(!print_options.timing);
+                print_options.timing = timing;
+                println!("Timing is {}.", if timing { "on" } else { "off" });
+            }
+            Self::Format(format) => print_options.format = format.clone(),
+            Self::RegisterCsv { name, path } => {
+                ctx.register_csv(name, path, CsvReadOptions::new())?
+            }
+            Self::RegisterParquet { name, path } => ctx.register_parquet(name, path)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands() -> Result<()> {
+        assert_eq!(Command::Quit, "\\q".parse()?);
+        assert_eq!(Command::Timing(None), "\\timing;".parse()?);
+        assert_eq!(Command::Timing(Some(false)), "\\timing off".parse()?);
+        assert_eq!(Command::Format(PrintFormat::Csv), "\\format csv".parse()?);
+        assert_eq!(
+            Command::RegisterParquet {
+                name: "t".to_string(),
+                path: "data/t.parquet".to_string()
+            },
+            "\\parquet t data/t.parquet".parse()?
+        );
+        assert!("\\csv t".parse::<Command>().is_err());
+        assert!("\\format xml".parse::<Command>().is_err());
+        assert!("\\x".parse::<Command>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn timing_leaves_quiet_alone() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        let mut print_options = PrintOptions {
+            format: PrintFormat::Table,
+            quiet: true,
+            timing: false,
+            table_formatter: Default::default(),
+        };
+        Command::Timing(None).execute(&mut ctx, &mut print_options)?;
+        assert!(print_options.timing);
+        assert!(print_options.quiet);
+        Command::Timing(Some(false)).execute(&mut ctx, &mut print_options)?;
+        assert!(!print_options.timing);
+        assert!(print_options.quiet);
+        Ok(())
+    }
+}
@@ -14,9 +14,11 @@
 // KIND, either express or implied.  See the License for the
 // specific language governing permissions and limitations
 // under the License.
+pub mod command;
 pub mod print_format;
 
 use datafusion::arrow::record_batch::RecordBatch;
+use datafusion::arrow_print::BatchFormatter;
 use datafusion::error::Result;
 use print_format::PrintFormat;
 use std::time::Instant;
@@ -25,6 +27,8 @@ use std::time::Instant;
 pub struct PrintOptions {
     pub format: PrintFormat,
     pub quiet: bool,
+    pub timing: bool,
+    pub table_formatter: BatchFormatter,
 }
 
 fn print_timing_info(row_count: usize, now: Instant) {
@@ -40,12 +44,12 @@ impl PrintOptions {
     /// print the batches to stdout using the specified format
     pub fn print_batches(&self, batches: &[RecordBatch], now: Instant) -> Result<()> {
         if batches.is_empty() {
-            if !self.quiet {
+            if self.timing {
                 print_timing_info(0, now);
             }
         } else {
-            self.format.print_batches(batches)?;
-            if !self.quiet {
+            self.format.print_batches(batches, &self.table_formatter)?;
+            if self.timing {
                 let row_count: usize = batches.iter().map(|b| b.num_rows()).sum();
                 print_timing_info(row_count, now);
             }
@@ -18,9 +18,11 @@
 #![allow(bare_trait_objects)]
 
 use clap::{crate_version, App, Arg};
+use datafusion::arrow_print::BatchFormatter;
 use datafusion::error::Result;
 use datafusion::execution::context::{ExecutionConfig, ExecutionContext};
 use datafusion_cli::{
+    command::Command,
     print_format::{all_print_formats, PrintFormat},
     PrintOptions,
 };
@@ -82,6 +84,20 @@ pub async fn main() {
                 )
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("max-rows")
+                .help("The maximum number of rows of the tables, default to all of them")
+                .long("max-rows")
+                .validator(is_valid_count)
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("max-column-width")
+                .help("The width above which the values of tables are truncated")
+                .long("max-column-width")
+                .validator(is_valid_count)
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("quiet")
                 .help("Reduce printing other than the results and work quietly")
@@ -112,7 +128,19 @@ pub async fn main() {
         .expect("Invalid format");
 
     let quiet = matches.is_present("quiet");
-    let print_options = PrintOptions { format, quiet };
+    let mut table_formatter = BatchFormatter::new().with_null("NULL");
+    if let Some(max_rows) = matches.value_of("max-rows") {
+        table_formatter = table_formatter.with_max_rows(max_rows.parse().unwrap());
+    }
+    if let Some(width) = matches.value_of("max-column-width") {
+        table_formatter = table_formatter.with_max_column_width(width.parse().unwrap());
+    }
+    let print_options = PrintOptions {
+        format,
+        quiet,
+        timing: !quiet,
+        table_formatter,
+    };
 
     if let Some(file_paths) = matches.values_of("file") {
         let files = file_paths
@@ -131,7 +159,7 @@ pub async fn main() {
 async fn exec_from_lines(
     ctx: &mut ExecutionContext,
     reader: &mut BufReader<File>,
-    print_options: PrintOptions,
+    mut print_options: PrintOptions,
 ) {
     let mut query = "".to_owned();
 
@@ -140,6 +168,13 @@ async fn exec_from_lines(
             Ok(line) if line.starts_with("--") => {
                 continue;
             }
+            Ok(line) if query.is_empty() && Command::is_command(&line) => {
+                match exec_command(ctx, &mut print_options, &line) {
+                    Ok(Command::Quit) => break,
+                    Ok(_) => {}
+                    Err(err) => println!("{:?}", err),
+                }
+            }
             Ok(line) => {
                 let line = line.trim_end();
                 query.push_str(line);
@@ -168,7 +203,10 @@ async fn exec_from_lines(
     }
 }
 
-async fn exec_from_repl(execution_config: ExecutionConfig, print_options: PrintOptions) {
+async fn exec_from_repl(
+    execution_config: ExecutionConfig,
+    mut print_options: PrintOptions,
+) {
     let mut ctx = ExecutionContext::with_config(execution_config);
 
     let mut rl = Editor::<()>::new();
@@ -183,6 +221,14 @@ async fn exec_from_repl(execution_config: ExecutionConfig, print_options: PrintO
             Ok(ref line) if line.starts_with("--") => {
                 continue;
             }
+            Ok(ref line) if query.is_empty() && Command::is_command(line) => {
+                rl.add_history_entry(line.trim_end());
+                match exec_command(&mut ctx, &mut print_options, line) {
+                    Ok(Command::Quit) => break,
+                    Ok(_) => {}
+                    Err(err) => println!("{:?}", err),
+                }
+            }
             Ok(ref line) if line.trim_end().ends_with(';') => {
                 query.push_str(line.trim_end());
                 rl.add_history_entry(query.clone());
@@ -228,20 +274,42 @@ fn is_valid_batch_size(size: String) -> std::result::Result<(), String> {
     }
 }
 
+fn is_valid_count(count: String) -> std::result::Result<(), String> {
+    match count.parse::<usize>() {
+        Ok(_) => Ok(()),
+        _ => Err(format!("Invalid count '{}'", count)),
+    }
+}
+
 fn is_exit_command(line: &str) -> bool {
     let line = line.trim_end().to_lowercase();
     line == "quit" || line == "exit"
 }
 
+/// Executes the backslash command of `line`, which is returned
+fn exec_command(
+    ctx: &mut ExecutionContext,
+    print_options: &mut PrintOptions,
+    line: &str,
+) -> Result<Command> {
+    let command = line.parse::<Command>()?;
+    command.execute(ctx, print_options)?;
+    Ok(command)
+}
+
 async fn exec_and_print(
     ctx: &mut ExecutionContext,
-    print_options: PrintOptions,
+    mut print_options: PrintOptions,
     sql: String,
 ) -> Result<()> {
     let df = ctx.sql(&sql)?;
     let now = Instant::now();
     let results = df.collect().await?;
 
+    // the floats are formatted as configured by the `SET`s of the session
+    print_options.table_formatter = print_options
+        .table_formatter
+        .with_float_format(ctx.float_format()?);
     print_options.print_batches(&results, now)?;
     Ok(())
 }
@@ -19,7 +19,7 @@
 use arrow::csv::writer::WriterBuilder;
 use arrow::json::{ArrayWriter, LineDelimitedWriter};
 use datafusion::arrow::record_batch::RecordBatch;
-use datafusion::arrow::util::pretty;
+use datafusion::arrow_print::BatchFormatter;
 use datafusion::error::{DataFusionError, Result};
 use std::fmt;
 use std::str::FromStr;
@@ -100,12 +100,17 @@ fn print_batches_with_sep(batches: &[RecordBatch], delimiter: u8) -> Result<Stri
 }
 
 impl PrintFormat {
-    /// print the batches to stdout using the specified format
-    pub fn print_batches(&self, batches: &[RecordBatch]) -> Result<()> {
+    /// print the batches to stdout using the specified format, tables are formatted
+    /// by `table_formatter`
+    pub fn print_batches(
+        &self,
+        batches: &[RecordBatch],
+        table_formatter: &BatchFormatter,
+    ) -> Result<()> {
         match self {
             Self::Csv => println!("{}", print_batches_with_sep(batches, b',')?),
             Self::Tsv => println!("{}", print_batches_with_sep(batches, b'\t')?),
-            Self::Table => pretty::print_batches(batches)?,
+            Self::Table => table_formatter.print(batches)?,
             Self::Json => println!("{}", batches_to_json!(ArrayWriter, batches)),
             Self::NdJson => {
                 println!("{}", batches_to_json!(LineDelimitedWriter, batches))
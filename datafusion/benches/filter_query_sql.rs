@@ -16,7 +16,7 @@
 // under the License.
 
 use arrow::{
-    array::{Float32Array, Float64Array},
+    array::{Float32Array, Float64Array, Int64Array, StringArray},
     datatypes::{DataType, Field, Schema},
     record_batch::RecordBatch,
 };
@@ -67,6 +67,42 @@ fn create_context(array_len: usize, batch_size: usize) -> Result<ExecutionContex
     Ok(ctx)
 }
 
+/// A table whose rows alternate between even and odd `i`, with a wide string column
+fn create_wide_context(array_len: usize, batch_size: usize) -> Result<ExecutionContext> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("i", DataType::Int64, false),
+        Field::new("f64", DataType::Float64, false),
+        Field::new("s", DataType::Utf8, false),
+    ]));
+
+    let batches = (0..array_len / batch_size)
+        .map(|i| {
+            let rows = i * batch_size..(i + 1) * batch_size;
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from_iter_values(
+                        rows.clone().map(|r| r as i64),
+                    )),
+                    Arc::new(Float64Array::from_iter_values(
+                        rows.clone().map(|r| r as f64),
+                    )),
+                    Arc::new(StringArray::from_iter_values(
+                        rows.map(|r| format!("{:0>64}", r)),
+                    )),
+                ],
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let mut ctx = ExecutionContext::new();
+    let provider = MemTable::try_new(schema, vec![batches])?;
+    ctx.register_table("t", Arc::new(provider))?;
+
+    Ok(ctx)
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     let array_len = 524_288; // 2^19
     let batch_size = 4096; // 2^12
@@ -85,6 +121,18 @@ fn criterion_benchmark(c: &mut Criterion) {
             ))
         })
     });
+
+    // the right side of AND is only evaluated for the rows with an even `i`, which
+    // copies these rows of the columns it reads but not of the string column
+    c.bench_function("filter_short_circuit", |b| {
+        let mut ctx = create_wide_context(array_len, batch_size).unwrap();
+        b.iter(|| {
+            block_on(query(
+                &mut ctx,
+                "select f64 from t where i % 2 = 0 and sqrt(f64) / 2 > 100",
+            ))
+        })
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
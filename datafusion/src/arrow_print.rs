@@ -0,0 +1,522 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Display of record batches as text tables
+
+use std::str::FromStr;
+
+use arrow::array::{Array, ArrayRef, Float32Array, Float64Array};
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
+
+use crate::error::{DataFusionError, Result};
+
+/// The output of NaN and infinite floating point values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFiniteFloats {
+    /// `NaN`, `inf` and `-inf`
+    String,
+    /// NULL values
+    Null,
+}
+
+impl NonFiniteFloats {
+    /// The name of the output, as in the `datafusion.format.non_finite_floats` option
+    pub fn name(&self) -> &'static str {
+        match self {
+            NonFiniteFloats::String => "string",
+            NonFiniteFloats::Null => "null",
+        }
+    }
+}
+
+impl FromStr for NonFiniteFloats {
+    type Err = DataFusionError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "string" => Ok(NonFiniteFloats::String),
+            "null" => Ok(NonFiniteFloats::Null),
+            _ => Err(DataFusionError::Plan(format!(
+                "Unsupported output of non-finite floats '{}', expected string or null",
+                name
+            ))),
+        }
+    }
+}
+
+/// Conversion of floating point values to text by the output utilities, such as
+/// [BatchFormatter]. By default, the values are written with the shortest
+/// representation that reads back to them, without scientific notation.
+///
+/// ```
+/// # use datafusion::arrow_print::{FloatFormat, NonFiniteFloats};
+/// let format = FloatFormat::new()
+///     .with_precision(2)
+///     .with_scientific_notation_above(6)
+///     .with_scientific_notation_below(3)
+///     .with_non_finite(NonFiniteFloats::Null);
+/// assert_eq!(format.format(0.30387865541334363), Some("0.30".to_string()));
+/// assert_eq!(format.format(1234567.0), Some("1.23e6".to_string()));
+/// assert_eq!(format.format(0.0001234), Some("1.23e-4".to_string()));
+/// assert_eq!(format.format(f64::NAN), None);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloatFormat {
+    precision: Option<usize>,
+    upper_exponent: Option<u32>,
+    lower_exponent: Option<u32>,
+    non_finite: NonFiniteFloats,
+}
+
+impl Default for FloatFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FloatFormat {
+    /// The shortest representation of the values, never in scientific notation, with
+    /// NaN and infinities as strings
+    pub fn new() -> Self {
+        Self {
+            precision: None,
+            upper_exponent: None,
+            lower_exponent: None,
+            non_finite: NonFiniteFloats::String,
+        }
+    }
+
+    /// Rounds the values to `digits` digits after the decimal point, of the mantissa
+    /// in scientific notation
+    pub fn with_precision(mut self, digits: usize) -> Self {
+        self.precision = Some(digits);
+        self
+    }
+
+    /// Writes the values whose absolute value is at least `10^exponent` in scientific
+    /// notation
+    pub fn with_scientific_notation_above(mut self, exponent: u32) -> Self {
+        self.upper_exponent = Some(exponent);
+        self
+    }
+
+    /// Writes the values other than 0 whose absolute value is below `10^-exponent` in
+    /// scientific notation
+    pub fn with_scientific_notation_below(mut self, exponent: u32) -> Self {
+        self.lower_exponent = Some(exponent);
+        self
+    }
+
+    /// The output of NaN and infinite values
+    pub fn with_non_finite(mut self, non_finite: NonFiniteFloats) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    /// The number of digits after the decimal point, if the values are rounded
+    pub fn precision(&self) -> Option<usize> {
+        self.precision
+    }
+
+    /// The exponent of the power of ten from which the values are in scientific
+    /// notation, if any
+    pub fn scientific_notation_above(&self) -> Option<u32> {
+        self.upper_exponent
+    }
+
+    /// The exponent of the negative power of ten below which the values are in
+    /// scientific notation, if any
+    pub fn scientific_notation_below(&self) -> Option<u32> {
+        self.lower_exponent
+    }
+
+    /// The output of NaN and infinite values
+    pub fn non_finite(&self) -> NonFiniteFloats {
+        self.non_finite
+    }
+
+    /// The text of `value`, none when it is a NULL value
+    pub fn format(&self, value: f64) -> Option<String> {
+        self.format_with(value, || value.to_string(), || format!("{:e}", value))
+    }
+
+    /// The text of `value`, whose shortest representation is the one of a `f32`
+    pub fn format_f32(&self, value: f32) -> Option<String> {
+        let shortest = || value.to_string();
+        self.format_with(f64::from(value), shortest, || format!("{:e}", value))
+    }
+
+    fn format_with(
+        &self,
+        value: f64,
+        shortest: impl FnOnce() -> String,
+        shortest_scientific: impl FnOnce() -> String,
+    ) -> Option<String> {
+        if !value.is_finite() {
+            return match self.non_finite {
+                NonFiniteFloats::String => Some(value.to_string()),
+                NonFiniteFloats::Null => None,
+            };
+        }
+        let abs = value.abs();
+        let scientific = self
+            .upper_exponent
+            .map_or(false, |exponent| abs >= 10f64.powi(exponent as i32))
+            || self.lower_exponent.map_or(false, |exponent| {
+                abs != 0.0 && abs < 10f64.powi(-(exponent as i32))
+            });
+        Some(match (self.precision, scientific) {
+            (Some(digits), true) => format!("{:.*e}", digits, value),
+            (Some(digits), false) => format!("{:.*}", digits, value),
+            (None, true) => shortest_scientific(),
+            (None, false) => shortest(),
+        })
+    }
+}
+
+/// Formats record batches as text tables for display, such as the results of the
+/// CLI or the plans of EXPLAIN:
+///
+/// ```text
+/// +---+-------+
+/// | a | b     |
+/// +---+-------+
+/// | 1 | hello |
+/// +---+-------+
+/// ```
+///
+/// Values with several lines, like plans, span several lines of the table.
+///
+/// ```
+/// # use std::sync::Arc;
+/// # use datafusion::arrow::array::Int32Array;
+/// # use datafusion::arrow::datatypes::{DataType, Field, Schema};
+/// # use datafusion::arrow::record_batch::RecordBatch;
+/// # use datafusion::arrow_print::BatchFormatter;
+/// let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+/// let a = Int32Array::from(vec![Some(1), None, Some(3)]);
+/// let batch = RecordBatch::try_new(schema, vec![Arc::new(a)])?;
+///
+/// let formatter = BatchFormatter::new().with_max_rows(2).with_null("NULL");
+/// assert_eq!(
+///     formatter.format(&[batch])?,
+///     "+------+\n| a    |\n+------+\n| 1    |\n| NULL |\n| ...  |\n+------+"
+/// );
+/// # Ok::<(), datafusion::error::DataFusionError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct BatchFormatter {
+    max_column_width: Option<usize>,
+    max_rows: Option<usize>,
+    null: String,
+    float_format: FloatFormat,
+}
+
+impl Default for BatchFormatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BatchFormatter {
+    /// A formatter of all the rows and whole values, NULL values are empty cells
+    pub fn new() -> Self {
+        Self {
+            max_column_width: None,
+            max_rows: None,
+            null: String::new(),
+            float_format: FloatFormat::new(),
+        }
+    }
+
+    /// Truncates the lines of values longer than `width` characters, their end is
+    /// replaced by `...`
+    pub fn with_max_column_width(mut self, width: usize) -> Self {
+        self.max_column_width = Some(width);
+        self
+    }
+
+    /// Only formats the first `rows` rows, followed by a row of `...` when there are
+    /// more
+    pub fn with_max_rows(mut self, rows: usize) -> Self {
+        self.max_rows = Some(rows);
+        self
+    }
+
+    /// Renders NULL values as `null`
+    pub fn with_null(mut self, null: impl Into<String>) -> Self {
+        self.null = null.into();
+        self
+    }
+
+    /// Whether floating point values are rendered in scientific notation, `1.5e3`
+    /// rather than `1500`
+    pub fn with_scientific_notation(mut self, scientific_notation: bool) -> Self {
+        let exponent = Some(0).filter(|_| scientific_notation);
+        self.float_format.upper_exponent = exponent;
+        self.float_format.lower_exponent = exponent;
+        self
+    }
+
+    /// Renders floating point values with `float_format`, the NULL values it produces
+    /// are rendered as the other NULL values
+    pub fn with_float_format(mut self, float_format: FloatFormat) -> Self {
+        self.float_format = float_format;
+        self
+    }
+
+    /// Formats the rows of `batches` as a table, whose columns are the ones of the
+    /// first batch. There is no table without batches.
+    pub fn format(&self, batches: &[RecordBatch]) -> Result<String> {
+        let schema = match batches.first() {
+            Some(batch) => batch.schema(),
+            None => return Ok(String::new()),
+        };
+        let header = schema
+            .fields()
+            .iter()
+            .map(|f| self.cell(f.name()))
+            .collect::<Vec<_>>();
+
+        let num_rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+        let max_rows = self.max_rows.unwrap_or(num_rows);
+        let mut rows = vec![];
+        'batches: for batch in batches {
+            for row in 0..batch.num_rows() {
+                if rows.len() == max_rows {
+                    break 'batches;
+                }
+                let cells = batch
+                    .columns()
+                    .iter()
+                    .map(|column| Ok(self.cell(&self.value(column, row)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                rows.push(cells);
+            }
+        }
+        if max_rows < num_rows {
+            rows.push(vec![vec!["...".to_string()]; header.len()]);
+        }
+
+        let widths = (0..header.len())
+            .map(|i| {
+                std::iter::once(&header[i])
+                    .chain(rows.iter().map(|row| &row[i]))
+                    .flatten()
+                    .map(|line| line.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+        let border = widths.iter().fold("+".to_string(), |border, width| {
+            format!("{}{}+", border, "-".repeat(width + 2))
+        });
+
+        let mut lines = vec![border.clone()];
+        push_row(&mut lines, &header, &widths);
+        lines.push(border.clone());
+        for row in &rows {
+            push_row(&mut lines, row, &widths);
+        }
+        lines.push(border);
+        Ok(lines.join("\n"))
+    }
+
+    /// Prints the rows of `batches` as a table to stdout
+    pub fn print(&self, batches: &[RecordBatch]) -> Result<()> {
+        println!("{}", self.format(batches)?);
+        Ok(())
+    }
+
+    /// The text of the value of `column` at `row`
+    fn value(&self, column: &ArrayRef, row: usize) -> Result<String> {
+        if column.is_null(row) {
+            return Ok(self.null.clone());
+        }
+        let float = match column.data_type() {
+            DataType::Float32 => {
+                let array = column.as_any().downcast_ref::<Float32Array>().unwrap();
+                self.float_format.format_f32(array.value(row))
+            }
+            DataType::Float64 => {
+                let array = column.as_any().downcast_ref::<Float64Array>().unwrap();
+                self.float_format.format(array.value(row))
+            }
+            _ => return Ok(array_value_to_string(column, row)?),
+        };
+        Ok(float.unwrap_or_else(|| self.null.clone()))
+    }
+
+    /// The lines of a cell of `text`, truncated to the maximum column width
+    fn cell(&self, text: &str) -> Vec<String> {
+        text.split('\n')
+            .map(|line| match self.max_column_width {
+                Some(width) if line.chars().count() > width => {
+                    let kept = line.chars().take(width.saturating_sub(3));
+                    format!("{}...", kept.collect::<String>())
+                }
+                _ => line.to_string(),
+            })
+            .collect()
+    }
+}
+
+/// Appends the lines of a table row, as many as the lines of its highest cell
+fn push_row(lines: &mut Vec<String>, cells: &[Vec<String>], widths: &[usize]) {
+    let height = cells.iter().map(|cell| cell.len()).max().unwrap_or(0);
+    for i in 0..height {
+        let line =
+            cells
+                .iter()
+                .zip(widths)
+                .fold("|".to_string(), |line, (cell, width)| {
+                    let text = cell.get(i).map(|text| text.as_str()).unwrap_or("");
+                    let padding = width - text.chars().count();
+                    format!("{} {}{} |", line, text, " ".repeat(padding))
+                });
+        lines.push(line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    fn batch() -> Result<RecordBatch> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("plan_type", DataType::Utf8, true),
+            Field::new("n", DataType::Int32, true),
+            Field::new("x", DataType::Float64, true),
+        ]));
+        Ok(RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(StringArray::from(vec![
+                    Some("logical_plan"),
+                    Some("Projection: #a\n  TableScan: t"),
+                    None,
+                ])),
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+                Arc::new(Float64Array::from(vec![Some(1500.0), Some(0.25), None])),
+            ],
+        )?)
+    }
+
+    #[test]
+    fn format_batches() -> Result<()> {
+        let expected = vec![
+            "+----------------+---+------+",
+            "| plan_type      | n | x    |",
+            "+----------------+---+------+",
+            "| logical_plan   | 1 | 1500 |",
+            "| Projection: #a |   | 0.25 |",
+            "|   TableScan: t |   |      |",
+            "|                | 3 |      |",
+            "+----------------+---+------+",
+        ];
+        assert_eq!(
+            BatchFormatter::new().format(&[batch()?])?,
+            expected.join("\n")
+        );
+        assert_eq!(BatchFormatter::new().format(&[])?, "");
+        Ok(())
+    }
+
+    #[test]
+    fn format_truncated_batches() -> Result<()> {
+        let formatter = BatchFormatter::new()
+            .with_max_column_width(8)
+            .with_max_rows(2)
+            .with_null("NULL")
+            .with_scientific_notation(true);
+        let expected = vec![
+            "+----------+-----+-------+",
+            "| plan_... | n   | x     |",
+            "+----------+-----+-------+",
+            "| logic... | 1   | 1.5e3 |",
+            "| ...      | ... | ...   |",
+            "+----------+-----+-------+",
+        ];
+        assert_eq!(formatter.format(&[batch()?])?, expected.join("\n"));
+
+        let formatter = BatchFormatter::new().with_max_rows(4).with_null("NULL");
+        let expected = vec![
+            "+----------------+------+------+",
+            "| plan_type      | n    | x    |",
+            "+----------------+------+------+",
+            "| logical_plan   | 1    | 1500 |",
+            "| Projection: #a | NULL | 0.25 |",
+            "|   TableScan: t |      |      |",
+            "| NULL           | 3    | NULL |",
+            "| logical_plan   | 1    | 1500 |",
+            "| ...            | ...  | ...  |",
+            "+----------------+------+------+",
+        ];
+        assert_eq!(
+            formatter.format(&[batch()?, batch()?])?,
+            expected.join("\n")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn format_floats() -> Result<()> {
+        let format = FloatFormat::new();
+        assert_eq!(
+            format.format(1e21),
+            Some("1000000000000000000000".to_string())
+        );
+        assert_eq!(format.format_f32(0.1), Some("0.1".to_string()));
+        assert_eq!(format.format(f64::NEG_INFINITY), Some("-inf".to_string()));
+
+        let format = format
+            .with_precision(1)
+            .with_scientific_notation_above(3)
+            .with_non_finite("null".parse()?);
+        assert_eq!(format.format(-1234.5), Some("-1.2e3".to_string()));
+        assert_eq!(format.format(999.94), Some("999.9".to_string()));
+        assert_eq!(format.format(1e-9), Some("0.0".to_string()));
+        assert_eq!(format.format(f64::NAN), None);
+        assert!("nan".parse::<NonFiniteFloats>().is_err());
+
+        let schema =
+            Arc::new(Schema::new(vec![Field::new("x", DataType::Float32, false)]));
+        let x = Float32Array::from(vec![f32::INFINITY, 2.5e-5]);
+        let batch = RecordBatch::try_new(schema, vec![Arc::new(x)])?;
+        let float_format = FloatFormat::new()
+            .with_scientific_notation_below(4)
+            .with_non_finite(NonFiniteFloats::Null);
+        let formatter = BatchFormatter::new()
+            .with_null("NULL")
+            .with_float_format(float_format);
+        let expected = vec![
+            "+--------+",
+            "| x      |",
+            "+--------+",
+            "| NULL   |",
+            "| 2.5e-5 |",
+            "+--------+",
+        ];
+        assert_eq!(formatter.format(&[batch])?, expected.join("\n"));
+        Ok(())
+    }
+}
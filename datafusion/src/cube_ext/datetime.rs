@@ -16,8 +16,10 @@
 // under the License.
 
 use crate::error::DataFusionError;
+use crate::physical_plan::datetime_expressions::datetime_to_timestamp;
 use crate::scalar::ScalarValue;
 use arrow::array::{Array, TimestampNanosecondArray, TimestampNanosecondBuilder};
+use arrow::datatypes::TimeUnit;
 use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
 
 pub fn date_addsub_array(
@@ -33,9 +35,9 @@ pub fn date_addsub_array(
                     result.append_null()?;
                 } else {
                     let t = Utc.timestamp_nanos(t.value(i));
-                    result.append_value(
-                        date_addsub_year_month(t, v, is_add)?.timestamp_nanos(),
-                    )?;
+                    result.append_value(timestamp_nanos(date_addsub_year_month(
+                        t, v, is_add,
+                    )?)?)?;
                 }
             }
         }
@@ -45,9 +47,9 @@ pub fn date_addsub_array(
                     result.append_null()?;
                 } else {
                     let t = Utc.timestamp_nanos(t.value(i));
-                    result.append_value(
-                        date_addsub_day_time(t, v, is_add)?.timestamp_nanos(),
-                    )?;
+                    result.append_value(timestamp_nanos(date_addsub_day_time(
+                        t, v, is_add,
+                    )?)?)?;
                 }
             }
         }
@@ -139,6 +141,17 @@ fn date_addsub_day_time(
     return Ok(t + Duration::days(days) + Duration::milliseconds(millis));
 }
 
+/// Converts the result of a date computation to a nanosecond timestamp, or fails if it
+/// is out of their range
+pub fn timestamp_nanos(t: DateTime<Utc>) -> Result<i64, DataFusionError> {
+    datetime_to_timestamp(&t.naive_utc(), &TimeUnit::Nanosecond).ok_or_else(|| {
+        DataFusionError::Execution(format!(
+            "Date {} is out of the range of nanosecond timestamps",
+            t
+        ))
+    })
+}
+
 fn change_ym(t: DateTime<Utc>, y: i32, m: u32) -> Option<DateTime<Utc>> {
     debug_assert!(1 <= m && m <= 12);
     let mut d = t.day();
@@ -136,7 +136,7 @@ pub fn plan_cross_join(
     let left = &inputs[0];
     let right = &inputs[1];
 
-    let schema = build_join_schema(&left.schema(), &right.schema(), &JoinType::Left);
+    let schema = build_join_schema(&left.schema(), &right.schema(), &JoinType::Inner);
     let on = planner.create_physical_expr(&node.on, node.schema(), &schema, ctx_state)?;
 
     Ok(CrossJoinExec {
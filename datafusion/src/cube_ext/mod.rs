@@ -24,6 +24,7 @@ pub mod ordfloat;
 pub mod rolling;
 pub mod sequence;
 pub mod stream;
+pub mod temporal;
 pub mod util;
 
 mod spawn;
@@ -0,0 +1,713 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Kernels on arrays of dates and timestamps, used by `date_part` and the `WEEK`,
+//! `YEARWEEK` and `ISOWEEK` functions. They are public, also re-exported as
+//! `datafusion::temporal`, so that embedders can call them on their own arrays.
+//!
+//! Timestamps without a time zone are UTC instants whose local times are those of a
+//! default zone, usually the time zone of the session. Timestamps with a time zone use
+//! the local times of their zone, which is either a fixed offset or a zone of the tz
+//! database, see [Zone]. Dictionary-encoded arrays are supported by [extract].
+//!
+//! ```
+//! # use std::sync::Arc;
+//! # use arrow::array::{Array, ArrayRef, Int32Array, TimestampSecondArray};
+//! use datafusion::temporal::{extract, DatePart, Zone};
+//!
+//! // 2021-01-03T12:00:00Z
+//! let timestamps: ArrayRef = Arc::new(TimestampSecondArray::from_vec(
+//!     vec![1_609_675_200],
+//!     Some("Asia/Tokyo".to_string()),
+//! ));
+//! let weekdays = extract(&timestamps, DatePart::WeekdayFromMonday, &Zone::utc())?;
+//! let weekdays = weekdays.as_any().downcast_ref::<Int32Array>().unwrap();
+//! // 2021-01-03T21:00:00 in Tokyo, a Sunday
+//! assert_eq!(weekdays.value(0), 6);
+//! # Ok::<(), datafusion::error::DataFusionError>(())
+//! ```
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::datetime_expressions::{parse_time_zone, units_per_second};
+use arrow::array::{
+    Array, ArrayRef, Date32Array, Date64Array, DictionaryArray, Int32Array, Int64Array,
+    UInt32Array,
+};
+use arrow::compute;
+use arrow::datatypes::{
+    ArrowDictionaryKeyType, ArrowNativeType, DataType, Int16Type, Int32Type, Int64Type,
+    Int8Type, TimeUnit, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use chrono::{
+    Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike,
+};
+use chrono_tz::Tz;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Weeks start on Monday instead of Sunday
+const WEEK_MONDAY_FIRST: u32 = 1;
+/// Days before the first week belong to the last week of the previous year instead
+/// of week 0
+const WEEK_YEAR: u32 = 2;
+/// The first week is the one with the first day of the week in the year, instead of
+/// the first one with 4 or more days in the year
+const WEEK_FIRST_WEEKDAY: u32 = 4;
+
+/// Days between 0001-01-01 and 1970-01-01
+const EPOCH_DAYS_FROM_CE: i64 = 719_163;
+
+/// Converts the `mode` of MySQL's `WEEK` function to the flags above
+fn week_behaviour(mode: u32) -> u32 {
+    let behaviour = mode & 7;
+    if behaviour & WEEK_MONDAY_FIRST == 0 {
+        behaviour ^ WEEK_FIRST_WEEKDAY
+    } else {
+        behaviour
+    }
+}
+
+fn days_in_year(year: i32) -> i64 {
+    match NaiveDate::from_ymd_opt(year, 2, 29) {
+        Some(_) => 366,
+        None => 365,
+    }
+}
+
+/// The year and the week of `date` numbered according to `behaviour`, following
+/// MySQL's `calc_week`
+fn calc_week(date: NaiveDate, behaviour: u32) -> (i32, u32) {
+    let monday_first = behaviour & WEEK_MONDAY_FIRST != 0;
+    let mut week_year = behaviour & WEEK_YEAR != 0;
+    let first_weekday = behaviour & WEEK_FIRST_WEEKDAY != 0;
+    // whether the days of the first, partial week of a year are in week 0
+    let in_week_zero = |weekday: i64| {
+        (first_weekday && weekday != 0) || (!first_weekday && weekday >= 4)
+    };
+
+    let mut year = date.year();
+    let day_number = date.num_days_from_ce() as i64;
+    let first_day = NaiveDate::from_ymd(year, 1, 1);
+    let mut first_day_number = first_day.num_days_from_ce() as i64;
+    let mut weekday = match monday_first {
+        true => first_day.weekday().num_days_from_monday(),
+        false => first_day.weekday().num_days_from_sunday(),
+    } as i64;
+
+    if date.month() == 1 && (date.day() as i64) <= 7 - weekday {
+        if !week_year && in_week_zero(weekday) {
+            return (year, 0);
+        }
+        week_year = true;
+        year -= 1;
+        let days = days_in_year(year);
+        first_day_number -= days;
+        weekday = (weekday + 53 * 7 - days) % 7;
+    }
+
+    let days = if in_week_zero(weekday) {
+        day_number - (first_day_number + 7 - weekday)
+    } else {
+        day_number - (first_day_number - weekday)
+    };
+    if week_year && days >= 52 * 7 {
+        let weekday = (weekday + days_in_year(year)) % 7;
+        if (!first_weekday && weekday < 4) || (first_weekday && weekday == 0) {
+            return (year + 1, 1);
+        }
+    }
+    (year, (days / 7 + 1) as u32)
+}
+
+/// The week of `date` like MySQL's `WEEK(date, mode)`, where `mode` picks the first
+/// day of the week, whether weeks are numbered from 0 or 1 and which week is the
+/// first one of a year:
+///
+/// | mode | first day | range | first week                    |
+/// |------|-----------|-------|-------------------------------|
+/// | 0    | Sunday    | 0-53  | with a Sunday in the year     |
+/// | 1    | Monday    | 0-53  | with 4 or more days this year |
+/// | 2    | Sunday    | 1-53  | with a Sunday in the year     |
+/// | 3    | Monday    | 1-53  | with 4 or more days this year |
+/// | 4    | Sunday    | 0-53  | with 4 or more days this year |
+/// | 5    | Monday    | 0-53  | with a Monday in the year     |
+/// | 6    | Sunday    | 1-53  | with 4 or more days this year |
+/// | 7    | Monday    | 1-53  | with a Monday in the year     |
+pub fn week(date: NaiveDate, mode: u32) -> u32 {
+    calc_week(date, week_behaviour(mode)).1
+}
+
+/// The year and week of `date` as `year * 100 + week` like MySQL's
+/// `YEARWEEK(date, mode)`. Weeks are numbered as by [week], except that days before
+/// the first week of a year are in the last week of the previous year.
+pub fn year_week(date: NaiveDate, mode: u32) -> i32 {
+    let (year, week) = calc_week(date, week_behaviour(mode) | WEEK_YEAR);
+    year * 100 + week as i32
+}
+
+/// The ISO 8601 week of `date`, i.e. [week] in mode 3
+pub fn iso_week(date: NaiveDate) -> u32 {
+    date.iso_week().week()
+}
+
+fn date_from_days(days: i64) -> Result<NaiveDate> {
+    i32::try_from(days + EPOCH_DAYS_FROM_CE)
+        .ok()
+        .and_then(NaiveDate::from_num_days_from_ce_opt)
+        .ok_or_else(|| {
+            DataFusionError::Execution(format!(
+                "Date {} days from the epoch is out of range",
+                days
+            ))
+        })
+}
+
+/// Applies `op` to the dates of an array of dates or of timestamps without a time
+/// zone, along with their indices
+pub fn map_dates<F>(array: &ArrayRef, op: F) -> Result<Int32Array>
+where
+    F: Fn(NaiveDate, usize) -> Result<Option<i32>>,
+{
+    let days: Vec<Option<i64>> = match array.data_type() {
+        DataType::Date32 => {
+            let array = array.as_any().downcast_ref::<Date32Array>().unwrap();
+            array.iter().map(|d| d.map(|d| d as i64)).collect()
+        }
+        DataType::Date64 => {
+            let array = array.as_any().downcast_ref::<Date64Array>().unwrap();
+            array
+                .iter()
+                .map(|d| d.map(|d| d.div_euclid(86_400_000)))
+                .collect()
+        }
+        DataType::Timestamp(unit, None) => {
+            let units_per_day = units_per_second(unit) * 86_400;
+            let values = compute::cast(array, &DataType::Int64)?;
+            let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+            values
+                .iter()
+                .map(|v| v.map(|v| v.div_euclid(units_per_day)))
+                .collect()
+        }
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "Expected dates or timestamps without a time zone, got {:?}",
+                other
+            )))
+        }
+    };
+    days.into_iter()
+        .enumerate()
+        .map(|(i, days)| match days {
+            Some(days) => op(date_from_days(days)?, i),
+            None => Ok(None),
+        })
+        .collect()
+}
+
+/// The time zone of timestamps: either a fixed offset, or a zone of the tz database
+/// whose offset depends on the instant because of daylight saving time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Zone {
+    /// A fixed offset from UTC
+    Fixed(FixedOffset),
+    /// A named zone of the tz database, e.g. `America/New_York`
+    Named(Tz),
+}
+
+impl Zone {
+    /// UTC, the default time zone of a session
+    pub fn utc() -> Self {
+        Zone::Fixed(FixedOffset::east(0))
+    }
+
+    /// Parses a fixed offset as accepted by [parse_time_zone], or the name of a zone of
+    /// the tz database
+    pub fn parse(s: &str) -> Result<Self> {
+        if let Ok(offset) = parse_time_zone(s) {
+            return Ok(Zone::Fixed(offset));
+        }
+        s.parse::<Tz>().map(Zone::Named).map_err(|_| {
+            DataFusionError::Plan(format!(
+                "Unsupported time zone '{}', expected a fixed offset such as +05:30 \
+                 or a name of the tz database such as Europe/Berlin",
+                s
+            ))
+        })
+    }
+
+    /// The offset from UTC of the local time of this zone at the UTC time `utc`
+    pub fn offset_at(&self, utc: &NaiveDateTime) -> FixedOffset {
+        match self {
+            Zone::Fixed(offset) => *offset,
+            Zone::Named(tz) => tz.offset_from_utc_datetime(utc).fix(),
+        }
+    }
+
+    /// The UTC time of the local time `local` of this zone. A local time that occurs
+    /// twice when the clocks are turned back is the earlier one, and `None` is returned
+    /// for a local time skipped when they are turned forward.
+    pub fn to_utc(&self, local: &NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Zone::Fixed(offset) => local
+                .checked_sub_signed(Duration::seconds(offset.local_minus_utc() as i64)),
+            Zone::Named(tz) => tz
+                .from_local_datetime(local)
+                .earliest()
+                .map(|datetime| datetime.naive_utc()),
+        }
+    }
+}
+
+impl From<FixedOffset> for Zone {
+    fn from(offset: FixedOffset) -> Self {
+        Zone::Fixed(offset)
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Zone::Fixed(offset) => write!(f, "{}", offset),
+            Zone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+/// The offset from UTC of the time zone named `tz` at the UTC time `utc`, or `None`
+/// if `tz` is neither a fixed offset nor a zone of the tz database
+pub fn using_chrono_tz_and_utc_naive_date_time(
+    tz: &str,
+    utc: NaiveDateTime,
+) -> Option<FixedOffset> {
+    Zone::parse(tz).ok().map(|zone| zone.offset_at(&utc))
+}
+
+/// The local dates and times of an array of dates or timestamps. Timestamps with a
+/// time zone are in the local times of their zone, and those without one in the local
+/// times of `default_zone`. Dates are at midnight.
+pub fn local_datetimes(
+    array: &ArrayRef,
+    default_zone: &Zone,
+) -> Result<Vec<Option<NaiveDateTime>>> {
+    let (unit, zone) = match array.data_type() {
+        DataType::Null => return Ok(vec![None; array.len()]),
+        DataType::Date32 => {
+            let array = array.as_any().downcast_ref::<Date32Array>().unwrap();
+            return array
+                .iter()
+                .map(|d| {
+                    d.map(|d| Ok(date_from_days(d as i64)?.and_hms(0, 0, 0)))
+                        .transpose()
+                })
+                .collect();
+        }
+        DataType::Date64 => (&TimeUnit::Millisecond, Zone::utc()),
+        DataType::Timestamp(unit, None) => (unit, *default_zone),
+        DataType::Timestamp(unit, Some(tz)) => (unit, Zone::parse(tz)?),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "Expected dates or timestamps, got {:?}",
+                other
+            )))
+        }
+    };
+    let units = units_per_second(unit);
+    let values = compute::cast(array, &DataType::Int64)?;
+    let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+    values
+        .iter()
+        .map(|v| {
+            v.map(|v| {
+                let nanos = v.rem_euclid(units) * (1_000_000_000 / units);
+                NaiveDateTime::from_timestamp_opt(v.div_euclid(units), nanos as u32)
+                    .and_then(|utc| {
+                        let offset = zone.offset_at(&utc).local_minus_utc();
+                        utc.checked_add_signed(Duration::seconds(offset as i64))
+                    })
+                    .ok_or_else(|| {
+                        DataFusionError::Execution(format!(
+                            "Timestamp {} is out of range",
+                            v
+                        ))
+                    })
+            })
+            .transpose()
+        })
+        .collect()
+}
+
+/// The seconds since the epoch of an array of dates or timestamps, whatever their
+/// time zone
+pub fn epoch(array: &ArrayRef) -> Result<Int64Array> {
+    let units = match array.data_type() {
+        DataType::Null => return Ok(Int64Array::from(vec![None; array.len()])),
+        DataType::Date32 => {
+            let array = array.as_any().downcast_ref::<Date32Array>().unwrap();
+            return Ok(array.iter().map(|d| d.map(|d| d as i64 * 86_400)).collect());
+        }
+        DataType::Date64 => 1_000,
+        DataType::Timestamp(unit, _) => units_per_second(unit),
+        other => {
+            return Err(DataFusionError::Execution(format!(
+                "Expected dates or timestamps, got {:?}",
+                other
+            )))
+        }
+    };
+    let values = compute::cast(array, &DataType::Int64)?;
+    let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+    Ok(values
+        .iter()
+        .map(|v| v.map(|v| v.div_euclid(units)))
+        .collect())
+}
+
+/// A part of dates and timestamps, as named in `date_part`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatePart {
+    /// `year`
+    Year,
+    /// `quarter`: from 1 to 4
+    Quarter,
+    /// `month`: from 1 to 12
+    Month,
+    /// `day`: the day of the month, from 1
+    Day,
+    /// `hour`: from 0 to 23
+    Hour,
+    /// `minute`: from 0 to 59
+    Minute,
+    /// `second`: from 0 to 59, or 60 during a leap second
+    Second,
+    /// `doy`: the day of the year, from 1
+    DayOfYear,
+    /// `dow`: the day of the week, from 0 for Sunday to 6 for Saturday
+    DayOfWeek,
+    /// `weekday`: the day of the week, from 0 for Monday to 6 for Sunday
+    WeekdayFromMonday,
+    /// `week` or `isoweek`: the ISO 8601 week, see [iso_week]
+    IsoWeek,
+    /// `isoyear`: the ISO 8601 week-numbering year, which differs from the year for
+    /// days in the first or last week of a year
+    IsoYear,
+    /// `isodow`: the ISO 8601 day of the week, from 1 for Monday to 7 for Sunday
+    IsoDayOfWeek,
+    /// `yearweek`: the year and week as by [year_week] in mode 0
+    YearWeek,
+    /// `epoch`: the seconds since 1970-01-01T00:00:00Z, see [epoch]
+    Epoch,
+}
+
+impl FromStr for DatePart {
+    type Err = DataFusionError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_lowercase().as_str() {
+            "year" => DatePart::Year,
+            "quarter" => DatePart::Quarter,
+            "month" => DatePart::Month,
+            "day" => DatePart::Day,
+            "hour" => DatePart::Hour,
+            "minute" => DatePart::Minute,
+            "second" => DatePart::Second,
+            "doy" => DatePart::DayOfYear,
+            "dow" => DatePart::DayOfWeek,
+            "weekday" => DatePart::WeekdayFromMonday,
+            "week" | "isoweek" => DatePart::IsoWeek,
+            "isoyear" => DatePart::IsoYear,
+            "isodow" => DatePart::IsoDayOfWeek,
+            "yearweek" => DatePart::YearWeek,
+            "epoch" => DatePart::Epoch,
+            _ => {
+                return Err(DataFusionError::Execution(format!(
+                    "Date part '{}' not supported",
+                    s
+                )))
+            }
+        })
+    }
+}
+
+impl DatePart {
+    /// The type of the extracted values: Int64 for [DatePart::Epoch], which
+    /// overflows an Int32 after 2038, and Int32 for the other parts
+    pub fn data_type(&self) -> DataType {
+        match self {
+            DatePart::Epoch => DataType::Int64,
+            _ => DataType::Int32,
+        }
+    }
+
+    /// The part of the local date and time `datetime`. For [DatePart::Epoch], which
+    /// is not a part of local times, `datetime` is taken as UTC.
+    pub fn of(&self, datetime: &NaiveDateTime) -> i64 {
+        (match self {
+            DatePart::Year => datetime.year(),
+            DatePart::Quarter => (datetime.month0() / 3 + 1) as i32,
+            DatePart::Month => datetime.month() as i32,
+            DatePart::Day => datetime.day() as i32,
+            DatePart::Hour => datetime.hour() as i32,
+            DatePart::Minute => datetime.minute() as i32,
+            DatePart::Second => {
+                (datetime.second() + datetime.nanosecond() / 1_000_000_000) as i32
+            }
+            DatePart::DayOfYear => datetime.ordinal() as i32,
+            DatePart::DayOfWeek => datetime.weekday().num_days_from_sunday() as i32,
+            DatePart::WeekdayFromMonday => {
+                datetime.weekday().num_days_from_monday() as i32
+            }
+            DatePart::IsoWeek => iso_week(datetime.date()) as i32,
+            DatePart::IsoYear => datetime.iso_week().year(),
+            DatePart::IsoDayOfWeek => datetime.weekday().number_from_monday() as i32,
+            DatePart::YearWeek => year_week(datetime.date(), 0),
+            DatePart::Epoch => return datetime.timestamp(),
+        }) as i64
+    }
+}
+
+/// Extracts `part` from an array of dates or timestamps, which may be
+/// dictionary-encoded. The parts other than [DatePart::Epoch] are taken from local
+/// times as computed by [local_datetimes]. The result is of [DatePart::data_type].
+pub fn extract(
+    array: &ArrayRef,
+    part: DatePart,
+    default_zone: &Zone,
+) -> Result<ArrayRef> {
+    if let DataType::Dictionary(_, _) = array.data_type() {
+        return map_dictionary_values(array, |values| {
+            extract(values, part, default_zone)
+        });
+    }
+    if part == DatePart::Epoch {
+        return Ok(Arc::new(epoch(array)?));
+    }
+    let parts = local_datetimes(array, default_zone)?
+        .iter()
+        .map(|d| d.as_ref().map(|d| part.of(d) as i32))
+        .collect::<Int32Array>();
+    Ok(Arc::new(parts))
+}
+
+/// Extracts the part named `part` as by [extract], see [DatePart] for the names
+pub fn date_part(array: &ArrayRef, part: &str, default_zone: &Zone) -> Result<ArrayRef> {
+    extract(array, part.parse()?, default_zone)
+}
+
+/// Applies `op` to the values of a dictionary-encoded array and expands the result
+/// with the keys of the dictionary, so that `op` runs once per distinct value. Other
+/// arrays are passed to `op` as they are.
+pub fn map_dictionary_values<F>(array: &ArrayRef, op: F) -> Result<ArrayRef>
+where
+    F: Fn(&ArrayRef) -> Result<ArrayRef>,
+{
+    match array.data_type() {
+        DataType::Dictionary(key_type, _) => match **key_type {
+            DataType::Int8 => take_dictionary_values::<Int8Type, _>(array, op),
+            DataType::Int16 => take_dictionary_values::<Int16Type, _>(array, op),
+            DataType::Int32 => take_dictionary_values::<Int32Type, _>(array, op),
+            DataType::Int64 => take_dictionary_values::<Int64Type, _>(array, op),
+            DataType::UInt8 => take_dictionary_values::<UInt8Type, _>(array, op),
+            DataType::UInt16 => take_dictionary_values::<UInt16Type, _>(array, op),
+            DataType::UInt32 => take_dictionary_values::<UInt32Type, _>(array, op),
+            DataType::UInt64 => take_dictionary_values::<UInt64Type, _>(array, op),
+            ref other => Err(DataFusionError::Internal(format!(
+                "Unsupported dictionary key type {:?}",
+                other
+            ))),
+        },
+        _ => op(array),
+    }
+}
+
+fn take_dictionary_values<K, F>(array: &ArrayRef, op: F) -> Result<ArrayRef>
+where
+    K: ArrowDictionaryKeyType,
+    F: Fn(&ArrayRef) -> Result<ArrayRef>,
+{
+    let dictionary = array.as_any().downcast_ref::<DictionaryArray<K>>().unwrap();
+    let values = op(dictionary.values())?;
+    let indices = dictionary
+        .keys()
+        .iter()
+        .map(|k| k.and_then(|k| k.to_usize()).map(|k| k as u32))
+        .collect::<UInt32Array>();
+    Ok(compute::take(values.as_ref(), &indices, None)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{
+        PrimitiveBuilder, PrimitiveDictionaryBuilder, TimestampSecondArray,
+    };
+    use arrow::datatypes::Date32Type;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    #[test]
+    fn mysql_weeks() {
+        // examples of the MySQL documentation
+        assert_eq!(week(date(2008, 2, 20), 0), 7);
+        assert_eq!(week(date(2008, 2, 20), 1), 8);
+        assert_eq!(week(date(2008, 12, 31), 1), 53);
+        assert_eq!(week(date(2000, 1, 1), 0), 0);
+        assert_eq!(week(date(2000, 1, 1), 2), 52);
+        assert_eq!(year_week(date(1987, 1, 1), 0), 198652);
+
+        let modes = (0..8)
+            .map(|mode| week(date(2021, 1, 3), mode))
+            .collect::<Vec<_>>();
+        assert_eq!(modes, vec![1, 0, 1, 53, 1, 0, 1, 52]);
+        assert_eq!(year_week(date(2024, 12, 30), 0), 202452);
+        assert_eq!(year_week(date(2024, 12, 30), 3), 202501);
+    }
+
+    fn parts(array: &ArrayRef, part: &str) -> Result<Vec<Option<i64>>> {
+        let parts = date_part(array, part, &Zone::utc())?;
+        assert_eq!(parts.data_type(), &part.parse::<DatePart>()?.data_type());
+        let parts = compute::cast(&parts, &DataType::Int64)?;
+        let parts = parts.as_any().downcast_ref::<Int64Array>().unwrap();
+        Ok(parts.iter().collect())
+    }
+
+    #[test]
+    fn named_time_zones() -> Result<()> {
+        let new_york = Zone::parse("America/New_York")?;
+        let winter = NaiveDate::from_ymd(2021, 1, 15).and_hms(12, 0, 0);
+        let summer = NaiveDate::from_ymd(2021, 7, 15).and_hms(12, 0, 0);
+        assert_eq!(new_york.offset_at(&winter), FixedOffset::west(5 * 3600));
+        assert_eq!(new_york.offset_at(&summer), FixedOffset::west(4 * 3600));
+        assert_eq!(
+            Zone::parse("+05:30")?,
+            Zone::Fixed(FixedOffset::east(5 * 3600 + 1800))
+        );
+        assert!(Zone::parse("Mars/Olympus_Mons").is_err());
+        assert_eq!(
+            using_chrono_tz_and_utc_naive_date_time("Europe/Berlin", summer),
+            Some(FixedOffset::east(2 * 3600))
+        );
+        assert_eq!(
+            using_chrono_tz_and_utc_naive_date_time("CEST", summer),
+            None
+        );
+
+        // 01:30 occurs twice when the clocks are turned back, 02:30 is skipped when
+        // they are turned forward
+        let local = |m, d, h| NaiveDate::from_ymd(2021, m, d).and_hms(h, 30, 0);
+        assert_eq!(
+            new_york.to_utc(&local(11, 7, 1)),
+            Some(NaiveDate::from_ymd(2021, 11, 7).and_hms(5, 30, 0))
+        );
+        assert_eq!(new_york.to_utc(&local(3, 14, 2)), None);
+        assert_eq!(new_york.to_string(), "America/New_York");
+
+        // 2021-03-14T06:30:00Z and 2021-03-14T07:30:00Z, around the switch to
+        // daylight saving time at 2021-03-14T07:00:00Z in New York
+        let timestamps: ArrayRef = Arc::new(TimestampSecondArray::from_vec(
+            vec![1_615_703_400, 1_615_707_000],
+            Some("America/New_York".to_string()),
+        ));
+        assert_eq!(parts(&timestamps, "hour")?, vec![Some(1), Some(3)]);
+        assert_eq!(
+            parts(&timestamps, "epoch")?,
+            vec![Some(1_615_703_400), Some(1_615_707_000)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dictionary_date_parts() -> Result<()> {
+        // 2021-01-03 is a Sunday, 2020-12-31 the 366th day of 2020
+        let mut builder = PrimitiveDictionaryBuilder::<Int8Type, Date32Type>::new(
+            PrimitiveBuilder::new(4),
+            PrimitiveBuilder::new(2),
+        );
+        for date in [Some(18_630), None, Some(18_627), Some(18_630)] {
+            match date {
+                Some(date) => builder.append(date).map(|_| ())?,
+                None => builder.append_null()?,
+            }
+        }
+        let array: ArrayRef = Arc::new(builder.finish());
+        assert_eq!(parts(&array, "dow")?, vec![Some(0), None, Some(4), Some(0)]);
+        assert_eq!(
+            parts(&array, "doy")?,
+            vec![Some(3), None, Some(366), Some(3)]
+        );
+        assert_eq!(
+            parts(&array, "epoch")?,
+            vec![
+                Some(1_609_632_000),
+                None,
+                Some(1_609_372_800),
+                Some(1_609_632_000)
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn iso_date_parts() -> Result<()> {
+        // 2021-01-03 (Sunday), 2020-12-31 (Thursday), 2024-12-30 (Monday)
+        let dates: ArrayRef = Arc::new(Date32Array::from(vec![
+            Some(18_630),
+            Some(18_627),
+            None,
+            Some(20_087),
+        ]));
+        assert_eq!(
+            parts(&dates, "quarter")?,
+            vec![Some(1), Some(4), None, Some(4)]
+        );
+        assert_eq!(
+            parts(&dates, "isoyear")?,
+            vec![Some(2020), Some(2020), None, Some(2025)]
+        );
+        assert_eq!(
+            parts(&dates, "isodow")?,
+            vec![Some(7), Some(4), None, Some(1)]
+        );
+        assert_eq!(
+            parts(&dates, "weekday")?,
+            vec![Some(6), Some(3), None, Some(0)]
+        );
+        assert_eq!(parts(&dates, "dow")?, vec![Some(0), Some(4), None, Some(1)]);
+        assert!(parts(&dates, "fortnight").is_err());
+
+        let leap_second =
+            NaiveDate::from_ymd(2016, 12, 31).and_hms_milli(23, 59, 59, 1_500);
+        assert_eq!(DatePart::Second.of(&leap_second), 60);
+        assert_eq!(DatePart::Epoch.of(&leap_second), 1_483_228_799);
+        assert_eq!("ISOWEEK".parse::<DatePart>()?, DatePart::IsoWeek);
+        Ok(())
+    }
+
+    #[test]
+    fn iso_weeks() {
+        let mut day = date(1999, 12, 1);
+        while day < date(2030, 1, 31) {
+            assert_eq!(week(day, 3), iso_week(day), "{}", day);
+            let iso = day.iso_week();
+            assert_eq!(year_week(day, 3), iso.year() * 100 + iso.week() as i32);
+            day = day.succ();
+        }
+    }
+}
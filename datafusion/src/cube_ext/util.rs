@@ -16,8 +16,11 @@
 // under the License.
 
 use crate::scalar::ScalarValue;
-use arrow::array::ArrayRef;
+use arrow::array::{new_null_array, ArrayBuilder, ArrayRef};
 use arrow::compute::{total_cmp_32, total_cmp_64};
+use arrow::datatypes::DataType;
+use arrow::error::Result as ArrowResult;
+use std::any::Any;
 use std::cmp::Ordering;
 
 /// Generic code to help implement generic operations on arrays.
@@ -27,9 +30,10 @@ macro_rules! cube_match_array {
     ($array: expr, $matcher: ident) => {{
         use $crate::arrow::array::*;
         use $crate::arrow::datatypes::*;
+        use $crate::cube_ext::util::NullBuilder;
         let a = $array;
         match a.data_type() {
-            DataType::Null => panic!("null type is not supported"),
+            DataType::Null => ($matcher!(a, NullArray, NullBuilder, Null)),
             DataType::Boolean => ($matcher!(a, BooleanArray, BooleanBuilder, Boolean)),
             DataType::Int8 => ($matcher!(a, Int8Array, PrimitiveBuilder<Int8Type>, Int8)),
             DataType::Int16 => {
@@ -206,7 +210,12 @@ macro_rules! cube_match_array {
 macro_rules! cube_match_scalar {
     ($scalar: expr, $matcher: ident $(, $arg: tt)*) => {{
         use $crate::arrow::array::*;
+        use $crate::cube_ext::util::NullBuilder;
         match $scalar {
+            ScalarValue::Null => {
+                let v = &None::<()>;
+                ($matcher!($($arg ,)* v, NullBuilder))
+            }
             ScalarValue::Boolean(v) => ($matcher!($($arg ,)* v, BooleanBuilder)),
             ScalarValue::Float32(v) => ($matcher!($($arg ,)* v, Float32Builder)),
             ScalarValue::Float64(v) => ($matcher!($($arg ,)* v, Float64Builder)),
@@ -262,6 +271,57 @@ macro_rules! cube_match_scalar {
     }};
 }
 
+/// Builds a [NullArray](arrow::array::NullArray), for the `DataType::Null` arms of
+/// [crate::cube_match_array] and [crate::cube_match_scalar]
+#[derive(Debug, Default)]
+pub struct NullBuilder {
+    len: usize,
+}
+
+impl NullBuilder {
+    /// Creates an empty builder, the capacity is ignored
+    pub fn new(_capacity: usize) -> Self {
+        Self::default()
+    }
+
+    /// Appends a null
+    pub fn append_null(&mut self) -> ArrowResult<()> {
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Appends a null, the only value of the null type
+    pub fn append_value(&mut self, _v: ()) -> ArrowResult<()> {
+        self.append_null()
+    }
+}
+
+impl ArrayBuilder for NullBuilder {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        new_null_array(&DataType::Null, std::mem::take(&mut self.len))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
 /// Panics if scalars are of different types.
 pub fn cmp_same_types(
     l: &ScalarValue,
@@ -387,6 +447,9 @@ pub fn cmp_array_row_same_types(
     }
 
     macro_rules! cmp_row {
+        ($l: expr, NullArray, $($rest: tt)*) => {{
+            return Ordering::Equal;
+        }};
         ($l: expr, Float32Array, $($rest: tt)*) => {{
             let l = $l.as_any().downcast_ref::<Float32Array>().unwrap();
             let r = r.as_any().downcast_ref::<Float32Array>().unwrap();
@@ -146,6 +146,22 @@ pub trait DataFrame: Send + Sync {
     /// ```
     fn union(&self, dataframe: Arc<dyn DataFrame>) -> Result<Arc<dyn DataFrame>>;
 
+    /// Calculate the union of two [`DataFrame`]s, removing duplicate rows. The two
+    /// [`DataFrame`]s must have exactly the same schema
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new())?;
+    /// let df = df.union_distinct(df.clone())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn union_distinct(&self, dataframe: Arc<dyn DataFrame>)
+        -> Result<Arc<dyn DataFrame>>;
+
     /// Sort the DataFrame by the specified sorting expressions. Any expression can be turned into
     /// a sort expression by calling its [sort](../logical_plan/enum.Expr.html#method.sort) method.
     ///
@@ -271,6 +287,23 @@ pub trait DataFrame: Send + Sync {
     /// ```
     fn explain(&self, verbose: bool) -> Result<Arc<dyn DataFrame>>;
 
+    /// Return a DataFrame computing the column statistics of the table this
+    /// DataFrame reads, as `ANALYZE <table>` does. The statistics are stored in the
+    /// catalog when the returned DataFrame is executed.
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// ctx.register_csv("example", "tests/example.csv", CsvReadOptions::new())?;
+    /// let batches = ctx.table("example")?.analyze()?.collect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn analyze(&self) -> Result<Arc<dyn DataFrame>>;
+
     /// Return a `FunctionRegistry` used to plan udf's calls
     ///
     /// ```
@@ -0,0 +1,248 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A table provider that adds the statistics computed by `ANALYZE` to another table.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+
+use crate::datasource::datasource::{
+    ColumnStatistics, Statistics, TableProviderFilterPushDown, TableVersion,
+};
+use crate::datasource::{TableProvider, TableType};
+use crate::error::Result;
+use crate::logical_plan::Expr;
+use crate::physical_plan::ExecutionPlan;
+
+/// A table with the statistics of its data at the time it was analyzed. The
+/// statistics of the source table take precedence, the analyzed ones are only
+/// reported where the source has none. They are not updated by inserts, so the
+/// statistics are never exact once an analyzed value is reported.
+pub struct AnalyzedTable {
+    source: Arc<dyn TableProvider>,
+    statistics: Statistics,
+}
+
+impl AnalyzedTable {
+    /// Create a table reporting `statistics` for `source`. When `source` was
+    /// analyzed before, the new statistics replace the previous ones.
+    pub fn new(source: Arc<dyn TableProvider>, statistics: Statistics) -> Self {
+        let source = match source.as_any().downcast_ref::<AnalyzedTable>() {
+            Some(analyzed) => analyzed.source.clone(),
+            None => source,
+        };
+        Self { source, statistics }
+    }
+
+    /// The table that was analyzed
+    pub fn source(&self) -> &Arc<dyn TableProvider> {
+        &self.source
+    }
+
+    /// The statistics computed by the analysis
+    pub fn analyzed_statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    /// The statistics of the source completed by the analyzed ones, and whether
+    /// any analyzed value was used.
+    fn merged_statistics(&self) -> (Statistics, bool) {
+        let mut used = false;
+        let source = self.source.statistics();
+        let analyzed = &self.statistics;
+        let column_statistics =
+            match (source.column_statistics, &analyzed.column_statistics) {
+                (Some(source), Some(analyzed)) => Some(
+                    source
+                        .into_iter()
+                        .zip(analyzed)
+                        .map(|(source, analyzed)| ColumnStatistics {
+                            null_count: merge(
+                                source.null_count,
+                                &analyzed.null_count,
+                                &mut used,
+                            ),
+                            max_value: merge(
+                                source.max_value,
+                                &analyzed.max_value,
+                                &mut used,
+                            ),
+                            min_value: merge(
+                                source.min_value,
+                                &analyzed.min_value,
+                                &mut used,
+                            ),
+                            distinct_count: merge(
+                                source.distinct_count,
+                                &analyzed.distinct_count,
+                                &mut used,
+                            ),
+                        })
+                        .collect(),
+                ),
+                (source, analyzed) => merge(source, analyzed, &mut used),
+            };
+        let statistics = Statistics {
+            num_rows: merge(source.num_rows, &analyzed.num_rows, &mut used),
+            total_byte_size: merge(
+                source.total_byte_size,
+                &analyzed.total_byte_size,
+                &mut used,
+            ),
+            column_statistics,
+        };
+        (statistics, used)
+    }
+}
+
+/// Returns the source value, or the analyzed one when the source has none, in
+/// which case `used` is set.
+fn merge<T: Clone>(
+    source: Option<T>,
+    analyzed: &Option<T>,
+    used: &mut bool,
+) -> Option<T> {
+    if source.is_some() {
+        return source;
+    }
+    *used |= analyzed.is_some();
+    analyzed.clone()
+}
+
+impl TableProvider for AnalyzedTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.source.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.source.table_type()
+    }
+
+    fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.source.scan(projection, batch_size, filters, limit)
+    }
+
+    fn scan_at_version(
+        &self,
+        version: &TableVersion,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.source
+            .scan_at_version(version, projection, batch_size, filters, limit)
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.merged_statistics().0
+    }
+
+    /// The analyzed statistics may be stale, so the statistics are only exact
+    /// when the source provides all of them and says they are exact.
+    fn has_exact_statistics(&self) -> bool {
+        self.source.has_exact_statistics() && !self.merged_statistics().1
+    }
+
+    fn supports_filter_pushdown(
+        &self,
+        filter: &Expr,
+    ) -> Result<TableProviderFilterPushDown> {
+        self.source.supports_filter_pushdown(filter)
+    }
+
+    fn insert(&self, batches: Vec<RecordBatch>) -> Result<()> {
+        self.source.insert(batches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::MemTable;
+    use crate::scalar::ScalarValue;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    #[test]
+    fn source_statistics_take_precedence() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![Some(1), None, Some(3)]))],
+        )?;
+        let source = Arc::new(MemTable::try_new(schema, vec![vec![batch]])?);
+
+        let analyzed = ColumnStatistics {
+            null_count: Some(5),
+            max_value: Some(ScalarValue::Int32(Some(3))),
+            min_value: Some(ScalarValue::Int32(Some(1))),
+            distinct_count: Some(2),
+        };
+        let table = AnalyzedTable::new(source, table_statistics(analyzed.clone()));
+        // analyzing again replaces the statistics instead of stacking tables
+        let table = AnalyzedTable::new(Arc::new(table), table_statistics(analyzed));
+        assert!(table.source().as_any().is::<MemTable>());
+
+        let statistics = table.statistics();
+        assert_eq!(statistics.num_rows, Some(3));
+        assert_eq!(
+            statistics.column_statistics,
+            Some(vec![ColumnStatistics {
+                null_count: Some(1),
+                max_value: Some(ScalarValue::Int32(Some(3))),
+                min_value: Some(ScalarValue::Int32(Some(1))),
+                distinct_count: Some(2),
+            }])
+        );
+        // the analyzed min, max and distinct count may be stale
+        assert!(!table.has_exact_statistics());
+
+        let table = AnalyzedTable::new(
+            table.source().clone(),
+            table_statistics(ColumnStatistics {
+                null_count: Some(5),
+                max_value: None,
+                min_value: None,
+                distinct_count: None,
+            }),
+        );
+        assert!(table.has_exact_statistics());
+        Ok(())
+    }
+
+    fn table_statistics(column: ColumnStatistics) -> Statistics {
+        Statistics {
+            num_rows: Some(10),
+            total_byte_size: None,
+            column_statistics: Some(vec![column]),
+        }
+    }
+}
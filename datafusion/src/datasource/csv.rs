@@ -54,6 +54,7 @@ pub struct CsvFile {
     has_header: bool,
     delimiter: u8,
     file_extension: String,
+    file_split_size: Option<usize>,
     statistics: Statistics,
 }
 
@@ -82,6 +83,7 @@ impl CsvFile {
             has_header: options.has_header,
             delimiter: options.delimiter,
             file_extension: String::from(options.file_extension),
+            file_split_size: options.file_split_size,
             statistics: Statistics::default(),
         })
     }
@@ -107,6 +109,7 @@ impl CsvFile {
             delimiter: options.delimiter,
             statistics: Statistics::default(),
             file_extension: String::new(),
+            file_split_size: None,
         })
     }
 
@@ -121,10 +124,10 @@ impl CsvFile {
                 let (schema, _) = arrow::csv::reader::infer_file_schema(
                     &mut reader,
                     options.delimiter,
-                    Some(options.schema_infer_max_records),
+                    options.schema_infer_limit(),
                     options.has_header,
                 )?;
-                schema
+                options.apply_schema_overrides(schema)
             }
         });
 
@@ -135,6 +138,7 @@ impl CsvFile {
             delimiter: options.delimiter,
             statistics: Statistics::default(),
             file_extension: String::new(),
+            file_split_size: None,
         })
     }
 
@@ -178,11 +182,14 @@ impl TableProvider for CsvFile {
         _filters: &[Expr],
         limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        let opts = CsvReadOptions::new()
+        let mut opts = CsvReadOptions::new()
             .schema(&self.schema)
             .has_header(self.has_header)
             .delimiter(self.delimiter)
             .file_extension(self.file_extension.as_str());
+        if let Some(file_split_size) = self.file_split_size {
+            opts = opts.file_split_size(file_split_size);
+        }
         let batch_size = limit
             .map(|l| std::cmp::min(l, batch_size))
             .unwrap_or(batch_size);
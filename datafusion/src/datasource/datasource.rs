@@ -18,12 +18,16 @@
 //! Data source traits
 
 use std::any::Any;
+use std::fmt;
 use std::sync::Arc;
 
-use crate::error::Result;
+use crate::error::{DataFusionError, Result};
 use crate::logical_plan::Expr;
 use crate::physical_plan::ExecutionPlan;
-use crate::{arrow::datatypes::SchemaRef, scalar::ScalarValue};
+use crate::{
+    arrow::{datatypes::SchemaRef, record_batch::RecordBatch},
+    scalar::ScalarValue,
+};
 
 /// This table statistics are estimates.
 /// It can not be used directly in the precise compute
@@ -77,6 +81,24 @@ pub enum TableType {
     Temporary,
 }
 
+/// Identifies a snapshot of a table for scans at a version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableVersion {
+    /// A version number assigned by the table provider
+    Version(u64),
+    /// The state of the table at a point in time, in nanoseconds since the Unix epoch
+    Timestamp(i64),
+}
+
+impl fmt::Display for TableVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TableVersion::Version(v) => write!(f, "version {}", v),
+            TableVersion::Timestamp(t) => write!(f, "timestamp {}", t),
+        }
+    }
+}
+
 /// Source table
 pub trait TableProvider: Sync + Send {
     /// Returns the table provider as [`Any`](std::any::Any) so that it can be
@@ -104,6 +126,24 @@ pub trait TableProvider: Sync + Send {
         limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>>;
 
+    /// Create an ExecutionPlan that will scan the snapshot of the table identified by
+    /// `version`, e.g. to read consistent data from a replica that is being updated.
+    /// The arguments are the same as for [`scan`](TableProvider::scan). Providers
+    /// without snapshots return an error.
+    fn scan_at_version(
+        &self,
+        version: &TableVersion,
+        _projection: &Option<Vec<usize>>,
+        _batch_size: usize,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Err(DataFusionError::NotImplemented(format!(
+            "Table does not support scans at {}",
+            version
+        )))
+    }
+
     /// Returns the table Statistics
     /// Statistics should be optional because not all data sources can provide statistics.
     fn statistics(&self) -> Statistics;
@@ -121,4 +161,12 @@ pub trait TableProvider: Sync + Send {
     ) -> Result<TableProviderFilterPushDown> {
         Ok(TableProviderFilterPushDown::Unsupported)
     }
+
+    /// Appends `batches` to the table. The batches have the columns of the table
+    /// schema, in order. Read-only providers return an error.
+    fn insert(&self, _batches: Vec<RecordBatch>) -> Result<()> {
+        Err(DataFusionError::NotImplemented(
+            "Table does not support inserts".to_string(),
+        ))
+    }
 }
@@ -22,7 +22,7 @@
 use futures::StreamExt;
 use log::debug;
 use std::any::Any;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use arrow::datatypes::{Field, Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
@@ -44,8 +44,12 @@ use super::datasource::ColumnStatistics;
 /// In-memory table
 pub struct MemTable {
     schema: SchemaRef,
-    batches: Vec<Vec<RecordBatch>>,
-    statistics: Statistics,
+    /// The batches of each partition and their statistics, updated by inserts
+    data: RwLock<(Vec<Vec<RecordBatch>>, Statistics)>,
+    /// Values of the columns omitted by inserts
+    column_defaults: Vec<(String, Expr)>,
+    /// Expressions computing the values of generated columns on insert
+    generated_columns: Vec<(String, Expr)>,
 }
 
 // Calculates statistics based on partitions
@@ -99,8 +103,9 @@ impl MemTable {
 
             Ok(Self {
                 schema,
-                batches: partitions,
-                statistics,
+                data: RwLock::new((partitions, statistics)),
+                column_defaults: vec![],
+                generated_columns: vec![],
             })
         } else {
             Err(DataFusionError::Plan(
@@ -109,6 +114,36 @@ impl MemTable {
         }
     }
 
+    /// Use `defaults`, pairs of column names and expressions, for the values of the
+    /// columns omitted by inserts. Other omitted columns are NULL.
+    pub fn with_column_defaults(mut self, defaults: Vec<(String, Expr)>) -> Self {
+        self.column_defaults = defaults;
+        self
+    }
+
+    /// Compute the values of the `columns`, pairs of column names and expressions
+    /// referring to other columns of the table, for the inserted rows
+    pub fn with_generated_columns(mut self, columns: Vec<(String, Expr)>) -> Self {
+        self.generated_columns = columns;
+        self
+    }
+
+    /// The default value of the column `name`, if it has one
+    pub fn column_default(&self, name: &str) -> Option<&Expr> {
+        self.column_defaults
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, e)| e)
+    }
+
+    /// The expression computing the column `name`, if it is generated
+    pub fn generated_column(&self, name: &str) -> Option<&Expr> {
+        self.generated_columns
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, e)| e)
+    }
+
     /// Create a mem table by reading from another data source
     pub async fn load(
         t: Arc<dyn TableProvider>,
@@ -208,19 +243,55 @@ impl TableProvider for MemTable {
         let projected_schema = Arc::new(Schema::new(projected_columns?));
 
         Ok(Arc::new(MemoryExec::try_new(
-            &self.batches.clone(),
+            &self.data.read().unwrap().0,
             projected_schema,
             projection.clone(),
         )?))
     }
 
     fn statistics(&self) -> Statistics {
-        self.statistics.clone()
+        self.data.read().unwrap().1.clone()
     }
 
     fn has_exact_statistics(&self) -> bool {
         true
     }
+
+    fn insert(&self, batches: Vec<RecordBatch>) -> Result<()> {
+        let batches = batches
+            .into_iter()
+            .map(|batch| {
+                for (field, column) in self.schema.fields().iter().zip(batch.columns()) {
+                    if !field.is_nullable() && column.null_count() != 0 {
+                        return Err(DataFusionError::Execution(format!(
+                            "Cannot insert NULL into non-nullable column {}",
+                            field.name()
+                        )));
+                    }
+                }
+                Ok(RecordBatch::try_new(
+                    self.schema.clone(),
+                    batch.columns().to_vec(),
+                )?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut data = self.data.write().unwrap();
+        let (partitions, statistics) = &mut *data;
+        if partitions.is_empty() {
+            partitions.push(vec![]);
+        }
+        // keep the partitions balanced by adding each batch to the smallest one
+        for batch in batches {
+            partitions
+                .iter_mut()
+                .min_by_key(|p| p.iter().map(RecordBatch::num_rows).sum::<usize>())
+                .unwrap()
+                .push(batch);
+        }
+        *statistics = calculate_statistics(&self.schema, partitions);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -323,6 +394,48 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_insert() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(Int32Array::from(vec![Some(3), None])),
+            ],
+        )?;
+
+        let provider = MemTable::try_new(schema.clone(), vec![vec![], vec![]])?;
+        provider.insert(vec![batch.clone(), batch])?;
+        let statistics = provider.statistics();
+        assert_eq!(statistics.num_rows, Some(4));
+        assert_eq!(statistics.column_statistics.unwrap()[1].null_count, Some(2));
+
+        // the batches are spread over the partitions
+        let exec = provider.scan(&None, 1024, &[], None)?;
+        for partition in 0..2 {
+            let batches = common::collect(exec.execute(partition).await?).await?;
+            assert_eq!(batches.len(), 1);
+        }
+
+        let nulls = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![None])),
+                Arc::new(Int32Array::from(vec![Some(1)])),
+            ],
+        )?;
+        let err = provider.insert(vec![nulls]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Cannot insert NULL into non-nullable column a"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_invalid_projection() -> Result<()> {
         let schema = Arc::new(Schema::new(vec![
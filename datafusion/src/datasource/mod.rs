@@ -17,15 +17,17 @@
 
 //! DataFusion data sources
 
+pub mod analyzed;
 pub mod csv;
 pub mod datasource;
 pub mod empty;
 pub mod json;
 pub mod memory;
 pub mod parquet;
+pub mod snapshot;
 
 pub use self::csv::{CsvFile, CsvReadOptions};
-pub use self::datasource::{TableProvider, TableType};
+pub use self::datasource::{TableProvider, TableType, TableVersion};
 pub use self::memory::MemTable;
 
 /// Source for table input data
@@ -23,12 +23,14 @@ use std::sync::Arc;
 
 use arrow::datatypes::*;
 
-use crate::datasource::datasource::Statistics;
+use crate::datasource::datasource::{ColumnStatistics, Statistics};
 use crate::datasource::TableProvider;
-use crate::error::Result;
+use crate::error::{DataFusionError, Result};
 use crate::logical_plan::{combine_filters, Expr};
-use crate::physical_plan::parquet::{MetadataCacheFactory, ParquetExec};
-use crate::physical_plan::ExecutionPlan;
+use crate::physical_plan::parquet::{
+    shredded_schema, DeletionVectorProvider, MetadataCacheFactory, ParquetExec,
+};
+use crate::physical_plan::{common, ExecutionPlan};
 
 use super::datasource::TableProviderFilterPushDown;
 
@@ -40,6 +42,9 @@ pub struct ParquetTable {
     statistics: Statistics,
     max_concurrency: usize,
     enable_pruning: bool,
+    file_sort_order: Option<Vec<usize>>,
+    deletion_vectors: Option<Arc<dyn DeletionVectorProvider>>,
+    shredded_fields: bool,
 }
 
 impl ParquetTable {
@@ -64,9 +69,12 @@ impl ParquetTable {
             path,
             metadata_cache_factory,
             schema,
-            statistics: parquet_exec.statistics().to_owned(),
+            statistics: parquet_exec.dataset_statistics().to_owned(),
             max_concurrency,
             enable_pruning: true,
+            file_sort_order: None,
+            deletion_vectors: None,
+            shredded_fields: false,
         })
     }
 
@@ -85,6 +93,61 @@ impl ParquetTable {
         self.enable_pruning = enable_pruning;
         self
     }
+
+    /// Declare that each file is sorted on the `columns` and that the files are
+    /// ordered the same way when sorted by name, e.g. files named after the time range
+    /// they cover. Scans then read the files in name order, splitting them into groups
+    /// of consecutive files with one partition per group, and report the order of the
+    /// partitions in their output hints so that it does not need to be sorted again.
+    pub fn with_file_sort_order(mut self, columns: &[&str]) -> Result<Self> {
+        let sort_order = columns
+            .iter()
+            .map(|name| {
+                self.schema.index_of(name).map_err(|_| {
+                    DataFusionError::Plan(format!(
+                        "Sort column {} not found in the Parquet schema",
+                        name
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.file_sort_order = Some(sort_order);
+        Ok(self)
+    }
+
+    /// Skip the rows of each file that `deletion_vectors` reports as deleted. The
+    /// statistics of the table are no longer exact, as they include deleted rows.
+    pub fn with_deletion_vectors(
+        mut self,
+        deletion_vectors: Arc<dyn DeletionVectorProvider>,
+    ) -> Self {
+        self.deletion_vectors = Some(deletion_vectors);
+        self
+    }
+
+    /// Add the fields nested in the struct columns of the files to the schema of the
+    /// table as columns of their own, named by their path such as `s.a.b`, see
+    /// [shredded_schema]. Scans of these columns only read the column chunks of the
+    /// nested fields rather than those of the whole struct columns, and the
+    /// `NestedFieldPushDown` optimizer rule rewrites `s.a.b` to such a column.
+    pub fn with_shredded_fields(mut self) -> Self {
+        if self.shredded_fields {
+            return self;
+        }
+        let schema = shredded_schema(&self.schema);
+        if let Some(column_statistics) = &mut self.statistics.column_statistics {
+            let unknown = ColumnStatistics {
+                null_count: None,
+                max_value: None,
+                min_value: None,
+                distinct_count: None,
+            };
+            column_statistics.resize(schema.fields().len(), unknown);
+        }
+        self.schema = Arc::new(schema);
+        self.shredded_fields = true;
+        self
+    }
 }
 
 impl TableProvider for ParquetTable {
@@ -121,17 +184,59 @@ impl TableProvider for ParquetTable {
         } else {
             None
         };
-        Ok(Arc::new(ParquetExec::try_from_path_with_cache(
-            &self.path,
-            projection.clone(),
-            predicate,
+        let batch_size = limit
+            .map(|l| std::cmp::min(l, batch_size))
+            .unwrap_or(batch_size);
+        // The files to read are chosen by the number of rows in their metadata, which
+        // counts deleted rows too. Limits are applied after the scan in this case.
+        let limit = if self.deletion_vectors.is_some() {
+            None
+        } else {
             limit
-                .map(|l| std::cmp::min(l, batch_size))
-                .unwrap_or(batch_size),
-            self.max_concurrency,
-            limit,
-            self.metadata_cache_factory.make_noop_cache(),
-        )?))
+        };
+        // the projection of the parquet reader only defaults to the file schema
+        let projection = match projection {
+            None if self.shredded_fields => {
+                Some((0..self.schema.fields().len()).collect())
+            }
+            projection => projection.clone(),
+        };
+        let mut exec = match &self.file_sort_order {
+            None => ParquetExec::try_from_path_with_cache(
+                &self.path,
+                projection,
+                predicate,
+                batch_size,
+                self.max_concurrency,
+                limit,
+                self.metadata_cache_factory.make_noop_cache(),
+            )?,
+            Some(sort_order) => {
+                let mut filenames = common::build_file_list(&self.path, ".parquet")?;
+                if filenames.is_empty() {
+                    return Err(DataFusionError::Plan(format!(
+                        "No Parquet files (with .parquet extension) found at path {}",
+                        self.path
+                    )));
+                }
+                filenames.sort();
+                let filenames = filenames.iter().map(|f| f.as_str()).collect::<Vec<_>>();
+                ParquetExec::try_from_files_with_cache(
+                    &filenames,
+                    projection,
+                    predicate,
+                    batch_size,
+                    self.max_concurrency,
+                    limit,
+                    self.metadata_cache_factory.make_noop_cache(),
+                )?
+                .with_file_sort_order(sort_order.clone())
+            }
+        };
+        if let Some(deletion_vectors) = &self.deletion_vectors {
+            exec = exec.with_deletion_vectors(deletion_vectors.clone());
+        }
+        Ok(Arc::new(exec))
     }
 
     fn statistics(&self) -> Statistics {
@@ -139,7 +244,8 @@ impl TableProvider for ParquetTable {
     }
 
     fn has_exact_statistics(&self) -> bool {
-        true
+        // the statistics of the files include deleted rows
+        self.deletion_vectors.is_none()
     }
 }
 
@@ -369,6 +475,147 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn file_sort_order_hints() -> Result<()> {
+        let testdata = crate::test_util::parquet_test_data();
+        let filename = format!("{}/alltypes_plain.parquet", testdata);
+        let table = ParquetTable::try_new(
+            &filename,
+            Arc::new(BasicMetadataCacheFactory::new()),
+            2,
+        )?
+        .with_file_sort_order(&["id", "bool_col"])?;
+
+        let exec = table.scan(&Some(vec![1, 0]), 1024, &[], None)?;
+        assert_eq!(exec.output_hints().sort_order, Some(vec![1, 0]));
+        let exec = table.scan(&Some(vec![0, 2]), 1024, &[], None)?;
+        assert_eq!(exec.output_hints().sort_order, Some(vec![0]));
+        let exec = table.scan(&Some(vec![1]), 1024, &[], None)?;
+        assert_eq!(exec.output_hints().sort_order, None);
+
+        let table = load_table("alltypes_plain.parquet")?;
+        let exec = table.scan(&None, 1024, &[], None)?;
+        assert_eq!(exec.output_hints().sort_order, None);
+
+        let err = ParquetTable::try_new(
+            &filename,
+            Arc::new(BasicMetadataCacheFactory::new()),
+            2,
+        )?
+        .with_file_sort_order(&["missing"])
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Sort column missing not found in the Parquet schema"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_with_deletion_vectors() -> Result<()> {
+        use crate::test::DeleteRows;
+
+        let testdata = crate::test_util::parquet_test_data();
+        let filename = format!("{}/alltypes_plain.parquet", testdata);
+        let table = ParquetTable::try_new(
+            &filename,
+            Arc::new(BasicMetadataCacheFactory::new()),
+            2,
+        )?
+        .with_deletion_vectors(Arc::new(DeleteRows(vec![1, 6])));
+        assert!(!table.has_exact_statistics());
+
+        let batch = get_first_batch(Arc::new(table), &Some(vec![0])).await?;
+        let array = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap();
+        let values = (0..array.len()).map(|i| array.value(i)).collect::<Vec<_>>();
+        assert_eq!(values, vec![4, 6, 7, 2, 3, 1]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_shredded_fields() -> Result<()> {
+        use crate::execution::context::ExecutionContext;
+        use arrow::array::{ArrayRef, StructArray};
+        use parquet::arrow::ArrowWriter;
+
+        let tmp_dir = tempfile::TempDir::new()?;
+        let filename = format!("{}/a.parquet", tmp_dir.path().to_str().unwrap());
+        let s = StructArray::from(vec![
+            (
+                Field::new("a", DataType::Int32, false),
+                Arc::new(Int32Array::from(vec![1, 2, 3])) as ArrayRef,
+            ),
+            (
+                Field::new("b", DataType::Float64, false),
+                Arc::new(Float64Array::from(vec![0.5, 1.5, 2.5])) as ArrayRef,
+            ),
+        ]);
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "s",
+            s.data_type().clone(),
+            false,
+        )]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(s)])?;
+        let file = std::fs::File::create(&filename)?;
+        let mut writer = ArrowWriter::try_new(file, schema, None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        let table = ParquetTable::try_new(
+            &filename,
+            Arc::new(BasicMetadataCacheFactory::new()),
+            1,
+        )?
+        .with_shredded_fields();
+        assert_eq!(table.schema().fields().len(), 3);
+        assert_eq!(
+            table
+                .statistics()
+                .column_statistics
+                .map(|stats| stats.len()),
+            Some(3)
+        );
+
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table("t", Arc::new(table))?;
+        let sql = "SELECT s.b FROM t WHERE s.a > 1";
+        let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+        // only the shredded fields are read
+        let plan = format!("{:?}", plan);
+        assert!(
+            plan.contains("TableScan: t projection=Some([1, 2])"),
+            "{}",
+            plan
+        );
+
+        let batches = ctx.sql(sql)?.collect().await?;
+        let expected = vec![
+            "+-------+",
+            "| t.s.b |",
+            "+-------+",
+            "| 1.5   |",
+            "| 2.5   |",
+            "+-------+",
+        ];
+        crate::assert_batches_eq!(expected, &batches);
+
+        // the shredded fields only duplicate the struct column
+        let df = ctx.sql("SELECT * FROM t")?;
+        let names = df
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.qualified_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["t.s"]);
+        Ok(())
+    }
+
     fn load_table(name: &str) -> Result<Arc<dyn TableProvider>> {
         let testdata = crate::test_util::parquet_test_data();
         let filename = format!("{}/{}", testdata, name);
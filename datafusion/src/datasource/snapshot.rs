@@ -0,0 +1,204 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! A table provider that reads a snapshot of another table at a fixed version.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+
+use crate::datasource::datasource::{
+    Statistics, TableProviderFilterPushDown, TableVersion,
+};
+use crate::datasource::{TableProvider, TableType};
+use crate::error::Result;
+use crate::logical_plan::Expr;
+use crate::physical_plan::ExecutionPlan;
+
+/// The snapshot of a table at a version. Scans of the snapshot are
+/// [scans at the version](TableProvider::scan_at_version) of the table.
+pub struct TableSnapshot {
+    source: Arc<dyn TableProvider>,
+    version: TableVersion,
+}
+
+impl TableSnapshot {
+    /// Create the snapshot of `source` at `version`
+    pub fn new(source: Arc<dyn TableProvider>, version: TableVersion) -> Self {
+        Self { source, version }
+    }
+
+    /// The table the snapshot is taken from
+    pub fn source(&self) -> &Arc<dyn TableProvider> {
+        &self.source
+    }
+
+    /// The version of the snapshot
+    pub fn version(&self) -> &TableVersion {
+        &self.version
+    }
+}
+
+impl TableProvider for TableSnapshot {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.source.schema()
+    }
+
+    fn table_type(&self) -> TableType {
+        self.source.table_type()
+    }
+
+    fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.source
+            .scan_at_version(&self.version, projection, batch_size, filters, limit)
+    }
+
+    /// The statistics of the current version of the table, which are only estimates
+    /// for the snapshot
+    fn statistics(&self) -> Statistics {
+        self.source.statistics()
+    }
+
+    fn supports_filter_pushdown(
+        &self,
+        filter: &Expr,
+    ) -> Result<TableProviderFilterPushDown> {
+        self.source.supports_filter_pushdown(filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::MemTable;
+    use crate::error::DataFusionError;
+    use crate::execution::context::ExecutionContext;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+
+    /// Keeps every version of a table in memory
+    struct VersionedTable {
+        versions: Vec<MemTable>,
+    }
+
+    impl VersionedTable {
+        fn try_new(versions: Vec<Vec<i32>>) -> Result<Self> {
+            let schema =
+                Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+            let versions = versions
+                .into_iter()
+                .map(|values| {
+                    let batch = RecordBatch::try_new(
+                        schema.clone(),
+                        vec![Arc::new(Int32Array::from(values))],
+                    )?;
+                    MemTable::try_new(schema.clone(), vec![vec![batch]])
+                })
+                .collect::<Result<_>>()?;
+            Ok(Self { versions })
+        }
+    }
+
+    impl TableProvider for VersionedTable {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn schema(&self) -> SchemaRef {
+            self.versions[0].schema()
+        }
+
+        fn scan(
+            &self,
+            projection: &Option<Vec<usize>>,
+            batch_size: usize,
+            filters: &[Expr],
+            limit: Option<usize>,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            let latest = TableVersion::Version(self.versions.len() as u64 - 1);
+            self.scan_at_version(&latest, projection, batch_size, filters, limit)
+        }
+
+        fn scan_at_version(
+            &self,
+            version: &TableVersion,
+            projection: &Option<Vec<usize>>,
+            batch_size: usize,
+            filters: &[Expr],
+            limit: Option<usize>,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            match version {
+                TableVersion::Version(v) if (*v as usize) < self.versions.len() => self
+                    .versions[*v as usize]
+                    .scan(projection, batch_size, filters, limit),
+                _ => Err(DataFusionError::Plan(format!("No {}", version))),
+            }
+        }
+
+        fn statistics(&self) -> Statistics {
+            Statistics::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn scan_snapshots() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table(
+            "t",
+            Arc::new(VersionedTable::try_new(vec![vec![1], vec![1, 2]])?),
+        )?;
+
+        let df = ctx.table_at_version("t", TableVersion::Version(0))?;
+        let results = df.collect().await?;
+        let expected = vec!["+---+", "| a |", "+---+", "| 1 |", "+---+"];
+        crate::assert_batches_eq!(expected, &results);
+
+        let results = ctx.table("t")?.collect().await?;
+        let expected = vec!["+---+", "| a |", "+---+", "| 1 |", "| 2 |", "+---+"];
+        crate::assert_batches_eq!(expected, &results);
+
+        let df = ctx.table_at_version("t", TableVersion::Version(2))?;
+        assert_eq!(
+            df.collect().await.unwrap_err().to_string(),
+            "Error during planning: No version 2"
+        );
+
+        // tables without snapshots
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let memory = TableSnapshot::new(
+            Arc::new(MemTable::try_new(schema, vec![])?),
+            TableVersion::Timestamp(0),
+        );
+        assert_eq!(
+            memory.scan(&None, 1024, &[], None).unwrap_err().to_string(),
+            "This feature is not implemented: Table does not support scans at timestamp 0"
+        );
+        Ok(())
+    }
+}
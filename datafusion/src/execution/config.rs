@@ -0,0 +1,540 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Runtime configuration options, readable and settable from SQL via
+//! `SHOW <key>` and `SET <key> = <value>`.
+
+use crate::error::{DataFusionError, Result};
+use crate::scalar::ScalarValue;
+use arrow::datatypes::DataType;
+use std::collections::BTreeMap;
+
+/// Number of rows in the batches produced by data sources
+pub const OPT_BATCH_SIZE: &str = "datafusion.execution.batch_size";
+
+/// Number of partitions to use when repartitioning data for parallel execution
+pub const OPT_TARGET_PARTITIONS: &str = "datafusion.execution.target_partitions";
+
+/// Upper bound (in bytes) on memory used by operators that can track their usage
+pub const OPT_MEMORY_LIMIT: &str = "datafusion.execution.memory_limit";
+
+/// Whether the Parquet reader uses predicates to prune row groups
+pub const OPT_PARQUET_PRUNING: &str = "datafusion.execution.parquet_pruning";
+
+/// Whether CAST replaces values that cannot be converted with NULL instead of failing
+pub const OPT_LENIENT_CAST: &str = "datafusion.execution.lenient_cast";
+
+/// Collation used to compare and sort strings that have no explicit `COLLATE`
+pub const OPT_COLLATION: &str = "datafusion.execution.collation";
+
+/// Handling of the integer and decimal arithmetic and SUMs that overflow their type
+pub const OPT_ARITHMETIC_OVERFLOW: &str = "datafusion.execution.arithmetic_overflow";
+
+/// Whether SUM and AVG of floats use a compensated summation whose result does not
+/// depend on the batches and the partitions of the values
+pub const OPT_COMPENSATED_FLOAT_SUM: &str = "datafusion.execution.compensated_float_sum";
+
+/// Time zone of strings without an offset converted to timestamps and of the parts
+/// extracted from timestamps
+pub const OPT_TIME_ZONE: &str = "datafusion.execution.time_zone";
+
+/// Seed for the generators used by `random()` and `uuid()`
+pub const OPT_RANDOM_SEED: &str = "datafusion.execution.random_seed";
+
+/// Comma separated namespaces searched for the functions called without a namespace
+pub const OPT_FUNCTION_SEARCH_PATH: &str = "datafusion.sql.function_search_path";
+
+/// User of the session returned by `user()`
+pub const OPT_SESSION_USER: &str = "datafusion.session.user";
+
+/// Identifier of the connection of the session returned by `connection_id()`
+pub const OPT_CONNECTION_ID: &str = "datafusion.session.connection_id";
+
+/// Version of the server returned by `version()`
+pub const OPT_SERVER_VERSION: &str = "datafusion.session.server_version";
+
+/// Whether joins are repartitioned on their keys
+pub const OPT_REPARTITION_JOINS: &str = "datafusion.optimizer.repartition_joins";
+
+/// Whether aggregates are repartitioned on their group keys
+pub const OPT_REPARTITION_AGGREGATIONS: &str =
+    "datafusion.optimizer.repartition_aggregations";
+
+/// Maximum number of rows of a hash join build side that is collected into a single
+/// partition shared by all probe partitions
+pub const OPT_HASH_JOIN_SINGLE_PARTITION_THRESHOLD: &str =
+    "datafusion.optimizer.hash_join_single_partition_threshold";
+
+/// Whether window functions are repartitioned on their partition keys
+pub const OPT_REPARTITION_WINDOWS: &str = "datafusion.optimizer.repartition_windows";
+
+/// Maximum number of times a fixpoint batch of optimizer rules is applied to a plan
+pub const OPT_OPTIMIZER_MAX_PASSES: &str = "datafusion.optimizer.max_passes";
+
+/// Whether an optimizer rule that fails is skipped instead of failing the query
+pub const OPT_SKIP_FAILED_RULES: &str = "datafusion.optimizer.skip_failed_rules";
+
+/// Number of digits after the decimal point of the floats written by the output
+/// utilities
+pub const OPT_FLOAT_PRECISION: &str = "datafusion.format.float_precision";
+
+/// Exponent of the power of ten from which the floats written by the output
+/// utilities are in scientific notation
+pub const OPT_SCIENTIFIC_NOTATION_UPPER_EXPONENT: &str =
+    "datafusion.format.scientific_notation_upper_exponent";
+
+/// Exponent of the negative power of ten below which the floats written by the
+/// output utilities are in scientific notation
+pub const OPT_SCIENTIFIC_NOTATION_LOWER_EXPONENT: &str =
+    "datafusion.format.scientific_notation_lower_exponent";
+
+/// Whether NaN and infinite floats are written as strings or NULL values by the
+/// output utilities
+pub const OPT_NON_FINITE_FLOATS: &str = "datafusion.format.non_finite_floats";
+
+/// Splits the value of the `datafusion.sql.function_search_path` option into its
+/// namespaces
+pub fn parse_function_search_path(path: &str) -> Vec<String> {
+    path.split(',')
+        .map(|namespace| namespace.trim())
+        .filter(|namespace| !namespace.is_empty())
+        .map(|namespace| namespace.to_string())
+        .collect()
+}
+
+/// Definition of a single configuration option
+#[derive(Debug, Clone)]
+pub struct ConfigDefinition {
+    /// Fully qualified key, e.g. `datafusion.execution.batch_size`
+    pub key: String,
+    /// Human readable description of the option
+    pub description: String,
+    /// Type of the values accepted by the option
+    pub data_type: DataType,
+    /// Value used when the option was not set explicitly
+    pub default_value: ScalarValue,
+}
+
+impl ConfigDefinition {
+    /// Creates a new option definition
+    pub fn new(
+        key: impl Into<String>,
+        description: impl Into<String>,
+        data_type: DataType,
+        default_value: ScalarValue,
+    ) -> Self {
+        Self {
+            key: key.into(),
+            description: description.into(),
+            data_type,
+            default_value,
+        }
+    }
+
+    /// Creates a boolean option definition
+    pub fn new_bool(
+        key: impl Into<String>,
+        description: impl Into<String>,
+        default_value: bool,
+    ) -> Self {
+        Self::new(
+            key,
+            description,
+            DataType::Boolean,
+            ScalarValue::Boolean(Some(default_value)),
+        )
+    }
+
+    /// Creates an unsigned integer option definition
+    pub fn new_u64(
+        key: impl Into<String>,
+        description: impl Into<String>,
+        default_value: Option<u64>,
+    ) -> Self {
+        Self::new(
+            key,
+            description,
+            DataType::UInt64,
+            ScalarValue::UInt64(default_value),
+        )
+    }
+
+    /// Creates a string option definition
+    pub fn new_string(
+        key: impl Into<String>,
+        description: impl Into<String>,
+        default_value: Option<String>,
+    ) -> Self {
+        Self::new(
+            key,
+            description,
+            DataType::Utf8,
+            ScalarValue::Utf8(default_value),
+        )
+    }
+
+    /// Parses the textual representation of a value for this option
+    pub fn parse_value(&self, value: &str) -> Result<ScalarValue> {
+        let invalid = || {
+            DataFusionError::Plan(format!(
+                "Invalid value '{}' for configuration option '{}' of type {:?}",
+                value, self.key, self.data_type
+            ))
+        };
+        let is_null = value.eq_ignore_ascii_case("null");
+        match self.data_type {
+            DataType::Boolean => match value.to_lowercase().as_str() {
+                "true" | "on" | "1" => Ok(ScalarValue::Boolean(Some(true))),
+                "false" | "off" | "0" => Ok(ScalarValue::Boolean(Some(false))),
+                _ => Err(invalid()),
+            },
+            DataType::UInt64 if is_null => Ok(ScalarValue::UInt64(None)),
+            DataType::UInt64 => value
+                .parse::<u64>()
+                .map(|v| ScalarValue::UInt64(Some(v)))
+                .map_err(|_| invalid()),
+            DataType::Utf8 if is_null => Ok(ScalarValue::Utf8(None)),
+            DataType::Utf8 => Ok(ScalarValue::Utf8(Some(value.to_string()))),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Structured collection of configuration options keyed by their
+/// fully qualified names
+#[derive(Debug, Clone)]
+pub struct ConfigOptions {
+    definitions: BTreeMap<String, ConfigDefinition>,
+    options: BTreeMap<String, ScalarValue>,
+}
+
+impl Default for ConfigOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigOptions {
+    /// Creates options populated with the built-in definitions and their defaults
+    pub fn new() -> Self {
+        let definitions = vec![
+            ConfigDefinition::new_u64(
+                OPT_BATCH_SIZE,
+                "Default batch size when reading data sources",
+                Some(8192),
+            ),
+            ConfigDefinition::new_u64(
+                OPT_TARGET_PARTITIONS,
+                "Number of partitions (and concurrent threads) for query execution",
+                Some(num_cpus::get() as u64),
+            ),
+            ConfigDefinition::new_u64(
+                OPT_MEMORY_LIMIT,
+                "Maximum number of bytes operators may buffer in memory. \
+                 Unlimited when not set",
+                None,
+            ),
+            ConfigDefinition::new_bool(
+                OPT_PARQUET_PRUNING,
+                "Use filter predicates to prune Parquet row groups",
+                true,
+            ),
+            ConfigDefinition::new_bool(
+                OPT_LENIENT_CAST,
+                "Make CAST produce NULL for values that cannot be converted, as \
+                 TRY_CAST does, instead of failing the query",
+                false,
+            ),
+            ConfigDefinition::new_string(
+                OPT_COLLATION,
+                "Collation of string comparisons (=, <>, <, <=, >, >=, BETWEEN, IN and \
+                 JOIN ... ON), ORDER BY, GROUP BY and SELECT DISTINCT without an \
+                 explicit COLLATE: binary, case_insensitive, accent_insensitive or \
+                 case_accent_insensitive. Groups show the smallest of their original \
+                 strings. GROUPING SETS, JOIN ... USING, MIN and MAX compare bytes",
+                Some("binary".to_string()),
+            ),
+            ConfigDefinition::new_string(
+                OPT_ARITHMETIC_OVERFLOW,
+                "Handling of the results of +, -, * and SUM of integers and decimals \
+                 that overflow their type: unchecked, leaving them to the compute \
+                 kernels, error, failing the query, or promote, computing them in a \
+                 wider integer or decimal type and failing the query if they still \
+                 overflow. Types are only promoted in SQL queries",
+                Some("unchecked".to_string()),
+            ),
+            ConfigDefinition::new_bool(
+                OPT_COMPENSATED_FLOAT_SUM,
+                "Compute SUM and AVG of floats with a compensated (Kahan) summation \
+                 of the values, merging the partial sums of partitions in a fixed \
+                 order, so that their results are more accurate and do not vary with \
+                 the batch size or the order in which partitions complete",
+                false,
+            ),
+            ConfigDefinition::new_string(
+                OPT_TIME_ZONE,
+                "Time zone of the session: UTC, a fixed offset such as +05:30 or a \
+                 zone of the tz database such as America/New_York. \
+                 Strings without an offset are converted to timestamps in this time \
+                 zone and date_part extracts the local hour and date",
+                Some("UTC".to_string()),
+            ),
+            ConfigDefinition::new_u64(
+                OPT_RANDOM_SEED,
+                "Seed for random number generation, making the results of random() \
+                 and uuid() reproducible. Uses an unseeded generator when not set",
+                None,
+            ),
+            ConfigDefinition::new_string(
+                OPT_FUNCTION_SEARCH_PATH,
+                "Comma separated namespaces of the functions, such as pg_catalog, that \
+                 can be called without their namespace. Built-in functions are found \
+                 first, then functions registered without a namespace",
+                Some("pg_catalog".to_string()),
+            ),
+            ConfigDefinition::new_string(
+                OPT_SESSION_USER,
+                "User of the session, as `user@host`, returned by user(). NULL when \
+                 not set",
+                None,
+            ),
+            ConfigDefinition::new_u64(
+                OPT_CONNECTION_ID,
+                "Identifier of the client connection returned by connection_id(). \
+                 NULL when not set",
+                None,
+            ),
+            ConfigDefinition::new_string(
+                OPT_SERVER_VERSION,
+                "Server version returned by version(). MySQL clients choose the \
+                 features they use from it",
+                Some("8.0.25".to_string()),
+            ),
+            ConfigDefinition::new_bool(
+                OPT_REPARTITION_JOINS,
+                "Repartition join inputs on the join keys",
+                true,
+            ),
+            ConfigDefinition::new_bool(
+                OPT_REPARTITION_AGGREGATIONS,
+                "Repartition aggregate inputs on the group keys",
+                true,
+            ),
+            ConfigDefinition::new_bool(
+                OPT_REPARTITION_WINDOWS,
+                "Repartition window function inputs on the partition keys",
+                true,
+            ),
+            ConfigDefinition::new_u64(
+                OPT_HASH_JOIN_SINGLE_PARTITION_THRESHOLD,
+                "Hash joins whose build side is known to have at most this many rows \
+                 build a single hash table shared by all probe partitions instead of \
+                 repartitioning both inputs",
+                Some(100_000),
+            ),
+            ConfigDefinition::new_u64(
+                OPT_OPTIMIZER_MAX_PASSES,
+                "Maximum number of times the fixpoint batches of logical optimizer \
+                 rules are applied, stopping early when a pass does not change the plan",
+                Some(1),
+            ),
+            ConfigDefinition::new_bool(
+                OPT_SKIP_FAILED_RULES,
+                "Skip the logical optimizer rules that return an error, keeping the \
+                 plan they were given, instead of failing the query. The errors are \
+                 logged as warnings and shown by EXPLAIN VERBOSE",
+                false,
+            ),
+            ConfigDefinition::new_u64(
+                OPT_FLOAT_PRECISION,
+                "Number of digits after the decimal point of the floats in the output \
+                 of results, as tables or JSON rows. Floats are written with their \
+                 shortest exact representation when not set",
+                None,
+            ),
+            ConfigDefinition::new_u64(
+                OPT_SCIENTIFIC_NOTATION_UPPER_EXPONENT,
+                "Floats whose absolute value is at least 10 to this power are written \
+                 in scientific notation in the output of results. Never when not set",
+                None,
+            ),
+            ConfigDefinition::new_u64(
+                OPT_SCIENTIFIC_NOTATION_LOWER_EXPONENT,
+                "Floats other than zero whose absolute value is below 10 to the minus \
+                 this power are written in scientific notation in the output of \
+                 results. Never when not set",
+                None,
+            ),
+            ConfigDefinition::new_string(
+                OPT_NON_FINITE_FLOATS,
+                "Output of NaN and infinite floats in results: string, as NaN, inf \
+                 and -inf, or null",
+                Some("string".to_string()),
+            ),
+        ];
+
+        let mut options = ConfigOptions {
+            definitions: BTreeMap::new(),
+            options: BTreeMap::new(),
+        };
+        for d in definitions {
+            options.register(d);
+        }
+        options
+    }
+
+    /// Registers a new option (or replaces an existing definition), resetting
+    /// its value to the default. Keys are case insensitive.
+    pub fn register(&mut self, mut definition: ConfigDefinition) {
+        definition.key = definition.key.to_lowercase();
+        self.options
+            .insert(definition.key.clone(), definition.default_value.clone());
+        self.definitions.insert(definition.key.clone(), definition);
+    }
+
+    /// Returns the definitions of all known options, ordered by key
+    pub fn definitions(&self) -> impl Iterator<Item = &ConfigDefinition> {
+        self.definitions.values()
+    }
+
+    /// Returns the current values of all known options, ordered by key
+    pub fn options(&self) -> &BTreeMap<String, ScalarValue> {
+        &self.options
+    }
+
+    /// Sets the value of an option. Keys are case insensitive.
+    pub fn set(&mut self, key: &str, value: ScalarValue) -> Result<()> {
+        let definition = self.definition(key)?;
+        if value.get_datatype() != definition.data_type {
+            return Err(DataFusionError::Plan(format!(
+                "Configuration option '{}' expects a value of type {:?}, got {:?}",
+                definition.key,
+                definition.data_type,
+                value.get_datatype()
+            )));
+        }
+        let key = definition.key.clone();
+        self.options.insert(key, value);
+        Ok(())
+    }
+
+    /// Parses `value` according to the option type and sets it
+    pub fn set_str(&mut self, key: &str, value: &str) -> Result<()> {
+        let value = self.definition(key)?.parse_value(value)?;
+        self.set(key, value)
+    }
+
+    /// Returns the current value of an option, if it exists
+    pub fn get(&self, key: &str) -> Option<ScalarValue> {
+        self.options.get(&key.to_lowercase()).cloned()
+    }
+
+    /// Returns the value of a boolean option, `None` if it is not set
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key) {
+            Some(ScalarValue::Boolean(v)) => v,
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an unsigned integer option, `None` if it is not set
+    pub fn get_u64(&self, key: &str) -> Option<u64> {
+        match self.get(key) {
+            Some(ScalarValue::UInt64(v)) => v,
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a string option, `None` if it is not set
+    pub fn get_string(&self, key: &str) -> Option<String> {
+        match self.get(key) {
+            Some(ScalarValue::Utf8(v)) => v,
+            _ => None,
+        }
+    }
+
+    fn definition(&self, key: &str) -> Result<&ConfigDefinition> {
+        self.definitions.get(&key.to_lowercase()).ok_or_else(|| {
+            DataFusionError::Plan(format!("Unknown configuration option '{}'", key))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_get() -> Result<()> {
+        let mut options = ConfigOptions::new();
+        assert_eq!(options.get_u64(OPT_BATCH_SIZE), Some(8192));
+        assert_eq!(options.get_u64(OPT_MEMORY_LIMIT), None);
+
+        options.set_str("DataFusion.Execution.Batch_Size", "1024")?;
+        assert_eq!(options.get_u64(OPT_BATCH_SIZE), Some(1024));
+
+        options.set_str(OPT_PARQUET_PRUNING, "off")?;
+        assert_eq!(options.get_bool(OPT_PARQUET_PRUNING), Some(false));
+
+        options.set_str(OPT_TIME_ZONE, "+05:30")?;
+        assert_eq!(
+            options.get_string(OPT_TIME_ZONE),
+            Some("+05:30".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keys_follow_the_naming_pattern() {
+        // `datafusion.<area>.<name>`
+        for definition in ConfigOptions::new().definitions() {
+            let parts = definition.key.split('.').collect::<Vec<_>>();
+            assert_eq!(parts.len(), 3, "{}", definition.key);
+            assert_eq!(parts[0], "datafusion", "{}", definition.key);
+            assert!(
+                ["execution", "optimizer", "sql", "session", "format"]
+                    .contains(&parts[1]),
+                "{}",
+                definition.key
+            );
+        }
+    }
+
+    #[test]
+    fn register_mixed_case_key() -> Result<()> {
+        let mut options = ConfigOptions::new();
+        options.register(ConfigDefinition::new_u64(
+            "Extension.Scan.Fetch_Size",
+            "Rows fetched at once",
+            Some(10),
+        ));
+        assert_eq!(options.get_u64("extension.scan.fetch_size"), Some(10));
+        options.set_str("EXTENSION.SCAN.FETCH_SIZE", "20")?;
+        assert_eq!(options.get_u64("Extension.Scan.Fetch_Size"), Some(20));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_values() {
+        let mut options = ConfigOptions::new();
+        assert!(options.set_str(OPT_BATCH_SIZE, "many").is_err());
+        assert!(options.set_str("datafusion.unknown", "1").is_err());
+        assert!(options
+            .set(OPT_PARQUET_PRUNING, ScalarValue::UInt64(Some(1)))
+            .is_err());
+    }
+}
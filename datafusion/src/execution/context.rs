@@ -21,19 +21,21 @@ use crate::{
         catalog::{CatalogList, MemoryCatalogList},
         information_schema::CatalogWithInformationSchema,
     },
-    logical_plan::{PlanType, ToStringifiedPlan},
+    logical_plan::{PlanType, StringifiedPlan, ToStringifiedPlan},
     optimizer::{
-        aggregate_statistics::AggregateStatistics, eliminate_limit::EliminateLimit,
-        hash_build_probe_order::HashBuildProbeOrder,
+        aggregate_statistics::AggregateStatistics, eliminate_filter::EliminateFilter,
+        eliminate_limit::EliminateLimit, hash_build_probe_order::HashBuildProbeOrder,
     },
     physical_optimizer::optimizer::PhysicalOptimizerRule,
     physical_plan::parquet::{BasicMetadataCacheFactory, MetadataCacheFactory},
 };
-use log::debug;
+use log::{debug, warn};
 use std::fs;
 use std::path::Path;
+use std::str::FromStr;
 use std::string::String;
 use std::sync::Arc;
+use std::time::Instant;
 use std::{
     collections::{HashMap, HashSet},
     sync::Mutex,
@@ -44,6 +46,7 @@ use tokio::task::{self, JoinHandle};
 
 use arrow::csv;
 
+use crate::arrow_print::{FloatFormat, NonFiniteFloats};
 use crate::catalog::{
     catalog::{CatalogProvider, MemoryCatalogProvider},
     schema::{MemorySchemaProvider, SchemaProvider},
@@ -51,27 +54,59 @@ use crate::catalog::{
 };
 use crate::datasource::csv::CsvFile;
 use crate::datasource::parquet::ParquetTable;
-use crate::datasource::TableProvider;
+use crate::datasource::snapshot::TableSnapshot;
+use crate::datasource::{MemTable, TableProvider, TableVersion};
 use crate::error::{DataFusionError, Result};
+use crate::execution::config::{
+    parse_function_search_path, ConfigDefinition, ConfigOptions, OPT_ARITHMETIC_OVERFLOW,
+    OPT_BATCH_SIZE, OPT_COLLATION, OPT_COMPENSATED_FLOAT_SUM, OPT_CONNECTION_ID,
+    OPT_FLOAT_PRECISION, OPT_FUNCTION_SEARCH_PATH,
+    OPT_HASH_JOIN_SINGLE_PARTITION_THRESHOLD, OPT_LENIENT_CAST, OPT_MEMORY_LIMIT,
+    OPT_NON_FINITE_FLOATS, OPT_OPTIMIZER_MAX_PASSES, OPT_PARQUET_PRUNING,
+    OPT_RANDOM_SEED, OPT_REPARTITION_AGGREGATIONS, OPT_REPARTITION_JOINS,
+    OPT_REPARTITION_WINDOWS, OPT_SCIENTIFIC_NOTATION_LOWER_EXPONENT,
+    OPT_SCIENTIFIC_NOTATION_UPPER_EXPONENT, OPT_SERVER_VERSION, OPT_SESSION_USER,
+    OPT_SKIP_FAILED_RULES, OPT_TARGET_PARTITIONS, OPT_TIME_ZONE,
+};
 use crate::execution::dataframe_impl::DataFrameImpl;
 use crate::logical_plan::{
-    FunctionRegistry, LogicalPlan, LogicalPlanBuilder, UNNAMED_TABLE,
+    FunctionRegistry, LogicalPlan, LogicalPlanBuilder, ToDFSchema, UNNAMED_TABLE,
 };
+use crate::optimizer::column_pruning::ColumnPruning;
 use crate::optimizer::constant_folding::ConstantFolding;
+use crate::optimizer::eliminate_join::EliminateJoin;
+use crate::optimizer::eliminate_outer_join::EliminateOuterJoin;
 use crate::optimizer::filter_push_down::FilterPushDown;
+use crate::optimizer::infer_predicates::InferPredicates;
 use crate::optimizer::limit_push_down::LimitPushDown;
-use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::merge_aggregates::MergeAggregates;
+use crate::optimizer::merge_projections::MergeProjections;
+use crate::optimizer::nested_field_push_down::NestedFieldPushDown;
+use crate::optimizer::optimizer::{OptimizerBatch, OptimizerRule, OptimizerRuleMetrics};
 use crate::optimizer::projection_push_down::ProjectionPushDown;
 use crate::optimizer::simplify_expressions::SimplifyExpressions;
+use crate::physical_optimizer::cost::{CostModel, DefaultCostModel};
+use crate::physical_optimizer::grouped_top_k::GroupedTopK;
+use crate::physical_optimizer::join_selection::JoinSelection;
 use crate::physical_optimizer::merge_exec::AddCoalescePartitionsExec;
+use crate::physical_optimizer::partial_sort::PartialSort;
 use crate::physical_optimizer::repartition::Repartition;
 
 use crate::cube_ext::joinagg::FoldCrossJoinAggregate;
+use crate::cube_ext::temporal::Zone;
 use crate::physical_plan::csv::CsvReadOptions;
+use crate::physical_plan::encoders::{self, EncodedStream};
+use crate::physical_plan::expressions::{
+    col as physical_col, ArithmeticOverflow, PhysicalSortExpr,
+};
+use crate::physical_plan::parquet::ParquetWriteOptions;
 use crate::physical_plan::planner::DefaultPhysicalPlanner;
+use crate::physical_plan::sort::{SortExec, SortOptions};
+use crate::physical_plan::string_expressions::Collation;
 use crate::physical_plan::udf::ScalarUDF;
-use crate::physical_plan::ExecutionPlan;
 use crate::physical_plan::PhysicalPlanner;
+use crate::physical_plan::{ExecutionPlan, SendableRecordBatchStream};
+use crate::scalar::ScalarValue;
 use crate::sql::{
     parser::{DFParser, FileType},
     planner::{ContextProvider, SqlToRel},
@@ -202,10 +237,82 @@ impl ExecutionContext {
                 ))),
             },
 
-            plan => Ok(Arc::new(DataFrameImpl::new(
-                self.state.clone(),
-                &self.optimize(&plan)?,
-            ))),
+            LogicalPlan::CreateMemoryTable {
+                ref name,
+                ref schema,
+                ref column_defaults,
+                ref generated_columns,
+                if_not_exists,
+                ref input,
+            } => {
+                let table_ref: TableReference = name.as_str().into();
+                let exists = self
+                    .state
+                    .lock()
+                    .unwrap()
+                    .schema_for_ref(table_ref)?
+                    .table(table_ref.table())
+                    .is_some();
+                if exists && if_not_exists {
+                    let plan = LogicalPlanBuilder::empty(false).build()?;
+                    return Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)));
+                } else if exists {
+                    return Err(DataFusionError::Plan(format!(
+                        "Table '{}' already exists",
+                        name
+                    )));
+                }
+
+                let table = Arc::new(
+                    MemTable::try_new(
+                        Arc::new(schema.as_ref().to_owned().into()),
+                        vec![vec![]],
+                    )?
+                    .with_column_defaults(column_defaults.clone())
+                    .with_generated_columns(generated_columns.clone()),
+                );
+                // the rows of CREATE TABLE ... AS are inserted when the returned
+                // DataFrame is executed, and the table is only registered once they
+                // are
+                let plan = match input {
+                    Some(input) => LogicalPlan::Insert {
+                        table_name: name.clone(),
+                        table,
+                        input: input.clone(),
+                        create_table: true,
+                        schema: LogicalPlan::insert_schema().to_dfschema_ref()?,
+                    },
+                    None => {
+                        self.register_table(name.as_str(), table)?;
+                        LogicalPlanBuilder::empty(false).build()?
+                    }
+                };
+                Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
+            }
+
+            LogicalPlan::SetVariable {
+                ref variable,
+                ref value,
+                ..
+            } => {
+                self.state
+                    .lock()
+                    .unwrap()
+                    .config
+                    .set_config_option(variable, value)?;
+                let plan = LogicalPlanBuilder::empty(false).build()?;
+                Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
+            }
+
+            plan => {
+                // the statement is executed with the start time it was optimized with
+                let execution_props = self.state.lock().unwrap().new_execution_props();
+                let plan = self.optimize_with_props(&plan, &execution_props)?;
+                Ok(Arc::new(
+                    DataFrameImpl::new(self.state.clone(), &plan)
+                        .with_execution_props(execution_props),
+                ))
+            }
         }
     }
 
@@ -247,6 +354,12 @@ impl ExecutionContext {
     ///
     /// `SELECT MY_FUNC(x)...` will look for a function named `"my_func"`
     /// `SELECT "my_FUNC"(x)` will look for a function named `"my_FUNC"`
+    ///
+    /// Functions can be registered under a namespace by naming them
+    /// `"<namespace>.<name>"`, e.g. `SELECT pg_catalog.version()` looks for a function
+    /// named `"pg_catalog.version"`. The namespaces of the
+    /// [`ExecutionConfig::with_function_search_path`] are also searched for the
+    /// functions called without a namespace.
     pub fn register_udf(&mut self, f: ScalarUDF) {
         self.state
             .lock()
@@ -262,6 +375,8 @@ impl ExecutionContext {
     ///
     /// `SELECT MY_UDAF(x)...` will look for an aggregate named `"my_udaf"`
     /// `SELECT "my_UDAF"(x)` will look for an aggregate named `"my_UDAF"`
+    ///
+    /// Aggregates are registered under a namespace like [`Self::register_udf`].
     pub fn register_udaf(&mut self, f: AggregateUDF) {
         self.state
             .lock()
@@ -430,6 +545,33 @@ impl ExecutionContext {
         }
     }
 
+    /// Retrieves a DataFrame reading the snapshot of the table at `version`, as
+    /// returned by [`TableProvider::scan_at_version`].
+    pub fn table_at_version<'a>(
+        &self,
+        table_ref: impl Into<TableReference<'a>>,
+        version: TableVersion,
+    ) -> Result<Arc<dyn DataFrame>> {
+        let table_ref = table_ref.into();
+        let schema = self.state.lock().unwrap().schema_for_ref(table_ref)?;
+        match schema.table(table_ref.table()) {
+            Some(provider) => {
+                let snapshot = TableSnapshot::new(provider, version);
+                let plan = LogicalPlanBuilder::scan(
+                    table_ref.table(),
+                    Arc::new(snapshot),
+                    None,
+                )?
+                .build()?;
+                Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
+            }
+            _ => Err(DataFusionError::Plan(format!(
+                "No table named '{}'",
+                table_ref.table()
+            ))),
+        }
+    }
+
     /// Returns the set of available tables in the default catalog and schema.
     ///
     /// Use [`table`] to get a specific table.
@@ -453,6 +595,17 @@ impl ExecutionContext {
 
     /// Optimizes the logical plan by applying optimizer rules.
     pub fn optimize(&self, plan: &LogicalPlan) -> Result<LogicalPlan> {
+        let execution_props = self.state.lock().unwrap().new_execution_props();
+        self.optimize_with_props(plan, &execution_props)
+    }
+
+    /// Optimizes the logical plan for an execution with `execution_props`, e.g. folding
+    /// `now()` into its start time
+    pub(crate) fn optimize_with_props(
+        &self,
+        plan: &LogicalPlan,
+        execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
         if let LogicalPlan::Explain {
             verbose,
             plan,
@@ -463,11 +616,20 @@ impl ExecutionContext {
             let mut stringified_plans = stringified_plans.clone();
 
             // optimize the child plan, capturing the output of each optimizer
-            let plan = self.optimize_internal(plan, |optimized_plan, optimizer| {
-                let optimizer_name = optimizer.name().to_string();
-                let plan_type = PlanType::OptimizedLogicalPlan { optimizer_name };
-                stringified_plans.push(optimized_plan.to_stringified(plan_type));
-            })?;
+            let plan = self.optimize_internal(
+                plan,
+                execution_props,
+                |optimized_plan, metrics| {
+                    let optimizer_name = metrics.name.clone();
+                    let plan_type = PlanType::OptimizedLogicalPlan { optimizer_name };
+                    stringified_plans.push(match &metrics.error {
+                        Some(e) => {
+                            StringifiedPlan::new(plan_type, format!("SKIPPED: {}", e))
+                        }
+                        None => optimized_plan.to_stringified(plan_type),
+                    });
+                },
+            )?;
 
             Ok(LogicalPlan::Explain {
                 verbose: *verbose,
@@ -476,24 +638,63 @@ impl ExecutionContext {
                 schema: schema.clone(),
             })
         } else {
-            self.optimize_internal(plan, |_, _| {})
+            self.optimize_internal(plan, execution_props, |_, _| {})
         }
     }
 
+    /// Optimizes the logical plan like [`Self::optimize`], also returning the time
+    /// spent in each optimizer rule and the errors of the skipped rules.
+    pub fn optimize_with_metrics(
+        &self,
+        plan: &LogicalPlan,
+    ) -> Result<(LogicalPlan, Vec<OptimizerRuleMetrics>)> {
+        let mut metrics = vec![];
+        let execution_props = self.state.lock().unwrap().new_execution_props();
+        let plan = self
+            .optimize_internal(plan, &execution_props, |_, m| metrics.push(m.clone()))?;
+        Ok((plan, metrics))
+    }
+
     /// Creates a physical plan from a logical plan.
     pub fn create_physical_plan(
         &self,
         logical_plan: &LogicalPlan,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        let mut state = self.state.lock().unwrap();
-        state.execution_props.start_execution();
+        let execution_props = self.state.lock().unwrap().new_execution_props();
+        self.create_physical_plan_with_props(logical_plan, execution_props)
+    }
 
+    /// Creates a physical plan from a logical plan for an execution with
+    /// `execution_props`, e.g. binding the UDFs to its start time
+    pub(crate) fn create_physical_plan_with_props(
+        &self,
+        logical_plan: &LogicalPlan,
+        execution_props: ExecutionProps,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // the props are set on a copy, the plans of the context do not share them
+        let mut state = self.state.lock().unwrap().clone();
+        state.execution_props = execution_props;
         state
             .config
             .query_planner
             .create_physical_plan(logical_plan, &state)
     }
 
+    /// Formatting of floats in the output of results, as configured by the
+    /// `datafusion.format` options of the session
+    pub fn float_format(&self) -> Result<FloatFormat> {
+        self.state.lock().unwrap().config.float_format()
+    }
+
+    /// Encodes `stream` as newline-delimited JSON rows, with the floats formatted as
+    /// configured by the session, see [encoders::json_encode]
+    pub fn json_encode(
+        &self,
+        stream: SendableRecordBatchStream,
+    ) -> Result<EncodedStream> {
+        Ok(encoders::json_encode(stream, self.float_format()?))
+    }
+
     /// Executes a query and writes the results to a partitioned CSV file.
     pub async fn write_csv(
         &self,
@@ -539,7 +740,44 @@ impl ExecutionContext {
         path: impl AsRef<str>,
         writer_properties: Option<WriterProperties>,
     ) -> Result<()> {
-        let path = path.as_ref();
+        self.write_parquet_files(plan, path.as_ref(), writer_properties)
+            .await
+    }
+
+    /// Executes a query and writes the results to a partitioned Parquet file, with
+    /// the writer properties and the order of the rows in each file given by `options`.
+    pub async fn write_parquet_with_options(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        path: impl AsRef<str>,
+        options: ParquetWriteOptions,
+    ) -> Result<()> {
+        let plan = if options.sort_by().is_empty() {
+            plan
+        } else {
+            let schema = plan.schema();
+            let expr = options
+                .sort_by()
+                .iter()
+                .map(|name| {
+                    Ok(PhysicalSortExpr {
+                        expr: physical_col(name, &schema)?,
+                        options: SortOptions::default(),
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Arc::new(SortExec::new_with_partitioning(expr, plan, true))
+        };
+        self.write_parquet_files(plan, path.as_ref(), Some(options.writer_properties()))
+            .await
+    }
+
+    async fn write_parquet_files(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        path: &str,
+        writer_properties: Option<WriterProperties>,
+    ) -> Result<()> {
         // create directory to contain the Parquet files (one per partition)
         let fs_path = Path::new(path);
         match fs::create_dir(fs_path) {
@@ -581,22 +819,78 @@ impl ExecutionContext {
     fn optimize_internal<F>(
         &self,
         plan: &LogicalPlan,
+        execution_props: &ExecutionProps,
         mut observer: F,
     ) -> Result<LogicalPlan>
     where
-        F: FnMut(&LogicalPlan, &dyn OptimizerRule),
+        F: FnMut(&LogicalPlan, &OptimizerRuleMetrics),
     {
-        let state = &mut self.state.lock().unwrap();
-        let execution_props = &mut state.execution_props.clone();
-        let optimizers = &state.config.optimizers;
-
-        let execution_props = execution_props.start_execution();
+        // the state is not locked while the rules run, so that they can resolve
+        // functions through the context
+        let (batches, skip_failed_rules, max_passes) = {
+            let state = self.state.lock().unwrap();
+            let config = &state.config;
+            let batches = config
+                .optimizers
+                .iter()
+                .filter(|batch| {
+                    let enabled = config.optimizer_batch_enabled(batch);
+                    if !enabled {
+                        debug!("Optimizer batch '{}' is disabled", batch.name());
+                    }
+                    enabled
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            (
+                batches,
+                config.skip_failed_rules(),
+                config.optimizer_max_passes(),
+            )
+        };
+        let mut execution_props = execution_props.clone();
+        execution_props.function_registry =
+            Some(Arc::new(ExecutionContext::from(self.state.clone())));
+        let execution_props = &execution_props;
 
         let mut new_plan = plan.clone();
         debug!("Logical plan:\n {:?}", plan);
-        for optimizer in optimizers {
-            new_plan = optimizer.optimize(&new_plan, execution_props)?;
-            observer(&new_plan, optimizer.as_ref());
+        for batch in &batches {
+            let passes = if batch.fixpoint() { max_passes } else { 1 };
+            for pass in 1..=passes {
+                // only compare the plans when another pass may follow
+                let previous = (pass < passes).then(|| format!("{:?}", new_plan));
+                for optimizer in batch.rules() {
+                    let start = Instant::now();
+                    let result = optimizer.optimize(&new_plan, execution_props);
+                    let mut metrics = OptimizerRuleMetrics {
+                        name: optimizer.name().to_string(),
+                        elapsed: start.elapsed(),
+                        error: None,
+                    };
+                    match result {
+                        Ok(plan) => new_plan = plan,
+                        Err(e) if skip_failed_rules => {
+                            warn!("Skipping optimizer rule '{}': {}", metrics.name, e);
+                            metrics.error = Some(e.to_string());
+                        }
+                        Err(e) => return Err(e),
+                    }
+                    debug!(
+                        "Optimizer rule '{}' took {:?}",
+                        metrics.name, metrics.elapsed
+                    );
+                    observer(&new_plan, &metrics);
+                }
+                if previous.map_or(true, |p| p == format!("{:?}", new_plan)) {
+                    debug!(
+                        "Optimizer batch '{}' finished after {} pass(es)",
+                        batch.name(),
+                        pass
+                    );
+                    break;
+                }
+            }
         }
         debug!("Optimized logical plan:\n {:?}", new_plan);
         Ok(new_plan)
@@ -655,14 +949,16 @@ pub struct ExecutionConfig {
     pub concurrency: usize,
     /// Default batch size when reading data sources
     pub batch_size: usize,
-    /// Responsible for optimizing a logical plan
-    optimizers: Vec<Arc<dyn OptimizerRule + Send + Sync>>,
+    /// Responsible for optimizing a logical plan, applied batch by batch
+    optimizers: Vec<OptimizerBatch>,
     /// Responsible for optimizing a physical execution plan
     pub physical_optimizers: Vec<Arc<dyn PhysicalOptimizerRule + Send + Sync>>,
     /// Responsible for planning `LogicalPlan`s, and `ExecutionPlan`
     query_planner: Arc<dyn QueryPlanner + Send + Sync>,
     /// Responsible for constructing ParquetMetadataCaches.
     pub metadata_cache_factory: Arc<dyn MetadataCacheFactory>,
+    /// Decides between alternative physical plans
+    cost_model: Arc<dyn CostModel>,
     /// Default catalog name for table resolution
     default_catalog: String,
     /// Default schema name for table resolution
@@ -683,40 +979,117 @@ pub struct ExecutionConfig {
     pub repartition_windows: bool,
     /// Should Datafusion parquet reader using the predicate to prune data
     parquet_pruning: bool,
+    /// Runtime configuration options, including those without a dedicated field
+    config_options: ConfigOptions,
+    /// Registry consulted for functions that were not registered in the context
+    function_registry: Option<Arc<dyn FunctionRegistry + Send + Sync>>,
 }
 
 impl Default for ExecutionConfig {
     fn default() -> Self {
+        let mut config_options = ConfigOptions::new();
+        let optimizers = vec![
+            OptimizerBatch::new(
+                "rewrite",
+                vec![
+                    Arc::new(NestedFieldPushDown::new()),
+                    Arc::new(ProjectionPushDown::new()),
+                    Arc::new(FilterPushDown::new()),
+                    Arc::new(ConstantFolding::new()),
+                    Arc::new(EliminateLimit::new()),
+                    Arc::new(AggregateStatistics::new()),
+                    Arc::new(SimplifyExpressions::new()),
+                ],
+            )
+            .with_fixpoint(true),
+            // opt-in: a second fixpoint over the whole plan, which costs planning time
+            // for every query and only helps those filtering on join keys
+            OptimizerBatch::new(
+                "predicate_inference",
+                vec![
+                    Arc::new(InferPredicates::new()),
+                    Arc::new(FilterPushDown::new()),
+                ],
+            )
+            .with_fixpoint(true)
+            .with_enabled_by_default(false),
+            // opt-in: embedders such as CubeStore plan the joins of the logical plan
+            // themselves and rely on the join types the query was written with
+            OptimizerBatch::new(
+                "join_elimination",
+                vec![
+                    Arc::new(EliminateOuterJoin::new()),
+                    Arc::new(EliminateJoin::new()),
+                ],
+            )
+            .with_enabled_by_default(false),
+            // opt-in: embedders such as CubeStore match the aggregations of derived
+            // tables against their pre-aggregations
+            OptimizerBatch::new(
+                "merge_aggregates",
+                vec![Arc::new(MergeAggregates::new())],
+            )
+            .with_enabled_by_default(false),
+            // opt-in: only sound for tables whose min/max statistics are exact and
+            // kept up to date by inserts, which most table providers do not report
+            OptimizerBatch::new(
+                "filter_elimination",
+                vec![Arc::new(EliminateFilter::new())],
+            )
+            .with_enabled_by_default(false),
+            // opt-in: every added projection is an extra operator to execute, which
+            // only pays off when it drops wide columns early
+            OptimizerBatch::new("column_pruning", vec![Arc::new(ColumnPruning::new())])
+                .with_enabled_by_default(false),
+            // opt-in: merging drops the aliases of the inner projections, which
+            // embedders such as CubeStore match on when planning
+            OptimizerBatch::new(
+                "merge_projections",
+                vec![Arc::new(MergeProjections::new())],
+            )
+            .with_enabled_by_default(false),
+            OptimizerBatch::new(
+                "final",
+                vec![
+                    Arc::new(HashBuildProbeOrder::new()),
+                    Arc::new(LimitPushDown::new()),
+                    Arc::new(FoldCrossJoinAggregate {}), // CubeStore extension.
+                ],
+            ),
+        ];
+        for batch in &optimizers {
+            register_batch_option(&mut config_options, batch);
+        }
         Self {
-            concurrency: num_cpus::get(),
-            batch_size: 8192,
-            optimizers: vec![
-                Arc::new(ProjectionPushDown::new()),
-                Arc::new(FilterPushDown::new()),
-                Arc::new(ConstantFolding::new()),
-                Arc::new(EliminateLimit::new()),
-                Arc::new(AggregateStatistics::new()),
-                Arc::new(SimplifyExpressions::new()),
-                Arc::new(HashBuildProbeOrder::new()),
-                Arc::new(LimitPushDown::new()),
-                Arc::new(FoldCrossJoinAggregate {}), // CubeStore extension.
-            ],
+            concurrency: config_options.get_u64(OPT_TARGET_PARTITIONS).unwrap() as usize,
+            batch_size: config_options.get_u64(OPT_BATCH_SIZE).unwrap() as usize,
+            optimizers,
             physical_optimizers: vec![
                 // NOTE: disabled in the CubeStore fork.
                 // Arc::new(CoalesceBatches::new()),
+                Arc::new(JoinSelection::new()),
+                Arc::new(GroupedTopK::new()),
                 Arc::new(Repartition::new()),
                 Arc::new(AddCoalescePartitionsExec::new()),
+                Arc::new(PartialSort::new()),
             ],
             query_planner: Arc::new(DefaultQueryPlanner {}),
             metadata_cache_factory: Arc::new(BasicMetadataCacheFactory::new()),
+            cost_model: Arc::new(DefaultCostModel::new()),
             default_catalog: "datafusion".to_owned(),
             default_schema: "public".to_owned(),
             create_default_catalog_and_schema: true,
             information_schema: false,
-            repartition_joins: true,
-            repartition_aggregations: true,
-            repartition_windows: true,
-            parquet_pruning: true,
+            repartition_joins: config_options.get_bool(OPT_REPARTITION_JOINS).unwrap(),
+            repartition_aggregations: config_options
+                .get_bool(OPT_REPARTITION_AGGREGATIONS)
+                .unwrap(),
+            repartition_windows: config_options
+                .get_bool(OPT_REPARTITION_WINDOWS)
+                .unwrap(),
+            parquet_pruning: config_options.get_bool(OPT_PARQUET_PRUNING).unwrap(),
+            config_options,
+            function_registry: None,
         }
     }
 }
@@ -761,6 +1134,17 @@ impl ExecutionConfig {
         self
     }
 
+    /// Replace the default cost model of the physical planner and optimizer rules
+    pub fn with_cost_model(mut self, cost_model: Arc<dyn CostModel>) -> Self {
+        self.cost_model = cost_model;
+        self
+    }
+
+    /// The cost model of the physical planner and optimizer rules
+    pub fn cost_model(&self) -> &Arc<dyn CostModel> {
+        &self.cost_model
+    }
+
     /// Replace the physical optimizer rules
     pub fn with_physical_optimizer_rules(
         mut self,
@@ -770,15 +1154,73 @@ impl ExecutionConfig {
         self
     }
 
-    /// Adds a new [`OptimizerRule`]
+    /// Adds a new [`OptimizerRule`] at the end of the last batch of rules
     pub fn add_optimizer_rule(
         mut self,
         optimizer_rule: Arc<dyn OptimizerRule + Send + Sync>,
     ) -> Self {
-        self.optimizers.push(optimizer_rule);
+        match self.optimizers.last_mut() {
+            Some(batch) => batch.add_rule(optimizer_rule),
+            None => {
+                return self.add_optimizer_batch(OptimizerBatch::new(
+                    "user_defined",
+                    vec![optimizer_rule],
+                ))
+            }
+        }
+        self
+    }
+
+    /// Adds a new [`OptimizerBatch`], applied after the existing ones. It can be
+    /// enabled or disabled later, see [`OptimizerBatch::enabled_option`].
+    pub fn add_optimizer_batch(mut self, batch: OptimizerBatch) -> Self {
+        register_batch_option(&mut self.config_options, &batch);
+        self.optimizers.push(batch);
+        self
+    }
+
+    /// The batches of logical optimizer rules, in the order they are applied
+    pub fn optimizer_batches(&self) -> &[OptimizerBatch] {
+        &self.optimizers
+    }
+
+    /// Enables or disables a batch of logical optimizer rules by its name
+    pub fn with_optimizer_batch_enabled(mut self, name: &str, enabled: bool) -> Self {
+        let key = OptimizerBatch::new(name, vec![]).enabled_option();
+        self.config_options
+            .set(&key, ScalarValue::Boolean(Some(enabled)))
+            .unwrap();
         self
     }
 
+    /// Whether `batch` is applied by the logical optimizer
+    pub fn optimizer_batch_enabled(&self, batch: &OptimizerBatch) -> bool {
+        self.config_options
+            .get_bool(&batch.enabled_option())
+            .unwrap_or_else(|| batch.enabled_by_default())
+    }
+
+    /// Customize the maximum number of times a fixpoint batch of logical optimizer
+    /// rules is applied to a plan
+    pub fn with_optimizer_max_passes(mut self, passes: usize) -> Self {
+        self.config_options
+            .set(
+                OPT_OPTIMIZER_MAX_PASSES,
+                ScalarValue::UInt64(Some(passes as u64)),
+            )
+            .unwrap();
+        self
+    }
+
+    /// Maximum number of times a fixpoint batch of logical optimizer rules is applied
+    /// to a plan, at least once
+    pub fn optimizer_max_passes(&self) -> usize {
+        self.config_options
+            .get_u64(OPT_OPTIMIZER_MAX_PASSES)
+            .unwrap_or(1)
+            .max(1) as usize
+    }
+
     /// Adds a new [`PhysicalOptimizerRule`]
     pub fn add_physical_optimizer_rule(
         mut self,
@@ -799,6 +1241,11 @@ impl ExecutionConfig {
         self
     }
 
+    /// Name of the default schema, which is also the database of the session
+    pub fn default_schema(&self) -> &str {
+        &self.default_schema
+    }
+
     /// Controls whether the default catalog and schema will be automatically created
     pub fn create_default_catalog_and_schema(mut self, create: bool) -> Self {
         self.create_default_catalog_and_schema = create;
@@ -834,155 +1281,580 @@ impl ExecutionConfig {
         self.parquet_pruning = enabled;
         self
     }
-}
 
-/// Holds per-execution properties and data (such as starting timestamps, etc).
-/// An instance of this struct is created each time a [`LogicalPlan`] is prepared for
-/// execution (optimized). If the same plan is optimized multiple times, a new
-/// `ExecutionProps` is created each time.
-#[derive(Clone)]
-pub struct ExecutionProps {
-    pub(crate) query_execution_start_time: DateTime<Utc>,
-}
+    /// Sets a registry used to resolve functions that were not registered with
+    /// [`ExecutionContext::register_udf`] or [`ExecutionContext::register_udaf`].
+    ///
+    /// The registry is consulted by the SQL planner and is available to optimizer
+    /// rules through [`ExecutionProps::function_registry`], so functions can be
+    /// created lazily on first use instead of being registered upfront.
+    pub fn with_function_registry(
+        mut self,
+        registry: Arc<dyn FunctionRegistry + Send + Sync>,
+    ) -> Self {
+        self.function_registry = Some(registry);
+        self
+    }
 
-/// Execution context for registering data sources and executing queries
-#[derive(Clone)]
-pub struct ExecutionContextState {
-    /// Collection of catalogs containing schemas and ultimately TableProviders
-    pub catalog_list: Arc<dyn CatalogList>,
-    /// Scalar functions that are registered with the context
-    pub scalar_functions: HashMap<String, Arc<ScalarUDF>>,
-    /// Variable provider that are registered with the context
-    pub var_provider: HashMap<VarType, Arc<dyn VarProvider + Send + Sync>>,
-    /// Aggregate functions registered in the context
-    pub aggregate_functions: HashMap<String, Arc<AggregateUDF>>,
-    /// Context configuration
-    pub config: ExecutionConfig,
-    /// Execution properties
-    pub execution_props: ExecutionProps,
-}
+    /// Customize the memory limit (in bytes) for operators that track their usage
+    pub fn with_memory_limit(mut self, limit: usize) -> Self {
+        self.config_options
+            .set(OPT_MEMORY_LIMIT, ScalarValue::UInt64(Some(limit as u64)))
+            .unwrap();
+        self
+    }
 
-impl ExecutionProps {
-    /// Creates a new execution props
-    pub fn new() -> Self {
-        ExecutionProps {
-            query_execution_start_time: chrono::Utc::now(),
-        }
+    /// Seeds the generators of `random()` and `uuid()` to get reproducible results
+    pub fn with_random_seed(mut self, seed: u64) -> Self {
+        self.config_options
+            .set(OPT_RANDOM_SEED, ScalarValue::UInt64(Some(seed)))
+            .unwrap();
+        self
     }
 
-    /// Marks the execution of query started timestamp
-    pub fn start_execution(&mut self) -> &Self {
-        self.query_execution_start_time = chrono::Utc::now();
-        &*self
+    /// Makes CAST produce NULL for values that cannot be converted instead of
+    /// failing the query
+    pub fn with_lenient_cast(mut self, enabled: bool) -> Self {
+        self.config_options
+            .set(OPT_LENIENT_CAST, ScalarValue::Boolean(Some(enabled)))
+            .unwrap();
+        self
     }
-}
 
-impl ExecutionContextState {
-    /// Returns new ExecutionContextState
-    pub fn new() -> Self {
-        ExecutionContextState {
-            catalog_list: Arc::new(MemoryCatalogList::new()),
-            scalar_functions: HashMap::new(),
-            var_provider: HashMap::new(),
-            aggregate_functions: HashMap::new(),
-            config: ExecutionConfig::new(),
-            execution_props: ExecutionProps::new(),
-        }
+    /// Whether CAST produces NULL for values that cannot be converted
+    pub fn lenient_cast(&self) -> bool {
+        self.config_options
+            .get_bool(OPT_LENIENT_CAST)
+            .unwrap_or(false)
     }
 
-    fn resolve_table_ref<'a>(
-        &'a self,
-        table_ref: impl Into<TableReference<'a>>,
-    ) -> ResolvedTableReference<'a> {
-        table_ref
-            .into()
-            .resolve(&self.config.default_catalog, &self.config.default_schema)
+    /// Customize the collation of string comparisons and ORDER BY without an
+    /// explicit `COLLATE`
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.config_options
+            .set(
+                OPT_COLLATION,
+                ScalarValue::Utf8(Some(collation.name().to_string())),
+            )
+            .unwrap();
+        self
     }
 
-    fn schema_for_ref<'a>(
-        &'a self,
-        table_ref: impl Into<TableReference<'a>>,
-    ) -> Result<Arc<dyn SchemaProvider>> {
-        let resolved_ref = self.resolve_table_ref(table_ref.into());
+    /// Collation of string comparisons and ORDER BY without an explicit `COLLATE`
+    pub fn collation(&self) -> Collation {
+        self.config_options
+            .get_string(OPT_COLLATION)
+            .and_then(|name| Collation::from_str(&name).ok())
+            .unwrap_or(Collation::Binary)
+    }
 
-        self.catalog_list
-            .catalog(resolved_ref.catalog)
-            .ok_or_else(|| {
-                DataFusionError::Plan(format!(
-                    "failed to resolve catalog: {}",
-                    resolved_ref.catalog
-                ))
-            })?
-            .schema(resolved_ref.schema)
-            .ok_or_else(|| {
-                DataFusionError::Plan(format!(
-                    "failed to resolve schema: {}",
-                    resolved_ref.schema
-                ))
-            })
+    /// Customize the handling of integer and decimal arithmetic that overflows
+    pub fn with_arithmetic_overflow(mut self, overflow: ArithmeticOverflow) -> Self {
+        self.config_options
+            .set(
+                OPT_ARITHMETIC_OVERFLOW,
+                ScalarValue::Utf8(Some(overflow.name().to_string())),
+            )
+            .unwrap();
+        self
     }
 
-    /// Returns the MetadataCacheFactory
-    pub fn metadata_cache_factory(&self) -> &Arc<dyn MetadataCacheFactory> {
-        &self.config.metadata_cache_factory
+    /// Handling of integer and decimal arithmetic that overflows, unchecked unless
+    /// configured otherwise
+    pub fn arithmetic_overflow(&self) -> ArithmeticOverflow {
+        self.config_options
+            .get_string(OPT_ARITHMETIC_OVERFLOW)
+            .and_then(|name| ArithmeticOverflow::from_str(&name).ok())
+            .unwrap_or(ArithmeticOverflow::Unchecked)
     }
-}
 
-impl ContextProvider for ExecutionContextState {
-    fn get_table_provider(&self, name: TableReference) -> Option<Arc<dyn TableProvider>> {
-        let resolved_ref = self.resolve_table_ref(name);
-        let schema = self.schema_for_ref(resolved_ref).ok()?;
-        schema.table(resolved_ref.table)
+    /// Customize whether SUM and AVG of floats use a compensated summation, whose
+    /// result does not depend on the batches and partitions of the values
+    pub fn with_compensated_float_sum(mut self, enabled: bool) -> Self {
+        self.config_options
+            .set(
+                OPT_COMPENSATED_FLOAT_SUM,
+                ScalarValue::Boolean(Some(enabled)),
+            )
+            .unwrap();
+        self
     }
 
-    fn get_function_meta(&self, name: &str) -> Option<Arc<ScalarUDF>> {
-        self.scalar_functions.get(name).cloned()
+    /// Whether SUM and AVG of floats use a compensated summation
+    pub fn compensated_float_sum(&self) -> bool {
+        self.config_options
+            .get_bool(OPT_COMPENSATED_FLOAT_SUM)
+            .unwrap_or(false)
     }
 
-    fn get_aggregate_meta(&self, name: &str) -> Option<Arc<AggregateUDF>> {
-        self.aggregate_functions.get(name).cloned()
+    /// Customize the time zone of the session, in which strings without an offset
+    /// are converted to timestamps and parts of timestamps are extracted. It is a
+    /// fixed offset or a zone of the tz database.
+    pub fn with_time_zone(mut self, time_zone: impl Into<Zone>) -> Self {
+        let time_zone = time_zone.into();
+        self.config_options
+            .set(
+                OPT_TIME_ZONE,
+                ScalarValue::Utf8(Some(time_zone.to_string())),
+            )
+            .unwrap();
+        self
     }
-}
 
-impl FunctionRegistry for ExecutionContextState {
-    fn udfs(&self) -> HashSet<String> {
-        self.scalar_functions.keys().cloned().collect()
+    /// Time zone of the session, UTC unless configured otherwise
+    pub fn time_zone(&self) -> Zone {
+        self.config_options
+            .get_string(OPT_TIME_ZONE)
+            .and_then(|name| Zone::parse(&name).ok())
+            .unwrap_or_else(Zone::utc)
     }
 
-    fn udf(&self, name: &str) -> Result<Arc<ScalarUDF>> {
-        let result = self.scalar_functions.get(name);
+    /// Customize the namespaces searched, in order, for the functions called without
+    /// a namespace that are neither built-in nor registered without a namespace
+    pub fn with_function_search_path(mut self, namespaces: &[&str]) -> Self {
+        self.config_options
+            .set(
+                OPT_FUNCTION_SEARCH_PATH,
+                ScalarValue::Utf8(Some(namespaces.join(","))),
+            )
+            .unwrap();
+        self
+    }
 
-        result.cloned().ok_or_else(|| {
-            DataFusionError::Plan(format!(
-                "There is no UDF named \"{}\" in the registry",
-                name
-            ))
-        })
+    /// Namespaces searched for the functions called without a namespace
+    pub fn function_search_path(&self) -> Vec<String> {
+        self.config_options
+            .get_string(OPT_FUNCTION_SEARCH_PATH)
+            .map(|path| parse_function_search_path(&path))
+            .unwrap_or_default()
     }
 
-    fn udaf(&self, name: &str) -> Result<Arc<AggregateUDF>> {
-        let result = self.aggregate_functions.get(name);
+    /// Customize the user of the session returned by `user()`
+    pub fn with_session_user(mut self, user: impl Into<String>) -> Self {
+        self.config_options
+            .set(OPT_SESSION_USER, ScalarValue::Utf8(Some(user.into())))
+            .unwrap();
+        self
+    }
 
-        result.cloned().ok_or_else(|| {
-            DataFusionError::Plan(format!(
-                "There is no UDAF named \"{}\" in the registry",
-                name
-            ))
-        })
+    /// User of the session, if set
+    pub fn session_user(&self) -> Option<String> {
+        self.config_options.get_string(OPT_SESSION_USER)
     }
-}
 
-#[cfg(test)]
-mod tests {
+    /// Customize the identifier of the connection returned by `connection_id()`
+    pub fn with_connection_id(mut self, connection_id: u64) -> Self {
+        self.config_options
+            .set(OPT_CONNECTION_ID, ScalarValue::UInt64(Some(connection_id)))
+            .unwrap();
+        self
+    }
 
-    use super::*;
-    use crate::physical_plan::functions::make_scalar_function;
-    use crate::physical_plan::{collect, collect_partitioned};
-    use crate::test;
-    use crate::variable::VarType;
-    use crate::{
-        assert_batches_eq, assert_batches_sorted_eq,
-        logical_plan::{col, create_udf, sum, Expr},
+    /// Identifier of the connection of the session, if set
+    pub fn connection_id(&self) -> Option<u64> {
+        self.config_options.get_u64(OPT_CONNECTION_ID)
+    }
+
+    /// Customize the server version returned by `version()`
+    pub fn with_server_version(mut self, version: impl Into<String>) -> Self {
+        self.config_options
+            .set(OPT_SERVER_VERSION, ScalarValue::Utf8(Some(version.into())))
+            .unwrap();
+        self
+    }
+
+    /// Server version reported to the clients
+    pub fn server_version(&self) -> String {
+        self.config_options
+            .get_string(OPT_SERVER_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// Customize the maximum number of rows of a hash join build side that is shared
+    /// by all probe partitions instead of being repartitioned
+    pub fn with_hash_join_single_partition_threshold(mut self, rows: usize) -> Self {
+        self.config_options
+            .set(
+                OPT_HASH_JOIN_SINGLE_PARTITION_THRESHOLD,
+                ScalarValue::UInt64(Some(rows as u64)),
+            )
+            .unwrap();
+        self
+    }
+
+    /// Maximum number of rows of a hash join build side shared by all probe partitions
+    pub fn hash_join_single_partition_threshold(&self) -> usize {
+        self.config_options
+            .get_u64(OPT_HASH_JOIN_SINGLE_PARTITION_THRESHOLD)
+            .unwrap_or(0) as usize
+    }
+
+    /// Skips the logical optimizer rules that return an error instead of failing the
+    /// query. The plan given to a failing rule is passed on to the next rule.
+    pub fn with_skip_failed_rules(mut self, enabled: bool) -> Self {
+        self.config_options
+            .set(OPT_SKIP_FAILED_RULES, ScalarValue::Boolean(Some(enabled)))
+            .unwrap();
+        self
+    }
+
+    /// Whether the logical optimizer rules that return an error are skipped
+    pub fn skip_failed_rules(&self) -> bool {
+        self.config_options
+            .get_bool(OPT_SKIP_FAILED_RULES)
+            .unwrap_or(false)
+    }
+
+    /// Memory limit (in bytes) for operators that track their usage, if any
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.config_options
+            .get_u64(OPT_MEMORY_LIMIT)
+            .map(|l| l as usize)
+    }
+
+    /// Seed for the generators of `random()` and `uuid()`, if any
+    pub fn random_seed(&self) -> Option<u64> {
+        self.config_options.get_u64(OPT_RANDOM_SEED)
+    }
+
+    /// Customize the formatting of floats in the output of results
+    pub fn with_float_format(mut self, float_format: FloatFormat) -> Self {
+        let options = [
+            (
+                OPT_FLOAT_PRECISION,
+                ScalarValue::UInt64(float_format.precision().map(|p| p as u64)),
+            ),
+            (
+                OPT_SCIENTIFIC_NOTATION_UPPER_EXPONENT,
+                ScalarValue::UInt64(
+                    float_format.scientific_notation_above().map(u64::from),
+                ),
+            ),
+            (
+                OPT_SCIENTIFIC_NOTATION_LOWER_EXPONENT,
+                ScalarValue::UInt64(
+                    float_format.scientific_notation_below().map(u64::from),
+                ),
+            ),
+            (
+                OPT_NON_FINITE_FLOATS,
+                ScalarValue::Utf8(Some(float_format.non_finite().name().to_string())),
+            ),
+        ];
+        for (key, value) in options {
+            self.config_options.set(key, value).unwrap();
+        }
+        self
+    }
+
+    /// Formatting of floats in the output of results, their shortest representation
+    /// unless configured otherwise. An error when the options have invalid values.
+    pub fn float_format(&self) -> Result<FloatFormat> {
+        float_format(&self.config_options)
+    }
+
+    /// Returns a snapshot of all configuration options with their current values
+    pub fn config_options(&self) -> ConfigOptions {
+        let mut options = self.config_options.clone();
+        let fields = [
+            (
+                OPT_BATCH_SIZE,
+                ScalarValue::UInt64(Some(self.batch_size as u64)),
+            ),
+            (
+                OPT_TARGET_PARTITIONS,
+                ScalarValue::UInt64(Some(self.concurrency as u64)),
+            ),
+            (
+                OPT_PARQUET_PRUNING,
+                ScalarValue::Boolean(Some(self.parquet_pruning)),
+            ),
+            (
+                OPT_REPARTITION_JOINS,
+                ScalarValue::Boolean(Some(self.repartition_joins)),
+            ),
+            (
+                OPT_REPARTITION_AGGREGATIONS,
+                ScalarValue::Boolean(Some(self.repartition_aggregations)),
+            ),
+            (
+                OPT_REPARTITION_WINDOWS,
+                ScalarValue::Boolean(Some(self.repartition_windows)),
+            ),
+        ];
+        for (key, value) in fields {
+            options.set(key, value).unwrap();
+        }
+        options
+    }
+
+    /// Sets a configuration option from its textual representation, as done by
+    /// `SET <key> = <value>` statements
+    pub fn set_config_option(&mut self, key: &str, value: &str) -> Result<()> {
+        let mut options = self.config_options();
+        options.set_str(key, value)?;
+
+        float_format(&options)?;
+
+        let batch_size = options.get_u64(OPT_BATCH_SIZE).unwrap_or(0) as usize;
+        let concurrency = options.get_u64(OPT_TARGET_PARTITIONS).unwrap_or(0) as usize;
+        let max_passes = options.get_u64(OPT_OPTIMIZER_MAX_PASSES).unwrap_or(0);
+        if batch_size == 0 || concurrency == 0 || max_passes == 0 {
+            return Err(DataFusionError::Plan(format!(
+                "Configuration option '{}' must be greater than zero",
+                key
+            )));
+        }
+
+        self.batch_size = batch_size;
+        self.concurrency = concurrency;
+        self.parquet_pruning = options.get_bool(OPT_PARQUET_PRUNING).unwrap();
+        self.repartition_joins = options.get_bool(OPT_REPARTITION_JOINS).unwrap();
+        self.repartition_aggregations =
+            options.get_bool(OPT_REPARTITION_AGGREGATIONS).unwrap();
+        self.repartition_windows = options.get_bool(OPT_REPARTITION_WINDOWS).unwrap();
+        self.config_options = options;
+        Ok(())
+    }
+}
+
+/// The formatting of floats configured by the `datafusion.format` options of
+/// `options`
+fn float_format(options: &ConfigOptions) -> Result<FloatFormat> {
+    // bounds of the precision of `format!` and the exponent of `f64::powi`
+    let option = |key, max: u64| match options.get_u64(key) {
+        Some(value) if value > max => Err(DataFusionError::Plan(format!(
+            "Configuration option '{}' must be at most {}",
+            key, max
+        ))),
+        value => Ok(value),
+    };
+    let mut float_format = FloatFormat::new();
+    if let Some(precision) = option(OPT_FLOAT_PRECISION, u16::MAX as u64)? {
+        float_format = float_format.with_precision(precision as usize);
+    }
+    let exponent = |key| option(key, i32::MAX as u64);
+    if let Some(exponent) = exponent(OPT_SCIENTIFIC_NOTATION_UPPER_EXPONENT)? {
+        float_format = float_format.with_scientific_notation_above(exponent as u32);
+    }
+    if let Some(exponent) = exponent(OPT_SCIENTIFIC_NOTATION_LOWER_EXPONENT)? {
+        float_format = float_format.with_scientific_notation_below(exponent as u32);
+    }
+    // `SET datafusion.format.non_finite_floats = null` clears the option
+    let non_finite = match options.get_string(OPT_NON_FINITE_FLOATS) {
+        Some(name) => NonFiniteFloats::from_str(&name)?,
+        None => NonFiniteFloats::Null,
+    };
+    Ok(float_format.with_non_finite(non_finite))
+}
+
+/// Registers the option enabling `batch`, see [`OptimizerBatch::enabled_option`]
+fn register_batch_option(options: &mut ConfigOptions, batch: &OptimizerBatch) {
+    options.register(ConfigDefinition::new_bool(
+        batch.enabled_option(),
+        format!(
+            "Apply the '{}' batch of logical optimizer rules",
+            batch.name()
+        ),
+        batch.enabled_by_default(),
+    ));
+}
+
+/// Holds per-execution properties and data (such as starting timestamps, etc).
+/// An instance of this struct is created each time a [`LogicalPlan`] is prepared for
+/// execution (optimized or planned). A SQL statement keeps the instance it was
+/// optimized with for its physical planning, so that all the calls of `now()` and the
+/// other current time functions in the statement agree.
+#[derive(Clone)]
+pub struct ExecutionProps {
+    pub(crate) query_execution_start_time: DateTime<Utc>,
+    pub(crate) function_registry: Option<Arc<dyn FunctionRegistry + Send + Sync>>,
+    /// Time zone of the session. Timestamps without a time zone are UTC, so `now()`
+    /// is the same in every time zone, but strings without an offset are local times
+    /// of this time zone and parts such as the hour or the date are extracted from
+    /// local times.
+    pub(crate) time_zone: Zone,
+}
+
+/// Execution context for registering data sources and executing queries
+#[derive(Clone)]
+pub struct ExecutionContextState {
+    /// Collection of catalogs containing schemas and ultimately TableProviders
+    pub catalog_list: Arc<dyn CatalogList>,
+    /// Scalar functions that are registered with the context
+    pub scalar_functions: HashMap<String, Arc<ScalarUDF>>,
+    /// Variable provider that are registered with the context
+    pub var_provider: HashMap<VarType, Arc<dyn VarProvider + Send + Sync>>,
+    /// Aggregate functions registered in the context
+    pub aggregate_functions: HashMap<String, Arc<AggregateUDF>>,
+    /// Context configuration
+    pub config: ExecutionConfig,
+    /// Execution properties
+    pub execution_props: ExecutionProps,
+}
+
+impl ExecutionProps {
+    /// Creates a new execution props
+    pub fn new() -> Self {
+        ExecutionProps {
+            query_execution_start_time: chrono::Utc::now(),
+            function_registry: None,
+            time_zone: Zone::utc(),
+        }
+    }
+
+    /// Returns the registry used to resolve functions during optimization, if any
+    pub fn function_registry(&self) -> Option<&(dyn FunctionRegistry + Send + Sync)> {
+        self.function_registry.as_deref()
+    }
+
+    /// Returns the time zone of the session
+    pub fn time_zone(&self) -> &Zone {
+        &self.time_zone
+    }
+
+    /// Returns the start time of the current statement, which is the value of `now()`
+    pub fn query_execution_start_time(&self) -> &DateTime<Utc> {
+        &self.query_execution_start_time
+    }
+
+    /// Marks the execution of query started timestamp
+    pub fn start_execution(&mut self) -> &Self {
+        self.query_execution_start_time = chrono::Utc::now();
+        &*self
+    }
+}
+
+impl ExecutionContextState {
+    /// Returns new ExecutionContextState
+    pub fn new() -> Self {
+        ExecutionContextState {
+            catalog_list: Arc::new(MemoryCatalogList::new()),
+            scalar_functions: HashMap::new(),
+            var_provider: HashMap::new(),
+            aggregate_functions: HashMap::new(),
+            config: ExecutionConfig::new(),
+            execution_props: ExecutionProps::new(),
+        }
+    }
+
+    /// The properties of an execution starting now, in the time zone of the session
+    pub(crate) fn new_execution_props(&self) -> ExecutionProps {
+        let mut execution_props = self.execution_props.clone();
+        execution_props.time_zone = self.config.time_zone();
+        execution_props.start_execution();
+        execution_props
+    }
+
+    fn resolve_table_ref<'a>(
+        &'a self,
+        table_ref: impl Into<TableReference<'a>>,
+    ) -> ResolvedTableReference<'a> {
+        table_ref
+            .into()
+            .resolve(&self.config.default_catalog, &self.config.default_schema)
+    }
+
+    pub(crate) fn schema_for_ref<'a>(
+        &'a self,
+        table_ref: impl Into<TableReference<'a>>,
+    ) -> Result<Arc<dyn SchemaProvider>> {
+        let resolved_ref = self.resolve_table_ref(table_ref.into());
+
+        self.catalog_list
+            .catalog(resolved_ref.catalog)
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "failed to resolve catalog: {}",
+                    resolved_ref.catalog
+                ))
+            })?
+            .schema(resolved_ref.schema)
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "failed to resolve schema: {}",
+                    resolved_ref.schema
+                ))
+            })
+    }
+
+    /// Returns the MetadataCacheFactory
+    pub fn metadata_cache_factory(&self) -> &Arc<dyn MetadataCacheFactory> {
+        &self.config.metadata_cache_factory
+    }
+}
+
+impl ContextProvider for ExecutionContextState {
+    fn get_table_provider(&self, name: TableReference) -> Option<Arc<dyn TableProvider>> {
+        let resolved_ref = self.resolve_table_ref(name);
+        let schema = self.schema_for_ref(resolved_ref).ok()?;
+        schema.table(resolved_ref.table)
+    }
+
+    fn get_function_meta(&self, name: &str) -> Option<Arc<ScalarUDF>> {
+        self.udf(name).ok()
+    }
+
+    fn get_aggregate_meta(&self, name: &str) -> Option<Arc<AggregateUDF>> {
+        self.udaf(name).ok()
+    }
+
+    fn get_config_options(&self) -> Option<ConfigOptions> {
+        Some(self.config.config_options())
+    }
+}
+
+impl FunctionRegistry for ExecutionContextState {
+    fn udfs(&self) -> HashSet<String> {
+        let mut udfs: HashSet<String> = self.scalar_functions.keys().cloned().collect();
+        if let Some(registry) = &self.config.function_registry {
+            udfs.extend(registry.udfs());
+        }
+        udfs
+    }
+
+    fn udf(&self, name: &str) -> Result<Arc<ScalarUDF>> {
+        if let Some(udf) = self.scalar_functions.get(name) {
+            return Ok(udf.clone());
+        }
+        if let Some(registry) = &self.config.function_registry {
+            return registry.udf(name);
+        }
+
+        Err(DataFusionError::Plan(format!(
+            "There is no UDF named \"{}\" in the registry",
+            name
+        )))
+    }
+
+    fn udaf(&self, name: &str) -> Result<Arc<AggregateUDF>> {
+        if let Some(udaf) = self.aggregate_functions.get(name) {
+            return Ok(udaf.clone());
+        }
+        if let Some(registry) = &self.config.function_registry {
+            return registry.udaf(name);
+        }
+
+        Err(DataFusionError::Plan(format!(
+            "There is no UDAF named \"{}\" in the registry",
+            name
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::physical_plan::functions::{
+        make_scalar_function, ScalarFunctionImplementation,
+    };
+    use crate::physical_plan::{collect, collect_partitioned, ColumnarValue};
+    use crate::test;
+    use crate::variable::VarType;
+    use crate::{
+        assert_batches_eq, assert_batches_sorted_eq,
+        logical_plan::{col, create_udf, sum, Expr},
     };
     use crate::{
         datasource::{empty::EmptyTable, MemTable, TableType},
@@ -1868,7 +2740,7 @@ mod tests {
         .await
         .unwrap_err();
 
-        assert_eq!(results.to_string(), "Error during planning: Coercion from [Timestamp(Nanosecond, None)] to the signature Uniform(1, [Int8, Int16, Int32, Int64, Int96, Int64Decimal(0), Int64Decimal(1), Int64Decimal(2), Int64Decimal(3), Int64Decimal(4), Int64Decimal(5), Int64Decimal(10), Int96Decimal(0), Int96Decimal(1), Int96Decimal(2), Int96Decimal(3), Int96Decimal(4), Int96Decimal(5), Int96Decimal(10), UInt8, UInt16, UInt32, UInt64, Float32, Float64]) failed.");
+        assert_eq!(results.to_string(), "Error during planning: Coercion from [Timestamp(Nanosecond, None)] to the signature Uniform(1, [Int8, Int16, Int32, Int64, Int96, Int64Decimal(0), Int64Decimal(1), Int64Decimal(2), Int64Decimal(3), Int64Decimal(4), Int64Decimal(5), Int64Decimal(10), Int96Decimal(0), Int96Decimal(1), Int96Decimal(2), Int96Decimal(3), Int96Decimal(4), Int96Decimal(5), Int96Decimal(10), UInt8, UInt16, UInt32, UInt64, Float32, Float64, Interval(YearMonth), Interval(DayTime)]) failed.");
 
         Ok(())
     }
@@ -1963,9 +2835,18 @@ mod tests {
             "SELECT avg(nanos), avg(micros), avg(millis), avg(secs) FROM t",
         )
         .await
-        .unwrap_err();
+        .unwrap();
+
+        // the average epochs are rounded to the unit of the timestamps
+        let expected = vec![
+            "+-------------------------------+----------------------------+-------------------------+---------------------+",
+            "| AVG(nanos)                    | AVG(micros)                | AVG(millis)             | AVG(secs)           |",
+            "+-------------------------------+----------------------------+-------------------------+---------------------+",
+            "| 2017-04-10 03:11:50.188941962 | 2017-04-10 03:11:50.188942 | 2017-04-10 03:11:50.189 | 2017-04-10 03:11:50 |",
+            "+-------------------------------+----------------------------+-------------------------+---------------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &results);
 
-        assert_eq!(results.to_string(), "Error during planning: Coercion from [Timestamp(Nanosecond, None)] to the signature Uniform(1, [Int8, Int16, Int32, Int64, Int96, Int64Decimal(0), Int64Decimal(1), Int64Decimal(2), Int64Decimal(3), Int64Decimal(4), Int64Decimal(5), Int64Decimal(10), Int96Decimal(0), Int96Decimal(1), Int96Decimal(2), Int96Decimal(3), Int96Decimal(4), Int96Decimal(5), Int96Decimal(10), UInt8, UInt16, UInt32, UInt64, Float32, Float64]) failed.");
         Ok(())
     }
 
@@ -2755,43 +3636,246 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn query_csv_with_custom_partition_extension() -> Result<()> {
+    async fn write_parquet_with_options() -> Result<()> {
         let tmp_dir = TempDir::new()?;
+        let mut ctx = create_ctx(&tmp_dir, 1)?;
+        let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
 
-        // The main stipulation of this test: use a file extension that isn't .csv.
-        let file_extension = ".tst";
+        let logical_plan = ctx.create_logical_plan("SELECT 10 - c2 AS d FROM test")?;
+        let logical_plan = ctx.optimize(&logical_plan)?;
+        let physical_plan = ctx.create_physical_plan(&logical_plan)?;
+        let options = ParquetWriteOptions::new()
+            .with_max_row_group_size(4)
+            .with_column_dictionary_enabled("d", false)
+            .with_sort_by(vec!["d".to_string()]);
+        ctx.write_parquet_with_options(physical_plan, &out_dir, options)
+            .await?;
 
-        let mut ctx = ExecutionContext::new();
-        let schema = populate_csv_partitions(&tmp_dir, 2, file_extension)?;
-        ctx.register_csv(
-            "test",
-            tmp_dir.path().to_str().unwrap(),
-            CsvReadOptions::new()
-                .schema(&schema)
-                .file_extension(file_extension),
+        let path = format!("{}/part-0.parquet", out_dir);
+        let reader = parquet::file::serialized_reader::SerializedFileReader::new(
+            File::open(&path)?,
         )?;
-        let results =
-            plan_and_collect(&mut ctx, "SELECT SUM(c1), SUM(c2), COUNT(*) FROM test")
-                .await?;
+        assert_eq!(
+            parquet::file::reader::FileReader::metadata(&reader).num_row_groups(),
+            3
+        );
 
-        assert_eq!(results.len(), 1);
+        let mut ctx = ExecutionContext::new();
+        ctx.register_parquet("t", &path)?;
+        let results = plan_and_collect(&mut ctx, "SELECT d FROM t").await?;
         let expected = vec![
-            "+---------+---------+-----------------+",
-            "| SUM(c1) | SUM(c2) | COUNT(UInt8(1)) |",
-            "+---------+---------+-----------------+",
-            "| 10      | 110     | 20              |",
-            "+---------+---------+-----------------+",
+            "+----+", "| d  |", "+----+", "| 0  |", "| 1  |", "| 2  |", "| 3  |",
+            "| 4  |", "| 5  |", "| 6  |", "| 7  |", "| 8  |", "| 9  |", "| 10 |",
+            "+----+",
         ];
         assert_batches_eq!(expected, &results);
-
         Ok(())
     }
 
-    #[test]
-    fn send_context_to_threads() -> Result<()> {
-        // ensure ExecutionContexts can be used in a multi-threaded
-        // environment. Usecase is for concurrent planing.
-        let tmp_dir = TempDir::new()?;
+    #[tokio::test]
+    async fn create_table_with_defaults_and_generated_columns() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        plan_and_collect(
+            &mut ctx,
+            "CREATE TABLE t (a INT DEFAULT 10, b INT GENERATED ALWAYS AS (a + 1), c VARCHAR)",
+        )
+        .await?;
+
+        let results = plan_and_collect(&mut ctx, "INSERT INTO t (c) SELECT 'x'").await?;
+        let expected = vec![
+            "+-------+",
+            "| count |",
+            "+-------+",
+            "| 1     |",
+            "+-------+",
+        ];
+        assert_batches_eq!(expected, &results);
+        plan_and_collect(&mut ctx, "INSERT INTO t SELECT 1, 'y'").await?;
+        plan_and_collect(&mut ctx, "INSERT INTO t (a) SELECT 2").await?;
+
+        let results =
+            plan_and_collect(&mut ctx, "SELECT a, b, c FROM t ORDER BY a").await?;
+        let expected = vec![
+            "+----+----+---+",
+            "| a  | b  | c |",
+            "+----+----+---+",
+            "| 1  | 2  | y |",
+            "| 2  | 3  |   |",
+            "| 10 | 11 | x |",
+            "+----+----+---+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        let err = plan_and_collect(&mut ctx, "INSERT INTO t (b) SELECT 1")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Cannot insert into generated column b"
+        );
+
+        // the table is filled when the CREATE TABLE ... AS statement is executed
+        plan_and_collect(&mut ctx, "CREATE TABLE u AS SELECT c, b FROM t WHERE a > 1")
+            .await?;
+        let results = plan_and_collect(&mut ctx, "SELECT * FROM u ORDER BY b").await?;
+        let expected = vec![
+            "+---+----+",
+            "| c | b  |",
+            "+---+----+",
+            "|   | 3  |",
+            "| x | 11 |",
+            "+---+----+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        // the table is only registered once its rows are inserted
+        let df = ctx.sql("CREATE TABLE v AS SELECT a FROM t")?;
+        assert!(ctx.table("v").is_err());
+        df.collect().await?;
+        assert!(ctx.table("v").is_ok());
+        plan_and_collect(&mut ctx, "CREATE TABLE w AS SELECT 1 / (a - a) FROM t")
+            .await
+            .unwrap_err();
+        assert!(ctx.table("w").is_err());
+
+        plan_and_collect(&mut ctx, "CREATE TABLE IF NOT EXISTS u (x INT)").await?;
+        let err = plan_and_collect(&mut ctx, "CREATE TABLE u (x INT)")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Table 'u' already exists"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn analyze_table() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        plan_and_collect(&mut ctx, "CREATE TABLE t (a INT DEFAULT 0, b VARCHAR)").await?;
+        plan_and_collect(&mut ctx, "INSERT INTO t SELECT 3, 'x'").await?;
+        plan_and_collect(&mut ctx, "INSERT INTO t SELECT 1, 'y'").await?;
+        plan_and_collect(&mut ctx, "INSERT INTO t SELECT 3, NULL").await?;
+
+        let results = plan_and_collect(&mut ctx, "ANALYZE t").await?;
+        let expected = vec![
+            "+-------------+------------+----------------+-----------+-----------+",
+            "| column_name | null_count | distinct_count | min_value | max_value |",
+            "+-------------+------------+----------------+-----------+-----------+",
+            "| a           | 0          | 2              | 1         | 3         |",
+            "| b           | 1          | 2              | x         | y         |",
+            "+-------------+------------+----------------+-----------+-----------+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        let statistics = match ctx.table("t")?.to_logical_plan() {
+            LogicalPlan::TableScan { source, .. } => source.statistics(),
+            plan => panic!("Unexpected plan {:?}", plan),
+        };
+        assert_eq!(statistics.num_rows, Some(3));
+        let column = &statistics.column_statistics.unwrap()[0];
+        assert_eq!(column.distinct_count, Some(2));
+        assert_eq!(column.min_value, Some(ScalarValue::Int32(Some(1))));
+        assert_eq!(column.max_value, Some(ScalarValue::Int32(Some(3))));
+
+        // the analyzed table still uses the column defaults, and the row count
+        // reported by the table itself is up to date
+        plan_and_collect(&mut ctx, "INSERT INTO t (b) SELECT 'z'").await?;
+        let results = ctx.table("t")?.analyze()?.collect().await?;
+        let expected = vec![
+            "+-------------+------------+----------------+-----------+-----------+",
+            "| column_name | null_count | distinct_count | min_value | max_value |",
+            "+-------------+------------+----------------+-----------+-----------+",
+            "| a           | 0          | 3              | 0         | 3         |",
+            "| b           | 1          | 3              | x         | z         |",
+            "+-------------+------------+----------------+-----------+-----------+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        let err = ctx
+            .table("t")?
+            .select_columns(&["a"])?
+            .analyze()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Only the DataFrame of a table can be analyzed"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_after_analyze_and_insert() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        plan_and_collect(&mut ctx, "CREATE TABLE t (a INT, b VARCHAR)").await?;
+        plan_and_collect(&mut ctx, "INSERT INTO t SELECT 1, 'x'").await?;
+        plan_and_collect(&mut ctx, "INSERT INTO t SELECT 3, 'y'").await?;
+        plan_and_collect(&mut ctx, "ANALYZE t").await?;
+
+        // the analyzed values are stale once rows are inserted
+        plan_and_collect(&mut ctx, "INSERT INTO t SELECT 100, 'z'").await?;
+        let results =
+            plan_and_collect(&mut ctx, "SELECT a, b FROM t WHERE a > 50").await?;
+        let expected = vec![
+            "+-----+---+",
+            "| a   | b |",
+            "+-----+---+",
+            "| 100 | z |",
+            "+-----+---+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        let results =
+            plan_and_collect(&mut ctx, "SELECT COUNT(*), MAX(a) FROM t").await?;
+        let expected = vec![
+            "+-----------------+--------+",
+            "| COUNT(UInt8(1)) | MAX(a) |",
+            "+-----------------+--------+",
+            "| 3               | 100    |",
+            "+-----------------+--------+",
+        ];
+        assert_batches_eq!(expected, &results);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn query_csv_with_custom_partition_extension() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+
+        // The main stipulation of this test: use a file extension that isn't .csv.
+        let file_extension = ".tst";
+
+        let mut ctx = ExecutionContext::new();
+        let schema = populate_csv_partitions(&tmp_dir, 2, file_extension)?;
+        ctx.register_csv(
+            "test",
+            tmp_dir.path().to_str().unwrap(),
+            CsvReadOptions::new()
+                .schema(&schema)
+                .file_extension(file_extension),
+        )?;
+        let results =
+            plan_and_collect(&mut ctx, "SELECT SUM(c1), SUM(c2), COUNT(*) FROM test")
+                .await?;
+
+        assert_eq!(results.len(), 1);
+        let expected = vec![
+            "+---------+---------+-----------------+",
+            "| SUM(c1) | SUM(c2) | COUNT(UInt8(1)) |",
+            "+---------+---------+-----------------+",
+            "| 10      | 110     | 20              |",
+            "+---------+---------+-----------------+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        Ok(())
+    }
+
+    #[test]
+    fn send_context_to_threads() -> Result<()> {
+        // ensure ExecutionContexts can be used in a multi-threaded
+        // environment. Usecase is for concurrent planing.
+        let tmp_dir = TempDir::new()?;
         let partition_count = 4;
         let ctx = Arc::new(Mutex::new(create_ctx(&tmp_dir, partition_count)?));
 
@@ -2832,6 +3916,180 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn statement_start_time_is_shared() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        let unbound: ScalarFunctionImplementation = Arc::new(|_: &[ColumnarValue]| {
+            Err(DataFusionError::Internal("unbound".to_string()))
+        });
+        let started_at = create_udf(
+            "started_at",
+            vec![DataType::Int64],
+            Arc::new(DataType::Timestamp(TimeUnit::Nanosecond, None)),
+            unbound,
+        )
+        .with_statement_function(Arc::new(|props: &ExecutionProps| {
+            let start = crate::physical_plan::datetime_expressions::now_nanos(
+                *props.query_execution_start_time(),
+                None,
+            );
+            let bound: ScalarFunctionImplementation =
+                Arc::new(move |_: &[ColumnarValue]| {
+                    Ok(ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(
+                        Some(start),
+                    )))
+                });
+            Ok(bound)
+        }));
+        ctx.register_udf(started_at);
+
+        // now() is folded by the optimizer, the UDF is bound by the physical planner
+        let results =
+            plan_and_collect(&mut ctx, "SELECT started_at(1) = now() AS same").await?;
+        let expected = vec!["+------+", "| same |", "+------+", "| true |", "+------+"];
+        assert_batches_eq!(expected, &results);
+
+        let df = ctx.sql("SELECT started_at(1) AS t")?;
+        let first = df.collect().await?;
+        thread::sleep(std::time::Duration::from_millis(1));
+        let second = ctx.sql("SELECT started_at(1) AS t")?.collect().await?;
+        assert_ne!(
+            arrow::util::pretty::pretty_format_batches(&first)?,
+            arrow::util::pretty::pretty_format_batches(&second)?
+        );
+
+        // the start times are taken per plan, the context is left unchanged
+        let start_time = |ctx: &ExecutionContext| {
+            ctx.state
+                .lock()
+                .unwrap()
+                .execution_props
+                .query_execution_start_time
+        };
+        let before = start_time(&ctx);
+        thread::sleep(std::time::Duration::from_millis(1));
+        let plan = ctx.optimize(&ctx.create_logical_plan("SELECT now()")?)?;
+        ctx.create_physical_plan(&plan)?;
+        ctx.sql("SELECT now()")?.collect().await?;
+        assert_eq!(before, start_time(&ctx));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn skip_failed_optimizer_rules() -> Result<()> {
+        struct FailingRule {}
+        impl OptimizerRule for FailingRule {
+            fn optimize(
+                &self,
+                _plan: &LogicalPlan,
+                _execution_props: &ExecutionProps,
+            ) -> Result<LogicalPlan> {
+                Err(DataFusionError::Internal("rule is broken".to_string()))
+            }
+
+            fn name(&self) -> &str {
+                "failing_rule"
+            }
+        }
+
+        let config = ExecutionConfig::new().add_optimizer_rule(Arc::new(FailingRule {}));
+        let mut ctx = ExecutionContext::with_config(config.clone());
+        let err = plan_and_collect(&mut ctx, "SELECT 1").await.unwrap_err();
+        assert!(err.to_string().contains("rule is broken"), "{}", err);
+
+        let mut ctx = ExecutionContext::with_config(config.with_skip_failed_rules(true));
+        let results = plan_and_collect(&mut ctx, "SELECT 1 AS one").await?;
+        let expected = vec!["+-----+", "| one |", "+-----+", "| 1   |", "+-----+"];
+        assert_batches_eq!(expected, &results);
+
+        let plan = ctx.create_logical_plan("SELECT 1 AS one")?;
+        let (_, metrics) = ctx.optimize_with_metrics(&plan)?;
+        let failed = metrics.last().unwrap();
+        assert_eq!(failed.name, "failing_rule");
+        assert_eq!(failed.error.as_deref(), Some(err.to_string().as_str()));
+        assert!(metrics[..metrics.len() - 1]
+            .iter()
+            .all(|m| m.error.is_none()));
+
+        let plan = ctx.create_logical_plan("EXPLAIN VERBOSE SELECT 1 AS one")?;
+        match ctx.optimize(&plan)? {
+            LogicalPlan::Explain {
+                stringified_plans, ..
+            } => {
+                let skipped = stringified_plans.last().unwrap();
+                assert_eq!(
+                    skipped.plan_type.to_string(),
+                    "logical_plan after failing_rule"
+                );
+                assert_eq!(*skipped.plan, format!("SKIPPED: {}", err));
+            }
+            plan => panic!("plan was not an explain: {:?}", plan),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn optimizer_batches_run_to_fixpoint() -> Result<()> {
+        /// Decrements the limit at the root of the plan down to 1
+        struct DecrementLimit {}
+        impl OptimizerRule for DecrementLimit {
+            fn optimize(
+                &self,
+                plan: &LogicalPlan,
+                _execution_props: &ExecutionProps,
+            ) -> Result<LogicalPlan> {
+                Ok(match plan {
+                    LogicalPlan::Limit { n, input } if *n > 1 => LogicalPlan::Limit {
+                        n: n - 1,
+                        input: input.clone(),
+                    },
+                    _ => plan.clone(),
+                })
+            }
+
+            fn name(&self) -> &str {
+                "decrement_limit"
+            }
+        }
+
+        let plan = LogicalPlanBuilder::empty(true).limit(4)?.build()?;
+        let optimize = |max_passes: usize, enabled: bool| -> Result<(String, usize)> {
+            let config = ExecutionConfig::new()
+                .with_optimizer_batch_enabled("rewrite", false)
+                .with_optimizer_batch_enabled("final", false)
+                .add_optimizer_batch(
+                    OptimizerBatch::new("decrement", vec![Arc::new(DecrementLimit {})])
+                        .with_fixpoint(true),
+                )
+                .with_optimizer_batch_enabled("decrement", enabled)
+                .with_optimizer_max_passes(max_passes);
+            let ctx = ExecutionContext::with_config(config);
+            let (plan, metrics) = ctx.optimize_with_metrics(&plan)?;
+            Ok((
+                format!("{:?}", plan).lines().next().unwrap().to_string(),
+                metrics.len(),
+            ))
+        };
+
+        assert_eq!(optimize(1, true)?, ("Limit: 3".to_string(), 1));
+        assert_eq!(optimize(2, true)?, ("Limit: 2".to_string(), 2));
+        // stops after the pass that does not change the plan
+        assert_eq!(optimize(10, true)?, ("Limit: 1".to_string(), 4));
+        assert_eq!(optimize(10, false)?, ("Limit: 4".to_string(), 0));
+
+        let mut config = ExecutionConfig::new();
+        config.set_config_option("datafusion.optimizer.max_passes", "3")?;
+        config.set_config_option("datafusion.optimizer.enable_batch_final", "false")?;
+        assert_eq!(config.optimizer_max_passes(), 3);
+        assert!(
+            !config.optimizer_batch_enabled(config.optimizer_batches().last().unwrap())
+        );
+        assert!(config
+            .set_config_option("datafusion.optimizer.max_passes", "0")
+            .is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn scalar_udf() -> Result<()> {
         let schema = Schema::new(vec![
@@ -3410,7 +4668,358 @@ mod tests {
             .await
             .unwrap_err();
 
-        assert_eq!(err.to_string(), "This feature is not implemented: SHOW SOMETHING_UNKNOWN not implemented. Supported syntax: SHOW <TABLES>");
+        assert_eq!(err.to_string(), "This feature is not implemented: SHOW SOMETHING_UNKNOWN not implemented. Supported syntax: SHOW <TABLES | ALL | option name>");
+    }
+
+    #[tokio::test]
+    async fn custom_function_registry() -> Result<()> {
+        /// Creates `add_<n>` functions on demand
+        struct LazyRegistry {}
+
+        impl FunctionRegistry for LazyRegistry {
+            fn udfs(&self) -> HashSet<String> {
+                HashSet::new()
+            }
+
+            fn udf(&self, name: &str) -> Result<Arc<ScalarUDF>> {
+                let n = name
+                    .strip_prefix("add_")
+                    .and_then(|n| n.parse::<i64>().ok())
+                    .ok_or_else(|| {
+                        DataFusionError::Plan(format!("No function {}", name))
+                    })?;
+                let fun = make_scalar_function(move |args: &[ArrayRef]| {
+                    let array = args[0].as_any().downcast_ref::<Int64Array>().unwrap();
+                    Ok(Arc::new(
+                        array
+                            .iter()
+                            .map(|v| v.map(|v| v + n))
+                            .collect::<Int64Array>(),
+                    ) as ArrayRef)
+                });
+                Ok(Arc::new(create_udf(
+                    name,
+                    vec![DataType::Int64],
+                    Arc::new(DataType::Int64),
+                    fun,
+                )))
+            }
+
+            fn udaf(&self, name: &str) -> Result<Arc<AggregateUDF>> {
+                Err(DataFusionError::Plan(format!("No aggregate {}", name)))
+            }
+        }
+
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_function_registry(Arc::new(LazyRegistry {})),
+        );
+
+        let result =
+            plan_and_collect(&mut ctx, "SELECT add_5(CAST(1 AS BIGINT)) AS v").await?;
+        let expected = vec!["+---+", "| v |", "+---+", "| 6 |", "+---+"];
+        assert_batches_eq!(expected, &result);
+
+        let err = plan_and_collect(&mut ctx, "SELECT add_x(1)")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Invalid function 'add_x'"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn optimizer_rules_resolve_context_functions() -> Result<()> {
+        /// Fails unless `add_one` can be resolved
+        struct ResolveAddOne {}
+        impl OptimizerRule for ResolveAddOne {
+            fn optimize(
+                &self,
+                plan: &LogicalPlan,
+                execution_props: &ExecutionProps,
+            ) -> Result<LogicalPlan> {
+                execution_props
+                    .function_registry()
+                    .unwrap()
+                    .udf("add_one")?;
+                Ok(plan.clone())
+            }
+
+            fn name(&self) -> &str {
+                "resolve_add_one"
+            }
+        }
+
+        let config =
+            ExecutionConfig::new().add_optimizer_rule(Arc::new(ResolveAddOne {}));
+        let mut ctx = ExecutionContext::with_config(config);
+        let plan = LogicalPlanBuilder::empty(true).build()?;
+        assert!(ctx.optimize(&plan).is_err());
+
+        // functions registered after the context was created are found
+        let fun = make_scalar_function(|args: &[ArrayRef]| Ok(args[0].clone()));
+        ctx.register_udf(create_udf(
+            "add_one",
+            vec![DataType::Int64],
+            Arc::new(DataType::Int64),
+            fun,
+        ));
+        ctx.optimize(&plan)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn namespaced_udf() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        let add_one = make_scalar_function(|args: &[ArrayRef]| {
+            let array = args[0].as_any().downcast_ref::<Int64Array>().unwrap();
+            Ok(Arc::new(
+                array
+                    .iter()
+                    .map(|v| v.map(|v| v + 1))
+                    .collect::<Int64Array>(),
+            ) as ArrayRef)
+        });
+        ctx.register_udf(create_udf(
+            "my_ns.add_one",
+            vec![DataType::Int64],
+            Arc::new(DataType::Int64),
+            add_one,
+        ));
+
+        let expected = vec!["+---+", "| v |", "+---+", "| 2 |", "+---+"];
+        let result =
+            plan_and_collect(&mut ctx, "SELECT my_ns.add_one(CAST(1 AS BIGINT)) AS v")
+                .await?;
+        assert_batches_eq!(expected, &result);
+
+        let err = plan_and_collect(&mut ctx, "SELECT add_one(CAST(1 AS BIGINT)) AS v")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Invalid function 'add_one'"
+        );
+
+        plan_and_collect(
+            &mut ctx,
+            "SET datafusion.sql.function_search_path = 'my_ns, pg_catalog'",
+        )
+        .await?;
+        let result =
+            plan_and_collect(&mut ctx, "SELECT add_one(CAST(1 AS BIGINT)) AS v").await?;
+        assert_batches_eq!(expected, &result);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn udf_takes_precedence_over_builtin() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        let shout = make_scalar_function(|args: &[ArrayRef]| {
+            let array = args[0].as_any().downcast_ref::<StringArray>().unwrap();
+            Ok(Arc::new(
+                array
+                    .iter()
+                    .map(|v| v.map(|v| format!("{}!", v.to_uppercase())))
+                    .collect::<StringArray>(),
+            ) as ArrayRef)
+        });
+        ctx.register_udf(create_udf(
+            "upper",
+            vec![DataType::Utf8],
+            Arc::new(DataType::Utf8),
+            shout,
+        ));
+
+        let expected = vec!["+----+", "| v  |", "+----+", "| A! |", "+----+"];
+        let result = plan_and_collect(&mut ctx, "SELECT upper('a') AS v").await?;
+        assert_batches_eq!(expected, &result);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn information_functions() -> Result<()> {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .with_default_catalog_and_schema("cube", "db")
+                .with_session_user("alice@localhost")
+                .with_connection_id(7),
+        );
+        let result = plan_and_collect(
+            &mut ctx,
+            "SELECT database(), user(), connection_id(), version()",
+        )
+        .await?;
+        let expected = vec![
+            "+------------+-----------------+-----------------+-----------+",
+            "| database() | user()          | connection_id() | version() |",
+            "+------------+-----------------+-----------------+-----------+",
+            "| db         | alice@localhost | 7               | 8.0.25    |",
+            "+------------+-----------------+-----------------+-----------+",
+        ];
+        assert_batches_eq!(expected, &result);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn set_and_show_variable() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+
+        plan_and_collect(&mut ctx, "SET datafusion.execution.batch_size = 1024").await?;
+        plan_and_collect(&mut ctx, "SET datafusion.execution.parquet_pruning TO off")
+            .await?;
+        {
+            let state = ctx.state.lock().unwrap();
+            assert_eq!(state.config.batch_size, 1024);
+            assert!(!state.config.parquet_pruning);
+        }
+
+        let result =
+            plan_and_collect(&mut ctx, "SHOW datafusion.execution.batch_size").await?;
+        let expected = vec![
+            "+---------------------------------+---------+",
+            "| name                            | setting |",
+            "+---------------------------------+---------+",
+            "| datafusion.execution.batch_size | 1024    |",
+            "+---------------------------------+---------+",
+        ];
+        assert_batches_eq!(expected, &result);
+
+        let result = plan_and_collect(&mut ctx, "SHOW ALL").await?;
+        let options = ctx.state.lock().unwrap().config.config_options();
+        assert_eq!(result[0].num_rows(), options.options().len());
+
+        let err = plan_and_collect(&mut ctx, "SET datafusion.execution.batch_size = 0")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Configuration option 'datafusion.execution.batch_size' must be greater than zero"
+        );
+
+        let err = plan_and_collect(&mut ctx, "SET datafusion.execution.foo = 1")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Unknown configuration option 'datafusion.execution.foo'"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn set_time_zone() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        assert_eq!(ctx.state.lock().unwrap().config.time_zone(), Zone::utc());
+
+        plan_and_collect(&mut ctx, "SET datafusion.execution.time_zone = '-03:30'")
+            .await?;
+        assert_eq!(
+            ctx.state.lock().unwrap().config.time_zone(),
+            Zone::Fixed(chrono::FixedOffset::west(12_600))
+        );
+
+        let sql = "SELECT CAST('2021-03-01 20:00:00' AS TIMESTAMP) AS t, \
+                   date_part('hour', CAST('2021-03-01T20:00:00Z' AS TIMESTAMP)) AS h";
+        let result = plan_and_collect(&mut ctx, sql).await?;
+        let expected = vec![
+            "+---------------------+----+",
+            "| t                   | h  |",
+            "+---------------------+----+",
+            "| 2021-03-01 23:30:00 | 16 |",
+            "+---------------------+----+",
+        ];
+        assert_batches_eq!(expected, &result);
+
+        let err =
+            plan_and_collect(&mut ctx, "SET datafusion.execution.time_zone = 'Mars'")
+                .await
+                .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Unsupported time zone 'Mars', expected a fixed offset such as +05:30 or a name of the tz database such as Europe/Berlin"
+        );
+
+        // the offset of a named zone depends on the date
+        plan_and_collect(
+            &mut ctx,
+            "SET datafusion.execution.time_zone = 'America/New_York'",
+        )
+        .await?;
+        let sql = "SELECT CAST('2021-01-15 12:00:00' AS TIMESTAMP) AS winter, \
+                   CAST('2021-07-15 12:00:00' AS TIMESTAMP) AS summer, \
+                   date_part('hour', CAST('2021-07-15T20:00:00Z' AS TIMESTAMP)) AS h";
+        let result = plan_and_collect(&mut ctx, sql).await?;
+        let expected = vec![
+            "+---------------------+---------------------+----+",
+            "| winter              | summer              | h  |",
+            "+---------------------+---------------------+----+",
+            "| 2021-01-15 17:00:00 | 2021-07-15 16:00:00 | 16 |",
+            "+---------------------+---------------------+----+",
+        ];
+        assert_batches_eq!(expected, &result);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn set_float_format() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        assert_eq!(
+            ctx.state.lock().unwrap().config.float_format()?,
+            FloatFormat::new()
+        );
+
+        for sql in [
+            "SET datafusion.format.float_precision = 2",
+            "SET datafusion.format.scientific_notation_upper_exponent = 6",
+            "SET datafusion.format.non_finite_floats = 'NULL'",
+        ] {
+            plan_and_collect(&mut ctx, sql).await?;
+        }
+        let float_format = ctx.float_format()?;
+        assert_eq!(
+            float_format,
+            FloatFormat::new()
+                .with_precision(2)
+                .with_scientific_notation_above(6)
+                .with_non_finite(NonFiniteFloats::Null)
+        );
+        assert_eq!(float_format.format(1e7 / 3.0), Some("3.33e6".to_string()));
+
+        let plan = ctx.create_logical_plan("SELECT 1.0 / 3 AS f")?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?)?;
+        let chunks = ctx.json_encode(plan.execute(0).await?)?;
+        let chunks = chunks.try_collect::<Vec<_>>().await?;
+        assert_eq!(
+            String::from_utf8(chunks.concat()).unwrap(),
+            "{\"f\":0.33}\n"
+        );
+
+        let config = ExecutionConfig::new().with_float_format(float_format.clone());
+        assert_eq!(config.float_format()?, float_format);
+
+        let err = plan_and_collect(
+            &mut ctx,
+            "SET datafusion.format.non_finite_floats = 'zero'",
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Unsupported output of non-finite floats 'zero', expected string or null"
+        );
+
+        let err =
+            plan_and_collect(&mut ctx, "SET datafusion.format.float_precision = 100000")
+                .await
+                .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Configuration option 'datafusion.format.float_precision' must be at most 65535"
+        );
+        assert_eq!(ctx.float_format()?, float_format);
+        Ok(())
     }
 
     #[tokio::test]
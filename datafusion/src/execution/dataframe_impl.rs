@@ -20,15 +20,17 @@
 use std::sync::{Arc, Mutex};
 
 use crate::arrow::record_batch::RecordBatch;
-use crate::error::Result;
-use crate::execution::context::{ExecutionContext, ExecutionContextState};
+use crate::error::{DataFusionError, Result};
+use crate::execution::context::{
+    ExecutionContext, ExecutionContextState, ExecutionProps,
+};
 use crate::logical_plan::{
     col, DFSchema, Expr, FunctionRegistry, JoinType, LogicalPlan, LogicalPlanBuilder,
-    Partitioning,
+    Partitioning, ToDFSchema,
 };
 use crate::{
     dataframe::*,
-    physical_plan::{collect, collect_partitioned},
+    physical_plan::{collect, collect_partitioned, ExecutionPlan},
 };
 
 use async_trait::async_trait;
@@ -37,6 +39,8 @@ use async_trait::async_trait;
 pub struct DataFrameImpl {
     ctx_state: Arc<Mutex<ExecutionContextState>>,
     plan: LogicalPlan,
+    /// The properties the plan was optimized with, if any
+    execution_props: Option<ExecutionProps>,
 }
 
 impl DataFrameImpl {
@@ -45,8 +49,32 @@ impl DataFrameImpl {
         Self {
             ctx_state,
             plan: plan.clone(),
+            execution_props: None,
         }
     }
+
+    /// Executes the plan with the properties it was optimized with, instead of new
+    /// ones taken at every execution
+    pub(crate) fn with_execution_props(
+        mut self,
+        execution_props: ExecutionProps,
+    ) -> Self {
+        self.execution_props = Some(execution_props);
+        self
+    }
+
+    /// The physical plan of the optimized plan, with the same properties for the
+    /// optimization and the physical planning
+    fn create_physical_plan(&self) -> Result<Arc<dyn ExecutionPlan>> {
+        let state = self.ctx_state.lock().unwrap().clone();
+        let execution_props = match &self.execution_props {
+            Some(execution_props) => execution_props.clone(),
+            None => state.new_execution_props(),
+        };
+        let ctx = ExecutionContext::from(Arc::new(Mutex::new(state)));
+        let plan = ctx.optimize_with_props(&self.plan, &execution_props)?;
+        ctx.create_physical_plan_with_props(&plan, execution_props)
+    }
 }
 
 #[async_trait]
@@ -141,20 +169,14 @@ impl DataFrame for DataFrameImpl {
     // Convert the logical plan represented by this DataFrame into a physical plan and
     // execute it
     async fn collect(&self) -> Result<Vec<RecordBatch>> {
-        let state = self.ctx_state.lock().unwrap().clone();
-        let ctx = ExecutionContext::from(Arc::new(Mutex::new(state)));
-        let plan = ctx.optimize(&self.plan)?;
-        let plan = ctx.create_physical_plan(&plan)?;
+        let plan = self.create_physical_plan()?;
         Ok(collect(plan).await?)
     }
 
     // Convert the logical plan represented by this DataFrame into a physical plan and
     // execute it
     async fn collect_partitioned(&self) -> Result<Vec<Vec<RecordBatch>>> {
-        let state = self.ctx_state.lock().unwrap().clone();
-        let ctx = ExecutionContext::from(Arc::new(Mutex::new(state)));
-        let plan = ctx.optimize(&self.plan)?;
-        let plan = ctx.create_physical_plan(&plan)?;
+        let plan = self.create_physical_plan()?;
         Ok(collect_partitioned(plan).await?)
     }
 
@@ -170,6 +192,24 @@ impl DataFrame for DataFrameImpl {
         Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
     }
 
+    fn analyze(&self) -> Result<Arc<dyn DataFrame>> {
+        let plan = match &self.plan {
+            LogicalPlan::TableScan {
+                table_name, source, ..
+            } => LogicalPlan::Analyze {
+                table_name: table_name.clone(),
+                table: source.clone(),
+                schema: LogicalPlan::analyze_schema().to_dfschema_ref()?,
+            },
+            _ => {
+                return Err(DataFusionError::Plan(
+                    "Only the DataFrame of a table can be analyzed".to_string(),
+                ))
+            }
+        };
+        Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
+    }
+
     fn registry(&self) -> Arc<dyn FunctionRegistry> {
         let registry = self.ctx_state.lock().unwrap().clone();
         Arc::new(registry)
@@ -181,6 +221,16 @@ impl DataFrame for DataFrameImpl {
             .build()?;
         Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
     }
+
+    fn union_distinct(
+        &self,
+        dataframe: Arc<dyn DataFrame>,
+    ) -> Result<Arc<dyn DataFrame>> {
+        let plan = LogicalPlanBuilder::from(self.to_logical_plan())
+            .union_distinct(dataframe.to_logical_plan())?
+            .build()?;
+        Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
+    }
 }
 
 #[cfg(test)]
@@ -17,5 +17,6 @@
 
 //! DataFusion query execution
 
+pub mod config;
 pub mod context;
 pub mod dataframe_impl;
@@ -0,0 +1,487 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! C interface to execution contexts, data frames and their result streams, for
+//! the bindings of other languages such as Python.
+//!
+//! The results are exported through the
+//! [Arrow C Data Interface](https://arrow.apache.org/docs/format/CDataInterface.html)
+//! without copies: each record batch is a struct array whose fields are the columns
+//! of the batch, as in the Arrow C Stream Interface. The caller allocates the
+//! `ArrowArray` and `ArrowSchema` structs that are moved into and releases them.
+//!
+//! The functions returning an `int` return `0` on success and `-1` on failure,
+//! those returning a handle return a null pointer on failure. The message of the
+//! last failure of the calling thread is given by [`datafusion_last_error`]. Panics
+//! are failures too, they do not unwind into the caller. The functions block on the
+//! runtime of their context, they must not be called from within a Tokio runtime.
+//!
+//! ```c
+//! DataFusionContext *ctx = datafusion_context_new();
+//! datafusion_context_register_csv(ctx, "t", "data/t.csv");
+//! DataFusionDataFrame *df = datafusion_context_sql(ctx, "SELECT a FROM t");
+//! DataFusionStream *stream = datafusion_dataframe_execute(df);
+//! struct ArrowArray array;
+//! struct ArrowSchema schema;
+//! while (datafusion_stream_next(stream, &array, &schema) == 0) {
+//!     // ... consume the batch, then
+//!     array.release(&array);
+//!     schema.release(&schema);
+//! }
+//! datafusion_stream_free(stream);
+//! datafusion_dataframe_free(df);
+//! datafusion_context_free(ctx);
+//! ```
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::future::Future;
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::Arc;
+
+use arrow::array::{Array, StructArray};
+use arrow::datatypes::SchemaRef;
+use arrow::ffi::{FFI_ArrowArray, FFI_ArrowSchema};
+use arrow::record_batch::RecordBatch;
+use futures::StreamExt;
+use tokio::runtime::{Handle, Runtime};
+
+use crate::dataframe::DataFrame;
+use crate::error::{DataFusionError, Result};
+use crate::execution::context::ExecutionContext;
+use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use crate::physical_plan::{ExecutionPlan, SendableRecordBatchStream};
+use crate::prelude::CsvReadOptions;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// An execution context, with the runtime executing the queries of its data frames
+pub struct DataFusionContext {
+    ctx: ExecutionContext,
+    runtime: Arc<Runtime>,
+}
+
+/// A data frame of a context
+pub struct DataFusionDataFrame {
+    df: Arc<dyn DataFrame>,
+    ctx: ExecutionContext,
+    runtime: Arc<Runtime>,
+}
+
+/// The stream of the record batches of an executed data frame
+pub struct DataFusionStream {
+    stream: SendableRecordBatchStream,
+    schema: SchemaRef,
+    runtime: Arc<Runtime>,
+}
+
+/// Records `e` as the last error of the calling thread
+fn set_last_error(e: DataFusionError) {
+    let message = CString::new(e.to_string().replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+/// The error of a panic with the payload `payload`
+fn panic_error(payload: Box<dyn Any + Send>) -> DataFusionError {
+    let message = match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown cause".to_string(),
+        },
+    };
+    DataFusionError::Internal(format!("Panic: {}", message))
+}
+
+/// The value of `f` on success, `failure` after recording its error or panic otherwise
+fn handle_error<T>(f: impl FnOnce() -> Result<T>, failure: T) -> T {
+    let result = panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(panic_error(payload)));
+    result.unwrap_or_else(|e| {
+        set_last_error(e);
+        failure
+    })
+}
+
+/// Drops `value`, recording a panic of its drop as the last error
+fn free<T>(value: Box<T>) {
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| drop(value))) {
+        set_last_error(panic_error(payload));
+    }
+}
+
+/// Runs `future` to completion on `runtime`, an error when the calling thread is
+/// already within a Tokio runtime, on which blocking would panic
+fn block_on<F: Future>(runtime: &Runtime, future: F) -> Result<F::Output> {
+    if Handle::try_current().is_ok() {
+        return Err(DataFusionError::Execution(
+            "Cannot block on a query from within a Tokio runtime".to_string(),
+        ));
+    }
+    Ok(runtime.block_on(future))
+}
+
+/// `0` on success, `-1` after recording the error otherwise
+fn status(f: impl FnOnce() -> Result<()>) -> c_int {
+    handle_error(|| f().map(|_| 0), -1)
+}
+
+/// A handle of `value` to be freed by the caller
+fn into_handle<T>(f: impl FnOnce() -> Result<T>) -> *mut T {
+    handle_error(
+        || f().map(|value| Box::into_raw(Box::new(value))),
+        ptr::null_mut(),
+    )
+}
+
+/// The handle behind `handle`, an error when it is null
+unsafe fn handle<'a, T>(handle: *mut T, name: &str) -> Result<&'a mut T> {
+    handle
+        .as_mut()
+        .ok_or_else(|| DataFusionError::Execution(format!("Null {} handle", name)))
+}
+
+/// The UTF-8 string of `s`, an error when it is null or not valid UTF-8
+unsafe fn string<'a>(s: *const c_char, name: &str) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(DataFusionError::Execution(format!("Null {}", name)));
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| {
+        DataFusionError::Execution(format!("The {} is not valid UTF-8", name))
+    })
+}
+
+/// Moves the C Data Interface structs of `array` into `out_schema` and, unless it is
+/// null, `out_array`
+unsafe fn export(
+    array: &dyn Array,
+    out_array: *mut FFI_ArrowArray,
+    out_schema: *mut FFI_ArrowSchema,
+) -> Result<()> {
+    if out_schema.is_null() {
+        return Err(DataFusionError::Execution("Null ArrowSchema".to_string()));
+    }
+    let (array_ptr, schema_ptr) = array.to_raw()?;
+    // the exported structs are only referenced by these pointers, they are moved out
+    // of their allocations rather than released
+    let unwrap = |_| DataFusionError::Internal("Shared exported array".to_string());
+    let ffi_array = Arc::try_unwrap(Arc::from_raw(array_ptr)).map_err(unwrap)?;
+    let ffi_schema = Arc::try_unwrap(Arc::from_raw(schema_ptr)).map_err(unwrap)?;
+    if !out_array.is_null() {
+        ptr::write(out_array, ffi_array);
+    }
+    ptr::write(out_schema, ffi_schema);
+    Ok(())
+}
+
+/// Creates an execution context, to be freed with [`datafusion_context_free`]
+#[no_mangle]
+pub extern "C" fn datafusion_context_new() -> *mut DataFusionContext {
+    into_handle(|| {
+        Ok(DataFusionContext {
+            ctx: ExecutionContext::new(),
+            runtime: Arc::new(Runtime::new()?),
+        })
+    })
+}
+
+/// Frees an execution context, its data frames and streams remain valid
+///
+/// # Safety
+///
+/// `ctx` must be null or a context of [`datafusion_context_new`] not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_context_free(ctx: *mut DataFusionContext) {
+    if !ctx.is_null() {
+        free(Box::from_raw(ctx));
+    }
+}
+
+/// Registers the CSV files, with a header, of `path` as the table `name`
+///
+/// # Safety
+///
+/// `ctx` must be a live context, `name` and `path` NUL-terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_context_register_csv(
+    ctx: *mut DataFusionContext,
+    name: *const c_char,
+    path: *const c_char,
+) -> c_int {
+    status(|| {
+        let ctx = handle(ctx, "context")?;
+        let (name, path) = (string(name, "table name")?, string(path, "path")?);
+        ctx.ctx.register_csv(name, path, CsvReadOptions::new())
+    })
+}
+
+/// Registers the Parquet files of `path` as the table `name`
+///
+/// # Safety
+///
+/// `ctx` must be a live context, `name` and `path` NUL-terminated strings
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_context_register_parquet(
+    ctx: *mut DataFusionContext,
+    name: *const c_char,
+    path: *const c_char,
+) -> c_int {
+    status(|| {
+        let ctx = handle(ctx, "context")?;
+        let (name, path) = (string(name, "table name")?, string(path, "path")?);
+        ctx.ctx.register_parquet(name, path)
+    })
+}
+
+/// Plans the SQL query `sql`, the data frame is to be freed with
+/// [`datafusion_dataframe_free`]
+///
+/// # Safety
+///
+/// `ctx` must be a live context and `sql` a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_context_sql(
+    ctx: *mut DataFusionContext,
+    sql: *const c_char,
+) -> *mut DataFusionDataFrame {
+    into_handle(|| {
+        let ctx = handle(ctx, "context")?;
+        let df = ctx.ctx.sql(string(sql, "query")?)?;
+        Ok(DataFusionDataFrame {
+            df,
+            ctx: ctx.ctx.clone(),
+            runtime: ctx.runtime.clone(),
+        })
+    })
+}
+
+/// Frees a data frame
+///
+/// # Safety
+///
+/// `df` must be null or a data frame not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_dataframe_free(df: *mut DataFusionDataFrame) {
+    if !df.is_null() {
+        free(Box::from_raw(df));
+    }
+}
+
+/// Executes a data frame, the stream of its results, merged into a single one, is
+/// to be freed with [`datafusion_stream_free`]
+///
+/// # Safety
+///
+/// `df` must be a live data frame
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_dataframe_execute(
+    df: *mut DataFusionDataFrame,
+) -> *mut DataFusionStream {
+    into_handle(|| {
+        let df = handle(df, "data frame")?;
+        let plan = df.ctx.optimize(&df.df.to_logical_plan())?;
+        let mut plan = df.ctx.create_physical_plan(&plan)?;
+        if plan.output_partitioning().partition_count() != 1 {
+            plan = Arc::new(CoalescePartitionsExec::new(plan));
+        }
+        let stream = block_on(&df.runtime, plan.execute(0))??;
+        Ok(DataFusionStream {
+            stream,
+            schema: plan.schema(),
+            runtime: df.runtime.clone(),
+        })
+    })
+}
+
+/// Moves the schema of the batches of a stream, a struct type of their columns, into
+/// `out_schema`
+///
+/// # Safety
+///
+/// `stream` must be a live stream and `out_schema` point to an `ArrowSchema`
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_stream_schema(
+    stream: *mut DataFusionStream,
+    out_schema: *mut FFI_ArrowSchema,
+) -> c_int {
+    status(|| {
+        let stream = handle(stream, "stream")?;
+        let batch = RecordBatch::new_empty(stream.schema.clone());
+        export(&StructArray::from(batch), ptr::null_mut(), out_schema)
+    })
+}
+
+/// Moves the next batch of a stream, as a struct array of its columns, into
+/// `out_array` and `out_schema`. Returns `1` without moving anything at the end of
+/// the stream.
+///
+/// # Safety
+///
+/// `stream` must be a live stream, `out_array` point to an `ArrowArray` and
+/// `out_schema` to an `ArrowSchema`
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_stream_next(
+    stream: *mut DataFusionStream,
+    out_array: *mut FFI_ArrowArray,
+    out_schema: *mut FFI_ArrowSchema,
+) -> c_int {
+    handle_error(
+        || {
+            let stream = handle(stream, "stream")?;
+            if out_array.is_null() {
+                return Err(DataFusionError::Execution("Null ArrowArray".to_string()));
+            }
+            match block_on(&stream.runtime, stream.stream.next())? {
+                Some(batch) => {
+                    export(&StructArray::from(batch?), out_array, out_schema)?;
+                    Ok(0)
+                }
+                None => Ok(1),
+            }
+        },
+        -1,
+    )
+}
+
+/// Frees a stream, its batches moved out remain valid
+///
+/// # Safety
+///
+/// `stream` must be null or a stream not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn datafusion_stream_free(stream: *mut DataFusionStream) {
+    if !stream.is_null() {
+        free(Box::from_raw(stream));
+    }
+}
+
+/// The message of the last failure of a function of this interface on the calling
+/// thread, null when there is none. It remains valid until the next failure.
+#[no_mangle]
+pub extern "C" fn datafusion_last_error() -> *const c_char {
+    panic::catch_unwind(|| {
+        LAST_ERROR.with(|last_error| match &*last_error.borrow() {
+            Some(message) => message.as_ptr(),
+            None => ptr::null(),
+        })
+    })
+    .unwrap_or(ptr::null())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{make_array_from_raw, ArrayRef, Int64Array};
+    use arrow::ffi::ArrowArray;
+
+    fn c_string(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+
+    /// Imports the next batch of `stream`, none at its end
+    unsafe fn next(stream: *mut DataFusionStream) -> Result<Option<ArrayRef>> {
+        let (array, schema) = ArrowArray::into_raw(ArrowArray::empty());
+        let (array, schema) = (array as *mut _, schema as *mut _);
+        match datafusion_stream_next(stream, array, schema) {
+            0 => Ok(Some(make_array_from_raw(array, schema)?)),
+            1 => Ok(None),
+            _ => Err(DataFusionError::Execution(
+                CStr::from_ptr(datafusion_last_error())
+                    .to_string_lossy()
+                    .into_owned(),
+            )),
+        }
+    }
+
+    #[test]
+    fn execute_query() -> Result<()> {
+        unsafe {
+            let ctx = datafusion_context_new();
+            let sql = c_string("SELECT 1 + 2 AS a");
+            let df = datafusion_context_sql(ctx, sql.as_ptr());
+            assert!(!df.is_null());
+            let stream = datafusion_dataframe_execute(df);
+            assert!(!stream.is_null());
+
+            let batch = next(stream)?.unwrap();
+            let batch = batch.as_any().downcast_ref::<StructArray>().unwrap();
+            assert_eq!(batch.num_columns(), 1);
+            let a = batch.column_by_name("a").unwrap();
+            let a = a.as_any().downcast_ref::<Int64Array>().unwrap();
+            assert_eq!(a.values(), &[3]);
+            assert!(next(stream)?.is_none());
+
+            datafusion_stream_free(stream);
+            datafusion_dataframe_free(df);
+            datafusion_context_free(ctx);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn report_errors() {
+        unsafe {
+            let ctx = datafusion_context_new();
+            let sql = c_string("SELECT a FROM missing");
+            assert!(datafusion_context_sql(ctx, sql.as_ptr()).is_null());
+            let error = CStr::from_ptr(datafusion_last_error());
+            assert!(error.to_str().unwrap().contains("missing"));
+
+            let name = c_string("t");
+            let status = datafusion_context_register_csv(ctx, name.as_ptr(), ptr::null());
+            assert_eq!(status, -1);
+            let error = CStr::from_ptr(datafusion_last_error());
+            assert_eq!(error.to_str().unwrap(), "Execution error: Null path");
+            datafusion_context_free(ctx);
+        }
+    }
+
+    #[test]
+    fn report_panics() {
+        let status = handle_error(|| panic!("boom at {}", 1), -1);
+        assert_eq!(status, -1);
+        let error = unsafe { CStr::from_ptr(datafusion_last_error()) };
+        let error = error.to_str().unwrap();
+        assert!(
+            error.starts_with("Internal error: Panic: boom at 1."),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn report_execution_within_runtime() {
+        unsafe {
+            let ctx = datafusion_context_new();
+            let sql = c_string("SELECT 1");
+            let df = datafusion_context_sql(ctx, sql.as_ptr());
+            let runtime = Runtime::new().unwrap();
+            runtime.block_on(async {
+                assert!(datafusion_dataframe_execute(df).is_null());
+            });
+            let error = CStr::from_ptr(datafusion_last_error());
+            assert!(error.to_str().unwrap().contains("within a Tokio runtime"));
+            datafusion_dataframe_free(df);
+            datafusion_context_free(ctx);
+        }
+    }
+}
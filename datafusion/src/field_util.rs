@@ -0,0 +1,145 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Utility functions for complex field access
+
+use arrow::datatypes::{DataType, Field};
+
+use crate::error::{DataFusionError, Result};
+
+/// Returns the field produced by `expr[key]` for an `expr` of `data_type` and a key
+/// of `key_type`. The key is evaluated for every row, so any integer type is
+/// accepted, and the field is nullable as indexes out of bounds produce NULL.
+pub fn get_indexed_field(data_type: &DataType, key_type: &DataType) -> Result<Field> {
+    let item = list_item(data_type, &[key_type])?;
+    Ok(Field::new(item.name(), item.data_type().clone(), true))
+}
+
+/// Returns the field produced by `expr[start:stop]` for an `expr` of `data_type` and
+/// bounds of `start_type` and `stop_type`, a list of the items of `expr`.
+pub fn get_list_range_field(
+    data_type: &DataType,
+    start_type: &DataType,
+    stop_type: &DataType,
+) -> Result<Field> {
+    let item = list_item(data_type, &[start_type, stop_type])?;
+    Ok(Field::new(
+        "list",
+        DataType::List(Box::new(item.clone())),
+        true,
+    ))
+}
+
+/// Returns the field produced by `expr.name` for an `expr` of `data_type`, a struct
+/// with a field `name`. The field is nullable as it is NULL when the struct is.
+pub fn get_struct_field(data_type: &DataType, name: &str) -> Result<Field> {
+    match data_type {
+        DataType::Struct(fields) => match fields.iter().find(|f| f.name() == name) {
+            Some(field) => Ok(Field::new(name, field.data_type().clone(), true)),
+            None => Err(DataFusionError::Plan(format!(
+                "Field {} not found in struct {:?}",
+                name, data_type
+            ))),
+        },
+        data_type => Err(DataFusionError::Plan(format!(
+            "Only structs have named fields, got {:?}",
+            data_type
+        ))),
+    }
+}
+
+/// The field of the items of a list indexed by keys of `key_types`
+fn list_item<'a>(data_type: &'a DataType, key_types: &[&DataType]) -> Result<&'a Field> {
+    match data_type {
+        DataType::List(item) | DataType::FixedSizeList(item, _) => {
+            match key_types.iter().find(|t| !is_index_type(t)) {
+                Some(key_type) => Err(DataFusionError::Plan(format!(
+                    "Lists can only be indexed by integers, got {:?}",
+                    key_type
+                ))),
+                None => Ok(item),
+            }
+        }
+        data_type => Err(DataFusionError::Plan(format!(
+            "Only lists can be indexed, got {:?}",
+            data_type
+        ))),
+    }
+}
+
+fn is_index_type(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Null
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_index_types() -> Result<()> {
+        let list = DataType::List(Box::new(Field::new("item", DataType::Utf8, false)));
+        for key_type in &[DataType::Int64, DataType::UInt8, DataType::Null] {
+            assert_eq!(
+                get_indexed_field(&list, key_type)?,
+                Field::new("item", DataType::Utf8, true)
+            );
+        }
+        assert!(get_indexed_field(&list, &DataType::Utf8).is_err());
+        assert!(get_indexed_field(&DataType::Utf8, &DataType::Int64).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn list_range_types() -> Result<()> {
+        let item = Field::new("item", DataType::Int32, true);
+        let list = DataType::FixedSizeList(Box::new(item.clone()), 3);
+        assert_eq!(
+            get_list_range_field(&list, &DataType::Int64, &DataType::Int8)?,
+            Field::new("list", DataType::List(Box::new(item)), true)
+        );
+        assert!(
+            get_list_range_field(&list, &DataType::Int64, &DataType::Float64).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn struct_field_types() -> Result<()> {
+        let data_type = DataType::Struct(vec![
+            Field::new("a", DataType::Int64, false),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        assert_eq!(
+            get_struct_field(&data_type, "a")?,
+            Field::new("a", DataType::Int64, true)
+        );
+        assert!(get_struct_field(&data_type, "c").is_err());
+        assert!(get_struct_field(&DataType::Int64, "a").is_err());
+        Ok(())
+    }
+}
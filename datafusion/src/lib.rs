@@ -213,11 +213,15 @@
 
 extern crate sqlparser;
 
+pub mod arrow_print;
 pub mod catalog;
 pub mod dataframe;
 pub mod datasource;
 pub mod error;
 pub mod execution;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod field_util;
 pub mod logical_plan;
 pub mod optimizer;
 pub mod physical_optimizer;
@@ -229,6 +233,7 @@ pub mod variable;
 
 #[allow(missing_docs)]
 pub mod cube_ext;
+pub use cube_ext::temporal;
 
 // re-export dependencies from arrow-rs to minimise version maintenance for crate users
 pub use arrow;
@@ -45,6 +45,7 @@ use crate::logical_plan::{
     columnize_expr, normalize_col, normalize_cols, Column, DFField, DFSchema,
     DFSchemaRef, Partitioning,
 };
+use crate::physical_plan::expressions::{timestamp_coercion, union_coercion};
 use crate::sql::utils::find_columns;
 use arrow::datatypes::{DataType, TimeUnit};
 
@@ -295,6 +296,15 @@ impl LogicalPlanBuilder {
         Ok(Self::from(union_with_alias(self.plan.clone(), plan, None)?))
     }
 
+    /// Apply a union that removes duplicate rows
+    pub fn union_distinct(&self, plan: LogicalPlan) -> Result<Self> {
+        Ok(Self::from(union_distinct_with_alias(
+            self.plan.clone(),
+            plan,
+            None,
+        )?))
+    }
+
     /// Apply a join with on constraint
     pub fn join(
         &self,
@@ -331,18 +341,87 @@ impl LogicalPlanBuilder {
         let left_keys = left_keys.into_iter().collect::<Result<Vec<Column>>>()?;
         let right_keys = right_keys.into_iter().collect::<Result<Vec<Column>>>()?;
 
-        let on: Vec<(_, _)> = left_keys.into_iter().zip(right_keys.into_iter()).collect();
+        let mut on: Vec<(_, _)> =
+            left_keys.into_iter().zip(right_keys.into_iter()).collect();
+
+        // Timestamp keys of different units are compared in the finer unit
+        let mut left_casts = Vec::new();
+        let mut right_casts = Vec::new();
+        for (i, (l, r)) in on.iter_mut().enumerate() {
+            let left_type = self.plan.schema().field_from_column(l)?.data_type();
+            let right_type = right.schema().field_from_column(r)?.data_type();
+            if left_type == right_type {
+                continue;
+            }
+            if let Some(key_type) = timestamp_coercion(left_type, right_type) {
+                if *left_type != key_type {
+                    let name = format!("__left_join_key_{}", i);
+                    left_casts.push(
+                        Expr::Column(l.clone())
+                            .cast_to(&key_type, self.plan.schema())?
+                            .alias(&name),
+                    );
+                    *l = Column::from_name(name);
+                }
+                if *right_type != key_type {
+                    let name = format!("__right_join_key_{}", i);
+                    right_casts.push(
+                        Expr::Column(r.clone())
+                            .cast_to(&key_type, right.schema())?
+                            .alias(&name),
+                    );
+                    *r = Column::from_name(name);
+                }
+            }
+        }
+        if left_casts.is_empty() && right_casts.is_empty() {
+            let join_schema =
+                build_join_schema(self.plan.schema(), right.schema(), &join_type)?;
+
+            return Ok(Self::from(LogicalPlan::Join {
+                left: Arc::new(self.plan.clone()),
+                right: Arc::new(right.clone()),
+                on,
+                join_type,
+                join_constraint: JoinConstraint::On,
+                schema: DFSchemaRef::new(join_schema),
+            }));
+        }
+
+        // The cast keys are added to the inputs and projected away after the join
+        let with_casts = |plan: &LogicalPlan, casts: Vec<Expr>| -> Result<LogicalPlan> {
+            if casts.is_empty() {
+                return Ok(plan.clone());
+            }
+            let exprs = plan
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| Expr::Column(f.qualified_column()))
+                .chain(casts);
+            LogicalPlanBuilder::from(plan.clone())
+                .project(exprs)?
+                .build()
+        };
+        let left = with_casts(&self.plan, left_casts)?;
+        let right_with_casts = with_casts(right, right_casts)?;
         let join_schema =
-            build_join_schema(self.plan.schema(), right.schema(), &join_type)?;
+            build_join_schema(left.schema(), right_with_casts.schema(), &join_type)?;
+        let output = build_join_schema(self.plan.schema(), right.schema(), &join_type)?
+            .fields()
+            .iter()
+            .map(|f| Expr::Column(f.qualified_column()))
+            .collect::<Vec<_>>();
 
-        Ok(Self::from(LogicalPlan::Join {
-            left: Arc::new(self.plan.clone()),
-            right: Arc::new(right.clone()),
+        Self::from(LogicalPlan::Join {
+            left: Arc::new(left),
+            right: Arc::new(right_with_casts),
             on,
             join_type,
             join_constraint: JoinConstraint::On,
             schema: DFSchemaRef::new(join_schema),
-        }))
+        })
+        .project(output)
     }
 
     /// Apply a join with using constraint, which duplicates all join columns in output schema.
@@ -376,6 +455,78 @@ impl LogicalPlanBuilder {
         }))
     }
 
+    /// Keep the rows whose values of the expressions `row` are in the output of
+    /// `subquery`, i.e. filter on `(row) IN (subquery)`. This is planned as a semi
+    /// join, with the keys of both sides cast to their common type, see
+    /// [`union_coercion`].
+    pub fn filter_in_subquery(
+        &self,
+        row: Vec<Expr>,
+        subquery: &LogicalPlan,
+    ) -> Result<Self> {
+        let subquery_fields = subquery.schema().fields();
+        if row.len() != subquery_fields.len() {
+            return Err(DataFusionError::Plan(format!(
+                "IN subquery returns {} columns, but {} are compared with it",
+                subquery_fields.len(),
+                row.len()
+            )));
+        }
+
+        let left_schema = self.plan.schema();
+        let mut left_keys = Vec::with_capacity(row.len());
+        let mut left_exprs = Vec::new();
+        let mut right_exprs = Vec::with_capacity(row.len());
+        for (i, (expr, field)) in row.into_iter().zip(subquery_fields).enumerate() {
+            let expr = normalize_col(expr, &self.plan)?;
+            let data_type = expr.get_type(left_schema)?;
+            let key_type =
+                union_coercion(&data_type, field.data_type()).ok_or_else(|| {
+                    DataFusionError::Plan(format!(
+                        "IN subquery cannot compare {:?} of type {} with values of type {}",
+                        expr,
+                        data_type,
+                        field.data_type()
+                    ))
+                })?;
+
+            let mut key = Expr::Column(field.qualified_column());
+            if *field.data_type() != key_type {
+                key = key.cast_to(&key_type, subquery.schema())?;
+            }
+            right_exprs.push(key.alias(&format!("__in_subquery_{}", i)));
+
+            match expr {
+                Expr::Column(column) if data_type == key_type => left_keys.push(column),
+                expr => {
+                    let name = format!("__in_subquery_key_{}", i);
+                    left_exprs.push(expr.cast_to(&key_type, left_schema)?.alias(&name));
+                    left_keys.push(Column::from_name(name));
+                }
+            }
+        }
+        let right_keys = (0..left_keys.len())
+            .map(|i| Column::from_name(format!("__in_subquery_{}", i)))
+            .collect::<Vec<_>>();
+        let right = LogicalPlanBuilder::from(subquery.clone())
+            .project(right_exprs)?
+            .build()?;
+        if left_exprs.is_empty() {
+            return self.join(&right, JoinType::Semi, (left_keys, right_keys));
+        }
+
+        // The computed and the cast keys are added to the input and projected away
+        // after the join
+        let output = left_schema
+            .fields()
+            .iter()
+            .map(|f| Expr::Column(f.qualified_column()))
+            .collect::<Vec<_>>();
+        self.project(output.iter().cloned().chain(left_exprs))?
+            .join(&right, JoinType::Semi, (left_keys, right_keys))?
+            .project(output)
+    }
+
     /// Apply a cross join
     pub fn cross_join(&self, right: &LogicalPlan) -> Result<Self> {
         let schema = self.plan.schema().join(right.schema())?;
@@ -618,7 +769,8 @@ fn build_rolling_aggregate_schema(
 }
 
 /// Creates a schema for a join operation.
-/// The fields from the left side are first
+/// The fields from the left side are first. Fields of the side that can be
+/// missing a match in an outer join are nullable.
 pub fn build_join_schema(
     left: &DFSchema,
     right: &DFSchema,
@@ -626,10 +778,16 @@ pub fn build_join_schema(
 ) -> Result<DFSchema> {
     let fields: Vec<DFField> = match join_type {
         JoinType::Inner | JoinType::Left | JoinType::Full | JoinType::Right => {
-            let right_fields = right.fields().iter();
-            let left_fields = left.fields().iter();
+            let right_fields = right
+                .fields()
+                .iter()
+                .map(|f| nullable_if(f, join_type.is_right_nullable()));
+            let left_fields = left
+                .fields()
+                .iter()
+                .map(|f| nullable_if(f, join_type.is_left_nullable()));
             // left then right
-            left_fields.chain(right_fields).cloned().collect()
+            left_fields.chain(right_fields).collect()
         }
         JoinType::Semi | JoinType::Anti => {
             // Only use the left side for the schema
@@ -640,6 +798,20 @@ pub fn build_join_schema(
     DFSchema::new(fields)
 }
 
+/// Returns a copy of `field` that is nullable if `nullable` is set
+fn nullable_if(field: &DFField, nullable: bool) -> DFField {
+    if nullable && !field.is_nullable() {
+        DFField::new(
+            field.qualifier().map(|q| q.as_str()),
+            field.name(),
+            field.data_type().clone(),
+            true,
+        )
+    } else {
+        field.clone()
+    }
+}
+
 /// Errors if one or more expressions have equal names.
 fn validate_unique_names<'a>(
     node_name: &str,
@@ -667,7 +839,8 @@ fn validate_unique_names<'a>(
     })
 }
 
-/// Union two logical plans with an optional alias.
+/// Union two logical plans with an optional alias. The columns of the inputs are cast to
+/// a common type when their types differ, e.g. Int32 and Int64 columns to Int64.
 pub fn union_with_alias(
     left_plan: LogicalPlan,
     right_plan: LogicalPlan,
@@ -701,20 +874,130 @@ pub fn union_with_alias(
         ));
     }
 
+    let inputs = coerce_union_inputs(inputs)?;
     Ok(LogicalPlan::Union {
-        schema: union_schema,
+        schema: build_union_schema(&alias, &inputs),
         inputs,
         alias,
     })
 }
 
+/// Casts the columns of the `inputs` of a union to the common type of the columns at
+/// the same position in all the inputs, the inputs whose types differ are projected.
+fn coerce_union_inputs(inputs: Vec<LogicalPlan>) -> Result<Vec<LogicalPlan>> {
+    let mut types = inputs[0]
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.data_type().clone())
+        .collect::<Vec<_>>();
+    for input in &inputs[1..] {
+        for (data_type, field) in types.iter_mut().zip(input.schema().fields()) {
+            *data_type =
+                union_coercion(data_type, field.data_type()).ok_or_else(|| {
+                    DataFusionError::Plan(format!(
+                        "UNION can not combine the column {} of type {:?} with {:?}",
+                        field.name(),
+                        field.data_type(),
+                        data_type
+                    ))
+                })?;
+        }
+    }
+
+    inputs
+        .into_iter()
+        .map(|input| {
+            let schema = input.schema();
+            if schema
+                .fields()
+                .iter()
+                .zip(&types)
+                .all(|(f, data_type)| f.data_type() == data_type)
+            {
+                return Ok(input);
+            }
+            let expr = schema
+                .fields()
+                .iter()
+                .zip(&types)
+                .map(|(f, data_type)| {
+                    let column = Expr::Column(f.qualified_column());
+                    if f.data_type() == data_type {
+                        Ok(column)
+                    } else {
+                        Ok(column.cast_to(data_type, schema)?.alias(f.name()))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            // the cast columns keep their qualifier, as the other columns do
+            let fields = schema
+                .fields()
+                .iter()
+                .zip(&types)
+                .map(|(f, data_type)| {
+                    DFField::new(
+                        f.qualifier().map(|q| q.as_str()),
+                        f.name(),
+                        data_type.clone(),
+                        f.is_nullable(),
+                    )
+                })
+                .collect();
+            Ok(LogicalPlan::Projection {
+                expr,
+                input: Arc::new(input),
+                schema: DFSchemaRef::new(DFSchema::new(fields)?),
+            })
+        })
+        .collect()
+}
+
+/// Creates a union that removes duplicate rows. The rows are deduplicated by grouping on all
+/// the columns, which aggregates each partition of the union before combining the partial
+/// results by hash of the rows.
+pub fn union_distinct_with_alias(
+    left_plan: LogicalPlan,
+    right_plan: LogicalPlan,
+    alias: Option<String>,
+) -> Result<LogicalPlan> {
+    let union = union_with_alias(left_plan, right_plan, alias)?;
+    let group_expr = union
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| Expr::Column(f.qualified_column()))
+        .collect::<Vec<_>>();
+    LogicalPlanBuilder::from(union)
+        .aggregate(group_expr, vec![])?
+        .build()
+}
+
 /// Compute the schema for union
 pub fn build_union_schema(
     alias: &Option<String>,
     inputs: &[LogicalPlan],
 ) -> Arc<DFSchema> {
     assert!(1 <= inputs.len());
-    let union_schema = (**inputs[0].schema()).clone();
+    // a column of the union is nullable if it is nullable in any input
+    let fields = inputs[0]
+        .schema()
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let nullable = inputs.iter().any(|input| {
+                input
+                    .schema()
+                    .fields()
+                    .get(i)
+                    .map_or(false, |f| f.is_nullable())
+            });
+            nullable_if(f, nullable)
+        })
+        .collect();
+    let union_schema = DFSchema::new(fields)
+        .expect("the fields of the first input of the union are unique");
     let union_schema = Arc::new(match alias {
         Some(ref alias) => union_schema.replace_qualifier(alias.as_str()),
         None => union_schema.strip_qualifiers(),
@@ -722,45 +1005,110 @@ pub fn build_union_schema(
     union_schema
 }
 
-/// Resolves an `Expr::Wildcard` to a collection of `Expr::Column`'s.
+/// Resolves an `Expr::Wildcard` to a collection of `Expr::Column`'s. The shredded
+/// fields of a table, see [`is_shredded_field`], are left out.
 pub(crate) fn expand_wildcard(
     schema: &DFSchema,
     plan: &LogicalPlan,
 ) -> Result<Vec<Expr>> {
-    let using_columns = plan.using_columns()?;
-    let columns_to_skip = using_columns
+    // For each set of columns joined with USING, possibly over several joins, only expand
+    // to one column in projection
+    let columns_to_skip = merge_overlapping(wildcard_using_columns(plan))
         .into_iter()
-        // For each USING JOIN condition, only expand to one column in projection
-        .map(|cols| {
+        .flat_map(|cols| {
             let mut cols = cols.into_iter().collect::<Vec<_>>();
             // sort join columns to make sure we consistently keep the same
             // qualified column
             cols.sort();
             cols.into_iter().skip(1)
         })
-        .flatten()
         .collect::<HashSet<_>>();
 
-    if columns_to_skip.is_empty() {
-        Ok(schema
-            .fields()
-            .iter()
-            .map(|f| Expr::Column(f.qualified_column()))
-            .collect::<Vec<Expr>>())
-    } else {
-        Ok(schema
-            .fields()
-            .iter()
-            .filter_map(|f| {
-                let col = f.qualified_column();
-                if !columns_to_skip.contains(&col) {
-                    Some(Expr::Column(col))
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<Expr>>())
+    Ok(schema
+        .fields()
+        .iter()
+        .filter(|f| !is_shredded_field(schema, f))
+        .map(|f| f.qualified_column())
+        .filter(|col| !columns_to_skip.contains(col))
+        .map(Expr::Column)
+        .collect::<Vec<Expr>>())
+}
+
+/// Whether `field` is named by the path of a field nested in a struct column of the
+/// same relation, such as `s.a.b` for the field `b` of the field `a` of `s`. These
+/// columns, exposed by [`ParquetTable::with_shredded_fields`], only duplicate the
+/// values of the struct column.
+fn is_shredded_field(schema: &DFSchema, field: &DFField) -> bool {
+    let mut path = field.name().split('.');
+    let column = Column {
+        relation: field.qualifier().cloned(),
+        name: path.next().unwrap_or_default().to_string(),
+    };
+    let mut data_type = match schema.field_from_column(&column) {
+        Ok(parent) if parent.name() != field.name() => parent.data_type(),
+        _ => return false,
+    };
+    for name in path {
+        data_type = match data_type {
+            DataType::Struct(fields) => match fields.iter().find(|f| f.name() == name) {
+                Some(nested) => nested.data_type(),
+                None => return false,
+            },
+            _ => return false,
+        };
     }
+    true
+}
+
+/// Returns the columns of the USING joins that are part of the output of `plan`. Unlike
+/// [`LogicalPlan::using_columns`], this skips the joins below projections, aggregates,
+/// unions and extension nodes, as their columns are out of scope of a wildcard.
+fn wildcard_using_columns(plan: &LogicalPlan) -> Vec<HashSet<Column>> {
+    match plan {
+        LogicalPlan::Join {
+            join_constraint: JoinConstraint::Using,
+            on,
+            left,
+            right,
+            ..
+        } => {
+            let mut using_columns = wildcard_using_columns(left);
+            using_columns.extend(wildcard_using_columns(right));
+            using_columns.push(
+                on.iter()
+                    .flat_map(|(l, r)| [l.clone(), r.clone()])
+                    .collect::<HashSet<_>>(),
+            );
+            using_columns
+        }
+        LogicalPlan::Filter { .. }
+        | LogicalPlan::Sort { .. }
+        | LogicalPlan::Limit { .. }
+        | LogicalPlan::Skip { .. }
+        | LogicalPlan::Repartition { .. }
+        | LogicalPlan::Window { .. }
+        | LogicalPlan::Join { .. }
+        | LogicalPlan::CrossJoin { .. } => plan
+            .inputs()
+            .into_iter()
+            .flat_map(wildcard_using_columns)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Merges the sets that share a column, e.g. the columns of `t1 JOIN t2 USING (id)` with
+/// the ones of a join of the result with `t3 USING (id)`
+fn merge_overlapping(sets: Vec<HashSet<Column>>) -> Vec<HashSet<Column>> {
+    let mut merged: Vec<HashSet<Column>> = vec![];
+    for mut set in sets {
+        let (overlapping, disjoint): (Vec<_>, Vec<_>) =
+            merged.into_iter().partition(|m| !m.is_disjoint(&set));
+        overlapping.into_iter().for_each(|m| set.extend(m));
+        merged = disjoint;
+        merged.push(set);
+    }
+    merged
 }
 
 #[cfg(test)]
@@ -865,6 +1213,80 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn plan_builder_filter_in_subquery() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("id", DataType::Int64, false)]);
+        let subquery = LogicalPlanBuilder::scan_empty(Some("t2"), &schema, None)?
+            .project(vec![col("id")])?
+            .build()?;
+
+        let plan = LogicalPlanBuilder::scan_empty(Some("t1"), &employee_schema(), None)?
+            .filter_in_subquery(vec![col("id")], &subquery)?
+            .build()?;
+
+        // the Int32 keys are widened rather than the Int64 ones truncated
+        let expected = "Projection: #t1.id, #t1.first_name, #t1.last_name, #t1.state, #t1.salary\
+        \n  Join: #__in_subquery_key_0 = #__in_subquery_0\
+        \n    Projection: #t1.id, #t1.first_name, #t1.last_name, #t1.state, #t1.salary, CAST(#t1.id AS Int64) AS __in_subquery_key_0\
+        \n      TableScan: t1 projection=None\
+        \n    Projection: #t2.id AS __in_subquery_0\
+        \n      Projection: #t2.id\
+        \n        TableScan: t2 projection=None";
+        assert_eq!(expected, format!("{:?}", plan));
+        assert_eq!(plan.schema().fields().len(), 5);
+
+        let plan = LogicalPlanBuilder::scan_empty(Some("t1"), &employee_schema(), None)?
+            .filter_in_subquery(vec![col("id") + lit(1_i64)], &subquery)?
+            .build()?;
+        let expected = "Projection: #t1.id, #t1.first_name, #t1.last_name, #t1.state, #t1.salary\
+        \n  Join: #__in_subquery_key_0 = #__in_subquery_0\
+        \n    Projection: #t1.id, #t1.first_name, #t1.last_name, #t1.state, #t1.salary, #t1.id Plus Int64(1) AS __in_subquery_key_0\
+        \n      TableScan: t1 projection=None\
+        \n    Projection: #t2.id AS __in_subquery_0\
+        \n      Projection: #t2.id\
+        \n        TableScan: t2 projection=None";
+        assert_eq!(expected, format!("{:?}", plan));
+
+        let plan = LogicalPlanBuilder::scan_empty(Some("t1"), &employee_schema(), None)?
+            .filter_in_subquery(vec![col("id"), col("salary")], &subquery);
+        assert!(plan.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn plan_builder_join_timestamps_of_different_units() -> Result<()> {
+        let seconds = Schema::new(vec![
+            Field::new("ts", DataType::Timestamp(TimeUnit::Second, None), false),
+            Field::new("a", DataType::Int32, false),
+        ]);
+        let nanos = Schema::new(vec![Field::new(
+            "ts",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        )]);
+        let right = LogicalPlanBuilder::scan_empty(Some("t2"), &nanos, None)?.build()?;
+
+        let plan = LogicalPlanBuilder::scan_empty(Some("t1"), &seconds, None)?
+            .join(
+                &right,
+                JoinType::Inner,
+                (vec![Column::from_name("ts")], vec![Column::from_name("ts")]),
+            )?
+            .build()?;
+
+        let expected = "Projection: #t1.ts, #t1.a, #t2.ts\
+        \n  Join: #__left_join_key_0 = #t2.ts\
+        \n    Projection: #t1.ts, #t1.a, CAST(#t1.ts AS Timestamp(Nanosecond, None)) AS __left_join_key_0\
+        \n      TableScan: t1 projection=None\
+        \n    TableScan: t2 projection=None";
+        assert_eq!(expected, format!("{:?}", plan));
+        assert_eq!(
+            plan.schema().field(0).data_type(),
+            &DataType::Timestamp(TimeUnit::Second, None)
+        );
+        Ok(())
+    }
+
     #[test]
     fn plan_builder_union_combined_single_union() -> Result<()> {
         let plan = LogicalPlanBuilder::scan_empty(
@@ -891,6 +1313,61 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn plan_builder_union_coerces_types() -> Result<()> {
+        let plan = LogicalPlanBuilder::scan_empty(
+            Some("t1"),
+            &employee_schema(),
+            Some(vec![0, 1]),
+        )?;
+        let wide = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new(
+                "first_name",
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+                true,
+            ),
+        ]);
+        let wide = LogicalPlanBuilder::scan_empty(Some("t2"), &wide, None)?.build()?;
+
+        let plan = plan.union(wide)?.union(plan.build()?)?.build()?;
+
+        let expected = "Union\
+        \n  Projection: CAST(#t1.id AS Int64) AS id, #t1.first_name\
+        \n    TableScan: t1 projection=Some([0, 1])\
+        \n  Projection: #t2.id, CAST(#t2.first_name AS Utf8) AS first_name\
+        \n    TableScan: t2 projection=None\
+        \n  Projection: CAST(#t1.id AS Int64) AS id, #t1.first_name\
+        \n    TableScan: t1 projection=Some([0, 1])";
+        assert_eq!(expected, format!("{:?}", plan));
+        assert_eq!(plan.schema().field(0).data_type(), &DataType::Int64);
+        assert_eq!(plan.schema().field(1).data_type(), &DataType::Utf8);
+        assert!(plan.schema().field(1).is_nullable());
+        // the cast columns keep their qualifier
+        let names = plan.inputs()[0]
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.qualified_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["t1.id", "t1.first_name"]);
+
+        let strings = Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("first_name", DataType::Utf8, false),
+        ]);
+        let strings =
+            LogicalPlanBuilder::scan_empty(Some("t3"), &strings, None)?.build()?;
+        let plan = LogicalPlanBuilder::scan_empty(
+            Some("t1"),
+            &employee_schema(),
+            Some(vec![0, 1]),
+        )?
+        .union(strings);
+        assert!(plan.is_err());
+        Ok(())
+    }
+
     #[test]
     fn projection_non_unique_names() -> Result<()> {
         let plan = LogicalPlanBuilder::scan_empty(
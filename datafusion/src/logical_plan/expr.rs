@@ -20,15 +20,26 @@
 
 pub use super::Operator;
 use crate::error::{DataFusionError, Result};
+use crate::field_util;
 use crate::logical_plan::{window_frames, DFField, DFSchema, LogicalPlan};
 use crate::physical_plan::{
-    aggregates, expressions::binary_operator_data_type, functions, udf::ScalarUDF,
+    aggregates,
+    expressions::binary_operator_data_type,
+    functions,
+    higher_order_functions::{self, HigherOrderFunction},
+    string_expressions::Collation,
+    type_coercion,
+    udf::ScalarUDF,
     window_functions,
 };
 use crate::{physical_plan::udaf::AggregateUDF, scalar::ScalarValue};
-use aggregates::{AccumulatorFunctionImplementation, StateTypeFunction};
+use aggregates::{
+    AccumulatorFunctionImplementation, AggregateFunction, StateTypeFunction,
+};
 use arrow::{compute::can_cast_types, datatypes::DataType};
-use functions::{ReturnTypeFunction, ScalarFunctionImplementation, Signature};
+use functions::{
+    ReturnTypeFunction, ScalarFunctionImplementation, Signature, Volatility,
+};
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
 use sqlparser::ast::RollingOffset;
@@ -37,6 +48,7 @@ use std::convert::Infallible;
 use std::fmt;
 use std::str::FromStr;
 use std::sync::Arc;
+use window_functions::{BuiltInWindowFunction, WindowFunction};
 
 /// A named reference to a qualified field in a schema.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
@@ -329,6 +341,15 @@ pub enum Expr {
         args: Vec<Expr>,
         /// Whether this is a DISTINCT aggregation or not
         distinct: bool,
+        /// Whether NULL inputs are skipped (`IGNORE NULLS`) by functions that
+        /// would otherwise collect them, such as ARRAY_AGG
+        ignore_nulls: bool,
+        /// Order of the inputs, `Expr::Sort` expressions, for functions whose result
+        /// depends on it, such as STRING_AGG
+        order_by: Vec<Expr>,
+        /// Only the input rows for which this predicate is true are aggregated,
+        /// `FILTER (WHERE predicate)`
+        filter: Option<Box<Expr>>,
     },
     /// Represents the call of a window function with arguments.
     WindowFunction {
@@ -370,10 +391,61 @@ pub enum Expr {
         /// Whether the expression is negated
         negated: bool,
     },
+    /// Represents the call of a higher-order function, whose last argument is a
+    /// [Expr::Lambda] applied to the elements of the list of its first argument.
+    HigherOrderFunction {
+        /// The function
+        fun: HigherOrderFunction,
+        /// List of expressions to feed to the functions as arguments
+        args: Vec<Expr>,
+    },
+    /// A lambda such as `x -> x + 1`, only valid as the last argument of a
+    /// [Expr::HigherOrderFunction]. Its body refers to its parameters as columns,
+    /// and cannot refer to the columns of the input.
+    Lambda {
+        /// The names of the parameters
+        params: Vec<String>,
+        /// The body of the lambda
+        body: Box<Expr>,
+    },
+    /// An element or a slice of a list, `expr[key]` or `expr[start:stop]`, or a
+    /// field of a struct, `expr.name`. The indexes are evaluated for every row.
+    GetIndexedField {
+        /// The list or the struct
+        expr: Box<Expr>,
+        /// The elements to get
+        field: GetFieldAccess,
+    },
     /// Represents a reference to all fields in a schema.
     Wildcard,
 }
 
+/// The elements of a list or the field of a struct selected by
+/// [Expr::GetIndexedField]. Indexes are 1-based as in Postgres, and negative indexes
+/// count from the end of the list, -1 being its last element.
+#[derive(Clone, PartialEq)]
+pub enum GetFieldAccess {
+    /// The element at an index, `expr[key]`, NULL when it is out of bounds
+    ListIndex {
+        /// The index of the element
+        key: Box<Expr>,
+    },
+    /// The list of the elements between two indexes, both included,
+    /// `expr[start:stop]`. The bounds are clamped to the list, so the slice is empty
+    /// rather than NULL when they are out of bounds.
+    ListRange {
+        /// The index of the first element
+        start: Box<Expr>,
+        /// The index of the last element
+        stop: Box<Expr>,
+    },
+    /// The field of a struct, `expr.name`, NULL when the struct is
+    NamedStructField {
+        /// The name of the field
+        name: String,
+    },
+}
+
 impl Expr {
     /// Returns the [arrow::datatypes::DataType] of the expression based on [arrow::datatypes::Schema].
     ///
@@ -388,7 +460,22 @@ impl Expr {
             Expr::Column(c) => Ok(schema.field_from_column(c)?.data_type().clone()),
             Expr::ScalarVariable(_) => Ok(DataType::Utf8),
             Expr::Literal(l) => Ok(l.get_datatype()),
-            Expr::Case { when_then_expr, .. } => when_then_expr[0].1.get_type(schema),
+            Expr::Case {
+                when_then_expr,
+                else_expr,
+                ..
+            } => {
+                // the type of the first result that is not an untyped NULL
+                let mut data_type = DataType::Null;
+                let results = when_then_expr.iter().map(|(_, t)| t).chain(else_expr);
+                for result in results {
+                    data_type = result.get_type(schema)?;
+                    if data_type != DataType::Null {
+                        break;
+                    }
+                }
+                Ok(data_type)
+            }
             Expr::Cast { data_type, .. } => Ok(data_type.clone()),
             Expr::TryCast { data_type, .. } => Ok(data_type.clone()),
             Expr::ScalarUDF { fun, args } => {
@@ -396,6 +483,7 @@ impl Expr {
                     .iter()
                     .map(|e| e.get_type(schema))
                     .collect::<Result<Vec<_>>>()?;
+                let data_types = type_coercion::data_types(&data_types, &fun.signature)?;
                 Ok((fun.return_type)(&data_types)?.as_ref().clone())
             }
             Expr::ScalarFunction { fun, args } => {
@@ -403,7 +491,14 @@ impl Expr {
                     .iter()
                     .map(|e| e.get_type(schema))
                     .collect::<Result<Vec<_>>>()?;
-                functions::return_type(fun, &data_types)
+                let literals = args
+                    .iter()
+                    .map(|e| match e {
+                        Expr::Literal(value) => Some(value),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                functions::return_type_of_args(fun, &data_types, &literals)
             }
             Expr::WindowFunction { fun, args, .. } => {
                 let data_types = args
@@ -412,11 +507,22 @@ impl Expr {
                     .collect::<Result<Vec<_>>>()?;
                 window_functions::return_type(fun, &data_types)
             }
-            Expr::AggregateFunction { fun, args, .. } => {
-                let data_types = args
+            Expr::AggregateFunction {
+                fun,
+                args,
+                order_by,
+                ..
+            } => {
+                let mut data_types = args
                     .iter()
                     .map(|e| e.get_type(schema))
                     .collect::<Result<Vec<_>>>()?;
+                if aggregates::is_ordered_set(fun) {
+                    // the values of WITHIN GROUP (ORDER BY ...) are aggregated
+                    for e in order_by {
+                        data_types.push(e.get_type(schema)?);
+                    }
+                }
                 aggregates::return_type(fun, &data_types)
             }
             Expr::AggregateUDF { fun, args, .. } => {
@@ -424,6 +530,7 @@ impl Expr {
                     .iter()
                     .map(|e| e.get_type(schema))
                     .collect::<Result<Vec<_>>>()?;
+                let data_types = type_coercion::data_types(&data_types, &fun.signature)?;
                 Ok((fun.return_type)(&data_types)?.as_ref().clone())
             }
             Expr::Not(_) => Ok(DataType::Boolean),
@@ -443,6 +550,29 @@ impl Expr {
             Expr::Between { .. } => Ok(DataType::Boolean),
             Expr::InList { .. } => Ok(DataType::Boolean),
             Expr::RollingAggregate { agg, .. } => agg.get_type(schema),
+            Expr::HigherOrderFunction { fun, args } => {
+                higher_order_functions::return_type(fun, args, schema)
+            }
+            Expr::Lambda { .. } => Err(lambda_outside_function()),
+            Expr::GetIndexedField { expr, field } => {
+                let data_type = expr.get_type(schema)?;
+                let field = match field {
+                    GetFieldAccess::ListIndex { key } => {
+                        field_util::get_indexed_field(&data_type, &key.get_type(schema)?)?
+                    }
+                    GetFieldAccess::ListRange { start, stop } => {
+                        field_util::get_list_range_field(
+                            &data_type,
+                            &start.get_type(schema)?,
+                            &stop.get_type(schema)?,
+                        )?
+                    }
+                    GetFieldAccess::NamedStructField { name } => {
+                        field_util::get_struct_field(&data_type, name)?
+                    }
+                };
+                Ok(field.data_type().clone())
+            }
             Expr::Wildcard => Err(DataFusionError::Internal(
                 "Wildcard expressions are not valid in a logical query plan".to_owned(),
             )),
@@ -462,28 +592,53 @@ impl Expr {
             Expr::Literal(value) => Ok(value.is_null()),
             Expr::ScalarVariable(_) => Ok(true),
             Expr::Case {
+                expr,
                 when_then_expr,
                 else_expr,
-                ..
             } => {
-                // this expression is nullable if any of the input expressions are nullable
-                let then_nullable = when_then_expr
-                    .iter()
-                    .map(|(_, t)| t.nullable(input_schema))
-                    .collect::<Result<Vec<_>>>()?;
-                if then_nullable.contains(&true) {
-                    Ok(true)
-                } else if let Some(e) = else_expr {
-                    e.nullable(input_schema)
-                } else {
-                    Ok(false)
+                // this expression is nullable if any of the results is nullable, except
+                // for the results that are only taken when they are not null, as in the
+                // expansion of IFNULL(a, b): `CASE WHEN a IS NOT NULL THEN a ELSE b END`
+                for (when, then) in when_then_expr {
+                    let guarded = expr.is_none()
+                        && matches!(when.as_ref(), Expr::IsNotNull(e) if e == then);
+                    if !guarded && then.nullable(input_schema)? {
+                        return Ok(true);
+                    }
+                }
+                match else_expr {
+                    Some(e) => e.nullable(input_schema),
+                    // rows that match no condition are null
+                    None => Ok(true),
                 }
             }
             Expr::Cast { expr, .. } => expr.nullable(input_schema),
             Expr::TryCast { .. } => Ok(true),
-            Expr::ScalarFunction { .. } => Ok(true),
+            Expr::ScalarFunction { fun, args } => {
+                let args_nullable = args
+                    .iter()
+                    .map(|e| e.nullable(input_schema))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(fun.nullable(&args_nullable))
+            }
             Expr::ScalarUDF { .. } => Ok(true),
-            Expr::WindowFunction { .. } => Ok(true),
+            Expr::WindowFunction { fun, .. } => match fun {
+                WindowFunction::AggregateFunction(AggregateFunction::Count)
+                | WindowFunction::BuiltInWindowFunction(
+                    BuiltInWindowFunction::RowNumber
+                    | BuiltInWindowFunction::Rank
+                    | BuiltInWindowFunction::DenseRank
+                    | BuiltInWindowFunction::PercentRank
+                    | BuiltInWindowFunction::CumeDist
+                    | BuiltInWindowFunction::Ntile,
+                ) => Ok(false),
+                _ => Ok(true),
+            },
+            // the other aggregates are null for groups without values
+            Expr::AggregateFunction {
+                fun: AggregateFunction::Count,
+                ..
+            } => Ok(false),
             Expr::AggregateFunction { .. } => Ok(true),
             Expr::AggregateUDF { .. } => Ok(true),
             Expr::RollingAggregate { .. } => Ok(true),
@@ -497,8 +652,21 @@ impl Expr {
                 ..
             } => Ok(left.nullable(input_schema)? || right.nullable(input_schema)?),
             Expr::Sort { ref expr, .. } => expr.nullable(input_schema),
-            Expr::Between { ref expr, .. } => expr.nullable(input_schema),
-            Expr::InList { ref expr, .. } => expr.nullable(input_schema),
+            Expr::Between {
+                ref expr,
+                ref low,
+                ref high,
+                ..
+            } => Ok(expr.nullable(input_schema)?
+                || low.nullable(input_schema)?
+                || high.nullable(input_schema)?),
+            // `a IN (b, NULL)` is null when `a` is not `b`
+            Expr::InList {
+                ref expr, ref list, ..
+            } => Ok(expr.nullable(input_schema)? || any_nullable(list, input_schema)?),
+            Expr::HigherOrderFunction { .. } => Ok(true),
+            Expr::Lambda { .. } => Err(lambda_outside_function()),
+            Expr::GetIndexedField { .. } => Ok(true),
             Expr::Wildcard => Err(DataFusionError::Internal(
                 "Wildcard expressions are not valid in a logical query plan".to_owned(),
             )),
@@ -540,7 +708,8 @@ impl Expr {
         let this_type = self.get_type(schema)?;
         if this_type == *cast_to_type {
             Ok(self)
-        } else if can_cast_types(&this_type, cast_to_type) {
+        } else if this_type == DataType::Null || can_cast_types(&this_type, cast_to_type)
+        {
             Ok(Expr::Cast {
                 expr: Box::new(self),
                 data_type: cast_to_type.clone(),
@@ -652,6 +821,35 @@ impl Expr {
         Expr::IsNotNull(Box::new(self))
     }
 
+    /// Return `self[key]`, the element of the list `self` at the 1-based index `key`,
+    /// NULL when it is out of bounds
+    pub fn index(self, key: Expr) -> Expr {
+        Expr::GetIndexedField {
+            expr: Box::new(self),
+            field: GetFieldAccess::ListIndex { key: Box::new(key) },
+        }
+    }
+
+    /// Return `self[start:stop]`, the list of the elements of the list `self` from
+    /// the 1-based index `start` to `stop`, both included
+    pub fn slice(self, start: Expr, stop: Expr) -> Expr {
+        Expr::GetIndexedField {
+            expr: Box::new(self),
+            field: GetFieldAccess::ListRange {
+                start: Box::new(start),
+                stop: Box::new(stop),
+            },
+        }
+    }
+
+    /// Return `self.name`, the field `name` of the struct `self`
+    pub fn field(self, name: impl Into<String>) -> Expr {
+        Expr::GetIndexedField {
+            expr: Box::new(self),
+            field: GetFieldAccess::NamedStructField { name: name.into() },
+        }
+    }
+
     /// Create a sort expression from an existing expression.
     ///
     /// ```
@@ -666,6 +864,72 @@ impl Expr {
         }
     }
 
+    /// Restrict an aggregate function to the input rows for which `predicate` is
+    /// true, i.e. `SUM(foo) FILTER (WHERE predicate)`.
+    ///
+    /// ```
+    /// # use datafusion::logical_plan::{col, lit, sum};
+    /// let filtered = sum(col("foo")).filter(col("bar").gt(lit(1)))?;
+    /// # Ok::<(), datafusion::error::DataFusionError>(())
+    /// ```
+    pub fn filter(self, predicate: Expr) -> Result<Expr> {
+        match self {
+            Expr::AggregateFunction {
+                fun,
+                args,
+                distinct,
+                ignore_nulls,
+                order_by,
+                ..
+            } => Ok(Expr::AggregateFunction {
+                fun,
+                args,
+                distinct,
+                ignore_nulls,
+                order_by,
+                filter: Some(Box::new(predicate)),
+            }),
+            _ => Err(DataFusionError::Plan(format!(
+                "FILTER is only valid for aggregate functions, got {:?}",
+                self
+            ))),
+        }
+    }
+
+    /// Returns the most volatile [Volatility] of the functions called in this
+    /// expression, [Volatility::Immutable] if there are none.
+    pub fn volatility(&self) -> Volatility {
+        struct VolatilityVisitor {
+            volatility: Volatility,
+        }
+
+        impl ExpressionVisitor for VolatilityVisitor {
+            fn pre_visit(mut self, expr: &Expr) -> Result<Recursion<Self>> {
+                let volatility = match expr {
+                    Expr::ScalarFunction { fun, .. } => fun.volatility(),
+                    Expr::ScalarUDF { fun, .. } => fun.volatility,
+                    // the body of a lambda is not visited as a child
+                    Expr::Lambda { body, .. } => body.volatility(),
+                    _ => Volatility::Immutable,
+                };
+                self.volatility = self.volatility.max(volatility);
+                Ok(Recursion::Continue(self))
+            }
+        }
+
+        self.accept(VolatilityVisitor {
+            volatility: Volatility::Immutable,
+        })
+        .map(|v| v.volatility)
+        .unwrap_or(Volatility::Volatile)
+    }
+
+    /// Returns true if evaluating this expression twice may produce different results,
+    /// e.g. because it calls `random()`.
+    pub fn is_volatile(&self) -> bool {
+        self.volatility() == Volatility::Volatile
+    }
+
     /// Performs a depth first walk of an expression and
     /// its children, calling [`ExpressionVisitor::pre_visit`] and
     /// `visitor.post_visit`.
@@ -775,9 +1039,24 @@ impl Expr {
                     .try_fold(visitor, |visitor, arg| arg.accept(visitor))?;
                 Ok(visitor)
             }
-            Expr::AggregateFunction { args, .. } => args
-                .iter()
-                .try_fold(visitor, |visitor, arg| arg.accept(visitor)),
+            Expr::AggregateFunction {
+                args,
+                order_by,
+                filter,
+                ..
+            } => {
+                let visitor = args
+                    .iter()
+                    .try_fold(visitor, |visitor, arg| arg.accept(visitor))?;
+                let visitor = order_by
+                    .iter()
+                    .try_fold(visitor, |visitor, arg| arg.accept(visitor))?;
+                if let Some(filter) = filter.as_ref() {
+                    filter.accept(visitor)
+                } else {
+                    Ok(visitor)
+                }
+            }
             Expr::AggregateUDF { args, .. } => args
                 .iter()
                 .try_fold(visitor, |visitor, arg| arg.accept(visitor)),
@@ -787,6 +1066,22 @@ impl Expr {
                 list.iter()
                     .try_fold(visitor, |visitor, arg| arg.accept(visitor))
             }
+            Expr::HigherOrderFunction { args, .. } => args
+                .iter()
+                .try_fold(visitor, |visitor, arg| arg.accept(visitor)),
+            // the columns of the body are the parameters, not columns of the input
+            Expr::Lambda { .. } => Ok(visitor),
+            Expr::GetIndexedField { expr, field } => {
+                let visitor = expr.accept(visitor)?;
+                match field {
+                    GetFieldAccess::ListIndex { key } => key.accept(visitor),
+                    GetFieldAccess::ListRange { start, stop } => {
+                        let visitor = start.accept(visitor)?;
+                        stop.accept(visitor)
+                    }
+                    GetFieldAccess::NamedStructField { .. } => Ok(visitor),
+                }
+            }
             Expr::Wildcard => Ok(visitor),
         }?;
 
@@ -926,10 +1221,16 @@ impl Expr {
                 args,
                 fun,
                 distinct,
+                ignore_nulls,
+                order_by,
+                filter,
             } => Expr::AggregateFunction {
                 args: rewrite_vec(args, rewriter)?,
                 fun,
                 distinct,
+                ignore_nulls,
+                order_by: rewrite_vec(order_by, rewriter)?,
+                filter: rewrite_option_box(filter, rewriter)?,
             },
             Expr::AggregateUDF { args, fun } => Expr::AggregateUDF {
                 args: rewrite_vec(args, rewriter)?,
@@ -955,6 +1256,28 @@ impl Expr {
                 end: end_bound,
                 offset,
             },
+            Expr::HigherOrderFunction { fun, args } => Expr::HigherOrderFunction {
+                args: rewrite_vec(args, rewriter)?,
+                fun,
+            },
+            Expr::Lambda { params, body } => Expr::Lambda { params, body },
+            Expr::GetIndexedField { expr, field } => Expr::GetIndexedField {
+                expr: rewrite_boxed(expr, rewriter)?,
+                field: match field {
+                    GetFieldAccess::ListIndex { key } => GetFieldAccess::ListIndex {
+                        key: rewrite_boxed(key, rewriter)?,
+                    },
+                    GetFieldAccess::ListRange { start, stop } => {
+                        GetFieldAccess::ListRange {
+                            start: rewrite_boxed(start, rewriter)?,
+                            stop: rewrite_boxed(stop, rewriter)?,
+                        }
+                    }
+                    GetFieldAccess::NamedStructField { name } => {
+                        GetFieldAccess::NamedStructField { name }
+                    }
+                },
+            },
             Expr::Wildcard => Expr::Wildcard,
         };
 
@@ -1308,7 +1631,10 @@ pub fn min(expr: Expr) -> Expr {
     Expr::AggregateFunction {
         fun: aggregates::AggregateFunction::Min,
         distinct: false,
+        ignore_nulls: false,
         args: vec![expr],
+        order_by: vec![],
+        filter: None,
     }
 }
 
@@ -1317,7 +1643,10 @@ pub fn max(expr: Expr) -> Expr {
     Expr::AggregateFunction {
         fun: aggregates::AggregateFunction::Max,
         distinct: false,
+        ignore_nulls: false,
         args: vec![expr],
+        order_by: vec![],
+        filter: None,
     }
 }
 
@@ -1326,7 +1655,10 @@ pub fn sum(expr: Expr) -> Expr {
     Expr::AggregateFunction {
         fun: aggregates::AggregateFunction::Sum,
         distinct: false,
+        ignore_nulls: false,
         args: vec![expr],
+        order_by: vec![],
+        filter: None,
     }
 }
 
@@ -1335,7 +1667,10 @@ pub fn avg(expr: Expr) -> Expr {
     Expr::AggregateFunction {
         fun: aggregates::AggregateFunction::Avg,
         distinct: false,
+        ignore_nulls: false,
         args: vec![expr],
+        order_by: vec![],
+        filter: None,
     }
 }
 
@@ -1344,7 +1679,10 @@ pub fn count(expr: Expr) -> Expr {
     Expr::AggregateFunction {
         fun: aggregates::AggregateFunction::Count,
         distinct: false,
+        ignore_nulls: false,
         args: vec![expr],
+        order_by: vec![],
+        filter: None,
     }
 }
 
@@ -1353,7 +1691,169 @@ pub fn count_distinct(expr: Expr) -> Expr {
     Expr::AggregateFunction {
         fun: aggregates::AggregateFunction::Count,
         distinct: true,
+        ignore_nulls: false,
+        args: vec![expr],
+        order_by: vec![],
+        filter: None,
+    }
+}
+
+/// Create an expression to represent the any_value() aggregate function, an
+/// arbitrary non-null value of `expr`
+pub fn any_value(expr: Expr) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::AnyValue,
+        distinct: false,
+        ignore_nulls: false,
+        args: vec![expr],
+        order_by: vec![],
+        filter: None,
+    }
+}
+
+/// Create an expression to represent the array_agg() aggregate function
+pub fn array_agg(expr: Expr) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::ArrayAgg,
+        distinct: false,
+        ignore_nulls: false,
+        args: vec![expr],
+        order_by: vec![],
+        filter: None,
+    }
+}
+
+/// Create an expression to represent array_agg() skipping NULL values,
+/// i.e. `array_agg(expr IGNORE NULLS)`
+pub fn array_agg_ignore_nulls(expr: Expr) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::ArrayAgg,
+        distinct: false,
+        ignore_nulls: true,
+        args: vec![expr],
+        order_by: vec![],
+        filter: None,
+    }
+}
+
+/// Create an expression to represent array_agg() of the first `limit` values of
+/// `expr` in the order of the `order_by` sort expressions, i.e.
+/// `array_agg(expr ORDER BY ... LIMIT limit)`
+pub fn array_agg_limit(expr: Expr, order_by: Vec<Expr>, limit: usize) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::ArrayAgg,
+        distinct: false,
+        ignore_nulls: false,
+        args: vec![expr, lit(limit as i64)],
+        order_by,
+        filter: None,
+    }
+}
+
+/// Create an expression to represent the string_agg() aggregate function, which
+/// concatenates the values of `expr` separated by `delimiter`, in the order of the
+/// `order_by` sort expressions, i.e. `string_agg(expr, delimiter ORDER BY ...)`
+pub fn string_agg(expr: Expr, delimiter: Expr, order_by: Vec<Expr>) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::StringAgg,
+        distinct: false,
+        ignore_nulls: false,
+        args: vec![expr, delimiter],
+        order_by,
+        filter: None,
+    }
+}
+
+/// Create an expression to represent the first() aggregate function, the value of
+/// `expr` in the first row in the order of the `order_by` sort expressions, i.e.
+/// `first(expr ORDER BY ...)`. The plan fails when `order_by` is empty.
+pub fn first(expr: Expr, order_by: Vec<Expr>) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::First,
+        distinct: false,
+        ignore_nulls: false,
+        args: vec![expr],
+        order_by,
+        filter: None,
+    }
+}
+
+/// Create an expression to represent the last() aggregate function, the value of
+/// `expr` in the last row in the order of the `order_by` sort expressions, i.e.
+/// `last(expr ORDER BY ...)`, such as the latest value with `col("ts").sort(true, true)`.
+/// The plan fails when `order_by` is empty.
+pub fn last(expr: Expr, order_by: Vec<Expr>) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::Last,
+        distinct: false,
+        ignore_nulls: false,
         args: vec![expr],
+        order_by,
+        filter: None,
+    }
+}
+
+/// Create an expression to represent the percentile_cont() ordered-set aggregate
+/// function, the `fraction` percentile of the values of `order_by` interpolated
+/// between the closest ones, i.e. `percentile_cont(fraction) WITHIN GROUP (ORDER BY expr)`.
+/// `order_by` is a sort expression, such as `col("x").sort(true, false)`
+pub fn percentile_cont(fraction: Expr, order_by: Expr) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::PercentileCont,
+        distinct: false,
+        ignore_nulls: false,
+        args: vec![fraction],
+        order_by: vec![order_by],
+        filter: None,
+    }
+}
+
+/// Create an expression to represent the percentile_disc() ordered-set aggregate
+/// function, the first value of `order_by` at or after the `fraction` percentile,
+/// i.e. `percentile_disc(fraction) WITHIN GROUP (ORDER BY expr)`. `order_by` is a sort
+/// expression, such as `col("x").sort(true, false)`
+pub fn percentile_disc(fraction: Expr, order_by: Expr) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::PercentileDisc,
+        distinct: false,
+        ignore_nulls: false,
+        args: vec![fraction],
+        order_by: vec![order_by],
+        filter: None,
+    }
+}
+
+/// Create a lambda with `params`, to be passed to a higher-order function such as
+/// [array_transform]
+pub fn lambda(params: &[&str], body: Expr) -> Expr {
+    Expr::Lambda {
+        params: params.iter().map(|p| p.to_string()).collect(),
+        body: Box::new(body),
+    }
+}
+
+/// Returns the list of the results of `lambda` on the elements of `list`
+pub fn array_transform(list: Expr, lambda: Expr) -> Expr {
+    Expr::HigherOrderFunction {
+        fun: HigherOrderFunction::ArrayTransform,
+        args: vec![list, lambda],
+    }
+}
+
+/// Returns the elements of `list` for which `lambda` is true
+pub fn array_filter(list: Expr, lambda: Expr) -> Expr {
+    Expr::HigherOrderFunction {
+        fun: HigherOrderFunction::ArrayFilter,
+        args: vec![list, lambda],
+    }
+}
+
+/// Folds the elements of `list` with `lambda`, which takes the accumulated value and
+/// an element, starting from `initial`
+pub fn array_reduce(list: Expr, initial: Expr, lambda: Expr) -> Expr {
+    Expr::HigherOrderFunction {
+        fun: HigherOrderFunction::ArrayReduce,
+        args: vec![list, initial, lambda],
     }
 }
 
@@ -1366,6 +1866,46 @@ pub fn in_list(expr: Expr, list: Vec<Expr>, negated: bool) -> Expr {
     }
 }
 
+/// Create an expression comparing two row values for equality, e.g. `(a, b) = (1, 2)`.
+/// It is true when all the values are equal, false when one pair differs and NULL
+/// otherwise, which is `a = 1 AND b = 2`.
+pub fn row_eq(left: Vec<Expr>, right: Vec<Expr>) -> Result<Expr> {
+    if left.is_empty() || left.len() != right.len() {
+        return Err(DataFusionError::Plan(format!(
+            "Cannot compare rows of {} and {} values",
+            left.len(),
+            right.len()
+        )));
+    }
+    let comparisons = left
+        .into_iter()
+        .zip(right)
+        .map(|(l, r)| l.eq(r))
+        .collect::<Vec<_>>();
+    Ok(combine_filters(&comparisons).unwrap())
+}
+
+/// Create an expression checking whether a row value is in a list of row values,
+/// e.g. `(a, b) IN ((1, 2), (3, 4))`, which is `(a, b) = (1, 2) OR (a, b) = (3, 4)`.
+pub fn in_row_list(row: Vec<Expr>, list: Vec<Vec<Expr>>, negated: bool) -> Result<Expr> {
+    if row.len() == 1 && list.iter().all(|r| r.len() == 1) {
+        let list = list.into_iter().map(|mut r| r.remove(0)).collect();
+        return Ok(in_list(row.into_iter().next().unwrap(), list, negated));
+    }
+    let mut result: Option<Expr> = None;
+    for values in list {
+        let eq = row_eq(row.clone(), values)?;
+        result = Some(match result {
+            Some(result) => or(result, eq),
+            None => eq,
+        });
+    }
+    let result = result.ok_or_else(|| {
+        DataFusionError::Plan("IN list of rows cannot be empty".to_string())
+    })?;
+    Ok(if negated { result.not() } else { result })
+}
+
 /// Trait for converting a type to a [`Literal`] literal expression.
 pub trait Literal {
     /// convert the value to a Literal expression
@@ -1438,6 +1978,51 @@ pub fn concat_ws(sep: impl Into<String>, values: &[Expr]) -> Expr {
     }
 }
 
+/// Returns the hex encoded SHA-2 digest of `expr`, with `bit_length` one of
+/// 224, 256, 384 or 512 (0 is treated as 256).
+pub fn sha2(expr: Expr, bit_length: Expr) -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::SHA2,
+        args: vec![expr, bit_length],
+    }
+}
+
+/// Encodes binary data into a textual representation, `encoding` is one of
+/// `'base64'` or `'hex'`.
+pub fn encode(expr: Expr, encoding: Expr) -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::Encode,
+        args: vec![expr, encoding],
+    }
+}
+
+/// Decodes binary data from a textual representation, `encoding` is one of
+/// `'base64'` or `'hex'`.
+pub fn decode(expr: Expr, encoding: Expr) -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::Decode,
+        args: vec![expr, encoding],
+    }
+}
+
+/// Returns true if the IP address `ip` belongs to the network `cidr`, given in
+/// CIDR notation such as `'10.0.0.0/8'`.
+pub fn cidr_contains(ip: Expr, cidr: Expr) -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::CidrContains,
+        args: vec![ip, cidr],
+    }
+}
+
+/// Returns the key comparing `expr` under `collation`, to be used in comparisons,
+/// `ORDER BY` and `GROUP BY` instead of the string itself (`expr COLLATE collation`).
+pub fn collate(expr: Expr, collation: Collation) -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::CollationKey,
+        args: vec![expr, lit(collation.name())],
+    }
+}
+
 /// Returns a random value in the range 0.0 <= x < 1.0
 pub fn random() -> Expr {
     Expr::ScalarFunction {
@@ -1446,6 +2031,14 @@ pub fn random() -> Expr {
     }
 }
 
+/// Returns a random version 4 UUID as a string
+pub fn uuid() -> Expr {
+    Expr::ScalarFunction {
+        fun: functions::BuiltinScalarFunction::Uuid,
+        args: vec![],
+    }
+}
+
 /// Create an convenience function representing a unary scalar function
 macro_rules! unary_scalar_expr {
     ($ENUM:ident, $FUNC:ident) => {
@@ -1488,12 +2081,17 @@ unary_scalar_expr!(Btrim, btrim);
 unary_scalar_expr!(CharacterLength, character_length);
 unary_scalar_expr!(CharacterLength, length);
 unary_scalar_expr!(Chr, chr);
+unary_scalar_expr!(FromHex, from_hex);
+unary_scalar_expr!(InetAton, inet_aton);
+unary_scalar_expr!(InetNtoa, inet_ntoa);
 unary_scalar_expr!(InitCap, initcap);
 unary_scalar_expr!(Left, left);
 unary_scalar_expr!(Lower, lower);
 unary_scalar_expr!(Lpad, lpad);
 unary_scalar_expr!(Ltrim, ltrim);
 unary_scalar_expr!(MD5, md5);
+unary_scalar_expr!(CRC32, crc32);
+unary_scalar_expr!(XXHash64, xxhash64);
 unary_scalar_expr!(OctetLength, octet_length);
 unary_scalar_expr!(RegexpMatch, regexp_match);
 unary_scalar_expr!(RegexpReplace, regexp_replace);
@@ -1503,6 +2101,7 @@ unary_scalar_expr!(Reverse, reverse);
 unary_scalar_expr!(Right, right);
 unary_scalar_expr!(Rpad, rpad);
 unary_scalar_expr!(Rtrim, rtrim);
+unary_scalar_expr!(SHA1, sha1);
 unary_scalar_expr!(SHA224, sha224);
 unary_scalar_expr!(SHA256, sha256);
 unary_scalar_expr!(SHA384, sha384);
@@ -1663,8 +2262,28 @@ impl fmt::Debug for Expr {
                 fun,
                 distinct,
                 ref args,
-                ..
-            } => fmt_function(f, &fun.to_string(), *distinct, args),
+                ignore_nulls,
+                order_by,
+                filter,
+            } => {
+                fmt_function(f, &fun.to_string(), *distinct, args)?;
+                if *ignore_nulls {
+                    write!(f, " IGNORE NULLS")?;
+                }
+                if aggregates::is_ordered_set(fun) {
+                    let order_by = order_by
+                        .iter()
+                        .map(|e| format!("{:?}", e))
+                        .collect::<Vec<_>>();
+                    write!(f, " WITHIN GROUP (ORDER BY {})", order_by.join(", "))?;
+                } else if !order_by.is_empty() {
+                    write!(f, " ORDER BY {:?}", order_by)?;
+                }
+                if let Some(filter) = filter {
+                    write!(f, " FILTER (WHERE {:?})", filter)?;
+                }
+                Ok(())
+            }
             Expr::AggregateUDF { fun, ref args, .. } => {
                 fmt_function(f, &fun.name, false, args)
             }
@@ -1702,6 +2321,21 @@ impl fmt::Debug for Expr {
                     write!(f, "{:?} IN ({:?})", expr, list)
                 }
             }
+            Expr::HigherOrderFunction { fun, args } => {
+                fmt_function(f, &fun.to_string(), false, args)
+            }
+            Expr::Lambda { params, body } => {
+                write!(f, "({}) -> {:?}", params.join(", "), body)
+            }
+            Expr::GetIndexedField { expr, field } => match field {
+                GetFieldAccess::ListIndex { key } => write!(f, "{:?}[{:?}]", expr, key),
+                GetFieldAccess::ListRange { start, stop } => {
+                    write!(f, "{:?}[{:?}:{:?}]", expr, start, stop)
+                }
+                GetFieldAccess::NamedStructField { name } => {
+                    write!(f, "{:?}.{}", expr, name)
+                }
+            },
             Expr::Wildcard => write!(f, "*"),
         }
     }
@@ -1724,6 +2358,23 @@ fn create_function_name(
     Ok(format!("{}({}{})", fun, distinct_str, names.join(",")))
 }
 
+/// Whether any of the expressions is nullable
+fn any_nullable(exprs: &[Expr], input_schema: &DFSchema) -> Result<bool> {
+    for e in exprs {
+        if e.nullable(input_schema)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn lambda_outside_function() -> DataFusionError {
+    DataFusionError::Plan(
+        "Lambdas are only valid as the last argument of a higher-order function"
+            .to_string(),
+    )
+}
+
 /// Returns a readable name of an expression based on the input schema.
 /// This function recursively transverses the expression for names such as "CAST(a > 2)".
 fn create_name(e: &Expr, input_schema: &DFSchema) -> Result<String> {
@@ -1813,8 +2464,36 @@ fn create_name(e: &Expr, input_schema: &DFSchema) -> Result<String> {
             fun,
             distinct,
             args,
-            ..
-        } => create_function_name(&fun.to_string(), *distinct, args, input_schema),
+            ignore_nulls,
+            order_by,
+            filter,
+        } => {
+            let mut parts = vec![create_function_name(
+                &fun.to_string(),
+                *distinct,
+                args,
+                input_schema,
+            )?];
+            if *ignore_nulls {
+                parts.push("IGNORE NULLS".to_string());
+            }
+            if aggregates::is_ordered_set(fun) {
+                let order_by = order_by
+                    .iter()
+                    .map(|e| format!("{:?}", e))
+                    .collect::<Vec<_>>();
+                parts.push(format!("WITHIN GROUP (ORDER BY {})", order_by.join(", ")));
+            } else if !order_by.is_empty() {
+                parts.push(format!("ORDER BY {:?}", order_by));
+            }
+            if let Some(filter) = filter {
+                parts.push(format!(
+                    "FILTER (WHERE {})",
+                    create_name(filter, input_schema)?
+                ));
+            }
+            Ok(parts.join(" "))
+        }
         Expr::AggregateUDF { fun, args } => {
             let mut names = Vec::with_capacity(args.len());
             for e in args {
@@ -1847,6 +2526,31 @@ fn create_name(e: &Expr, input_schema: &DFSchema) -> Result<String> {
                 Ok(format!("{} IN ({:?})", expr, list))
             }
         }
+        Expr::HigherOrderFunction { fun, args } => {
+            create_function_name(&fun.to_string(), false, args, input_schema)
+        }
+        Expr::Lambda { params, body } => Ok(format!(
+            "({}) -> {}",
+            params.join(", "),
+            create_name(body, input_schema)?
+        )),
+        Expr::GetIndexedField { expr, field } => {
+            let expr = create_name(expr, input_schema)?;
+            match field {
+                GetFieldAccess::ListIndex { key } => {
+                    Ok(format!("{}[{}]", expr, create_name(key, input_schema)?))
+                }
+                GetFieldAccess::ListRange { start, stop } => Ok(format!(
+                    "{}[{}:{}]",
+                    expr,
+                    create_name(start, input_schema)?,
+                    create_name(stop, input_schema)?
+                )),
+                GetFieldAccess::NamedStructField { name } => {
+                    Ok(format!("{}.{}", expr, name))
+                }
+            }
+        }
         other => Err(DataFusionError::NotImplemented(format!(
             "Create name does not support logical expression {:?}",
             other
@@ -1901,6 +2605,29 @@ mod tests {
         )
     }
 
+    #[test]
+    fn row_value_comparisons() -> Result<()> {
+        let expr = row_eq(vec![col("a"), col("b")], vec![lit(1), lit(2)])?;
+        assert_eq!(format!("{:?}", expr), "#a Eq Int32(1) And #b Eq Int32(2)");
+
+        let expr = in_row_list(
+            vec![col("a"), col("b")],
+            vec![vec![lit(1), lit(2)], vec![lit(3), lit(4)]],
+            true,
+        )?;
+        assert_eq!(
+            format!("{:?}", expr),
+            "NOT #a Eq Int32(1) And #b Eq Int32(2) Or #a Eq Int32(3) And #b Eq Int32(4)"
+        );
+
+        let expr = in_row_list(vec![col("a")], vec![vec![lit(1)], vec![lit(2)]], false)?;
+        assert_eq!(format!("{:?}", expr), "#a IN ([Int32(1), Int32(2)])");
+
+        assert!(row_eq(vec![col("a")], vec![lit(1), lit(2)]).is_err());
+        assert!(in_row_list(vec![col("a"), col("b")], vec![], false).is_err());
+        Ok(())
+    }
+
     #[test]
     fn filter_is_null_and_is_not_null() {
         let col_null = col("col1");
@@ -2076,12 +2803,17 @@ mod tests {
         test_unary_scalar_expr!(CharacterLength, character_length);
         test_unary_scalar_expr!(CharacterLength, length);
         test_unary_scalar_expr!(Chr, chr);
+        test_unary_scalar_expr!(FromHex, from_hex);
+        test_unary_scalar_expr!(InetAton, inet_aton);
+        test_unary_scalar_expr!(InetNtoa, inet_ntoa);
         test_unary_scalar_expr!(InitCap, initcap);
         test_unary_scalar_expr!(Left, left);
         test_unary_scalar_expr!(Lower, lower);
         test_unary_scalar_expr!(Lpad, lpad);
         test_unary_scalar_expr!(Ltrim, ltrim);
         test_unary_scalar_expr!(MD5, md5);
+        test_unary_scalar_expr!(CRC32, crc32);
+        test_unary_scalar_expr!(XXHash64, xxhash64);
         test_unary_scalar_expr!(OctetLength, octet_length);
         test_unary_scalar_expr!(RegexpMatch, regexp_match);
         test_unary_scalar_expr!(RegexpReplace, regexp_replace);
@@ -2091,6 +2823,7 @@ mod tests {
         test_unary_scalar_expr!(Right, right);
         test_unary_scalar_expr!(Rpad, rpad);
         test_unary_scalar_expr!(Rtrim, rtrim);
+        test_unary_scalar_expr!(SHA1, sha1);
         test_unary_scalar_expr!(SHA224, sha224);
         test_unary_scalar_expr!(SHA256, sha256);
         test_unary_scalar_expr!(SHA384, sha384);
@@ -2104,4 +2837,44 @@ mod tests {
         test_unary_scalar_expr!(Trim, trim);
         test_unary_scalar_expr!(Upper, upper);
     }
+
+    #[test]
+    fn nullability() -> Result<()> {
+        let schema = DFSchema::new(vec![
+            DFField::new(Some("t"), "a", DataType::Int32, false),
+            DFField::new(Some("t"), "b", DataType::Int32, true),
+        ])?;
+        let nullable = |e: Expr| e.nullable(&schema).unwrap();
+
+        assert!(!nullable(super::super::count(col("b"))));
+        assert!(nullable(super::super::sum(col("a"))));
+        assert!(!nullable(super::super::abs(col("a"))));
+        assert!(nullable(super::super::abs(col("b"))));
+        // IFNULL(b, a)
+        assert!(!nullable(
+            when(col("b").is_not_null(), col("b")).otherwise(col("a"))?
+        ));
+        assert!(nullable(when(col("b").gt(lit(1)), col("a")).end()?));
+        assert!(nullable(
+            col("a").in_list(vec![lit(1), lit(ScalarValue::Int32(None))], false)
+        ));
+        assert!(nullable(Expr::Between {
+            expr: Box::new(col("a")),
+            negated: false,
+            low: Box::new(col("b")),
+            high: Box::new(lit(10)),
+        }));
+        Ok(())
+    }
+
+    #[test]
+    fn aggregate_filter() -> Result<()> {
+        let filtered = super::super::sum(col("a")).filter(col("b").gt(lit(1)))?;
+        assert_eq!(
+            format!("{:?}", filtered),
+            "SUM(#a) FILTER (WHERE #b Gt Int32(1))"
+        );
+        assert!(col("a").filter(col("b").gt(lit(1))).is_err());
+        Ok(())
+    }
 }
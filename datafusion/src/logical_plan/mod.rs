@@ -31,20 +31,26 @@ mod plan;
 mod registry;
 pub mod window_frames;
 pub use builder::{
-    build_join_schema, union_with_alias, LogicalPlanBuilder, UNNAMED_TABLE,
+    build_join_schema, union_distinct_with_alias, union_with_alias, LogicalPlanBuilder,
+    UNNAMED_TABLE,
 };
 pub use dfschema::{DFField, DFSchema, DFSchemaRef, ToDFSchema};
 pub use display::display_schema;
 pub use expr::{
-    abs, acos, and, array, ascii, asin, atan, avg, binary_expr, bit_length, btrim, case,
-    ceil, character_length, chr, col, columnize_expr, combine_filters, concat, concat_ws,
-    cos, count, count_distinct, create_udaf, create_udf, exp, exprlist_to_fields, floor,
-    in_list, initcap, left, length, lit, ln, log10, log2, lower, lpad, ltrim, max, md5,
-    min, normalize_col, normalize_cols, now, octet_length, or, random, regexp_match,
-    regexp_replace, repeat, replace, replace_col, reverse, right, round, rpad, rtrim,
-    sha224, sha256, sha384, sha512, signum, sin, split_part, sqrt, starts_with, strpos,
-    substr, sum, tan, to_hex, translate, trim, trunc, unnormalize_col, unnormalize_cols,
-    upper, when, Column, Expr, ExprRewriter, ExpressionVisitor, Literal, Recursion,
+    abs, acos, and, any_value, array, array_agg, array_agg_ignore_nulls, array_agg_limit,
+    array_filter, array_reduce, array_transform, ascii, asin, atan, avg, binary_expr,
+    bit_length, btrim, case, ceil, character_length, chr, cidr_contains, col, collate,
+    columnize_expr, combine_filters, concat, concat_ws, cos, count, count_distinct,
+    crc32, create_udaf, create_udf, decode, encode, exp, exprlist_to_fields, first,
+    floor, from_hex, in_list, in_row_list, inet_aton, inet_ntoa, initcap, lambda, last,
+    left, length, lit, ln, log10, log2, lower, lpad, ltrim, max, md5, min, normalize_col,
+    normalize_cols, now, octet_length, or, percentile_cont, percentile_disc, random,
+    regexp_match, regexp_replace, repeat, replace, replace_col, reverse, right, round,
+    row_eq, rpad, rtrim, sha1, sha2, sha224, sha256, sha384, sha512, signum, sin,
+    split_part, sqrt, starts_with, string_agg, strpos, substr, sum, tan, to_hex,
+    translate, trim, trunc, unnormalize_col, unnormalize_cols, upper, uuid, when,
+    xxhash64, Column, Expr, ExprRewriter, ExpressionVisitor, GetFieldAccess, Literal,
+    Recursion,
 };
 pub use extension::UserDefinedLogicalNode;
 pub use operators::Operator;
@@ -49,6 +49,20 @@ pub enum JoinType {
     Anti,
 }
 
+impl JoinType {
+    /// Whether the join produces rows with nulls in place of the columns of the left
+    /// input, i.e. the output columns coming from the left input are nullable
+    pub fn is_left_nullable(&self) -> bool {
+        matches!(self, JoinType::Right | JoinType::Full)
+    }
+
+    /// Whether the join produces rows with nulls in place of the columns of the right
+    /// input, i.e. the output columns coming from the right input are nullable
+    pub fn is_right_nullable(&self) -> bool {
+        matches!(self, JoinType::Left | JoinType::Full)
+    }
+}
+
 /// Join constraint
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum JoinConstraint {
@@ -209,6 +223,54 @@ pub enum LogicalPlan {
         /// Whether the CSV file contains a header
         has_header: bool,
     },
+    /// Creates an in-memory table, filled with the rows of `input` if present.
+    CreateMemoryTable {
+        /// The table name
+        name: String,
+        /// The table schema
+        schema: DFSchemaRef,
+        /// Default values of the columns omitted by inserts, by column name
+        column_defaults: Vec<(String, Expr)>,
+        /// Expressions computing the generated columns, by column name
+        generated_columns: Vec<(String, Expr)>,
+        /// Whether to do nothing if the table already exists
+        if_not_exists: bool,
+        /// The query providing the rows of `CREATE TABLE ... AS <query>`
+        input: Option<Arc<LogicalPlan>>,
+    },
+    /// Appends the rows of `input` to a table and produces the number of rows
+    Insert {
+        /// The name of the table
+        table_name: String,
+        /// The table the rows are appended to
+        table: Arc<dyn TableProvider>,
+        /// The rows to append, with the columns of the table in order
+        input: Arc<LogicalPlan>,
+        /// Whether the table is registered under `table_name` once the rows are
+        /// appended, for `CREATE TABLE ... AS`
+        create_table: bool,
+        /// The output schema (a single count column)
+        schema: DFSchemaRef,
+    },
+    /// Computes the column statistics of a table and stores them in the catalog,
+    /// producing a row with the statistics of every column
+    Analyze {
+        /// The name of the table
+        table_name: String,
+        /// The table to analyze
+        table: Arc<dyn TableProvider>,
+        /// The output schema, see [`LogicalPlan::analyze_schema`]
+        schema: DFSchemaRef,
+    },
+    /// Sets a runtime configuration option, e.g. `SET datafusion.execution.batch_size = 1024`.
+    SetVariable {
+        /// The fully qualified option name
+        variable: String,
+        /// The textual value, parsed according to the option type
+        value: String,
+        /// The output schema (always empty)
+        schema: DFSchemaRef,
+    },
     /// Produces a relation with string representations of
     /// various parts of the plan
     Explain {
@@ -247,6 +309,10 @@ impl LogicalPlan {
             LogicalPlan::Limit { input, .. } => input.schema(),
             LogicalPlan::Skip { input, .. } => input.schema(),
             LogicalPlan::CreateExternalTable { schema, .. } => schema,
+            LogicalPlan::CreateMemoryTable { schema, .. } => schema,
+            LogicalPlan::Insert { schema, .. } => schema,
+            LogicalPlan::Analyze { schema, .. } => schema,
+            LogicalPlan::SetVariable { schema, .. } => schema,
             LogicalPlan::Explain { schema, .. } => schema,
             LogicalPlan::Extension { node } => node.schema(),
             LogicalPlan::Union { schema, .. } => schema,
@@ -261,6 +327,7 @@ impl LogicalPlan {
             } => vec![projected_schema],
             LogicalPlan::Window { input, schema, .. }
             | LogicalPlan::Aggregate { input, schema, .. }
+            | LogicalPlan::Insert { input, schema, .. }
             | LogicalPlan::Projection { input, schema, .. } => {
                 let mut schemas = input.all_schemas();
                 schemas.insert(0, schema);
@@ -288,7 +355,10 @@ impl LogicalPlan {
             LogicalPlan::Extension { node } => vec![node.schema()],
             LogicalPlan::Explain { schema, .. }
             | LogicalPlan::EmptyRelation { schema, .. }
-            | LogicalPlan::CreateExternalTable { schema, .. } => vec![schema],
+            | LogicalPlan::CreateExternalTable { schema, .. }
+            | LogicalPlan::CreateMemoryTable { schema, .. }
+            | LogicalPlan::Analyze { schema, .. }
+            | LogicalPlan::SetVariable { schema, .. } => vec![schema],
             LogicalPlan::Limit { input, .. }
             | LogicalPlan::Skip { input, .. }
             | LogicalPlan::Repartition { input, .. }
@@ -305,6 +375,26 @@ impl LogicalPlan {
         ]))
     }
 
+    /// Returns the (fixed) output schema for insert plans
+    pub fn insert_schema() -> SchemaRef {
+        SchemaRef::new(Schema::new(vec![Field::new(
+            "count",
+            DataType::UInt64,
+            false,
+        )]))
+    }
+
+    /// Returns the (fixed) output schema for analyze plans
+    pub fn analyze_schema() -> SchemaRef {
+        SchemaRef::new(Schema::new(vec![
+            Field::new("column_name", DataType::Utf8, false),
+            Field::new("null_count", DataType::UInt64, true),
+            Field::new("distinct_count", DataType::UInt64, true),
+            Field::new("min_value", DataType::Utf8, true),
+            Field::new("max_value", DataType::Utf8, true),
+        ]))
+    }
+
     /// returns all expressions (non-recursively) in the current
     /// logical plan node. This does not include expressions in any
     /// children
@@ -337,6 +427,10 @@ impl LogicalPlan {
             | LogicalPlan::Limit { .. }
             | LogicalPlan::Skip { .. }
             | LogicalPlan::CreateExternalTable { .. }
+            | LogicalPlan::CreateMemoryTable { .. }
+            | LogicalPlan::Insert { .. }
+            | LogicalPlan::Analyze { .. }
+            | LogicalPlan::SetVariable { .. }
             | LogicalPlan::CrossJoin { .. }
             | LogicalPlan::Explain { .. }
             | LogicalPlan::Union { .. } => {
@@ -362,10 +456,16 @@ impl LogicalPlan {
             LogicalPlan::Extension { node } => node.inputs(),
             LogicalPlan::Union { inputs, .. } => inputs.iter().collect(),
             LogicalPlan::Explain { plan, .. } => vec![plan],
+            LogicalPlan::Insert { input, .. } => vec![input],
+            LogicalPlan::CreateMemoryTable { input, .. } => {
+                input.iter().map(|input| input.as_ref()).collect()
+            }
             // plans without inputs
             LogicalPlan::TableScan { .. }
             | LogicalPlan::EmptyRelation { .. }
-            | LogicalPlan::CreateExternalTable { .. } => vec![],
+            | LogicalPlan::CreateExternalTable { .. }
+            | LogicalPlan::Analyze { .. }
+            | LogicalPlan::SetVariable { .. } => vec![],
         }
     }
 
@@ -508,10 +608,17 @@ impl LogicalPlan {
                 true
             }
             LogicalPlan::Explain { plan, .. } => plan.accept(visitor)?,
+            LogicalPlan::Insert { input, .. } => input.accept(visitor)?,
+            LogicalPlan::CreateMemoryTable { input, .. } => match input {
+                Some(input) => input.accept(visitor)?,
+                None => true,
+            },
             // plans without inputs
             LogicalPlan::TableScan { .. }
             | LogicalPlan::EmptyRelation { .. }
-            | LogicalPlan::CreateExternalTable { .. } => true,
+            | LogicalPlan::CreateExternalTable { .. }
+            | LogicalPlan::Analyze { .. }
+            | LogicalPlan::SetVariable { .. } => true,
         };
         if !recurse {
             return Ok(false);
@@ -803,6 +910,20 @@ impl LogicalPlan {
                     LogicalPlan::CreateExternalTable { ref name, .. } => {
                         write!(f, "CreateExternalTable: {:?}", name)
                     }
+                    LogicalPlan::CreateMemoryTable { ref name, .. } => {
+                        write!(f, "CreateMemoryTable: {:?}", name)
+                    }
+                    LogicalPlan::Insert { ref table_name, .. } => {
+                        write!(f, "Insert: {:?}", table_name)
+                    }
+                    LogicalPlan::Analyze { ref table_name, .. } => {
+                        write!(f, "Analyze: {:?}", table_name)
+                    }
+                    LogicalPlan::SetVariable {
+                        ref variable,
+                        ref value,
+                        ..
+                    } => write!(f, "SetVariable: {} = {:?}", variable, value),
                     LogicalPlan::Explain { .. } => write!(f, "Explain"),
                     LogicalPlan::Union { .. } => write!(f, "Union"),
                     LogicalPlan::Extension { ref node } => node.fmt_for_explain(f),
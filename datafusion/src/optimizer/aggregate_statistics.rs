@@ -18,7 +18,10 @@
 //! Utilizing exact statistics from sources to avoid scanning data
 use std::{sync::Arc, vec};
 
+use arrow::datatypes::Schema;
+
 use crate::{
+    datasource::datasource::ColumnStatistics,
     execution::context::ExecutionProps,
     logical_plan::{col, DFField, DFSchema, Expr, LogicalPlan},
     physical_plan::aggregates::AggregateFunction,
@@ -57,20 +60,41 @@ impl OptimizerRule for AggregateStatistics {
                 let mut agg = vec![];
                 // expressions that can be replaced by constants
                 let mut projections = vec![];
-                if let Some(num_rows) = match input.as_ref() {
-                    LogicalPlan::TableScan { source, .. }
-                        if source.has_exact_statistics() =>
-                    {
-                        source.statistics().num_rows
+                if let Some((num_rows, source_schema, column_statistics)) =
+                    match input.as_ref() {
+                        LogicalPlan::TableScan {
+                            source, filters, ..
+                        } if source.has_exact_statistics() && filters.is_empty() => {
+                            let statistics = source.statistics();
+                            statistics.num_rows.map(|num_rows| {
+                                (num_rows, source.schema(), statistics.column_statistics)
+                            })
+                        }
+                        _ => None,
                     }
-                    _ => None,
-                } {
+                {
                     for expr in aggr_expr {
+                        if let Some(null_count) = counted_column_null_count(
+                            expr,
+                            &source_schema,
+                            &column_statistics,
+                        ) {
+                            // COUNT(col) counts the rows where col is not NULL
+                            projections.push(Expr::Alias(
+                                Box::new(Expr::Literal(ScalarValue::UInt64(Some(
+                                    num_rows.saturating_sub(null_count) as u64,
+                                )))),
+                                expr.name(input.schema())?,
+                            ));
+                            continue;
+                        }
                         match expr {
                             Expr::AggregateFunction {
                                 fun: AggregateFunction::Count,
                                 args,
                                 distinct: false,
+                                filter: None,
+                                ..
                             } if args
                                 == &[Expr::Literal(ScalarValue::UInt8(Some(1)))] =>
                             {
@@ -148,6 +172,31 @@ impl OptimizerRule for AggregateStatistics {
     }
 }
 
+/// The null count of the column counted by `expr` if it is `COUNT(col)` and the
+/// statistics of the column are known
+fn counted_column_null_count(
+    expr: &Expr,
+    schema: &Schema,
+    column_statistics: &Option<Vec<ColumnStatistics>>,
+) -> Option<usize> {
+    match expr {
+        Expr::AggregateFunction {
+            fun: AggregateFunction::Count,
+            args,
+            distinct: false,
+            filter: None,
+            ..
+        } => match args.as_slice() {
+            [Expr::Column(c)] => {
+                let index = schema.index_of(&c.name).ok()?;
+                column_statistics.as_ref()?.get(index)?.null_count
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -160,7 +209,10 @@ mod tests {
     use crate::optimizer::aggregate_statistics::AggregateStatistics;
     use crate::optimizer::optimizer::OptimizerRule;
     use crate::{
-        datasource::{datasource::Statistics, TableProvider},
+        datasource::{
+            datasource::{ColumnStatistics, Statistics},
+            TableProvider,
+        },
         logical_plan::Expr,
     };
 
@@ -190,7 +242,12 @@ mod tests {
             Statistics {
                 num_rows: Some(self.num_rows),
                 total_byte_size: None,
-                column_statistics: None,
+                column_statistics: Some(vec![ColumnStatistics {
+                    null_count: Some(10),
+                    max_value: None,
+                    min_value: None,
+                    distinct_count: None,
+                }]),
             }
         }
         fn has_exact_statistics(&self) -> bool {
@@ -325,6 +382,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn optimize_count_column_using_null_count() -> Result<()> {
+        use crate::execution::context::ExecutionContext;
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table(
+            "test",
+            Arc::new(TestTableProvider {
+                num_rows: 100,
+                is_exact: true,
+            }),
+        )
+        .unwrap();
+
+        let plan = ctx
+            .create_logical_plan("select count(a), count(*) from test")
+            .unwrap();
+        let expected = "\
+            Projection: #COUNT(test.a), #COUNT(UInt8(1))\
+            \n  Projection: UInt64(90) AS COUNT(test.a), UInt64(100) AS COUNT(Uint8(1))\
+            \n    EmptyRelation";
+
+        assert_optimized_plan_eq(&plan, expected);
+
+        let plan = ctx
+            .create_logical_plan("select count(a) from test where a < 5")
+            .unwrap();
+        let expected = "\
+            Projection: #COUNT(test.a)\
+            \n  Aggregate: groupBy=[[]], aggr=[[COUNT(#test.a)]]\
+            \n    Filter: #test.a Lt Int64(5)\
+            \n      TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
     fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
         let opt = AggregateStatistics::new();
         let optimized_plan = opt.optimize(plan, &ExecutionProps::new()).unwrap();
@@ -0,0 +1,409 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Column pruning optimizer rule removes the columns that are not used by the rest of
+//! the plan from every node, adding projections below joins, windows, unions and
+//! aggregations that would otherwise carry them
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::cube_ext::alias::LogicalAlias;
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::{
+    exprlist_to_fields, Column, DFField, DFSchema, Expr, LogicalPlan, LogicalPlanBuilder,
+};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::projection_push_down::unalias_required_columns;
+use crate::optimizer::utils;
+use crate::sql::utils::find_sort_exprs;
+
+/// Optimizer rule computing the columns required by every node of the plan, top-down,
+/// and narrowing the outputs of the nodes to these columns. Unlike
+/// [`ProjectionPushDown`](super::projection_push_down::ProjectionPushDown), which
+/// prunes the scans, it also drops the columns only used by filters and join keys
+/// as soon as they are no longer needed.
+pub struct ColumnPruning {}
+
+impl ColumnPruning {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for ColumnPruning {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        _execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        let required = plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.qualified_column())
+            .collect();
+        prune(plan, &required)
+    }
+
+    fn name(&self) -> &str {
+        "column_pruning"
+    }
+}
+
+fn is_required(field: &DFField, required: &HashSet<Column>) -> bool {
+    required.contains(&field.qualified_column())
+        || required.contains(&Column::from_name(field.name()))
+}
+
+/// The indexes of the required fields of `schema`. Keeps the first field when none is
+/// required, as the plans need a column to produce their rows.
+fn required_indices(schema: &DFSchema, required: &HashSet<Column>) -> Vec<usize> {
+    let indices = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| is_required(f, required))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if indices.is_empty() && !schema.fields().is_empty() {
+        vec![0]
+    } else {
+        indices
+    }
+}
+
+/// A projection of the `fields` of `input`
+fn project_fields(input: LogicalPlan, fields: Vec<DFField>) -> Result<LogicalPlan> {
+    Ok(LogicalPlan::Projection {
+        expr: fields
+            .iter()
+            .map(|f| Expr::Column(f.qualified_column()))
+            .collect(),
+        input: Arc::new(input),
+        schema: Arc::new(DFSchema::new(fields)?),
+    })
+}
+
+/// Rewrites `plan` to produce exactly the `required` columns of its output, adding a
+/// projection when the node itself can not drop the others.
+fn narrow(plan: &LogicalPlan, required: &HashSet<Column>) -> Result<LogicalPlan> {
+    let plan = prune(plan, required)?;
+    let schema = plan.schema().clone();
+    let indices = required_indices(&schema, required);
+    if indices.len() == schema.fields().len() {
+        return Ok(plan);
+    }
+    let fields = indices.iter().map(|i| schema.field(*i).clone()).collect();
+    project_fields(plan, fields)
+}
+
+/// Rewrites `plan` to drop the columns that are neither `required` from its output nor
+/// used by the node itself. The output may keep columns that are not required, e.g.
+/// the columns of a filter predicate, [`narrow`] removes them.
+fn prune(plan: &LogicalPlan, required: &HashSet<Column>) -> Result<LogicalPlan> {
+    match plan {
+        LogicalPlan::Projection {
+            expr,
+            input,
+            schema,
+        } => {
+            let indices = required_indices(schema, required);
+            let expr = indices.iter().map(|i| expr[*i].clone()).collect::<Vec<_>>();
+            let fields = indices.iter().map(|i| schema.field(*i).clone()).collect();
+
+            let mut input_required = HashSet::new();
+            utils::exprlist_to_columns(&expr, &mut input_required)?;
+            Ok(LogicalPlan::Projection {
+                expr,
+                input: Arc::new(prune(input, &input_required)?),
+                schema: Arc::new(DFSchema::new(fields)?),
+            })
+        }
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Skip { input, .. }
+        | LogicalPlan::Repartition { input, .. } => {
+            let expr = plan.expressions();
+            let mut input_required = required.clone();
+            utils::exprlist_to_columns(&expr, &mut input_required)?;
+            utils::from_plan(plan, &expr, &[prune(input, &input_required)?])
+        }
+        LogicalPlan::Join {
+            left, right, on, ..
+        } => {
+            let mut input_required = required.clone();
+            for (l, r) in on {
+                input_required.insert(l.clone());
+                input_required.insert(r.clone());
+            }
+            let left = narrow(left, &input_required)?;
+            let right = narrow(right, &input_required)?;
+            utils::from_plan(plan, &[], &[left, right])
+        }
+        LogicalPlan::CrossJoin { left, right, .. } => {
+            let left = narrow(left, required)?;
+            let right = narrow(right, required)?;
+            utils::from_plan(plan, &[], &[left, right])
+        }
+        LogicalPlan::Window {
+            input,
+            window_expr,
+            schema,
+        } => {
+            let window_expr = window_expr
+                .iter()
+                .zip(schema.fields())
+                .filter(|(_, f)| is_required(f, required))
+                .map(|(e, _)| e.clone())
+                .collect::<Vec<_>>();
+
+            let mut input_required = required.clone();
+            utils::exprlist_to_columns(&window_expr, &mut input_required)?;
+            utils::exprlist_to_columns(
+                &find_sort_exprs(&window_expr),
+                &mut input_required,
+            )?;
+            let input = narrow(input, &input_required)?;
+            if window_expr.is_empty() {
+                return Ok(input);
+            }
+            LogicalPlanBuilder::from(input).window(window_expr)?.build()
+        }
+        LogicalPlan::Aggregate {
+            input,
+            group_expr,
+            aggr_expr,
+            schema,
+        } => {
+            let mut new_aggr_expr = aggr_expr
+                .iter()
+                .zip(&schema.fields()[group_expr.len()..])
+                .filter(|(_, f)| is_required(f, required))
+                .map(|(e, _)| e.clone())
+                .collect::<Vec<_>>();
+            // an aggregation without group keys needs an aggregate to produce its row
+            if group_expr.is_empty() && new_aggr_expr.is_empty() {
+                new_aggr_expr.extend(aggr_expr.iter().take(1).cloned());
+            }
+
+            let mut input_required = HashSet::new();
+            utils::exprlist_to_columns(group_expr, &mut input_required)?;
+            utils::exprlist_to_columns(&new_aggr_expr, &mut input_required)?;
+            let input = narrow(input, &input_required)?;
+
+            let all_expr = group_expr.iter().chain(new_aggr_expr.iter());
+            let schema = DFSchema::new(exprlist_to_fields(all_expr, input.schema())?)?;
+            Ok(LogicalPlan::Aggregate {
+                input: Arc::new(input),
+                group_expr: group_expr.clone(),
+                aggr_expr: new_aggr_expr,
+                schema: Arc::new(schema),
+            })
+        }
+        LogicalPlan::Union { inputs, schema, .. } => {
+            // the columns of the inputs are matched by position
+            let indices = required_indices(schema, required);
+            if indices.len() == schema.fields().len() {
+                let inputs = inputs
+                    .iter()
+                    .map(|input| {
+                        let input_required = input
+                            .schema()
+                            .fields()
+                            .iter()
+                            .map(|f| f.qualified_column())
+                            .collect();
+                        prune(input, &input_required)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                return utils::from_plan(plan, &[], &inputs);
+            }
+            let inputs = inputs
+                .iter()
+                .map(|input| {
+                    let fields = indices
+                        .iter()
+                        .map(|i| input.schema().field(*i).clone())
+                        .collect::<Vec<_>>();
+                    let input_required =
+                        fields.iter().map(|f| f.qualified_column()).collect();
+                    let input = prune(input, &input_required)?;
+                    if input.schema().fields() == &fields {
+                        Ok(input)
+                    } else {
+                        project_fields(input, fields)
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            utils::from_plan(plan, &[], &inputs)
+        }
+        LogicalPlan::TableScan {
+            table_name,
+            source,
+            projection,
+            projected_schema,
+            filters,
+            limit,
+        } => {
+            let indices = required_indices(projected_schema, required);
+            if indices.len() == projected_schema.fields().len() {
+                return Ok(plan.clone());
+            }
+            let fields = indices
+                .iter()
+                .map(|i| projected_schema.field(*i).clone())
+                .collect();
+            let projection = indices
+                .iter()
+                .map(|i| projection.as_ref().map_or(*i, |p| p[*i]))
+                .collect();
+            Ok(LogicalPlan::TableScan {
+                table_name: table_name.clone(),
+                source: source.clone(),
+                projection: Some(projection),
+                projected_schema: Arc::new(DFSchema::new(fields)?),
+                filters: filters.clone(),
+                limit: *limit,
+            })
+        }
+        LogicalPlan::Extension { node } if node.as_any().is::<LogicalAlias>() => {
+            let input = node.inputs()[0];
+            let input_required = unalias_required_columns(input.schema(), required);
+            utils::from_plan(plan, &[], &[prune(input, &input_required)?])
+        }
+        LogicalPlan::EmptyRelation { .. }
+        | LogicalPlan::CreateExternalTable { .. }
+        | LogicalPlan::Analyze { .. }
+        | LogicalPlan::SetVariable { .. } => Ok(plan.clone()),
+        // all other nodes: the inputs keep all their columns
+        LogicalPlan::CreateMemoryTable { .. }
+        | LogicalPlan::Insert { .. }
+        | LogicalPlan::Explain { .. }
+        | LogicalPlan::Extension { .. } => {
+            let inputs = plan
+                .inputs()
+                .into_iter()
+                .map(|input| {
+                    let input_required = input
+                        .schema()
+                        .fields()
+                        .iter()
+                        .map(|f| f.qualified_column())
+                        .collect();
+                    prune(input, &input_required)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            utils::from_plan(plan, &plan.expressions(), &inputs)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, lit, max, JoinType};
+    use crate::test::*;
+
+    #[test]
+    fn prune_filter_columns_below_join() -> Result<()> {
+        let left = LogicalPlanBuilder::from(test_table_scan()?)
+            .filter(col("c").gt(lit(1u32)))?
+            .build()?;
+        let right = test_table_scan_with_name("test2")?;
+        let plan = LogicalPlanBuilder::from(left)
+            .join(&right, JoinType::Inner, (vec!["a"], vec!["a"]))?
+            .project(vec![col("test.b"), col("test2.c")])?
+            .build()?;
+
+        let expected = "Projection: #test.b, #test2.c\
+        \n  Join: #test.a = #test2.a\
+        \n    Projection: #test.a, #test.b\
+        \n      Filter: #test.c Gt UInt32(1)\
+        \n        TableScan: test projection=None\
+        \n    TableScan: test2 projection=Some([0, 2])";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn prune_aggregate_input() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .filter(col("c").gt(lit(1u32)))?
+            .aggregate(vec![col("a")], vec![max(col("b"))])?
+            .project(vec![col("a")])?
+            .build()?;
+
+        let expected = "Projection: #test.a\
+        \n  Aggregate: groupBy=[[#test.a]], aggr=[[]]\
+        \n    Projection: #test.a\
+        \n      Filter: #test.c Gt UInt32(1)\
+        \n        TableScan: test projection=Some([0, 2])";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn prune_union_by_position() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .project(vec![col("a"), col("b")])?
+            .union(
+                LogicalPlanBuilder::from(test_table_scan_with_name("test2")?)
+                    .project(vec![col("c").alias("a"), col("a").alias("b")])?
+                    .build()?,
+            )?
+            .project(vec![col("b")])?
+            .build()?;
+
+        let expected = "Projection: #b\
+        \n  Union\
+        \n    Projection: #test.b\
+        \n      TableScan: test projection=Some([1])\
+        \n    Projection: #test2.a AS b\
+        \n      TableScan: test2 projection=Some([0])";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_a_column_for_the_rows() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .project(vec![lit(1u32).alias("one")])?
+            .build()?;
+
+        let expected = "Projection: UInt32(1) AS one\
+        \n  TableScan: test projection=Some([0])";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let rule = ColumnPruning::new();
+        let optimized_plan = rule
+            .optimize(plan, &ExecutionProps::new())
+            .expect("failed to optimize plan");
+        assert_eq!(format!("{:?}", optimized_plan), expected);
+        // applying the rule again does not change the plan
+        let reoptimized_plan = rule
+            .optimize(&optimized_plan, &ExecutionProps::new())
+            .expect("failed to optimize plan");
+        assert_eq!(format!("{:?}", reoptimized_plan), expected);
+    }
+}
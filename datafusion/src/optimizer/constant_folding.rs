@@ -20,17 +20,20 @@
 
 use std::sync::Arc;
 
-use arrow::compute::kernels::cast_utils::string_to_timestamp_nanos;
-use arrow::datatypes::DataType;
+use arrow::datatypes::{DataType, Schema};
 
-use crate::error::Result;
-use crate::execution::context::ExecutionProps;
-use crate::logical_plan::{DFSchemaRef, Expr, ExprRewriter, LogicalPlan, Operator};
+use crate::error::{DataFusionError, Result};
+use crate::execution::context::{ExecutionContextState, ExecutionProps};
+use crate::logical_plan::{
+    DFSchema, DFSchemaRef, Expr, ExprRewriter, LogicalPlan, Operator,
+};
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::utils;
-use crate::physical_plan::functions::BuiltinScalarFunction;
+use crate::physical_plan::expressions::{cast_array_in_zone, Literal};
+use crate::physical_plan::functions::Volatility;
+use crate::physical_plan::planner::{evaluate_const, DefaultPhysicalPlanner};
 use crate::scalar::ScalarValue;
-use arrow::compute::{kernels, DEFAULT_CAST_OPTIONS};
+use arrow::compute::DEFAULT_CAST_OPTIONS;
 
 /// Optimizer that simplifies comparison expressions involving boolean literals.
 ///
@@ -41,6 +44,8 @@ use arrow::compute::{kernels, DEFAULT_CAST_OPTIONS};
 /// * `false = true` and `true = false` to `false`
 /// * `!!expr` to `expr`
 /// * `expr = null` and `expr != null` to `null`
+/// * calls of functions that are not [Volatility::Volatile] with literal arguments to
+///   their value, e.g. `now()` to the start time of the query
 pub struct ConstantFolding {}
 
 impl ConstantFolding {
@@ -77,6 +82,10 @@ impl OptimizerRule for ConstantFolding {
             | LogicalPlan::Aggregate { .. }
             | LogicalPlan::Repartition { .. }
             | LogicalPlan::CreateExternalTable { .. }
+            | LogicalPlan::CreateMemoryTable { .. }
+            | LogicalPlan::Insert { .. }
+            | LogicalPlan::Analyze { .. }
+            | LogicalPlan::SetVariable { .. }
             | LogicalPlan::Extension { .. }
             | LogicalPlan::Sort { .. }
             | LogicalPlan::Explain { .. }
@@ -127,6 +136,45 @@ impl<'a> ConstantRewriter<'a> {
 
         false
     }
+
+    /// Whether the function call `expr` has literal arguments and a value known at
+    /// planning time, as its [Volatility] is not volatile. The information functions
+    /// are stable but bound from the configuration of the session, which optimizer
+    /// rules do not see, so they are left to the physical planner.
+    fn can_evaluate(&self, expr: &Expr) -> bool {
+        let args = match expr {
+            Expr::ScalarFunction { fun, .. } if fun.is_session_information() => {
+                return false
+            }
+            Expr::ScalarFunction { args, .. } | Expr::ScalarUDF { args, .. } => args,
+            _ => return false,
+        };
+        expr.volatility() != Volatility::Volatile
+            && args.iter().all(|arg| matches!(arg, Expr::Literal(_)))
+    }
+
+    /// Evaluates `expr` with the properties of the query, e.g. its start time for
+    /// `now()`. Errors, such as invalid arguments, are left to the execution.
+    fn evaluate(&self, expr: &Expr) -> Result<Expr> {
+        let ctx_state = ExecutionContextState {
+            execution_props: self.execution_props.clone(),
+            ..ExecutionContextState::new()
+        };
+        let physical_expr = DefaultPhysicalPlanner::default().create_physical_expr(
+            expr,
+            &DFSchema::empty(),
+            &Schema::empty(),
+            &ctx_state,
+        )?;
+        let value = evaluate_const(physical_expr)?;
+        match value.as_any().downcast_ref::<Literal>() {
+            Some(literal) => Ok(Expr::Literal(literal.value().clone())),
+            None => Err(DataFusionError::Internal(format!(
+                "{:?} was not evaluated to a literal",
+                expr
+            ))),
+        }
+    }
 }
 
 impl<'a> ExprRewriter for ConstantRewriter<'a> {
@@ -212,42 +260,10 @@ impl<'a> ExprRewriter for ConstantRewriter<'a> {
                     Expr::Not(inner)
                 }
             }
-            Expr::ScalarFunction {
-                fun: BuiltinScalarFunction::Now,
-                ..
-            } => Expr::Literal(ScalarValue::TimestampNanosecond(Some(
-                self.execution_props
-                    .query_execution_start_time
-                    .timestamp_nanos(),
-            ))),
-            Expr::ScalarFunction {
-                fun: BuiltinScalarFunction::ToTimestamp,
-                args,
-            } => {
-                if !args.is_empty() {
-                    match &args[0] {
-                        Expr::Literal(ScalarValue::Utf8(Some(val))) => {
-                            match string_to_timestamp_nanos(val) {
-                                Ok(timestamp) => Expr::Literal(
-                                    ScalarValue::TimestampNanosecond(Some(timestamp)),
-                                ),
-                                _ => Expr::ScalarFunction {
-                                    fun: BuiltinScalarFunction::ToTimestamp,
-                                    args,
-                                },
-                            }
-                        }
-                        _ => Expr::ScalarFunction {
-                            fun: BuiltinScalarFunction::ToTimestamp,
-                            args,
-                        },
-                    }
-                } else {
-                    Expr::ScalarFunction {
-                        fun: BuiltinScalarFunction::ToTimestamp,
-                        args,
-                    }
-                }
+            expr @ (Expr::ScalarFunction { .. } | Expr::ScalarUDF { .. })
+                if self.can_evaluate(&expr) =>
+            {
+                self.evaluate(&expr).unwrap_or(expr)
             }
             Expr::Cast {
                 expr: inner,
@@ -255,10 +271,11 @@ impl<'a> ExprRewriter for ConstantRewriter<'a> {
             } => match inner.as_ref() {
                 Expr::Literal(val) => {
                     let scalar_array = val.to_array();
-                    let cast_array = kernels::cast::cast_with_options(
+                    let cast_array = cast_array_in_zone(
                         &scalar_array,
                         &data_type,
                         &DEFAULT_CAST_OPTIONS,
+                        &self.execution_props.time_zone,
                     )?;
                     let cast_scalar = ScalarValue::try_from_array(&cast_array, 0)?;
                     Expr::Literal(cast_scalar)
@@ -283,9 +300,10 @@ mod tests {
     use crate::logical_plan::{
         col, lit, max, min, DFField, DFSchema, LogicalPlanBuilder,
     };
+    use crate::physical_plan::functions::BuiltinScalarFunction;
 
     use arrow::datatypes::*;
-    use chrono::{DateTime, Utc};
+    use chrono::{DateTime, TimeZone, Utc};
 
     fn test_table_scan() -> Result<LogicalPlan> {
         let schema = Schema::new(vec![
@@ -675,6 +693,7 @@ mod tests {
         let rule = ConstantFolding::new();
         let execution_props = ExecutionProps {
             query_execution_start_time: *date_time,
+            ..ExecutionProps::new()
         };
 
         let optimized_plan = rule
@@ -705,6 +724,35 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn to_timestamp_expr_in_time_zone() {
+        let table_scan = test_table_scan().unwrap();
+        let proj = vec![Expr::ScalarFunction {
+            args: vec![Expr::Literal(ScalarValue::Utf8(Some(
+                "2020-09-08T12:00:00".to_string(),
+            )))],
+            fun: BuiltinScalarFunction::ToTimestamp,
+        }];
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .project(proj)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let rule = ConstantFolding::new();
+        let execution_props = ExecutionProps {
+            time_zone: chrono::FixedOffset::east(2 * 3600).into(),
+            ..ExecutionProps::new()
+        };
+        let optimized_plan = rule
+            .optimize(&plan, &execution_props)
+            .expect("failed to optimize plan");
+
+        let expected = "Projection: TimestampNanosecond(1599559200000000000)\
+            \n  TableScan: test projection=None";
+        assert_eq!(expected, format!("{:?}", optimized_plan));
+    }
+
     #[test]
     fn to_timestamp_expr_wrong_arg() {
         let table_scan = test_table_scan().unwrap();
@@ -840,4 +888,64 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn current_time_exprs_in_time_zone() {
+        let table_scan = test_table_scan().unwrap();
+        let proj = vec![
+            Expr::ScalarFunction {
+                args: vec![lit(0_i64)],
+                fun: BuiltinScalarFunction::Now,
+            },
+            Expr::ScalarFunction {
+                args: vec![],
+                fun: BuiltinScalarFunction::LocalTimestamp,
+            },
+            Expr::ScalarFunction {
+                args: vec![],
+                fun: BuiltinScalarFunction::CurrentDate,
+            },
+        ];
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .project(proj)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let rule = ConstantFolding::new();
+        let execution_props = ExecutionProps {
+            query_execution_start_time: Utc.ymd(2021, 3, 1).and_hms(23, 0, 0),
+            time_zone: chrono::FixedOffset::east(2 * 3600).into(),
+            ..ExecutionProps::new()
+        };
+        let optimized_plan = rule
+            .optimize(&plan, &execution_props)
+            .expect("failed to optimize plan");
+
+        let expected = "Projection: TimestampNanosecond(1614639600000000000), \
+            TimestampNanosecond(1614646800000000000), Date32(\"18688\")\
+            \n  TableScan: test projection=None";
+        assert_eq!(expected, format!("{:?}", optimized_plan));
+    }
+
+    #[test]
+    fn fold_by_volatility() -> Result<()> {
+        let call = |fun, args| Expr::ScalarFunction { fun, args };
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .project(vec![
+                call(BuiltinScalarFunction::Abs, vec![lit(-2_i64)]),
+                call(BuiltinScalarFunction::Abs, vec![col("d")]),
+                call(BuiltinScalarFunction::Random, vec![]),
+                call(BuiltinScalarFunction::Version, vec![]),
+            ])?
+            .build()?;
+
+        // immutable calls of literals are folded, volatile ones never are and the
+        // session information is only known to the physical planner
+        let expected = "Projection: Float64(2), abs(#test.d), random(), version()\
+            \n  TableScan: test projection=None";
+        let actual = get_optimized_plan_formatted(&plan, &chrono::Utc::now());
+        assert_eq!(expected, actual);
+        Ok(())
+    }
 }
@@ -0,0 +1,215 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Eliminate filter optimizer rule replaces the scans whose filter no row of the table
+//! satisfies with an empty relation
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::{DFSchema, LogicalPlan};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::range_analysis::RangeAnalysis;
+
+use super::utils;
+
+/// Optimization rule that replaces a filter of a table scan with an
+/// [LogicalPlan::EmptyRelation] when the [RangeAnalysis] of the predicate against the
+/// min/max statistics of the table proves it false for every row, e.g.
+/// `WHERE ts < '2020-01-01'` on a table whose oldest `ts` is in 2021. Only tables
+/// reporting exact statistics are considered, estimates may miss some rows.
+pub struct EliminateFilter;
+
+impl EliminateFilter {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for EliminateFilter {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        match plan {
+            LogicalPlan::Filter { predicate, input } => {
+                if let LogicalPlan::TableScan {
+                    table_name, source, ..
+                } = input.as_ref()
+                {
+                    let statistics = source.statistics();
+                    if let (true, Some(column_statistics)) =
+                        (source.has_exact_statistics(), statistics.column_statistics)
+                    {
+                        let schema = DFSchema::try_from_qualified_schema(
+                            table_name,
+                            source.schema().as_ref(),
+                        )?;
+                        let analysis = RangeAnalysis::new(&schema)
+                            .with_statistics(&column_statistics);
+                        if analysis.evaluate_predicate(predicate)? == Some(false) {
+                            return Ok(LogicalPlan::EmptyRelation {
+                                produce_one_row: false,
+                                schema: input.schema().clone(),
+                            });
+                        }
+                    }
+                }
+                Ok(plan.clone())
+            }
+            _ => {
+                let expr = plan.expressions();
+                let new_inputs = plan
+                    .inputs()
+                    .iter()
+                    .map(|plan| self.optimize(plan, execution_props))
+                    .collect::<Result<Vec<_>>>()?;
+
+                utils::from_plan(plan, &expr, &new_inputs)
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        "eliminate_filter"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::datasource::analyzed::AnalyzedTable;
+    use crate::datasource::datasource::{ColumnStatistics, Statistics};
+    use crate::datasource::{MemTable, TableProvider};
+    use crate::logical_plan::{col, lit, Expr, LogicalPlanBuilder};
+    use crate::physical_plan::ExecutionPlan;
+    use crate::scalar::ScalarValue;
+    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+
+    /// A table reporting `statistics`, exact when `is_exact` is set
+    struct TestTableProvider {
+        statistics: Statistics,
+        is_exact: bool,
+    }
+
+    impl TableProvider for TestTableProvider {
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+
+        fn schema(&self) -> SchemaRef {
+            Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]))
+        }
+
+        fn scan(
+            &self,
+            _projection: &Option<Vec<usize>>,
+            _batch_size: usize,
+            _filters: &[Expr],
+            _limit: Option<usize>,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            unimplemented!()
+        }
+
+        fn statistics(&self) -> Statistics {
+            self.statistics.clone()
+        }
+
+        fn has_exact_statistics(&self) -> bool {
+            self.is_exact
+        }
+    }
+
+    fn range_statistics(min: i32, max: i32) -> Statistics {
+        Statistics {
+            num_rows: None,
+            total_byte_size: None,
+            column_statistics: Some(vec![ColumnStatistics {
+                null_count: Some(0),
+                max_value: Some(ScalarValue::Int32(Some(max))),
+                min_value: Some(ScalarValue::Int32(Some(min))),
+                distinct_count: None,
+            }]),
+        }
+    }
+
+    fn scan_with_range(min: i32, max: i32) -> Result<LogicalPlanBuilder> {
+        let table = TestTableProvider {
+            statistics: range_statistics(min, max),
+            is_exact: true,
+        };
+        LogicalPlanBuilder::scan("test", Arc::new(table), None)
+    }
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = EliminateFilter::new()
+            .optimize(plan, &ExecutionProps::new())
+            .expect("failed to optimize plan");
+        assert_eq!(format!("{:?}", optimized_plan), expected);
+        assert_eq!(optimized_plan.schema(), plan.schema());
+    }
+
+    #[test]
+    fn filter_out_of_range() -> Result<()> {
+        let plan = scan_with_range(10, 20)?
+            .filter(col("a").lt(lit(10i32)).or(col("a").gt(lit(25i32))))?
+            .project(vec![col("a")])?
+            .build()?;
+        let expected = "Projection: #test.a\
+        \n  EmptyRelation";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_in_range() -> Result<()> {
+        let plan = scan_with_range(10, 20)?
+            .filter(col("a").lt_eq(lit(10i32)))?
+            .build()?;
+        let expected = "Filter: #test.a LtEq Int32(10)\
+        \n  TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_with_inexact_statistics() -> Result<()> {
+        let expected = "Filter: #test.a Gt Int32(25)\
+        \n  TableScan: test projection=None";
+
+        let table = TestTableProvider {
+            statistics: range_statistics(10, 20),
+            is_exact: false,
+        };
+        let plan = LogicalPlanBuilder::scan("test", Arc::new(table), None)?
+            .filter(col("a").gt(lit(25i32)))?
+            .build()?;
+        assert_optimized_plan_eq(&plan, expected);
+
+        // the analyzed range does not include the rows inserted since
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let source = Arc::new(MemTable::try_new(schema, vec![vec![]])?);
+        let table = AnalyzedTable::new(source, range_statistics(10, 20));
+        let plan = LogicalPlanBuilder::scan("test", Arc::new(table), None)?
+            .filter(col("a").gt(lit(25i32)))?
+            .build()?;
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+}
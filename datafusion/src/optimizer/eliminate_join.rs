@@ -0,0 +1,226 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Eliminate join optimizer rule removes the outer joins whose nullable side is not
+//! used and matches each row at most once
+
+use std::collections::HashSet;
+
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::{Column, DFSchema, JoinType, LogicalPlan, LogicalPlanBuilder};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+use crate::sql::utils::find_sort_exprs;
+
+/// Optimizer rule that replaces a left join by its left input when no column of the
+/// right input is used above the join and the right input has at most one row for
+/// every value of its join keys, e.g. in
+///
+/// ```sql
+/// SELECT o.id, o.amount FROM orders o
+/// LEFT JOIN (SELECT id, MAX(name) AS name FROM customers GROUP BY id) c
+/// ON o.customer_id = c.id
+/// ```
+///
+/// the join neither adds nor removes rows of `orders` and the query only reads
+/// `orders`. Right joins are replaced by their right input likewise.
+pub struct EliminateJoin {}
+
+impl EliminateJoin {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for EliminateJoin {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        _execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        optimize(plan, &schema_columns(plan.schema()))
+    }
+
+    fn name(&self) -> &str {
+        "eliminate_join"
+    }
+}
+
+fn schema_columns(schema: &DFSchema) -> HashSet<Column> {
+    schema
+        .fields()
+        .iter()
+        .map(|f| f.qualified_column())
+        .collect()
+}
+
+/// Eliminates the joins of `plan`, of which only the `required` columns are used
+fn optimize(plan: &LogicalPlan, required: &HashSet<Column>) -> Result<LogicalPlan> {
+    match plan {
+        LogicalPlan::Join {
+            left,
+            right,
+            on,
+            join_type,
+            ..
+        } => {
+            let (left_keys, right_keys): (HashSet<_>, HashSet<_>) =
+                on.iter().cloned().unzip();
+            let uses_any = |input: &LogicalPlan| {
+                input
+                    .schema()
+                    .fields()
+                    .iter()
+                    .any(|f| required.contains(&f.qualified_column()))
+            };
+            match join_type {
+                JoinType::Left
+                    if !uses_any(right) && utils::is_unique(right, &right_keys) =>
+                {
+                    return optimize(left, required);
+                }
+                JoinType::Right
+                    if !uses_any(left) && utils::is_unique(left, &left_keys) =>
+                {
+                    return optimize(right, required);
+                }
+                _ => {}
+            }
+
+            let mut input_required = required.clone();
+            input_required.extend(left_keys);
+            input_required.extend(right_keys);
+            let left = optimize(left, &input_required)?;
+            let right = optimize(right, &input_required)?;
+            utils::from_plan(plan, &[], &[left, right])
+        }
+        LogicalPlan::Projection { expr, input, .. } => {
+            let mut input_required = HashSet::new();
+            utils::exprlist_to_columns(expr, &mut input_required)?;
+            utils::from_plan(plan, expr, &[optimize(input, &input_required)?])
+        }
+        LogicalPlan::Aggregate { input, .. } => {
+            let expr = plan.expressions();
+            let mut input_required = HashSet::new();
+            utils::exprlist_to_columns(&expr, &mut input_required)?;
+            utils::from_plan(plan, &expr, &[optimize(input, &input_required)?])
+        }
+        LogicalPlan::Window {
+            input, window_expr, ..
+        } => {
+            let mut input_required = required.clone();
+            utils::exprlist_to_columns(window_expr, &mut input_required)?;
+            utils::exprlist_to_columns(
+                &find_sort_exprs(window_expr),
+                &mut input_required,
+            )?;
+            // the schema of the window includes the columns of its input
+            LogicalPlanBuilder::from(optimize(input, &input_required)?)
+                .window(window_expr.clone())?
+                .build()
+        }
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Skip { input, .. }
+        | LogicalPlan::Repartition { input, .. } => {
+            let expr = plan.expressions();
+            let mut input_required = required.clone();
+            utils::exprlist_to_columns(&expr, &mut input_required)?;
+            utils::from_plan(plan, &expr, &[optimize(input, &input_required)?])
+        }
+        // all other nodes use all the columns of their inputs
+        _ => {
+            let inputs = plan
+                .inputs()
+                .into_iter()
+                .map(|input| optimize(input, &schema_columns(input.schema())))
+                .collect::<Result<Vec<_>>>()?;
+            utils::from_plan(plan, &plan.expressions(), &inputs)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, max, Expr};
+    use crate::test::*;
+
+    fn left_join_of_aggregate(group_by: Vec<Expr>) -> Result<LogicalPlanBuilder> {
+        let right = LogicalPlanBuilder::from(test_table_scan_with_name("test2")?)
+            .aggregate(group_by, vec![max(col("c"))])?
+            .build()?;
+        LogicalPlanBuilder::from(test_table_scan()?).join(
+            &right,
+            JoinType::Left,
+            (vec!["a"], vec!["a"]),
+        )
+    }
+
+    #[test]
+    fn eliminate_left_join_of_unique_keys() -> Result<()> {
+        let plan = left_join_of_aggregate(vec![col("a")])?
+            .project(vec![col("test.a"), col("test.b")])?
+            .build()?;
+
+        let expected = "Projection: #test.a, #test.b\
+        \n  TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_join_of_duplicate_keys() -> Result<()> {
+        let plan = left_join_of_aggregate(vec![col("a"), col("b")])?
+            .project(vec![col("test.a"), col("test.b")])?
+            .build()?;
+
+        let expected = "Projection: #test.a, #test.b\
+        \n  Join: #test.a = #test2.a\
+        \n    TableScan: test projection=None\
+        \n    Aggregate: groupBy=[[#test2.a, #test2.b]], aggr=[[MAX(#test2.c)]]\
+        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_join_of_used_columns() -> Result<()> {
+        let plan = left_join_of_aggregate(vec![col("a")])?
+            .project(vec![col("test.b"), col("MAX(test2.c)")])?
+            .build()?;
+
+        let expected = "Projection: #test.b, #MAX(test2.c)\
+        \n  Join: #test.a = #test2.a\
+        \n    TableScan: test projection=None\
+        \n    Aggregate: groupBy=[[#test2.a]], aggr=[[MAX(#test2.c)]]\
+        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = EliminateJoin::new()
+            .optimize(plan, &ExecutionProps::new())
+            .expect("failed to optimize plan");
+        assert_eq!(format!("{:?}", optimized_plan), expected);
+        assert_eq!(optimized_plan.schema(), plan.schema());
+    }
+}
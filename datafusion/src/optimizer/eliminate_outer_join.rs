@@ -0,0 +1,256 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Eliminate outer join optimizer rule turns outer joins into inner joins when a filter
+//! removes the rows they pad with nulls
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::{Column, DFSchema, Expr, JoinType, LogicalPlan, Operator};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+
+/// Optimizer rule that reduces the outer joins below a filter rejecting nulls in the
+/// columns of their nullable side, e.g. in
+///
+/// ```sql
+/// SELECT * FROM t1 LEFT JOIN t2 ON t1.a = t2.a WHERE t2.b > 1
+/// ```
+///
+/// the left join becomes an inner join, as the rows of `t1` without a match in `t2`
+/// have a null `b` and do not pass the filter. Likewise a full join becomes a left,
+/// right or inner join.
+pub struct EliminateOuterJoin {}
+
+impl EliminateOuterJoin {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for EliminateOuterJoin {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        let plan = match plan {
+            LogicalPlan::Filter { predicate, input } => match input.as_ref() {
+                LogicalPlan::Join {
+                    left,
+                    right,
+                    on,
+                    join_type,
+                    join_constraint,
+                    schema,
+                } => {
+                    let left_rejected =
+                        rejects_nulls(predicate, &schema_columns(left.schema()));
+                    let right_rejected =
+                        rejects_nulls(predicate, &schema_columns(right.schema()));
+                    let new_join_type = match join_type {
+                        JoinType::Left if right_rejected => JoinType::Inner,
+                        JoinType::Right if left_rejected => JoinType::Inner,
+                        JoinType::Full => match (left_rejected, right_rejected) {
+                            (true, true) => JoinType::Inner,
+                            (true, false) => JoinType::Left,
+                            (false, true) => JoinType::Right,
+                            (false, false) => JoinType::Full,
+                        },
+                        join_type => *join_type,
+                    };
+                    LogicalPlan::Filter {
+                        predicate: predicate.clone(),
+                        input: Arc::new(LogicalPlan::Join {
+                            left: left.clone(),
+                            right: right.clone(),
+                            on: on.clone(),
+                            join_type: new_join_type,
+                            join_constraint: *join_constraint,
+                            // the columns stay nullable for the nodes above
+                            schema: schema.clone(),
+                        }),
+                    }
+                }
+                _ => plan.clone(),
+            },
+            _ => plan.clone(),
+        };
+
+        let inputs = plan
+            .inputs()
+            .into_iter()
+            .map(|input| self.optimize(input, execution_props))
+            .collect::<Result<Vec<_>>>()?;
+        match plan {
+            // keep the schema of the join, see above
+            LogicalPlan::Join {
+                on,
+                join_type,
+                join_constraint,
+                schema,
+                ..
+            } => Ok(LogicalPlan::Join {
+                left: Arc::new(inputs[0].clone()),
+                right: Arc::new(inputs[1].clone()),
+                on,
+                join_type,
+                join_constraint,
+                schema,
+            }),
+            _ => utils::from_plan(&plan, &plan.expressions(), &inputs),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "eliminate_outer_join"
+    }
+}
+
+fn schema_columns(schema: &DFSchema) -> HashSet<Column> {
+    schema
+        .fields()
+        .iter()
+        .map(|f| f.qualified_column())
+        .collect()
+}
+
+/// Whether the predicate is false or null when any of the `columns` it uses is null
+fn rejects_nulls(predicate: &Expr, columns: &HashSet<Column>) -> bool {
+    match predicate {
+        Expr::BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+        } => rejects_nulls(left, columns) || rejects_nulls(right, columns),
+        Expr::BinaryExpr {
+            left,
+            op: Operator::Or,
+            right,
+        } => rejects_nulls(left, columns) && rejects_nulls(right, columns),
+        Expr::BinaryExpr {
+            left,
+            op:
+                Operator::Eq
+                | Operator::NotEq
+                | Operator::Lt
+                | Operator::LtEq
+                | Operator::Gt
+                | Operator::GtEq
+                | Operator::Like
+                | Operator::NotLike
+                | Operator::ILike
+                | Operator::NotILike,
+            right,
+        } => is_null_if_null(left, columns) || is_null_if_null(right, columns),
+        Expr::IsNotNull(expr)
+        | Expr::Between { expr, .. }
+        | Expr::InList { expr, .. } => is_null_if_null(expr, columns),
+        Expr::Column(c) => columns.contains(c),
+        _ => false,
+    }
+}
+
+/// Whether the expression is null when any of the `columns` it uses is null
+fn is_null_if_null(expr: &Expr, columns: &HashSet<Column>) -> bool {
+    match expr {
+        Expr::Column(c) => columns.contains(c),
+        Expr::BinaryExpr {
+            left,
+            op:
+                Operator::Plus
+                | Operator::Minus
+                | Operator::Multiply
+                | Operator::Divide
+                | Operator::Modulus,
+            right,
+        } => is_null_if_null(left, columns) || is_null_if_null(right, columns),
+        Expr::Cast { expr, .. } | Expr::TryCast { expr, .. } | Expr::Negative(expr) => {
+            is_null_if_null(expr, columns)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, lit, LogicalPlanBuilder};
+    use crate::test::*;
+
+    fn join(join_type: JoinType, predicate: Expr) -> Result<LogicalPlan> {
+        LogicalPlanBuilder::from(test_table_scan()?)
+            .join(
+                &test_table_scan_with_name("test2")?,
+                join_type,
+                (vec!["a"], vec!["a"]),
+            )?
+            .filter(predicate)?
+            .build()
+    }
+
+    #[test]
+    fn left_join_with_null_rejecting_filter() -> Result<()> {
+        let plan = join(JoinType::Left, col("test2.b").gt(lit(1u32)))?;
+        assert_eq!(optimized_join_type(&plan)?, JoinType::Inner);
+        Ok(())
+    }
+
+    #[test]
+    fn left_join_with_filter_keeping_nulls() -> Result<()> {
+        let plan = join(
+            JoinType::Left,
+            col("test2.b").is_null().or(col("test2.b").gt(lit(1u32))),
+        )?;
+        assert_eq!(optimized_join_type(&plan)?, JoinType::Left);
+        // the filter on the preserved side does not matter
+        let plan = join(JoinType::Left, col("test.b").gt(lit(1u32)))?;
+        assert_eq!(optimized_join_type(&plan)?, JoinType::Left);
+        Ok(())
+    }
+
+    #[test]
+    fn full_join_with_filter_on_one_side() -> Result<()> {
+        let plan = join(
+            JoinType::Full,
+            (col("test.b") + lit(1u32))
+                .eq(lit(3u32))
+                .and(col("test2.c").is_null()),
+        )?;
+        assert_eq!(optimized_join_type(&plan)?, JoinType::Left);
+        let plan = join(
+            JoinType::Full,
+            col("test2.c").in_list(vec![lit(1u32)], true),
+        )?;
+        assert_eq!(optimized_join_type(&plan)?, JoinType::Right);
+        Ok(())
+    }
+
+    fn optimized_join_type(plan: &LogicalPlan) -> Result<JoinType> {
+        let optimized_plan =
+            EliminateOuterJoin::new().optimize(plan, &ExecutionProps::new())?;
+        assert_eq!(optimized_plan.schema(), plan.schema());
+        match optimized_plan.inputs()[0] {
+            LogicalPlan::Join { join_type, .. } => Ok(*join_type),
+            plan => panic!("plan is not a join: {:?}", plan),
+        }
+    }
+}
@@ -20,9 +20,6 @@ use crate::execution::context::ExecutionProps;
 use crate::logical_plan::{and, replace_col, Column, JoinType, LogicalPlan};
 use crate::logical_plan::{DFSchema, Expr};
 use crate::optimizer::optimizer::OptimizerRule;
-use crate::optimizer::projection_push_down::{
-    unalias_columns_in_expr, unalias_required_columns,
-};
 use crate::optimizer::utils;
 use crate::{error::Result, logical_plan::Operator};
 
@@ -84,6 +81,26 @@ fn get_predicates<'a>(
         .unzip()
 }
 
+/// Maps the columns of a union, both qualified and unqualified, to the columns of one of its
+/// inputs
+fn union_columns_to_input(
+    union_schema: &DFSchema,
+    input_schema: &DFSchema,
+) -> Vec<(Column, Column)> {
+    union_schema
+        .fields()
+        .iter()
+        .zip(input_schema.fields())
+        .flat_map(|(union_field, input_field)| {
+            let input_column = input_field.qualified_column();
+            vec![
+                (union_field.qualified_column(), input_column.clone()),
+                (Column::from_name(union_field.name()), input_column),
+            ]
+        })
+        .collect()
+}
+
 /// Optimizes the plan
 fn push_down(state: &State, plan: &LogicalPlan) -> Result<LogicalPlan> {
     let new_inputs = plan
@@ -160,7 +177,7 @@ fn issue_filters(
 }
 
 /// converts "A AND B AND C" => [A, B, C]
-fn split_members<'a>(predicate: &'a Expr, predicates: &mut Vec<&'a Expr>) {
+pub(crate) fn split_members<'a>(predicate: &'a Expr, predicates: &mut Vec<&'a Expr>) {
     match predicate {
         Expr::BinaryExpr {
             right,
@@ -307,6 +324,8 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
             split_members(predicate, &mut predicates);
 
             // Predicates without referencing columns (WHERE FALSE, WHERE 1=1, etc.)
+            // and volatile predicates (WHERE random() < 0.5) that must be evaluated
+            // exactly where they were written
             let mut no_col_predicates = vec![];
 
             predicates
@@ -314,7 +333,7 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                 .try_for_each::<_, Result<()>>(|predicate| {
                     let mut columns: HashSet<Column> = HashSet::new();
                     utils::expr_to_columns(predicate, &mut columns)?;
-                    if columns.is_empty() {
+                    if columns.is_empty() || predicate.is_volatile() {
                         no_col_predicates.push(predicate)
                     } else {
                         // collect the predicate
@@ -349,6 +368,24 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                 projection.insert(field.qualified_name(), expr);
             });
 
+            // filters on volatile expressions can not be pushed through the projection,
+            // as that would evaluate the expression again with a different result
+            let volatile_columns = schema
+                .fields()
+                .iter()
+                .filter(|f| projection[&f.qualified_name()].is_volatile())
+                .map(|f| f.qualified_column())
+                .collect::<HashSet<_>>();
+            let kept_filters = state
+                .filters
+                .iter()
+                .filter(|(_, columns)| !columns.is_disjoint(&volatile_columns))
+                .cloned()
+                .collect::<Vec<_>>();
+            state
+                .filters
+                .retain(|(_, columns)| columns.is_disjoint(&volatile_columns));
+
             // re-write all filters based on this projection
             // E.g. in `Filter: #b\n  Projection: #a > 1 as b`, we can swap them, but the filter must be "#a > 1"
             for (predicate, columns) in state.filters.iter_mut() {
@@ -361,7 +398,13 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
             // optimize inner
             let new_input = optimize(input, state)?;
 
-            utils::from_plan(plan, expr, &[new_input])
+            let plan = utils::from_plan(plan, expr, &[new_input])?;
+            if kept_filters.is_empty() {
+                Ok(plan)
+            } else {
+                let predicates = kept_filters.iter().map(|(p, _)| p).collect::<Vec<_>>();
+                Ok(add_filter(plan, &predicates))
+            }
         }
         LogicalPlan::Aggregate {
             input, aggr_expr, ..
@@ -391,29 +434,32 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
             schema,
             alias,
         } => {
-            // union all is filter-commutable
-            let alias = match alias {
-                None => return push_down(&state, plan),
-                Some(a) => a,
-            };
-
+            // union all is filter-commutable. The columns of the union match the columns
+            // of each input by position, so the filters are rewritten for every input.
             let inputs = inputs
                 .iter()
-                .map(|p| {
-                    let schema = p.schema();
+                .map(|input| {
+                    let columns = union_columns_to_input(schema, input.schema());
+                    let replace_map = columns
+                        .iter()
+                        .map(|(u, i)| (u, i))
+                        .collect::<HashMap<_, _>>();
                     let mut state = state.clone();
                     for (predicate, cols) in &mut state.filters {
-                        *predicate = unalias_columns_in_expr(schema, predicate);
-                        *cols = unalias_required_columns(schema, cols);
+                        *predicate = replace_col(predicate.clone(), &replace_map)?;
+                        *cols = cols
+                            .iter()
+                            .map(|c| replace_map.get(c).map_or(c, |c| *c).clone())
+                            .collect();
                     }
-                    optimize(p, state)
+                    optimize(input, state)
                 })
                 .collect::<Result<Vec<_>>>()?;
 
             Ok(LogicalPlan::Union {
                 inputs,
                 schema: schema.clone(),
-                alias: Some(alias.clone()),
+                alias: alias.clone(),
             })
         }
         LogicalPlan::Limit { input, .. } | LogicalPlan::Skip { input, .. } => {
@@ -623,7 +669,7 @@ mod tests {
     use crate::datasource::datasource::Statistics;
     use crate::datasource::TableProvider;
     use crate::logical_plan::{
-        lit, sum, union_with_alias, DFSchema, Expr, LogicalPlanBuilder, Operator,
+        lit, random, sum, union_with_alias, DFSchema, Expr, LogicalPlanBuilder, Operator,
     };
     use crate::physical_plan::ExecutionPlan;
     use crate::test::*;
@@ -756,6 +802,24 @@ mod tests {
         Ok(())
     }
 
+    /// verifies that a filter on a volatile expression is not pushed below the projection
+    #[test]
+    fn filter_keep_volatile_projection() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .project(vec![col("a"), random().alias("r")])?
+            .filter(col("r").lt(lit(0.5)))?
+            .filter(col("a").eq(lit(1i64)))?
+            .build()?;
+        let expected = "\
+            Filter: #r Lt Float64(0.5)\
+            \n  Projection: #test.a, random() AS r\
+            \n    Filter: #test.a Eq Int64(1)\
+            \n      TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
     fn add(left: Expr, right: Expr) -> Expr {
         Expr::BinaryExpr {
             left: Box::new(left),
@@ -942,9 +1006,9 @@ mod tests {
         // filter appears below Union
         let expected = "\
             Union\
-            \n  Filter: #a Eq Int64(1)\
+            \n  Filter: #test.a Eq Int64(1)\
             \n    TableScan: test projection=None\
-            \n  Filter: #a Eq Int64(1)\
+            \n  Filter: #test.a Eq Int64(1)\
             \n    TableScan: test projection=None";
         assert_optimized_plan_eq(&plan, expected);
         Ok(())
@@ -970,6 +1034,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn union_all_of_different_relations() -> Result<()> {
+        let t2 = LogicalPlanBuilder::from(test_table_scan_with_name("t2")?)
+            .project(vec![col("b").alias("a"), col("a").alias("b"), col("c")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(test_table_scan_with_name("t1")?)
+            .union(t2)?
+            .filter(col("a").eq(lit(1i64)))?
+            .build()?;
+        // the filter refers to the columns of each input
+        let expected = "\
+            Union\
+            \n  Filter: #t1.a Eq Int64(1)\
+            \n    TableScan: t1 projection=None\
+            \n  Projection: #t2.b AS a, #t2.a AS b, #t2.c\
+            \n    Filter: #t2.b Eq Int64(1)\
+            \n      TableScan: t2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
     /// verifies that filters with the same columns are correctly placed
     #[test]
     fn filter_2_breaks_limits() -> Result<()> {
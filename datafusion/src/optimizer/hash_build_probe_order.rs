@@ -86,6 +86,10 @@ fn get_num_rows(logical_plan: &LogicalPlan) -> Option<usize> {
         }
         // the following operators are special cases and not querying data
         LogicalPlan::CreateExternalTable { .. } => None,
+        LogicalPlan::CreateMemoryTable { .. } => None,
+        LogicalPlan::Insert { .. } => None,
+        LogicalPlan::Analyze { .. } => None,
+        LogicalPlan::SetVariable { .. } => None,
         LogicalPlan::Explain { .. } => None,
         // we do not support estimating rows with extensions yet
         LogicalPlan::Extension { .. } => None,
@@ -205,6 +209,10 @@ impl OptimizerRule for HashBuildProbeOrder {
             | LogicalPlan::EmptyRelation { .. }
             | LogicalPlan::Sort { .. }
             | LogicalPlan::CreateExternalTable { .. }
+            | LogicalPlan::CreateMemoryTable { .. }
+            | LogicalPlan::Insert { .. }
+            | LogicalPlan::Analyze { .. }
+            | LogicalPlan::SetVariable { .. }
             | LogicalPlan::Explain { .. }
             | LogicalPlan::Union { .. }
             | LogicalPlan::Extension { .. } => {
@@ -0,0 +1,382 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Infer predicates optimizer rule derives the filters of one side of a join from the
+//! filters of the other side and the join keys
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::{
+    combine_filters, replace_col, Column, DFSchema, Expr, JoinType, LogicalPlan, Operator,
+};
+use crate::optimizer::filter_push_down::split_members;
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+
+/// Optimizer rule that adds the filters implied by the equalities of a join, e.g. in
+///
+/// ```sql
+/// SELECT * FROM orders o JOIN orders_rollup r ON o.ts = r.ts WHERE o.ts > '2021-01-01'
+/// ```
+///
+/// `r.ts > '2021-01-01'` holds for all the joined rows and filtering `orders_rollup`
+/// by it lets the scans of both tables prune their partitions.
+///
+/// Only the filters comparing a join key with literals are derived. They are placed on
+/// top of the other input of the join, [FilterPushDown](super::filter_push_down::FilterPushDown)
+/// moves them further down. The filters of an input are only derived for the other
+/// input when the rows of the latter without a match are dropped by the join.
+pub struct InferPredicates {}
+
+impl InferPredicates {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for InferPredicates {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        _execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        optimize(plan)
+    }
+
+    fn name(&self) -> &str {
+        "infer_predicates"
+    }
+}
+
+fn optimize(plan: &LogicalPlan) -> Result<LogicalPlan> {
+    match plan {
+        LogicalPlan::Filter { predicate, input }
+            if matches!(input.as_ref(), LogicalPlan::Join { .. }) =>
+        {
+            let mut above = vec![];
+            split_members(predicate, &mut above);
+            Ok(LogicalPlan::Filter {
+                predicate: predicate.clone(),
+                input: Arc::new(optimize_join(input, &above)?),
+            })
+        }
+        LogicalPlan::Join { .. } => optimize_join(plan, &[]),
+        _ => {
+            let inputs = plan
+                .inputs()
+                .into_iter()
+                .map(optimize)
+                .collect::<Result<Vec<_>>>()?;
+            utils::from_plan(plan, &plan.expressions(), &inputs)
+        }
+    }
+}
+
+/// Adds the filters derived from the `above` predicates of a filter over the join and
+/// from the filters of its inputs to the inputs of the join
+fn optimize_join(plan: &LogicalPlan, above: &[&Expr]) -> Result<LogicalPlan> {
+    let (left, right, on, join_type, join_constraint, schema) = match plan {
+        LogicalPlan::Join {
+            left,
+            right,
+            on,
+            join_type,
+            join_constraint,
+            schema,
+        } => (left, right, on, join_type, join_constraint, schema),
+        _ => unreachable!("optimize_join only valid for JOIN nodes"),
+    };
+    let left = optimize(left)?;
+    let right = optimize(right)?;
+
+    let left_facts = facts(&left)?;
+    let right_facts = facts(&right)?;
+    let left_columns = schema_columns(left.schema());
+    let right_columns = schema_columns(right.schema());
+
+    // the filters above the join reject the nulls of the padded rows, so they hold for
+    // the rows of both inputs that are joined
+    let mut from_left = vec![];
+    let mut from_right = vec![];
+    for predicate in above {
+        let mut columns = HashSet::new();
+        utils::expr_to_columns(predicate, &mut columns)?;
+        if columns.is_subset(&left_columns) {
+            from_left.push((*predicate).clone());
+        } else if columns.is_subset(&right_columns) {
+            from_right.push((*predicate).clone());
+        }
+    }
+    // the rows of the right input without a match are dropped
+    if matches!(
+        join_type,
+        JoinType::Inner | JoinType::Left | JoinType::Semi | JoinType::Anti
+    ) {
+        from_left.extend(left_facts.iter().cloned());
+    }
+    // the rows of the left input without a match are dropped
+    if matches!(
+        join_type,
+        JoinType::Inner | JoinType::Right | JoinType::Semi
+    ) {
+        from_right.extend(right_facts.iter().cloned());
+    }
+
+    let mut left_to_right = HashMap::<&Column, Vec<&Column>>::new();
+    let mut right_to_left = HashMap::<&Column, Vec<&Column>>::new();
+    for (l, r) in on {
+        left_to_right.entry(l).or_default().push(r);
+        right_to_left.entry(r).or_default().push(l);
+    }
+
+    let new_left = add_filter(left, derive(&from_right, &right_to_left, &left_facts)?);
+    let new_right = add_filter(right, derive(&from_left, &left_to_right, &right_facts)?);
+
+    // keep the schema of the join, the derived filters do not change its columns
+    Ok(LogicalPlan::Join {
+        left: Arc::new(new_left),
+        right: Arc::new(new_right),
+        on: on.clone(),
+        join_type: *join_type,
+        join_constraint: *join_constraint,
+        schema: schema.clone(),
+    })
+}
+
+fn schema_columns(schema: &DFSchema) -> HashSet<Column> {
+    schema
+        .fields()
+        .iter()
+        .map(|f| f.qualified_column())
+        .collect()
+}
+
+fn add_filter(plan: LogicalPlan, predicates: Vec<Expr>) -> LogicalPlan {
+    match combine_filters(&predicates) {
+        Some(predicate) => LogicalPlan::Filter {
+            predicate,
+            input: Arc::new(plan),
+        },
+        None => plan,
+    }
+}
+
+/// The predicates that hold for all the rows produced by `plan`
+fn facts(plan: &LogicalPlan) -> Result<Vec<Expr>> {
+    Ok(match plan {
+        LogicalPlan::Filter { predicate, input } => {
+            let mut predicates = vec![];
+            split_members(predicate, &mut predicates);
+            let mut facts = predicates.into_iter().cloned().collect::<Vec<_>>();
+            facts.extend(self::facts(input)?);
+            facts
+        }
+        LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Skip { input, .. }
+        | LogicalPlan::Repartition { input, .. } => facts(input)?,
+        LogicalPlan::Projection { expr, input, .. } => {
+            // the columns passed through by the projection
+            let columns = expr
+                .iter()
+                .filter_map(|e| match e {
+                    Expr::Column(c) => Some(c.clone()),
+                    _ => None,
+                })
+                .collect::<HashSet<_>>();
+            let mut facts = vec![];
+            for fact in self::facts(input)? {
+                let mut used = HashSet::new();
+                utils::expr_to_columns(&fact, &mut used)?;
+                if used.is_subset(&columns) {
+                    facts.push(fact);
+                }
+            }
+            facts
+        }
+        LogicalPlan::Join {
+            left,
+            right,
+            join_type,
+            ..
+        } => match join_type {
+            JoinType::Inner => {
+                let mut facts = self::facts(left)?;
+                facts.extend(self::facts(right)?);
+                facts
+            }
+            JoinType::Left | JoinType::Semi | JoinType::Anti => facts(left)?,
+            JoinType::Right => facts(right)?,
+            JoinType::Full => vec![],
+        },
+        _ => vec![],
+    })
+}
+
+/// The predicates on the `equal` columns implied by the `predicates`, except the
+/// `known` ones
+fn derive(
+    predicates: &[Expr],
+    equal: &HashMap<&Column, Vec<&Column>>,
+    known: &[Expr],
+) -> Result<Vec<Expr>> {
+    let mut derived = vec![];
+    for predicate in predicates {
+        let column = match compared_column(predicate) {
+            Some(column) => column,
+            None => continue,
+        };
+        for other in equal.get(column).into_iter().flatten() {
+            let mut replace = HashMap::new();
+            replace.insert(column, *other);
+            let predicate = replace_col(predicate.clone(), &replace)?;
+            if !known.contains(&predicate) && !derived.contains(&predicate) {
+                derived.push(predicate);
+            }
+        }
+    }
+    Ok(derived)
+}
+
+/// The column the predicate compares with literals, if that is all it does
+fn compared_column(predicate: &Expr) -> Option<&Column> {
+    match predicate {
+        Expr::BinaryExpr {
+            left,
+            op:
+                Operator::Eq
+                | Operator::NotEq
+                | Operator::Lt
+                | Operator::LtEq
+                | Operator::Gt
+                | Operator::GtEq,
+            right,
+        } => match (left.as_ref(), right.as_ref()) {
+            (Expr::Column(c), Expr::Literal(_)) | (Expr::Literal(_), Expr::Column(c)) => {
+                Some(c)
+            }
+            _ => None,
+        },
+        Expr::Between {
+            expr, low, high, ..
+        } => match (expr.as_ref(), low.as_ref(), high.as_ref()) {
+            (Expr::Column(c), Expr::Literal(_), Expr::Literal(_)) => Some(c),
+            _ => None,
+        },
+        Expr::InList { expr, list, .. } => match expr.as_ref() {
+            Expr::Column(c) if list.iter().all(|e| matches!(e, Expr::Literal(_))) => {
+                Some(c)
+            }
+            _ => None,
+        },
+        Expr::IsNotNull(expr) => match expr.as_ref() {
+            Expr::Column(c) => Some(c),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, lit, LogicalPlanBuilder};
+    use crate::test::*;
+
+    fn join(
+        join_type: JoinType,
+        left_predicate: Option<Expr>,
+        right_predicate: Option<Expr>,
+    ) -> Result<LogicalPlanBuilder> {
+        let filtered = |plan: LogicalPlan, predicate: Option<Expr>| match predicate {
+            Some(predicate) => LogicalPlanBuilder::from(plan)
+                .filter(predicate)
+                .and_then(|builder| builder.build()),
+            None => Ok(plan),
+        };
+        let left = filtered(test_table_scan()?, left_predicate)?;
+        let right = filtered(test_table_scan_with_name("test2")?, right_predicate)?;
+        LogicalPlanBuilder::from(left).join(&right, join_type, (vec!["a"], vec!["a"]))
+    }
+
+    #[test]
+    fn derive_filter_of_other_input() -> Result<()> {
+        let plan = join(JoinType::Inner, Some(col("a").gt(lit(1u32))), None)?.build()?;
+
+        let expected = "Join: #test.a = #test2.a\
+        \n  Filter: #test.a Gt UInt32(1)\
+        \n    TableScan: test projection=None\
+        \n  Filter: #test2.a Gt UInt32(1)\
+        \n    TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_rows_of_preserved_input() -> Result<()> {
+        let plan = join(
+            JoinType::Left,
+            Some(col("b").gt(lit(1u32))),
+            Some(col("a").gt(lit(1u32))),
+        )?
+        .build()?;
+
+        let expected = "Join: #test.a = #test2.a\
+        \n  Filter: #test.b Gt UInt32(1)\
+        \n    TableScan: test projection=None\
+        \n  Filter: #test2.a Gt UInt32(1)\
+        \n    TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn derive_filter_above_join() -> Result<()> {
+        let plan = join(JoinType::Left, None, None)?
+            .filter(
+                col("test2.a")
+                    .lt(lit(5u32))
+                    .and(col("test2.b").eq(col("test.b"))),
+            )?
+            .build()?;
+
+        let expected = "Filter: #test2.a Lt UInt32(5) And #test2.b Eq #test.b\
+        \n  Join: #test.a = #test2.a\
+        \n    Filter: #test.a Lt UInt32(5)\
+        \n      TableScan: test projection=None\
+        \n    TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = InferPredicates::new()
+            .optimize(plan, &ExecutionProps::new())
+            .expect("failed to optimize plan");
+        assert_eq!(format!("{:?}", optimized_plan), expected);
+        assert_eq!(optimized_plan.schema(), plan.schema());
+        // the derived filters are only added once
+        let optimized_again = InferPredicates::new()
+            .optimize(&optimized_plan, &ExecutionProps::new())
+            .expect("failed to optimize plan");
+        assert_eq!(format!("{:?}", optimized_again), expected);
+    }
+}
@@ -0,0 +1,429 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Merge aggregates optimizer rule collapses the aggregations of aggregated derived
+//! tables and the aggregations of inputs without duplicate group keys
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::cube_ext::alias::LogicalAlias;
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::{
+    exprlist_to_fields, Column, DFSchemaRef, Expr, LogicalPlan, LogicalPlanBuilder,
+};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+use crate::physical_plan::aggregates::AggregateFunction;
+
+/// Optimizer rule that simplifies an aggregation of an aggregation:
+///
+/// * when the outer group keys are a subset of the inner ones and the outer aggregates
+///   can be computed from the input of the inner aggregation, e.g. `SUM` of a `SUM`
+///   or of a `COUNT`, `MAX` of a `MAX`, the two aggregations are merged into one,
+/// * when the input has at most one row per outer group, e.g. as it is grouped by the
+///   same keys, the outer aggregation is replaced by a projection if its aggregates are
+///   `MIN`, `MAX`, `SUM` or `ANY_VALUE`.
+///
+/// The aggregations may be separated by the projections and aliases of derived tables
+/// that only rename columns.
+pub struct MergeAggregates {}
+
+impl MergeAggregates {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for MergeAggregates {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        let inputs = plan
+            .inputs()
+            .into_iter()
+            .map(|input| self.optimize(input, execution_props))
+            .collect::<Result<Vec<_>>>()?;
+        let plan = utils::from_plan(plan, &plan.expressions(), &inputs)?;
+
+        if let LogicalPlan::Aggregate {
+            input,
+            group_expr,
+            aggr_expr,
+            schema,
+        } = &plan
+        {
+            if let Some(projection) =
+                aggregate_to_projection(input, group_expr, aggr_expr, schema)?
+            {
+                return Ok(projection);
+            }
+            if let Some(merged) = merge_aggregates(input, group_expr, aggr_expr, schema)?
+            {
+                return Ok(merged);
+            }
+        }
+        Ok(plan)
+    }
+
+    fn name(&self) -> &str {
+        "merge_aggregates"
+    }
+}
+
+/// Aliases `expr` to `name` unless it already has that name
+fn with_name(expr: Expr, name: &str, schema: &DFSchemaRef) -> Result<Expr> {
+    Ok(if expr.name(schema)? == name {
+        expr
+    } else {
+        expr.alias(name)
+    })
+}
+
+/// The projection computing the aggregation when its input has at most one row per
+/// group
+fn aggregate_to_projection(
+    input: &Arc<LogicalPlan>,
+    group_expr: &[Expr],
+    aggr_expr: &[Expr],
+    schema: &DFSchemaRef,
+) -> Result<Option<LogicalPlan>> {
+    // an aggregation without groups produces a row for an empty input
+    if group_expr.is_empty() {
+        return Ok(None);
+    }
+    let mut group_columns = HashSet::new();
+    for e in group_expr {
+        match e {
+            Expr::Column(c) => group_columns.insert(c.clone()),
+            _ => return Ok(None),
+        };
+    }
+    if !utils::is_unique(input, &group_columns) {
+        return Ok(None);
+    }
+
+    let mut expr = group_expr.to_vec();
+    for (e, field) in aggr_expr.iter().zip(&schema.fields()[group_expr.len()..]) {
+        let arg = match e {
+            Expr::AggregateFunction {
+                fun:
+                    AggregateFunction::Min
+                    | AggregateFunction::Max
+                    | AggregateFunction::Sum
+                    | AggregateFunction::AnyValue,
+                args,
+                filter: None,
+                ..
+            } if args.len() == 1 => args[0].clone(),
+            _ => return Ok(None),
+        };
+        let arg = if &arg.get_type(input.schema())? == field.data_type() {
+            arg
+        } else {
+            Expr::Cast {
+                expr: Box::new(arg),
+                data_type: field.data_type().clone(),
+            }
+        };
+        expr.push(with_name(arg, field.name(), input.schema())?);
+    }
+
+    Ok(Some(LogicalPlan::Projection {
+        expr,
+        input: input.clone(),
+        schema: schema.clone(),
+    }))
+}
+
+/// The aggregation below `plan` through nodes that only rename columns, with the
+/// columns of the aggregation by the output columns of `plan`
+fn renamed_aggregate(
+    plan: &LogicalPlan,
+) -> Option<(&LogicalPlan, HashMap<Column, Column>)> {
+    match plan {
+        LogicalPlan::Aggregate { schema, .. } => Some((
+            plan,
+            schema
+                .fields()
+                .iter()
+                .map(|f| (f.qualified_column(), f.qualified_column()))
+                .collect(),
+        )),
+        LogicalPlan::Projection {
+            expr,
+            input,
+            schema,
+        } => {
+            let (aggregate, columns) = renamed_aggregate(input)?;
+            let mut renamed = HashMap::new();
+            for (field, e) in schema.fields().iter().zip(expr) {
+                let column = match e {
+                    Expr::Column(c) => c,
+                    Expr::Alias(e, _) => match e.as_ref() {
+                        Expr::Column(c) => c,
+                        _ => return None,
+                    },
+                    _ => return None,
+                };
+                renamed.insert(field.qualified_column(), columns.get(column)?.clone());
+            }
+            Some((aggregate, renamed))
+        }
+        LogicalPlan::Extension { node } => {
+            let alias = node.as_any().downcast_ref::<LogicalAlias>()?;
+            let (aggregate, columns) = renamed_aggregate(&alias.input)?;
+            let mut renamed = HashMap::new();
+            for (field, input_field) in alias
+                .schema
+                .fields()
+                .iter()
+                .zip(alias.input.schema().fields())
+            {
+                let column = columns.get(&input_field.qualified_column())?;
+                renamed.insert(field.qualified_column(), column.clone());
+            }
+            Some((aggregate, renamed))
+        }
+        _ => None,
+    }
+}
+
+/// The aggregation of the input of the inner aggregation computing the outer one
+fn merge_aggregates(
+    input: &LogicalPlan,
+    group_expr: &[Expr],
+    aggr_expr: &[Expr],
+    schema: &DFSchemaRef,
+) -> Result<Option<LogicalPlan>> {
+    let (inner, columns) = match renamed_aggregate(input) {
+        Some(renamed) => renamed,
+        None => return Ok(None),
+    };
+    let (inner_input, inner_group_expr, inner_aggr_expr, inner_schema) = match inner {
+        LogicalPlan::Aggregate {
+            input,
+            group_expr,
+            aggr_expr,
+            schema,
+        } => (input, group_expr, aggr_expr, schema),
+        _ => return Ok(None),
+    };
+    // the expression of the inner aggregation computing the column, with whether it
+    // is a group key
+    let inner_expr = |e: &Expr| -> Option<(Expr, bool)> {
+        let column = match e {
+            Expr::Column(c) => columns.get(c)?,
+            _ => return None,
+        };
+        let index = inner_schema.index_of_column(column).ok()?;
+        Some(match index.checked_sub(inner_group_expr.len()) {
+            None => (inner_group_expr[index].clone(), true),
+            Some(index) => (inner_aggr_expr[index].clone(), false),
+        })
+    };
+
+    let mut new_group_expr = vec![];
+    for e in group_expr {
+        match inner_expr(e) {
+            Some((e, true)) => new_group_expr.push(e),
+            _ => return Ok(None),
+        }
+    }
+
+    let mut new_aggr_expr = vec![];
+    for e in aggr_expr {
+        let (fun, arg) = match e {
+            Expr::AggregateFunction {
+                fun,
+                args,
+                distinct: false,
+                filter: None,
+                ..
+            } if args.len() == 1 => (fun, &args[0]),
+            _ => return Ok(None),
+        };
+        let new_expr = match inner_expr(arg) {
+            Some((group, true)) => match fun {
+                AggregateFunction::Min
+                | AggregateFunction::Max
+                | AggregateFunction::AnyValue => Expr::AggregateFunction {
+                    fun: fun.clone(),
+                    args: vec![group],
+                    distinct: false,
+                    ignore_nulls: false,
+                    order_by: vec![],
+                    filter: None,
+                },
+                _ => return Ok(None),
+            },
+            Some((inner, false)) => {
+                let inner_fun = match &inner {
+                    Expr::AggregateFunction {
+                        fun,
+                        distinct: false,
+                        ..
+                    } => fun.clone(),
+                    _ => return Ok(None),
+                };
+                match (fun, inner_fun) {
+                    (AggregateFunction::Sum, AggregateFunction::Sum)
+                    | (AggregateFunction::Min, AggregateFunction::Min)
+                    | (AggregateFunction::Max, AggregateFunction::Max)
+                    | (AggregateFunction::AnyValue, AggregateFunction::AnyValue) => inner,
+                    // the sum of no counts is null, not 0
+                    (AggregateFunction::Sum, AggregateFunction::Count)
+                        if !group_expr.is_empty() || inner_group_expr.is_empty() =>
+                    {
+                        inner
+                    }
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+        new_aggr_expr.push(new_expr);
+    }
+
+    let aggregate = match LogicalPlanBuilder::from(inner_input.as_ref().clone())
+        .aggregate(new_group_expr, new_aggr_expr)
+        .and_then(|b| b.build())
+    {
+        Ok(aggregate) => aggregate,
+        // e.g. the same aggregate computed twice
+        Err(_) => return Ok(None),
+    };
+
+    // rename the columns of the merged aggregation to the ones of the outer one
+    let merged_schema = aggregate.schema().clone();
+    let expr = merged_schema
+        .fields()
+        .iter()
+        .zip(schema.fields())
+        .map(|(merged, outer)| {
+            let column = Expr::Column(merged.qualified_column());
+            if merged.qualified_column() == outer.qualified_column() {
+                column
+            } else {
+                column.alias(outer.name())
+            }
+        })
+        .collect::<Vec<_>>();
+    let fields = exprlist_to_fields(&expr, &merged_schema)?;
+    let same_schema = fields.len() == schema.fields().len()
+        && fields.iter().zip(schema.fields()).all(|(merged, outer)| {
+            merged.qualified_column() == outer.qualified_column()
+                && merged.data_type() == outer.data_type()
+        });
+    if !same_schema {
+        return Ok(None);
+    }
+
+    Ok(Some(LogicalPlan::Projection {
+        expr,
+        input: Arc::new(aggregate),
+        schema: schema.clone(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{any_value, col, count, max, min, sum};
+    use crate::test::*;
+
+    #[test]
+    fn merge_aggregate_of_aggregate() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .aggregate(
+                vec![col("a"), col("b")],
+                vec![sum(col("c")), count(col("c")), max(col("c"))],
+            )?
+            .project(vec![
+                col("a"),
+                col("SUM(test.c)").alias("s"),
+                col("COUNT(test.c)").alias("n"),
+                col("MAX(test.c)").alias("m"),
+            ])?
+            .aggregate(
+                vec![col("a")],
+                vec![sum(col("s")), sum(col("n")), max(col("m"))],
+            )?
+            .build()?;
+
+        let expected = "Projection: #test.a, #SUM(test.c) AS SUM(s), #COUNT(test.c) AS SUM(n), #MAX(test.c) AS MAX(m)\
+        \n  Aggregate: groupBy=[[#test.a]], aggr=[[SUM(#test.c), COUNT(#test.c), MAX(#test.c)]]\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_any_value_of_any_value() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .aggregate(vec![col("a"), col("b")], vec![any_value(col("c"))])?
+            .project(vec![col("a"), col("ANY_VALUE(test.c)").alias("v")])?
+            .aggregate(vec![col("a")], vec![any_value(col("v"))])?
+            .build()?;
+
+        let expected = "Projection: #test.a, #ANY_VALUE(test.c) AS ANY_VALUE(v)\
+        \n  Aggregate: groupBy=[[#test.a]], aggr=[[ANY_VALUE(#test.c)]]\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_aggregate_of_other_functions() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .aggregate(vec![col("a"), col("b")], vec![sum(col("c"))])?
+            .aggregate(vec![col("a")], vec![min(col("SUM(test.c)"))])?
+            .build()?;
+
+        let expected = "Aggregate: groupBy=[[#test.a]], aggr=[[MIN(#SUM(test.c))]]\
+        \n  Aggregate: groupBy=[[#test.a, #test.b]], aggr=[[SUM(#test.c)]]\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn replace_aggregate_of_unique_keys() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .aggregate(vec![col("a")], vec![max(col("b"))])?
+            .aggregate(vec![col("a")], vec![min(col("MAX(test.b)"))])?
+            .build()?;
+
+        let expected = "Projection: #test.a, #MAX(test.b) AS MIN(MAX(test.b))\
+        \n  Aggregate: groupBy=[[#test.a]], aggr=[[MAX(#test.b)]]\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = MergeAggregates::new()
+            .optimize(plan, &ExecutionProps::new())
+            .expect("failed to optimize plan");
+        assert_eq!(format!("{:?}", optimized_plan), expected);
+        assert_eq!(optimized_plan.schema(), plan.schema());
+    }
+}
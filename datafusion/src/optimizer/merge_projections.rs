@@ -0,0 +1,263 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Merge projections optimizer rule removes the projections that pass their input
+//! through and merges adjacent projections into one
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::{exprlist_to_fields, Column, DFSchemaRef, Expr, LogicalPlan};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+
+/// Optimizer rule that removes the projections returning the columns of their input
+/// unchanged and replaces a projection of a projection by a single projection, e.g.
+///
+/// ```text
+/// Projection: #b Plus Int64(1)
+///   Projection: #test.a AS b
+///     Projection: #test.a, #test.c
+///       TableScan: test projection=Some([0, 2])
+/// ```
+///
+/// becomes
+///
+/// ```text
+/// Projection: #test.a Plus Int64(1) AS b Plus Int64(1)
+///   TableScan: test projection=Some([0, 2])
+/// ```
+///
+/// Projections are not merged when that would evaluate an expression of the inner
+/// projection more than once.
+pub struct MergeProjections {}
+
+impl MergeProjections {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for MergeProjections {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        let inputs = plan
+            .inputs()
+            .into_iter()
+            .map(|input| self.optimize(input, execution_props))
+            .collect::<Result<Vec<_>>>()?;
+        let plan = utils::from_plan(plan, &plan.expressions(), &inputs)?;
+        simplify_projection(plan)
+    }
+
+    fn name(&self) -> &str {
+        "merge_projections"
+    }
+}
+
+/// Removes or merges `plan` when it is a projection, the inputs are already simplified
+fn simplify_projection(plan: LogicalPlan) -> Result<LogicalPlan> {
+    let (expr, input, schema) = match &plan {
+        LogicalPlan::Projection {
+            expr,
+            input,
+            schema,
+        } => (expr, input, schema),
+        _ => return Ok(plan),
+    };
+
+    if is_pass_through(expr, input, schema) {
+        return Ok(input.as_ref().clone());
+    }
+
+    if let LogicalPlan::Projection {
+        expr: inner_expr,
+        input: inner_input,
+        schema: inner_schema,
+    } = input.as_ref()
+    {
+        if let Some(merged) = merge(expr, schema, inner_expr, inner_input, inner_schema)?
+        {
+            return simplify_projection(merged);
+        }
+    }
+    Ok(plan)
+}
+
+/// Whether the projection returns the columns of its input in their order
+fn is_pass_through(expr: &[Expr], input: &LogicalPlan, schema: &DFSchemaRef) -> bool {
+    let input_fields = input.schema().fields();
+    expr.len() == input_fields.len()
+        && expr.iter().zip(input_fields).all(|(e, f)| match e {
+            Expr::Column(c) => c == &f.qualified_column(),
+            _ => false,
+        })
+        && schema.fields() == input_fields
+}
+
+/// The projection of `inner_input` computing the outer projection, if any
+fn merge(
+    expr: &[Expr],
+    schema: &DFSchemaRef,
+    inner_expr: &[Expr],
+    inner_input: &Arc<LogicalPlan>,
+    inner_schema: &DFSchemaRef,
+) -> Result<Option<LogicalPlan>> {
+    // the inner expressions without their aliases by the columns they produce
+    let inner = inner_schema
+        .fields()
+        .iter()
+        .zip(inner_expr)
+        .map(|(f, e)| {
+            let e = match e {
+                Expr::Alias(e, _) => e.as_ref().clone(),
+                e => e.clone(),
+            };
+            (f.qualified_column(), e)
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut uses = HashMap::new();
+    let mut merged_expr = Vec::with_capacity(expr.len());
+    for (e, field) in expr.iter().zip(schema.fields()) {
+        let merged = match rewrite(e, &inner, &mut uses)? {
+            Some(merged) => merged,
+            None => return Ok(None),
+        };
+        // keep the names of the columns of the outer projection
+        let merged = if &merged.name(inner_input.schema())? == field.name() {
+            merged
+        } else {
+            match merged {
+                Expr::Alias(e, _) => Expr::Alias(e, field.name().clone()),
+                e => Expr::Alias(Box::new(e), field.name().clone()),
+            }
+        };
+        merged_expr.push(merged);
+    }
+
+    // computing an expression once in the inner projection is cheaper than evaluating
+    // it again for every use
+    let computed_again = uses.iter().any(|(column, count)| {
+        *count > 1 && !matches!(inner[*column], Expr::Column(_) | Expr::Literal(_))
+    });
+    if computed_again {
+        return Ok(None);
+    }
+
+    let fields = exprlist_to_fields(&merged_expr, inner_input.schema())?;
+    let same_schema = fields.len() == schema.fields().len()
+        && fields.iter().zip(schema.fields()).all(|(merged, outer)| {
+            merged.qualified_column() == outer.qualified_column()
+                && merged.data_type() == outer.data_type()
+        });
+    if !same_schema {
+        return Ok(None);
+    }
+
+    Ok(Some(LogicalPlan::Projection {
+        expr: merged_expr,
+        input: inner_input.clone(),
+        schema: schema.clone(),
+    }))
+}
+
+/// Replaces the columns of `expr` by the inner expressions computing them, counting
+/// their uses. `None` when a column is not produced by the inner projection.
+fn rewrite<'a>(
+    expr: &Expr,
+    inner: &'a HashMap<Column, Expr>,
+    uses: &mut HashMap<&'a Column, usize>,
+) -> Result<Option<Expr>> {
+    if let Expr::Column(c) = expr {
+        return Ok(inner.get_key_value(c).map(|(column, e)| {
+            *uses.entry(column).or_default() += 1;
+            e.clone()
+        }));
+    }
+
+    let mut expressions = vec![];
+    for e in utils::expr_sub_expressions(expr)? {
+        match rewrite(&e, inner, uses)? {
+            Some(e) => expressions.push(e),
+            None => return Ok(None),
+        }
+    }
+    utils::rewrite_expression(expr, &expressions).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, lit, LogicalPlanBuilder};
+    use crate::test::*;
+
+    #[test]
+    fn remove_pass_through_projection() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .project(vec![col("a"), col("b"), col("c")])?
+            .filter(col("a").eq(lit(1u32)))?
+            .build()?;
+
+        let expected = "Filter: #test.a Eq UInt32(1)\
+        \n  TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_adjacent_projections() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .project(vec![col("a"), col("c")])?
+            .project(vec![col("a").alias("b"), col("c")])?
+            .project(vec![col("c"), col("b") + lit(1u32)])?
+            .build()?;
+
+        let expected = "Projection: #test.c, #test.a Plus UInt32(1) AS b Plus UInt32(1)\
+        \n  TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_expressions_used_twice() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(test_table_scan()?)
+            .project(vec![(col("a") + col("b")).alias("s")])?
+            .project(vec![(col("s") * col("s")).alias("square")])?
+            .build()?;
+
+        let expected = "Projection: #s Multiply #s AS square\
+        \n  Projection: #test.a Plus #test.b AS s\
+        \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = MergeProjections::new()
+            .optimize(plan, &ExecutionProps::new())
+            .expect("failed to optimize plan");
+        assert_eq!(format!("{:?}", optimized_plan), expected);
+        assert_eq!(optimized_plan.schema(), plan.schema());
+    }
+}
@@ -19,12 +19,21 @@
 //! some simple rules to a logical plan, such as "Projection Push Down" and "Type Coercion".
 
 pub mod aggregate_statistics;
+pub mod column_pruning;
 pub mod constant_folding;
+pub mod eliminate_filter;
+pub mod eliminate_join;
 pub mod eliminate_limit;
+pub mod eliminate_outer_join;
 pub mod filter_push_down;
 pub mod hash_build_probe_order;
+pub mod infer_predicates;
 pub mod limit_push_down;
+pub mod merge_aggregates;
+pub mod merge_projections;
+pub mod nested_field_push_down;
 pub mod optimizer;
 pub mod projection_push_down;
+pub mod range_analysis;
 pub mod simplify_expressions;
 pub mod utils;
@@ -0,0 +1,188 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Nested field push down optimizer rule replaces the fields of struct columns by the
+//! columns of the shredded fields that table providers expose for them
+
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::{
+    Column, DFSchemaRef, Expr, ExprRewriter, GetFieldAccess, LogicalPlan,
+};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+
+/// Optimizer rule that replaces a field nested in a struct column, the field `b` of
+/// the field `a` of `#t.s`, by the column `s.a.b` of the input when there is one, as
+/// in a [ParquetTable] with shredded fields. Projection push down then reads this
+/// column rather than the whole struct column.
+///
+/// Only the expressions of projections and filters are rewritten, as the names of
+/// the expressions of other plans are part of their schema. Projections keep these
+/// names with an alias.
+///
+/// [ParquetTable]: crate::datasource::parquet::ParquetTable
+pub struct NestedFieldPushDown {}
+
+impl NestedFieldPushDown {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for NestedFieldPushDown {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        let inputs = plan
+            .inputs()
+            .into_iter()
+            .map(|input| self.optimize(input, execution_props))
+            .collect::<Result<Vec<_>>>()?;
+        let expr = match plan {
+            LogicalPlan::Projection { expr, input, .. } => {
+                let mut rewriter = ShreddedFieldRewriter {
+                    schema: input.schema(),
+                };
+                expr.iter()
+                    .map(|e| {
+                        let rewritten = e.clone().rewrite(&mut rewriter)?;
+                        if &rewritten == e {
+                            return Ok(rewritten);
+                        }
+                        // keep the name of the projected expression
+                        let name = e.name(input.schema())?;
+                        Ok(match rewritten {
+                            Expr::Alias(..) => rewritten,
+                            rewritten => rewritten.alias(&name),
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            }
+            LogicalPlan::Filter { predicate, input } => {
+                let mut rewriter = ShreddedFieldRewriter {
+                    schema: input.schema(),
+                };
+                vec![predicate.clone().rewrite(&mut rewriter)?]
+            }
+            _ => plan.expressions(),
+        };
+        utils::from_plan(plan, &expr, &inputs)
+    }
+
+    fn name(&self) -> &str {
+        "nested_field_push_down"
+    }
+}
+
+/// Replaces the fields of struct columns by the columns of `schema` for these fields
+struct ShreddedFieldRewriter<'a> {
+    schema: &'a DFSchemaRef,
+}
+
+impl<'a> ExprRewriter for ShreddedFieldRewriter<'a> {
+    fn mutate(&mut self, expr: Expr) -> Result<Expr> {
+        let column = match shredded_column(&expr) {
+            Some(column) => column,
+            None => return Ok(expr),
+        };
+        match self.schema.field_from_column(&column) {
+            Ok(_) => Ok(Expr::Column(column)),
+            Err(_) => Ok(expr),
+        }
+    }
+}
+
+/// The column of a shredded field for `expr` when it is a field nested in a struct
+/// column, `#t.s.a.b` for `#t.s` followed by the fields `a` and `b`
+fn shredded_column(expr: &Expr) -> Option<Column> {
+    match expr {
+        Expr::GetIndexedField {
+            expr,
+            field: GetFieldAccess::NamedStructField { name },
+        } => {
+            let parent = match expr.as_ref() {
+                Expr::Column(column) => column.clone(),
+                expr => shredded_column(expr)?,
+            };
+            Some(Column {
+                relation: parent.relation,
+                name: format!("{}.{}", parent.name, name),
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, lit, LogicalPlanBuilder};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    fn optimize(plan: &LogicalPlan) -> Result<LogicalPlan> {
+        NestedFieldPushDown::new().optimize(plan, &ExecutionProps::new())
+    }
+
+    fn nested_scan(shredded: bool) -> Result<LogicalPlanBuilder> {
+        let a = DataType::Struct(vec![Field::new("b", DataType::Int32, true)]);
+        let s = DataType::Struct(vec![Field::new("a", a, true)]);
+        let mut fields = vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("s", s, true),
+        ];
+        if shredded {
+            fields.push(Field::new("s.a.b", DataType::Int32, true));
+        }
+        LogicalPlanBuilder::scan_empty(Some("t"), &Schema::new(fields), None)
+    }
+
+    #[test]
+    fn rewrite_shredded_fields() -> Result<()> {
+        let field = col("s").field("a").field("b");
+        let plan = nested_scan(true)?
+            .filter(field.clone().gt(lit(1)))?
+            .project(vec![col("id"), field])?
+            .build()?;
+        let expected = "Projection: #t.id, #t.s.a.b AS t.s.a.b\
+        \n  Filter: #t.s.a.b Gt Int32(1)\
+        \n    TableScan: t projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(optimize(&plan)?.schema(), plan.schema());
+        Ok(())
+    }
+
+    #[test]
+    fn keep_fields_without_shredded_columns() -> Result<()> {
+        let plan = nested_scan(false)?
+            .project(vec![col("s").field("a").field("b"), col("s").field("a")])?
+            .build()?;
+        let expected = "Projection: #t.s.a.b, #t.s.a\
+        \n  TableScan: t projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let optimized_plan = optimize(plan).expect("failed to optimize plan");
+        let formatted_plan = format!("{:?}", optimized_plan);
+        assert_eq!(formatted_plan, expected);
+    }
+}
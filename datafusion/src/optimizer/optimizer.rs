@@ -17,6 +17,9 @@
 
 //! Query optimizer traits
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use crate::error::Result;
 use crate::execution::context::ExecutionProps;
 use crate::logical_plan::LogicalPlan;
@@ -35,3 +38,90 @@ pub trait OptimizerRule {
     /// A human readable name for this optimizer rule
     fn name(&self) -> &str;
 }
+
+/// A named group of [`OptimizerRule`]s applied in order. A fixpoint batch is applied
+/// again while it changes the plan, at most
+/// [`optimizer_max_passes`](crate::execution::context::ExecutionConfig::optimizer_max_passes)
+/// times, other batches are applied once. Each batch can be enabled or disabled with
+/// the configuration option named by [`OptimizerBatch::enabled_option`].
+#[derive(Clone)]
+pub struct OptimizerBatch {
+    name: String,
+    rules: Vec<Arc<dyn OptimizerRule + Send + Sync>>,
+    fixpoint: bool,
+    enabled_by_default: bool,
+}
+
+impl OptimizerBatch {
+    /// Create a batch applying `rules` once
+    pub fn new(
+        name: impl Into<String>,
+        rules: Vec<Arc<dyn OptimizerRule + Send + Sync>>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            rules,
+            fixpoint: false,
+            enabled_by_default: true,
+        }
+    }
+
+    /// Apply the rules until they no longer change the plan
+    pub fn with_fixpoint(mut self, fixpoint: bool) -> Self {
+        self.fixpoint = fixpoint;
+        self
+    }
+
+    /// Whether the batch is applied when its option was not set
+    pub fn with_enabled_by_default(mut self, enabled: bool) -> Self {
+        self.enabled_by_default = enabled;
+        self
+    }
+
+    /// The name of the batch
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The rules of the batch, in the order they are applied
+    pub fn rules(&self) -> &[Arc<dyn OptimizerRule + Send + Sync>] {
+        &self.rules
+    }
+
+    /// Whether the rules are applied until they no longer change the plan
+    pub fn fixpoint(&self) -> bool {
+        self.fixpoint
+    }
+
+    /// Whether the batch is applied when its option was not set
+    pub fn enabled_by_default(&self) -> bool {
+        self.enabled_by_default
+    }
+
+    /// Appends a rule to the batch
+    pub fn add_rule(&mut self, rule: Arc<dyn OptimizerRule + Send + Sync>) {
+        self.rules.push(rule);
+    }
+
+    /// Key of the boolean configuration option enabling the batch,
+    /// e.g. `datafusion.optimizer.enable_batch_rewrite`
+    pub fn enabled_option(&self) -> String {
+        format!(
+            "datafusion.optimizer.enable_batch_{}",
+            self.name.to_lowercase()
+        )
+    }
+}
+
+/// Outcome of a single [`OptimizerRule`] applied to a plan, see
+/// [`ExecutionContext::optimize_with_metrics`](crate::execution::context::ExecutionContext::optimize_with_metrics)
+#[derive(Debug, Clone)]
+pub struct OptimizerRuleMetrics {
+    /// The name of the rule
+    pub name: String,
+    /// Time spent in the rule
+    pub elapsed: Duration,
+    /// The error returned by the rule when it was skipped, see
+    /// [`ExecutionConfig::with_skip_failed_rules`](crate::execution::context::ExecutionConfig::with_skip_failed_rules)
+    pub error: Option<String>,
+}
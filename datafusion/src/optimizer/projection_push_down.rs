@@ -315,6 +315,9 @@ fn optimize_plan(
                     fun: aggregates::AggregateFunction::Count,
                     args: vec![lit(1_u8)],
                     distinct: false,
+                    ignore_nulls: false,
+                    order_by: vec![],
+                    filter: None,
                 }]
             } else {
                 new_aggr_expr
@@ -396,6 +399,10 @@ fn optimize_plan(
         | LogicalPlan::EmptyRelation { .. }
         | LogicalPlan::Sort { .. }
         | LogicalPlan::CreateExternalTable { .. }
+        | LogicalPlan::CreateMemoryTable { .. }
+        | LogicalPlan::Insert { .. }
+        | LogicalPlan::Analyze { .. }
+        | LogicalPlan::SetVariable { .. }
         | LogicalPlan::CrossJoin { .. }
         | LogicalPlan::Extension { .. } => {
             match plan {
@@ -407,6 +414,14 @@ fn optimize_plan(
                         );
                     }
                 }
+                LogicalPlan::Insert { .. } | LogicalPlan::CreateMemoryTable { .. } => {
+                    // all columns of the input are written to the table
+                    for input in plan.inputs() {
+                        new_required_columns.extend(
+                            input.schema().fields().iter().map(|f| f.qualified_column()),
+                        );
+                    }
+                }
                 _ => {}
             }
 
@@ -434,22 +449,6 @@ fn optimize_plan(
     }
 }
 
-pub(crate) fn unalias_columns_in_expr(unaliased_schema: &DFSchema, e: &Expr) -> Expr {
-    if let Expr::Column(Column { relation: _, name }) = e {
-        let f = match unaliased_schema.field_with_unqualified_name(name) {
-            Err(_) => return e.clone(), // Resolve errors must be handled elsewhere.
-            Ok(f) => f,
-        };
-        return Expr::Column(f.qualified_column());
-    }
-
-    let mut cs = utils::expr_sub_expressions(e).unwrap();
-    for c in &mut cs {
-        *c = unalias_columns_in_expr(unaliased_schema, &c);
-    }
-    utils::rewrite_expression(e, &cs).unwrap()
-}
-
 pub(crate) fn unalias_required_columns(
     unaliased_schema: &DFSchema,
     required_columns: &HashSet<Column>,
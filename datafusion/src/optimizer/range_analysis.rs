@@ -0,0 +1,597 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Range analysis computes the bounds of the values of expressions from the bounds of
+//! the columns they use, e.g. their min/max statistics
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use arrow::datatypes::{DataType, TimeUnit};
+
+use crate::datasource::datasource::ColumnStatistics;
+use crate::error::Result;
+use crate::logical_plan::{Column, DFSchema, Expr, Operator};
+use crate::physical_plan::datetime_expressions;
+use crate::physical_plan::functions::BuiltinScalarFunction;
+use crate::physical_plan::ColumnarValue;
+use crate::scalar::ScalarValue;
+
+/// The inclusive bounds of the non-null values of an expression, `None` when a side is
+/// unbounded. The bounds of a boolean expression tell whether it can be false or true.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval {
+    /// The smallest value
+    pub lower: Option<ScalarValue>,
+    /// The largest value
+    pub upper: Option<ScalarValue>,
+}
+
+impl Interval {
+    /// The interval between `lower` and `upper`, null values are unbounded
+    pub fn new(lower: Option<ScalarValue>, upper: Option<ScalarValue>) -> Self {
+        Self {
+            lower: lower.filter(|v| !v.is_null()),
+            upper: upper.filter(|v| !v.is_null()),
+        }
+    }
+
+    /// The interval of any value
+    pub fn unbounded() -> Self {
+        Self::new(None, None)
+    }
+
+    /// The interval of a single value
+    pub fn point(value: ScalarValue) -> Self {
+        Self::new(Some(value.clone()), Some(value))
+    }
+
+    /// The interval of the values of a column with the given statistics
+    pub fn from_statistics(statistics: &ColumnStatistics) -> Self {
+        Self::new(statistics.min_value.clone(), statistics.max_value.clone())
+    }
+
+    fn boolean(can_be_false: bool, can_be_true: bool) -> Self {
+        Self::new(
+            Some(ScalarValue::Boolean(Some(!can_be_false))),
+            Some(ScalarValue::Boolean(Some(can_be_true))),
+        )
+    }
+
+    /// Whether the boolean expression is true unless it is null
+    pub fn is_certainly_true(&self) -> bool {
+        self.lower == Some(ScalarValue::Boolean(Some(true)))
+    }
+
+    /// Whether the boolean expression is false or null
+    pub fn is_certainly_false(&self) -> bool {
+        self.upper == Some(ScalarValue::Boolean(Some(false)))
+    }
+
+    fn can_be_false(&self) -> bool {
+        !self.is_certainly_true()
+    }
+
+    fn can_be_true(&self) -> bool {
+        !self.is_certainly_false()
+    }
+}
+
+/// Computes the [Interval]s of expressions from the intervals of their columns
+pub struct RangeAnalysis<'a> {
+    schema: &'a DFSchema,
+    columns: HashMap<Column, Interval>,
+}
+
+impl<'a> RangeAnalysis<'a> {
+    /// The analysis of the expressions over `schema`, with unbounded columns
+    pub fn new(schema: &'a DFSchema) -> Self {
+        Self {
+            schema,
+            columns: HashMap::new(),
+        }
+    }
+
+    /// The analysis with the column bounded by `interval`
+    pub fn with_column(mut self, column: Column, interval: Interval) -> Self {
+        self.columns.insert(column, interval);
+        self
+    }
+
+    /// The analysis with the columns of the schema bounded by their statistics, given
+    /// in the order of the fields
+    pub fn with_statistics(mut self, statistics: &[ColumnStatistics]) -> Self {
+        for (field, statistics) in self.schema.fields().iter().zip(statistics) {
+            self.columns.insert(
+                field.qualified_column(),
+                Interval::from_statistics(statistics),
+            );
+        }
+        self
+    }
+
+    /// The bounds of the values of `expr`
+    pub fn interval(&self, expr: &Expr) -> Result<Interval> {
+        self.analyze(expr, &mut false)
+    }
+
+    /// Whether the integer arithmetic of `expr` can overflow, i.e. it is not proven to
+    /// stay in the range of its types
+    pub fn may_overflow(&self, expr: &Expr) -> Result<bool> {
+        let mut overflow = false;
+        self.analyze(expr, &mut overflow)?;
+        Ok(overflow)
+    }
+
+    /// `Some(false)` when the predicate is false or null for all the rows,
+    /// `Some(true)` when it is true for all the rows where it is not null
+    pub fn evaluate_predicate(&self, predicate: &Expr) -> Result<Option<bool>> {
+        let interval = self.interval(predicate)?;
+        Ok(if interval.is_certainly_false() {
+            Some(false)
+        } else if interval.is_certainly_true() {
+            Some(true)
+        } else {
+            None
+        })
+    }
+
+    fn analyze(&self, expr: &Expr, overflow: &mut bool) -> Result<Interval> {
+        Ok(match expr {
+            Expr::Alias(expr, _) => self.analyze(expr, overflow)?,
+            Expr::Column(c) => self
+                .columns
+                .get(c)
+                .cloned()
+                .unwrap_or_else(Interval::unbounded),
+            Expr::Literal(v) => Interval::point(v.clone()),
+            Expr::BinaryExpr { left, op, right } => {
+                let l = self.analyze(left, overflow)?;
+                let r = self.analyze(right, overflow)?;
+                match op {
+                    Operator::Plus | Operator::Minus | Operator::Multiply => {
+                        let data_type = expr.get_type(self.schema)?;
+                        arithmetic(&l, *op, &r, &data_type, overflow)
+                    }
+                    Operator::Eq
+                    | Operator::NotEq
+                    | Operator::Lt
+                    | Operator::LtEq
+                    | Operator::Gt
+                    | Operator::GtEq => comparison(&l, *op, &r),
+                    Operator::And => Interval::boolean(
+                        l.can_be_false() || r.can_be_false(),
+                        l.can_be_true() && r.can_be_true(),
+                    ),
+                    Operator::Or => Interval::boolean(
+                        l.can_be_false() && r.can_be_false(),
+                        l.can_be_true() || r.can_be_true(),
+                    ),
+                    _ => Interval::unbounded(),
+                }
+            }
+            Expr::Not(expr) => {
+                let i = self.analyze(expr, overflow)?;
+                Interval::boolean(i.can_be_true(), i.can_be_false())
+            }
+            Expr::Negative(expr) => {
+                let i = self.analyze(expr, overflow)?;
+                let data_type = expr.get_type(self.schema)?;
+                let zero = Interval::point(zero(&data_type));
+                arithmetic(&zero, Operator::Minus, &i, &data_type, overflow)
+            }
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                let i = self.analyze(expr, overflow)?;
+                let low = comparison(&i, Operator::GtEq, &self.analyze(low, overflow)?);
+                let high = comparison(&i, Operator::LtEq, &self.analyze(high, overflow)?);
+                let can_be_false = low.can_be_false() || high.can_be_false();
+                let can_be_true = low.can_be_true() && high.can_be_true();
+                if *negated {
+                    Interval::boolean(can_be_true, can_be_false)
+                } else {
+                    Interval::boolean(can_be_false, can_be_true)
+                }
+            }
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let i = self.analyze(expr, overflow)?;
+                let mut can_match = false;
+                for e in list {
+                    let e = self.analyze(e, overflow)?;
+                    can_match |= comparison(&i, Operator::Eq, &e).can_be_true();
+                }
+                if can_match {
+                    Interval::unbounded()
+                } else {
+                    Interval::boolean(!negated, *negated)
+                }
+            }
+            Expr::Cast { expr, data_type } | Expr::TryCast { expr, data_type } => {
+                let i = self.analyze(expr, overflow)?;
+                if is_ordered(&expr.get_type(self.schema)?) && is_ordered(data_type) {
+                    // numeric and temporal casts preserve the order of the values
+                    Interval::new(
+                        i.lower.and_then(|v| cast(&v, data_type)),
+                        i.upper.and_then(|v| cast(&v, data_type)),
+                    )
+                } else {
+                    Interval::unbounded()
+                }
+            }
+            Expr::ScalarFunction {
+                fun: BuiltinScalarFunction::DateTrunc,
+                args,
+            } => {
+                let i = self.analyze(&args[1], overflow)?;
+                match &args[0] {
+                    // truncation preserves the order of the values
+                    Expr::Literal(granularity @ ScalarValue::Utf8(Some(_))) => {
+                        Interval::new(
+                            i.lower.and_then(|v| date_trunc(granularity, v)),
+                            i.upper.and_then(|v| date_trunc(granularity, v)),
+                        )
+                    }
+                    _ => Interval::unbounded(),
+                }
+            }
+            _ => Interval::unbounded(),
+        })
+    }
+}
+
+/// The bounds of `l op r`, of the given type, flagging the integer results that do
+/// not fit in it
+fn arithmetic(
+    l: &Interval,
+    op: Operator,
+    r: &Interval,
+    data_type: &DataType,
+    overflow: &mut bool,
+) -> Interval {
+    let mut compute = |a: &Option<ScalarValue>, b: &Option<ScalarValue>| {
+        let result = match (a, b) {
+            (Some(a), Some(b)) => apply(a, op, b, data_type),
+            _ => None,
+        };
+        if result.is_none() && is_integer(data_type) {
+            *overflow = true;
+        }
+        result
+    };
+    match op {
+        Operator::Plus => {
+            Interval::new(compute(&l.lower, &r.lower), compute(&l.upper, &r.upper))
+        }
+        Operator::Minus => {
+            Interval::new(compute(&l.lower, &r.upper), compute(&l.upper, &r.lower))
+        }
+        Operator::Multiply => {
+            let products = [
+                compute(&l.lower, &r.lower),
+                compute(&l.lower, &r.upper),
+                compute(&l.upper, &r.lower),
+                compute(&l.upper, &r.upper),
+            ];
+            if products.iter().any(|p| p.is_none()) {
+                return Interval::unbounded();
+            }
+            let products = products.iter().flatten();
+            let lower = products
+                .clone()
+                .min_by(|a, b| compare(a, b).unwrap_or(Ordering::Equal));
+            let upper = products.max_by(|a, b| compare(a, b).unwrap_or(Ordering::Equal));
+            Interval::new(lower.cloned(), upper.cloned())
+        }
+        _ => Interval::unbounded(),
+    }
+}
+
+/// Whether the comparison can be false or true
+fn comparison(l: &Interval, op: Operator, r: &Interval) -> Interval {
+    let cmp = |a: &Option<ScalarValue>, b: &Option<ScalarValue>| match (a, b) {
+        (Some(a), Some(b)) => compare(a, b),
+        _ => None,
+    };
+    // the ordering of the upper bound of the left side and the lower one of the right
+    // side, and the other way round
+    let lu_rl = cmp(&l.upper, &r.lower);
+    let ll_ru = cmp(&l.lower, &r.upper);
+    let always_less = lu_rl == Some(Ordering::Less);
+    let always_greater = ll_ru == Some(Ordering::Greater);
+    let always_less_eq = matches!(lu_rl, Some(Ordering::Less | Ordering::Equal));
+    let always_greater_eq = matches!(ll_ru, Some(Ordering::Greater | Ordering::Equal));
+    let always_equal = always_less_eq && always_greater_eq;
+    let never_equal = always_less || always_greater;
+    let (can_be_false, can_be_true) = match op {
+        Operator::Eq => (!always_equal, !never_equal),
+        Operator::NotEq => (!never_equal, !always_equal),
+        Operator::Lt => (!always_less, !always_greater_eq),
+        Operator::LtEq => (!always_less_eq, !always_greater),
+        Operator::Gt => (!always_greater, !always_less_eq),
+        Operator::GtEq => (!always_greater_eq, !always_less),
+        _ => (true, true),
+    };
+    Interval::boolean(can_be_false, can_be_true)
+}
+
+fn is_integer(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+    )
+}
+
+fn is_ordered(data_type: &DataType) -> bool {
+    is_integer(data_type)
+        || matches!(
+            data_type,
+            DataType::Float32
+                | DataType::Float64
+                | DataType::Date32
+                | DataType::Date64
+                | DataType::Timestamp(_, _)
+        )
+}
+
+fn zero(data_type: &DataType) -> ScalarValue {
+    match data_type {
+        DataType::Float32 | DataType::Float64 => {
+            from_f64(0.0, data_type).unwrap_or(ScalarValue::Float64(Some(0.0)))
+        }
+        _ => from_i128(0, data_type).unwrap_or(ScalarValue::Int64(Some(0))),
+    }
+}
+
+fn as_i128(v: &ScalarValue) -> Option<i128> {
+    match v {
+        ScalarValue::Int8(v) => v.map(i128::from),
+        ScalarValue::Int16(v) => v.map(i128::from),
+        ScalarValue::Int32(v) => v.map(i128::from),
+        ScalarValue::Int64(v) => v.map(i128::from),
+        ScalarValue::UInt8(v) => v.map(i128::from),
+        ScalarValue::UInt16(v) => v.map(i128::from),
+        ScalarValue::UInt32(v) => v.map(i128::from),
+        ScalarValue::UInt64(v) => v.map(i128::from),
+        ScalarValue::Date32(v) => v.map(i128::from),
+        ScalarValue::Date64(v)
+        | ScalarValue::TimestampSecond(v)
+        | ScalarValue::TimestampMillisecond(v)
+        | ScalarValue::TimestampMicrosecond(v)
+        | ScalarValue::TimestampNanosecond(v) => v.map(i128::from),
+        _ => None,
+    }
+}
+
+fn as_f64(v: &ScalarValue) -> Option<f64> {
+    match v {
+        ScalarValue::Float32(v) => v.map(f64::from),
+        ScalarValue::Float64(v) => *v,
+        v if is_integer(&v.get_datatype()) => as_i128(v).map(|v| v as f64),
+        _ => None,
+    }
+}
+
+/// The integer value of the given type, `None` when it does not fit
+fn from_i128(v: i128, data_type: &DataType) -> Option<ScalarValue> {
+    Some(match data_type {
+        DataType::Int8 => ScalarValue::Int8(Some(i8::try_from(v).ok()?)),
+        DataType::Int16 => ScalarValue::Int16(Some(i16::try_from(v).ok()?)),
+        DataType::Int32 => ScalarValue::Int32(Some(i32::try_from(v).ok()?)),
+        DataType::Int64 => ScalarValue::Int64(Some(i64::try_from(v).ok()?)),
+        DataType::UInt8 => ScalarValue::UInt8(Some(u8::try_from(v).ok()?)),
+        DataType::UInt16 => ScalarValue::UInt16(Some(u16::try_from(v).ok()?)),
+        DataType::UInt32 => ScalarValue::UInt32(Some(u32::try_from(v).ok()?)),
+        DataType::UInt64 => ScalarValue::UInt64(Some(u64::try_from(v).ok()?)),
+        _ => return None,
+    })
+}
+
+fn from_f64(v: f64, data_type: &DataType) -> Option<ScalarValue> {
+    match data_type {
+        DataType::Float32 => Some(ScalarValue::Float32(Some(v as f32))),
+        DataType::Float64 => Some(ScalarValue::Float64(Some(v))),
+        _ => None,
+    }
+}
+
+/// `a op b` as a value of the given type, `None` when it is not known or does not fit
+fn apply(
+    a: &ScalarValue,
+    op: Operator,
+    b: &ScalarValue,
+    data_type: &DataType,
+) -> Option<ScalarValue> {
+    if is_integer(data_type) {
+        let (a, b) = (as_i128(a)?, as_i128(b)?);
+        let v = match op {
+            Operator::Plus => a.checked_add(b)?,
+            Operator::Minus => a.checked_sub(b)?,
+            Operator::Multiply => a.checked_mul(b)?,
+            _ => return None,
+        };
+        from_i128(v, data_type)
+    } else {
+        let (a, b) = (as_f64(a)?, as_f64(b)?);
+        let v = match op {
+            Operator::Plus => a + b,
+            Operator::Minus => a - b,
+            Operator::Multiply => a * b,
+            _ => return None,
+        };
+        from_f64(v, data_type)
+    }
+}
+
+/// The ordering of two non-null values, `None` when they are not comparable
+fn compare(a: &ScalarValue, b: &ScalarValue) -> Option<Ordering> {
+    let (a_type, b_type) = (a.get_datatype(), b.get_datatype());
+    if (is_integer(&a_type) && is_integer(&b_type))
+        || (a_type == b_type && is_ordered(&a_type))
+    {
+        if let (Some(a), Some(b)) = (as_i128(a), as_i128(b)) {
+            return Some(a.cmp(&b));
+        }
+    }
+    if let (Some(a), Some(b)) = (as_f64(a), as_f64(b)) {
+        return a.partial_cmp(&b);
+    }
+    match (a, b) {
+        (ScalarValue::Utf8(Some(a)), ScalarValue::Utf8(Some(b))) => Some(a.cmp(b)),
+        (ScalarValue::Boolean(Some(a)), ScalarValue::Boolean(Some(b))) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+fn cast(v: &ScalarValue, data_type: &DataType) -> Option<ScalarValue> {
+    let array = arrow::compute::cast(&v.to_array(), data_type).ok()?;
+    ScalarValue::try_from_array(&array, 0).ok()
+}
+
+fn date_trunc(granularity: &ScalarValue, v: ScalarValue) -> Option<ScalarValue> {
+    let v = cast(&v, &DataType::Timestamp(TimeUnit::Nanosecond, None))?;
+    match datetime_expressions::date_trunc(&[
+        ColumnarValue::Scalar(granularity.clone()),
+        ColumnarValue::Scalar(v),
+    ]) {
+        Ok(ColumnarValue::Scalar(v)) => Some(v),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, lit, Expr};
+    use crate::test::*;
+
+    fn analysis(schema: &DFSchema) -> RangeAnalysis {
+        RangeAnalysis::new(schema)
+            .with_column(
+                Column::from_qualified_name("test.a"),
+                Interval::new(
+                    Some(ScalarValue::UInt32(Some(10))),
+                    Some(ScalarValue::UInt32(Some(20))),
+                ),
+            )
+            .with_column(
+                Column::from_qualified_name("test.b"),
+                Interval::new(Some(ScalarValue::UInt32(Some(0))), None),
+            )
+    }
+
+    #[test]
+    fn arithmetic_bounds() -> Result<()> {
+        let scan = test_table_scan()?;
+        let analysis = analysis(scan.schema());
+
+        let expr = col("test.a") * lit(2u32) + lit(1u32);
+        assert_eq!(
+            analysis.interval(&expr)?,
+            Interval::new(
+                Some(ScalarValue::UInt32(Some(21))),
+                Some(ScalarValue::UInt32(Some(41))),
+            )
+        );
+        assert!(!analysis.may_overflow(&expr)?);
+
+        let expr = Expr::Cast {
+            expr: Box::new(col("test.a")),
+            data_type: DataType::Int64,
+        } - lit(30i64);
+        assert_eq!(
+            analysis.interval(&expr)?,
+            Interval::new(
+                Some(ScalarValue::Int64(Some(-20))),
+                Some(ScalarValue::Int64(Some(-10))),
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn overflow() -> Result<()> {
+        let scan = test_table_scan()?;
+        let analysis = analysis(scan.schema());
+
+        // 10 - 20 does not fit in UInt32
+        assert!(analysis.may_overflow(&(col("test.a") - col("test.a")))?);
+        // the upper bound of b is not known
+        assert!(analysis.may_overflow(&(col("test.b") + lit(1u32)))?);
+        assert!(!analysis.may_overflow(&(col("test.a") + lit(1u32)))?);
+        Ok(())
+    }
+
+    #[test]
+    fn predicates() -> Result<()> {
+        let scan = test_table_scan()?;
+        let analysis = analysis(scan.schema());
+
+        let evaluate = |e: Expr| analysis.evaluate_predicate(&e);
+        assert_eq!(evaluate(col("test.a").gt(lit(5u32)))?, Some(true));
+        assert_eq!(evaluate(col("test.a").gt(lit(20u32)))?, Some(false));
+        assert_eq!(evaluate(col("test.a").gt(lit(15u32)))?, None);
+        assert_eq!(
+            evaluate(col("test.a").lt(lit(5u32)).or(col("test.b").lt(lit(0u32))))?,
+            Some(false)
+        );
+        assert_eq!(
+            evaluate(col("test.a").in_list(vec![lit(1u32), lit(30u32)], false))?,
+            Some(false)
+        );
+        assert_eq!(evaluate(col("test.c").gt(lit(5u32)))?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn date_trunc_bounds() -> Result<()> {
+        let scan = test_table_scan()?;
+        let analysis = RangeAnalysis::new(scan.schema());
+        let expr = Expr::ScalarFunction {
+            fun: BuiltinScalarFunction::DateTrunc,
+            args: vec![
+                lit("day"),
+                lit(ScalarValue::TimestampNanosecond(Some(
+                    // 2021-01-02T03:04:05
+                    1_609_556_645_000_000_000,
+                ))),
+            ],
+        };
+        assert_eq!(
+            analysis.interval(&expr)?,
+            Interval::point(ScalarValue::TimestampNanosecond(Some(
+                // 2021-01-02T00:00:00
+                1_609_545_600_000_000_000
+            )))
+        );
+        Ok(())
+    }
+}
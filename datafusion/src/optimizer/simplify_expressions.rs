@@ -35,6 +35,10 @@ use crate::{error::Result, logical_plan::Operator};
 pub struct SimplifyExpressions {}
 
 fn expr_contains(expr: &Expr, needle: &Expr) -> bool {
+    // Two calls of a volatile function are never equivalent
+    if needle.is_volatile() {
+        return false;
+    }
     // We turned off the recursion becouse it lead to wrong optimizations like b > 2 and ((b > 2 and a=1) or (a = 3)) -> b > 2
     //    expr == needle
     match expr {
@@ -129,7 +133,7 @@ fn simplify(expr: &Expr) -> Expr {
             left,
             op: Operator::Or,
             right,
-        } if left == right => simplify(left),
+        } if left == right && !left.is_volatile() => simplify(left),
         Expr::BinaryExpr {
             left,
             op: Operator::And,
@@ -149,7 +153,7 @@ fn simplify(expr: &Expr) -> Expr {
             left,
             op: Operator::And,
             right,
-        } if left == right => simplify(right),
+        } if left == right && !left.is_volatile() => simplify(right),
         Expr::BinaryExpr {
             left,
             op: Operator::Multiply,
@@ -174,9 +178,9 @@ fn simplify(expr: &Expr) -> Expr {
             left,
             op: Operator::Divide,
             right,
-        } if left == right => lit(1),
+        } if left == right && !left.is_volatile() => lit(1),
         Expr::BinaryExpr { left, op, right }
-            if left == right && operator_is_boolean(*op) =>
+            if left == right && operator_is_boolean(*op) && !left.is_volatile() =>
         {
             simplify(left)
         }
@@ -303,7 +307,9 @@ impl SimplifyExpressions {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::logical_plan::{and, binary_expr, col, lit, Expr, LogicalPlanBuilder};
+    use crate::logical_plan::{
+        and, binary_expr, col, lit, random, Expr, LogicalPlanBuilder,
+    };
     use crate::test::*;
 
     fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
@@ -406,6 +412,16 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_simplify_keeps_volatile() -> Result<()> {
+        let expr = binary_expr(random(), Operator::Divide, random());
+        assert_eq!(simplify(&expr), expr);
+
+        let expr = and(random().lt(lit(0.5)), random().lt(lit(0.5)));
+        assert_eq!(simplify(&expr), expr);
+        Ok(())
+    }
+
     #[test]
     fn test_simplify_simple_and() -> Result<()> {
         // (c > 5) AND (c > 5)
@@ -18,11 +18,12 @@
 //! Collection of utility functions that are leveraged by the query optimizer rules
 
 use super::optimizer::OptimizerRule;
+use crate::cube_ext::alias::LogicalAlias;
 use crate::execution::context::ExecutionProps;
 use crate::logical_plan::builder::build_union_schema;
 use crate::logical_plan::{
-    build_join_schema, Column, DFSchemaRef, Expr, LogicalPlan, LogicalPlanBuilder,
-    Operator, Partitioning, Recursion,
+    build_join_schema, Column, DFSchemaRef, Expr, GetFieldAccess, LogicalPlan,
+    LogicalPlanBuilder, Operator, Partitioning, Recursion,
 };
 use crate::prelude::lit;
 use crate::scalar::ScalarValue;
@@ -80,6 +81,9 @@ impl ExpressionVisitor for ColumnNameVisitor<'_> {
             Expr::AggregateUDF { .. } => {}
             Expr::RollingAggregate { .. } => {}
             Expr::InList { .. } => {}
+            Expr::HigherOrderFunction { .. } => {}
+            Expr::Lambda { .. } => {}
+            Expr::GetIndexedField { .. } => {}
             Expr::Wildcard => {}
         }
         Ok(Recursion::Continue(self))
@@ -204,9 +208,39 @@ pub fn from_plan(
                 alias: alias.clone(),
             })
         }
+        LogicalPlan::Insert {
+            table_name,
+            table,
+            create_table,
+            schema,
+            ..
+        } => Ok(LogicalPlan::Insert {
+            table_name: table_name.clone(),
+            table: table.clone(),
+            input: Arc::new(inputs[0].clone()),
+            create_table: *create_table,
+            schema: schema.clone(),
+        }),
+        LogicalPlan::CreateMemoryTable {
+            name,
+            schema,
+            column_defaults,
+            generated_columns,
+            if_not_exists,
+            ..
+        } => Ok(LogicalPlan::CreateMemoryTable {
+            name: name.clone(),
+            schema: schema.clone(),
+            column_defaults: column_defaults.clone(),
+            generated_columns: generated_columns.clone(),
+            if_not_exists: *if_not_exists,
+            input: inputs.first().map(|input| Arc::new(input.clone())),
+        }),
         LogicalPlan::EmptyRelation { .. }
         | LogicalPlan::TableScan { .. }
         | LogicalPlan::CreateExternalTable { .. }
+        | LogicalPlan::Analyze { .. }
+        | LogicalPlan::SetVariable { .. }
         | LogicalPlan::Explain { .. } => Ok(plan.clone()),
     }
 }
@@ -236,7 +270,17 @@ pub fn expr_sub_expressions(expr: &Expr) -> Result<Vec<Expr>> {
             expr_list.extend(order_by.clone());
             Ok(expr_list)
         }
-        Expr::AggregateFunction { args, .. } => Ok(args.clone()),
+        Expr::AggregateFunction {
+            args,
+            order_by,
+            filter,
+            ..
+        } => Ok(args
+            .iter()
+            .chain(order_by)
+            .chain(filter.as_deref())
+            .cloned()
+            .collect()),
         Expr::AggregateUDF { args, .. } => Ok(args.clone()),
         Expr::Case {
             expr,
@@ -283,6 +327,20 @@ pub fn expr_sub_expressions(expr: &Expr) -> Result<Vec<Expr>> {
             Ok(expr_list)
         }
         Expr::RollingAggregate { agg, .. } => Ok(vec![agg.as_ref().to_owned()]),
+        Expr::HigherOrderFunction { args, .. } => Ok(args.clone()),
+        // the body of a lambda is planned against its parameters, not the input
+        Expr::Lambda { .. } => Ok(vec![]),
+        Expr::GetIndexedField { expr, field } => Ok(match field {
+            GetFieldAccess::ListIndex { key } => {
+                vec![expr.as_ref().to_owned(), key.as_ref().to_owned()]
+            }
+            GetFieldAccess::ListRange { start, stop } => vec![
+                expr.as_ref().to_owned(),
+                start.as_ref().to_owned(),
+                stop.as_ref().to_owned(),
+            ],
+            GetFieldAccess::NamedStructField { .. } => vec![expr.as_ref().to_owned()],
+        }),
         Expr::Wildcard { .. } => Err(DataFusionError::Internal(
             "Wildcard expressions are not valid in a logical query plan".to_owned(),
         )),
@@ -352,11 +410,27 @@ pub fn rewrite_expression(expr: &Expr, expressions: &[Expr]) -> Result<Expr> {
                 })
             }
         }
-        Expr::AggregateFunction { fun, distinct, .. } => Ok(Expr::AggregateFunction {
-            fun: fun.clone(),
-            args: expressions.to_vec(),
-            distinct: *distinct,
-        }),
+        Expr::AggregateFunction {
+            fun,
+            args,
+            distinct,
+            ignore_nulls,
+            order_by,
+            filter,
+        } => {
+            // the filter follows the arguments and the ORDER BY expressions
+            let order_by_end = args.len() + order_by.len();
+            Ok(Expr::AggregateFunction {
+                fun: fun.clone(),
+                args: expressions[..args.len()].to_vec(),
+                distinct: *distinct,
+                ignore_nulls: *ignore_nulls,
+                order_by: expressions[args.len()..order_by_end].to_vec(),
+                filter: filter
+                    .as_ref()
+                    .map(|_| Box::new(expressions[order_by_end].clone())),
+            })
+        }
         Expr::AggregateUDF { fun, .. } => Ok(Expr::AggregateUDF {
             fun: fun.clone(),
             args: expressions.to_vec(),
@@ -457,12 +531,85 @@ pub fn rewrite_expression(expr: &Expr, expressions: &[Expr]) -> Result<Expr> {
             end: end_bound.clone(),
             offset: *offset,
         }),
+        Expr::HigherOrderFunction { fun, .. } => Ok(Expr::HigherOrderFunction {
+            fun: *fun,
+            args: expressions.to_vec(),
+        }),
+        Expr::Lambda { .. } => Ok(expr.clone()),
+        Expr::GetIndexedField { field, .. } => Ok(Expr::GetIndexedField {
+            expr: Box::new(expressions[0].clone()),
+            field: match field {
+                GetFieldAccess::ListIndex { .. } => GetFieldAccess::ListIndex {
+                    key: Box::new(expressions[1].clone()),
+                },
+                GetFieldAccess::ListRange { .. } => GetFieldAccess::ListRange {
+                    start: Box::new(expressions[1].clone()),
+                    stop: Box::new(expressions[2].clone()),
+                },
+                GetFieldAccess::NamedStructField { name } => {
+                    GetFieldAccess::NamedStructField { name: name.clone() }
+                }
+            },
+        }),
         Expr::Wildcard { .. } => Err(DataFusionError::Internal(
             "Wildcard expressions are not valid in a logical query plan".to_owned(),
         )),
     }
 }
 
+/// Whether `plan` has at most one row for every value of the `columns`
+pub(crate) fn is_unique(plan: &LogicalPlan, columns: &HashSet<Column>) -> bool {
+    match plan {
+        LogicalPlan::Aggregate {
+            group_expr, schema, ..
+        } => schema.fields()[..group_expr.len()]
+            .iter()
+            .all(|f| columns.contains(&f.qualified_column())),
+        LogicalPlan::Projection {
+            expr,
+            input,
+            schema,
+        } => {
+            let mut input_columns = HashSet::new();
+            for (e, f) in expr.iter().zip(schema.fields()) {
+                if !columns.contains(&f.qualified_column()) {
+                    continue;
+                }
+                match e {
+                    Expr::Column(c) => input_columns.insert(c.clone()),
+                    Expr::Alias(e, _) => match e.as_ref() {
+                        Expr::Column(c) => input_columns.insert(c.clone()),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+            }
+            is_unique(input, &input_columns)
+        }
+        LogicalPlan::Filter { input, .. }
+        | LogicalPlan::Sort { input, .. }
+        | LogicalPlan::Limit { input, .. }
+        | LogicalPlan::Skip { input, .. } => is_unique(input, columns),
+        LogicalPlan::Extension { node } => {
+            match node.as_any().downcast_ref::<LogicalAlias>() {
+                Some(alias) => {
+                    let input_columns = alias
+                        .schema
+                        .fields()
+                        .iter()
+                        .zip(alias.input.schema().fields())
+                        .filter(|(f, _)| columns.contains(&f.qualified_column()))
+                        .map(|(_, input_field)| input_field.qualified_column())
+                        .collect();
+                    is_unique(&alias.input, &input_columns)
+                }
+                None => false,
+            }
+        }
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,128 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cost model deciding between alternative physical plans
+
+use std::sync::Arc;
+
+use crate::execution::context::ExecutionConfig;
+use crate::physical_plan::hash_aggregate::AggregateStrategy;
+use crate::physical_plan::{ExecutionPlan, Partitioning, PhysicalExpr};
+
+/// `CostModel` makes the decisions of the physical planner and optimizer rules that
+/// depend on the cost of the alternatives, e.g. on the size of the inputs. Embedders
+/// can replace it with [`ExecutionConfig::with_cost_model`] to tune these decisions
+/// for their storage.
+pub trait CostModel: Send + Sync {
+    /// Whether the inputs of a hash join should be swapped, making `right` the
+    /// build side
+    fn swap_join_inputs(
+        &self,
+        left: &dyn ExecutionPlan,
+        right: &dyn ExecutionPlan,
+    ) -> bool;
+
+    /// Whether the build side of a hash join should be collected into a single hash
+    /// table shared by all probe partitions instead of being hash partitioned
+    fn collect_build_side(
+        &self,
+        build: &dyn ExecutionPlan,
+        config: &ExecutionConfig,
+    ) -> bool;
+
+    /// Whether the output of `plan` should be repartitioned round robin into
+    /// `config.concurrency` partitions to execute its consumers in parallel
+    fn repartition(&self, plan: &dyn ExecutionPlan, config: &ExecutionConfig) -> bool;
+
+    /// The strategy of an aggregation of `input` grouped by `group_key`, one of the
+    /// `valid` strategies. The planner checks which strategies give correct results,
+    /// e.g. [`AggregateStrategy::InplaceSorted`] is only valid when the input is sorted
+    /// by the group key, and [`AggregateStrategy::Hash`] always is.
+    fn aggregate_strategy(
+        &self,
+        input: &dyn ExecutionPlan,
+        group_key: &[(Arc<dyn PhysicalExpr>, String)],
+        valid: &[AggregateStrategy],
+    ) -> AggregateStrategy;
+}
+
+/// The default [`CostModel`], based on the [statistics](ExecutionPlan::statistics)
+/// of the plans
+#[derive(Debug, Default)]
+pub struct DefaultCostModel {}
+
+impl DefaultCostModel {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl CostModel for DefaultCostModel {
+    /// Builds the hash table on the input with fewer rows
+    fn swap_join_inputs(
+        &self,
+        left: &dyn ExecutionPlan,
+        right: &dyn ExecutionPlan,
+    ) -> bool {
+        matches!(
+            (left.statistics().num_rows, right.statistics().num_rows),
+            (Some(l), Some(r)) if l > r
+        )
+    }
+
+    /// Collects build sides with at most
+    /// [`hash_join_single_partition_threshold`](ExecutionConfig::hash_join_single_partition_threshold)
+    /// rows
+    fn collect_build_side(
+        &self,
+        build: &dyn ExecutionPlan,
+        config: &ExecutionConfig,
+    ) -> bool {
+        let threshold = config.hash_join_single_partition_threshold();
+        build
+            .statistics()
+            .num_rows
+            .map_or(false, |rows| rows <= threshold)
+    }
+
+    /// Repartitions plans with fewer partitions than the concurrency, unless they are
+    /// hash partitioned as their consumers will likely depend on it
+    fn repartition(&self, plan: &dyn ExecutionPlan, config: &ExecutionConfig) -> bool {
+        match plan.output_partitioning() {
+            Partitioning::RoundRobinBatch(x) | Partitioning::UnknownPartitioning(x) => {
+                x < config.concurrency
+            }
+            Partitioning::Hash(_, _) => false,
+        }
+    }
+
+    /// Aggregates in place when the input is sorted by the group key, and with a hash
+    /// table otherwise
+    fn aggregate_strategy(
+        &self,
+        _input: &dyn ExecutionPlan,
+        _group_key: &[(Arc<dyn PhysicalExpr>, String)],
+        valid: &[AggregateStrategy],
+    ) -> AggregateStrategy {
+        if valid.contains(&AggregateStrategy::InplaceSorted) {
+            AggregateStrategy::InplaceSorted
+        } else {
+            AggregateStrategy::Hash
+        }
+    }
+}
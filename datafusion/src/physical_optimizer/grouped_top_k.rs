@@ -0,0 +1,369 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! GroupedTopK replaces ROW_NUMBER windows filtered on their row numbers with grouped
+//! TOP K plans
+use std::sync::Arc;
+
+use super::optimizer::PhysicalOptimizerRule;
+use crate::error::Result;
+use crate::execution::context::ExecutionConfig;
+use crate::logical_plan::Operator;
+use crate::physical_plan::expressions::{
+    BinaryExpr, CastExpr, Column, Literal, TryCastExpr,
+};
+use crate::physical_plan::filter::FilterExec;
+use crate::physical_plan::grouped_top_k::GroupedTopKExec;
+use crate::physical_plan::sort::SortExec;
+use crate::physical_plan::window_functions::BuiltInWindowFunction;
+use crate::physical_plan::windows::{BuiltInWindowExpr, WindowAggExec};
+use crate::physical_plan::{ExecutionPlan, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::datatypes::DataType;
+
+/// Optimizer rule that replaces a [FilterExec] keeping rows whose ROW_NUMBER is at
+/// most `k`, over a [WindowAggExec] computing the ROW_NUMBER over a [SortExec], with
+/// a [GroupedTopKExec], so that only the first `k` rows of each window partition are
+/// sorted and numbered.
+///
+/// The filter is kept above the grouped TOP K for its other conditions.
+pub struct GroupedTopK {}
+
+impl GroupedTopK {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl PhysicalOptimizerRule for GroupedTopK {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ExecutionConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let children = plan.children();
+        let plan = if children.is_empty() {
+            plan
+        } else {
+            let children = children
+                .into_iter()
+                .map(|child| self.optimize(child, config))
+                .collect::<Result<Vec<_>>>()?;
+            plan.with_new_children(children)?
+        };
+
+        let filter = match plan.as_any().downcast_ref::<FilterExec>() {
+            Some(filter) => filter,
+            None => return Ok(plan),
+        };
+        let window = match filter.input().as_any().downcast_ref::<WindowAggExec>() {
+            Some(window) if window.window_expr().len() == 1 => window,
+            _ => return Ok(plan),
+        };
+        let window_expr = &window.window_expr()[0];
+        match window_expr.as_any().downcast_ref::<BuiltInWindowExpr>() {
+            Some(e) if *e.fun() == BuiltInWindowFunction::RowNumber => {}
+            _ => return Ok(plan),
+        }
+        let sort = match window.input().as_any().downcast_ref::<SortExec>() {
+            Some(sort) if sort.skip() == 0 && sort.fetch().is_none() => sort,
+            _ => return Ok(plan),
+        };
+        let k = match row_number_limit(filter.predicate()) {
+            Some(k) => k,
+            None => return Ok(plan),
+        };
+        let partition_len = match partition_len(sort, window_expr.partition_by()) {
+            Some(len) => len,
+            None => return Ok(plan),
+        };
+
+        let top_k = GroupedTopKExec::try_new(
+            sort.expr().to_vec(),
+            partition_len,
+            k,
+            window_expr.field()?,
+            sort.input().clone(),
+            sort.preserve_partitioning(),
+        )?;
+        Ok(Arc::new(FilterExec::try_new(
+            filter.predicate().clone(),
+            Arc::new(top_k),
+        )?))
+    }
+
+    fn name(&self) -> &str {
+        "grouped_top_k"
+    }
+}
+
+/// Number of leading sort expressions of `sort` that are the window partition keys,
+/// or None if the keys are not all sorted on first
+fn partition_len(
+    sort: &SortExec,
+    partition_by: &[Arc<dyn PhysicalExpr>],
+) -> Option<usize> {
+    let partition_by = partition_by
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+    let len = sort
+        .expr()
+        .iter()
+        .take_while(|e| partition_by.contains(&e.expr.to_string()))
+        .count();
+    let sorted = &sort.expr()[..len];
+    if partition_by
+        .iter()
+        .all(|p| sorted.iter().any(|e| e.expr.to_string() == *p))
+    {
+        Some(len)
+    } else {
+        None
+    }
+}
+
+/// The greatest row number `predicate` keeps, if it only keeps the row numbers in the
+/// first column up to a positive limit
+fn row_number_limit(predicate: &Arc<dyn PhysicalExpr>) -> Option<usize> {
+    let binary = predicate.as_any().downcast_ref::<BinaryExpr>()?;
+    let (left, right) = (binary.left(), binary.right());
+    let limit = match binary.op() {
+        Operator::And => {
+            return match (row_number_limit(left), row_number_limit(right)) {
+                (Some(l), Some(r)) => Some(l.min(r)),
+                (l, r) => l.or(r),
+            }
+        }
+        op if is_row_number(left) => bound(op, literal_value(right)?)?,
+        op if is_row_number(right) => bound(&flip(op)?, literal_value(left)?)?,
+        _ => return None,
+    };
+    if limit < 1 {
+        return None;
+    }
+    usize::try_from(limit).ok()
+}
+
+/// The greatest value `x` for which `x op value` holds, for operators with one
+fn bound(op: &Operator, value: i128) -> Option<i128> {
+    match op {
+        Operator::LtEq | Operator::Eq => Some(value),
+        Operator::Lt => Some(value - 1),
+        _ => None,
+    }
+}
+
+/// The operator of the comparison with swapped sides
+fn flip(op: &Operator) -> Option<Operator> {
+    match op {
+        Operator::GtEq => Some(Operator::LtEq),
+        Operator::Gt => Some(Operator::Lt),
+        Operator::Eq => Some(Operator::Eq),
+        _ => None,
+    }
+}
+
+/// The expression under the casts of `expr` that keep all the integer row numbers
+fn strip_casts(expr: &Arc<dyn PhysicalExpr>) -> &Arc<dyn PhysicalExpr> {
+    let widens = |t: &DataType| {
+        matches!(t, DataType::Int64 | DataType::UInt64 | DataType::Float64)
+    };
+    if let Some(cast) = expr.as_any().downcast_ref::<CastExpr>() {
+        if widens(cast.cast_type()) {
+            return strip_casts(cast.expr());
+        }
+    } else if let Some(cast) = expr.as_any().downcast_ref::<TryCastExpr>() {
+        if widens(cast.cast_type()) {
+            return strip_casts(cast.expr());
+        }
+    }
+    expr
+}
+
+/// Whether `expr` is the row number column, which comes first in the window output
+fn is_row_number(expr: &Arc<dyn PhysicalExpr>) -> bool {
+    strip_casts(expr)
+        .as_any()
+        .downcast_ref::<Column>()
+        .map_or(false, |c| c.index() == 0)
+}
+
+fn literal_value(expr: &Arc<dyn PhysicalExpr>) -> Option<i128> {
+    match strip_casts(expr)
+        .as_any()
+        .downcast_ref::<Literal>()?
+        .value()
+    {
+        ScalarValue::Int8(Some(v)) => Some(*v as i128),
+        ScalarValue::Int16(Some(v)) => Some(*v as i128),
+        ScalarValue::Int32(Some(v)) => Some(*v as i128),
+        ScalarValue::Int64(Some(v)) => Some(*v as i128),
+        ScalarValue::UInt8(Some(v)) => Some(*v as i128),
+        ScalarValue::UInt16(Some(v)) => Some(*v as i128),
+        ScalarValue::UInt32(Some(v)) => Some(*v as i128),
+        ScalarValue::UInt64(Some(v)) => Some(*v as i128),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{binary, col, lit, PhysicalSortExpr};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::sort::SortOptions;
+    use crate::physical_plan::window_functions::WindowFunction;
+    use crate::physical_plan::windows::create_window_expr;
+    use crate::physical_plan::{collect, displayable};
+    use crate::test::build_table_i32;
+
+    /// ROW_NUMBER() OVER (PARTITION BY a ORDER BY b) AS rn, as planned
+    fn row_numbers() -> Result<Arc<dyn ExecutionPlan>> {
+        let batches = vec![
+            build_table_i32(
+                ("a", &vec![1, 2, 1, 2]),
+                ("b", &vec![4, 3, 2, 1]),
+                ("c", &vec![1, 2, 3, 4]),
+            ),
+            build_table_i32(
+                ("a", &vec![1, 1, 2]),
+                ("b", &vec![3, 0, 5]),
+                ("c", &vec![5, 6, 7]),
+            ),
+        ];
+        let schema = batches[0].schema();
+        let input: Arc<dyn ExecutionPlan> =
+            Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None)?);
+        let partition_by = vec![col("a", &schema)?];
+        let order_by = vec![PhysicalSortExpr {
+            expr: col("b", &schema)?,
+            options: SortOptions::default(),
+        }];
+        let sort_keys = vec![
+            PhysicalSortExpr {
+                expr: col("a", &schema)?,
+                options: SortOptions {
+                    descending: false,
+                    nulls_first: true,
+                },
+            },
+            order_by[0].clone(),
+        ];
+        let sorted = Arc::new(SortExec::try_new(sort_keys, input)?);
+        let row_number = create_window_expr(
+            &WindowFunction::BuiltInWindowFunction(BuiltInWindowFunction::RowNumber),
+            "rn".to_owned(),
+            &[],
+            &partition_by,
+            &order_by,
+            None,
+            &schema,
+        )?;
+        Ok(Arc::new(WindowAggExec::try_new(
+            vec![row_number],
+            sorted,
+            schema,
+        )?))
+    }
+
+    #[tokio::test]
+    async fn row_number_filter() -> Result<()> {
+        let window = row_numbers()?;
+        let schema = window.schema();
+        let predicate = binary(
+            col("rn", &schema)?,
+            Operator::LtEq,
+            lit(ScalarValue::UInt64(Some(2))),
+            &schema,
+        )?;
+        let plan: Arc<dyn ExecutionPlan> =
+            Arc::new(FilterExec::try_new(predicate, window)?);
+        let expected = collect(plan.clone()).await?;
+
+        let config = ExecutionConfig::new();
+        let optimized = GroupedTopK::new().optimize(plan, &config)?;
+        assert_eq!(
+            format!("{}", displayable(optimized.as_ref()).indent()).trim(),
+            "FilterExec: rn@0 <= 2\
+             \n  GroupedTopKExec: [a@0 ASC,b@1 ASC], partition_len=1, k=2\
+             \n    MemoryExec: partitions=1, partition_sizes=[2]"
+        );
+        assert_eq!(
+            arrow::util::pretty::pretty_format_batches(&collect(optimized).await?)?,
+            arrow::util::pretty::pretty_format_batches(&expected)?
+        );
+
+        // filters on other columns are kept
+        let predicate = binary(
+            col("c", &schema)?,
+            Operator::LtEq,
+            lit(ScalarValue::Int32(Some(2))),
+            &schema,
+        )?;
+        let plan: Arc<dyn ExecutionPlan> =
+            Arc::new(FilterExec::try_new(predicate, row_numbers()?)?);
+        let optimized = GroupedTopK::new().optimize(plan, &config)?;
+        assert!(optimized.children()[0]
+            .as_any()
+            .downcast_ref::<WindowAggExec>()
+            .is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn row_number_limits() -> Result<()> {
+        let schema = row_numbers()?.schema();
+        let rn = col("rn", &schema)?;
+        let c = col("c", &schema)?;
+        let n = |v| lit(ScalarValue::UInt64(Some(v)));
+        let cmp = |l, op, r| binary(l, op, r, &schema).unwrap();
+
+        assert_eq!(
+            row_number_limit(&cmp(rn.clone(), Operator::Lt, n(3))),
+            Some(2)
+        );
+        assert_eq!(
+            row_number_limit(&cmp(n(3), Operator::GtEq, rn.clone())),
+            Some(3)
+        );
+        assert_eq!(
+            row_number_limit(&cmp(rn.clone(), Operator::Eq, n(1))),
+            Some(1)
+        );
+        let both = cmp(
+            cmp(rn.clone(), Operator::LtEq, n(5)),
+            Operator::And,
+            cmp(c, Operator::Gt, lit(ScalarValue::Int32(Some(1)))),
+        );
+        assert_eq!(row_number_limit(&both), Some(5));
+
+        assert_eq!(row_number_limit(&cmp(rn.clone(), Operator::Lt, n(1))), None);
+        assert_eq!(
+            row_number_limit(&cmp(rn.clone(), Operator::GtEq, n(3))),
+            None
+        );
+        let either = cmp(
+            cmp(rn.clone(), Operator::LtEq, n(5)),
+            Operator::Or,
+            cmp(rn, Operator::Gt, n(1)),
+        );
+        assert_eq!(row_number_limit(&either), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! JoinSelection picks the build side and the partition mode of hash joins
+//! based on the statistics of their inputs
+use std::sync::Arc;
+
+use super::optimizer::PhysicalOptimizerRule;
+use crate::error::Result;
+use crate::execution::context::ExecutionConfig;
+use crate::logical_plan::JoinType;
+use crate::physical_plan::expressions::Column;
+use crate::physical_plan::hash_join::{HashJoinExec, PartitionMode};
+use crate::physical_plan::projection::ProjectionExec;
+use crate::physical_plan::repartition::RepartitionExec;
+use crate::physical_plan::{ExecutionPlan, Partitioning, PhysicalExpr};
+
+/// Optimizer rule that makes the smaller input of a hash join its build (left) side,
+/// and collects build sides that are small enough into a single hash table shared by
+/// all probe partitions (`PartitionMode::CollectLeft`) instead of hash partitioning
+/// both inputs. The sizes are compared by the [`CostModel`](super::cost::CostModel)
+/// of the configuration.
+pub struct JoinSelection {}
+
+impl JoinSelection {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl PhysicalOptimizerRule for JoinSelection {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ExecutionConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let children = plan.children();
+        let plan = if children.is_empty() {
+            plan
+        } else {
+            let children = children
+                .into_iter()
+                .map(|child| self.optimize(child, config))
+                .collect::<Result<Vec<_>>>()?;
+            plan.with_new_children(children)?
+        };
+
+        if let Some(join) = plan.as_any().downcast_ref::<HashJoinExec>() {
+            if let Some(new_plan) = select_join(join, config)? {
+                return Ok(new_plan);
+            }
+        }
+        Ok(plan)
+    }
+
+    fn name(&self) -> &str {
+        "join_selection"
+    }
+}
+
+/// Returns a replacement for `join` if its inputs should be swapped or its build side
+/// collected into a single partition
+fn select_join(
+    join: &HashJoinExec,
+    config: &ExecutionConfig,
+) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    let cost_model = config.cost_model();
+    let swap = supports_swap(join)
+        && cost_model.swap_join_inputs(join.left().as_ref(), join.right().as_ref());
+    let (build, probe, join_type) = if swap {
+        let join_type = swap_join_type(*join.join_type());
+        (join.right(), join.left(), join_type)
+    } else {
+        (join.left(), join.right(), *join.join_type())
+    };
+
+    // Unmatched build rows are produced by each probe partition, so only joins
+    // that never produce them can share a single build side.
+    let collect_left = *join.partition_mode() == PartitionMode::Partitioned
+        && matches!(join_type, JoinType::Inner | JoinType::Right)
+        && cost_model.collect_build_side(build.as_ref(), config);
+    if !swap && !collect_left {
+        return Ok(None);
+    }
+
+    let (build, mode) = if collect_left {
+        (without_hash_repartition(build), PartitionMode::CollectLeft)
+    } else {
+        (build.clone(), *join.partition_mode())
+    };
+    let on = if swap {
+        join.on()
+            .iter()
+            .map(|(l, r)| (r.clone(), l.clone()))
+            .collect()
+    } else {
+        join.on().to_vec()
+    };
+    let new_join: Arc<dyn ExecutionPlan> = Arc::new(HashJoinExec::try_new(
+        build,
+        probe.clone(),
+        on,
+        &join_type,
+        mode,
+    )?);
+    if !swap {
+        return Ok(Some(new_join));
+    }
+
+    // restore the original order of the columns: left, then right
+    let left_len = join.left().schema().fields().len();
+    let right_len = join.right().schema().fields().len();
+    let schema = new_join.schema();
+    let expr = (right_len..right_len + left_len)
+        .chain(0..right_len)
+        .map(|i| {
+            let name = schema.field(i).name();
+            (
+                Arc::new(Column::new(name, i)) as Arc<dyn PhysicalExpr>,
+                name.to_string(),
+            )
+        })
+        .collect();
+    Ok(Some(Arc::new(ProjectionExec::try_new(expr, new_join)?)))
+}
+
+/// Whether the inputs of `join` can be swapped. Columns of the join output are
+/// matched by name, so the names must be unique across both inputs.
+fn supports_swap(join: &HashJoinExec) -> bool {
+    let supported_type = match join.join_type() {
+        JoinType::Inner | JoinType::Left | JoinType::Right | JoinType::Full => true,
+        JoinType::Semi | JoinType::Anti => false,
+    };
+    let left_schema = join.left().schema();
+    let right_schema = join.right().schema();
+    supported_type
+        && left_schema
+            .fields()
+            .iter()
+            .all(|f| right_schema.field_with_name(f.name()).is_err())
+}
+
+fn swap_join_type(join_type: JoinType) -> JoinType {
+    match join_type {
+        JoinType::Left => JoinType::Right,
+        JoinType::Right => JoinType::Left,
+        join_type => join_type,
+    }
+}
+
+/// The input of the hash repartition added by the planner for partitioned joins
+fn without_hash_repartition(plan: &Arc<dyn ExecutionPlan>) -> Arc<dyn ExecutionPlan> {
+    match plan.as_any().downcast_ref::<RepartitionExec>() {
+        Some(repartition)
+            if matches!(repartition.partitioning(), Partitioning::Hash(_, _)) =>
+        {
+            repartition.input().clone()
+        }
+        _ => plan.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_optimizer::cost::{CostModel, DefaultCostModel};
+    use crate::physical_plan::hash_aggregate::AggregateStrategy;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::{collect, displayable};
+    use crate::test::build_table_i32;
+    use arrow::record_batch::RecordBatch;
+
+    fn memory_exec(batch: RecordBatch) -> Arc<dyn ExecutionPlan> {
+        let schema = batch.schema();
+        Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap())
+    }
+
+    fn partitioned(
+        plan: Arc<dyn ExecutionPlan>,
+        column: &str,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let expr = Arc::new(Column::new_with_schema(column, &plan.schema())?);
+        Ok(Arc::new(RepartitionExec::try_new(
+            plan,
+            Partitioning::Hash(vec![expr], 2),
+        )?))
+    }
+
+    fn plan_lines(plan: &Arc<dyn ExecutionPlan>) -> Vec<String> {
+        format!("{}", displayable(plan.as_ref()).indent())
+            .trim()
+            .lines()
+            .map(|l| l.to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn swap_and_collect_small_build_side() -> Result<()> {
+        let big = memory_exec(build_table_i32(
+            ("a1", &vec![1, 2, 3, 4]),
+            ("b1", &vec![4, 5, 6, 7]),
+            ("c1", &vec![7, 8, 9, 10]),
+        ));
+        let small = memory_exec(build_table_i32(
+            ("a2", &vec![2, 4]),
+            ("b2", &vec![5, 7]),
+            ("c2", &vec![70, 80]),
+        ));
+        let join: Arc<dyn ExecutionPlan> = Arc::new(HashJoinExec::try_new(
+            partitioned(big, "b1")?,
+            partitioned(small, "b2")?,
+            vec![(Column::new("b1", 1), Column::new("b2", 1))],
+            &JoinType::Left,
+            PartitionMode::Partitioned,
+        )?);
+        let expected = collect(join.clone()).await?;
+
+        let config = ExecutionConfig::new();
+        let optimized = JoinSelection::new().optimize(join.clone(), &config)?;
+        assert_eq!(
+            plan_lines(&optimized),
+            vec![
+                "ProjectionExec: expr=[a1@3 as a1, b1@4 as b1, c1@5 as c1, a2@0 as a2, b2@1 as b2, c2@2 as c2]",
+                "  HashJoinExec: mode=CollectLeft, join_type=Right, on=[(Column { name: \"b2\", index: 1 }, Column { name: \"b1\", index: 1 })]",
+                "    MemoryExec: partitions=1, partition_sizes=[1]",
+                "    RepartitionExec: partitioning=Hash([Column { name: \"b1\", index: 1 }], 2)",
+                "      MemoryExec: partitions=1, partition_sizes=[1]",
+            ]
+        );
+        assert_eq!(optimized.schema(), join.schema());
+
+        let sorted = |batches: Vec<RecordBatch>| {
+            let mut lines = arrow::util::pretty::pretty_format_batches(&batches)
+                .unwrap()
+                .lines()
+                .map(|l| l.to_string())
+                .collect::<Vec<_>>();
+            lines.sort();
+            lines
+        };
+        assert_eq!(sorted(collect(optimized).await?), sorted(expected));
+
+        // the build side is too big to be collected, only swap the inputs
+        let config = ExecutionConfig::new().with_hash_join_single_partition_threshold(1);
+        let optimized = JoinSelection::new().optimize(join, &config)?;
+        assert_eq!(
+            plan_lines(&optimized)[1],
+            "  HashJoinExec: mode=Partitioned, join_type=Right, on=[(Column { name: \"b2\", index: 1 }, Column { name: \"b1\", index: 1 })]",
+        );
+        Ok(())
+    }
+    /// Keeps the joins as planned
+    struct NoJoinSelection {}
+
+    impl CostModel for NoJoinSelection {
+        fn swap_join_inputs(&self, _: &dyn ExecutionPlan, _: &dyn ExecutionPlan) -> bool {
+            false
+        }
+
+        fn collect_build_side(&self, _: &dyn ExecutionPlan, _: &ExecutionConfig) -> bool {
+            false
+        }
+
+        fn repartition(
+            &self,
+            plan: &dyn ExecutionPlan,
+            config: &ExecutionConfig,
+        ) -> bool {
+            DefaultCostModel::new().repartition(plan, config)
+        }
+
+        fn aggregate_strategy(
+            &self,
+            input: &dyn ExecutionPlan,
+            group_key: &[(Arc<dyn PhysicalExpr>, String)],
+            valid: &[AggregateStrategy],
+        ) -> AggregateStrategy {
+            DefaultCostModel::new().aggregate_strategy(input, group_key, valid)
+        }
+    }
+
+    #[test]
+    fn custom_cost_model() -> Result<()> {
+        let big = memory_exec(build_table_i32(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 6]),
+            ("c1", &vec![7, 8, 9]),
+        ));
+        let small = memory_exec(build_table_i32(
+            ("a2", &vec![2]),
+            ("b2", &vec![5]),
+            ("c2", &vec![70]),
+        ));
+        let join: Arc<dyn ExecutionPlan> = Arc::new(HashJoinExec::try_new(
+            partitioned(big, "b1")?,
+            partitioned(small, "b2")?,
+            vec![(Column::new("b1", 1), Column::new("b2", 1))],
+            &JoinType::Inner,
+            PartitionMode::Partitioned,
+        )?);
+
+        let config = ExecutionConfig::new().with_cost_model(Arc::new(NoJoinSelection {}));
+        let optimized = JoinSelection::new().optimize(join.clone(), &config)?;
+        assert_eq!(plan_lines(&optimized), plan_lines(&join));
+        Ok(())
+    }
+}
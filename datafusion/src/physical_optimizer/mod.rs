@@ -19,7 +19,11 @@
 //! rules to a physical plan, such as "Repartition".
 
 pub mod coalesce_batches;
+pub mod cost;
+pub mod grouped_top_k;
+pub mod join_selection;
 pub mod merge_exec;
 pub mod optimizer;
+pub mod partial_sort;
 pub mod pruning;
 pub mod repartition;
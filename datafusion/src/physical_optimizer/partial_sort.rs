@@ -0,0 +1,223 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! PartialSort replaces sorts of inputs already sorted on a prefix of the sort key
+//! with partial sorts
+use std::sync::Arc;
+
+use super::optimizer::PhysicalOptimizerRule;
+use crate::error::Result;
+use crate::execution::context::ExecutionConfig;
+use crate::physical_plan::coalesce_batches::CoalesceBatchesExec;
+use crate::physical_plan::expressions::{Column, PhysicalSortExpr};
+use crate::physical_plan::filter::FilterExec;
+use crate::physical_plan::partial_sort::PartialSortExec;
+use crate::physical_plan::sort::SortExec;
+use crate::physical_plan::ExecutionPlan;
+
+/// Optimizer rule that replaces a [SortExec] with a [PartialSortExec] when its input
+/// is known to be sorted on the leading sort expressions, with the same sort options.
+///
+/// The input order is taken from the sorts below it, not from the output hints, which
+/// do not tell the direction of the order.
+pub struct PartialSort {}
+
+impl PartialSort {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl PhysicalOptimizerRule for PartialSort {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ExecutionConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let children = plan.children();
+        let plan = if children.is_empty() {
+            plan
+        } else {
+            let children = children
+                .into_iter()
+                .map(|child| self.optimize(child, config))
+                .collect::<Result<Vec<_>>>()?;
+            plan.with_new_children(children)?
+        };
+
+        let sort = match plan.as_any().downcast_ref::<SortExec>() {
+            Some(sort) => sort,
+            None => return Ok(plan),
+        };
+        let prefix_len = sorted_prefix_len(sort);
+        if prefix_len == 0 {
+            return Ok(plan);
+        }
+        Ok(Arc::new(
+            PartialSortExec::try_new(
+                sort.expr().to_vec(),
+                prefix_len,
+                sort.input().clone(),
+                sort.preserve_partitioning(),
+            )?
+            .with_skip(sort.skip())
+            .with_fetch(sort.fetch()),
+        ))
+    }
+
+    fn name(&self) -> &str {
+        "partial_sort"
+    }
+}
+
+/// Number of leading sort expressions of `sort` its input is known to be sorted on
+fn sorted_prefix_len(sort: &SortExec) -> usize {
+    let input = sort.input();
+    // the partitions are sorted separately, their concatenation is not sorted
+    if !sort.preserve_partitioning() && input.output_partitioning().partition_count() != 1
+    {
+        return 0;
+    }
+    let input_order = match known_order(input) {
+        Some(order) => order,
+        None => return 0,
+    };
+    sort.expr()
+        .iter()
+        .zip(input_order.iter())
+        .take_while(|(e, i)| {
+            let column = e.expr.as_any().downcast_ref::<Column>();
+            let input_column = i.expr.as_any().downcast_ref::<Column>();
+            let same_column = match (column, input_column) {
+                (Some(c), Some(input_column)) => c.index() == input_column.index(),
+                _ => false,
+            };
+            same_column
+                && e.options.descending == i.options.descending
+                && e.options.nulls_first == i.options.nulls_first
+        })
+        .count()
+}
+
+/// The sort expressions each partition of `plan` is known to be sorted on
+fn known_order(plan: &Arc<dyn ExecutionPlan>) -> Option<Vec<PhysicalSortExpr>> {
+    let any = plan.as_any();
+    if let Some(sort) = any.downcast_ref::<SortExec>() {
+        Some(sort.expr().to_vec())
+    } else if let Some(sort) = any.downcast_ref::<PartialSortExec>() {
+        Some(sort.expr().to_vec())
+    } else if let Some(filter) = any.downcast_ref::<FilterExec>() {
+        known_order(filter.input())
+    } else if let Some(coalesce) = any.downcast_ref::<CoalesceBatchesExec>() {
+        known_order(coalesce.input())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::sort::SortOptions;
+    use crate::physical_plan::{collect, displayable};
+    use crate::test::build_table_i32;
+
+    fn sort_expr(
+        name: &str,
+        plan: &Arc<dyn ExecutionPlan>,
+        options: SortOptions,
+    ) -> Result<PhysicalSortExpr> {
+        Ok(PhysicalSortExpr {
+            expr: col(name, &plan.schema())?,
+            options,
+        })
+    }
+
+    #[tokio::test]
+    async fn sort_on_sorted_prefix() -> Result<()> {
+        let batch = build_table_i32(
+            ("a", &vec![3, 1, 2, 1]),
+            ("b", &vec![1, 2, 3, 4]),
+            ("c", &vec![7, 8, 9, 10]),
+        );
+        let schema = batch.schema();
+        let input: Arc<dyn ExecutionPlan> =
+            Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None)?);
+        let sorted: Arc<dyn ExecutionPlan> = Arc::new(SortExec::try_new(
+            vec![sort_expr("a", &input, SortOptions::default())?],
+            input,
+        )?);
+        let descending = SortOptions {
+            descending: true,
+            nulls_first: true,
+        };
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(
+            SortExec::try_new(
+                vec![
+                    sort_expr("a", &sorted, SortOptions::default())?,
+                    sort_expr("b", &sorted, descending)?,
+                ],
+                sorted.clone(),
+            )?
+            .with_fetch(Some(3)),
+        );
+        let expected = collect(plan.clone()).await?;
+
+        let config = ExecutionConfig::new();
+        let optimized = PartialSort::new().optimize(plan, &config)?;
+        assert_eq!(
+            format!("{}", displayable(optimized.as_ref()).indent()).trim(),
+            "PartialSortExec: [a@0 ASC,b@1 DESC], prefix_len=1, fetch=3\
+             \n  SortExec: [a@0 ASC]\
+             \n    MemoryExec: partitions=1, partition_sizes=[1]"
+        );
+        assert_eq!(
+            arrow::util::pretty::pretty_format_batches(&collect(optimized).await?)?,
+            arrow::util::pretty::pretty_format_batches(&expected)?
+        );
+
+        // the prefix must be sorted in the same direction
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(SortExec::try_new(
+            vec![
+                sort_expr("a", &sorted, descending)?,
+                sort_expr("b", &sorted, SortOptions::default())?,
+            ],
+            sorted.clone(),
+        )?);
+        let optimized = PartialSort::new().optimize(plan, &config)?;
+        assert!(optimized.as_any().downcast_ref::<SortExec>().is_some());
+
+        let sorted_descending: Arc<dyn ExecutionPlan> = Arc::new(SortExec::try_new(
+            vec![sort_expr("a", &sorted, descending)?],
+            sorted,
+        )?);
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(SortExec::try_new(
+            vec![
+                sort_expr("a", &sorted_descending, descending)?,
+                sort_expr("b", &sorted_descending, SortOptions::default())?,
+            ],
+            sorted_descending,
+        )?);
+        let optimized = PartialSort::new().optimize(plan, &config)?;
+        let partial_sort = optimized.as_any().downcast_ref::<PartialSortExec>();
+        assert_eq!(partial_sort.map(|s| s.prefix_len()), Some(1));
+        Ok(())
+    }
+}
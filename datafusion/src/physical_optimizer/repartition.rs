@@ -22,7 +22,7 @@ use super::optimizer::PhysicalOptimizerRule;
 use crate::physical_plan::{
     empty::EmptyExec, repartition::RepartitionExec, ExecutionPlan,
 };
-use crate::physical_plan::{Distribution, Partitioning::*};
+use crate::physical_plan::{Distribution, Partitioning::RoundRobinBatch};
 use crate::{error::Result, execution::context::ExecutionConfig};
 
 /// Optimizer that introduces repartition to introduce more parallelism in the plan
@@ -36,7 +36,7 @@ impl Repartition {
 }
 
 fn optimize_concurrency(
-    concurrency: usize,
+    config: &ExecutionConfig,
     requires_single_partition: bool,
     plan: Arc<dyn ExecutionPlan>,
 ) -> Result<Arc<dyn ExecutionPlan>> {
@@ -51,7 +51,7 @@ fn optimize_concurrency(
             .iter()
             .map(|child| {
                 optimize_concurrency(
-                    concurrency,
+                    config,
                     matches!(
                         plan.required_child_distribution(),
                         Distribution::SinglePartition
@@ -63,14 +63,7 @@ fn optimize_concurrency(
         plan.with_new_children(children)?
     };
 
-    let perform_repartition = match new_plan.output_partitioning() {
-        // Apply when underlying node has less than `self.concurrency` amount of concurrency
-        RoundRobinBatch(x) => x < concurrency,
-        UnknownPartitioning(x) => x < concurrency,
-        // we don't want to introduce partitioning after hash partitioning
-        // as the plan will likely depend on this
-        Hash(_, _) => false,
-    };
+    let perform_repartition = config.cost_model().repartition(new_plan.as_ref(), config);
 
     // TODO: EmptyExec causes failures with RepartitionExec
     // But also not very useful to inlude
@@ -79,7 +72,7 @@ fn optimize_concurrency(
     if perform_repartition && !requires_single_partition && !is_empty_exec {
         Ok(Arc::new(RepartitionExec::try_new(
             new_plan,
-            RoundRobinBatch(concurrency),
+            RoundRobinBatch(config.concurrency),
         )?))
     } else {
         Ok(new_plan)
@@ -96,7 +89,7 @@ impl PhysicalOptimizerRule for Repartition {
         if config.concurrency == 1 {
             Ok(plan)
         } else {
-            optimize_concurrency(config.concurrency, true, plan)
+            optimize_concurrency(config, true, plan)
         }
     }
 
@@ -32,10 +32,12 @@ use super::{
     Accumulator, AggregateExpr, PhysicalExpr,
 };
 use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::scalar::ScalarValue;
 
 use crate::physical_plan::distinct_expressions;
 use crate::physical_plan::expressions;
-use arrow::datatypes::{DataType, Schema, TimeUnit};
+use arrow::datatypes::{DataType, Field, IntervalUnit, Schema, TimeUnit};
 use expressions::{avg_return_type, sum_return_type};
 use serde_derive::{Deserialize, Serialize};
 use std::{fmt, str::FromStr, sync::Arc};
@@ -61,12 +63,97 @@ pub enum AggregateFunction {
     Max,
     /// avg
     Avg,
+    /// any_value
+    AnyValue,
+    /// array_agg
+    ArrayAgg,
+    /// string_agg
+    StringAgg,
+    /// first
+    First,
+    /// last
+    Last,
+    /// regr_slope
+    RegrSlope,
+    /// regr_intercept
+    RegrIntercept,
+    /// regr_count
+    RegrCount,
+    /// regr_r2
+    RegrR2,
+    /// regr_avgx
+    RegrAvgx,
+    /// regr_avgy
+    RegrAvgy,
+    /// regr_sxx
+    RegrSxx,
+    /// regr_syy
+    RegrSyy,
+    /// regr_sxy
+    RegrSxy,
+    /// var_samp, or variance
+    VarSamp,
+    /// var_pop
+    VarPop,
+    /// stddev_samp, or stddev
+    StddevSamp,
+    /// stddev_pop
+    StddevPop,
+    /// covar_samp
+    CovarSamp,
+    /// covar_pop
+    CovarPop,
+    /// corr
+    Corr,
+    /// percentile_cont
+    PercentileCont,
+    /// percentile_disc
+    PercentileDisc,
+    /// approx_percentile_cont
+    ApproxPercentileCont,
+    /// approx_percentile_cont_digest
+    ApproxPercentileContDigest,
+    /// approx_percentile_cont_merge
+    ApproxPercentileContMerge,
+    /// grouping, which is only planned with GROUP BY
+    Grouping,
 }
 
 impl fmt::Display for AggregateFunction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // uppercase of the debug.
-        write!(f, "{}", format!("{:?}", self).to_uppercase())
+        match self {
+            AggregateFunction::AnyValue => write!(f, "ANY_VALUE"),
+            AggregateFunction::ArrayAgg => write!(f, "ARRAY_AGG"),
+            AggregateFunction::StringAgg => write!(f, "STRING_AGG"),
+            AggregateFunction::RegrSlope => write!(f, "REGR_SLOPE"),
+            AggregateFunction::RegrIntercept => write!(f, "REGR_INTERCEPT"),
+            AggregateFunction::RegrCount => write!(f, "REGR_COUNT"),
+            AggregateFunction::RegrR2 => write!(f, "REGR_R2"),
+            AggregateFunction::RegrAvgx => write!(f, "REGR_AVGX"),
+            AggregateFunction::RegrAvgy => write!(f, "REGR_AVGY"),
+            AggregateFunction::RegrSxx => write!(f, "REGR_SXX"),
+            AggregateFunction::RegrSyy => write!(f, "REGR_SYY"),
+            AggregateFunction::RegrSxy => write!(f, "REGR_SXY"),
+            AggregateFunction::VarSamp => write!(f, "VAR_SAMP"),
+            AggregateFunction::VarPop => write!(f, "VAR_POP"),
+            AggregateFunction::StddevSamp => write!(f, "STDDEV_SAMP"),
+            AggregateFunction::StddevPop => write!(f, "STDDEV_POP"),
+            AggregateFunction::CovarSamp => write!(f, "COVAR_SAMP"),
+            AggregateFunction::CovarPop => write!(f, "COVAR_POP"),
+            AggregateFunction::PercentileCont => write!(f, "PERCENTILE_CONT"),
+            AggregateFunction::PercentileDisc => write!(f, "PERCENTILE_DISC"),
+            AggregateFunction::ApproxPercentileCont => {
+                write!(f, "APPROX_PERCENTILE_CONT")
+            }
+            AggregateFunction::ApproxPercentileContDigest => {
+                write!(f, "APPROX_PERCENTILE_CONT_DIGEST")
+            }
+            AggregateFunction::ApproxPercentileContMerge => {
+                write!(f, "APPROX_PERCENTILE_CONT_MERGE")
+            }
+            // uppercase of the debug.
+            _ => write!(f, "{}", format!("{:?}", self).to_uppercase()),
+        }
     }
 }
 
@@ -79,6 +166,37 @@ impl FromStr for AggregateFunction {
             "count" => AggregateFunction::Count,
             "avg" => AggregateFunction::Avg,
             "sum" => AggregateFunction::Sum,
+            "any_value" => AggregateFunction::AnyValue,
+            "array_agg" => AggregateFunction::ArrayAgg,
+            "string_agg" => AggregateFunction::StringAgg,
+            "first" => AggregateFunction::First,
+            "last" => AggregateFunction::Last,
+            "regr_slope" => AggregateFunction::RegrSlope,
+            "regr_intercept" => AggregateFunction::RegrIntercept,
+            "regr_count" => AggregateFunction::RegrCount,
+            "regr_r2" => AggregateFunction::RegrR2,
+            "regr_avgx" => AggregateFunction::RegrAvgx,
+            "regr_avgy" => AggregateFunction::RegrAvgy,
+            "regr_sxx" => AggregateFunction::RegrSxx,
+            "regr_syy" => AggregateFunction::RegrSyy,
+            "regr_sxy" => AggregateFunction::RegrSxy,
+            "var_samp" | "variance" => AggregateFunction::VarSamp,
+            "var_pop" => AggregateFunction::VarPop,
+            "stddev_samp" | "stddev" => AggregateFunction::StddevSamp,
+            "stddev_pop" => AggregateFunction::StddevPop,
+            "covar_samp" => AggregateFunction::CovarSamp,
+            "covar_pop" => AggregateFunction::CovarPop,
+            "corr" => AggregateFunction::Corr,
+            "percentile_cont" => AggregateFunction::PercentileCont,
+            "percentile_disc" => AggregateFunction::PercentileDisc,
+            "approx_percentile_cont" => AggregateFunction::ApproxPercentileCont,
+            "approx_percentile_cont_digest" => {
+                AggregateFunction::ApproxPercentileContDigest
+            }
+            "approx_percentile_cont_merge" => {
+                AggregateFunction::ApproxPercentileContMerge
+            }
+            "grouping" => AggregateFunction::Grouping,
             _ => {
                 return Err(DataFusionError::Plan(format!(
                     "There is no built-in function named {}",
@@ -89,40 +207,157 @@ impl FromStr for AggregateFunction {
     }
 }
 
+/// Errors when `fun`, which takes any number of arguments, is called without any, as
+/// in `COUNT()`
+fn check_argument_count(fun: &AggregateFunction, count: usize) -> Result<()> {
+    if count == 0 && signature(fun) == Signature::VariadicAny {
+        return Err(DataFusionError::Plan(format!(
+            "{} requires at least one argument",
+            fun
+        )));
+    }
+    Ok(())
+}
+
 /// Returns the datatype of the scalar function
 pub fn return_type(fun: &AggregateFunction, arg_types: &[DataType]) -> Result<DataType> {
     // Note that this function *must* return the same type that the respective physical expression returns
     // or the execution panics.
 
-    // verify that this is a valid set of data types for this function
-    data_types(arg_types, &signature(fun))?;
+    check_argument_count(fun, arg_types.len())?;
+    // verify that this is a valid set of data types for this function, the return type
+    // is the one of the arguments coerced to them
+    let arg_types = &data_types(arg_types, &signature(fun))?;
 
     match fun {
         AggregateFunction::Count => Ok(DataType::UInt64),
-        AggregateFunction::Max | AggregateFunction::Min => Ok(arg_types[0].clone()),
+        AggregateFunction::Max | AggregateFunction::Min | AggregateFunction::AnyValue => {
+            Ok(arg_types[0].clone())
+        }
         AggregateFunction::Sum => sum_return_type(&arg_types[0]),
         AggregateFunction::Avg => avg_return_type(&arg_types[0]),
+        AggregateFunction::ArrayAgg => Ok(DataType::List(Box::new(Field::new(
+            "item",
+            arg_types[0].clone(),
+            true,
+        )))),
+        AggregateFunction::StringAgg => Ok(DataType::Utf8),
+        AggregateFunction::First | AggregateFunction::Last => Ok(arg_types[0].clone()),
+        AggregateFunction::RegrSlope
+        | AggregateFunction::RegrIntercept
+        | AggregateFunction::RegrCount
+        | AggregateFunction::RegrR2
+        | AggregateFunction::RegrAvgx
+        | AggregateFunction::RegrAvgy
+        | AggregateFunction::RegrSxx
+        | AggregateFunction::RegrSyy
+        | AggregateFunction::RegrSxy
+        | AggregateFunction::VarSamp
+        | AggregateFunction::VarPop
+        | AggregateFunction::StddevSamp
+        | AggregateFunction::StddevPop
+        | AggregateFunction::CovarSamp
+        | AggregateFunction::CovarPop
+        | AggregateFunction::Corr => {
+            Ok(expressions::regr_return_type(regr_type(fun).unwrap()))
+        }
+        AggregateFunction::PercentileCont => Ok(DataType::Float64),
+        AggregateFunction::PercentileDisc => Ok(arg_types[1].clone()),
+        AggregateFunction::ApproxPercentileCont => Ok(DataType::Float64),
+        AggregateFunction::ApproxPercentileContDigest => Ok(DataType::Binary),
+        // the merged digest, unless a fraction is given
+        AggregateFunction::ApproxPercentileContMerge if arg_types.len() == 1 => {
+            Ok(DataType::Binary)
+        }
+        AggregateFunction::ApproxPercentileContMerge => Ok(DataType::Float64),
+        AggregateFunction::Grouping => Ok(DataType::Int32),
     }
 }
 
+/// Whether `fun` is an ordered-set aggregate function, such as PERCENTILE_CONT, that
+/// aggregates the values of its `WITHIN GROUP (ORDER BY expr)` clause. In their
+/// signatures, the type of `expr` follows the ones of the direct arguments.
+pub fn is_ordered_set(fun: &AggregateFunction) -> bool {
+    matches!(
+        fun,
+        AggregateFunction::PercentileCont | AggregateFunction::PercentileDisc
+    )
+}
+
+/// Whether `fun` depends on the order of its input rows so much that it requires an
+/// `ORDER BY` clause, such as FIRST and LAST, whose result would be arbitrary without it
+pub fn requires_order_by(fun: &AggregateFunction) -> bool {
+    matches!(fun, AggregateFunction::First | AggregateFunction::Last)
+}
+
+/// The statistic computed by the REGR_* or moment function `fun`, such as VAR_SAMP,
+/// if it is one
+fn regr_type(fun: &AggregateFunction) -> Option<expressions::RegrType> {
+    use expressions::RegrType;
+    Some(match fun {
+        AggregateFunction::RegrSlope => RegrType::Slope,
+        AggregateFunction::RegrIntercept => RegrType::Intercept,
+        AggregateFunction::RegrCount => RegrType::Count,
+        AggregateFunction::RegrR2 => RegrType::R2,
+        AggregateFunction::RegrAvgx => RegrType::AvgX,
+        AggregateFunction::RegrAvgy => RegrType::AvgY,
+        AggregateFunction::RegrSxx => RegrType::SXX,
+        AggregateFunction::RegrSyy => RegrType::SYY,
+        AggregateFunction::RegrSxy => RegrType::SXY,
+        AggregateFunction::VarSamp => RegrType::VarSamp,
+        AggregateFunction::VarPop => RegrType::VarPop,
+        AggregateFunction::StddevSamp => RegrType::StddevSamp,
+        AggregateFunction::StddevPop => RegrType::StddevPop,
+        AggregateFunction::CovarSamp => RegrType::CovarSamp,
+        AggregateFunction::CovarPop => RegrType::CovarPop,
+        AggregateFunction::Corr => RegrType::Corr,
+        _ => return None,
+    })
+}
+
 /// Create a physical (function) expression.
 /// This function errors when `args`' can't be coerced to a valid argument type of the function.
+/// `ignore_nulls` only affects functions that would otherwise collect NULL values,
+/// such as ARRAY_AGG. `order_by` is the order of the input rows, it is ignored by
+/// the functions whose result does not depend on it. For ordered-set functions, it is
+/// the `WITHIN GROUP` clause, whose expression is aggregated after the `args`. FIRST
+/// and LAST require it, their result would be arbitrary otherwise.
 pub fn create_aggregate_expr(
     fun: &AggregateFunction,
     distinct: bool,
+    ignore_nulls: bool,
     args: &[Arc<dyn PhysicalExpr>],
+    order_by: &[PhysicalSortExpr],
     input_schema: &Schema,
     name: impl Into<String>,
 ) -> Result<Arc<dyn AggregateExpr>> {
     let name = name.into();
-    let arg = coerce(args, input_schema, &signature(fun))?;
-    if arg.is_empty() {
+    let args = if is_ordered_set(fun) {
+        if order_by.len() != 1 {
+            return Err(DataFusionError::Plan(format!(
+                "{} requires WITHIN GROUP (ORDER BY ...) with a single expression",
+                fun
+            )));
+        }
+        args.iter()
+            .cloned()
+            .chain(order_by.iter().map(|e| e.expr.clone()))
+            .collect::<Vec<_>>()
+    } else {
+        args.to_vec()
+    };
+    if requires_order_by(fun) && order_by.is_empty() {
+        return Err(DataFusionError::Plan(format!("{} requires ORDER BY", fun)));
+    }
+    check_argument_count(fun, args.len())?;
+    let coerced_args = coerce(&args, input_schema, &signature(fun))?;
+    if coerced_args.is_empty() {
         return Err(DataFusionError::Plan(format!(
             "Invalid or wrong number of arguments passed to aggregate: '{}'",
             name,
         )));
     }
-    let arg = arg[0].clone();
+    let arg = coerced_args[0].clone();
 
     let arg_types = args
         .iter()
@@ -132,6 +367,12 @@ pub fn create_aggregate_expr(
     let return_type = return_type(fun, &arg_types)?;
 
     Ok(match (fun, distinct) {
+        (AggregateFunction::Count, false) if args.len() > 1 => {
+            return Err(DataFusionError::Plan(format!(
+                "COUNT of several expressions is only valid with DISTINCT: '{}'",
+                name
+            )));
+        }
         (AggregateFunction::Count, false) => {
             Arc::new(expressions::Count::new(arg, name, return_type))
         }
@@ -143,6 +384,9 @@ pub fn create_aggregate_expr(
                 return_type,
             ))
         }
+        (AggregateFunction::Sum, false) if is_temporal(&return_type) => {
+            Arc::new(expressions::TemporalSum::new(arg, name, return_type))
+        }
         (AggregateFunction::Sum, false) => {
             Arc::new(expressions::Sum::new(arg, name, return_type))
         }
@@ -157,17 +401,235 @@ pub fn create_aggregate_expr(
         (AggregateFunction::Max, _) => {
             Arc::new(expressions::Max::new(arg, name, return_type))
         }
+        (AggregateFunction::Avg, false) if is_temporal(&return_type) => {
+            Arc::new(expressions::TemporalSum::new_avg(arg, name, return_type))
+        }
         (AggregateFunction::Avg, false) => {
             Arc::new(expressions::Avg::new(arg, name, return_type))
         }
+        // DISTINCT does not change the values that may be returned
+        (AggregateFunction::AnyValue, _) => {
+            Arc::new(expressions::AnyValue::new(arg, name, return_type))
+        }
         (AggregateFunction::Avg, true) => {
             return Err(DataFusionError::NotImplemented(
                 "AVG(DISTINCT) aggregations are not available".to_string(),
             ));
         }
+        (AggregateFunction::ArrayAgg, false) => {
+            let mut array_agg =
+                expressions::ArrayAgg::new(arg, name, arg_types[0].clone())
+                    .with_ignore_nulls(ignore_nulls)
+                    .with_order_by(order_by.to_vec(), input_schema)?;
+            if let Some(limit) = args.get(1) {
+                if let Some(limit) = array_agg_limit(limit)? {
+                    array_agg = array_agg.with_limit(limit);
+                }
+            }
+            Arc::new(array_agg)
+        }
+        (AggregateFunction::ArrayAgg, true) => {
+            return Err(DataFusionError::NotImplemented(
+                "ARRAY_AGG(DISTINCT) aggregations are not available".to_string(),
+            ));
+        }
+        (AggregateFunction::StringAgg, false) => {
+            let delimiter = string_agg_delimiter(&coerced_args[1])?;
+            Arc::new(
+                expressions::StringAgg::new(arg, delimiter, name)
+                    .with_order_by(order_by.to_vec(), input_schema)?,
+            )
+        }
+        (AggregateFunction::StringAgg, true) => {
+            return Err(DataFusionError::NotImplemented(
+                "STRING_AGG(DISTINCT) aggregations are not available".to_string(),
+            ));
+        }
+        // DISTINCT does not change the values that may be returned
+        (AggregateFunction::First | AggregateFunction::Last, _) => {
+            let first_last_type = match fun {
+                AggregateFunction::First => expressions::FirstLastType::First,
+                _ => expressions::FirstLastType::Last,
+            };
+            Arc::new(
+                expressions::FirstLast::new(arg, name, return_type, first_last_type)
+                    .with_ignore_nulls(ignore_nulls)
+                    .with_order_by(order_by.to_vec(), input_schema)?,
+            )
+        }
+        (
+            AggregateFunction::RegrSlope
+            | AggregateFunction::RegrIntercept
+            | AggregateFunction::RegrCount
+            | AggregateFunction::RegrR2
+            | AggregateFunction::RegrAvgx
+            | AggregateFunction::RegrAvgy
+            | AggregateFunction::RegrSxx
+            | AggregateFunction::RegrSyy
+            | AggregateFunction::RegrSxy
+            | AggregateFunction::VarSamp
+            | AggregateFunction::VarPop
+            | AggregateFunction::StddevSamp
+            | AggregateFunction::StddevPop
+            | AggregateFunction::CovarSamp
+            | AggregateFunction::CovarPop
+            | AggregateFunction::Corr,
+            distinct,
+        ) => {
+            if distinct {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "{}(DISTINCT) aggregations are not available",
+                    fun
+                )));
+            }
+            let regr_type = regr_type(fun).unwrap();
+            if coerced_args.len() == 1 {
+                Arc::new(expressions::Regr::new_univariate(arg, name, regr_type))
+            } else {
+                Arc::new(expressions::Regr::new(
+                    arg,
+                    coerced_args[1].clone(),
+                    name,
+                    regr_type,
+                ))
+            }
+        }
+        (AggregateFunction::PercentileCont | AggregateFunction::PercentileDisc, true) => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "{}(DISTINCT) aggregations are not available",
+                fun
+            )));
+        }
+        (
+            AggregateFunction::PercentileCont | AggregateFunction::PercentileDisc,
+            false,
+        ) => {
+            let percentile_type = match fun {
+                AggregateFunction::PercentileCont => {
+                    expressions::PercentileType::Continuous
+                }
+                _ => expressions::PercentileType::Discrete,
+            };
+            Arc::new(expressions::Percentile::new(
+                PhysicalSortExpr {
+                    expr: coerced_args[1].clone(),
+                    options: order_by[0].options,
+                },
+                percentile_fraction(fun, &args[0])?,
+                percentile_type,
+                name,
+                return_type,
+            ))
+        }
+        (
+            AggregateFunction::ApproxPercentileCont
+            | AggregateFunction::ApproxPercentileContDigest
+            | AggregateFunction::ApproxPercentileContMerge,
+            true,
+        ) => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "{}(DISTINCT) aggregations are not available",
+                fun
+            )));
+        }
+        (
+            AggregateFunction::ApproxPercentileCont
+            | AggregateFunction::ApproxPercentileContDigest
+            | AggregateFunction::ApproxPercentileContMerge,
+            false,
+        ) => {
+            let mut approx_percentile = expressions::ApproxPercentile::new(arg, name)
+                .with_merge(*fun == AggregateFunction::ApproxPercentileContMerge);
+            if let Some(fraction) = args.get(1) {
+                approx_percentile =
+                    approx_percentile.with_fraction(percentile_fraction(fun, fraction)?);
+            }
+            Arc::new(approx_percentile)
+        }
+        // the SQL planner replaces GROUPING by the grouping ids of the grouping sets
+        (AggregateFunction::Grouping, _) => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "GROUPING is only supported in a GROUP BY query: '{}'",
+                name
+            )));
+        }
     })
 }
 
+/// The fraction of PERCENTILE_CONT, PERCENTILE_DISC and APPROX_PERCENTILE_CONT,
+/// which must be a constant between 0 and 1.
+fn percentile_fraction(
+    fun: &AggregateFunction,
+    fraction: &Arc<dyn PhysicalExpr>,
+) -> Result<f64> {
+    let value = match fraction
+        .as_any()
+        .downcast_ref::<expressions::Literal>()
+        .map(|l| l.value())
+    {
+        Some(ScalarValue::Float64(v)) => *v,
+        Some(ScalarValue::Float32(v)) => v.map(f64::from),
+        Some(ScalarValue::Int64(v)) => v.map(|v| v as f64),
+        Some(ScalarValue::Int32(v)) => v.map(f64::from),
+        _ => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "{} fraction must be a numeric literal, got {}",
+                fun, fraction
+            )))
+        }
+    };
+    match value {
+        Some(v) if (0.0..=1.0).contains(&v) => Ok(v),
+        _ => Err(DataFusionError::Plan(format!(
+            "{} fraction must be between 0 and 1, got {}",
+            fun, fraction
+        ))),
+    }
+}
+
+/// The maximum number of values of ARRAY_AGG, which must be a non-negative integer
+/// literal. There is no limit when it is NULL.
+fn array_agg_limit(limit: &Arc<dyn PhysicalExpr>) -> Result<Option<usize>> {
+    let value = match limit
+        .as_any()
+        .downcast_ref::<expressions::Literal>()
+        .map(|l| l.value())
+    {
+        Some(ScalarValue::Int64(v)) => *v,
+        Some(ScalarValue::Int32(v)) => v.map(i64::from),
+        Some(ScalarValue::UInt32(v)) => v.map(i64::from),
+        _ => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "ARRAY_AGG limit must be an integer literal, got {}",
+                limit
+            )))
+        }
+    };
+    match value {
+        Some(v) if v < 0 => Err(DataFusionError::Plan(format!(
+            "ARRAY_AGG limit must not be negative, got {}",
+            limit
+        ))),
+        v => Ok(v.map(|v| v as usize)),
+    }
+}
+
+/// The delimiter of STRING_AGG, which must be a constant. A NULL delimiter
+/// concatenates the values without separating them, as in Postgres.
+fn string_agg_delimiter(delimiter: &Arc<dyn PhysicalExpr>) -> Result<String> {
+    match delimiter
+        .as_any()
+        .downcast_ref::<expressions::Literal>()
+        .map(|l| l.value())
+    {
+        Some(ScalarValue::Utf8(delimiter)) => Ok(delimiter.clone().unwrap_or_default()),
+        _ => Err(DataFusionError::NotImplemented(format!(
+            "STRING_AGG delimiter must be a string literal, got {}",
+            delimiter
+        ))),
+    }
+}
+
 static STRINGS: &[DataType] = &[DataType::Utf8, DataType::LargeUtf8];
 
 static NUMERICS: &[DataType] = &[
@@ -198,6 +660,22 @@ static NUMERICS: &[DataType] = &[
     DataType::Float64,
 ];
 
+/// Item types that lists produced by ARRAY_AGG can hold
+static LIST_ITEMS: &[DataType] = &[
+    DataType::Int8,
+    DataType::Int16,
+    DataType::Int32,
+    DataType::Int64,
+    DataType::UInt8,
+    DataType::UInt16,
+    DataType::UInt32,
+    DataType::UInt64,
+    DataType::Float32,
+    DataType::Float64,
+    DataType::Utf8,
+    DataType::LargeUtf8,
+];
+
 static TIMESTAMPS: &[DataType] = &[
     DataType::Timestamp(TimeUnit::Second, None),
     DataType::Timestamp(TimeUnit::Millisecond, None),
@@ -205,11 +683,32 @@ static TIMESTAMPS: &[DataType] = &[
     DataType::Timestamp(TimeUnit::Nanosecond, None),
 ];
 
+static INTERVALS: &[DataType] = &[
+    DataType::Interval(IntervalUnit::YearMonth),
+    DataType::Interval(IntervalUnit::DayTime),
+];
+
+/// Whether the SUM or AVG of values of `data_type` is a [expressions::TemporalSum]
+fn is_temporal(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Interval(_) | DataType::Timestamp(_, _))
+}
+
+/// The types of the arguments of the REGR_* and moment functions: the floats, to
+/// which the other numbers are coerced, and the decimals
+fn moment_types() -> impl Iterator<Item = DataType> {
+    let decimals = NUMERICS
+        .iter()
+        .filter(|t| matches!(t, DataType::Int64Decimal(_) | DataType::Int96Decimal(_)));
+    std::iter::once(DataType::Float64).chain(decimals.cloned())
+}
+
 /// the signatures supported by the function `fun`.
 pub fn signature(fun: &AggregateFunction) -> Signature {
     // note: the physical expression must accept the type returned by this function or the execution panics.
     match fun {
-        AggregateFunction::Count => Signature::Any(1),
+        // several arguments are only valid for COUNT(DISTINCT), which counts the
+        // distinct rows of their values
+        AggregateFunction::Count => Signature::VariadicAny,
         AggregateFunction::Min | AggregateFunction::Max => {
             let valid = STRINGS
                 .iter()
@@ -219,9 +718,87 @@ pub fn signature(fun: &AggregateFunction) -> Signature {
                 .collect::<Vec<_>>();
             Signature::Uniform(1, valid)
         }
-        AggregateFunction::Avg | AggregateFunction::Sum => {
-            Signature::Uniform(1, NUMERICS.to_vec())
+        AggregateFunction::Sum => {
+            let valid = NUMERICS
+                .iter()
+                .chain(INTERVALS.iter())
+                .cloned()
+                .collect::<Vec<_>>();
+            Signature::Uniform(1, valid)
         }
+        AggregateFunction::Avg => {
+            let valid = NUMERICS
+                .iter()
+                .chain(INTERVALS.iter())
+                .chain(TIMESTAMPS.iter())
+                .cloned()
+                .collect::<Vec<_>>();
+            Signature::Uniform(1, valid)
+        }
+        // the values followed by the optional maximum number of them
+        AggregateFunction::AnyValue => Signature::Any(1),
+        AggregateFunction::ArrayAgg => Signature::OneOf(
+            std::iter::once(Signature::Uniform(1, LIST_ITEMS.to_vec()))
+                .chain(
+                    LIST_ITEMS
+                        .iter()
+                        .map(|t| Signature::Exact(vec![t.clone(), DataType::Int64])),
+                )
+                .collect(),
+        ),
+        AggregateFunction::StringAgg => {
+            Signature::Exact(vec![DataType::Utf8, DataType::Utf8])
+        }
+        AggregateFunction::First | AggregateFunction::Last => Signature::Any(1),
+        // the decimals are not coerced to floats, their accumulator converts them
+        AggregateFunction::VarSamp
+        | AggregateFunction::VarPop
+        | AggregateFunction::StddevSamp
+        | AggregateFunction::StddevPop => Signature::Uniform(1, moment_types().collect()),
+        // the dependent variable y followed by the independent variable x
+        AggregateFunction::RegrSlope
+        | AggregateFunction::RegrIntercept
+        | AggregateFunction::RegrCount
+        | AggregateFunction::RegrR2
+        | AggregateFunction::RegrAvgx
+        | AggregateFunction::RegrAvgy
+        | AggregateFunction::RegrSxx
+        | AggregateFunction::RegrSyy
+        | AggregateFunction::RegrSxy
+        | AggregateFunction::CovarSamp
+        | AggregateFunction::CovarPop
+        | AggregateFunction::Corr => Signature::OneOf(
+            moment_types()
+                .flat_map(|y| {
+                    moment_types().map(move |x| Signature::Exact(vec![y.clone(), x]))
+                })
+                .collect(),
+        ),
+        // the fraction followed by the values of the WITHIN GROUP clause
+        AggregateFunction::PercentileCont => {
+            Signature::Exact(vec![DataType::Float64, DataType::Float64])
+        }
+        AggregateFunction::PercentileDisc => Signature::OneOf(
+            LIST_ITEMS
+                .iter()
+                .chain(TIMESTAMPS.iter())
+                .map(|t| Signature::Exact(vec![DataType::Float64, t.clone()]))
+                .collect(),
+        ),
+        // the values followed by the fraction
+        AggregateFunction::ApproxPercentileCont => {
+            Signature::Exact(vec![DataType::Float64, DataType::Float64])
+        }
+        AggregateFunction::ApproxPercentileContDigest => {
+            Signature::Exact(vec![DataType::Float64])
+        }
+        // the serialized digests followed by the optional fraction
+        AggregateFunction::ApproxPercentileContMerge => Signature::OneOf(vec![
+            Signature::Exact(vec![DataType::Binary]),
+            Signature::Exact(vec![DataType::Binary, DataType::Float64]),
+        ]),
+        // the keys of the GROUP BY
+        AggregateFunction::Grouping => Signature::VariadicAny,
     }
 }
 
@@ -250,9 +827,28 @@ mod tests {
     fn test_sum_upcasts() -> Result<()> {
         let observed = return_type(&AggregateFunction::Sum, &[DataType::UInt32])?;
         assert_eq!(DataType::UInt64, observed);
+
+        let observed = return_type(&AggregateFunction::Sum, &[DataType::Int8])?;
+        assert_eq!(DataType::Int64, observed);
+
+        let observed = return_type(&AggregateFunction::Sum, &[DataType::Float32])?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed =
+            return_type(&AggregateFunction::Sum, &[DataType::Int64Decimal(2)])?;
+        assert_eq!(DataType::Int96Decimal(2), observed);
         Ok(())
     }
 
+    #[test]
+    fn test_count_without_arguments() {
+        let err = return_type(&AggregateFunction::Count, &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: COUNT requires at least one argument"
+        );
+    }
+
     #[test]
     fn test_count_return_type() -> Result<()> {
         let observed = return_type(&AggregateFunction::Count, &[DataType::Utf8])?;
@@ -260,6 +856,10 @@ mod tests {
 
         let observed = return_type(&AggregateFunction::Count, &[DataType::Int8])?;
         assert_eq!(DataType::UInt64, observed);
+
+        let observed =
+            return_type(&AggregateFunction::Count, &[DataType::Int8, DataType::Utf8])?;
+        assert_eq!(DataType::UInt64, observed);
         Ok(())
     }
 
@@ -270,6 +870,154 @@ mod tests {
 
         let observed = return_type(&AggregateFunction::Avg, &[DataType::Float64])?;
         assert_eq!(DataType::Float64, observed);
+
+        let observed =
+            return_type(&AggregateFunction::Avg, &[DataType::Int64Decimal(2)])?;
+        assert_eq!(DataType::Int96Decimal(2), observed);
+
+        let observed = return_type(&AggregateFunction::Avg, &[DataType::Int96])?;
+        assert_eq!(DataType::Int96, observed);
+
+        let timestamp = DataType::Timestamp(TimeUnit::Second, None);
+        let observed = return_type(&AggregateFunction::Avg, &[timestamp.clone()])?;
+        assert_eq!(timestamp, observed);
+
+        let interval = DataType::Interval(IntervalUnit::DayTime);
+        let observed = return_type(&AggregateFunction::Avg, &[interval.clone()])?;
+        assert_eq!(interval, observed);
+        let observed = return_type(&AggregateFunction::Sum, &[interval.clone()])?;
+        assert_eq!(interval, observed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_agg_return_type() -> Result<()> {
+        let observed = return_type(&AggregateFunction::ArrayAgg, &[DataType::Utf8])?;
+        assert_eq!(
+            DataType::List(Box::new(Field::new("item", DataType::Utf8, true))),
+            observed
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_agg_return_type() -> Result<()> {
+        let observed = return_type(
+            &AggregateFunction::StringAgg,
+            &[DataType::Utf8, DataType::Utf8],
+        )?;
+        assert_eq!(DataType::Utf8, observed);
+
+        let observed = return_type(&AggregateFunction::StringAgg, &[DataType::Utf8]);
+        assert!(observed.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_first_last_return_type() -> Result<()> {
+        let observed = return_type(&AggregateFunction::First, &[DataType::Utf8])?;
+        assert_eq!(DataType::Utf8, observed);
+
+        let timestamp = DataType::Timestamp(TimeUnit::Millisecond, None);
+        let observed = return_type(&AggregateFunction::Last, &[timestamp.clone()])?;
+        assert_eq!(timestamp, observed);
+
+        let observed = return_type(
+            &AggregateFunction::Last,
+            &[DataType::Int32, DataType::Int32],
+        );
+        assert!(observed.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_percentile_return_type() -> Result<()> {
+        let observed = return_type(
+            &AggregateFunction::PercentileCont,
+            &[DataType::Float64, DataType::Int32],
+        )?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(
+            &AggregateFunction::PercentileDisc,
+            &[DataType::Int64, DataType::Int32],
+        )?;
+        assert_eq!(DataType::Int32, observed);
+
+        let timestamp = DataType::Timestamp(TimeUnit::Nanosecond, None);
+        let observed = return_type(
+            &AggregateFunction::PercentileDisc,
+            &[DataType::Float64, timestamp.clone()],
+        )?;
+        assert_eq!(timestamp, observed);
+
+        let observed = return_type(
+            &AggregateFunction::PercentileCont,
+            &[DataType::Float64, DataType::Utf8],
+        );
+        assert!(observed.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_approx_percentile_return_type() -> Result<()> {
+        let observed = return_type(
+            &AggregateFunction::ApproxPercentileCont,
+            &[DataType::Int32, DataType::Float64],
+        )?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(
+            &AggregateFunction::ApproxPercentileContDigest,
+            &[DataType::UInt32],
+        )?;
+        assert_eq!(DataType::Binary, observed);
+
+        let observed = return_type(
+            &AggregateFunction::ApproxPercentileContMerge,
+            &[DataType::Binary],
+        )?;
+        assert_eq!(DataType::Binary, observed);
+
+        let observed = return_type(
+            &AggregateFunction::ApproxPercentileContMerge,
+            &[DataType::Binary, DataType::Float64],
+        )?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(
+            &AggregateFunction::ApproxPercentileContMerge,
+            &[DataType::Float64, DataType::Float64],
+        );
+        assert!(observed.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_moment_return_type() -> Result<()> {
+        let observed = return_type(&AggregateFunction::VarSamp, &[DataType::Int32])?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed =
+            return_type(&AggregateFunction::StddevPop, &[DataType::Int96Decimal(10)])?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(
+            &AggregateFunction::Corr,
+            &[DataType::Int64Decimal(2), DataType::Int64],
+        )?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(
+            &AggregateFunction::RegrCount,
+            &[DataType::Float64, DataType::Int64Decimal(5)],
+        )?;
+        assert_eq!(DataType::UInt64, observed);
+
+        let observed = return_type(&AggregateFunction::VarPop, &[DataType::Utf8]);
+        assert!(observed.is_err());
+        let observed = return_type(&AggregateFunction::CovarPop, &[DataType::Float64]);
+        assert!(observed.is_err());
         Ok(())
     }
 
@@ -0,0 +1,294 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the ANALYZE plan, which computes the column statistics of a table and
+//! stores them in the catalog
+
+use std::any::Any;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use ahash::RandomState;
+use arrow::array::{ArrayRef, StringArray, UInt64Array};
+use arrow::datatypes::{Field, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use futures::StreamExt;
+
+use crate::catalog::schema::SchemaProvider;
+use crate::datasource::analyzed::AnalyzedTable;
+use crate::datasource::datasource::{ColumnStatistics, Statistics};
+use crate::datasource::TableProvider;
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::LogicalPlan;
+use crate::physical_plan::common::SizedRecordBatchStream;
+use crate::physical_plan::expressions::{col, Max, Min};
+use crate::physical_plan::hash_utils::create_hashes;
+use crate::physical_plan::{
+    Accumulator, AggregateExpr, DisplayFormatType, Distribution, ExecutionPlan,
+    Partitioning, SendableRecordBatchStream,
+};
+use crate::scalar::ScalarValue;
+
+/// Execution plan computing the statistics of the rows of a table scan and
+/// registering the table in its schema with these statistics, see
+/// [`AnalyzedTable`]. It produces a row with the statistics of every column.
+pub struct AnalyzeExec {
+    table_name: String,
+    table: Arc<dyn TableProvider>,
+    schema_provider: Arc<dyn SchemaProvider>,
+    input: Arc<dyn ExecutionPlan>,
+}
+
+impl AnalyzeExec {
+    /// Create a new AnalyzeExec for `table`, registered as `table_name` in
+    /// `schema_provider`. The input scans all the columns of the table.
+    pub fn new(
+        table_name: String,
+        table: Arc<dyn TableProvider>,
+        schema_provider: Arc<dyn SchemaProvider>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Self {
+        Self {
+            table_name,
+            table,
+            schema_provider,
+            input,
+        }
+    }
+
+    /// Name of the table
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+}
+
+impl fmt::Debug for AnalyzeExec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AnalyzeExec")
+            .field("table_name", &self.table_name)
+            .field("input", &self.input)
+            .finish()
+    }
+}
+
+/// Statistics of a column, updated with every batch of the table
+struct ColumnAnalyzer {
+    null_count: usize,
+    /// `None` for the types without an order
+    min: Option<Box<dyn Accumulator>>,
+    max: Option<Box<dyn Accumulator>>,
+    /// The hashes of the distinct values, `None` for the types that can not be hashed
+    hashes: Option<HashSet<u64>>,
+}
+
+impl ColumnAnalyzer {
+    fn new(field: &Field, input_schema: &SchemaRef) -> Result<Self> {
+        let expr = col(field.name(), input_schema)?;
+        let data_type = field.data_type().clone();
+        Ok(Self {
+            null_count: 0,
+            min: Min::new(expr.clone(), field.name(), data_type.clone())
+                .create_accumulator()
+                .ok(),
+            max: Max::new(expr, field.name(), data_type)
+                .create_accumulator()
+                .ok(),
+            hashes: Some(HashSet::new()),
+        })
+    }
+
+    fn update(&mut self, array: &ArrayRef, random_state: &RandomState) {
+        self.null_count += array.null_count();
+        for accumulator in [&mut self.min, &mut self.max] {
+            if let Some(a) = accumulator {
+                if a.update_batch(&[array.clone()]).is_err() {
+                    *accumulator = None;
+                }
+            }
+        }
+        if let Some(hashes) = &mut self.hashes {
+            let mut buffer = vec![0; array.len()];
+            match create_hashes(&[array.clone()], random_state, &mut buffer) {
+                Ok(buffer) => hashes.extend(
+                    buffer
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| array.is_valid(*i))
+                        .map(|(_, hash)| *hash),
+                ),
+                Err(_) => self.hashes = None,
+            }
+        }
+    }
+
+    fn finish(&self) -> ColumnStatistics {
+        let value = |accumulator: &Option<Box<dyn Accumulator>>| {
+            accumulator
+                .as_ref()
+                .and_then(|a| a.evaluate().ok())
+                .filter(|v| !v.is_null())
+        };
+        ColumnStatistics {
+            null_count: Some(self.null_count),
+            max_value: value(&self.max),
+            min_value: value(&self.min),
+            // hash collisions are rare enough for an estimate
+            distinct_count: self.hashes.as_ref().map(|h| h.len()),
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for AnalyzeExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        LogicalPlan::analyze_schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::SinglePartition
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(AnalyzeExec::new(
+                self.table_name.clone(),
+                self.table.clone(),
+                self.schema_provider.clone(),
+                children[0].clone(),
+            ))),
+            _ => Err(DataFusionError::Internal(
+                "AnalyzeExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "AnalyzeExec invalid partition {}",
+                partition
+            )));
+        }
+        if self.input.output_partitioning().partition_count() != 1 {
+            return Err(DataFusionError::Internal(
+                "AnalyzeExec requires a single input partition".to_string(),
+            ));
+        }
+
+        let input_schema = self.input.schema();
+        let mut columns = input_schema
+            .fields()
+            .iter()
+            .map(|f| ColumnAnalyzer::new(f, &input_schema))
+            .collect::<Result<Vec<_>>>()?;
+        let random_state = RandomState::with_seeds(0, 0, 0, 0);
+        let mut num_rows = 0;
+        let mut stream = self.input.execute(0).await?;
+        while let Some(batch) = stream.next().await {
+            let batch = batch?;
+            num_rows += batch.num_rows();
+            for (column, array) in columns.iter_mut().zip(batch.columns()) {
+                column.update(array, &random_state);
+            }
+        }
+
+        let column_statistics = columns.iter().map(|c| c.finish()).collect::<Vec<_>>();
+        let to_string =
+            |value: &Option<ScalarValue>| value.as_ref().map(|v| v.to_string());
+        let batch = RecordBatch::try_new(
+            self.schema(),
+            vec![
+                Arc::new(
+                    input_schema
+                        .fields()
+                        .iter()
+                        .map(|f| Some(f.name().as_str()))
+                        .collect::<StringArray>(),
+                ),
+                Arc::new(
+                    column_statistics
+                        .iter()
+                        .map(|s| s.null_count.map(|c| c as u64))
+                        .collect::<UInt64Array>(),
+                ),
+                Arc::new(
+                    column_statistics
+                        .iter()
+                        .map(|s| s.distinct_count.map(|c| c as u64))
+                        .collect::<UInt64Array>(),
+                ),
+                Arc::new(
+                    column_statistics
+                        .iter()
+                        .map(|s| to_string(&s.min_value))
+                        .collect::<StringArray>(),
+                ),
+                Arc::new(
+                    column_statistics
+                        .iter()
+                        .map(|s| to_string(&s.max_value))
+                        .collect::<StringArray>(),
+                ),
+            ],
+        )?;
+
+        let statistics = Statistics {
+            num_rows: Some(num_rows),
+            total_byte_size: None,
+            column_statistics: Some(column_statistics),
+        };
+        self.schema_provider.register_table(
+            self.table_name.clone(),
+            Arc::new(AnalyzedTable::new(self.table.clone(), statistics)),
+        )?;
+
+        Ok(Box::pin(SizedRecordBatchStream::new(
+            self.schema(),
+            vec![Arc::new(batch)],
+        )))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(f, "AnalyzeExec: table={}", self.table_name)
+            }
+        }
+    }
+}
@@ -19,6 +19,7 @@
 
 use crate::error::{DataFusionError, Result};
 use arrow::array::*;
+use arrow::compute;
 use arrow::datatypes::DataType;
 use std::sync::Arc;
 
@@ -151,6 +152,117 @@ pub fn array(values: &[ColumnarValue]) -> Result<ColumnarValue> {
     Ok(ColumnarValue::Array(array_array(&arrays)?))
 }
 
+/// The values of all the lists of a `List` or `FixedSizeList` array, along with the
+/// offsets of each list in them, such that the list `i` is at `offsets[i]` to
+/// `offsets[i + 1]`
+pub(crate) fn list_values_and_offsets(array: &ArrayRef) -> Result<(ArrayRef, Vec<i32>)> {
+    if let Some(list) = array.as_any().downcast_ref::<ListArray>() {
+        return Ok((list.values(), list.value_offsets().to_vec()));
+    }
+    if let Some(list) = array.as_any().downcast_ref::<FixedSizeListArray>() {
+        let offsets = (0..=list.len())
+            .map(|i| list.value_offset(0) + i as i32 * list.value_length())
+            .collect();
+        return Ok((list.values(), offsets));
+    }
+    Err(DataFusionError::Internal(format!(
+        "Expected a list, got {:?}",
+        array.data_type()
+    )))
+}
+
+/// Casts an argument that is either an untyped NULL or a string to Utf8
+fn utf8_arg(array: &ArrayRef) -> Result<ArrayRef> {
+    Ok(match array.data_type() {
+        DataType::Null => new_null_array(&DataType::Utf8, array.len()),
+        _ => compute::cast(array, &DataType::Utf8)?,
+    })
+}
+
+fn downcast_utf8(array: &ArrayRef) -> &StringArray {
+    array.as_any().downcast_ref::<StringArray>().unwrap()
+}
+
+/// STRING_TO_ARRAY SQL function: splits a string on a delimiter into a list of
+/// strings, like PostgreSQL. A NULL delimiter splits the string into characters, and
+/// an empty one does not split it. Elements equal to the optional third argument are
+/// NULL.
+pub fn string_to_array(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let strings = utf8_arg(&args[0])?;
+    let strings = downcast_utf8(&strings);
+    let delimiters = utf8_arg(&args[1])?;
+    let delimiters = downcast_utf8(&delimiters);
+    let null_strings = args.get(2).map(utf8_arg).transpose()?;
+    let null_strings = null_strings.as_ref().map(downcast_utf8);
+
+    let mut builder = ListBuilder::new(StringBuilder::new(strings.len()));
+    for i in 0..strings.len() {
+        if strings.is_null(i) {
+            builder.append(false)?;
+            continue;
+        }
+        let string = strings.value(i);
+        let null_string = null_strings.filter(|n| n.is_valid(i)).map(|n| n.value(i));
+        let mut append = |element: &str| -> Result<()> {
+            if Some(element) == null_string {
+                builder.values().append_null()?;
+            } else {
+                builder.values().append_value(element)?;
+            }
+            Ok(())
+        };
+        if string.is_empty() {
+            // no elements
+        } else if delimiters.is_null(i) {
+            let mut chars = [0; 4];
+            for c in string.chars() {
+                append(c.encode_utf8(&mut chars))?;
+            }
+        } else if delimiters.value(i).is_empty() {
+            append(string)?;
+        } else {
+            for element in string.split(delimiters.value(i)) {
+                append(element)?;
+            }
+        }
+        builder.append(true)?;
+    }
+    Ok(Arc::new(builder.finish()))
+}
+
+/// ARRAY_TO_STRING SQL function: joins the elements of a list, converted to strings,
+/// with a delimiter. NULL elements are skipped, or replaced by the optional third
+/// argument.
+pub fn array_to_string(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let (values, offsets) = list_values_and_offsets(&args[0])?;
+    let values = compute::cast(&values, &DataType::Utf8)?;
+    let values = downcast_utf8(&values);
+    let delimiters = utf8_arg(&args[1])?;
+    let delimiters = downcast_utf8(&delimiters);
+    let null_strings = args.get(2).map(utf8_arg).transpose()?;
+    let null_strings = null_strings.as_ref().map(downcast_utf8);
+
+    let mut builder = StringBuilder::new(args[0].len());
+    for i in 0..args[0].len() {
+        if args[0].is_null(i) || delimiters.is_null(i) {
+            builder.append_null()?;
+            continue;
+        }
+        let null_string = null_strings.filter(|n| n.is_valid(i)).map(|n| n.value(i));
+        let elements = (offsets[i] as usize..offsets[i + 1] as usize)
+            .filter_map(|j| {
+                if values.is_valid(j) {
+                    Some(values.value(j))
+                } else {
+                    null_string
+                }
+            })
+            .collect::<Vec<_>>();
+        builder.append_value(&elements.join(delimiters.value(i)))?;
+    }
+    Ok(Arc::new(builder.finish()))
+}
+
 /// Currently supported types by the array function.
 /// The order of these types correspond to the order on which coercion applies
 /// This should thus be from least informative to most informative
@@ -185,3 +297,94 @@ pub static SUPPORTED_ARRAY_TYPES: &[DataType] = &[
     DataType::Utf8,
     DataType::LargeUtf8,
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lists(array: &ArrayRef) -> Vec<Option<Vec<Option<String>>>> {
+        let array = array.as_any().downcast_ref::<ListArray>().unwrap();
+        (0..array.len())
+            .map(|i| {
+                array.is_valid(i).then(|| {
+                    let values = array.value(i);
+                    downcast_utf8(&values)
+                        .iter()
+                        .map(|v| v.map(|v| v.to_string()))
+                        .collect()
+                })
+            })
+            .collect()
+    }
+
+    fn strings(values: Vec<Option<&str>>) -> ArrayRef {
+        Arc::new(StringArray::from(values))
+    }
+
+    #[test]
+    fn test_string_to_array() -> Result<()> {
+        let result = string_to_array(&[
+            strings(vec![
+                Some("a,b,,c"),
+                Some("xy"),
+                Some(""),
+                None,
+                Some("a,b"),
+            ]),
+            strings(vec![Some(","), None, Some(","), Some(","), Some("")]),
+        ])?;
+        let owned = |v: Vec<Option<&str>>| {
+            Some(v.into_iter().map(|s| s.map(String::from)).collect())
+        };
+        assert_eq!(
+            lists(&result),
+            vec![
+                owned(vec![Some("a"), Some("b"), Some(""), Some("c")]),
+                owned(vec![Some("x"), Some("y")]),
+                owned(vec![]),
+                None,
+                owned(vec![Some("a,b")]),
+            ]
+        );
+
+        let result = string_to_array(&[
+            strings(vec![Some("a,*,b")]),
+            strings(vec![Some(",")]),
+            strings(vec![Some("*")]),
+        ])?;
+        assert_eq!(
+            lists(&result),
+            vec![owned(vec![Some("a"), None, Some("b")])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_to_string() -> Result<()> {
+        let list: ArrayRef = Arc::new(ListArray::from_iter_primitive::<
+            arrow::datatypes::Int32Type,
+            _,
+            _,
+        >(vec![
+            Some(vec![Some(1), None, Some(3)]),
+            None,
+            Some(vec![]),
+        ]));
+        let result = array_to_string(&[list.clone(), strings(vec![Some("-"); 3])])?;
+        assert_eq!(
+            downcast_utf8(&result).iter().collect::<Vec<_>>(),
+            vec![Some("1-3"), None, Some("")]
+        );
+
+        let result = array_to_string(&[
+            list,
+            strings(vec![Some(", "); 3]),
+            strings(vec![Some("?"); 3]),
+        ])?;
+        assert_eq!(
+            downcast_utf8(&result).iter().collect::<Vec<_>>(),
+            vec![Some("1, ?, 3"), None, Some("")]
+        );
+        Ok(())
+    }
+}
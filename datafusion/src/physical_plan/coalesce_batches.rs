@@ -106,14 +106,10 @@ impl ExecutionPlan for CoalesceBatchesExec {
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        Ok(Box::pin(CoalesceBatchesStream {
-            input: self.input.execute(partition).await?,
-            schema: self.input.schema(),
-            target_batch_size: self.target_batch_size,
-            buffer: Vec::new(),
-            buffered_rows: 0,
-            is_closed: false,
-        }))
+        Ok(coalesce_stream(
+            self.input.execute(partition).await?,
+            self.target_batch_size,
+        ))
     }
 
     fn fmt_as(
@@ -133,6 +129,21 @@ impl ExecutionPlan for CoalesceBatchesExec {
     }
 }
 
+/// Coalesce the batches of `input` into batches of at least `target_batch_size` rows
+pub(crate) fn coalesce_stream(
+    input: SendableRecordBatchStream,
+    target_batch_size: usize,
+) -> SendableRecordBatchStream {
+    Box::pin(CoalesceBatchesStream {
+        schema: input.schema(),
+        input,
+        target_batch_size,
+        buffer: Vec::new(),
+        buffered_rows: 0,
+        is_closed: false,
+    })
+}
+
 struct CoalesceBatchesStream {
     /// The input plan
     input: SendableRecordBatchStream,
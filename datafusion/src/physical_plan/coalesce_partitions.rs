@@ -22,7 +22,8 @@ use std::any::Any;
 use std::sync::Arc;
 
 use futures::channel::mpsc;
-use futures::Stream;
+use futures::{SinkExt, Stream, StreamExt};
+use tokio::task::JoinHandle;
 
 use async_trait::async_trait;
 
@@ -30,18 +31,25 @@ use arrow::record_batch::RecordBatch;
 use arrow::{datatypes::SchemaRef, error::Result as ArrowResult};
 
 use super::RecordBatchStream;
+use crate::cube_ext;
+use crate::datasource::datasource::Statistics;
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{
     DisplayFormatType, ExecutionPlan, OptimizerHints, Partitioning,
 };
 
 use super::SendableRecordBatchStream;
-use crate::physical_plan::common::spawn_execution;
-use pin_project_lite::pin_project;
+use crate::physical_plan::common::AbortOnDropMany;
 use std::option::Option::None;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 
 /// Merge execution plan executes partitions in parallel and combines them into a single
 /// partition. No guarantees are made about the order of the resulting partition.
+///
+/// The partitions are read up to their first non-empty batch in parallel, and each is
+/// merged as soon as it turns out to have rows, so that empty partitions are skipped.
+/// While a single partition has rows, it is read directly instead of being merged.
 #[derive(Debug)]
 pub struct CoalescePartitionsExec {
     /// Input execution plan
@@ -111,38 +119,38 @@ impl ExecutionPlan for CoalescePartitionsExec {
                 self.input.execute(0).await
             }
             _ => {
-                // use a stream that allows each sender to put in at
-                // least one result in an attempt to maximize
-                // parallelism.
-                let (sender, receiver) =
-                    mpsc::channel::<ArrowResult<RecordBatch>>(input_partitions);
-
-                // spawn independent tasks whose resulting streams (of batches)
-                // are sent to the channel for consumption.
-                for part_i in 0..input_partitions {
-                    spawn_execution(self.input.clone(), sender.clone(), part_i);
-                }
+                // find the first non-empty batch of every partition in parallel,
+                // the partitions are reported in the order they find it.
+                let (sender, first_batches) = mpsc::channel(input_partitions);
+                let join_handles = (0..input_partitions)
+                    .map(|part_i| {
+                        spawn_first_batch(self.input.clone(), sender.clone(), part_i)
+                    })
+                    .collect();
 
                 Ok(Box::pin(MergeStream {
-                    input: receiver,
                     schema: self.schema(),
+                    first_batches: Some(first_batches),
+                    _first_batch_tasks: AbortOnDropMany(join_handles),
+                    ready: None,
+                    single: None,
+                    merged: None,
+                    merged_sender: None,
+                    merge_tasks: AbortOnDropMany(Vec::new()),
+                    input_partitions,
                 }))
             }
         }
     }
 
     fn output_hints(&self) -> OptimizerHints {
-        let input_hints = self.input.output_hints();
-        let sort_order;
-        if self.input.output_partitioning().partition_count() <= 1 {
-            sort_order = input_hints.sort_order
-        } else {
-            sort_order = None
-        }
-        OptimizerHints {
-            sort_order,
-            single_value_columns: input_hints.single_value_columns,
-        }
+        self.input
+            .output_hints()
+            .merge_partitions(self.input.output_partitioning().partition_count())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
     }
 
     fn fmt_as(
@@ -158,11 +166,118 @@ impl ExecutionPlan for CoalescePartitionsExec {
     }
 }
 
-pin_project! {
-    struct MergeStream {
-        schema: SchemaRef,
-        #[pin]
-        input: mpsc::Receiver<ArrowResult<RecordBatch>>,
+/// A partition with its first non-empty batch and the stream of the rest of its batches
+type NonEmptyPartition = (RecordBatch, SendableRecordBatchStream);
+
+/// Spawns a task that executes a partition and reads it up to its first non-empty batch,
+/// and sends that batch with the rest of the stream, or `None` if the partition has no
+/// rows, to the provided mpsc sender
+fn spawn_first_batch(
+    input: Arc<dyn ExecutionPlan>,
+    mut output: mpsc::Sender<ArrowResult<Option<NonEmptyPartition>>>,
+    partition: usize,
+) -> JoinHandle<()> {
+    let output_unwind = output.clone();
+    cube_ext::spawn_mpsc_with_catch_unwind(
+        async move {
+            // If send fails, plan being torn down, no place to send the result
+            output.send(first_batch(input, partition).await).await.ok();
+        },
+        output_unwind,
+    )
+}
+
+/// Executes a partition and reads it up to its first non-empty batch
+async fn first_batch(
+    input: Arc<dyn ExecutionPlan>,
+    partition: usize,
+) -> ArrowResult<Option<NonEmptyPartition>> {
+    let mut stream = input
+        .execute(partition)
+        .await
+        .map_err(DataFusionError::into_arrow_external_error)?;
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        if batch.num_rows() != 0 {
+            return Ok(Some((batch, stream)));
+        }
+    }
+    Ok(None)
+}
+
+/// Spawns a task that writes `first`, if any, and then the rest of `input` to the
+/// provided mpsc sender
+fn spawn_forward(
+    first: Option<RecordBatch>,
+    mut input: SendableRecordBatchStream,
+    mut output: mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> JoinHandle<()> {
+    let output_unwind = output.clone();
+    cube_ext::spawn_mpsc_with_catch_unwind(
+        async move {
+            if let Some(first) = first {
+                if output.send(Ok(first)).await.is_err() {
+                    return;
+                }
+            }
+            while let Some(item) = input.next().await {
+                // If send fails, plan being torn down, stop pulling from the input
+                if output.send(item).await.is_err() {
+                    return;
+                }
+            }
+        },
+        output_unwind,
+    )
+}
+
+/// Stream of the batches of the non-empty partitions, which starts reading the only
+/// partition found to have rows directly, and merges the partitions in parallel once
+/// a second one is found.
+struct MergeStream {
+    schema: SchemaRef,
+    /// Partitions as they find their first non-empty batch, `None` once all reported
+    first_batches: Option<mpsc::Receiver<ArrowResult<Option<NonEmptyPartition>>>>,
+    // cancels the tasks reading the first batches when the stream is dropped
+    _first_batch_tasks: AbortOnDropMany<()>,
+    /// First batch of the partition read directly, not returned yet
+    ready: Option<RecordBatch>,
+    /// The only partition found to have rows so far
+    single: Option<SendableRecordBatchStream>,
+    /// Batches of the merged partitions, once several have rows
+    merged: Option<mpsc::Receiver<ArrowResult<RecordBatch>>>,
+    /// Sender for the partitions found to have rows after the merge started
+    merged_sender: Option<mpsc::Sender<ArrowResult<RecordBatch>>>,
+    // cancels the tasks feeding `merged` when the stream is dropped
+    merge_tasks: AbortOnDropMany<()>,
+    input_partitions: usize,
+}
+
+impl MergeStream {
+    /// Starts reading a partition that has rows, directly if it is the only one so far
+    /// and merged with the others otherwise
+    fn add_partition(&mut self, first: RecordBatch, input: SendableRecordBatchStream) {
+        if self.merged.is_none() && self.single.is_none() && self.ready.is_none() {
+            self.ready = Some(first);
+            self.single = Some(input);
+            return;
+        }
+        if self.merged.is_none() {
+            // use a stream that allows each sender to put in at
+            // least one result in an attempt to maximize
+            // parallelism.
+            let (sender, receiver) = mpsc::channel(self.input_partitions);
+            if let Some(single) = self.single.take() {
+                let handle = spawn_forward(self.ready.take(), single, sender.clone());
+                self.merge_tasks.0.push(handle);
+            }
+            self.merged = Some(receiver);
+            self.merged_sender = Some(sender);
+        }
+        let sender = self.merged_sender.clone().unwrap();
+        self.merge_tasks
+            .0
+            .push(spawn_forward(Some(first), input, sender));
     }
 }
 
@@ -170,11 +285,54 @@ impl Stream for MergeStream {
     type Item = ArrowResult<RecordBatch>;
 
     fn poll_next(
-        self: std::pin::Pin<&mut Self>,
-        cx: &mut std::task::Context<'_>,
-    ) -> std::task::Poll<Option<Self::Item>> {
-        let this = self.project();
-        this.input.poll_next(cx)
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(first) = self.ready.take() {
+                return Poll::Ready(Some(Ok(first)));
+            }
+
+            if let Some(first_batches) = &mut self.first_batches {
+                match first_batches.poll_next_unpin(cx) {
+                    Poll::Ready(Some(Ok(Some((first, input))))) => {
+                        self.add_partition(first, input);
+                        continue;
+                    }
+                    // empty partitions are not merged
+                    Poll::Ready(Some(Ok(None))) => continue,
+                    Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                    Poll::Ready(None) => {
+                        // no more partitions to merge, the merge ends with its inputs
+                        self.first_batches = None;
+                        self.merged_sender = None;
+                        continue;
+                    }
+                    Poll::Pending => {}
+                }
+            }
+
+            let batch = if let Some(single) = &mut self.single {
+                match single.poll_next_unpin(cx) {
+                    Poll::Ready(None) => {
+                        self.single = None;
+                        continue;
+                    }
+                    batch => batch,
+                }
+            } else if let Some(merged) = &mut self.merged {
+                merged.poll_next_unpin(cx)
+            } else if self.first_batches.is_none() {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            };
+            return match batch {
+                // empty batches are not passed on
+                Poll::Ready(Some(Ok(batch))) if batch.num_rows() == 0 => continue,
+                other => other,
+            };
+        }
     }
 }
 
@@ -188,9 +346,15 @@ impl RecordBatchStream for MergeStream {
 mod tests {
 
     use super::*;
+    use crate::assert_batches_eq;
     use crate::physical_plan::common;
     use crate::physical_plan::csv::{CsvExec, CsvReadOptions};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::union::UnionExec;
     use crate::test;
+    use crate::test::exec::PendingExec;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
 
     #[tokio::test]
     async fn merge() -> Result<()> {
@@ -227,4 +391,63 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn merge_skips_empty_partitions() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = |values: Vec<i32>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))])
+        };
+        let empty = RecordBatch::new_empty(schema.clone());
+
+        // the batches of a partition are returned in order
+        let partitions = vec![
+            vec![],
+            vec![empty.clone(), batch(vec![1, 2])?, batch(vec![3])?],
+            vec![empty.clone()],
+        ];
+        let input = MemoryExec::try_new(&partitions, schema.clone(), None)?;
+        let merge = CoalescePartitionsExec::new(Arc::new(input));
+        let batches = common::collect(merge.execute(0).await?).await?;
+        assert_eq!(batches.len(), 2);
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 1 |", "| 2 |", "| 3 |", "+---+"],
+            &batches
+        );
+
+        // no partition has rows
+        let partitions = vec![vec![], vec![empty.clone()], vec![empty]];
+        let input = MemoryExec::try_new(&partitions, schema.clone(), None)?;
+        let merge = CoalescePartitionsExec::new(Arc::new(input));
+        let batches = common::collect(merge.execute(0).await?).await?;
+        assert!(batches.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_does_not_wait_for_pending_partitions() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = |values: Vec<i32>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))])
+        };
+
+        // the partition with rows is returned while another one never produces a batch
+        let rows = MemoryExec::try_new(
+            &[vec![], vec![batch(vec![1, 2])?, batch(vec![3])?]],
+            schema.clone(),
+            None,
+        )?;
+        let pending = PendingExec::without_batches(schema.clone(), 1);
+        let input = UnionExec::new(vec![Arc::new(rows), Arc::new(pending)]);
+        let merge = CoalescePartitionsExec::new(Arc::new(input));
+        let mut stream = merge.execute(0).await?;
+        let batches = vec![stream.next().await.unwrap()?, stream.next().await.unwrap()?];
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 1 |", "| 2 |", "| 3 |", "+---+"],
+            &batches
+        );
+
+        Ok(())
+    }
 }
@@ -165,15 +165,31 @@ pub(crate) fn spawn_execution(
             };
 
             while let Some(item) = stream.next().await {
-                // If send fails, plan being torn down,
-                // there is no place to send the error
-                output.send(item).await.ok();
+                // If send fails, plan being torn down (e.g. a LIMIT was
+                // satisfied), stop pulling from the input
+                if output.send(item).await.is_err() {
+                    return;
+                }
             }
         },
         output_unwind,
     )
 }
 
+/// Aborts the wrapped tasks when dropped, so that tasks feeding a stream
+/// stop running as soon as that stream is dropped, e.g. once a LIMIT is
+/// satisfied, instead of running until their next send fails.
+#[derive(Debug)]
+pub(crate) struct AbortOnDropMany<T>(pub Vec<JoinHandle<T>>);
+
+impl<T> Drop for AbortOnDropMany<T> {
+    fn drop(&mut self) {
+        for join_handle in &self.0 {
+            join_handle.abort();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
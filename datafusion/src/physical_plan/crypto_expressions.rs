@@ -16,183 +16,260 @@
 // under the License.
 
 //! Crypto expressions
+use std::fmt::Write;
+use std::hash::Hasher;
+use std::iter::FromIterator;
 use std::sync::Arc;
 
 use md5::Md5;
+use sha1::Sha1;
 use sha2::{
     digest::Output as SHA2DigestOutput, Digest as SHA2Digest, Sha224, Sha256, Sha384,
     Sha512,
 };
+use twox_hash::XxHash64;
 
 use crate::{
     error::{DataFusionError, Result},
     scalar::ScalarValue,
 };
 use arrow::{
-    array::{Array, BinaryArray, GenericStringArray, StringOffsetSizeTrait},
+    array::{
+        new_null_array, Array, BinaryArray, Int64Array, LargeStringArray, StringArray,
+    },
     datatypes::DataType,
 };
 
-use super::{string_expressions::unary_string_function, ColumnarValue};
+use super::ColumnarValue;
 
-/// Computes the md5 of a string.
-fn md5_process(input: &str) -> String {
-    let mut digest = Md5::default();
-    digest.update(&input);
-
-    let mut result = String::new();
-
-    for byte in &digest.finalize() {
-        result.push_str(&format!("{:02x}", byte));
+/// Encodes bytes as a lowercase hex string.
+fn hex_encode<T: AsRef<[u8]>>(data: T) -> String {
+    let data = data.as_ref();
+    let mut result = String::with_capacity(data.len() * 2);
+    for byte in data {
+        write!(&mut result, "{:02x}", byte).unwrap();
     }
-
     result
 }
 
+/// Computes the md5 of a string.
+fn md5_process(input: &[u8]) -> String {
+    hex_encode(sha_process::<Md5>(input))
+}
+
 // It's not possible to return &[u8], because trait in trait without short lifetime
-fn sha_process<D: SHA2Digest + Default>(input: &str) -> SHA2DigestOutput<D> {
+fn sha_process<D: SHA2Digest + Default>(input: &[u8]) -> SHA2DigestOutput<D> {
     let mut digest = D::default();
-    digest.update(&input);
+    digest.update(input);
 
     digest.finalize()
 }
 
-/// # Errors
-/// This function errors when:
-/// * the number of arguments is not 1
-/// * the first argument is not castable to a `GenericStringArray`
-fn unary_binary_function<T, R, F>(
-    args: &[&dyn Array],
-    op: F,
-    name: &str,
-) -> Result<BinaryArray>
+/// Computes the 64 bit xxHash (seed 0), reinterpreted as a signed integer.
+fn xxhash64_process(input: &[u8]) -> i64 {
+    let mut hasher = XxHash64::with_seed(0);
+    hasher.write(input);
+    hasher.finish() as i64
+}
+
+/// Computes the CRC-32 (IEEE) checksum.
+fn crc32_process(input: &[u8]) -> i64 {
+    crc32fast::hash(input) as i64
+}
+
+/// Applies `op` to the bytes of every value of a Utf8, LargeUtf8 or Binary array.
+fn map_bytes<C, O, F>(array: &dyn Array, op: F, name: &str) -> Result<C>
 where
-    R: AsRef<[u8]>,
-    T: StringOffsetSizeTrait,
-    F: Fn(&str) -> R,
+    C: FromIterator<Option<O>>,
+    F: Fn(&[u8]) -> O,
 {
-    if args.len() != 1 {
-        return Err(DataFusionError::Internal(format!(
-            "{:?} args were supplied but {} takes exactly one argument",
-            args.len(),
-            name,
-        )));
+    fn downcast<'a, T: 'static>(array: &'a dyn Array) -> Result<&'a T> {
+        array.as_any().downcast_ref::<T>().ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "failed to downcast {:?} array",
+                array.data_type()
+            ))
+        })
     }
 
-    let array = args[0]
-        .as_any()
-        .downcast_ref::<GenericStringArray<T>>()
-        .ok_or_else(|| {
-            DataFusionError::Internal("failed to downcast to string".to_string())
-        })?;
-
     // first map is the iterator, second is for the `Option<_>`
-    Ok(array.iter().map(|x| x.map(|x| op(x))).collect())
+    match array.data_type() {
+        DataType::Utf8 => Ok(downcast::<StringArray>(array)?
+            .iter()
+            .map(|x| x.map(|x| op(x.as_bytes())))
+            .collect()),
+        DataType::LargeUtf8 => Ok(downcast::<LargeStringArray>(array)?
+            .iter()
+            .map(|x| x.map(|x| op(x.as_bytes())))
+            .collect()),
+        DataType::Binary => Ok(downcast::<BinaryArray>(array)?
+            .iter()
+            .map(|x| x.map(|x| op(x)))
+            .collect()),
+        other => Err(DataFusionError::Internal(format!(
+            "Unsupported data type {:?} for function {}",
+            other, name,
+        ))),
+    }
 }
 
-fn handle<F, R>(args: &[ColumnarValue], op: F, name: &str) -> Result<ColumnarValue>
+/// Returns the bytes of a Utf8, LargeUtf8 or Binary scalar.
+fn scalar_bytes<'a>(scalar: &'a ScalarValue, name: &str) -> Result<Option<&'a [u8]>> {
+    match scalar {
+        ScalarValue::Utf8(v) | ScalarValue::LargeUtf8(v) => {
+            Ok(v.as_ref().map(|v| v.as_bytes()))
+        }
+        ScalarValue::Binary(v) => Ok(v.as_deref()),
+        other => Err(DataFusionError::Internal(format!(
+            "Unsupported data type {:?} for function {}",
+            other, name,
+        ))),
+    }
+}
+
+/// Applies `op` to each value of the single Utf8, LargeUtf8 or Binary argument,
+/// collecting array results into `C` and wrapping scalar results with `to_scalar`.
+fn handle<C, O, F, S>(
+    args: &[ColumnarValue],
+    op: F,
+    to_scalar: S,
+    name: &str,
+) -> Result<ColumnarValue>
 where
-    R: AsRef<[u8]>,
-    F: Fn(&str) -> R,
+    C: Array + FromIterator<Option<O>> + 'static,
+    F: Fn(&[u8]) -> O,
+    S: Fn(Option<O>) -> ScalarValue,
 {
     match &args[0] {
-        ColumnarValue::Array(a) => match a.data_type() {
-            DataType::Utf8 => {
-                Ok(ColumnarValue::Array(Arc::new(unary_binary_function::<
-                    i32,
-                    _,
-                    _,
-                >(
-                    &[a.as_ref()], op, name
-                )?)))
-            }
-            DataType::LargeUtf8 => {
-                Ok(ColumnarValue::Array(Arc::new(unary_binary_function::<
-                    i64,
-                    _,
-                    _,
-                >(
-                    &[a.as_ref()], op, name
-                )?)))
-            }
-            other => Err(DataFusionError::Internal(format!(
-                "Unsupported data type {:?} for function {}",
-                other, name,
-            ))),
-        },
-        ColumnarValue::Scalar(scalar) => match scalar {
-            ScalarValue::Utf8(a) => {
-                let result = a.as_ref().map(|x| (op)(x).as_ref().to_vec());
-                Ok(ColumnarValue::Scalar(ScalarValue::Binary(result)))
-            }
-            ScalarValue::LargeUtf8(a) => {
-                let result = a.as_ref().map(|x| (op)(x).as_ref().to_vec());
-                Ok(ColumnarValue::Scalar(ScalarValue::Binary(result)))
-            }
-            other => Err(DataFusionError::Internal(format!(
-                "Unsupported data type {:?} for function {}",
-                other, name,
-            ))),
-        },
+        ColumnarValue::Array(a) => {
+            Ok(ColumnarValue::Array(Arc::new(map_bytes::<C, _, _>(
+                a.as_ref(),
+                op,
+                name,
+            )?)))
+        }
+        ColumnarValue::Scalar(scalar) => {
+            let result = scalar_bytes(scalar, name)?.map(op);
+            Ok(ColumnarValue::Scalar(to_scalar(result)))
+        }
     }
 }
 
-fn md5_array<T: StringOffsetSizeTrait>(
-    args: &[&dyn Array],
-) -> Result<GenericStringArray<i32>> {
-    unary_string_function::<T, i32, _, _>(args, md5_process, "md5")
+fn handle_binary<F, R>(args: &[ColumnarValue], op: F, name: &str) -> Result<ColumnarValue>
+where
+    R: AsRef<[u8]>,
+    F: Fn(&[u8]) -> R,
+{
+    handle::<BinaryArray, _, _, _>(
+        args,
+        op,
+        |v: Option<R>| ScalarValue::Binary(v.map(|v| v.as_ref().to_vec())),
+        name,
+    )
+}
+
+fn handle_hex<F>(args: &[ColumnarValue], op: F, name: &str) -> Result<ColumnarValue>
+where
+    F: Fn(&[u8]) -> String,
+{
+    handle::<StringArray, _, _, _>(args, op, ScalarValue::Utf8, name)
+}
+
+fn handle_int64<F>(args: &[ColumnarValue], op: F, name: &str) -> Result<ColumnarValue>
+where
+    F: Fn(&[u8]) -> i64,
+{
+    handle::<Int64Array, _, _, _>(args, op, ScalarValue::Int64, name)
 }
 
-/// crypto function that accepts Utf8 or LargeUtf8 and returns a [`ColumnarValue`]
+/// crypto function that accepts Utf8, LargeUtf8 or Binary and returns a [`ColumnarValue`]
+/// with the hex encoded digest. LargeUtf8 input produces LargeUtf8, otherwise Utf8.
 pub fn md5(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     match &args[0] {
-        ColumnarValue::Array(a) => match a.data_type() {
-            DataType::Utf8 => Ok(ColumnarValue::Array(Arc::new(md5_array::<i32>(&[
-                a.as_ref()
-            ])?))),
-            DataType::LargeUtf8 => {
-                Ok(ColumnarValue::Array(Arc::new(md5_array::<i64>(&[
-                    a.as_ref()
-                ])?)))
-            }
-            other => Err(DataFusionError::Internal(format!(
-                "Unsupported data type {:?} for function md5",
-                other,
-            ))),
-        },
-        ColumnarValue::Scalar(scalar) => match scalar {
-            ScalarValue::Utf8(a) => {
-                let result = a.as_ref().map(|x| md5_process(x));
-                Ok(ColumnarValue::Scalar(ScalarValue::Utf8(result)))
-            }
-            ScalarValue::LargeUtf8(a) => {
-                let result = a.as_ref().map(|x| md5_process(x));
-                Ok(ColumnarValue::Scalar(ScalarValue::LargeUtf8(result)))
-            }
-            other => Err(DataFusionError::Internal(format!(
-                "Unsupported data type {:?} for function md5",
-                other,
-            ))),
-        },
+        ColumnarValue::Array(a) if a.data_type() == &DataType::LargeUtf8 => {
+            Ok(ColumnarValue::Array(Arc::new(map_bytes::<
+                LargeStringArray,
+                _,
+                _,
+            >(
+                a.as_ref(),
+                md5_process,
+                "md5",
+            )?)))
+        }
+        ColumnarValue::Scalar(ScalarValue::LargeUtf8(a)) => {
+            let result = a.as_ref().map(|x| md5_process(x.as_bytes()));
+            Ok(ColumnarValue::Scalar(ScalarValue::LargeUtf8(result)))
+        }
+        _ => handle_hex(args, md5_process, "md5"),
     }
 }
 
-/// crypto function that accepts Utf8 or LargeUtf8 and returns a [`ColumnarValue`]
+/// crypto function that accepts Utf8, LargeUtf8 or Binary and returns a [`ColumnarValue`]
+/// with the hex encoded SHA-1 digest
+pub fn sha1(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    handle_hex(args, |x| hex_encode(sha_process::<Sha1>(x)), "sha1")
+}
+
+/// crypto function that accepts Utf8, LargeUtf8 or Binary and a constant bit length
+/// (224, 256, 384, 512 or 0 for 256) and returns a [`ColumnarValue`] with the hex
+/// encoded SHA-2 digest. Unsupported bit lengths produce NULL, as in MySQL.
+pub fn sha2(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let bit_length = match &args[1] {
+        ColumnarValue::Scalar(ScalarValue::Int64(v)) => *v,
+        _ => {
+            return Err(DataFusionError::NotImplemented(
+                "sha2 only supports a constant bit length".to_string(),
+            ))
+        }
+    };
+    let op: fn(&[u8]) -> String = match bit_length {
+        Some(0) | Some(256) => |x| hex_encode(sha_process::<Sha256>(x)),
+        Some(224) => |x| hex_encode(sha_process::<Sha224>(x)),
+        Some(384) => |x| hex_encode(sha_process::<Sha384>(x)),
+        Some(512) => |x| hex_encode(sha_process::<Sha512>(x)),
+        _ => {
+            return Ok(match &args[0] {
+                ColumnarValue::Array(a) => {
+                    ColumnarValue::Array(new_null_array(&DataType::Utf8, a.len()))
+                }
+                ColumnarValue::Scalar(_) => {
+                    ColumnarValue::Scalar(ScalarValue::Utf8(None))
+                }
+            })
+        }
+    };
+    handle_hex(&args[..1], op, "sha2")
+}
+
+/// crypto function that accepts Utf8, LargeUtf8 or Binary and returns a [`ColumnarValue`]
 pub fn sha224(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    handle(args, sha_process::<Sha224>, "ssh224")
+    handle_binary(args, sha_process::<Sha224>, "sha224")
 }
 
-/// crypto function that accepts Utf8 or LargeUtf8 and returns a [`ColumnarValue`]
+/// crypto function that accepts Utf8, LargeUtf8 or Binary and returns a [`ColumnarValue`]
 pub fn sha256(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    handle(args, sha_process::<Sha256>, "sha256")
+    handle_binary(args, sha_process::<Sha256>, "sha256")
 }
 
-/// crypto function that accepts Utf8 or LargeUtf8 and returns a [`ColumnarValue`]
+/// crypto function that accepts Utf8, LargeUtf8 or Binary and returns a [`ColumnarValue`]
 pub fn sha384(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    handle(args, sha_process::<Sha384>, "sha384")
+    handle_binary(args, sha_process::<Sha384>, "sha384")
 }
 
-/// crypto function that accepts Utf8 or LargeUtf8 and returns a [`ColumnarValue`]
+/// crypto function that accepts Utf8, LargeUtf8 or Binary and returns a [`ColumnarValue`]
 pub fn sha512(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    handle(args, sha_process::<Sha512>, "sha512")
+    handle_binary(args, sha_process::<Sha512>, "sha512")
+}
+
+/// non-cryptographic hash function that accepts Utf8, LargeUtf8 or Binary and returns
+/// the 64 bit xxHash of each value as Int64, e.g. for bucketing
+pub fn xxhash64(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    handle_int64(args, xxhash64_process, "xxhash64")
+}
+
+/// checksum function that accepts Utf8, LargeUtf8 or Binary and returns the CRC-32 of
+/// each value as a non-negative Int64, as in MySQL
+pub fn crc32(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    handle_int64(args, crc32_process, "crc32")
 }
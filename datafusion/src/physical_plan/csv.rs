@@ -21,13 +21,13 @@ use crate::error::{DataFusionError, Result};
 use crate::physical_plan::ExecutionPlan;
 use crate::physical_plan::{common, source::Source, Partitioning};
 use arrow::csv;
-use arrow::datatypes::{Schema, SchemaRef};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 use futures::Stream;
 use std::any::Any;
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -51,9 +51,19 @@ pub struct CsvReadOptions<'a> {
     pub schema: Option<&'a Schema>,
     /// Max number of rows to read from CSV files for schema inference if needed. Defaults to 1000.
     pub schema_infer_max_records: usize,
+    /// Read all rows of the CSV files for schema inference, ignoring
+    /// `schema_infer_max_records`. Defaults to false.
+    pub schema_infer_all_records: bool,
+    /// Types of some of the columns to use instead of the inferred ones. Columns of this
+    /// schema that are not present in the CSV files are ignored.
+    pub schema_infer_overrides: Option<&'a Schema>,
     /// File extension; only files with this extension are selected for data input.
     /// Defaults to ".csv".
     pub file_extension: &'a str,
+    /// Split files larger than this number of bytes into several partitions, each
+    /// reading the lines starting in its byte range. Requires that quoted values do not
+    /// contain line breaks. Defaults to `None`, reading each file in a single partition.
+    pub file_split_size: Option<usize>,
 }
 
 impl<'a> CsvReadOptions<'a> {
@@ -63,8 +73,11 @@ impl<'a> CsvReadOptions<'a> {
             has_header: true,
             schema: None,
             schema_infer_max_records: 1000,
+            schema_infer_all_records: false,
+            schema_infer_overrides: None,
             delimiter: b',',
             file_extension: ".csv",
+            file_split_size: None,
         }
     }
 
@@ -86,6 +99,12 @@ impl<'a> CsvReadOptions<'a> {
         self
     }
 
+    /// Split files larger than `bytes` into several partitions
+    pub fn file_split_size(mut self, bytes: usize) -> Self {
+        self.file_split_size = Some(bytes);
+        self
+    }
+
     /// Configure delimiter setting with Option, None value will be ignored
     pub fn delimiter_option(mut self, delimiter: Option<u8>) -> Self {
         if let Some(d) = delimiter {
@@ -105,6 +124,72 @@ impl<'a> CsvReadOptions<'a> {
         self.schema_infer_max_records = max_records;
         self
     }
+
+    /// Configure whether all records are read for schema inference
+    pub fn schema_infer_all_records(mut self, all_records: bool) -> Self {
+        self.schema_infer_all_records = all_records;
+        self
+    }
+
+    /// Specify the types of some of the columns instead of inferring them
+    pub fn schema_infer_overrides(mut self, overrides: &'a Schema) -> Self {
+        self.schema_infer_overrides = Some(overrides);
+        self
+    }
+
+    /// Max number of records to read for schema inference, `None` to read all of them
+    pub(crate) fn schema_infer_limit(&self) -> Option<usize> {
+        if self.schema_infer_all_records {
+            None
+        } else {
+            Some(self.schema_infer_max_records)
+        }
+    }
+
+    /// Replaces the types of the inferred columns that have an override
+    pub(crate) fn apply_schema_overrides(&self, inferred: Schema) -> Schema {
+        let overrides = match self.schema_infer_overrides {
+            Some(overrides) => overrides,
+            None => return inferred,
+        };
+        let fields = inferred
+            .fields()
+            .iter()
+            .map(|f| match overrides.field_with_name(f.name()) {
+                Ok(o) => Field::new(f.name(), o.data_type().clone(), o.is_nullable()),
+                Err(_) => f.clone(),
+            })
+            .collect();
+        Schema::new(fields)
+    }
+}
+
+/// Merges the schemas inferred from several CSV files. Columns inferred with different
+/// types are widened instead of failing the merge: a mix of integers and floats
+/// becomes `Float64` and any other mix becomes `Utf8`.
+fn merge_inferred_schemas(schemas: Vec<Schema>) -> Schema {
+    let mut fields: Vec<Field> = Vec::new();
+    for schema in schemas {
+        for field in schema.fields() {
+            match fields.iter_mut().find(|f| f.name() == field.name()) {
+                Some(merged) => {
+                    let data_type = match (merged.data_type(), field.data_type()) {
+                        (l, r) if l == r => l.clone(),
+                        (DataType::Int64, DataType::Float64)
+                        | (DataType::Float64, DataType::Int64) => DataType::Float64,
+                        _ => DataType::Utf8,
+                    };
+                    *merged = Field::new(
+                        field.name(),
+                        data_type,
+                        merged.is_nullable() || field.is_nullable(),
+                    );
+                }
+                None => fields.push(field.clone()),
+            }
+        }
+    }
+    Schema::new(fields)
 }
 
 /// Execution plan for scanning a CSV file
@@ -128,6 +213,18 @@ pub struct CsvExec {
     batch_size: usize,
     /// Limit in nr. of rows
     limit: Option<usize>,
+    /// Maximum size of the byte range of a file read by a single partition
+    file_split_size: Option<usize>,
+    /// Byte ranges of the files read by each partition
+    file_ranges: Vec<FileRange>,
+}
+
+/// Lines of a file starting in the byte range `start..end`
+#[derive(Debug, Clone)]
+struct FileRange {
+    file: usize,
+    start: u64,
+    end: u64,
 }
 
 impl CsvExec {
@@ -159,6 +256,7 @@ impl CsvExec {
             None => schema.clone(),
             Some(p) => Schema::new(p.iter().map(|i| schema.field(*i).clone()).collect()),
         };
+        let file_ranges = split_files(&filenames, options.file_split_size)?;
 
         Ok(Self {
             source: Source::PartitionedFiles {
@@ -173,6 +271,8 @@ impl CsvExec {
             projected_schema: Arc::new(projected_schema),
             batch_size,
             limit,
+            file_split_size: options.file_split_size,
+            file_ranges,
         })
     }
     /// Create a new execution plan for reading from a reader
@@ -208,6 +308,8 @@ impl CsvExec {
             projected_schema: Arc::new(projected_schema),
             batch_size,
             limit,
+            file_split_size: None,
+            file_ranges: vec![],
         })
     }
 
@@ -256,17 +358,35 @@ impl CsvExec {
         self.limit
     }
 
+    /// Maximum size of the byte range of a file read by a single partition
+    pub fn file_split_size(&self) -> Option<usize> {
+        self.file_split_size
+    }
+
     /// Infer schema for given CSV dataset
     pub fn try_infer_schema(
         filenames: &[String],
         options: &CsvReadOptions,
     ) -> Result<Schema> {
-        Ok(csv::infer_schema_from_files(
-            filenames,
-            options.delimiter,
-            Some(options.schema_infer_max_records),
-            options.has_header,
-        )?)
+        let mut records_to_read = options.schema_infer_limit();
+        let mut schemas = Vec::with_capacity(filenames.len());
+        for filename in filenames {
+            let mut file = File::open(filename)?;
+            let (schema, records_read) = csv::reader::infer_file_schema(
+                &mut file,
+                options.delimiter,
+                records_to_read,
+                options.has_header,
+            )?;
+            schemas.push(schema);
+            if let Some(to_read) = records_to_read.as_mut() {
+                *to_read -= records_read.min(*to_read);
+                if *to_read == 0 {
+                    break;
+                }
+            }
+        }
+        Ok(options.apply_schema_overrides(merge_inferred_schemas(schemas)))
     }
 }
 
@@ -285,7 +405,7 @@ impl ExecutionPlan for CsvExec {
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
         Partitioning::UnknownPartitioning(match &self.source {
-            Source::PartitionedFiles { filenames, .. } => filenames.len(),
+            Source::PartitionedFiles { .. } => self.file_ranges.len(),
             Source::Reader(_) => 1,
         })
     }
@@ -312,10 +432,24 @@ impl ExecutionPlan for CsvExec {
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
         match &self.source {
             Source::PartitionedFiles { filenames, .. } => {
-                Ok(Box::pin(CsvStream::try_new(
-                    &filenames[partition],
+                let range = &self.file_ranges[partition];
+                let filename = &filenames[range.file];
+                if range.start == 0 && range.end == u64::MAX {
+                    return Ok(Box::pin(CsvStream::try_new(
+                        filename,
+                        self.schema.clone(),
+                        self.has_header,
+                        self.delimiter,
+                        &self.projection,
+                        self.batch_size,
+                        self.limit,
+                    )?));
+                }
+                // only the first range of a file starts with the header
+                Ok(Box::pin(CsvStream::try_new_from_reader(
+                    FileRangeReader::try_new(filename, range.start, range.end)?,
                     self.schema.clone(),
-                    self.has_header,
+                    self.has_header && range.start == 0,
                     self.delimiter,
                     &self.projection,
                     self.batch_size,
@@ -365,6 +499,102 @@ impl ExecutionPlan for CsvExec {
     }
 }
 
+/// Splits the files into ranges of at most `split_size` bytes, or into a single range
+/// per file if there is no split size
+fn split_files(
+    filenames: &[String],
+    split_size: Option<usize>,
+) -> Result<Vec<FileRange>> {
+    let mut ranges = Vec::with_capacity(filenames.len());
+    for (file, filename) in filenames.iter().enumerate() {
+        let split_size = match split_size {
+            Some(split_size) => split_size.max(1) as u64,
+            None => {
+                ranges.push(FileRange {
+                    file,
+                    start: 0,
+                    end: u64::MAX,
+                });
+                continue;
+            }
+        };
+        let len = std::fs::metadata(filename)?.len();
+        let num_ranges = ((len + split_size - 1) / split_size).max(1);
+        let range_size = (len + num_ranges - 1) / num_ranges;
+        for i in 0..num_ranges {
+            ranges.push(FileRange {
+                file,
+                start: i * range_size,
+                end: if i == num_ranges - 1 {
+                    u64::MAX
+                } else {
+                    (i + 1) * range_size
+                },
+            });
+        }
+    }
+    Ok(ranges)
+}
+
+/// Reads the lines of a file that start in the byte range `start..end`: a line that
+/// starts before `start` belongs to the previous range and the last line is read past
+/// `end` up to its line break.
+struct FileRangeReader {
+    inner: BufReader<File>,
+    pos: u64,
+    end: u64,
+    at_line_start: bool,
+    finished: bool,
+}
+
+impl FileRangeReader {
+    fn try_new(filename: &str, start: u64, end: u64) -> Result<Self> {
+        let mut inner = BufReader::new(File::open(filename)?);
+        let mut pos = start;
+        if start != 0 {
+            // skip the rest of the line that starts in the previous range
+            inner.seek(SeekFrom::Start(start - 1))?;
+            pos = start - 1 + inner.read_until(b'\n', &mut Vec::new())? as u64;
+        }
+        Ok(Self {
+            inner,
+            pos,
+            end,
+            at_line_start: true,
+            finished: false,
+        })
+    }
+}
+
+impl Read for FileRangeReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.finished || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pos >= self.end && self.at_line_start {
+            self.finished = true;
+            return Ok(0);
+        }
+        let available = self.inner.fill_buf()?;
+        let mut n = available.len().min(buf.len());
+        if self.pos < self.end {
+            n = n.min((self.end - self.pos) as usize);
+        } else if let Some(i) = available[..n].iter().position(|b| *b == b'\n') {
+            // the last line ends here
+            n = i + 1;
+        }
+        if n == 0 {
+            self.finished = true;
+            return Ok(0);
+        }
+        buf[..n].copy_from_slice(&available[..n]);
+        self.at_line_start = buf[n - 1] == b'\n';
+        self.inner.consume(n);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
 /// Iterator over batches
 struct CsvStream<R: Read> {
     /// Arrow CSV reader
@@ -524,4 +754,84 @@ mod tests {
         assert_eq!("c5", batch_schema.field(2).name());
         Ok(())
     }
+
+    #[tokio::test]
+    async fn csv_exec_with_file_split() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let path = tmp_dir.path().join("a.csv");
+        let mut content = "c1,c2\n".to_string();
+        for i in 0..100 {
+            content.push_str(&format!("{},{}\n", i, "x".repeat(i % 7)));
+        }
+        std::fs::write(&path, content)?;
+        let schema = Schema::new(vec![
+            Field::new("c1", DataType::Int64, false),
+            Field::new("c2", DataType::Utf8, true),
+        ]);
+
+        for split_size in [1, 13, 64, 100_000] {
+            let csv = CsvExec::try_new(
+                path.to_str().unwrap(),
+                CsvReadOptions::new()
+                    .schema(&schema)
+                    .file_split_size(split_size),
+                None,
+                10,
+                None,
+            )?;
+            let partitions = csv.output_partitioning().partition_count();
+            assert!(split_size > 1000 || partitions > 1);
+            let mut values = vec![];
+            for partition in 0..partitions {
+                let batches = common::collect(csv.execute(partition).await?).await?;
+                for batch in batches {
+                    let c1 = batch
+                        .column(0)
+                        .as_any()
+                        .downcast_ref::<arrow::array::Int64Array>()
+                        .unwrap();
+                    values.extend(c1.values().iter().copied());
+                }
+            }
+            // every line is read exactly once and in order
+            assert_eq!(values, (0..100).collect::<Vec<i64>>());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn csv_infer_schema_sampling() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let mut filenames = vec![];
+        for (name, content) in
+            &[("a.csv", "c1,c2\n1,x\n2,y\n"), ("b.csv", "c1,c2\n1.5,3\n")]
+        {
+            let path = tmp_dir.path().join(name);
+            std::fs::write(&path, content)?;
+            filenames.push(path.to_str().unwrap().to_string());
+        }
+        let types = |schema: Schema| {
+            schema
+                .fields()
+                .iter()
+                .map(|f| f.data_type().clone())
+                .collect::<Vec<_>>()
+        };
+
+        // only the first file is sampled
+        let options = CsvReadOptions::new().schema_infer_max_records(2);
+        let schema = CsvExec::try_infer_schema(&filenames, &options)?;
+        assert_eq!(types(schema), vec![DataType::Int64, DataType::Utf8]);
+
+        // types of both files are widened
+        let options = options.schema_infer_all_records(true);
+        let schema = CsvExec::try_infer_schema(&filenames, &options)?;
+        assert_eq!(types(schema), vec![DataType::Float64, DataType::Utf8]);
+
+        let overrides = Schema::new(vec![Field::new("c1", DataType::Utf8, false)]);
+        let options = options.schema_infer_overrides(&overrides);
+        let schema = CsvExec::try_infer_schema(&filenames, &options)?;
+        assert_eq!(types(schema), vec![DataType::Utf8, DataType::Utf8]);
+        Ok(())
+    }
 }
@@ -16,14 +16,16 @@
 // under the License.
 
 //! DateTime expressions
+use std::str::FromStr;
 use std::sync::Arc;
 
 use super::ColumnarValue;
+use crate::cube_ext::temporal::{self as cube_temporal, Zone};
 use crate::{
     error::{DataFusionError, Result},
     scalar::{ScalarType, ScalarValue},
 };
-use arrow::array::{ArrayData, StringArray};
+use arrow::array::{ArrayData, Int64Array, StringArray};
 use arrow::buffer::Buffer;
 use arrow::datatypes::ToByteSlice;
 use arrow::{
@@ -34,11 +36,8 @@ use arrow::{
     },
 };
 use arrow::{
-    array::{
-        Date32Array, Date64Array, TimestampMicrosecondArray, TimestampMillisecondArray,
-        TimestampNanosecondArray, TimestampSecondArray,
-    },
-    compute::kernels::temporal,
+    array::{Time64NanosecondArray, TimestampNanosecondArray},
+    compute,
     datatypes::TimeUnit,
     temporal_conversions::timestamp_ns_to_datetime,
 };
@@ -197,42 +196,280 @@ fn string_to_timestamp_nanos_shim(s: &str) -> Result<i64> {
     string_to_timestamp_nanos(s).map_err(|e| e.into())
 }
 
+/// Years that are entirely in the range of nanosecond timestamps, i.e. between
+/// 1677-09-21 and 2262-04-11
+const NANOSECOND_YEARS: std::ops::RangeInclusive<i32> = 1678..=2261;
+
+/// The number of `unit`s in a second
+pub(crate) fn units_per_second(unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Millisecond => 1_000,
+        TimeUnit::Microsecond => 1_000_000,
+        TimeUnit::Nanosecond => 1_000_000_000,
+    }
+}
+
+/// Converts a timestamp from the unit `from` to `to`, or returns `None` if it is out of
+/// the range of timestamps of `to`. Conversions to coarser units round down.
+pub fn convert_timestamp_unit(value: i64, from: &TimeUnit, to: &TimeUnit) -> Option<i64> {
+    let (from, to) = (units_per_second(from), units_per_second(to));
+    if from <= to {
+        value.checked_mul(to / from)
+    } else {
+        Some(value.div_euclid(from / to))
+    }
+}
+
+/// Converts a UTC datetime to a timestamp of `unit`, or returns `None` if it is out of
+/// the range of timestamps of `unit`. Unlike `timestamp_nanos()`, this never panics.
+pub fn datetime_to_timestamp(datetime: &NaiveDateTime, unit: &TimeUnit) -> Option<i64> {
+    let per_second = units_per_second(unit);
+    let subsec = datetime.timestamp_subsec_nanos() as i64 / (1_000_000_000 / per_second);
+    datetime
+        .timestamp()
+        .checked_mul(per_second)?
+        .checked_add(subsec)
+}
+
+/// Parses a fixed time zone: `UTC`, `GMT`, `Z` or a fixed offset from UTC such as
+/// `+05:30`, `-0800` or `UTC+3`. The time zone of a session may also be a zone of the
+/// tz database, see [Zone::parse].
+pub fn parse_time_zone(s: &str) -> Result<FixedOffset> {
+    let error = || {
+        DataFusionError::Plan(format!(
+            "Unsupported time zone '{}', expected UTC or a fixed offset such as +05:30",
+            s
+        ))
+    };
+    let upper = s.trim().to_uppercase();
+    if upper.is_empty() {
+        return Err(error());
+    }
+    let offset = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper);
+    if offset.is_empty() || offset == "Z" {
+        return Ok(FixedOffset::east(0));
+    }
+    let (sign, offset) = match offset.as_bytes()[0] {
+        b'+' => (1, &offset[1..]),
+        b'-' => (-1, &offset[1..]),
+        _ => return Err(error()),
+    };
+    let (hours, minutes) = match offset.split_once(':') {
+        Some(parts) => parts,
+        None if offset.len() > 2 => offset.split_at(offset.len() - 2),
+        None => (offset, "0"),
+    };
+    let parse = |v: &str| match v.chars().all(|c| c.is_ascii_digit()) {
+        true => u32::from_str(v).ok(),
+        false => None,
+    };
+    match (parse(hours), parse(minutes)) {
+        (Some(hours), Some(minutes)) if hours < 24 && minutes < 60 => Ok(
+            FixedOffset::east(sign * (hours * 3600 + minutes * 60) as i32),
+        ),
+        _ => Err(error()),
+    }
+}
+
+/// Parses the formats accepted by [string_to_timestamp_nanos] into a UTC datetime,
+/// treating timestamps without an offset as local times of `time_zone`
+fn string_to_datetime(s: &str, time_zone: &Zone) -> Result<NaiveDateTime> {
+    let error =
+        || DataFusionError::Execution(format!("Error parsing '{}' as timestamp", s));
+    if let Ok(ts) = DateTime::parse_from_rfc3339(s) {
+        return Ok(ts.naive_utc());
+    }
+    if let Ok(ts) = DateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f%:z") {
+        return Ok(ts.naive_utc());
+    }
+    if let Ok(ts) = Utc.datetime_from_str(s, "%Y-%m-%d %H:%M:%S%.fZ") {
+        return Ok(ts.naive_utc());
+    }
+    for format in &[
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S",
+    ] {
+        if let Ok(ts) = NaiveDateTime::parse_from_str(s, format) {
+            return time_zone.to_utc(&ts).ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "Error parsing '{}' as timestamp: there is no such local time in \
+                     time zone {}",
+                    s, time_zone
+                ))
+            });
+        }
+    }
+    Err(error())
+}
+
+/// Parses a timestamp string into a timestamp of `unit`.
+///
+/// Strings are parsed by [string_to_timestamp_nanos], except for dates outside of the
+/// range of nanosecond timestamps, which it cannot represent. Those are parsed with the
+/// same formats and fail with an error if they are out of the range of `unit` as well.
+pub fn string_to_timestamp(s: &str, unit: &TimeUnit) -> Result<i64> {
+    string_to_timestamp_in_zone(s, unit, &Zone::utc())
+}
+
+/// Parses a timestamp string into a timestamp of `unit` like [string_to_timestamp],
+/// interpreting timestamps without an offset as local times of `time_zone`
+pub fn string_to_timestamp_in_zone(
+    s: &str,
+    unit: &TimeUnit,
+    time_zone: &Zone,
+) -> Result<i64> {
+    if *time_zone == Zone::utc() {
+        let year = s.trim_start().split('-').next().map(i32::from_str);
+        if let Some(Ok(year)) = year {
+            if NANOSECOND_YEARS.contains(&year) {
+                let nanos = string_to_timestamp_nanos_shim(s)?;
+                return Ok(nanos / (1_000_000_000 / units_per_second(unit)));
+            }
+        }
+    }
+    let datetime = string_to_datetime(s, time_zone)?;
+    datetime_to_timestamp(&datetime, unit).ok_or_else(|| {
+        DataFusionError::Execution(format!(
+            "Timestamp '{}' is out of the range of {:?} timestamps{}",
+            s,
+            unit,
+            match unit {
+                TimeUnit::Nanosecond =>
+                    ", consider a coarser unit, e.g. to_timestamp_micros",
+                _ => "",
+            }
+        ))
+    })
+}
+
 /// to_timestamp SQL function
 pub fn to_timestamp(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    handle::<TimestampNanosecondType, _, TimestampNanosecondType>(
-        args,
-        string_to_timestamp_nanos_shim,
-        "to_timestamp",
-    )
+    to_timestamp_in_zone(args, &TimeUnit::Nanosecond, &Zone::utc())
 }
 
 /// to_timestamp_millis SQL function
 pub fn to_timestamp_millis(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    handle::<TimestampMillisecondType, _, TimestampMillisecondType>(
-        args,
-        |s| string_to_timestamp_nanos_shim(s).map(|n| n / 1_000_000),
-        "to_timestamp_millis",
-    )
+    to_timestamp_in_zone(args, &TimeUnit::Millisecond, &Zone::utc())
 }
 
 /// to_timestamp_micros SQL function
 pub fn to_timestamp_micros(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    handle::<TimestampMicrosecondType, _, TimestampMicrosecondType>(
-        args,
-        |s| string_to_timestamp_nanos_shim(s).map(|n| n / 1_000),
-        "to_timestamp_micros",
-    )
+    to_timestamp_in_zone(args, &TimeUnit::Microsecond, &Zone::utc())
 }
 
 /// to_timestamp_seconds SQL function
 pub fn to_timestamp_seconds(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    handle::<TimestampSecondType, _, TimestampSecondType>(
-        args,
-        |s| string_to_timestamp_nanos_shim(s).map(|n| n / 1_000_000_000),
-        "to_timestamp_seconds",
+    to_timestamp_in_zone(args, &TimeUnit::Second, &Zone::utc())
+}
+
+/// to_timestamp SQL functions of `unit` for strings, interpreting timestamps without an
+/// offset as local times of `time_zone`
+pub fn to_timestamp_in_zone(
+    args: &[ColumnarValue],
+    unit: &TimeUnit,
+    time_zone: &Zone,
+) -> Result<ColumnarValue> {
+    let parse = |s: &str| string_to_timestamp_in_zone(s, unit, time_zone);
+    match unit {
+        TimeUnit::Second => handle::<TimestampSecondType, _, TimestampSecondType>(
+            args,
+            parse,
+            "to_timestamp_seconds",
+        ),
+        TimeUnit::Millisecond => handle::<
+            TimestampMillisecondType,
+            _,
+            TimestampMillisecondType,
+        >(args, parse, "to_timestamp_millis"),
+        TimeUnit::Microsecond => handle::<
+            TimestampMicrosecondType,
+            _,
+            TimestampMicrosecondType,
+        >(args, parse, "to_timestamp_micros"),
+        TimeUnit::Nanosecond => handle::<
+            TimestampNanosecondType,
+            _,
+            TimestampNanosecondType,
+        >(args, parse, "to_timestamp"),
+    }
+}
+
+/// Maximum number of fractional digits of the seconds returned by `now()` and the
+/// other current time functions
+pub const MAX_TIME_PRECISION: u32 = 9;
+
+const NANOS_PER_DAY: i64 = 86_400_000_000_000;
+
+/// Validates the precision argument of `now()` and the other current time functions
+pub fn time_precision(precision: &ScalarValue) -> Result<u32> {
+    let value = match precision {
+        ScalarValue::Int8(Some(v)) => *v as i64,
+        ScalarValue::Int16(Some(v)) => *v as i64,
+        ScalarValue::Int32(Some(v)) => *v as i64,
+        ScalarValue::Int64(Some(v)) => *v,
+        ScalarValue::UInt8(Some(v)) => *v as i64,
+        ScalarValue::UInt16(Some(v)) => *v as i64,
+        ScalarValue::UInt32(Some(v)) => *v as i64,
+        ScalarValue::UInt64(Some(v)) => *v as i64,
+        other => {
+            return Err(DataFusionError::Plan(format!(
+            "Precision of the current time must be a non-null integer constant, got {:?}",
+            other
+        )))
+        }
+    };
+    if (0..=MAX_TIME_PRECISION as i64).contains(&value) {
+        Ok(value as u32)
+    } else {
+        Err(DataFusionError::Plan(format!(
+            "Precision of the current time must be between 0 and {}, got {}",
+            MAX_TIME_PRECISION, value
+        )))
+    }
+}
+
+/// Rounds nanoseconds to `precision` fractional digits of a second
+fn round_nanos(nanos: i64, precision: Option<u32>) -> i64 {
+    match precision {
+        Some(precision) if precision < MAX_TIME_PRECISION => {
+            let unit = 10_i64.pow(MAX_TIME_PRECISION - precision);
+            (nanos + unit / 2).div_euclid(unit) * unit
+        }
+        _ => nanos,
+    }
+}
+
+/// The value of `now()` with `precision` fractional digits of a second
+pub fn now_nanos(now_ts: DateTime<Utc>, precision: Option<u32>) -> i64 {
+    round_nanos(now_ts.timestamp_nanos(), precision)
+}
+
+/// The value of `localtimestamp`: the local time of `time_zone` at `now_ts` with
+/// `precision` fractional digits of a second
+pub fn local_now_nanos(
+    now_ts: DateTime<Utc>,
+    time_zone: &Zone,
+    precision: Option<u32>,
+) -> i64 {
+    let offset = time_zone.offset_at(&now_ts.naive_utc()).local_minus_utc();
+    round_nanos(
+        now_ts.timestamp_nanos() + offset as i64 * 1_000_000_000,
+        precision,
     )
 }
 
+/// The value of `current_date`: the days since the epoch of the local date of
+/// `time_zone` at `now_ts`
+pub fn current_date_days(now_ts: DateTime<Utc>, time_zone: &Zone) -> i32 {
+    local_now_nanos(now_ts, time_zone, None).div_euclid(NANOS_PER_DAY) as i32
+}
+
 /// Create an implementation of `now()` that always returns the
 /// specified timestamp.
 ///
@@ -241,8 +478,9 @@ pub fn to_timestamp_seconds(args: &[ColumnarValue]) -> Result<ColumnarValue> {
 /// planning time and bound into a closure that
 pub fn make_now(
     now_ts: DateTime<Utc>,
+    precision: Option<u32>,
 ) -> impl Fn(&[ColumnarValue]) -> Result<ColumnarValue> {
-    let now_ts = Some(now_ts.timestamp_nanos());
+    let now_ts = Some(now_nanos(now_ts, precision));
     move |_arg| {
         Ok(ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(
             now_ts,
@@ -250,11 +488,59 @@ pub fn make_now(
     }
 }
 
+/// Create an implementation of `localtimestamp` that always returns the local time
+/// of `time_zone` at `now_ts`, chosen during planning like [make_now]
+pub fn make_local_timestamp(
+    now_ts: DateTime<Utc>,
+    time_zone: Zone,
+    precision: Option<u32>,
+) -> impl Fn(&[ColumnarValue]) -> Result<ColumnarValue> {
+    let local_ts = Some(local_now_nanos(now_ts, &time_zone, precision));
+    move |_arg| {
+        Ok(ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(
+            local_ts,
+        )))
+    }
+}
+
+/// Create an implementation of `current_date` that always returns the local date of
+/// `time_zone` at `now_ts`, chosen during planning like [make_now]
+pub fn make_current_date(
+    now_ts: DateTime<Utc>,
+    time_zone: Zone,
+) -> impl Fn(&[ColumnarValue]) -> Result<ColumnarValue> {
+    let days = Some(current_date_days(now_ts, &time_zone));
+    move |_arg| Ok(ColumnarValue::Scalar(ScalarValue::Date32(days)))
+}
+
+/// Create an implementation of `current_time` and `localtime` that always returns the
+/// local time of day of `time_zone` at `now_ts`, chosen during planning like
+/// [make_now]. As times have no scalar value, an array as long as the batch is
+/// returned.
+pub fn make_current_time(
+    now_ts: DateTime<Utc>,
+    time_zone: Zone,
+    precision: Option<u32>,
+) -> impl Fn(&[ColumnarValue]) -> Result<ColumnarValue> {
+    let nanos = local_now_nanos(now_ts, &time_zone, precision).rem_euclid(NANOS_PER_DAY);
+    move |args| {
+        // functions without arguments get a null array as long as the batch
+        let len = match args.first() {
+            Some(ColumnarValue::Array(array)) => array.len(),
+            _ => 1,
+        };
+        Ok(ColumnarValue::Array(Arc::new(Time64NanosecondArray::from(
+            vec![nanos; len],
+        ))))
+    }
+}
+
 fn quarter_month(date: &NaiveDateTime) -> u32 {
     1 + 3 * ((date.month() - 1) / 3)
 }
 
 fn date_trunc_single(granularity: &str, value: i64) -> Result<i64> {
+    let original = value;
     let value = timestamp_ns_to_datetime(value).with_nanosecond(0);
     let value = match granularity {
         "second" => value,
@@ -296,7 +582,15 @@ fn date_trunc_single(granularity: &str, value: i64) -> Result<i64> {
         }
     };
     // `with_x(0)` are infalible because `0` are always a valid
-    Ok(value.unwrap().timestamp_nanos())
+    let truncated = value.unwrap();
+    datetime_to_timestamp(&truncated, &TimeUnit::Nanosecond).ok_or_else(|| {
+        DataFusionError::Execution(format!(
+            "date_trunc of {} to {} is {}, which is out of the range of nanosecond timestamps",
+            timestamp_ns_to_datetime(original),
+            granularity,
+            truncated
+        ))
+    })
 }
 
 /// date_trunc SQL function
@@ -328,7 +622,12 @@ pub fn date_trunc(args: &[ColumnarValue]) -> Result<ColumnarValue> {
             let array = array
                 .as_any()
                 .downcast_ref::<TimestampNanosecondArray>()
-                .unwrap();
+                .ok_or_else(|| {
+                    DataFusionError::Execution(format!(
+                        "array of `date_trunc` must be of type Timestamp(Nanosecond, None), got {:?}",
+                        array.data_type()
+                    ))
+                })?;
             let array = array
                 .iter()
                 .map(f)
@@ -339,57 +638,105 @@ pub fn date_trunc(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     })
 }
 
-macro_rules! extract_date_part {
-    ($ARRAY: expr, $FN:expr) => {
-        match $ARRAY.data_type() {
-            DataType::Date32 => {
-                let array = $ARRAY.as_any().downcast_ref::<Date32Array>().unwrap();
-                Ok($FN(array)?)
-            }
-            DataType::Date64 => {
-                let array = $ARRAY.as_any().downcast_ref::<Date64Array>().unwrap();
-                Ok($FN(array)?)
-            }
-            DataType::Timestamp(time_unit, None) => match time_unit {
-                TimeUnit::Second => {
-                    let array = $ARRAY
-                        .as_any()
-                        .downcast_ref::<TimestampSecondArray>()
-                        .unwrap();
-                    Ok($FN(array)?)
-                }
-                TimeUnit::Millisecond => {
-                    let array = $ARRAY
-                        .as_any()
-                        .downcast_ref::<TimestampMillisecondArray>()
-                        .unwrap();
-                    Ok($FN(array)?)
-                }
-                TimeUnit::Microsecond => {
-                    let array = $ARRAY
-                        .as_any()
-                        .downcast_ref::<TimestampMicrosecondArray>()
-                        .unwrap();
-                    Ok($FN(array)?)
-                }
-                TimeUnit::Nanosecond => {
-                    let array = $ARRAY
-                        .as_any()
-                        .downcast_ref::<TimestampNanosecondArray>()
-                        .unwrap();
-                    Ok($FN(array)?)
-                }
-            },
-            datatype => Err(DataFusionError::Internal(format!(
-                "Extract does not support datatype {:?}",
-                datatype
-            ))),
+/// Shifts timestamps without a time zone, which are in UTC, to the local times of
+/// `time_zone`. Other arrays are returned as they are.
+fn to_local_timestamps(array: ArrayRef, time_zone: &Zone) -> Result<ArrayRef> {
+    match array.data_type() {
+        DataType::Timestamp(unit, None) if *time_zone != Zone::utc() => {
+            let units = units_per_second(unit);
+            let values = compute::cast(&array, &DataType::Int64)?;
+            let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+            let shifted = values
+                .iter()
+                .map(|v| {
+                    v.map(|v| {
+                        // the offset of a named zone depends on the instant
+                        let offset =
+                            NaiveDateTime::from_timestamp_opt(v.div_euclid(units), 0)
+                                .map(|utc| {
+                                    time_zone.offset_at(&utc).local_minus_utc() as i64
+                                });
+                        offset
+                            .and_then(|offset| v.checked_add(offset * units))
+                            .ok_or_else(|| {
+                                DataFusionError::Execution(format!(
+                                    "Timestamp {} is out of range in time zone {}",
+                                    v, time_zone
+                                ))
+                            })
+                    })
+                    .transpose()
+                })
+                .collect::<Result<Int64Array>>()?;
+            Ok(compute::cast(
+                &(Arc::new(shifted) as ArrayRef),
+                array.data_type(),
+            )?)
         }
+        _ => Ok(array),
+    }
+}
+
+/// Applies `op` to the local dates of `time_zone` of `args[0]` and to the week modes
+/// of `args[1]`, which default to 0
+fn map_week_modes<F>(args: &[ArrayRef], time_zone: &Zone, op: F) -> Result<ArrayRef>
+where
+    F: Fn(NaiveDate, u32) -> i32,
+{
+    let modes = match args.get(1) {
+        Some(modes) => {
+            Some(modes.as_any().downcast_ref::<Int64Array>().ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "Week mode must be Int64, got {:?}",
+                    modes.data_type()
+                ))
+            })?)
+        }
+        None => None,
     };
+    let dates = to_local_timestamps(args[0].clone(), time_zone)?;
+    let weeks = cube_temporal::map_dates(&dates, |date, i| {
+        Ok(match modes {
+            Some(modes) if modes.is_null(i) => None,
+            Some(modes) => Some(op(date, modes.value(i) as u32)),
+            None => Some(op(date, 0)),
+        })
+    })?;
+    Ok(Arc::new(weeks))
+}
+
+/// WEEK SQL function: the week of a date in the mode of MySQL's `WEEK`
+pub fn week(args: &[ArrayRef], time_zone: &Zone) -> Result<ArrayRef> {
+    map_week_modes(args, time_zone, |date, mode| {
+        cube_temporal::week(date, mode) as i32
+    })
+}
+
+/// YEARWEEK SQL function: the year and week of a date as `year * 100 + week`, in the
+/// mode of MySQL's `YEARWEEK`
+pub fn year_week(args: &[ArrayRef], time_zone: &Zone) -> Result<ArrayRef> {
+    map_week_modes(args, time_zone, cube_temporal::year_week)
+}
+
+/// ISOWEEK SQL function: the ISO 8601 week of a date
+pub fn iso_week(args: &[ArrayRef], time_zone: &Zone) -> Result<ArrayRef> {
+    map_week_modes(&args[..1], time_zone, |date, _| {
+        cube_temporal::iso_week(date) as i32
+    })
 }
 
 /// DATE_PART SQL function
 pub fn date_part(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    date_part_in_zone(args, &Zone::utc())
+}
+
+/// DATE_PART SQL function, extracting the parts of timestamps without a time zone from
+/// their local times in `time_zone`. Timestamps with a time zone use the local times of
+/// their own zone. See [cube_temporal::date_part] for the supported parts.
+pub fn date_part_in_zone(
+    args: &[ColumnarValue],
+    time_zone: &Zone,
+) -> Result<ColumnarValue> {
     if args.len() != 2 {
         return Err(DataFusionError::Execution(
             "Expected two arguments in DATE_PART".to_string(),
@@ -411,23 +758,12 @@ pub fn date_part(args: &[ColumnarValue]) -> Result<ColumnarValue> {
         ColumnarValue::Array(array) => array.clone(),
         ColumnarValue::Scalar(scalar) => scalar.to_array(),
     };
-
-    let arr = match date_part.to_lowercase().as_str() {
-        "hour" => extract_date_part!(array, temporal::hour),
-        "year" => extract_date_part!(array, temporal::year),
-        _ => Err(DataFusionError::Execution(format!(
-            "Date part '{}' not supported",
-            date_part
-        ))),
-    }?;
+    let arr = cube_temporal::date_part(&array, date_part, time_zone)?;
 
     Ok(if is_scalar {
-        ColumnarValue::Scalar(ScalarValue::try_from_array(
-            &(Arc::new(arr) as ArrayRef),
-            0,
-        )?)
+        ColumnarValue::Scalar(ScalarValue::try_from_array(&arr, 0)?)
     } else {
-        ColumnarValue::Array(Arc::new(arr))
+        ColumnarValue::Array(arr)
     })
 }
 
@@ -435,7 +771,7 @@ pub fn date_part(args: &[ColumnarValue]) -> Result<ColumnarValue> {
 mod tests {
     use std::sync::Arc;
 
-    use arrow::array::{ArrayRef, Int64Array, StringBuilder};
+    use arrow::array::{ArrayRef, Int64Array, StringBuilder, TimestampSecondArray};
 
     use super::*;
     use arrow::compute::kernels::cast_utils::string_to_timestamp_nanos;
@@ -562,6 +898,198 @@ mod tests {
         });
     }
 
+    #[test]
+    fn date_trunc_out_of_range() {
+        // 1677-09-21T00:12:43.145224192, the earliest nanosecond timestamp
+        let err = date_trunc_single("year", i64::MIN).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("out of the range of nanosecond timestamps"),
+            "{}",
+            err
+        );
+        assert!(date_trunc_single("second", i64::MIN).is_err());
+        assert!(date_trunc_single("day", i64::MAX).is_ok());
+    }
+
+    #[test]
+    fn string_to_timestamp_far_dates() -> Result<()> {
+        let s = "2020-09-08T13:42:29.190855Z";
+        assert_eq!(
+            string_to_timestamp(s, &TimeUnit::Millisecond)?,
+            1_599_572_549_190
+        );
+        assert_eq!(
+            string_to_timestamp(s, &TimeUnit::Nanosecond)?,
+            string_to_timestamp_nanos(s)?
+        );
+
+        let s = "3000-01-01T00:00:00Z";
+        assert_eq!(
+            string_to_timestamp(s, &TimeUnit::Microsecond)?,
+            32_503_680_000_000_000
+        );
+        assert_eq!(string_to_timestamp(s, &TimeUnit::Second)?, 32_503_680_000);
+        let err = string_to_timestamp(s, &TimeUnit::Nanosecond).unwrap_err();
+        assert!(err.to_string().contains("to_timestamp_micros"), "{}", err);
+
+        assert_eq!(
+            string_to_timestamp("1500-06-01 12:00:00", &TimeUnit::Millisecond)?,
+            -14_818_680_000_000
+        );
+        assert!(string_to_timestamp("3000-01-01 xx", &TimeUnit::Second).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn convert_timestamp_units() {
+        use TimeUnit::*;
+
+        assert_eq!(
+            convert_timestamp_unit(1_500, &Millisecond, &Second),
+            Some(1)
+        );
+        assert_eq!(
+            convert_timestamp_unit(-1_500, &Millisecond, &Second),
+            Some(-2)
+        );
+        assert_eq!(
+            convert_timestamp_unit(3, &Second, &Nanosecond),
+            Some(3_000_000_000)
+        );
+        assert_eq!(
+            convert_timestamp_unit(i64::MAX / 10, &Second, &Nanosecond),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_time_zones() -> Result<()> {
+        assert_eq!(parse_time_zone("UTC")?, FixedOffset::east(0));
+        assert_eq!(parse_time_zone("z")?, FixedOffset::east(0));
+        assert_eq!(parse_time_zone("+05:30")?, FixedOffset::east(19_800));
+        assert_eq!(parse_time_zone("-0800")?, FixedOffset::west(28_800));
+        assert_eq!(parse_time_zone("UTC+3")?, FixedOffset::east(10_800));
+        assert_eq!(parse_time_zone("gmt-03:30")?, FixedOffset::west(12_600));
+        for invalid in &["", "Europe/Berlin", "+25:00", "+05:75", "+5:+3", "UTC+"] {
+            let err = parse_time_zone(invalid).unwrap_err();
+            assert!(err.to_string().contains("Unsupported time zone"), "{}", err);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn string_to_timestamp_in_time_zone() -> Result<()> {
+        let zone = Zone::Fixed(FixedOffset::east(5 * 3600));
+        assert_eq!(
+            string_to_timestamp_in_zone("2020-01-01 10:00:00", &TimeUnit::Second, &zone)?,
+            1_577_854_800
+        );
+        // explicit offsets take precedence over the time zone
+        assert_eq!(
+            string_to_timestamp_in_zone(
+                "2020-01-01T10:00:00Z",
+                &TimeUnit::Second,
+                &zone
+            )?,
+            1_577_872_800
+        );
+        assert_eq!(
+            string_to_timestamp_in_zone(
+                "2020-01-01T10:00:00+01:00",
+                &TimeUnit::Second,
+                &zone
+            )?,
+            1_577_869_200
+        );
+
+        // the offset of a named zone depends on the date
+        let new_york = Zone::parse("America/New_York")?;
+        assert_eq!(
+            string_to_timestamp_in_zone(
+                "2021-01-15 12:00:00",
+                &TimeUnit::Second,
+                &new_york
+            )?,
+            1_610_730_000
+        );
+        assert_eq!(
+            string_to_timestamp_in_zone(
+                "2021-07-15 12:00:00",
+                &TimeUnit::Second,
+                &new_york
+            )?,
+            1_626_364_800
+        );
+        let err = string_to_timestamp_in_zone(
+            "2021-03-14 02:30:00",
+            &TimeUnit::Second,
+            &new_york,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("no such local time"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn date_part_in_time_zone() -> Result<()> {
+        // 2020-01-01T22:00:00Z
+        let timestamps: ArrayRef =
+            Arc::new(TimestampSecondArray::from_vec(vec![1_577_916_000], None));
+        let part = |name: &str, zone: FixedOffset| -> Result<Vec<i32>> {
+            let args = [
+                ColumnarValue::Scalar(ScalarValue::Utf8(Some(name.to_string()))),
+                ColumnarValue::Array(timestamps.clone()),
+            ];
+            match date_part_in_zone(&args, &zone.into())? {
+                ColumnarValue::Array(a) => Ok(a
+                    .as_any()
+                    .downcast_ref::<arrow::array::Int32Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()),
+                ColumnarValue::Scalar(_) => panic!("Expected an array"),
+            }
+        };
+        assert_eq!(part("hour", FixedOffset::east(0))?, vec![22]);
+        assert_eq!(part("hour", FixedOffset::east(5 * 3600))?, vec![3]);
+        assert_eq!(part("year", FixedOffset::east(5 * 3600))?, vec![2021]);
+        assert_eq!(part("year", FixedOffset::west(5 * 3600))?, vec![2020]);
+        Ok(())
+    }
+
+    #[test]
+    fn current_time_functions() -> Result<()> {
+        let now_ts = Utc.ymd(2021, 3, 1).and_hms_nano(23, 59, 59, 999_600_000);
+        let east = Zone::Fixed(FixedOffset::east(2 * 3600));
+        let west = Zone::Fixed(FixedOffset::west(3 * 3600));
+
+        assert_eq!(now_nanos(now_ts, None), now_ts.timestamp_nanos());
+        // rounds to the next day
+        assert_eq!(now_nanos(now_ts, Some(3)), 1_614_643_200_000_000_000);
+        assert_eq!(
+            local_now_nanos(now_ts, &west, Some(0)),
+            1_614_643_200_000_000_000 - 3 * 3_600_000_000_000
+        );
+        assert_eq!(current_date_days(now_ts, &east), 18_688);
+        assert_eq!(current_date_days(now_ts, &west), 18_687);
+
+        let batch = ColumnarValue::Array(Arc::new(arrow::array::NullArray::new(3)));
+        match make_current_time(now_ts, west, Some(0))(&[batch])? {
+            ColumnarValue::Array(a) => {
+                let a = a.as_any().downcast_ref::<Time64NanosecondArray>().unwrap();
+                assert_eq!(a.values(), &[75_600_000_000_000; 3]);
+            }
+            ColumnarValue::Scalar(_) => panic!("Expected an array"),
+        }
+
+        assert_eq!(time_precision(&ScalarValue::Int64(Some(6)))?, 6);
+        assert!(time_precision(&ScalarValue::Int64(Some(10))).is_err());
+        assert!(time_precision(&ScalarValue::Int64(None)).is_err());
+        assert!(time_precision(&ScalarValue::Utf8(Some("3".to_string()))).is_err());
+        Ok(())
+    }
+
     #[test]
     fn to_timestamp_invalid_input_type() -> Result<()> {
         // pass the wrong type of input array to to_timestamp and test
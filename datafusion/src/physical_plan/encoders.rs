@@ -0,0 +1,317 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Encoders of the record batches of result streams into the bytes sent by server
+//! frontends: an Arrow IPC stream or newline-delimited JSON rows.
+
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use arrow::array::*;
+use arrow::datatypes::DataType;
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
+use futures::{Stream, StreamExt};
+use serde_json::{Number, Value};
+
+use super::SendableRecordBatchStream;
+use crate::arrow_print::FloatFormat;
+use crate::error::{DataFusionError, Result};
+
+/// A stream of encoded chunks of bytes, one per record batch of the encoded stream
+pub type EncodedStream = Pin<Box<dyn Stream<Item = Result<Vec<u8>>> + Send>>;
+
+/// Encodes `stream` as an Arrow IPC stream. The first chunk starts with the schema
+/// message and the last one, after the chunks of the batches, ends the IPC stream.
+/// The encoding stops at the first error.
+pub fn ipc_encode(stream: SendableRecordBatchStream) -> Result<EncodedStream> {
+    let buffer = SharedBuffer::default();
+    let writer = StreamWriter::try_new(buffer.clone(), &stream.schema())?;
+    let state = Some((stream, writer, buffer));
+    Ok(Box::pin(futures::stream::unfold(
+        state,
+        |state| async move {
+            let (mut stream, mut writer, buffer) = state?;
+            match stream.next().await {
+                Some(batch) => match batch.and_then(|batch| writer.write(&batch)) {
+                    Ok(()) => Some((Ok(buffer.take()), Some((stream, writer, buffer)))),
+                    Err(e) => Some((Err(DataFusionError::from(e)), None)),
+                },
+                None => {
+                    let end = writer.finish().map(|_| buffer.take());
+                    Some((end.map_err(DataFusionError::from), None))
+                }
+            }
+        },
+    )))
+}
+
+/// Encodes `stream` as newline-delimited JSON rows, see [write_json_rows]
+pub fn json_encode(
+    stream: SendableRecordBatchStream,
+    float_format: FloatFormat,
+) -> EncodedStream {
+    Box::pin(stream.map(move |batch| {
+        let mut rows = vec![];
+        write_json_rows(&batch?, &float_format, &mut rows)?;
+        Ok(rows)
+    }))
+}
+
+/// Writes the rows of `batch` as JSON objects, one per line, whose keys are the
+/// column names in the order of the schema. The values keep their exact type:
+///
+/// * integers up to 64 bits, and finite floats, are JSON numbers. Floats are written
+///   as formatted by `float_format`, by default with their shortest representation,
+///   `0.1` rather than `0.10000000149011612` for a `Float32`. NaN and infinities are
+///   strings or NULL values, depending on `float_format`.
+/// * lists are JSON arrays and structs JSON objects.
+/// * the values without an exact JSON representation, such as decimals, 96-bit
+///   integers, dates, timestamps and binary values, are strings of their text.
+pub fn write_json_rows<W: Write>(
+    batch: &RecordBatch,
+    float_format: &FloatFormat,
+    writer: &mut W,
+) -> Result<()> {
+    let schema = batch.schema();
+    for row in 0..batch.num_rows() {
+        let fields = schema.fields().iter().map(|f| f.name().as_str());
+        write_object(writer, float_format, fields.zip(batch.columns()), row)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Writes the values of `columns` at `row` as a JSON object of their names
+fn write_object<'a, W: Write>(
+    writer: &mut W,
+    float_format: &FloatFormat,
+    columns: impl Iterator<Item = (&'a str, &'a ArrayRef)>,
+    row: usize,
+) -> Result<()> {
+    writer.write_all(b"{")?;
+    for (i, (name, column)) in columns.enumerate() {
+        if i != 0 {
+            writer.write_all(b",")?;
+        }
+        write_json(writer, &Value::from(name))?;
+        writer.write_all(b":")?;
+        write_value(writer, float_format, column, row)?;
+    }
+    writer.write_all(b"}")?;
+    Ok(())
+}
+
+/// Writes the value of `array` at `row` as JSON
+fn write_value<W: Write>(
+    writer: &mut W,
+    float_format: &FloatFormat,
+    array: &ArrayRef,
+    row: usize,
+) -> Result<()> {
+    if array.is_null(row) {
+        writer.write_all(b"null")?;
+        return Ok(());
+    }
+    match array.data_type() {
+        DataType::List(_) => {
+            let values = array
+                .as_any()
+                .downcast_ref::<ListArray>()
+                .unwrap()
+                .value(row);
+            writer.write_all(b"[")?;
+            for i in 0..values.len() {
+                if i != 0 {
+                    writer.write_all(b",")?;
+                }
+                write_value(writer, float_format, &values, i)?;
+            }
+            writer.write_all(b"]")?;
+            Ok(())
+        }
+        DataType::Struct(fields) => {
+            let array = array.as_any().downcast_ref::<StructArray>().unwrap();
+            let columns = (0..array.num_columns()).map(|i| array.column(i));
+            let names = fields.iter().map(|f| f.name().as_str());
+            write_object(writer, float_format, names.zip(columns), row)
+        }
+        _ => write_json(writer, &scalar_value(float_format, array, row)?),
+    }
+}
+
+macro_rules! value {
+    ($ARRAY:expr, $ROW:expr, $ARRAY_TYPE:ident) => {{
+        let array = $ARRAY.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+        Value::from(array.value($ROW))
+    }};
+}
+
+/// The JSON value of the non-null value of `array` at `row`, whose type is neither a
+/// list nor a struct
+fn scalar_value(
+    float_format: &FloatFormat,
+    array: &ArrayRef,
+    row: usize,
+) -> Result<Value> {
+    Ok(match array.data_type() {
+        DataType::Boolean => value!(array, row, BooleanArray),
+        DataType::Int8 => value!(array, row, Int8Array),
+        DataType::Int16 => value!(array, row, Int16Array),
+        DataType::Int32 => value!(array, row, Int32Array),
+        DataType::Int64 => value!(array, row, Int64Array),
+        DataType::UInt8 => value!(array, row, UInt8Array),
+        DataType::UInt16 => value!(array, row, UInt16Array),
+        DataType::UInt32 => value!(array, row, UInt32Array),
+        DataType::UInt64 => value!(array, row, UInt64Array),
+        // the formatted text of the float is kept by parsing it as a f64 rather than
+        // converting the value
+        DataType::Float32 => {
+            let array = array.as_any().downcast_ref::<Float32Array>().unwrap();
+            float_value(float_format.format_f32(array.value(row)))
+        }
+        DataType::Float64 => {
+            let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+            float_value(float_format.format(array.value(row)))
+        }
+        _ => Value::String(array_value_to_string(array, row)?),
+    })
+}
+
+fn float_value(text: Option<String>) -> Value {
+    match text {
+        Some(text) => match text.parse::<f64>().ok().and_then(Number::from_f64) {
+            Some(number) => Value::Number(number),
+            None => Value::String(text),
+        },
+        None => Value::Null,
+    }
+}
+
+fn write_json<W: Write>(writer: &mut W, value: &Value) -> Result<()> {
+    serde_json::to_writer(writer, value)
+        .map_err(|e| DataFusionError::Execution(format!("JSON encoding error: {}", e)))
+}
+
+/// The buffer written by the IPC writer, whose bytes are taken after each batch
+#[derive(Debug, Default, Clone)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrow_print::NonFiniteFloats;
+    use crate::physical_plan::common::SizedRecordBatchStream;
+    use arrow::datatypes::{Field, Int32Type, Schema};
+    use arrow::ipc::reader::StreamReader;
+
+    fn batch() -> Result<RecordBatch> {
+        let values = Int64Array::from(vec![Some(i64::MAX), None]);
+        let list = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), None]),
+            None,
+        ]);
+        let schema = Schema::new(vec![
+            Field::new("z", DataType::Int64, true),
+            Field::new("a", DataType::Float32, true),
+            Field::new("s", DataType::Utf8, true),
+            Field::new("l", list.data_type().clone(), true),
+        ]);
+        Ok(RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(values),
+                Arc::new(Float32Array::from(vec![0.1, f32::NAN])),
+                Arc::new(StringArray::from(vec![Some("a\"b"), None])),
+                Arc::new(list),
+            ],
+        )?)
+    }
+
+    fn stream(batches: Vec<RecordBatch>) -> SendableRecordBatchStream {
+        let schema = batches[0].schema();
+        let batches = batches.into_iter().map(Arc::new).collect();
+        Box::pin(SizedRecordBatchStream::new(schema, batches))
+    }
+
+    #[tokio::test]
+    async fn encode_json_rows() -> Result<()> {
+        let chunks = json_encode(stream(vec![batch()?, batch()?]), FloatFormat::new())
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(chunks.len(), 2);
+        let expected = "\
+            {\"z\":9223372036854775807,\"a\":0.1,\"s\":\"a\\\"b\",\"l\":[1,null]}\n\
+            {\"z\":null,\"a\":\"NaN\",\"s\":null,\"l\":null}\n";
+        assert_eq!(String::from_utf8(chunks[0].clone()).unwrap(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn write_formatted_floats() -> Result<()> {
+        let float_format = FloatFormat::new()
+            .with_precision(3)
+            .with_non_finite(NonFiniteFloats::Null);
+        let mut rows = vec![];
+        write_json_rows(&batch()?, &float_format, &mut rows)?;
+        let expected = "\
+            {\"z\":9223372036854775807,\"a\":0.1,\"s\":\"a\\\"b\",\"l\":[1,null]}\n\
+            {\"z\":null,\"a\":null,\"s\":null,\"l\":null}\n";
+        assert_eq!(String::from_utf8(rows).unwrap(), expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn encode_ipc_stream() -> Result<()> {
+        let chunks = ipc_encode(stream(vec![batch()?, batch()?]))?
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        // a chunk per batch and the end of the stream
+        assert_eq!(chunks.len(), 3);
+
+        let bytes = chunks.concat();
+        let reader = StreamReader::try_new(bytes.as_slice())?;
+        let batches = reader.collect::<arrow::error::Result<Vec<_>>>()?;
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[1].schema(), batch()?.schema());
+        assert_eq!(batches[1].column(0).data(), batch()?.column(0).data());
+        Ok(())
+    }
+}
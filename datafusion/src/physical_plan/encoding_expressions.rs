@@ -0,0 +1,276 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Encoding expressions: conversions between binary data and its textual
+//! (hex, base64) representation.
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::{
+    error::{DataFusionError, Result},
+    scalar::ScalarValue,
+};
+use arrow::{
+    array::{
+        Array, ArrayRef, BinaryArray, LargeBinaryArray, LargeStringArray, StringArray,
+    },
+    datatypes::DataType,
+};
+
+use super::ColumnarValue;
+
+/// Textual representations of binary data supported by `encode` and `decode`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Encoding {
+    Base64,
+    Hex,
+}
+
+impl FromStr for Encoding {
+    type Err = DataFusionError;
+    fn from_str(name: &str) -> Result<Encoding> {
+        Ok(match name.to_lowercase().as_str() {
+            "base64" => Encoding::Base64,
+            "hex" => Encoding::Hex,
+            _ => {
+                return Err(DataFusionError::Execution(format!(
+                    "There is no built-in encoding named '{}', currently supported encodings are: base64, hex",
+                    name
+                )))
+            }
+        })
+    }
+}
+
+impl Encoding {
+    fn encode(self, value: &[u8]) -> String {
+        match self {
+            Encoding::Base64 => base64::encode(value),
+            Encoding::Hex => hex::encode(value),
+        }
+    }
+
+    fn decode(self, value: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Encoding::Base64 => base64::decode(value).map_err(|e| {
+                DataFusionError::Execution(format!(
+                    "Failed to decode value using base64: {}",
+                    e
+                ))
+            }),
+            Encoding::Hex => hex::decode(value).map_err(|e| {
+                DataFusionError::Execution(format!(
+                    "Failed to decode value using hex: {}",
+                    e
+                ))
+            }),
+        }
+    }
+}
+
+/// Applies `op` to the bytes of every value of a string or binary array.
+fn map_bytes<C, O, F>(array: &dyn Array, op: F, name: &str) -> Result<C>
+where
+    C: FromIterator<Option<O>>,
+    F: Fn(&[u8]) -> Result<O>,
+{
+    macro_rules! map_array {
+        ($ARRAY_TYPE:ident, $TO_BYTES:expr) => {{
+            let array =
+                array
+                    .as_any()
+                    .downcast_ref::<$ARRAY_TYPE>()
+                    .ok_or_else(|| {
+                        DataFusionError::Internal(format!(
+                            "failed to downcast to {}",
+                            stringify!($ARRAY_TYPE)
+                        ))
+                    })?;
+            // first map is the iterator, second is for the `Option<_>`
+            array
+                .iter()
+                .map(|x| x.map(|x| op($TO_BYTES(x))).transpose())
+                .collect()
+        }};
+    }
+
+    match array.data_type() {
+        DataType::Utf8 => map_array!(StringArray, str::as_bytes),
+        DataType::LargeUtf8 => map_array!(LargeStringArray, str::as_bytes),
+        DataType::Binary => map_array!(BinaryArray, |x| x),
+        DataType::LargeBinary => map_array!(LargeBinaryArray, |x| x),
+        other => Err(DataFusionError::Internal(format!(
+            "Unsupported data type {:?} for function {}",
+            other, name,
+        ))),
+    }
+}
+
+/// Evaluates `fun` over the first argument as an array, converting back to a
+/// scalar when the argument was a scalar.
+fn handle<F>(args: &[ColumnarValue], fun: F) -> Result<ColumnarValue>
+where
+    F: Fn(&dyn Array) -> Result<ArrayRef>,
+{
+    match &args[0] {
+        ColumnarValue::Array(a) => Ok(ColumnarValue::Array(fun(a.as_ref())?)),
+        ColumnarValue::Scalar(scalar) => {
+            let result = fun(scalar.to_array().as_ref())?;
+            Ok(ColumnarValue::Scalar(ScalarValue::try_from_array(
+                &result, 0,
+            )?))
+        }
+    }
+}
+
+/// Returns the encoding named by the second (constant) argument of `name`.
+fn encoding_arg(args: &[ColumnarValue], name: &str) -> Result<Option<Encoding>> {
+    match &args[1] {
+        ColumnarValue::Scalar(ScalarValue::Utf8(v))
+        | ColumnarValue::Scalar(ScalarValue::LargeUtf8(v)) => {
+            v.as_deref().map(Encoding::from_str).transpose()
+        }
+        _ => Err(DataFusionError::NotImplemented(format!(
+            "{} only supports a constant encoding",
+            name
+        ))),
+    }
+}
+
+fn encode_array(array: &dyn Array, encoding: Encoding, name: &str) -> Result<ArrayRef> {
+    let op = |x: &[u8]| Ok(encoding.encode(x));
+    Ok(match array.data_type() {
+        DataType::LargeUtf8 | DataType::LargeBinary => {
+            Arc::new(map_bytes::<LargeStringArray, _, _>(array, op, name)?)
+        }
+        _ => Arc::new(map_bytes::<StringArray, _, _>(array, op, name)?),
+    })
+}
+
+fn decode_array(array: &dyn Array, encoding: Encoding, name: &str) -> Result<ArrayRef> {
+    let op = |x: &[u8]| encoding.decode(x);
+    Ok(match array.data_type() {
+        DataType::LargeUtf8 | DataType::LargeBinary => {
+            Arc::new(map_bytes::<LargeBinaryArray, _, _>(array, op, name)?)
+        }
+        _ => Arc::new(map_bytes::<BinaryArray, _, _>(array, op, name)?),
+    })
+}
+
+/// Returns a null result of `data_type` shaped like the first argument.
+fn null_result(args: &[ColumnarValue], data_type: &DataType) -> Result<ColumnarValue> {
+    Ok(match &args[0] {
+        ColumnarValue::Array(a) => {
+            ColumnarValue::Array(arrow::array::new_null_array(data_type, a.len()))
+        }
+        ColumnarValue::Scalar(_) => {
+            ColumnarValue::Scalar(ScalarValue::try_from(data_type)?)
+        }
+    })
+}
+
+/// Encodes binary data (or the bytes of a string) into its textual representation.
+/// encode('tom', 'base64') = 'dG9t'
+pub fn encode(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    match encoding_arg(args, "encode")? {
+        Some(encoding) => handle(args, |a| encode_array(a, encoding, "encode")),
+        None => null_result(args, &encode_return_type(&args[0].data_type())),
+    }
+}
+
+/// Decodes the textual representation of binary data.
+/// decode('dG9t', 'base64') = 'tom'::bytea
+pub fn decode(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    match encoding_arg(args, "decode")? {
+        Some(encoding) => handle(args, |a| decode_array(a, encoding, "decode")),
+        None => null_result(args, &decode_return_type(&args[0].data_type())),
+    }
+}
+
+/// Converts binary data to its hexadecimal representation.
+/// to_hex('tom'::bytea) = '746f6d'
+pub fn to_hex(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    handle(args, |a| encode_array(a, Encoding::Hex, "to_hex"))
+}
+
+/// Converts a hexadecimal string to binary data.
+/// from_hex('746f6d') = 'tom'::bytea
+pub fn from_hex(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    handle(args, |a| decode_array(a, Encoding::Hex, "from_hex"))
+}
+
+/// Return type of `encode` (and binary `to_hex`) for an input of `arg_type`
+pub(crate) fn encode_return_type(arg_type: &DataType) -> DataType {
+    match arg_type {
+        DataType::LargeUtf8 | DataType::LargeBinary => DataType::LargeUtf8,
+        _ => DataType::Utf8,
+    }
+}
+
+/// Return type of `decode` (and `from_hex`) for an input of `arg_type`
+pub(crate) fn decode_return_type(arg_type: &DataType) -> DataType {
+    match arg_type {
+        DataType::LargeUtf8 | DataType::LargeBinary => DataType::LargeBinary,
+        _ => DataType::Binary,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf8(v: &str) -> ColumnarValue {
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(v.to_string())))
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() -> Result<()> {
+        let input: ArrayRef = Arc::new(LargeBinaryArray::from(vec![
+            Some(b"tom".as_ref()),
+            None,
+            Some(b"".as_ref()),
+        ]));
+        for (encoding, expected) in [("base64", "dG9t"), ("hex", "746f6d")] {
+            let args = vec![ColumnarValue::Array(input.clone()), utf8(encoding)];
+            let encoded = encode(&args)?.into_array(3);
+            let strings = encoded.as_any().downcast_ref::<LargeStringArray>().unwrap();
+            assert_eq!(strings.value(0), expected);
+            assert!(strings.is_null(1));
+            assert_eq!(strings.value(2), "");
+
+            let args = vec![ColumnarValue::Array(encoded), utf8(encoding)];
+            let decoded = decode(&args)?.into_array(3);
+            assert_eq!(&decoded, &input);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn decode_errors() {
+        let args = vec![utf8("zz"), utf8("hex")];
+        assert!(decode(&args).is_err());
+
+        let args = vec![utf8("tom"), utf8("rot13")];
+        let err = encode(&args).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: There is no built-in encoding named 'rot13', currently supported encodings are: base64, hex"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the FILTER clause of aggregate expressions
+
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::{Array, ArrayRef, BooleanArray};
+use arrow::compute;
+use arrow::datatypes::Field;
+use smallvec::SmallVec;
+
+/// An aggregate expression restricted to the input rows for which a predicate is
+/// true, i.e. `SUM(x) FILTER (WHERE predicate)`. The predicate follows the
+/// expressions of the aggregate, the other rows never reach its accumulator.
+#[derive(Debug)]
+pub struct AggregateFilter {
+    aggregate: Arc<dyn AggregateExpr>,
+    predicate: Arc<dyn PhysicalExpr>,
+}
+
+impl AggregateFilter {
+    /// Create a new aggregate of the rows for which the boolean `predicate` is true
+    pub fn new(
+        aggregate: Arc<dyn AggregateExpr>,
+        predicate: Arc<dyn PhysicalExpr>,
+    ) -> Self {
+        Self {
+            aggregate,
+            predicate,
+        }
+    }
+}
+
+impl AggregateExpr for AggregateFilter {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        self.aggregate.field()
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        self.aggregate.state_fields()
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        let mut expressions = self.aggregate.expressions();
+        expressions.push(self.predicate.clone());
+        expressions
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(AggregateFilterAccumulator {
+            accumulator: self.aggregate.create_accumulator()?,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        self.aggregate.name()
+    }
+}
+
+/// Updates its accumulator with the values whose predicate, the last value, is true
+#[derive(Debug)]
+struct AggregateFilterAccumulator {
+    accumulator: Box<dyn Accumulator>,
+}
+
+/// The predicate and the values of the aggregate in the update of an accumulator
+fn split_predicate<T>(values: &[T]) -> Result<(&T, &[T])> {
+    values.split_last().ok_or_else(|| {
+        DataFusionError::Internal("FILTER without a predicate".to_string())
+    })
+}
+
+impl Accumulator for AggregateFilterAccumulator {
+    fn reset(&mut self) {
+        self.accumulator.reset()
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        self.accumulator.state()
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        match split_predicate(values)? {
+            (ScalarValue::Boolean(Some(true)), values) => self.accumulator.update(values),
+            _ => Ok(()),
+        }
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let (predicate, values) = split_predicate(values)?;
+        let predicate = predicate
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "FILTER predicate of type {:?}",
+                    predicate.data_type()
+                ))
+            })?;
+        // the rows whose predicate is NULL are not aggregated either
+        let mask = (0..predicate.len())
+            .map(|i| Some(predicate.is_valid(i) && predicate.value(i)))
+            .collect::<BooleanArray>();
+        let values = values
+            .iter()
+            .map(|array| compute::filter(array.as_ref(), &mask))
+            .collect::<arrow::error::Result<Vec<_>>>()?;
+        self.accumulator.update_batch(&values)
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        self.accumulator.merge(states)
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        self.accumulator.merge_batch(states)
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        self.accumulator.evaluate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use crate::physical_plan::expressions::{col, ArrayAgg, Count};
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Schema};
+    use arrow::record_batch::RecordBatch;
+
+    fn batch() -> Result<RecordBatch> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("p", DataType::Boolean, true),
+        ]);
+        Ok(RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3), Some(4)])),
+                Arc::new(BooleanArray::from(vec![
+                    Some(true),
+                    Some(true),
+                    Some(false),
+                    None,
+                ])),
+            ],
+        )?)
+    }
+
+    #[test]
+    fn filter_count() -> Result<()> {
+        let batch = batch()?;
+        let schema = batch.schema();
+        let count = Arc::new(Count::new(col("a", &schema)?, "bla", DataType::UInt64));
+        let agg = Arc::new(AggregateFilter::new(count, col("p", &schema)?));
+        assert_eq!(aggregate(&batch, agg)?, ScalarValue::UInt64(Some(1)));
+        Ok(())
+    }
+
+    #[test]
+    fn filter_array_agg() -> Result<()> {
+        // the NULL values of the rows that pass the filter are collected
+        let batch = batch()?;
+        let schema = batch.schema();
+        let array_agg =
+            Arc::new(ArrayAgg::new(col("a", &schema)?, "bla", DataType::Int32));
+        let agg = Arc::new(AggregateFilter::new(array_agg, col("p", &schema)?));
+        assert_eq!(
+            aggregate(&batch, agg)?,
+            ScalarValue::List(
+                Some(Box::new(vec![
+                    ScalarValue::Int32(Some(1)),
+                    ScalarValue::Int32(None)
+                ])),
+                Box::new(DataType::Int32)
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the ANY_VALUE aggregate expression
+
+use std::any::Any;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::ArrayRef;
+use arrow::datatypes::{DataType, Field};
+
+use super::format_state_name;
+use smallvec::smallvec;
+use smallvec::SmallVec;
+
+/// ANY_VALUE aggregate expression
+/// Returns an arbitrary non-null value of the given expression, NULL when all of them
+/// are. The state is the value itself, which is kept once found.
+#[derive(Debug)]
+pub struct AnyValue {
+    name: String,
+    data_type: DataType,
+    expr: Arc<dyn PhysicalExpr>,
+}
+
+impl AnyValue {
+    /// Create a new ANY_VALUE aggregate function
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            expr,
+            data_type,
+        }
+    }
+}
+
+impl AggregateExpr for AnyValue {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "any_value"),
+            self.data_type.clone(),
+            true,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(AnyValueAccumulator {
+            value: ScalarValue::try_from(&self.data_type)?,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug)]
+struct AnyValueAccumulator {
+    value: ScalarValue,
+}
+
+impl AnyValueAccumulator {
+    /// Keeps the first non-null value of `array` unless there is already one
+    fn update_array(&mut self, array: &ArrayRef) -> Result<()> {
+        if !self.value.is_null() || array.null_count() == array.len() {
+            return Ok(());
+        }
+        if let Some(index) = (0..array.len()).find(|i| array.is_valid(*i)) {
+            self.value = ScalarValue::try_from_array(array, index)?;
+        }
+        Ok(())
+    }
+
+    fn update_value(&mut self, value: &ScalarValue) {
+        if self.value.is_null() && !value.is_null() {
+            self.value = value.clone();
+        }
+    }
+}
+
+impl Accumulator for AnyValueAccumulator {
+    fn reset(&mut self) {
+        self.value = ScalarValue::try_from(&self.value.get_datatype())
+            .expect("scalar changed type?");
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        self.update_array(&values[0])
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        self.update_value(&values[0]);
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        self.update_value(&states[0]);
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        self.update_array(&states[0])
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        Ok(smallvec![self.value.clone()])
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(self.value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use arrow::array::StringArray;
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::RecordBatch;
+
+    fn any_value(values: Vec<Option<&str>>) -> Result<ScalarValue> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Utf8, true)]);
+        let a: ArrayRef = Arc::new(StringArray::from(values));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![a])?;
+        let agg = Arc::new(AnyValue::new(col("a", &schema)?, "bla", DataType::Utf8));
+        aggregate(&batch, agg)
+    }
+
+    #[test]
+    fn any_value_skips_nulls() -> Result<()> {
+        let value = any_value(vec![None, Some("x"), Some("y")])?;
+        assert_eq!(value, ScalarValue::from("x"));
+        assert_eq!(any_value(vec![None, None])?, ScalarValue::Utf8(None));
+        Ok(())
+    }
+
+    #[test]
+    fn any_value_merge() -> Result<()> {
+        let agg = AnyValue::new(
+            col(
+                "a",
+                &Schema::new(vec![Field::new("a", DataType::Int32, true)]),
+            )?,
+            "bla",
+            DataType::Int32,
+        );
+        let mut acc = agg.create_accumulator()?;
+        acc.merge(&[ScalarValue::Int32(None)])?;
+        acc.merge(&[ScalarValue::Int32(Some(2))])?;
+        acc.update(&[ScalarValue::Int32(Some(1))])?;
+        assert_eq!(acc.evaluate()?, ScalarValue::Int32(Some(2)));
+        Ok(())
+    }
+}
@@ -0,0 +1,627 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the APPROX_PERCENTILE_CONT aggregate expressions and the t-digests they
+//! compute
+
+use std::any::Any;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::{ArrayRef, BinaryArray, Float64Array};
+use arrow::compute::{self, total_cmp_64};
+use arrow::datatypes::{DataType, Field};
+
+use super::format_state_name;
+use smallvec::{smallvec, SmallVec};
+
+/// The maximum number of centroids of the t-digests of APPROX_PERCENTILE_CONT
+pub const DEFAULT_MAX_SIZE: usize = 100;
+
+/// The number of values an accumulator keeps before adding them to its digest
+const MAX_BUFFERED_VALUES: usize = 4096;
+
+/// The version of the serialized t-digests, their first byte
+const FORMAT_VERSION: u8 = 1;
+
+/// The size of the serialized t-digests without their centroids: the version, the
+/// maximum size, the count, the minimum, the maximum and the number of centroids
+const HEADER_SIZE: usize = 1 + 4 + 3 * 8 + 4;
+
+/// A cluster of close values, their mean and their number
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+impl Centroid {
+    fn add(&mut self, other: Centroid) {
+        let weight = self.weight + other.weight;
+        self.mean += (other.mean - self.mean) * other.weight / weight;
+        self.weight = weight;
+    }
+}
+
+/// A t-digest, the sketch of a distribution of values as at most `max_size`
+/// centroids, from which its quantiles are estimated. The centroids are smaller at
+/// the tails of the distribution, where the estimates are the most accurate.
+///
+/// This is the merging t-digest of Ted Dunning, "Computing Extremely Accurate
+/// Quantiles Using t-Digests", with the scale function `k_to_q`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TDigest {
+    /// ordered by their mean
+    centroids: Vec<Centroid>,
+    max_size: usize,
+    count: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SIZE)
+    }
+}
+
+impl TDigest {
+    /// Create an empty t-digest of at most `max_size` centroids
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            centroids: vec![],
+            max_size,
+            count: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// The number of values of the digest
+    pub fn count(&self) -> f64 {
+        self.count
+    }
+
+    /// Returns the digest of the values of this digest and of `values`. NaN values
+    /// are ignored.
+    pub fn merge_values(&self, mut values: Vec<f64>) -> TDigest {
+        values.retain(|v| !v.is_nan());
+        if values.is_empty() {
+            return self.clone();
+        }
+        values.sort_by(|l, r| total_cmp_64(*l, *r));
+
+        let mut centroids = Vec::with_capacity(self.centroids.len() + values.len());
+        let mut values = values.into_iter().peekable();
+        for centroid in &self.centroids {
+            while let Some(value) = values.next_if(|v| *v < centroid.mean) {
+                centroids.push(Centroid {
+                    mean: value,
+                    weight: 1.0,
+                });
+            }
+            centroids.push(*centroid);
+        }
+        centroids.extend(values.map(|mean| Centroid { mean, weight: 1.0 }));
+
+        let count = centroids.iter().map(|c| c.weight).sum();
+        TDigest {
+            min: self.min.min(centroids[0].mean),
+            max: self.max.max(centroids[centroids.len() - 1].mean),
+            centroids: compress(centroids, count, self.max_size),
+            max_size: self.max_size,
+            count,
+        }
+    }
+
+    /// Returns the digest of the values of all the `digests`, whose maximum size is
+    /// the largest of theirs
+    pub fn merge_digests<'a>(digests: impl IntoIterator<Item = &'a TDigest>) -> TDigest {
+        let digests = digests.into_iter().collect::<Vec<_>>();
+        let max_size = digests.iter().map(|d| d.max_size).max();
+        let mut merged = TDigest::new(max_size.unwrap_or(DEFAULT_MAX_SIZE));
+        let mut centroids = vec![];
+        for digest in digests {
+            if digest.count == 0.0 {
+                continue;
+            }
+            centroids.extend_from_slice(&digest.centroids);
+            merged.count += digest.count;
+            merged.min = merged.min.min(digest.min);
+            merged.max = merged.max.max(digest.max);
+        }
+        centroids.sort_by(|l, r| total_cmp_64(l.mean, r.mean));
+        merged.centroids = compress(centroids, merged.count, merged.max_size);
+        merged
+    }
+
+    /// The estimate of the `q` quantile of the values, with `q` between 0 and 1, or
+    /// None if the digest is empty
+    pub fn estimate_quantile(&self, q: f64) -> Option<f64> {
+        let centroids = &self.centroids;
+        if centroids.is_empty() {
+            return None;
+        }
+        let rank = q * self.count;
+        // the centroid of the quantile and the number of values before it
+        let (position, before) = if q > 0.5 {
+            if q >= 1.0 {
+                return Some(self.max);
+            }
+            let mut before = self.count;
+            let mut position = 0;
+            for (i, centroid) in centroids.iter().enumerate().rev() {
+                before -= centroid.weight;
+                if rank >= before {
+                    position = i;
+                    break;
+                }
+            }
+            (position, before)
+        } else {
+            if q <= 0.0 {
+                return Some(self.min);
+            }
+            let mut before = 0.0;
+            let mut position = centroids.len() - 1;
+            for (i, centroid) in centroids.iter().enumerate() {
+                if rank < before + centroid.weight {
+                    position = i;
+                    break;
+                }
+                before += centroid.weight;
+            }
+            (position, before)
+        };
+
+        // interpolates within the centroid, bounded by its neighbours
+        let centroid = centroids[position];
+        let (mut min, mut max, mut delta) = (self.min, self.max, 0.0);
+        if centroids.len() > 1 {
+            if position == 0 {
+                max = centroids[1].mean;
+                delta = max - centroid.mean;
+            } else if position == centroids.len() - 1 {
+                min = centroids[position - 1].mean;
+                delta = centroid.mean - min;
+            } else {
+                min = centroids[position - 1].mean;
+                max = centroids[position + 1].mean;
+                delta = (max - min) / 2.0;
+            }
+        }
+        let value = centroid.mean + ((rank - before) / centroid.weight - 0.5) * delta;
+        Some(value.max(min).min(max))
+    }
+
+    /// Serializes the digest, to be deserialized by [TDigest::from_bytes]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + 16 * self.centroids.len());
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&(self.max_size as u32).to_le_bytes());
+        for value in &[self.count, self.min, self.max] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.centroids.len() as u32).to_le_bytes());
+        for centroid in &self.centroids {
+            bytes.extend_from_slice(&centroid.mean.to_le_bytes());
+            bytes.extend_from_slice(&centroid.weight.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Deserializes a digest serialized by [TDigest::to_bytes]
+    pub fn from_bytes(bytes: &[u8]) -> Result<TDigest> {
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let f64_at = |i: usize| f64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        let valid = bytes.len() >= HEADER_SIZE
+            && bytes[0] == FORMAT_VERSION
+            && u32_at(1) > 0
+            && bytes.len() == HEADER_SIZE + 16 * u32_at(HEADER_SIZE - 4) as usize;
+        if !valid {
+            return Err(DataFusionError::Execution(format!(
+                "Invalid serialized t-digest of {} bytes",
+                bytes.len()
+            )));
+        }
+        let centroids = (HEADER_SIZE..bytes.len())
+            .step_by(16)
+            .map(|i| Centroid {
+                mean: f64_at(i),
+                weight: f64_at(i + 8),
+            })
+            .collect();
+        Ok(TDigest {
+            centroids,
+            max_size: u32_at(1) as usize,
+            count: f64_at(5),
+            min: f64_at(13),
+            max: f64_at(21),
+        })
+    }
+}
+
+/// The fraction of the values up to the `k`th centroid of a digest of `max_size`
+/// centroids
+fn k_to_q(k: f64, max_size: f64) -> f64 {
+    let k_div_d = k / max_size;
+    if k_div_d >= 0.5 {
+        let base = 1.0 - k_div_d;
+        1.0 - 2.0 * base * base
+    } else {
+        2.0 * k_div_d * k_div_d
+    }
+}
+
+/// Merges the adjacent `centroids`, ordered by their mean, of `count` values into
+/// at most `max_size` ones
+fn compress(centroids: Vec<Centroid>, count: f64, max_size: usize) -> Vec<Centroid> {
+    let mut centroids = centroids.into_iter();
+    let mut current = match centroids.next() {
+        Some(centroid) => centroid,
+        None => return vec![],
+    };
+    let mut result = Vec::with_capacity(max_size);
+    let mut k = 1.0;
+    let mut weight_limit = k_to_q(k, max_size as f64) * count;
+    let mut weight_so_far = current.weight;
+    for centroid in centroids {
+        weight_so_far += centroid.weight;
+        if weight_so_far <= weight_limit {
+            current.add(centroid);
+        } else {
+            result.push(current);
+            k += 1.0;
+            weight_limit = k_to_q(k, max_size as f64) * count;
+            current = centroid;
+        }
+    }
+    result.push(current);
+    result
+}
+
+/// APPROX_PERCENTILE_CONT aggregate expression and its variants, that compute the
+/// t-digest of numeric values, or merge serialized t-digests, and return either the
+/// estimate of a percentile or the serialized digest.
+///
+/// The serialized digests are Binary values, that can be stored to merge them later
+/// with APPROX_PERCENTILE_CONT_MERGE, as pre-aggregates of the percentiles.
+#[derive(Debug)]
+pub struct ApproxPercentile {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    fraction: Option<f64>,
+    merge: bool,
+}
+
+impl ApproxPercentile {
+    /// Create a new aggregate expression returning the serialized t-digest of the
+    /// values of `expr`, whose type is Float64
+    pub fn new(expr: Arc<dyn PhysicalExpr>, name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            expr,
+            fraction: None,
+            merge: false,
+        }
+    }
+
+    /// Returns the estimate of the `fraction` percentile, between 0 and 1, as a
+    /// Float64 rather than the serialized digest
+    pub fn with_fraction(mut self, fraction: f64) -> Self {
+        self.fraction = Some(fraction);
+        self
+    }
+
+    /// Whether the values of the expression are serialized digests to merge
+    pub fn with_merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    fn data_type(&self) -> DataType {
+        match self.fraction {
+            Some(_) => DataType::Float64,
+            None => DataType::Binary,
+        }
+    }
+}
+
+impl AggregateExpr for ApproxPercentile {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type(), true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "digest"),
+            DataType::Binary,
+            true,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ApproxPercentileAccumulator {
+            digest: TDigest::default(),
+            values: vec![],
+            fraction: self.fraction,
+            merge: self.merge,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug)]
+struct ApproxPercentileAccumulator {
+    digest: TDigest,
+    /// the values not added to the digest yet
+    values: Vec<f64>,
+    fraction: Option<f64>,
+    merge: bool,
+}
+
+impl ApproxPercentileAccumulator {
+    /// The digest of all the values
+    fn digest(&self) -> TDigest {
+        self.digest.merge_values(self.values.clone())
+    }
+
+    fn add_value(&mut self, value: f64) {
+        self.values.push(value);
+        if self.values.len() >= MAX_BUFFERED_VALUES {
+            self.digest = self.digest.merge_values(std::mem::take(&mut self.values));
+        }
+    }
+
+    fn merge_digest(&mut self, bytes: &[u8]) -> Result<()> {
+        let digest = TDigest::from_bytes(bytes)?;
+        self.digest = TDigest::merge_digests(&[self.digest.clone(), digest]);
+        Ok(())
+    }
+}
+
+impl Accumulator for ApproxPercentileAccumulator {
+    fn reset(&mut self) {
+        self.digest = TDigest::default();
+        self.values.clear();
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        Ok(smallvec![ScalarValue::Binary(Some(
+            self.digest().to_bytes()
+        ))])
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        match &values[0] {
+            ScalarValue::Float64(value) if !self.merge => {
+                if let Some(value) = value {
+                    self.add_value(*value);
+                }
+                Ok(())
+            }
+            ScalarValue::Binary(digest) if self.merge => match digest {
+                Some(digest) => self.merge_digest(digest),
+                None => Ok(()),
+            },
+            value => Err(DataFusionError::Internal(format!(
+                "APPROX_PERCENTILE_CONT is not expected to receive a scalar {:?}",
+                value
+            ))),
+        }
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        if self.merge {
+            return self.merge_batch(values);
+        }
+        let values = compute::cast(&values[0], &DataType::Float64)?;
+        let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+        for value in values.iter().flatten() {
+            self.add_value(value);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        match &states[0] {
+            ScalarValue::Binary(Some(digest)) => self.merge_digest(digest),
+            ScalarValue::Binary(None) => Ok(()),
+            state => Err(DataFusionError::Internal(format!(
+                "Unexpected accumulator state {:?}",
+                state
+            ))),
+        }
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        let digests = states[0]
+            .as_any()
+            .downcast_ref::<BinaryArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "Unexpected t-digests of type {:?}",
+                    states[0].data_type()
+                ))
+            })?;
+        let mut merged = vec![self.digest.clone()];
+        for digest in digests.iter().flatten() {
+            merged.push(TDigest::from_bytes(digest)?);
+        }
+        self.digest = TDigest::merge_digests(&merged);
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let digest = self.digest();
+        Ok(match self.fraction {
+            Some(fraction) => ScalarValue::Float64(digest.estimate_quantile(fraction)),
+            None if digest.count() == 0.0 => ScalarValue::Binary(None),
+            None => ScalarValue::Binary(Some(digest.to_bytes())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::RecordBatch;
+
+    fn approx_percentile(array: ArrayRef, agg: ApproxPercentile) -> Result<ScalarValue> {
+        let schema = Schema::new(vec![Field::new("a", array.data_type().clone(), true)]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![array])?;
+        aggregate(&batch, Arc::new(agg))
+    }
+
+    fn digest_of(values: &[f64]) -> ArrayRef {
+        let digest = TDigest::default().merge_values(values.to_vec());
+        Arc::new(BinaryArray::from(vec![digest.to_bytes().as_slice()]))
+    }
+
+    #[test]
+    fn estimate_quantiles() {
+        // exact while there are fewer values than centroids
+        let digest = TDigest::default().merge_values((1..=10).map(f64::from).collect());
+        assert_eq!(digest.estimate_quantile(0.0), Some(1.0));
+        assert_eq!(digest.estimate_quantile(0.5), Some(5.5));
+        assert_eq!(digest.estimate_quantile(1.0), Some(10.0));
+        assert_eq!(TDigest::default().estimate_quantile(0.5), None);
+
+        let values = (0..100_000).map(|v| ((v * 7919) % 100_000) as f64);
+        let digest = values
+            .collect::<Vec<_>>()
+            .chunks(1000)
+            .fold(TDigest::default(), |d, chunk| {
+                d.merge_values(chunk.to_vec())
+            });
+        assert!(digest.centroids.len() <= DEFAULT_MAX_SIZE);
+        for q in &[0.01, 0.25, 0.5, 0.75, 0.99] {
+            let estimate = digest.estimate_quantile(*q).unwrap();
+            assert!(
+                (estimate - q * 100_000.0).abs() < 500.0,
+                "{} {}",
+                q,
+                estimate
+            );
+        }
+    }
+
+    #[test]
+    fn merge_digests() {
+        let values = (0..10_000).map(|v| (v % 1000) as f64).collect::<Vec<_>>();
+        let digests = values
+            .chunks(3000)
+            .map(|chunk| TDigest::default().merge_values(chunk.to_vec()))
+            .collect::<Vec<_>>();
+        let merged = TDigest::merge_digests(&digests);
+        assert_eq!(merged.count(), 10_000.0);
+        assert_eq!(merged.estimate_quantile(0.0), Some(0.0));
+        assert_eq!(merged.estimate_quantile(1.0), Some(999.0));
+        let median = merged.estimate_quantile(0.5).unwrap();
+        assert!((median - 500.0).abs() < 10.0, "{}", median);
+    }
+
+    #[test]
+    fn serialize_digests() -> Result<()> {
+        let digest = TDigest::default().merge_values(vec![3.0, 1.0, 2.0]);
+        let bytes = digest.to_bytes();
+        assert_eq!(bytes.len(), HEADER_SIZE + 3 * 16);
+        assert_eq!(TDigest::from_bytes(&bytes)?, digest);
+        assert_eq!(
+            TDigest::from_bytes(&TDigest::default().to_bytes())?,
+            TDigest::default()
+        );
+
+        let err = TDigest::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Invalid serialized t-digest of 80 bytes"
+        );
+        assert!(TDigest::from_bytes(b"digest").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn approx_percentile_cont() -> Result<()> {
+        let array: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(4.0),
+            None,
+            Some(1.0),
+            Some(3.0),
+            Some(2.0),
+        ]));
+        let schema = Schema::new(vec![Field::new("a", DataType::Float64, true)]);
+        let agg = ApproxPercentile::new(col("a", &schema)?, "bla").with_fraction(0.5);
+        assert_eq!(
+            approx_percentile(array.clone(), agg)?,
+            ScalarValue::Float64(Some(2.5))
+        );
+
+        // the digest of the values, serialized
+        let agg = ApproxPercentile::new(col("a", &schema)?, "bla");
+        let digest = TDigest::default().merge_values(vec![1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(
+            approx_percentile(array, agg)?,
+            ScalarValue::Binary(Some(digest.to_bytes()))
+        );
+
+        let empty: ArrayRef = Arc::new(Float64Array::from(vec![None as Option<f64>]));
+        let agg = ApproxPercentile::new(col("a", &schema)?, "bla");
+        assert_eq!(approx_percentile(empty, agg)?, ScalarValue::Binary(None));
+        Ok(())
+    }
+
+    #[test]
+    fn approx_percentile_cont_merge() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Binary, true)]);
+        let agg = ApproxPercentile::new(col("a", &schema)?, "bla")
+            .with_merge(true)
+            .with_fraction(0.5);
+        let mut acc = agg.create_accumulator()?;
+        acc.update_batch(&[digest_of(&[1.0, 5.0, 3.0])])?;
+        acc.update_batch(&[digest_of(&[2.0, 4.0, 6.0])])?;
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(Some(3.5)));
+
+        // the partial aggregates merge the digests of their states
+        let mut merged = agg.create_accumulator()?;
+        merged.merge(&acc.state()?)?;
+        merged.merge(&[ScalarValue::Binary(None)])?;
+        assert_eq!(merged.evaluate()?, ScalarValue::Float64(Some(3.5)));
+
+        let invalid: ArrayRef = Arc::new(BinaryArray::from(vec![b"digest".as_ref()]));
+        assert!(acc.update_batch(&[invalid]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,491 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines physical expressions that can evaluated at runtime during query execution
+
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::ArrayRef;
+use arrow::compute::{lexsort_to_indices, SortColumn, SortOptions};
+use arrow::datatypes::{DataType, Field, Schema};
+
+use super::{format_state_name, PhysicalSortExpr};
+use smallvec::SmallVec;
+
+/// ARRAY_AGG aggregate expression
+/// Collects the values of the given expression into a list, optionally in the order
+/// given by `ORDER BY` expressions and only the first ones of them.
+#[derive(Debug)]
+pub struct ArrayAgg {
+    name: String,
+    data_type: DataType,
+    expr: Arc<dyn PhysicalExpr>,
+    ignore_nulls: bool,
+    order_by: Vec<PhysicalSortExpr>,
+    order_by_types: Vec<DataType>,
+    limit: Option<usize>,
+}
+
+impl ArrayAgg {
+    /// Create a new ARRAY_AGG aggregate function. `data_type` is the type of
+    /// the list items, i.e. the type of `expr`.
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            expr,
+            data_type,
+            ignore_nulls: false,
+            order_by: vec![],
+            order_by_types: vec![],
+            limit: None,
+        }
+    }
+
+    /// Skip NULL values instead of adding them to the list (`IGNORE NULLS`)
+    pub fn with_ignore_nulls(mut self, ignore_nulls: bool) -> Self {
+        self.ignore_nulls = ignore_nulls;
+        self
+    }
+
+    /// Collect the values in the given order rather than in the order of the input
+    /// rows (`ARRAY_AGG(expr ORDER BY ...)`)
+    pub fn with_order_by(
+        mut self,
+        order_by: Vec<PhysicalSortExpr>,
+        input_schema: &Schema,
+    ) -> Result<Self> {
+        self.order_by_types = order_by
+            .iter()
+            .map(|e| e.expr.data_type(input_schema))
+            .collect::<Result<_>>()?;
+        self.order_by = order_by;
+        Ok(self)
+    }
+
+    /// Only collect the first `limit` values, in the order of `ORDER BY` if any
+    /// (`ARRAY_AGG(expr ORDER BY ... LIMIT limit)`)
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+impl AggregateExpr for ArrayAgg {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, list_type(&self.data_type), true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        let mut fields = vec![Field::new(
+            &format_state_name(&self.name, "array_agg"),
+            list_type(&self.data_type),
+            true,
+        )];
+        for (i, data_type) in self.order_by_types.iter().enumerate() {
+            fields.push(Field::new(
+                &format_state_name(&self.name, &format!("array_agg_key[{}]", i)),
+                list_type(data_type),
+                true,
+            ));
+        }
+        Ok(fields)
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        let mut exprs = vec![self.expr.clone()];
+        exprs.extend(self.order_by.iter().map(|e| e.expr.clone()));
+        exprs
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ArrayAggAccumulator {
+            values: vec![],
+            keys: vec![vec![]; self.order_by.len()],
+            data_type: self.data_type.clone(),
+            key_types: self.order_by_types.clone(),
+            sort_options: self.order_by.iter().map(|e| e.options).collect(),
+            ignore_nulls: self.ignore_nulls,
+            limit: self.limit,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn list_type(data_type: &DataType) -> DataType {
+    DataType::List(Box::new(Field::new("item", data_type.clone(), true)))
+}
+
+#[derive(Debug)]
+struct ArrayAggAccumulator {
+    values: Vec<ScalarValue>,
+    /// The values of the ORDER BY expressions for each of `values`, one vector per
+    /// expression
+    keys: Vec<Vec<ScalarValue>>,
+    data_type: DataType,
+    key_types: Vec<DataType>,
+    sort_options: Vec<SortOptions>,
+    ignore_nulls: bool,
+    limit: Option<usize>,
+}
+
+impl ArrayAggAccumulator {
+    /// Adds the value with its keys, `row` is the value followed by its keys
+    fn push(&mut self, row: &[ScalarValue]) {
+        if self.ignore_nulls && row[0].is_null() {
+            return;
+        }
+        // without ORDER BY, the first values are the ones of the result
+        if self.keys.is_empty() && Some(self.values.len()) == self.limit {
+            return;
+        }
+        self.values.push(row[0].clone());
+        for (keys, key) in self.keys.iter_mut().zip(&row[1..]) {
+            keys.push(key.clone());
+        }
+    }
+
+    /// The indices of the values of the result, in its order
+    fn result_indices(&self) -> Result<Vec<usize>> {
+        if self.limit == Some(0) {
+            return Ok(vec![]);
+        }
+        if self.keys.is_empty() || self.values.is_empty() {
+            return Ok((0..self.values.len()).collect());
+        }
+        let columns = self
+            .keys
+            .iter()
+            .zip(&self.sort_options)
+            .map(|(keys, options)| {
+                Ok(SortColumn {
+                    values: ScalarValue::iter_to_array(keys.iter().cloned())?,
+                    options: Some(*options),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let indices = lexsort_to_indices(&columns, self.limit)?;
+        Ok(indices.values().iter().map(|i| *i as usize).collect())
+    }
+
+    /// Only keeps the values of the result once there are more than twice as many,
+    /// which bounds the memory used with both ORDER BY and LIMIT
+    fn compact(&mut self) -> Result<()> {
+        match self.limit {
+            Some(limit) if self.values.len() > 2 * limit => {
+                let indices = self.result_indices()?;
+                let take = |values: &[ScalarValue]| -> Vec<ScalarValue> {
+                    indices.iter().map(|i| values[*i].clone()).collect()
+                };
+                self.values = take(&self.values);
+                self.keys = self.keys.iter().map(|keys| take(keys)).collect();
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Accumulator for ArrayAggAccumulator {
+    fn reset(&mut self) {
+        self.values.clear();
+        for keys in &mut self.keys {
+            keys.clear();
+        }
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let array = &values[0];
+        if self.ignore_nulls && array.null_count() == array.len() {
+            return Ok(());
+        }
+        for index in 0..array.len() {
+            let row = values
+                .iter()
+                .map(|array| ScalarValue::try_from_array(array, index))
+                .collect::<Result<Vec<_>>>()?;
+            self.push(&row);
+        }
+        self.compact()
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        self.push(values);
+        self.compact()
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        let lists = states
+            .iter()
+            .map(|state| match state {
+                ScalarValue::List(values, _) => Ok(values),
+                state => Err(DataFusionError::Internal(format!(
+                    "Unexpected accumulator state {:?}",
+                    state
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        match lists[0] {
+            Some(values) => {
+                for index in 0..values.len() {
+                    let row = lists
+                        .iter()
+                        .map(|list| match list {
+                            Some(list) => Ok(list[index].clone()),
+                            None => Err(DataFusionError::Internal(
+                                "ARRAY_AGG state without keys".to_string(),
+                            )),
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    self.push(&row);
+                }
+                self.compact()
+            }
+            // partial aggregate without any input rows
+            None => Ok(()),
+        }
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        let indices = self.result_indices()?;
+        let list = |values: &[ScalarValue], data_type: &DataType| {
+            // no rows are a NULL list rather than an empty one, as in `evaluate`
+            let values = if indices.is_empty() {
+                None
+            } else {
+                Some(Box::new(
+                    indices.iter().map(|i| values[*i].clone()).collect(),
+                ))
+            };
+            ScalarValue::List(values, Box::new(data_type.clone()))
+        };
+        let mut state = SmallVec::new();
+        state.push(list(&self.values, &self.data_type));
+        for (keys, data_type) in self.keys.iter().zip(&self.key_types) {
+            state.push(list(keys, data_type));
+        }
+        Ok(state)
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        // as in Postgres, aggregating no rows produces NULL rather than an empty list
+        let indices = self.result_indices()?;
+        if indices.is_empty() {
+            return Ok(ScalarValue::List(None, Box::new(self.data_type.clone())));
+        }
+        Ok(ScalarValue::List(
+            Some(Box::new(
+                indices.iter().map(|i| self.values[*i].clone()).collect(),
+            )),
+            Box::new(self.data_type.clone()),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use arrow::array::Int32Array;
+    use arrow::record_batch::RecordBatch;
+
+    fn array_agg(ignore_nulls: bool) -> Result<ScalarValue> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None, Some(3)]));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![a])?;
+        let agg = Arc::new(
+            ArrayAgg::new(col("a", &schema)?, "bla", DataType::Int32)
+                .with_ignore_nulls(ignore_nulls),
+        );
+        aggregate(&batch, agg)
+    }
+
+    #[test]
+    fn array_agg_respect_nulls() -> Result<()> {
+        let expected = ScalarValue::List(
+            Some(Box::new(vec![
+                ScalarValue::Int32(Some(1)),
+                ScalarValue::Int32(None),
+                ScalarValue::Int32(Some(3)),
+            ])),
+            Box::new(DataType::Int32),
+        );
+        assert_eq!(array_agg(false)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn array_agg_ignore_nulls() -> Result<()> {
+        let expected = ScalarValue::List(
+            Some(Box::new(vec![
+                ScalarValue::Int32(Some(1)),
+                ScalarValue::Int32(Some(3)),
+            ])),
+            Box::new(DataType::Int32),
+        );
+        assert_eq!(array_agg(true)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn array_agg_merge() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+        let mut acc = ArrayAgg::new(col("a", &schema)?, "bla", DataType::Int32)
+            .with_ignore_nulls(true)
+            .create_accumulator()?;
+        acc.update(&[ScalarValue::Int32(Some(1))])?;
+        acc.merge(&[ScalarValue::List(None, Box::new(DataType::Int32))])?;
+        acc.merge(&[ScalarValue::List(
+            Some(Box::new(vec![
+                ScalarValue::Int32(None),
+                ScalarValue::Int32(Some(2)),
+            ])),
+            Box::new(DataType::Int32),
+        )])?;
+        assert_eq!(
+            acc.evaluate()?,
+            ScalarValue::List(
+                Some(Box::new(vec![
+                    ScalarValue::Int32(Some(1)),
+                    ScalarValue::Int32(Some(2)),
+                ])),
+                Box::new(DataType::Int32),
+            )
+        );
+        Ok(())
+    }
+
+    fn ordered_array_agg(descending: bool, limit: Option<usize>) -> Result<ScalarValue> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3), Some(4)])),
+                Arc::new(Int32Array::from(vec![Some(20), Some(40), Some(10), None])),
+            ],
+        )?;
+        let order_by = vec![PhysicalSortExpr {
+            expr: col("b", &schema)?,
+            options: SortOptions {
+                descending,
+                nulls_first: false,
+            },
+        }];
+        let mut agg = ArrayAgg::new(col("a", &schema)?, "bla", DataType::Int32)
+            .with_order_by(order_by, &schema)?;
+        if let Some(limit) = limit {
+            agg = agg.with_limit(limit);
+        }
+        aggregate(&batch, Arc::new(agg))
+    }
+
+    fn int32_list(values: Vec<Option<i32>>) -> ScalarValue {
+        ScalarValue::List(
+            Some(Box::new(
+                values.into_iter().map(ScalarValue::Int32).collect(),
+            )),
+            Box::new(DataType::Int32),
+        )
+    }
+
+    #[test]
+    fn array_agg_order_by() -> Result<()> {
+        assert_eq!(
+            ordered_array_agg(false, None)?,
+            int32_list(vec![Some(3), Some(1), None, Some(4)])
+        );
+        assert_eq!(
+            ordered_array_agg(true, None)?,
+            int32_list(vec![None, Some(1), Some(3), Some(4)])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn array_agg_limit() -> Result<()> {
+        assert_eq!(
+            ordered_array_agg(false, Some(2))?,
+            int32_list(vec![Some(3), Some(1)])
+        );
+        assert_eq!(ordered_array_agg(true, Some(1))?, int32_list(vec![None]));
+        assert_eq!(
+            ordered_array_agg(false, Some(0))?,
+            ScalarValue::List(None, Box::new(DataType::Int32))
+        );
+
+        // without ORDER BY, the first values of the input
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+        let mut acc = ArrayAgg::new(col("a", &schema)?, "bla", DataType::Int32)
+            .with_limit(2)
+            .create_accumulator()?;
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![5, 6, 7]));
+        acc.update_batch(&[a])?;
+        acc.merge(&[int32_list(vec![Some(8)])])?;
+        assert_eq!(acc.evaluate()?, int32_list(vec![Some(5), Some(6)]));
+        Ok(())
+    }
+
+    #[test]
+    fn array_agg_limit_merge() -> Result<()> {
+        // the states of partial aggregates only keep the first values and their keys
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]);
+        let order_by = vec![PhysicalSortExpr {
+            expr: col("b", &schema)?,
+            options: SortOptions::default(),
+        }];
+        let agg = ArrayAgg::new(col("a", &schema)?, "bla", DataType::Int32)
+            .with_order_by(order_by, &schema)?
+            .with_limit(1);
+        let mut partial = agg.create_accumulator()?;
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3]));
+        let b: ArrayRef = Arc::new(Int32Array::from(vec![30, 10, 20]));
+        partial.update_batch(&[a, b])?;
+        let state = partial.state()?;
+        assert_eq!(
+            state.as_slice(),
+            &[int32_list(vec![Some(2)]), int32_list(vec![Some(10)])]
+        );
+
+        let mut acc = agg.create_accumulator()?;
+        acc.merge(&[int32_list(vec![Some(4)]), int32_list(vec![Some(5)])])?;
+        acc.merge(&state)?;
+        assert_eq!(acc.evaluate()?, int32_list(vec![Some(4)]));
+        Ok(())
+    }
+}
@@ -33,20 +33,24 @@ use arrow::{
     datatypes::Field,
 };
 
+use super::compensated_sum::{compensated_state_fields, CompensatedSumAccumulator};
 use super::{format_state_name, sum};
 use smallvec::smallvec;
 use smallvec::SmallVec;
 
 /// AVG aggregate expression
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Avg {
     name: String,
     data_type: DataType,
     nullable: bool,
     expr: Arc<dyn PhysicalExpr>,
+    compensated: bool,
 }
 
-/// function return type of an average
+/// function return type of an average. The average of 96 bits integers and decimals
+/// is of the type of their SUM, rounded half away from zero, as a Float64 would lose
+/// their precision.
 pub fn avg_return_type(arg_type: &DataType) -> Result<DataType> {
     match arg_type {
         DataType::Int8
@@ -59,6 +63,11 @@ pub fn avg_return_type(arg_type: &DataType) -> Result<DataType> {
         | DataType::UInt64
         | DataType::Float32
         | DataType::Float64 => Ok(DataType::Float64),
+        DataType::Int96 | DataType::Int64Decimal(_) | DataType::Int96Decimal(_) => {
+            sum::sum_return_type(arg_type)
+        }
+        // the average interval, or the timestamp of the average epoch
+        DataType::Interval(_) | DataType::Timestamp(_, _) => Ok(arg_type.clone()),
         other => Err(DataFusionError::Plan(format!(
             "AVG does not support {:?}",
             other
@@ -78,8 +87,16 @@ impl Avg {
             expr,
             data_type,
             nullable: true,
+            compensated: false,
         }
     }
+
+    /// Whether the sum of floats is computed with a compensated summation, whose
+    /// result does not depend on the batches and the partitions of the values
+    pub fn with_compensated(mut self, compensated: bool) -> Self {
+        self.compensated = compensated;
+        self
+    }
 }
 
 impl AggregateExpr for Avg {
@@ -89,10 +106,13 @@ impl AggregateExpr for Avg {
     }
 
     fn field(&self) -> Result<Field> {
-        Ok(Field::new(&self.name, DataType::Float64, true))
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
     }
 
     fn state_fields(&self) -> Result<Vec<Field>> {
+        if self.compensated {
+            return Ok(compensated_state_fields(&self.name));
+        }
         Ok(vec![
             Field::new(
                 &format_state_name(&self.name, "count"),
@@ -101,17 +121,18 @@ impl AggregateExpr for Avg {
             ),
             Field::new(
                 &format_state_name(&self.name, "sum"),
-                DataType::Float64,
+                self.data_type.clone(),
                 true,
             ),
         ])
     }
 
     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
-        Ok(Box::new(AvgAccumulator::try_new(
-            // avg is f64
-            &DataType::Float64,
-        )?))
+        if self.compensated {
+            return Ok(Box::new(CompensatedSumAccumulator::new(true)));
+        }
+        // the sum is of the type of the average
+        Ok(Box::new(AvgAccumulator::try_new(&self.data_type)?))
     }
 
     fn uses_groups_accumulator(&self) -> bool {
@@ -123,10 +144,18 @@ impl AggregateExpr for Avg {
     fn create_groups_accumulator(
         &self,
     ) -> arrow::error::Result<Option<Box<dyn GroupsAccumulator>>> {
+        if self.compensated {
+            return Ok(Some(Box::new(GroupsAccumulatorFlatAdapter::<
+                CompensatedSumAccumulator,
+            >::new(|| {
+                Ok(CompensatedSumAccumulator::new(true))
+            }))));
+        }
+        let data_type = self.data_type.clone();
         Ok(Some(Box::new(
-            GroupsAccumulatorFlatAdapter::<AvgAccumulator>::new(|| {
-                // avg is f64 (as in create_accumulator)
-                AvgAccumulator::try_new(&DataType::Float64)
+            GroupsAccumulatorFlatAdapter::<AvgAccumulator>::new(move || {
+                // as in create_accumulator
+                AvgAccumulator::try_new(&data_type)
             }),
         )))
     }
@@ -211,17 +240,33 @@ impl Accumulator for AvgAccumulator {
     }
 
     fn evaluate(&self) -> Result<ScalarValue> {
+        let average = |sum: Option<i128>| sum.map(|sum| divide_rounded(sum, self.count));
         match self.sum {
             ScalarValue::Float64(e) => {
                 Ok(ScalarValue::Float64(e.map(|f| f / self.count as f64)))
             }
+            ScalarValue::Int96(sum) => Ok(ScalarValue::Int96(average(sum))),
+            ScalarValue::Int96Decimal(sum, scale) => {
+                Ok(ScalarValue::Int96Decimal(average(sum), scale))
+            }
             _ => Err(DataFusionError::Internal(
-                "Sum should be f64 on average".to_string(),
+                "Sum should be f64, Int96 or Int96Decimal on average".to_string(),
             )),
         }
     }
 }
 
+/// `sum / count` rounded half away from zero
+fn divide_rounded(sum: i128, count: u64) -> i128 {
+    let count = count as i128;
+    let (quotient, remainder) = (sum / count, sum % count);
+    if 2 * remainder.abs() >= count {
+        quotient + sum.signum()
+    } else {
+        quotient
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -311,6 +356,65 @@ mod tests {
         )
     }
 
+    #[test]
+    fn avg_int64_decimal() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int64Decimal2Array::from(vec![
+            Some(150),
+            None,
+            Some(250),
+            Some(400),
+        ]));
+        // 2.666... is rounded to 2.67
+        generic_test_op!(
+            a,
+            DataType::Int64Decimal(2),
+            Avg,
+            ScalarValue::Int96Decimal(Some(267), 2),
+            DataType::Int96Decimal(2)
+        )
+    }
+
+    #[test]
+    fn avg_int96_decimal() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int96Decimal10Array::from(vec![
+            15_000_000_000_i128,
+            25_000_000_000_i128,
+        ]));
+        generic_test_op!(
+            a,
+            DataType::Int96Decimal(10),
+            Avg,
+            ScalarValue::Int96Decimal(Some(20_000_000_000), 10),
+            DataType::Int96Decimal(10)
+        )
+    }
+
+    #[test]
+    fn avg_int96() -> Result<()> {
+        // beyond the 53 bits of precision of a Float64
+        let a: ArrayRef = Arc::new(Int96Array::from(vec![
+            Some(1_i128 << 80),
+            Some((1_i128 << 80) + 3),
+            None,
+        ]));
+        generic_test_op!(
+            a,
+            DataType::Int96,
+            Avg,
+            ScalarValue::Int96(Some((1_i128 << 80) + 2)),
+            DataType::Int96
+        )
+    }
+
+    #[test]
+    fn divide_rounded_half_away_from_zero() {
+        assert_eq!(divide_rounded(5, 2), 3);
+        assert_eq!(divide_rounded(-5, 2), -3);
+        assert_eq!(divide_rounded(4, 3), 1);
+        assert_eq!(divide_rounded(-4, 3), -1);
+        assert_eq!(divide_rounded(6, 3), 2);
+    }
+
     fn aggregate(
         batch: &RecordBatch,
         agg: Arc<dyn AggregateExpr>,
@@ -15,6 +15,7 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::str::FromStr;
 use std::{any::Any, sync::Arc};
 
 use arrow::array::TimestampMillisecondArray;
@@ -49,16 +50,80 @@ use crate::physical_plan::expressions::try_cast;
 use crate::physical_plan::{ColumnarValue, PhysicalExpr};
 use crate::scalar::ScalarValue;
 
-use super::coercion::{eq_coercion, numerical_coercion, order_coercion, string_coercion};
+use super::coercion::{
+    eq_coercion, null_coercion, numerical_coercion, order_coercion, string_coercion,
+};
 use crate::physical_plan::expressions::coercion::{is_numeric, string_implicit_cast};
 use arrow::compute::{eq_bool, neq_bool};
 
+/// Handling of the integer and decimal results of `+`, `-`, `*` and `SUM` that do
+/// not fit their type, set with the `datafusion.execution.arithmetic_overflow` option
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithmeticOverflow {
+    /// Leaves the results to the compute kernels, which wrap them in release builds
+    Unchecked,
+    /// Fails the query with an execution error
+    Error,
+    /// Computes the results of SQL queries in wider types, see [promoted_type], and
+    /// fails the query when they still overflow
+    Promote,
+}
+
+impl ArithmeticOverflow {
+    /// The name of the handling, as in the `datafusion.execution.arithmetic_overflow`
+    /// option
+    pub fn name(&self) -> &'static str {
+        match self {
+            ArithmeticOverflow::Unchecked => "unchecked",
+            ArithmeticOverflow::Error => "error",
+            ArithmeticOverflow::Promote => "promote",
+        }
+    }
+
+    /// Whether the results are checked for overflows
+    pub fn is_checked(&self) -> bool {
+        *self != ArithmeticOverflow::Unchecked
+    }
+}
+
+impl FromStr for ArithmeticOverflow {
+    type Err = DataFusionError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "unchecked" => Ok(ArithmeticOverflow::Unchecked),
+            "error" => Ok(ArithmeticOverflow::Error),
+            "promote" => Ok(ArithmeticOverflow::Promote),
+            _ => Err(DataFusionError::Plan(format!(
+                "Unknown arithmetic overflow handling '{}', expected one of unchecked, \
+                 error, promote",
+                name
+            ))),
+        }
+    }
+}
+
+/// The wider type to which the results of arithmetic of integers or decimals of
+/// `data_type` are promoted, such that adding, subtracting or multiplying two values
+/// does not overflow. None for the other types and for the widest types, `Int96` and
+/// `Int96Decimal`.
+pub fn promoted_type(data_type: &DataType) -> Option<DataType> {
+    match data_type {
+        DataType::Int8 | DataType::Int16 | DataType::Int32 => Some(DataType::Int64),
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 => Some(DataType::UInt64),
+        DataType::Int64 | DataType::UInt64 => Some(DataType::Int96),
+        DataType::Int64Decimal(scale) => Some(DataType::Int96Decimal(*scale)),
+        _ => None,
+    }
+}
+
 /// Binary expression
 #[derive(Debug)]
 pub struct BinaryExpr {
     left: Arc<dyn PhysicalExpr>,
     op: Operator,
     right: Arc<dyn PhysicalExpr>,
+    overflow_check: bool,
 }
 
 impl BinaryExpr {
@@ -68,7 +133,19 @@ impl BinaryExpr {
         op: Operator,
         right: Arc<dyn PhysicalExpr>,
     ) -> Self {
-        Self { left, op, right }
+        Self {
+            left,
+            op,
+            right,
+            overflow_check: false,
+        }
+    }
+
+    /// Whether `+`, `-` and `*` of integers and decimals fail with an error rather
+    /// than wrap on overflow
+    pub fn with_overflow_check(mut self, overflow_check: bool) -> Self {
+        self.overflow_check = overflow_check;
+        self
     }
 
     /// Get the left side of the binary expression
@@ -87,6 +164,41 @@ impl BinaryExpr {
     }
 }
 
+impl BinaryExpr {
+    /// Evaluates the right side of AND or OR only for the rows whose result the left
+    /// side leaves undecided, i.e. where it is not false for AND or not true for OR.
+    /// The other rows of the right side are NULL, which the kleene kernels then ignore.
+    /// Returns `None` when the left side decides every row on its own.
+    fn evaluate_undecided_right(
+        &self,
+        left: &ColumnarValue,
+        batch: &RecordBatch,
+    ) -> Result<Option<ColumnarValue>> {
+        let decisive = self.op == Operator::Or;
+        let left = match left {
+            ColumnarValue::Scalar(ScalarValue::Boolean(Some(v))) if *v == decisive => {
+                return Ok(None)
+            }
+            ColumnarValue::Scalar(_) => return self.right.evaluate(batch).map(Some),
+            ColumnarValue::Array(array) => {
+                array.as_any().downcast_ref::<BooleanArray>().unwrap()
+            }
+        };
+
+        let undecided = (0..left.len())
+            .map(|i| left.is_null(i) || left.value(i) != decisive)
+            .collect::<Vec<_>>();
+        match undecided.iter().filter(|u| **u).count() {
+            0 => Ok(None),
+            n if n == left.len() => self.right.evaluate(batch).map(Some),
+            _ => self
+                .right
+                .evaluate_selection(batch, &BooleanArray::from(undecided))
+                .map(Some),
+        }
+    }
+}
+
 impl std::fmt::Display for BinaryExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "{} {} {}", self.left, self.op, self.right)
@@ -544,6 +656,134 @@ macro_rules! binary_array_op {
     }};
 }
 
+/// The smallest value of `Int96`, whose values are stored as `i128`
+const INT96_MIN: i128 = -(1 << 95);
+
+/// The largest value of `Int96`, whose values are stored as `i128`
+const INT96_MAX: i128 = (1 << 95) - 1;
+
+/// Whether `value` is in the range of `Int96` and `Int96Decimal`, the values of their
+/// `i128` native type outside of it overflow them
+pub(crate) fn fits_int96(value: &i128) -> bool {
+    (INT96_MIN..=INT96_MAX).contains(value)
+}
+
+/// Applies an arithmetic operator with checked native operations, the error of the
+/// first result that overflows, or is not accepted by `$FITS` when given
+macro_rules! checked_op {
+    ($LEFT:expr, $RIGHT:expr, $OP:expr, $METHOD:ident, $DT:ident) => {{
+        checked_op!($LEFT, $RIGHT, $OP, $METHOD, $DT, |_| true)
+    }};
+    ($LEFT:expr, $RIGHT:expr, $OP:expr, $METHOD:ident, $DT:ident, $FITS:expr) => {{
+        let ll = $LEFT
+            .as_any()
+            .downcast_ref::<$DT>()
+            .expect("checked_op failed to downcast array");
+        let rr = $RIGHT
+            .as_any()
+            .downcast_ref::<$DT>()
+            .expect("checked_op failed to downcast array");
+        let result = ll
+            .iter()
+            .zip(rr.iter())
+            .map(|(l, r)| match (l, r) {
+                (Some(l), Some(r)) => {
+                    l.$METHOD(r).filter($FITS).map(Some).ok_or_else(|| {
+                        DataFusionError::Execution(format!(
+                            "Arithmetic overflow: {} {} {} is out of range of {:?}",
+                            l,
+                            $OP,
+                            r,
+                            $LEFT.data_type()
+                        ))
+                    })
+                }
+                _ => Ok(None),
+            })
+            .collect::<Result<$DT>>();
+        result.map(|result| Arc::new(result) as ArrayRef)
+    }};
+}
+
+/// Applies an arithmetic operator to integer or decimal arrays with checked native
+/// operations. Returns from the calling function with `None` for the other types.
+macro_rules! checked_primitive_array_op {
+    ($LEFT:expr, $RIGHT:expr, $OP:expr, $METHOD:ident) => {{
+        match $LEFT.data_type() {
+            DataType::Int8 => checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int8Array),
+            DataType::Int16 => checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int16Array),
+            DataType::Int32 => checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int32Array),
+            DataType::Int64 => checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int64Array),
+            DataType::Int96 => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int96Array, fits_int96)
+            }
+            DataType::UInt8 => checked_op!($LEFT, $RIGHT, $OP, $METHOD, UInt8Array),
+            DataType::UInt16 => checked_op!($LEFT, $RIGHT, $OP, $METHOD, UInt16Array),
+            DataType::UInt32 => checked_op!($LEFT, $RIGHT, $OP, $METHOD, UInt32Array),
+            DataType::UInt64 => checked_op!($LEFT, $RIGHT, $OP, $METHOD, UInt64Array),
+            DataType::Int64Decimal(0) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int64Decimal0Array)
+            }
+            DataType::Int64Decimal(1) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int64Decimal1Array)
+            }
+            DataType::Int64Decimal(2) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int64Decimal2Array)
+            }
+            DataType::Int64Decimal(3) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int64Decimal3Array)
+            }
+            DataType::Int64Decimal(4) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int64Decimal4Array)
+            }
+            DataType::Int64Decimal(5) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int64Decimal5Array)
+            }
+            DataType::Int64Decimal(10) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int64Decimal10Array)
+            }
+            DataType::Int96Decimal(0) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int96Decimal0Array, fits_int96)
+            }
+            DataType::Int96Decimal(1) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int96Decimal1Array, fits_int96)
+            }
+            DataType::Int96Decimal(2) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int96Decimal2Array, fits_int96)
+            }
+            DataType::Int96Decimal(3) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int96Decimal3Array, fits_int96)
+            }
+            DataType::Int96Decimal(4) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int96Decimal4Array, fits_int96)
+            }
+            DataType::Int96Decimal(5) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int96Decimal5Array, fits_int96)
+            }
+            DataType::Int96Decimal(10) => {
+                checked_op!($LEFT, $RIGHT, $OP, $METHOD, Int96Decimal10Array, fits_int96)
+            }
+            _ => return None,
+        }
+    }};
+}
+
+/// Adds, subtracts or multiplies integers or decimals, failing on overflow rather
+/// than wrapping. None for the other operators and types, left to the kernels.
+/// Decimals are only added and subtracted, their products are floats.
+fn checked_arithmetic(
+    left: &ArrayRef,
+    op: &Operator,
+    right: &ArrayRef,
+) -> Option<Result<ArrayRef>> {
+    Some(match op {
+        Operator::Plus => checked_primitive_array_op!(left, right, op, checked_add),
+        Operator::Minus => checked_primitive_array_op!(left, right, op, checked_sub),
+        Operator::Multiply => checked_primitive_array_op!(left, right, op, checked_mul),
+        _ => return None,
+    })
+}
+
 /// Invoke a boolean kernel on a pair of arrays
 macro_rules! boolean_op {
     ($LEFT:expr, $RIGHT:expr, $OP:ident) => {{
@@ -588,14 +828,17 @@ fn common_binary_type(
     let result = match op {
         Operator::And | Operator::Or => match (lhs_type, rhs_type) {
             // logical binary boolean operators can only be evaluated in bools
-            (DataType::Boolean, DataType::Boolean) => Some(DataType::Boolean),
+            (DataType::Boolean | DataType::Null, DataType::Boolean | DataType::Null) => {
+                Some(DataType::Boolean)
+            }
             _ => None,
         },
         // logical equality operators have their own rules, and always return a boolean
         Operator::Eq | Operator::NotEq => eq_coercion(lhs_type, rhs_type),
         // "like" operators operate on strings and always return a boolean
         Operator::Like | Operator::NotLike | Operator::ILike | Operator::NotILike => {
-            string_coercion(lhs_type, rhs_type)
+            null_coercion(lhs_type, rhs_type)
+                .or_else(|| string_coercion(lhs_type, rhs_type))
         }
         // order-comparison operators have their own rules
         Operator::Lt | Operator::Gt | Operator::GtEq | Operator::LtEq => {
@@ -604,11 +847,13 @@ fn common_binary_type(
         // for math expressions, the final value of the coercion is also the return type
         // because coercion favours higher information types
         Operator::Divide | Operator::Multiply | Operator::Modulus => {
-            multi_div_conversion(lhs_type, rhs_type)
+            null_coercion(lhs_type, rhs_type)
+                .or_else(|| multi_div_conversion(lhs_type, rhs_type))
                 .or_else(|| numerical_coercion(lhs_type, rhs_type))
                 .or_else(|| string_implicit_cast(lhs_type, rhs_type))
         }
-        Operator::Plus | Operator::Minus => numerical_coercion(lhs_type, rhs_type)
+        Operator::Plus | Operator::Minus => null_coercion(lhs_type, rhs_type)
+            .or_else(|| numerical_coercion(lhs_type, rhs_type))
             .or_else(|| string_implicit_cast(lhs_type, rhs_type)),
     };
 
@@ -678,9 +923,23 @@ impl PhysicalExpr for BinaryExpr {
         Ok(self.left.nullable(input_schema)? || self.right.nullable(input_schema)?)
     }
 
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        crate::physical_plan::column_indices(vec![&self.left, &self.right])
+    }
+
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let left_value = self.left.evaluate(batch)?;
-        let right_value = self.right.evaluate(batch)?;
+        let right_value = match self.op {
+            Operator::And | Operator::Or
+                if left_value.data_type() == DataType::Boolean =>
+            {
+                match self.evaluate_undecided_right(&left_value, batch)? {
+                    Some(right_value) => right_value,
+                    None => return Ok(left_value),
+                }
+            }
+            _ => self.right.evaluate(batch)?,
+        };
         let left_data_type = left_value.data_type();
         let right_data_type = right_value.data_type();
 
@@ -691,6 +950,38 @@ impl PhysicalExpr for BinaryExpr {
             )));
         }
 
+        // two untyped NULLs are only left when there is no other type to cast them to,
+        // and the result of any operator on them is NULL
+        if left_data_type == DataType::Null {
+            let data_type =
+                binary_operator_data_type(&left_data_type, &self.op, &right_data_type)?;
+            return Ok(match (&left_value, &right_value) {
+                (ColumnarValue::Scalar(_), ColumnarValue::Scalar(_)) => {
+                    ColumnarValue::Scalar(ScalarValue::try_from(&data_type)?)
+                }
+                _ => ColumnarValue::Array(new_null_array(&data_type, batch.num_rows())),
+            });
+        }
+
+        // the scalar kernels do not accept NULL, while every operator except AND and OR
+        // is NULL when one of its operands is
+        if let (ColumnarValue::Array(array), ColumnarValue::Scalar(scalar))
+        | (ColumnarValue::Scalar(scalar), ColumnarValue::Array(array)) =
+            (&left_value, &right_value)
+        {
+            if scalar.is_null() && !matches!(self.op, Operator::And | Operator::Or) {
+                let data_type = binary_operator_data_type(
+                    &left_data_type,
+                    &self.op,
+                    &right_data_type,
+                )?;
+                return Ok(ColumnarValue::Array(new_null_array(
+                    &data_type,
+                    array.len(),
+                )));
+            }
+        }
+
         let scalar_result = match (&left_value, &right_value) {
             (ColumnarValue::Array(array), ColumnarValue::Scalar(scalar)) => {
                 // if left is array and right is literal - use scalar operations
@@ -783,6 +1074,12 @@ impl PhysicalExpr for BinaryExpr {
             }
         }
 
+        if self.overflow_check {
+            if let Some(result) = checked_arithmetic(&left, &self.op, &right) {
+                return result.map(ColumnarValue::Array);
+            }
+        }
+
         let result: Result<ArrayRef> = match &self.op {
             Operator::Like => binary_string_array_op!(left, right, like),
             Operator::NotLike => binary_string_array_op!(left, right, nlike),
@@ -858,6 +1155,20 @@ pub fn binary(
     Ok(Arc::new(BinaryExpr::new(l, op, r)))
 }
 
+/// Create a binary expression like [binary], whose arithmetic of integers and
+/// decimals fails on overflow
+pub fn checked_binary(
+    lhs: Arc<dyn PhysicalExpr>,
+    op: Operator,
+    rhs: Arc<dyn PhysicalExpr>,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let (l, r) = binary_cast(lhs, &op, rhs, input_schema)?;
+    Ok(Arc::new(
+        BinaryExpr::new(l, op, r).with_overflow_check(true),
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use arrow::datatypes::{ArrowNumericType, Field, Int32Type, Schema, SchemaRef};
@@ -866,7 +1177,7 @@ mod tests {
     use super::*;
     use crate::error::Result;
 
-    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::{col, lit};
 
     // Create a binary expression without coercion. Used here when we do not want to coerce the expressions
     // to valid types. Usage can result in an execution (after plan) error.
@@ -907,6 +1218,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn binary_with_null_literal() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        let a = Int32Array::from(vec![1, 2]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(a)])?;
+        let null = lit(ScalarValue::Int32(None));
+
+        for op in &[Operator::Lt, Operator::Eq, Operator::Divide] {
+            let expr = binary_simple(col("a", &schema)?, *op, null.clone());
+            let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            assert_eq!(result.len(), 2);
+            assert_eq!(result.null_count(), 2);
+            assert_eq!(result.data_type(), &expr.data_type(&schema)?);
+
+            let expr = binary_simple(null.clone(), *op, col("a", &schema)?);
+            let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            assert_eq!(result.null_count(), 2);
+        }
+        Ok(())
+    }
+
     #[test]
     fn binary_nested() -> Result<()> {
         let schema = Schema::new(vec![
@@ -1290,6 +1622,85 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn checked_arithmetic_op() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Int64, true),
+        ]));
+        let a = Int64Array::from(vec![Some(1), None, Some(i64::MIN)]);
+        let b = Int64Array::from(vec![Some(2), Some(3), Some(2)]);
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(a), Arc::new(b)])?;
+
+        let plus = checked_binary(
+            col("a", &schema)?,
+            Operator::Plus,
+            col("b", &schema)?,
+            &schema,
+        )?;
+        let result = plus.evaluate(&batch)?.into_array(batch.num_rows());
+        let expected = Int64Array::from(vec![Some(3), None, Some(i64::MIN + 2)]);
+        assert_eq!(result.as_ref(), &expected);
+
+        let multiply = checked_binary(
+            col("a", &schema)?,
+            Operator::Multiply,
+            col("b", &schema)?,
+            &schema,
+        )?;
+        let err = multiply.evaluate(&batch).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Arithmetic overflow: -9223372036854775808 * 2 is out of \
+             range of Int64"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn checked_arithmetic_op_int96() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int96, true),
+            Field::new("b", DataType::Int96, true),
+        ]));
+        let a = Int96Array::from(vec![(1 << 95) - 2, -(1 << 95)]);
+        let b = Int96Array::from(vec![1, 0]);
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(a), Arc::new(b)])?;
+        let plus = checked_binary(
+            col("a", &schema)?,
+            Operator::Plus,
+            col("b", &schema)?,
+            &schema,
+        )?;
+        let result = plus.evaluate(&batch)?.into_array(batch.num_rows());
+        let expected = Int96Array::from(vec![(1 << 95) - 1, -(1 << 95)]);
+        assert_eq!(result.as_ref(), &expected);
+
+        // the sums are in the range of i128, not of Int96
+        let a = Int96Array::from(vec![(1 << 95) - 1]);
+        let b = Int96Array::from(vec![1]);
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(a), Arc::new(b)])?;
+        let err = plus.evaluate(&batch).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Arithmetic overflow: 39614081257132168796771975167 + 1 is \
+             out of range of Int96"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn promoted_types() {
+        assert_eq!(promoted_type(&DataType::Int16), Some(DataType::Int64));
+        assert_eq!(promoted_type(&DataType::UInt64), Some(DataType::Int96));
+        assert_eq!(
+            promoted_type(&DataType::Int64Decimal(2)),
+            Some(DataType::Int96Decimal(2))
+        );
+        assert_eq!(promoted_type(&DataType::Int96), None);
+        assert_eq!(promoted_type(&DataType::Float64), None);
+    }
+
     fn apply_arithmetic<T: ArrowNumericType>(
         schema: SchemaRef,
         data: Vec<ArrayRef>,
@@ -1410,6 +1821,66 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn logic_op_evaluates_right_for_undecided_rows() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Boolean, true),
+            Field::new("c", DataType::Int32, false),
+        ]));
+        // dividing by c fails in the rows that the left side already decides
+        let right = |schema: &Schema| -> Result<Arc<dyn PhysicalExpr>> {
+            Ok(binary_simple(
+                binary_simple(
+                    lit(ScalarValue::Int32(Some(10))),
+                    Operator::Divide,
+                    col("c", schema)?,
+                ),
+                Operator::Gt,
+                lit(ScalarValue::Int32(Some(1))),
+            ))
+        };
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(BooleanArray::from(vec![Some(true), None, Some(false)])),
+                Arc::new(Int32Array::from(vec![0, 5, 20])),
+            ],
+        )?;
+        let or = binary_simple(col("a", &schema)?, Operator::Or, right(&schema)?);
+        let result = or.evaluate(&batch)?.into_array(batch.num_rows());
+        let expected = BooleanArray::from(vec![Some(true), Some(true), Some(false)]);
+        assert_eq!(result.as_ref(), &expected);
+
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(BooleanArray::from(vec![Some(false), None, Some(true)])),
+                Arc::new(Int32Array::from(vec![0, 20, 5])),
+            ],
+        )?;
+        let and = binary_simple(col("a", &schema)?, Operator::And, right(&schema)?);
+        let result = and.evaluate(&batch)?.into_array(batch.num_rows());
+        let expected = BooleanArray::from(vec![Some(false), Some(false), Some(true)]);
+        assert_eq!(result.as_ref(), &expected);
+
+        // the right side is not evaluated at all when the left side decides every row
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(BooleanArray::from(vec![false, false])),
+                Arc::new(Int32Array::from(vec![0, 0])),
+            ],
+        )?;
+        let result = and.evaluate(&batch)?.into_array(batch.num_rows());
+        assert_eq!(result.as_ref(), &BooleanArray::from(vec![false, false]));
+
+        // only the columns that the right side reads are filtered
+        assert_eq!(right(&schema)?.column_indices(), Some(vec![1]));
+        assert_eq!(and.column_indices(), Some(vec![0, 1]));
+
+        Ok(())
+    }
+
     #[test]
     #[ignore = "Cube Store coerces strings to numerics"]
     fn test_coersion_error() -> Result<()> {
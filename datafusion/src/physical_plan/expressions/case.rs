@@ -20,6 +20,7 @@ use std::{any::Any, sync::Arc};
 use crate::arrow::compute::cast;
 use crate::arrow::datatypes::TimeUnit;
 use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::IsNotNullExpr;
 use crate::physical_plan::{ColumnarValue, PhysicalExpr};
 use arrow::array::{self, *};
 use arrow::compute::{eq, eq_utf8};
@@ -488,14 +489,14 @@ impl CaseExpr {
     ///     [ELSE result]
     /// END
     fn case_when_with_expr(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
-        let return_type = self.when_then_expr[0].1.data_type(&batch.schema())?;
+        let return_type = self.data_type(&batch.schema())?;
         let expr = self.expr.as_ref().unwrap();
         let base_value = expr.evaluate(batch)?;
         let base_value = base_value.into_array(batch.num_rows());
 
         // start with the else condition, or nulls
         let mut current_value: Option<ArrayRef> = if let Some(e) = &self.else_expr {
-            Some(e.evaluate(batch)?.into_array(batch.num_rows()))
+            Some(result_array(e.evaluate(batch)?, &return_type, batch)?)
         } else {
             Some(new_null_array(&return_type, batch.num_rows()))
         };
@@ -508,7 +509,7 @@ impl CaseExpr {
             let when_value = when_value.into_array(batch.num_rows());
 
             let then_value = self.when_then_expr[i].1.evaluate(batch)?;
-            let then_value = then_value.into_array(batch.num_rows());
+            let then_value = result_array(then_value, &return_type, batch)?;
 
             // build boolean array representing which rows match the "when" value
             let when_match =
@@ -538,11 +539,11 @@ impl CaseExpr {
     ///      [ELSE result]
     /// END
     fn case_when_no_expr(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
-        let return_type = self.when_then_expr[0].1.data_type(&batch.schema())?;
+        let return_type = self.data_type(&batch.schema())?;
 
         // start with the else condition, or nulls
         let mut current_value: Option<ArrayRef> = if let Some(e) = &self.else_expr {
-            Some(e.evaluate(batch)?.into_array(batch.num_rows()))
+            Some(result_array(e.evaluate(batch)?, &return_type, batch)?)
         } else {
             Some(new_null_array(&return_type, batch.num_rows()))
         };
@@ -560,7 +561,7 @@ impl CaseExpr {
                 .expect("WHEN expression did not return a BooleanArray");
 
             let then_value = self.when_then_expr[i].1.evaluate(batch)?;
-            let then_value = then_value.into_array(batch.num_rows());
+            let then_value = result_array(then_value, &return_type, batch)?;
 
             let return_type = then_value.data_type();
             let else_value = current_value
@@ -586,25 +587,47 @@ impl PhysicalExpr for CaseExpr {
     }
 
     fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
-        self.when_then_expr[0].1.data_type(input_schema)
+        // the type of the first result that is not an untyped NULL
+        let mut data_type = DataType::Null;
+        let results = self.when_then_expr.iter().map(|(_, t)| t);
+        for result in results.chain(&self.else_expr) {
+            data_type = result.data_type(input_schema)?;
+            if data_type != DataType::Null {
+                break;
+            }
+        }
+        Ok(data_type)
     }
 
     fn nullable(&self, input_schema: &Schema) -> Result<bool> {
-        // this expression is nullable if any of the input expressions are nullable
-        let then_nullable = self
-            .when_then_expr
-            .iter()
-            .map(|(_, t)| t.nullable(input_schema))
-            .collect::<Result<Vec<_>>>()?;
-        if then_nullable.contains(&true) {
-            Ok(true)
-        } else if let Some(e) = &self.else_expr {
-            e.nullable(input_schema)
-        } else {
-            Ok(false)
+        // this expression is nullable if any of the results is nullable, except for the
+        // results that are only taken when they are not null, as in the logical plan
+        for (when, then) in &self.when_then_expr {
+            let guarded = self.expr.is_none()
+                && when
+                    .as_any()
+                    .downcast_ref::<IsNotNullExpr>()
+                    .map_or(false, |e| e.arg().to_string() == then.to_string());
+            if !guarded && then.nullable(input_schema)? {
+                return Ok(true);
+            }
+        }
+        match &self.else_expr {
+            Some(e) => e.nullable(input_schema),
+            // rows that match no condition are null
+            None => Ok(true),
         }
     }
 
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        crate::physical_plan::column_indices(
+            self.expr
+                .iter()
+                .chain(self.when_then_expr.iter().flat_map(|(w, t)| vec![w, t]))
+                .chain(self.else_expr.iter()),
+        )
+    }
+
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         if self.expr.is_some() {
             // this use case evaluates "expr" and then compares the values with the "when"
@@ -618,6 +641,21 @@ impl PhysicalExpr for CaseExpr {
     }
 }
 
+/// Converts a result of the CASE expression into an array, typing untyped NULLs with
+/// the return type
+fn result_array(
+    value: ColumnarValue,
+    return_type: &DataType,
+    batch: &RecordBatch,
+) -> Result<ArrayRef> {
+    let array = value.into_array(batch.num_rows());
+    if array.data_type() == &DataType::Null {
+        Ok(new_null_array(return_type, array.len()))
+    } else {
+        Ok(array)
+    }
+}
+
 /// Create a CASE expression
 pub fn case(
     expr: Option<Arc<dyn PhysicalExpr>>,
@@ -20,20 +20,33 @@ use std::fmt;
 use std::sync::Arc;
 
 use super::ColumnarValue;
+use crate::cube_ext::temporal::Zone;
 use crate::error::{DataFusionError, Result};
 
+use crate::physical_plan::datetime_expressions::{
+    convert_timestamp_unit, string_to_timestamp_in_zone,
+};
 use crate::physical_plan::PhysicalExpr;
 use crate::scalar::ScalarValue;
+use arrow::array::{
+    new_null_array, Array, ArrayRef, Int64Array, StringArray, TimestampMicrosecondArray,
+    TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray,
+};
 use arrow::compute;
 use arrow::compute::kernels;
 use arrow::compute::CastOptions;
-use arrow::datatypes::{DataType, Schema};
+use arrow::datatypes::{DataType, Schema, TimeUnit};
+use arrow::error::ArrowError;
 use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
 use compute::can_cast_types;
 
 /// provide Datafusion default cast options
 pub const DEFAULT_DATAFUSION_CAST_OPTIONS: CastOptions = CastOptions { safe: false };
 
+/// Maximum number of values that could not be converted reported by a failed CAST
+pub const MAX_CAST_ERROR_EXAMPLES: usize = 5;
+
 /// CAST expression casts an expression to a specific data type and returns a runtime error on invalid cast
 #[derive(Debug)]
 pub struct CastExpr {
@@ -43,6 +56,8 @@ pub struct CastExpr {
     cast_type: DataType,
     /// Cast options
     cast_options: CastOptions,
+    /// Time zone of strings without an offset cast to timestamps
+    time_zone: Zone,
 }
 
 impl CastExpr {
@@ -56,9 +71,17 @@ impl CastExpr {
             expr,
             cast_type,
             cast_options,
+            time_zone: Zone::utc(),
         }
     }
 
+    /// Interpret strings without an offset cast to timestamps as local times of
+    /// `time_zone` instead of UTC
+    pub fn with_time_zone(mut self, time_zone: Zone) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
     /// The expression to cast
     pub fn expr(&self) -> &Arc<dyn PhysicalExpr> {
         &self.expr
@@ -68,6 +91,11 @@ impl CastExpr {
     pub fn cast_type(&self) -> &DataType {
         &self.cast_type
     }
+
+    /// Time zone of strings without an offset cast to timestamps
+    pub fn time_zone(&self) -> &Zone {
+        &self.time_zone
+    }
 }
 
 impl fmt::Display for CastExpr {
@@ -90,9 +118,13 @@ impl PhysicalExpr for CastExpr {
         self.expr.nullable(input_schema)
     }
 
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        self.expr.column_indices()
+    }
+
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let value = self.expr.evaluate(batch)?;
-        cast_column(&value, &self.cast_type, &self.cast_options)
+        cast_column_in_zone(&value, &self.cast_type, &self.cast_options, &self.time_zone)
     }
 }
 
@@ -101,21 +133,192 @@ pub fn cast_column(
     value: &ColumnarValue,
     cast_type: &DataType,
     cast_options: &CastOptions,
+) -> Result<ColumnarValue> {
+    cast_column_in_zone(value, cast_type, cast_options, &Zone::utc())
+}
+
+/// Casts a ColumnarValue like [cast_column], interpreting strings without an offset
+/// cast to timestamps as local times of `time_zone`
+pub fn cast_column_in_zone(
+    value: &ColumnarValue,
+    cast_type: &DataType,
+    cast_options: &CastOptions,
+    time_zone: &Zone,
 ) -> Result<ColumnarValue> {
     match value {
-        ColumnarValue::Array(array) => Ok(ColumnarValue::Array(
-            kernels::cast::cast_with_options(array, cast_type, cast_options)?,
-        )),
+        ColumnarValue::Array(array) => Ok(ColumnarValue::Array(cast_array_in_zone(
+            array,
+            cast_type,
+            cast_options,
+            time_zone,
+        )?)),
+        ColumnarValue::Scalar(ScalarValue::Null) => {
+            Ok(ColumnarValue::Scalar(ScalarValue::try_from(cast_type)?))
+        }
         ColumnarValue::Scalar(scalar) => {
             let scalar_array = scalar.to_array();
             let cast_array =
-                kernels::cast::cast_with_options(&scalar_array, cast_type, cast_options)?;
+                cast_array_in_zone(&scalar_array, cast_type, cast_options, time_zone)?;
             let cast_scalar = ScalarValue::try_from_array(&cast_array, 0)?;
             Ok(ColumnarValue::Scalar(cast_scalar))
         }
     }
 }
 
+/// Casts a whole array with the arrow cast kernel.
+///
+/// When a strict cast of a string array fails, the returned error reports how many
+/// values could not be converted along with up to [`MAX_CAST_ERROR_EXAMPLES`] of
+/// them, instead of only the first one found by the kernel.
+pub fn cast_array(
+    array: &ArrayRef,
+    cast_type: &DataType,
+    cast_options: &CastOptions,
+) -> Result<ArrayRef> {
+    cast_array_in_zone(array, cast_type, cast_options, &Zone::utc())
+}
+
+/// Casts a whole array like [cast_array], interpreting strings without an offset cast
+/// to timestamps as local times of `time_zone`
+pub fn cast_array_in_zone(
+    array: &ArrayRef,
+    cast_type: &DataType,
+    cast_options: &CastOptions,
+    time_zone: &Zone,
+) -> Result<ArrayRef> {
+    // untyped NULLs can be cast to any type
+    if array.data_type() == &DataType::Null {
+        return Ok(new_null_array(cast_type, array.len()));
+    }
+    // timestamps are converted with checked arithmetic, as the kernel overflows on dates
+    // outside of the range of nanosecond timestamps
+    match (array.data_type(), cast_type) {
+        (DataType::Utf8, DataType::Timestamp(unit, tz)) => {
+            let strings = array.as_any().downcast_ref::<StringArray>().unwrap();
+            let mut first_error = None;
+            let values = strings
+                .iter()
+                .map(|s| {
+                    s.and_then(|s| {
+                        match string_to_timestamp_in_zone(s, unit, time_zone) {
+                            Ok(v) => Some(v),
+                            Err(e) => {
+                                first_error.get_or_insert(e);
+                                None
+                            }
+                        }
+                    })
+                })
+                .collect();
+            let result = timestamp_array(values, unit, tz);
+            return match first_error {
+                Some(e) if !cast_options.safe => {
+                    Err(failed_cast_error(array, &result, cast_type, e)?)
+                }
+                _ => Ok(result),
+            };
+        }
+        (DataType::Timestamp(from, _), DataType::Timestamp(to, tz)) if from != to => {
+            let values = kernels::cast::cast(array, &DataType::Int64)?;
+            let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+            let values = values
+                .iter()
+                .map(|v| v.and_then(|v| convert_timestamp_unit(v, from, to)))
+                .collect();
+            let result = timestamp_array(values, to, tz);
+            if !cast_options.safe && result.null_count() != array.null_count() {
+                let error = DataFusionError::Execution(format!(
+                    "Values out of the range of {:?} timestamps",
+                    to
+                ));
+                return Err(failed_cast_error(array, &result, cast_type, error)?);
+            }
+            return Ok(result);
+        }
+        _ => {}
+    }
+    match kernels::cast::cast_with_options(array, cast_type, cast_options) {
+        Ok(result) => Ok(result),
+        Err(e)
+            if !cast_options.safe
+                && matches!(array.data_type(), DataType::Utf8 | DataType::LargeUtf8) =>
+        {
+            Err(string_cast_error(array, cast_type, e)?)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Builds a timestamp array of `unit` and time zone `tz`
+fn timestamp_array(
+    values: Vec<Option<i64>>,
+    unit: &TimeUnit,
+    tz: &Option<String>,
+) -> ArrayRef {
+    match unit {
+        TimeUnit::Second => {
+            Arc::new(TimestampSecondArray::from_opt_vec(values, tz.clone()))
+        }
+        TimeUnit::Millisecond => {
+            Arc::new(TimestampMillisecondArray::from_opt_vec(values, tz.clone()))
+        }
+        TimeUnit::Microsecond => {
+            Arc::new(TimestampMicrosecondArray::from_opt_vec(values, tz.clone()))
+        }
+        TimeUnit::Nanosecond => {
+            Arc::new(TimestampNanosecondArray::from_opt_vec(values, tz.clone()))
+        }
+    }
+}
+
+/// Builds the error of a failed strict cast of a string array
+fn string_cast_error(
+    array: &ArrayRef,
+    cast_type: &DataType,
+    error: ArrowError,
+) -> Result<DataFusionError> {
+    // a single lenient cast finds all the values that cannot be converted
+    let lenient =
+        kernels::cast::cast_with_options(array, cast_type, &CastOptions { safe: true })?;
+    failed_cast_error(array, &lenient, cast_type, error)
+}
+
+/// Builds the error of a failed strict cast from the result of the lenient cast, in which
+/// the values that cannot be converted are NULL
+fn failed_cast_error<E: fmt::Display + Into<DataFusionError>>(
+    array: &ArrayRef,
+    lenient: &ArrayRef,
+    cast_type: &DataType,
+    error: E,
+) -> Result<DataFusionError> {
+    let mut failed = 0;
+    let mut examples = Vec::new();
+    for i in 0..array.len() {
+        if array.is_valid(i) && lenient.is_null(i) {
+            if examples.len() < MAX_CAST_ERROR_EXAMPLES {
+                examples.push(format!(
+                    "row {}: '{}'",
+                    i,
+                    array_value_to_string(array, i)?
+                ));
+            }
+            failed += 1;
+        }
+    }
+    if failed == 0 {
+        return Ok(error.into());
+    }
+    Ok(DataFusionError::Execution(format!(
+        "{}. {} of {} values cannot be cast from {:?} to {:?}, e.g. {}",
+        error,
+        failed,
+        array.len(),
+        array.data_type(),
+        cast_type,
+        examples.join(", ")
+    )))
+}
+
 /// Return a PhysicalExpression representing `expr` casted to
 /// `cast_type`, if any casting is needed.
 ///
@@ -125,12 +328,27 @@ pub fn cast_with_options(
     input_schema: &Schema,
     cast_type: DataType,
     cast_options: CastOptions,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    cast_in_zone(expr, input_schema, cast_type, cast_options, Zone::utc())
+}
+
+/// Return a PhysicalExpression representing `expr` casted to `cast_type` like
+/// [cast_with_options], where strings without an offset cast to timestamps are local
+/// times of `time_zone`
+pub fn cast_in_zone(
+    expr: Arc<dyn PhysicalExpr>,
+    input_schema: &Schema,
+    cast_type: DataType,
+    cast_options: CastOptions,
+    time_zone: Zone,
 ) -> Result<Arc<dyn PhysicalExpr>> {
     let expr_type = expr.data_type(input_schema)?;
     if expr_type == cast_type {
         Ok(expr.clone())
-    } else if can_cast_types(&expr_type, &cast_type) {
-        Ok(Arc::new(CastExpr::new(expr, cast_type, cast_options)))
+    } else if expr_type == DataType::Null || can_cast_types(&expr_type, &cast_type) {
+        Ok(Arc::new(
+            CastExpr::new(expr, cast_type, cast_options).with_time_zone(time_zone),
+        ))
     } else {
         Err(DataFusionError::Internal(format!(
             "Unsupported CAST from {:?} to {:?}",
@@ -307,4 +525,39 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn cast_error_reports_failed_values() -> Result<()> {
+        let values = (0..20)
+            .map(|i| {
+                if i % 3 == 0 {
+                    Some(format!("x{}", i))
+                } else {
+                    Some(i.to_string())
+                }
+            })
+            .chain(vec![None])
+            .collect::<Vec<_>>();
+        let array: ArrayRef = Arc::new(StringArray::from(values));
+
+        let err = cast_array(&array, &DataType::Int64, &DEFAULT_DATAFUSION_CAST_OPTIONS)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Cannot cast string 'x0'"), "{}", err);
+        assert!(
+            err.contains(
+                "7 of 21 values cannot be cast from Utf8 to Int64, e.g. row 0: 'x0', \
+                 row 3: 'x3', row 6: 'x6', row 9: 'x9', row 12: 'x12'"
+            ),
+            "{}",
+            err
+        );
+
+        // lenient cast replaces the values with nulls
+        let result = cast_array(&array, &DataType::Int64, &CastOptions { safe: true })?;
+        let result = result.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(result.null_count(), 8);
+        assert_eq!(result.value(1), 1);
+        Ok(())
+    }
 }
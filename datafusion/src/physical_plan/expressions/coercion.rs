@@ -48,6 +48,15 @@ pub fn is_numeric(dt: &DataType) -> bool {
         }
 }
 
+/// Coercion rules for an untyped NULL: it takes the type of the other side, so that
+/// the NULL is cast to it. Two NULLs stay untyped.
+pub fn null_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    match (lhs_type, rhs_type) {
+        (DataType::Null, other) | (other, DataType::Null) => Some(other.clone()),
+        _ => None,
+    }
+}
+
 /// Coercion rules for dictionary values (aka the type of the  dictionary itself)
 fn dictionary_value_coercion(
     lhs_type: &DataType,
@@ -106,6 +115,36 @@ pub fn temporal_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<Dat
     }
 }
 
+/// Coercion rule for timestamps of different units: the finer of the two units, so that
+/// e.g. `Timestamp(Second)` columns can be compared with `Timestamp(Nanosecond)` ones.
+/// The time zone is kept only if both sides agree on it.
+pub fn timestamp_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (Timestamp(lhs_unit, lhs_tz), Timestamp(rhs_unit, rhs_tz)) => {
+            let unit = match (lhs_unit, rhs_unit) {
+                (TimeUnit::Nanosecond, _) | (_, TimeUnit::Nanosecond) => {
+                    TimeUnit::Nanosecond
+                }
+                (TimeUnit::Microsecond, _) | (_, TimeUnit::Microsecond) => {
+                    TimeUnit::Microsecond
+                }
+                (TimeUnit::Millisecond, _) | (_, TimeUnit::Millisecond) => {
+                    TimeUnit::Millisecond
+                }
+                (TimeUnit::Second, TimeUnit::Second) => TimeUnit::Second,
+            };
+            let tz = if lhs_tz == rhs_tz {
+                lhs_tz.clone()
+            } else {
+                None
+            };
+            Some(Timestamp(unit, tz))
+        }
+        _ => None,
+    }
+}
+
 /// Coercion rule for numerical types: The type that both lhs and rhs
 /// can be casted to for numerical calculation, while maintaining
 /// maximum precision
@@ -255,7 +294,9 @@ pub fn eq_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType>
         // same type => equality is possible
         return Some(lhs_type.clone());
     }
-    numerical_coercion(lhs_type, rhs_type)
+    null_coercion(lhs_type, rhs_type)
+        .or_else(|| timestamp_coercion(lhs_type, rhs_type))
+        .or_else(|| numerical_coercion(lhs_type, rhs_type))
         .or_else(|| eq_bool_coercion(lhs_type, rhs_type))
         .or_else(|| dictionary_coercion(lhs_type, rhs_type))
         .or_else(|| temporal_coercion(lhs_type, rhs_type))
@@ -278,13 +319,42 @@ pub fn order_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataTy
         return Some(lhs_type.clone());
     }
 
-    numerical_coercion(lhs_type, rhs_type)
+    null_coercion(lhs_type, rhs_type)
+        .or_else(|| timestamp_coercion(lhs_type, rhs_type))
+        .or_else(|| numerical_coercion(lhs_type, rhs_type))
         .or_else(|| string_coercion(lhs_type, rhs_type))
         .or_else(|| dictionary_coercion(lhs_type, rhs_type))
         .or_else(|| temporal_coercion(lhs_type, rhs_type))
         .or_else(|| string_implicit_cast(lhs_type, rhs_type))
 }
 
+/// Float32 only represents the integers of up to 24 bits exactly, it is widened to
+/// Float64 with wider integers rather than losing their precision
+fn float_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (Float32, Int32 | Int64 | Int96 | UInt32 | UInt64)
+        | (Int32 | Int64 | Int96 | UInt32 | UInt64, Float32) => Some(Float64),
+        _ => None,
+    }
+}
+
+/// Coercion rules for the columns of the inputs of a UNION: the type the values of both
+/// inputs are cast to, which widens numbers and timestamps and unpacks dictionaries.
+/// Unlike comparisons, strings are not implicitly cast to other types.
+pub fn union_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    if lhs_type == rhs_type {
+        return Some(lhs_type.clone());
+    }
+
+    null_coercion(lhs_type, rhs_type)
+        .or_else(|| timestamp_coercion(lhs_type, rhs_type))
+        .or_else(|| float_coercion(lhs_type, rhs_type))
+        .or_else(|| numerical_coercion(lhs_type, rhs_type))
+        .or_else(|| string_coercion(lhs_type, rhs_type))
+        .or_else(|| dictionary_coercion(lhs_type, rhs_type))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -310,4 +380,71 @@ mod tests {
         let rhs_type = Dictionary(Box::new(Int8), Box::new(Utf8));
         assert_eq!(dictionary_coercion(&lhs_type, &rhs_type), Some(Utf8));
     }
+
+    #[test]
+    fn test_null_coercion() {
+        use DataType::*;
+
+        assert_eq!(null_coercion(&Null, &Int64), Some(Int64));
+        assert_eq!(null_coercion(&Utf8, &Null), Some(Utf8));
+        assert_eq!(null_coercion(&Null, &Null), Some(Null));
+        assert_eq!(null_coercion(&Int32, &Int64), None);
+        assert_eq!(eq_coercion(&Null, &Boolean), Some(Boolean));
+        assert_eq!(order_coercion(&Date32, &Null), Some(Date32));
+    }
+
+    #[test]
+    fn test_timestamp_coercion() {
+        use DataType::*;
+
+        let seconds = Timestamp(TimeUnit::Second, None);
+        let millis = Timestamp(TimeUnit::Millisecond, None);
+        let nanos = Timestamp(TimeUnit::Nanosecond, None);
+        assert_eq!(timestamp_coercion(&seconds, &nanos), Some(nanos.clone()));
+        assert_eq!(timestamp_coercion(&nanos, &millis), Some(nanos.clone()));
+        assert_eq!(eq_coercion(&millis, &seconds), Some(millis.clone()));
+        assert_eq!(order_coercion(&seconds, &millis), Some(millis));
+
+        let utc = Some("UTC".to_string());
+        assert_eq!(
+            timestamp_coercion(
+                &Timestamp(TimeUnit::Second, utc.clone()),
+                &Timestamp(TimeUnit::Microsecond, utc.clone())
+            ),
+            Some(Timestamp(TimeUnit::Microsecond, utc.clone()))
+        );
+        assert_eq!(
+            timestamp_coercion(&Timestamp(TimeUnit::Second, utc), &nanos),
+            Some(nanos)
+        );
+        assert_eq!(timestamp_coercion(&seconds, &Int64), None);
+    }
+
+    #[test]
+    fn test_union_coercion() {
+        use DataType::*;
+
+        assert_eq!(union_coercion(&Int32, &Int64), Some(Int64));
+        assert_eq!(union_coercion(&Float32, &Int64), Some(Float64));
+        assert_eq!(union_coercion(&UInt32, &Float32), Some(Float64));
+        assert_eq!(union_coercion(&Float32, &Int16), Some(Float32));
+        assert_eq!(
+            union_coercion(&Int64Decimal(2), &Int64Decimal(3)),
+            Some(Int64Decimal(3))
+        );
+        assert_eq!(union_coercion(&Null, &Utf8), Some(Utf8));
+        assert_eq!(union_coercion(&Utf8, &LargeUtf8), Some(LargeUtf8));
+        let dictionary = Dictionary(Box::new(Int32), Box::new(Utf8));
+        assert_eq!(union_coercion(&dictionary, &Utf8), Some(Utf8));
+        assert_eq!(union_coercion(&dictionary, &dictionary), Some(dictionary));
+        assert_eq!(
+            union_coercion(
+                &Timestamp(TimeUnit::Second, None),
+                &Timestamp(TimeUnit::Millisecond, None)
+            ),
+            Some(Timestamp(TimeUnit::Millisecond, None))
+        );
+        assert_eq!(union_coercion(&Utf8, &Int64), None);
+        assert_eq!(union_coercion(&Boolean, &Int8), None);
+    }
 }
@@ -82,6 +82,10 @@ impl PhysicalExpr for Column {
         Ok(input_schema.field(self.index).is_nullable())
     }
 
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        Some(vec![self.index])
+    }
+
     /// Evaluate the expression
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         Ok(ColumnarValue::Array(batch.column(self.index).clone()))
@@ -0,0 +1,245 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the compensated accumulator of the SUM and AVG of floats
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::Accumulator;
+use crate::scalar::ScalarValue;
+use arrow::array::{ArrayRef, Float64Array};
+use arrow::compute::{self, total_cmp_64};
+use arrow::datatypes::{DataType, Field};
+
+use super::format_state_name;
+use smallvec::smallvec;
+use smallvec::SmallVec;
+
+/// A sum of floats with the compensation of its rounding errors, following the
+/// Kahan-Babuška-Neumaier algorithm
+#[derive(Debug, Default, Clone, Copy)]
+struct CompensatedSum {
+    sum: f64,
+    compensation: f64,
+}
+
+impl CompensatedSum {
+    fn add(&mut self, value: f64) {
+        let sum = self.sum + value;
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - sum) + value;
+        } else {
+            self.compensation += (value - sum) + self.sum;
+        }
+        self.sum = sum;
+    }
+
+    fn value(&self) -> f64 {
+        // the compensation of infinite sums is NaN
+        if self.sum.is_finite() {
+            self.sum + self.compensation
+        } else {
+            self.sum
+        }
+    }
+}
+
+/// The state fields of a compensated SUM or AVG named `name`
+pub(super) fn compensated_state_fields(name: &str) -> Vec<Field> {
+    vec![
+        Field::new(&format_state_name(name, "count"), DataType::UInt64, true),
+        Field::new(&format_state_name(name, "sum"), DataType::Float64, true),
+        Field::new(
+            &format_state_name(name, "compensation"),
+            DataType::Float64,
+            true,
+        ),
+    ]
+}
+
+/// An accumulator of the SUM or the AVG of floats, as a Float64, whose result does
+/// not depend on how the values are split in batches or partial aggregates.
+///
+/// The values are added one by one to a compensated sum. The partial sums that are
+/// merged are kept and added in the order of their values, rather than in the order
+/// the partitions computing them complete.
+#[derive(Debug)]
+pub(super) struct CompensatedSumAccumulator {
+    average: bool,
+    count: u64,
+    sum: CompensatedSum,
+    merged: Vec<CompensatedSum>,
+}
+
+impl CompensatedSumAccumulator {
+    /// Creates an accumulator of the SUM, or of the AVG if `average`
+    pub(super) fn new(average: bool) -> Self {
+        Self {
+            average,
+            count: 0,
+            sum: CompensatedSum::default(),
+            merged: vec![],
+        }
+    }
+
+    /// The sum of the values and of the merged sums
+    fn total(&self) -> CompensatedSum {
+        if self.merged.is_empty() {
+            return self.sum;
+        }
+        let mut sums = self.merged.clone();
+        sums.push(self.sum);
+        sums.sort_by(|l, r| {
+            total_cmp_64(l.sum, r.sum)
+                .then_with(|| total_cmp_64(l.compensation, r.compensation))
+        });
+        let mut total = CompensatedSum::default();
+        for sum in sums {
+            total.add(sum.sum);
+            total.add(sum.compensation);
+        }
+        total
+    }
+}
+
+impl Accumulator for CompensatedSumAccumulator {
+    fn reset(&mut self) {
+        self.count = 0;
+        self.sum = CompensatedSum::default();
+        self.merged.clear();
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        let total = self.total();
+        Ok(smallvec![
+            ScalarValue::UInt64(Some(self.count)),
+            ScalarValue::Float64(Some(total.sum)),
+            ScalarValue::Float64(Some(total.compensation)),
+        ])
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        let value = match &values[0] {
+            ScalarValue::Float64(v) => *v,
+            ScalarValue::Float32(v) => v.map(f64::from),
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Compensated sum is not expected to receive a scalar {:?}",
+                    other
+                )))
+            }
+        };
+        if let Some(value) = value {
+            self.count += 1;
+            self.sum.add(value);
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let values = compute::cast(&values[0], &DataType::Float64)?;
+        let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+        for value in values.iter().flatten() {
+            self.count += 1;
+            self.sum.add(value);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        match states {
+            [ScalarValue::UInt64(count), ScalarValue::Float64(sum), ScalarValue::Float64(compensation)] =>
+            {
+                self.count += count.unwrap_or(0);
+                self.merged.push(CompensatedSum {
+                    sum: sum.unwrap_or(0.0),
+                    compensation: compensation.unwrap_or(0.0),
+                });
+                Ok(())
+            }
+            _ => Err(DataFusionError::Internal(format!(
+                "Unexpected state {:?} of compensated sum",
+                states
+            ))),
+        }
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        if self.count == 0 {
+            return Ok(ScalarValue::Float64(None));
+        }
+        let sum = self.total().value();
+        Ok(ScalarValue::Float64(Some(if self.average {
+            sum / self.count as f64
+        } else {
+            sum
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn sum_batches(batches: &[Vec<f64>]) -> Result<CompensatedSumAccumulator> {
+        let mut acc = CompensatedSumAccumulator::new(false);
+        for batch in batches {
+            let values: ArrayRef = Arc::new(Float64Array::from(batch.clone()));
+            acc.update_batch(&[values])?;
+        }
+        Ok(acc)
+    }
+
+    #[test]
+    fn compensated_sum() -> Result<()> {
+        let acc = sum_batches(&[vec![1e16, 1.0, -1e16]])?;
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(Some(1.0)));
+
+        let acc = sum_batches(&[vec![f64::INFINITY, 1.0]])?;
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(Some(f64::INFINITY)));
+
+        let acc = sum_batches(&[vec![]])?;
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(None));
+        Ok(())
+    }
+
+    #[test]
+    fn compensated_sum_is_order_stable() -> Result<()> {
+        let values = (1..=1000).map(|i| 1.0 / i as f64).collect::<Vec<_>>();
+        let whole = sum_batches(&[values.clone()])?.evaluate()?;
+        let split = sum_batches(&[values[..3].to_vec(), values[3..].to_vec()])?;
+        assert_eq!(split.evaluate()?, whole);
+
+        // the partial sums give the same result whatever the order of their merge
+        let partials = [
+            sum_batches(&[values[..500].to_vec()])?.state()?,
+            sum_batches(&[values[500..].to_vec()])?.state()?,
+            sum_batches(&[vec![1e-3, 7.0]])?.state()?,
+        ];
+        let mut merged = CompensatedSumAccumulator::new(true);
+        for state in &partials {
+            merged.merge(state)?;
+        }
+        let mut reversed = CompensatedSumAccumulator::new(true);
+        for state in partials.iter().rev() {
+            reversed.merge(state)?;
+        }
+        assert_eq!(merged.evaluate()?, reversed.evaluate()?);
+        assert_eq!(merged.state()?, reversed.state()?);
+        Ok(())
+    }
+}
@@ -0,0 +1,429 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the FIRST and LAST aggregate expressions
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::{build_compare, ArrayRef, DynComparator};
+use arrow::compute::SortOptions;
+use arrow::datatypes::{DataType, Field, Schema};
+
+use super::{format_state_name, PhysicalSortExpr};
+use smallvec::SmallVec;
+
+/// The row whose value is returned by [FirstLast]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirstLastType {
+    /// FIRST, the value of the first row
+    First,
+    /// LAST, the value of the last row
+    Last,
+}
+
+/// FIRST and LAST aggregate expressions
+/// Return the value of the given expression in the first, or the last, of the rows in
+/// the order given by `ORDER BY` expressions, such as the latest value of a column
+/// with `LAST(value ORDER BY ts)`. The planners require `ORDER BY`: without it, the
+/// rows are in the order they are aggregated, which is arbitrary. The state is the
+/// value and the keys of that row only, rather than all the rows of the group.
+#[derive(Debug)]
+pub struct FirstLast {
+    name: String,
+    data_type: DataType,
+    first_last_type: FirstLastType,
+    expr: Arc<dyn PhysicalExpr>,
+    ignore_nulls: bool,
+    order_by: Vec<PhysicalSortExpr>,
+    order_by_types: Vec<DataType>,
+}
+
+impl FirstLast {
+    /// Create a new FIRST or LAST aggregate function
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        name: impl Into<String>,
+        data_type: DataType,
+        first_last_type: FirstLastType,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            data_type,
+            first_last_type,
+            expr,
+            ignore_nulls: false,
+            order_by: vec![],
+            order_by_types: vec![],
+        }
+    }
+
+    /// Skip the rows whose value is NULL (`IGNORE NULLS`)
+    pub fn with_ignore_nulls(mut self, ignore_nulls: bool) -> Self {
+        self.ignore_nulls = ignore_nulls;
+        self
+    }
+
+    /// Order the rows by the given expressions rather than in the order they are
+    /// aggregated (`FIRST(expr ORDER BY ...)`)
+    pub fn with_order_by(
+        mut self,
+        order_by: Vec<PhysicalSortExpr>,
+        input_schema: &Schema,
+    ) -> Result<Self> {
+        self.order_by_types = order_by
+            .iter()
+            .map(|e| e.expr.data_type(input_schema))
+            .collect::<Result<_>>()?;
+        self.order_by = order_by;
+        Ok(self)
+    }
+
+    fn state_name(&self, field: &str) -> String {
+        let prefix = match self.first_last_type {
+            FirstLastType::First => "first",
+            FirstLastType::Last => "last",
+        };
+        format_state_name(&self.name, &format!("{}{}", prefix, field))
+    }
+}
+
+impl AggregateExpr for FirstLast {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        let mut fields = vec![Field::new(
+            &self.state_name(""),
+            self.data_type.clone(),
+            true,
+        )];
+        for (i, data_type) in self.order_by_types.iter().enumerate() {
+            fields.push(Field::new(
+                &self.state_name(&format!("_key[{}]", i)),
+                data_type.clone(),
+                true,
+            ));
+        }
+        // the value and the keys of the row may all be NULL
+        fields.push(Field::new(
+            &self.state_name("_is_set"),
+            DataType::Boolean,
+            true,
+        ));
+        Ok(fields)
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        let mut exprs = vec![self.expr.clone()];
+        exprs.extend(self.order_by.iter().map(|e| e.expr.clone()));
+        exprs
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(FirstLastAccumulator {
+            first_last_type: self.first_last_type,
+            row: None,
+            data_type: self.data_type.clone(),
+            key_types: self.order_by_types.clone(),
+            sort_options: self.order_by.iter().map(|e| e.options).collect(),
+            ignore_nulls: self.ignore_nulls,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug)]
+struct FirstLastAccumulator {
+    first_last_type: FirstLastType,
+    /// The value of the row followed by its keys, None until a row is aggregated
+    row: Option<Vec<ScalarValue>>,
+    data_type: DataType,
+    key_types: Vec<DataType>,
+    sort_options: Vec<SortOptions>,
+    ignore_nulls: bool,
+}
+
+/// Compares the keys of rows of two batches, in the order of `sort_options`
+struct KeyComparator<'a> {
+    left: &'a [ArrayRef],
+    right: &'a [ArrayRef],
+    comparators: Vec<DynComparator>,
+    sort_options: &'a [SortOptions],
+}
+
+impl<'a> KeyComparator<'a> {
+    fn try_new(
+        left: &'a [ArrayRef],
+        right: &'a [ArrayRef],
+        sort_options: &'a [SortOptions],
+    ) -> Result<Self> {
+        let comparators = left
+            .iter()
+            .zip(right)
+            .map(|(l, r)| build_compare(l.as_ref(), r.as_ref()))
+            .collect::<arrow::error::Result<_>>()?;
+        Ok(Self {
+            left,
+            right,
+            comparators,
+            sort_options,
+        })
+    }
+
+    fn compare(&self, left_row: usize, right_row: usize) -> Ordering {
+        let keys = self.left.iter().zip(self.right);
+        for (i, (l, r)) in keys.enumerate() {
+            let options = &self.sort_options[i];
+            match (l.is_valid(left_row), r.is_valid(right_row)) {
+                (false, true) if options.nulls_first => return Ordering::Less,
+                (false, true) => return Ordering::Greater,
+                (true, false) if options.nulls_first => return Ordering::Greater,
+                (true, false) => return Ordering::Less,
+                (false, false) => {}
+                (true, true) => match self.comparators[i](left_row, right_row) {
+                    Ordering::Equal => {}
+                    o if options.descending => return o.reverse(),
+                    o => return o,
+                },
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl FirstLastAccumulator {
+    /// Whether a row whose keys compare to the ones of the kept row as `ordering`
+    /// replaces it. Among the rows with equal keys, FIRST keeps the first aggregated
+    /// and LAST the last one.
+    fn replaces(&self, ordering: Ordering) -> bool {
+        match self.first_last_type {
+            FirstLastType::First => ordering == Ordering::Less,
+            FirstLastType::Last => ordering != Ordering::Less,
+        }
+    }
+
+    /// Keeps the first or the last of the rows of `values`, the arrays of the value
+    /// followed by the ones of the keys, and of the kept row
+    fn update_arrays(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let array = &values[0];
+        let mut rows =
+            (0..array.len()).filter(|i| !self.ignore_nulls || array.is_valid(*i));
+        let first = match rows.next() {
+            Some(first) => first,
+            None => return Ok(()),
+        };
+        let keys = &values[1..];
+        let comparator = KeyComparator::try_new(keys, keys, &self.sort_options)?;
+        let mut index = first;
+        for row in rows {
+            if self.replaces(comparator.compare(row, index)) {
+                index = row;
+            }
+        }
+
+        if let Some(row) = &self.row {
+            let kept = row[1..]
+                .iter()
+                .map(|key| key.to_array_of_size(1))
+                .collect::<Vec<_>>();
+            let comparator = KeyComparator::try_new(keys, &kept, &self.sort_options)?;
+            if !self.replaces(comparator.compare(index, 0)) {
+                return Ok(());
+            }
+        }
+        self.row = Some(
+            values
+                .iter()
+                .map(|array| ScalarValue::try_from_array(array, index))
+                .collect::<Result<_>>()?,
+        );
+        Ok(())
+    }
+}
+
+impl Accumulator for FirstLastAccumulator {
+    fn reset(&mut self) {
+        self.row = None;
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        self.update_arrays(values)
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        let arrays = values
+            .iter()
+            .map(|v| v.to_array_of_size(1))
+            .collect::<Vec<_>>();
+        self.update_arrays(&arrays)
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        match states.split_last() {
+            Some((ScalarValue::Boolean(Some(true)), row)) => self.update(row),
+            // partial aggregate without any input rows
+            Some((ScalarValue::Boolean(_), _)) => Ok(()),
+            _ => Err(DataFusionError::Internal(format!(
+                "Unexpected state {:?} of FIRST or LAST",
+                states
+            ))),
+        }
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        let mut state = SmallVec::new();
+        match &self.row {
+            Some(row) => {
+                state.extend(row.iter().cloned());
+                state.push(ScalarValue::Boolean(Some(true)));
+            }
+            None => {
+                state.push(ScalarValue::try_from(&self.data_type)?);
+                for data_type in &self.key_types {
+                    state.push(ScalarValue::try_from(data_type)?);
+                }
+                state.push(ScalarValue::Boolean(Some(false)));
+            }
+        }
+        Ok(state)
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        match &self.row {
+            Some(row) => Ok(row[0].clone()),
+            None => ScalarValue::try_from(&self.data_type),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+
+    fn first_last(
+        first_last_type: FirstLastType,
+        ignore_nulls: bool,
+        order_by: Vec<(&str, SortOptions)>,
+    ) -> Result<ScalarValue> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", DataType::Int32, true),
+        ]);
+        let a: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("x"),
+            None,
+            Some("y"),
+            Some("z"),
+        ]));
+        let b: ArrayRef =
+            Arc::new(Int32Array::from(vec![Some(2), Some(3), None, Some(1)]));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![a, b])?;
+        let order_by = order_by
+            .into_iter()
+            .map(|(name, options)| {
+                Ok(PhysicalSortExpr {
+                    expr: col(name, &schema)?,
+                    options,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let agg = Arc::new(
+            FirstLast::new(col("a", &schema)?, "bla", DataType::Utf8, first_last_type)
+                .with_ignore_nulls(ignore_nulls)
+                .with_order_by(order_by, &schema)?,
+        );
+        aggregate(&batch, agg)
+    }
+
+    #[test]
+    fn first_last_in_input_order() -> Result<()> {
+        let first = first_last(FirstLastType::First, false, vec![])?;
+        assert_eq!(first, ScalarValue::from("x"));
+        let last = first_last(FirstLastType::Last, false, vec![])?;
+        assert_eq!(last, ScalarValue::from("z"));
+        Ok(())
+    }
+
+    #[test]
+    fn first_last_order_by() -> Result<()> {
+        let asc = SortOptions {
+            descending: false,
+            nulls_first: false,
+        };
+        let first = first_last(FirstLastType::First, false, vec![("b", asc)])?;
+        assert_eq!(first, ScalarValue::from("z"));
+        // the row of the NULL key is the last one
+        let last = first_last(FirstLastType::Last, false, vec![("b", asc)])?;
+        assert_eq!(last, ScalarValue::from("y"));
+
+        let desc = SortOptions {
+            descending: true,
+            nulls_first: false,
+        };
+        let first = first_last(FirstLastType::First, false, vec![("b", desc)])?;
+        assert_eq!(first, ScalarValue::Utf8(None));
+        let first = first_last(FirstLastType::First, true, vec![("b", desc)])?;
+        assert_eq!(first, ScalarValue::from("x"));
+        Ok(())
+    }
+
+    #[test]
+    fn first_last_merge() -> Result<()> {
+        let new_accumulator = |first_last_type| FirstLastAccumulator {
+            first_last_type,
+            row: None,
+            data_type: DataType::Utf8,
+            key_types: vec![DataType::Int32],
+            sort_options: vec![SortOptions::default()],
+            ignore_nulls: false,
+        };
+        for (first_last_type, expected) in
+            [(FirstLastType::First, "a"), (FirstLastType::Last, "c")]
+        {
+            let mut partial = new_accumulator(first_last_type);
+            partial.update(&[ScalarValue::from("b"), ScalarValue::Int32(Some(2))])?;
+            partial.update(&[ScalarValue::from("a"), ScalarValue::Int32(Some(1))])?;
+
+            let mut acc = new_accumulator(first_last_type);
+            assert_eq!(acc.evaluate()?, ScalarValue::Utf8(None));
+            acc.merge(&new_accumulator(first_last_type).state()?)?;
+            acc.update(&[ScalarValue::from("c"), ScalarValue::Int32(Some(3))])?;
+            acc.merge(&partial.state()?)?;
+            assert_eq!(acc.evaluate()?, ScalarValue::from(expected));
+        }
+        Ok(())
+    }
+}
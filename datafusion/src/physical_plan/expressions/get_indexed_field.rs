@@ -0,0 +1,534 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! get field of a list or a struct
+
+use std::{any::Any, sync::Arc};
+
+use arrow::array::{
+    new_null_array, Array, ArrayData, ArrayRef, BooleanArray, Int64Array, ListArray,
+    StructArray, UInt32Array,
+};
+use arrow::buffer::Buffer;
+use arrow::compute;
+use arrow::{
+    datatypes::{DataType, Schema, ToByteSlice},
+    record_batch::RecordBatch,
+};
+
+use crate::error::{DataFusionError, Result};
+use crate::field_util;
+use crate::physical_plan::array_expressions::list_values_and_offsets;
+use crate::physical_plan::{ColumnarValue, PhysicalExpr};
+
+/// The elements of a list or the field of a struct selected by [GetIndexedFieldExpr]
+#[derive(Debug)]
+pub enum GetFieldAccessExpr {
+    /// The element at an index, `arg[key]`
+    ListIndex {
+        /// The index of the element
+        key: Arc<dyn PhysicalExpr>,
+    },
+    /// The list of the elements between two indexes, both included,
+    /// `arg[start:stop]`
+    ListRange {
+        /// The index of the first element
+        start: Arc<dyn PhysicalExpr>,
+        /// The index of the last element
+        stop: Arc<dyn PhysicalExpr>,
+    },
+    /// The field of a struct, `arg.name`
+    NamedStructField {
+        /// The name of the field
+        name: String,
+    },
+}
+
+/// Expression to get an element or a slice of a list, `arg[key]` or
+/// `arg[start:stop]`, or a field of a struct, `arg.name`. The indexes are 1-based
+/// and evaluated for every row, negative indexes count from the end of the list. An
+/// element out of bounds is NULL, and the bounds of a slice are clamped to the list.
+#[derive(Debug)]
+pub struct GetIndexedFieldExpr {
+    arg: Arc<dyn PhysicalExpr>,
+    field: GetFieldAccessExpr,
+}
+
+impl GetIndexedFieldExpr {
+    /// Create new get field expression
+    pub fn new(arg: Arc<dyn PhysicalExpr>, field: GetFieldAccessExpr) -> Self {
+        Self { arg, field }
+    }
+
+    /// Get the input expression
+    pub fn arg(&self) -> &Arc<dyn PhysicalExpr> {
+        &self.arg
+    }
+
+    /// Get the elements to select
+    pub fn field(&self) -> &GetFieldAccessExpr {
+        &self.field
+    }
+}
+
+impl std::fmt::Display for GetIndexedFieldExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.field {
+            GetFieldAccessExpr::ListIndex { key } => write!(f, "({})[{}]", self.arg, key),
+            GetFieldAccessExpr::ListRange { start, stop } => {
+                write!(f, "({})[{}:{}]", self.arg, start, stop)
+            }
+            GetFieldAccessExpr::NamedStructField { name } => {
+                write!(f, "({}).{}", self.arg, name)
+            }
+        }
+    }
+}
+
+impl PhysicalExpr for GetIndexedFieldExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        let data_type = self.arg.data_type(input_schema)?;
+        let field = match &self.field {
+            GetFieldAccessExpr::ListIndex { key } => {
+                field_util::get_indexed_field(&data_type, &key.data_type(input_schema)?)?
+            }
+            GetFieldAccessExpr::ListRange { start, stop } => {
+                field_util::get_list_range_field(
+                    &data_type,
+                    &start.data_type(input_schema)?,
+                    &stop.data_type(input_schema)?,
+                )?
+            }
+            GetFieldAccessExpr::NamedStructField { name } => {
+                field_util::get_struct_field(&data_type, name)?
+            }
+        };
+        Ok(field.data_type().clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        let keys = match &self.field {
+            GetFieldAccessExpr::ListIndex { key } => vec![key],
+            GetFieldAccessExpr::ListRange { start, stop } => vec![start, stop],
+            GetFieldAccessExpr::NamedStructField { .. } => vec![],
+        };
+        crate::physical_plan::column_indices(keys.into_iter().chain(vec![&self.arg]))
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let arg = self.arg.evaluate(batch)?.into_array(batch.num_rows());
+        let indexes = |e: &Arc<dyn PhysicalExpr>| -> Result<ArrayRef> {
+            let indexes = e.evaluate(batch)?.into_array(batch.num_rows());
+            Ok(match indexes.data_type() {
+                DataType::Null => new_null_array(&DataType::Int64, indexes.len()),
+                _ => compute::cast(&indexes, &DataType::Int64)?,
+            })
+        };
+        let result = match &self.field {
+            GetFieldAccessExpr::ListIndex { key } => {
+                let lists = &arg;
+                let (values, offsets) = list_values_and_offsets(lists)?;
+                let keys = indexes(key)?;
+                let keys = downcast_indexes(&keys);
+                let indices = (0..lists.len())
+                    .map(|i| {
+                        if lists.is_null(i) || keys.is_null(i) {
+                            return None;
+                        }
+                        let (start, len) = (offsets[i], offsets[i + 1] - offsets[i]);
+                        element_position(len, keys.value(i))
+                            .map(|position| (start + position) as u32)
+                    })
+                    .collect::<UInt32Array>();
+                compute::take(values.as_ref(), &indices, None)?
+            }
+            GetFieldAccessExpr::ListRange { start, stop } => {
+                let lists = &arg;
+                let (values, offsets) = list_values_and_offsets(lists)?;
+                let starts = indexes(start)?;
+                let starts = downcast_indexes(&starts);
+                let stops = indexes(stop)?;
+                let stops = downcast_indexes(&stops);
+                let mut indices = vec![];
+                let mut result_offsets = vec![0_i32];
+                let mut valid = vec![];
+                for i in 0..lists.len() {
+                    let is_valid =
+                        lists.is_valid(i) && starts.is_valid(i) && stops.is_valid(i);
+                    if is_valid {
+                        let len = offsets[i + 1] - offsets[i];
+                        let range = slice_positions(len, starts.value(i), stops.value(i));
+                        indices
+                            .extend(range.map(|position| (offsets[i] + position) as u32));
+                    }
+                    result_offsets.push(indices.len() as i32);
+                    valid.push(is_valid);
+                }
+                let item = field_util::get_list_range_field(
+                    lists.data_type(),
+                    &DataType::Int64,
+                    &DataType::Int64,
+                )?;
+                let values = take_values(&values, &indices)?;
+                let nulls = BooleanArray::from(valid);
+                let data = ArrayData::builder(item.data_type().clone())
+                    .len(lists.len())
+                    .add_buffer(Buffer::from(result_offsets.to_byte_slice()))
+                    .add_child_data(values.data().clone())
+                    .null_bit_buffer(nulls.values().clone())
+                    .build();
+                Arc::new(ListArray::from(data))
+            }
+            GetFieldAccessExpr::NamedStructField { name } => struct_field(&arg, name)?,
+        };
+        Ok(ColumnarValue::Array(result))
+    }
+}
+
+fn downcast_indexes(array: &ArrayRef) -> &Int64Array {
+    array.as_any().downcast_ref::<Int64Array>().unwrap()
+}
+
+/// The 0-based position in a list of `len` elements of the element at the 1-based
+/// index `key`, counted from the end when it is negative, None when it is out of
+/// bounds
+fn element_position(len: i32, key: i64) -> Option<i32> {
+    let len = len as i64;
+    let position = if key < 0 { len + key } else { key - 1 };
+    (0 <= position && position < len).then(|| position as i32)
+}
+
+/// The 0-based positions in a list of `len` elements of the elements between the
+/// 1-based indexes `start` and `stop`, clamped to the list
+fn slice_positions(len: i32, start: i64, stop: i64) -> std::ops::Range<i32> {
+    let len = len as i64;
+    let position = |index: i64| if index < 0 { len + index } else { index - 1 };
+    let start = position(start).max(0);
+    let end = (position(stop) + 1).min(len);
+    if start < end {
+        start as i32..end as i32
+    } else {
+        0..0
+    }
+}
+
+/// The elements of `values` at `indices`, sharing the values rather than copying
+/// them when the indices are consecutive, as in slices of consecutive rows that
+/// reach the ends of their lists
+fn take_values(values: &ArrayRef, indices: &[u32]) -> Result<ArrayRef> {
+    let consecutive = indices.windows(2).all(|w| w[1] == w[0] + 1);
+    if consecutive {
+        let offset = indices.first().copied().unwrap_or(0) as usize;
+        return Ok(values.slice(offset, indices.len()));
+    }
+    Ok(compute::take(
+        values.as_ref(),
+        &UInt32Array::from(indices.to_vec()),
+        None,
+    )?)
+}
+
+/// The field `name` of the structs of `array`, NULL where the struct is NULL
+pub(crate) fn struct_field(array: &ArrayRef, name: &str) -> Result<ArrayRef> {
+    let structs = array
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "Expected a struct to get field {}, got {:?}",
+                name,
+                array.data_type()
+            ))
+        })?;
+    let field = structs.column_by_name(name).ok_or_else(|| {
+        DataFusionError::Internal(format!("Field {} not found in struct", name))
+    })?;
+    if structs.null_count() == 0 {
+        return Ok(field.clone());
+    }
+    // the values of the fields of a NULL struct are not necessarily NULL
+    let indices = (0..structs.len())
+        .map(|i| structs.is_valid(i).then(|| i as u32))
+        .collect::<UInt32Array>();
+    Ok(compute::take(field.as_ref(), &indices, None)?)
+}
+
+/// Create a get field expression for the element at `key`, checking that the key
+/// can index `arg`
+pub fn get_indexed_field(
+    arg: Arc<dyn PhysicalExpr>,
+    key: Arc<dyn PhysicalExpr>,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let expr = GetIndexedFieldExpr::new(arg, GetFieldAccessExpr::ListIndex { key });
+    expr.data_type(input_schema)?;
+    Ok(Arc::new(expr))
+}
+
+/// Create a get field expression for the slice `start:stop`, checking that the
+/// bounds can index `arg`
+pub fn get_list_range(
+    arg: Arc<dyn PhysicalExpr>,
+    start: Arc<dyn PhysicalExpr>,
+    stop: Arc<dyn PhysicalExpr>,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let expr =
+        GetIndexedFieldExpr::new(arg, GetFieldAccessExpr::ListRange { start, stop });
+    expr.data_type(input_schema)?;
+    Ok(Arc::new(expr))
+}
+
+/// Create a get field expression for the field `name`, checking that `arg` is a
+/// struct with such a field
+pub fn get_struct_field(
+    arg: Arc<dyn PhysicalExpr>,
+    name: impl Into<String>,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let expr = GetIndexedFieldExpr::new(
+        arg,
+        GetFieldAccessExpr::NamedStructField { name: name.into() },
+    );
+    expr.data_type(input_schema)?;
+    Ok(Arc::new(expr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{col, lit};
+    use crate::scalar::ScalarValue;
+    use arrow::array::{FixedSizeListArray, Int32Array, StringArray};
+    use arrow::datatypes::{Field, Int64Type};
+
+    fn lists() -> ArrayRef {
+        Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(vec![Some(1), Some(2), Some(3)]),
+            None,
+            Some(vec![]),
+            Some(vec![Some(4), None]),
+        ]))
+    }
+
+    /// Evaluates `expr` on `lists()` as `l` and `[3, 1, 1, NULL]` as `k`
+    fn evaluate(
+        expr: impl Fn(&Schema) -> Result<Arc<dyn PhysicalExpr>>,
+    ) -> Result<ArrayRef> {
+        let lists = lists();
+        let schema = Schema::new(vec![
+            Field::new("l", lists.data_type().clone(), true),
+            Field::new("k", DataType::Int32, true),
+        ]);
+        let keys = Arc::new(Int32Array::from(vec![Some(3), Some(1), Some(1), None]));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![lists, keys])?;
+        Ok(expr(&schema)?
+            .evaluate(&batch)?
+            .into_array(batch.num_rows()))
+    }
+
+    fn get_indexed_values(key: Option<i64>) -> Result<Vec<Option<i64>>> {
+        let key = match key {
+            Some(key) => lit(ScalarValue::Int64(Some(key))),
+            None => lit(ScalarValue::Null),
+        };
+        let array =
+            evaluate(|schema| get_indexed_field(col("l", schema)?, key.clone(), schema))?;
+        Ok(to_vec(&array))
+    }
+
+    fn get_list_range_values(
+        start: i64,
+        stop: i64,
+    ) -> Result<Vec<Option<Vec<Option<i64>>>>> {
+        let array = evaluate(|schema| {
+            get_list_range(
+                col("l", schema)?,
+                lit(ScalarValue::Int64(Some(start))),
+                lit(ScalarValue::Int64(Some(stop))),
+                schema,
+            )
+        })?;
+        let lists = array.as_any().downcast_ref::<ListArray>().unwrap();
+        Ok((0..lists.len())
+            .map(|i| lists.is_valid(i).then(|| to_vec(&lists.value(i))))
+            .collect())
+    }
+
+    fn to_vec(array: &ArrayRef) -> Vec<Option<i64>> {
+        array
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .iter()
+            .collect()
+    }
+
+    #[test]
+    fn get_indexed_field_by_constant() -> Result<()> {
+        assert_eq!(
+            get_indexed_values(Some(1))?,
+            vec![Some(1), None, None, Some(4)]
+        );
+        assert_eq!(
+            get_indexed_values(Some(3))?,
+            vec![Some(3), None, None, None]
+        );
+        // from the end
+        assert_eq!(
+            get_indexed_values(Some(-1))?,
+            vec![Some(3), None, None, None]
+        );
+        assert_eq!(
+            get_indexed_values(Some(-2))?,
+            vec![Some(2), None, None, Some(4)]
+        );
+        // out of bounds
+        for key in &[0, 4, -4] {
+            assert_eq!(get_indexed_values(Some(*key))?, vec![None; 4]);
+        }
+        assert_eq!(get_indexed_values(None)?, vec![None; 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn get_indexed_field_by_column() -> Result<()> {
+        let array = evaluate(|schema| {
+            get_indexed_field(col("l", schema)?, col("k", schema)?, schema)
+        })?;
+        assert_eq!(to_vec(&array), vec![Some(3), None, None, None]);
+
+        let values = StringArray::from(vec!["a", "b", "c", "d", "e", "f"]);
+        let lists: ArrayRef = Arc::new(FixedSizeListArray::from(
+            ArrayData::builder(DataType::FixedSizeList(
+                Box::new(Field::new("item", DataType::Utf8, true)),
+                2,
+            ))
+            .len(3)
+            .add_child_data(values.data().clone())
+            .build(),
+        ));
+        let schema = Schema::new(vec![
+            Field::new("l", lists.data_type().clone(), true),
+            Field::new("k", DataType::Int64, false),
+        ]);
+        let keys = Arc::new(Int64Array::from(vec![2, 1, 3]));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![lists, keys])?;
+        let expr = get_indexed_field(col("l", &schema)?, col("k", &schema)?, &schema)?;
+        assert_eq!(expr.data_type(&schema)?, DataType::Utf8);
+        let array = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(
+            array.iter().collect::<Vec<_>>(),
+            vec![Some("b"), Some("c"), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn get_list_range_by_constant() -> Result<()> {
+        assert_eq!(
+            get_list_range_values(2, 3)?,
+            vec![
+                Some(vec![Some(2), Some(3)]),
+                None,
+                Some(vec![]),
+                Some(vec![None])
+            ]
+        );
+        // clamped to the lists, shares the values of the lists
+        assert_eq!(
+            get_list_range_values(0, 5)?,
+            vec![
+                Some(vec![Some(1), Some(2), Some(3)]),
+                None,
+                Some(vec![]),
+                Some(vec![Some(4), None])
+            ]
+        );
+        // from the end
+        assert_eq!(
+            get_list_range_values(-2, -1)?,
+            vec![
+                Some(vec![Some(2), Some(3)]),
+                None,
+                Some(vec![]),
+                Some(vec![Some(4), None])
+            ]
+        );
+        assert_eq!(
+            get_list_range_values(3, 2)?,
+            vec![Some(vec![]), None, Some(vec![]), Some(vec![])]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn take_consecutive_values() -> Result<()> {
+        let values: ArrayRef = Arc::new(Int64Array::from(vec![1, 2, 3, 4]));
+        let taken = take_values(&values, &[1, 2, 3])?;
+        assert_eq!(taken.offset(), 1);
+        assert_eq!(to_vec(&taken), vec![Some(2), Some(3), Some(4)]);
+        let taken = take_values(&values, &[3, 0])?;
+        assert_eq!(taken.offset(), 0);
+        assert_eq!(to_vec(&taken), vec![Some(4), Some(1)]);
+        Ok(())
+    }
+
+    #[test]
+    fn get_struct_field_of_null_structs() -> Result<()> {
+        let values = Int64Array::from(vec![Some(1), Some(2), None]);
+        let fields = vec![Field::new("a", DataType::Int64, true)];
+        let structs: ArrayRef = Arc::new(StructArray::from(
+            ArrayData::builder(DataType::Struct(fields))
+                .len(3)
+                .add_child_data(values.data().clone())
+                .null_bit_buffer(
+                    BooleanArray::from(vec![true, false, true]).values().clone(),
+                )
+                .build(),
+        ));
+        let schema =
+            Schema::new(vec![Field::new("s", structs.data_type().clone(), true)]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![structs])?;
+        let expr = get_struct_field(col("s", &schema)?, "a", &schema)?;
+        assert_eq!(expr.to_string(), "(s@0).a");
+        let array = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        assert_eq!(to_vec(&array), vec![Some(1), None, None]);
+        assert!(get_struct_field(col("s", &schema)?, "b", &schema).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn get_indexed_field_invalid_key() -> Result<()> {
+        let schema =
+            Schema::new(vec![Field::new("l", lists().data_type().clone(), true)]);
+        let key = lit(ScalarValue::Utf8(Some("a".to_string())));
+        assert!(get_indexed_field(col("l", &schema)?, key.clone(), &schema).is_err());
+        let schema = Schema::new(vec![Field::new("s", DataType::Utf8, true)]);
+        assert!(get_indexed_field(col("s", &schema)?, key, &schema).is_err());
+        Ok(())
+    }
+}
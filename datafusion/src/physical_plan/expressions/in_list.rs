@@ -59,7 +59,7 @@ macro_rules! make_contains {
                         contains_null = true;
                         None
                     }
-                    ScalarValue::Utf8(None) => {
+                    ScalarValue::Null | ScalarValue::Utf8(None) => {
                         contains_null = true;
                         None
                     }
@@ -112,7 +112,7 @@ macro_rules! make_contains {
                         contains_null = true;
                         None
                     }
-                    ScalarValue::Utf8(None) => {
+                    ScalarValue::Null | ScalarValue::Utf8(None) => {
                         contains_null = true;
                         None
                     }
@@ -165,7 +165,7 @@ macro_rules! make_contains {
                         contains_null = true;
                         None
                     }
-                    ScalarValue::Utf8(None) => {
+                    ScalarValue::Null | ScalarValue::Utf8(None) => {
                         contains_null = true;
                         None
                     }
@@ -255,7 +255,7 @@ impl InListExpr {
             .flat_map(|expr| match expr {
                 ColumnarValue::Scalar(s) => match s {
                     ScalarValue::Utf8(Some(v)) => Some(v.as_str()),
-                    ScalarValue::Utf8(None) => {
+                    ScalarValue::Null | ScalarValue::Utf8(None) => {
                         contains_null = true;
                         None
                     }
@@ -326,6 +326,10 @@ impl PhysicalExpr for InListExpr {
         self.expr.nullable(input_schema)
     }
 
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        crate::physical_plan::column_indices(self.list.iter().chain(vec![&self.expr]))
+    }
+
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let value = self.expr.evaluate(batch)?;
         let value_data_type = value.data_type();
@@ -65,6 +65,10 @@ impl PhysicalExpr for IsNotNullExpr {
         Ok(false)
     }
 
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        self.arg.column_indices()
+    }
+
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let arg = self.arg.evaluate(batch)?;
         match arg {
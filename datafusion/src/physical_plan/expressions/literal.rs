@@ -66,6 +66,10 @@ impl PhysicalExpr for Literal {
         Ok(true)
     }
 
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        Some(vec![])
+    }
+
     fn evaluate(&self, _batch: &RecordBatch) -> Result<ColumnarValue> {
         Ok(ColumnarValue::Scalar(self.value.clone()))
     }
@@ -25,6 +25,10 @@ use crate::physical_plan::PhysicalExpr;
 use arrow::compute::kernels::sort::{SortColumn, SortOptions};
 use arrow::record_batch::RecordBatch;
 
+mod aggregate_filter;
+mod any_value;
+mod approx_percentile;
+mod array_agg;
 mod average;
 #[macro_use]
 mod binary;
@@ -32,7 +36,10 @@ mod case;
 mod cast;
 mod coercion;
 mod column;
+mod compensated_sum;
 mod count;
+mod first_last;
+mod get_indexed_field;
 mod in_list;
 mod is_not_null;
 mod is_null;
@@ -43,19 +50,41 @@ mod negative;
 mod not;
 mod nth_value;
 mod nullif;
+mod percentile;
 mod rank;
+mod regr;
 mod row_number;
+mod sorted_percentile;
+mod string_agg;
 mod sum;
+mod temporal_sum;
 mod try_cast;
 
+pub use aggregate_filter::AggregateFilter;
+pub use any_value::AnyValue;
+pub use approx_percentile::{ApproxPercentile, TDigest};
+pub use array_agg::ArrayAgg;
 pub use average::{avg_return_type, Avg, AvgAccumulator};
-pub use binary::{binary, binary_operator_data_type, BinaryExpr};
+pub use binary::{
+    binary, binary_operator_data_type, checked_binary, promoted_type, ArithmeticOverflow,
+    BinaryExpr,
+};
 pub use case::{case, CaseExpr};
 pub use cast::{
-    cast, cast_column, cast_with_options, CastExpr, DEFAULT_DATAFUSION_CAST_OPTIONS,
+    cast, cast_array, cast_array_in_zone, cast_column, cast_column_in_zone, cast_in_zone,
+    cast_with_options, CastExpr, DEFAULT_DATAFUSION_CAST_OPTIONS,
+    MAX_CAST_ERROR_EXAMPLES,
 };
+pub use coercion::timestamp_coercion;
+pub use coercion::union_coercion;
 pub use column::{col, Column};
 pub use count::Count;
+pub use first_last::{FirstLast, FirstLastType};
+pub(crate) use get_indexed_field::struct_field;
+pub use get_indexed_field::{
+    get_indexed_field, get_list_range, get_struct_field, GetFieldAccessExpr,
+    GetIndexedFieldExpr,
+};
 pub use in_list::{in_list, InListExpr};
 pub use is_not_null::{is_not_null, IsNotNullExpr};
 pub use is_null::{is_null, IsNullExpr};
@@ -66,10 +95,15 @@ pub use negative::{negative, NegativeExpr};
 pub use not::{not, NotExpr};
 pub use nth_value::NthValue;
 pub use nullif::{nullif_func, SUPPORTED_NULLIF_TYPES};
+pub use percentile::{Percentile, PercentileType};
 pub use rank::{dense_rank, rank};
+pub use regr::{regr_return_type, Regr, RegrType};
 pub use row_number::RowNumber;
+pub use sorted_percentile::SortedPercentile;
+pub use string_agg::StringAgg;
 pub use sum::{sum_return_type, Sum};
-pub use try_cast::{try_cast, TryCastExpr};
+pub use temporal_sum::TemporalSum;
+pub use try_cast::{try_cast, try_cast_in_zone, TryCastExpr};
 
 /// returns the name of the state
 pub fn format_state_name(name: &str, state_name: &str) -> String {
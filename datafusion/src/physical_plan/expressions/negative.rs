@@ -85,6 +85,10 @@ impl PhysicalExpr for NegativeExpr {
         self.arg.nullable(input_schema)
     }
 
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        self.arg.column_indices()
+    }
+
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let arg = self.arg.evaluate(batch)?;
         match arg {
@@ -21,7 +21,7 @@ use std::any::Any;
 use std::fmt;
 use std::sync::Arc;
 
-use super::ColumnarValue;
+use super::{try_cast, ColumnarValue};
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::PhysicalExpr;
 use crate::scalar::ScalarValue;
@@ -68,6 +68,10 @@ impl PhysicalExpr for NotExpr {
         self.arg.nullable(input_schema)
     }
 
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        self.arg.column_indices()
+    }
+
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let arg = self.arg.evaluate(batch)?;
         match arg {
@@ -109,7 +113,10 @@ pub fn not(
     input_schema: &Schema,
 ) -> Result<Arc<dyn PhysicalExpr>> {
     let data_type = arg.data_type(input_schema)?;
-    if data_type != DataType::Boolean {
+    if data_type == DataType::Null {
+        let arg = try_cast(arg, input_schema, DataType::Boolean)?;
+        Ok(Arc::new(NotExpr::new(arg)))
+    } else if data_type != DataType::Boolean {
         Err(DataFusionError::Internal(format!(
             "NOT '{:?}' can't be evaluated because the expression's type is {:?}, not boolean",
             arg, data_type,
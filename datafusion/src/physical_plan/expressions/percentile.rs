@@ -0,0 +1,343 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines physical expressions that can evaluated at runtime during query execution
+
+use std::any::Any;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::{ArrayRef, Float64Array};
+use arrow::compute::{lexsort_to_indices, SortColumn, SortOptions};
+use arrow::datatypes::{DataType, Field};
+
+use super::{format_state_name, PhysicalSortExpr};
+use smallvec::{smallvec, SmallVec};
+
+/// How a percentile is computed from the values around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PercentileType {
+    /// PERCENTILE_CONT, interpolates linearly between the two closest values
+    Continuous,
+    /// PERCENTILE_DISC, the first value whose position is at or after the percentile
+    Discrete,
+}
+
+/// PERCENTILE_CONT and PERCENTILE_DISC ordered-set aggregate expressions.
+/// Computes the exact percentile of the non-null values of the expression of
+/// `WITHIN GROUP (ORDER BY expr)`, in the order given by its sort options.
+#[derive(Debug)]
+pub struct Percentile {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    options: SortOptions,
+    fraction: f64,
+    percentile_type: PercentileType,
+    data_type: DataType,
+}
+
+impl Percentile {
+    /// Create a new PERCENTILE_CONT or PERCENTILE_DISC aggregate function of the
+    /// values of `order_by`. `fraction` is between 0 and 1 and `data_type` is the
+    /// type of the values, Float64 for PERCENTILE_CONT.
+    pub fn new(
+        order_by: PhysicalSortExpr,
+        fraction: f64,
+        percentile_type: PercentileType,
+        name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            expr: order_by.expr,
+            options: order_by.options,
+            fraction,
+            percentile_type,
+            data_type,
+        }
+    }
+
+    /// The fraction of the percentile, between 0 and 1
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+
+    /// Whether the percentile is continuous or discrete
+    pub fn percentile_type(&self) -> PercentileType {
+        self.percentile_type
+    }
+
+    /// The sort options of the values
+    pub fn options(&self) -> SortOptions {
+        self.options
+    }
+}
+
+impl AggregateExpr for Percentile {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![Field::new(
+            &format_state_name(&self.name, "percentile"),
+            DataType::List(Box::new(Field::new("item", self.data_type.clone(), true))),
+            true,
+        )])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(PercentileAccumulator {
+            values: vec![],
+            options: self.options,
+            fraction: self.fraction,
+            percentile_type: self.percentile_type,
+            data_type: self.data_type.clone(),
+        }))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Keeps all the non-null values to sort them when evaluated, percentiles are exact
+#[derive(Debug)]
+struct PercentileAccumulator {
+    values: Vec<ScalarValue>,
+    options: SortOptions,
+    fraction: f64,
+    percentile_type: PercentileType,
+    data_type: DataType,
+}
+
+impl Accumulator for PercentileAccumulator {
+    fn reset(&mut self) {
+        self.values.clear();
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let array = &values[0];
+        for index in 0..array.len() {
+            if array.is_valid(index) {
+                self.values.push(ScalarValue::try_from_array(array, index)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        if !values[0].is_null() {
+            self.values.push(values[0].clone());
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        match &states[0] {
+            ScalarValue::List(Some(values), _) => {
+                self.values
+                    .extend(values.iter().filter(|v| !v.is_null()).cloned());
+                Ok(())
+            }
+            // partial aggregate without any input rows
+            ScalarValue::List(None, _) => Ok(()),
+            state => Err(DataFusionError::Internal(format!(
+                "Unexpected accumulator state {:?}",
+                state
+            ))),
+        }
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        let values = if self.values.is_empty() {
+            None
+        } else {
+            Some(Box::new(self.values.clone()))
+        };
+        Ok(smallvec![ScalarValue::List(
+            values,
+            Box::new(self.data_type.clone())
+        )])
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        if self.values.is_empty() {
+            return ScalarValue::try_from(&self.data_type);
+        }
+        let values = ScalarValue::iter_to_array(self.values.iter().cloned())?;
+        let indices = lexsort_to_indices(
+            &[SortColumn {
+                values: values.clone(),
+                options: Some(self.options),
+            }],
+            None,
+        )?;
+        let sorted = |i: usize| indices.value(i) as usize;
+        let n = self.values.len();
+        match self.percentile_type {
+            PercentileType::Discrete => {
+                // the first value whose cumulative distribution reaches the fraction
+                let position = ((self.fraction * n as f64).ceil() as usize).max(1) - 1;
+                ScalarValue::try_from_array(&values, sorted(position.min(n - 1)))
+            }
+            PercentileType::Continuous => {
+                let values =
+                    values
+                        .as_any()
+                        .downcast_ref::<Float64Array>()
+                        .ok_or_else(|| {
+                            DataFusionError::Internal(format!(
+                                "PERCENTILE_CONT of {:?} values",
+                                values.data_type()
+                            ))
+                        })?;
+                let position = self.fraction * (n - 1) as f64;
+                let (lower, upper) = (position.floor(), position.ceil());
+                let lower_value = values.value(sorted(lower as usize));
+                let upper_value = values.value(sorted(upper as usize));
+                Ok(ScalarValue::Float64(Some(
+                    lower_value + (position - lower) * (upper_value - lower_value),
+                )))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::RecordBatch;
+
+    fn percentile(
+        array: ArrayRef,
+        fraction: f64,
+        percentile_type: PercentileType,
+        descending: bool,
+    ) -> Result<ScalarValue> {
+        let schema = Schema::new(vec![Field::new("a", array.data_type().clone(), true)]);
+        let data_type = match percentile_type {
+            PercentileType::Continuous => DataType::Float64,
+            PercentileType::Discrete => array.data_type().clone(),
+        };
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![array])?;
+        let order_by = PhysicalSortExpr {
+            expr: col("a", &schema)?,
+            options: SortOptions {
+                descending,
+                nulls_first: false,
+            },
+        };
+        let agg = Arc::new(Percentile::new(
+            order_by,
+            fraction,
+            percentile_type,
+            "bla",
+            data_type,
+        ));
+        aggregate(&batch, agg)
+    }
+
+    #[test]
+    fn percentile_cont() -> Result<()> {
+        let a: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(4.0),
+            None,
+            Some(1.0),
+            Some(3.0),
+            Some(2.0),
+        ]));
+        let cont = |fraction, descending| {
+            percentile(a.clone(), fraction, PercentileType::Continuous, descending)
+        };
+        assert_eq!(cont(0.5, false)?, ScalarValue::Float64(Some(2.5)));
+        assert_eq!(cont(0.25, false)?, ScalarValue::Float64(Some(1.75)));
+        assert_eq!(cont(0.25, true)?, ScalarValue::Float64(Some(3.25)));
+        assert_eq!(cont(0.0, false)?, ScalarValue::Float64(Some(1.0)));
+        assert_eq!(cont(1.0, false)?, ScalarValue::Float64(Some(4.0)));
+        Ok(())
+    }
+
+    #[test]
+    fn percentile_disc() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![
+            Some(4),
+            None,
+            Some(1),
+            Some(3),
+            Some(2),
+        ]));
+        let disc = |fraction, descending| {
+            percentile(a.clone(), fraction, PercentileType::Discrete, descending)
+        };
+        assert_eq!(disc(0.5, false)?, ScalarValue::Int32(Some(2)));
+        assert_eq!(disc(0.51, false)?, ScalarValue::Int32(Some(3)));
+        assert_eq!(disc(0.25, true)?, ScalarValue::Int32(Some(4)));
+        assert_eq!(disc(0.0, false)?, ScalarValue::Int32(Some(1)));
+        assert_eq!(disc(1.0, false)?, ScalarValue::Int32(Some(4)));
+        Ok(())
+    }
+
+    #[test]
+    fn percentile_of_nulls() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![None, None]));
+        assert_eq!(
+            percentile(a, 0.5, PercentileType::Discrete, false)?,
+            ScalarValue::Int32(None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn percentile_merge() -> Result<()> {
+        let new_accumulator = || PercentileAccumulator {
+            values: vec![],
+            options: SortOptions::default(),
+            fraction: 0.5,
+            percentile_type: PercentileType::Continuous,
+            data_type: DataType::Float64,
+        };
+        let mut partial = new_accumulator();
+        partial.update(&[ScalarValue::Float64(Some(3.0))])?;
+        partial.update(&[ScalarValue::Float64(None)])?;
+        partial.update(&[ScalarValue::Float64(Some(1.0))])?;
+
+        let mut acc = new_accumulator();
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(None));
+        acc.merge(&new_accumulator().state()?)?;
+        acc.update(&[ScalarValue::Float64(Some(10.0))])?;
+        acc.merge(&partial.state()?)?;
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(Some(3.0)));
+        Ok(())
+    }
+}
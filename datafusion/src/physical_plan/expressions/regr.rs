@@ -0,0 +1,568 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines physical expressions that can evaluated at runtime during query execution
+
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::{
+    Array, ArrayRef, Float64Array, Int64Decimal0Array, Int64Decimal10Array,
+    Int64Decimal1Array, Int64Decimal2Array, Int64Decimal3Array, Int64Decimal4Array,
+    Int64Decimal5Array, Int96Decimal0Array, Int96Decimal10Array, Int96Decimal1Array,
+    Int96Decimal2Array, Int96Decimal3Array, Int96Decimal4Array, Int96Decimal5Array,
+};
+use arrow::datatypes::{DataType, Field};
+
+use super::format_state_name;
+use smallvec::{smallvec, SmallVec};
+
+/// The statistic of the linear regression of `y` on `x`, or of the moments of `x` and
+/// `y`, computed by [Regr]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegrType {
+    /// REGR_SLOPE, the slope of the least-squares-fit line
+    Slope,
+    /// REGR_INTERCEPT, the y-intercept of the least-squares-fit line
+    Intercept,
+    /// REGR_COUNT, the number of rows where both `y` and `x` are not NULL
+    Count,
+    /// REGR_R2, the square of the correlation coefficient
+    R2,
+    /// REGR_AVGX, the average of `x`
+    AvgX,
+    /// REGR_AVGY, the average of `y`
+    AvgY,
+    /// REGR_SXX, the sum of squares of the deviations of `x`
+    SXX,
+    /// REGR_SYY, the sum of squares of the deviations of `y`
+    SYY,
+    /// REGR_SXY, the sum of products of the deviations of `x` and `y`
+    SXY,
+    /// VAR_POP, the population variance of `x`
+    VarPop,
+    /// VAR_SAMP, the sample variance of `x`
+    VarSamp,
+    /// STDDEV_POP, the population standard deviation of `x`
+    StddevPop,
+    /// STDDEV_SAMP, the sample standard deviation of `x`
+    StddevSamp,
+    /// COVAR_POP, the population covariance of `x` and `y`
+    CovarPop,
+    /// COVAR_SAMP, the sample covariance of `x` and `y`
+    CovarSamp,
+    /// CORR, the correlation coefficient of `x` and `y`
+    Corr,
+}
+
+/// function return type of a linear regression aggregate
+pub fn regr_return_type(regr_type: RegrType) -> DataType {
+    match regr_type {
+        RegrType::Count => DataType::UInt64,
+        _ => DataType::Float64,
+    }
+}
+
+/// REGR_* aggregate expressions, statistics of the linear regression of the
+/// dependent variable `y` on the independent variable `x`, and the COVAR_* and CORR
+/// ones of `y` and `x`. As in Postgres, the rows where `y` or `x` is NULL are ignored.
+/// The VAR_* and STDDEV_* aggregate expressions are the statistics of `x` alone.
+#[derive(Debug)]
+pub struct Regr {
+    name: String,
+    regr_type: RegrType,
+    /// None for the statistics of `x` alone
+    y: Option<Arc<dyn PhysicalExpr>>,
+    x: Arc<dyn PhysicalExpr>,
+}
+
+impl Regr {
+    /// Create a new REGR_*, COVAR_* or CORR aggregate function, `y` and `x` must be
+    /// Float64 or decimals
+    pub fn new(
+        y: Arc<dyn PhysicalExpr>,
+        x: Arc<dyn PhysicalExpr>,
+        name: impl Into<String>,
+        regr_type: RegrType,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            regr_type,
+            y: Some(y),
+            x,
+        }
+    }
+
+    /// Create a new VAR_* or STDDEV_* aggregate function of `x`, which must be Float64
+    /// or a decimal
+    pub fn new_univariate(
+        x: Arc<dyn PhysicalExpr>,
+        name: impl Into<String>,
+        regr_type: RegrType,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            regr_type,
+            y: None,
+            x,
+        }
+    }
+}
+
+impl AggregateExpr for Regr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(
+            &self.name,
+            regr_return_type(self.regr_type),
+            self.regr_type != RegrType::Count,
+        ))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        let field = |state: &str, data_type: DataType| {
+            Field::new(&format_state_name(&self.name, state), data_type, true)
+        };
+        Ok(vec![
+            field("count", DataType::UInt64),
+            field("mean_x", DataType::Float64),
+            field("mean_y", DataType::Float64),
+            field("m2_x", DataType::Float64),
+            field("m2_y", DataType::Float64),
+            field("c_xy", DataType::Float64),
+        ])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        self.y.iter().chain(Some(&self.x)).cloned().collect()
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(RegrAccumulator::new(self.regr_type)))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Accumulates the moments of `x` and `y` with Welford's online algorithm, which
+/// unlike sums of squares does not lose precision when the values are large compared
+/// to their deviations
+#[derive(Debug)]
+struct RegrAccumulator {
+    regr_type: RegrType,
+    count: u64,
+    mean_x: f64,
+    mean_y: f64,
+    /// Sum of squares of the deviations of `x`
+    m2_x: f64,
+    /// Sum of squares of the deviations of `y`
+    m2_y: f64,
+    /// Sum of products of the deviations of `x` and `y`
+    c_xy: f64,
+}
+
+impl RegrAccumulator {
+    fn new(regr_type: RegrType) -> Self {
+        Self {
+            regr_type,
+            count: 0,
+            mean_x: 0.0,
+            mean_y: 0.0,
+            m2_x: 0.0,
+            m2_y: 0.0,
+            c_xy: 0.0,
+        }
+    }
+
+    fn push(&mut self, y: f64, x: f64) {
+        self.count += 1;
+        let n = self.count as f64;
+        let delta_x = x - self.mean_x;
+        let delta_y = y - self.mean_y;
+        self.mean_x += delta_x / n;
+        self.mean_y += delta_y / n;
+        self.m2_x += delta_x * (x - self.mean_x);
+        self.m2_y += delta_y * (y - self.mean_y);
+        self.c_xy += delta_x * (y - self.mean_y);
+    }
+
+    /// Combines the moments with those of another set of rows
+    fn combine(&mut self, other: &RegrAccumulator) {
+        if other.count == 0 {
+            return;
+        }
+        let count = self.count + other.count;
+        let (n1, n2, n) = (self.count as f64, other.count as f64, count as f64);
+        let delta_x = other.mean_x - self.mean_x;
+        let delta_y = other.mean_y - self.mean_y;
+        self.mean_x += delta_x * n2 / n;
+        self.mean_y += delta_y * n2 / n;
+        self.m2_x += other.m2_x + delta_x * delta_x * n1 * n2 / n;
+        self.m2_y += other.m2_y + delta_y * delta_y * n1 * n2 / n;
+        self.c_xy += other.c_xy + delta_x * delta_y * n1 * n2 / n;
+        self.count = count;
+    }
+}
+
+/// The value of a decimal of `scale` whose unscaled value is `value`
+fn decimal_value(value: i128, scale: u8) -> f64 {
+    value as f64 / 10_f64.powi(scale as i32)
+}
+
+fn float_value(value: &ScalarValue) -> Result<Option<f64>> {
+    match value {
+        ScalarValue::Float64(value) => Ok(*value),
+        ScalarValue::Int64Decimal(value, scale) => {
+            Ok(value.map(|v| decimal_value(v as i128, *scale)))
+        }
+        ScalarValue::Int96Decimal(value, scale) => {
+            Ok(value.map(|v| decimal_value(v, *scale)))
+        }
+        value => Err(DataFusionError::Internal(format!(
+            "Unexpected REGR value {:?}",
+            value
+        ))),
+    }
+}
+
+macro_rules! decimal_values {
+    ($ARRAY:expr, $ARRAY_TYPE:ident, $SCALE:expr) => {{
+        let array = $ARRAY.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+        let values = array
+            .iter()
+            .map(|v| v.map(|v| decimal_value(v as i128, $SCALE)))
+            .collect::<Float64Array>();
+        Arc::new(values)
+    }};
+}
+
+/// The values of `array` as Float64, which are converted from decimals
+fn float_values(array: &ArrayRef) -> ArrayRef {
+    match array.data_type() {
+        DataType::Int64Decimal(0) => decimal_values!(array, Int64Decimal0Array, 0),
+        DataType::Int64Decimal(1) => decimal_values!(array, Int64Decimal1Array, 1),
+        DataType::Int64Decimal(2) => decimal_values!(array, Int64Decimal2Array, 2),
+        DataType::Int64Decimal(3) => decimal_values!(array, Int64Decimal3Array, 3),
+        DataType::Int64Decimal(4) => decimal_values!(array, Int64Decimal4Array, 4),
+        DataType::Int64Decimal(5) => decimal_values!(array, Int64Decimal5Array, 5),
+        DataType::Int64Decimal(10) => decimal_values!(array, Int64Decimal10Array, 10),
+        DataType::Int96Decimal(0) => decimal_values!(array, Int96Decimal0Array, 0),
+        DataType::Int96Decimal(1) => decimal_values!(array, Int96Decimal1Array, 1),
+        DataType::Int96Decimal(2) => decimal_values!(array, Int96Decimal2Array, 2),
+        DataType::Int96Decimal(3) => decimal_values!(array, Int96Decimal3Array, 3),
+        DataType::Int96Decimal(4) => decimal_values!(array, Int96Decimal4Array, 4),
+        DataType::Int96Decimal(5) => decimal_values!(array, Int96Decimal5Array, 5),
+        DataType::Int96Decimal(10) => decimal_values!(array, Int96Decimal10Array, 10),
+        _ => array.clone(),
+    }
+}
+
+fn float_array(array: &ArrayRef) -> Result<&Float64Array> {
+    array
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "Unexpected REGR values of type {:?}",
+                array.data_type()
+            ))
+        })
+}
+
+impl Accumulator for RegrAccumulator {
+    fn reset(&mut self) {
+        *self = RegrAccumulator::new(self.regr_type);
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        Ok(smallvec![
+            ScalarValue::from(self.count),
+            ScalarValue::from(self.mean_x),
+            ScalarValue::from(self.mean_y),
+            ScalarValue::from(self.m2_x),
+            ScalarValue::from(self.m2_y),
+            ScalarValue::from(self.c_xy),
+        ])
+    }
+
+    // the values of `x` alone are also the ones of `y`
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        let (y, x) = (&values[0], &values[values.len() - 1]);
+        if let (Some(y), Some(x)) = (float_value(y)?, float_value(x)?) {
+            self.push(y, x);
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let y = float_values(&values[0]);
+        let x = float_values(&values[values.len() - 1]);
+        let (y, x) = (float_array(&y)?, float_array(&x)?);
+        for i in 0..y.len() {
+            if y.is_valid(i) && x.is_valid(i) {
+                self.push(y.value(i), x.value(i));
+            }
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        let count = match &states[0] {
+            ScalarValue::UInt64(Some(count)) => *count,
+            state => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unexpected REGR count state {:?}",
+                    state
+                )))
+            }
+        };
+        let moment = |i: usize| -> Result<f64> {
+            Ok(float_value(&states[i])?.unwrap_or_default())
+        };
+        self.combine(&RegrAccumulator {
+            regr_type: self.regr_type,
+            count,
+            mean_x: moment(1)?,
+            mean_y: moment(2)?,
+            m2_x: moment(3)?,
+            m2_y: moment(4)?,
+            c_xy: moment(5)?,
+        });
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        if self.regr_type == RegrType::Count {
+            return Ok(ScalarValue::from(self.count));
+        }
+        if self.count == 0 {
+            return Ok(ScalarValue::Float64(None));
+        }
+        let n = self.count as f64;
+        // the regression line is undefined when all the values of `x` are equal
+        let slope = (self.m2_x != 0.0).then(|| self.c_xy / self.m2_x);
+        let value = match self.regr_type {
+            RegrType::Slope => slope,
+            RegrType::Intercept => slope.map(|slope| self.mean_y - slope * self.mean_x),
+            RegrType::R2 if self.m2_x == 0.0 => None,
+            // a horizontal line fits the values of `y` perfectly
+            RegrType::R2 if self.m2_y == 0.0 => Some(1.0),
+            RegrType::R2 => Some(self.c_xy * self.c_xy / (self.m2_x * self.m2_y)),
+            RegrType::AvgX => Some(self.mean_x),
+            RegrType::AvgY => Some(self.mean_y),
+            RegrType::SXX => Some(self.m2_x),
+            RegrType::SYY => Some(self.m2_y),
+            RegrType::SXY => Some(self.c_xy),
+            RegrType::VarPop => Some(self.m2_x / n),
+            RegrType::VarSamp => (self.count > 1).then(|| self.m2_x / (n - 1.0)),
+            RegrType::StddevPop => Some((self.m2_x / n).sqrt()),
+            RegrType::StddevSamp => {
+                (self.count > 1).then(|| (self.m2_x / (n - 1.0)).sqrt())
+            }
+            RegrType::CovarPop => Some(self.c_xy / n),
+            RegrType::CovarSamp => (self.count > 1).then(|| self.c_xy / (n - 1.0)),
+            // the correlation is undefined when all the values of `x` or `y` are equal
+            RegrType::Corr if self.m2_x == 0.0 || self.m2_y == 0.0 => None,
+            RegrType::Corr => Some(self.c_xy / (self.m2_x * self.m2_y).sqrt()),
+            RegrType::Count => unreachable!(),
+        };
+        Ok(ScalarValue::Float64(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::RecordBatch;
+
+    /// Aggregates points close to y = 2x + 1, with a NULL in each column
+    fn regr(regr_type: RegrType) -> Result<ScalarValue> {
+        let schema = Schema::new(vec![
+            Field::new("y", DataType::Float64, true),
+            Field::new("x", DataType::Float64, true),
+        ]);
+        let y: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(3.0),
+            Some(5.0),
+            None,
+            Some(9.0),
+            Some(8.0),
+        ]));
+        let x: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(1.0),
+            Some(2.0),
+            Some(3.0),
+            None,
+            Some(4.0),
+        ]));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![y, x])?;
+        let agg = Arc::new(Regr::new(
+            col("y", &schema)?,
+            col("x", &schema)?,
+            "bla",
+            regr_type,
+        ));
+        aggregate(&batch, agg)
+    }
+
+    fn float(value: ScalarValue) -> f64 {
+        match value {
+            ScalarValue::Float64(Some(value)) => value,
+            value => panic!("unexpected {:?}", value),
+        }
+    }
+
+    #[test]
+    fn regr_statistics() -> Result<()> {
+        // (x, y) are (1, 3), (2, 5), (4, 8)
+        assert_eq!(regr(RegrType::Count)?, ScalarValue::from(3_u64));
+        let expected = vec![
+            (RegrType::AvgX, 7.0 / 3.0),
+            (RegrType::AvgY, 16.0 / 3.0),
+            (RegrType::SXX, 14.0 / 3.0),
+            (RegrType::SYY, 38.0 / 3.0),
+            (RegrType::SXY, 23.0 / 3.0),
+            (RegrType::Slope, 23.0 / 14.0),
+            (RegrType::Intercept, 16.0 / 3.0 - 23.0 / 14.0 * 7.0 / 3.0),
+            (RegrType::R2, 23.0 * 23.0 / (14.0 * 38.0)),
+        ];
+        for (regr_type, expected) in expected {
+            let value = float(regr(regr_type)?);
+            assert!(
+                (value - expected).abs() < 1e-9,
+                "{:?}: {} != {}",
+                regr_type,
+                value,
+                expected
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn moment_statistics() -> Result<()> {
+        // (x, y) are (1, 3), (2, 5), (4, 8)
+        let expected = vec![
+            (RegrType::CovarPop, 23.0 / 9.0),
+            (RegrType::CovarSamp, 23.0 / 6.0),
+            (RegrType::Corr, 23.0 / (14.0_f64 * 38.0).sqrt()),
+        ];
+        for (regr_type, expected) in expected {
+            let value = float(regr(regr_type)?);
+            assert!(
+                (value - expected).abs() < 1e-9,
+                "{:?}: {}",
+                regr_type,
+                value
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn decimal_variance() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("x", DataType::Int64Decimal(2), true)]);
+        let x: ArrayRef = Arc::new(Int64Decimal2Array::from(vec![
+            Some(150),
+            None,
+            Some(250),
+            Some(400),
+        ]));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![x])?;
+        // the values are 1.5, 2.5 and 4, whose mean is 8 / 3
+        let expected = vec![
+            (RegrType::VarPop, 19.0 / 18.0),
+            (RegrType::VarSamp, 19.0 / 12.0),
+            (RegrType::StddevPop, (19.0_f64 / 18.0).sqrt()),
+            (RegrType::StddevSamp, (19.0_f64 / 12.0).sqrt()),
+        ];
+        for (regr_type, expected) in expected {
+            let agg =
+                Arc::new(Regr::new_univariate(col("x", &schema)?, "bla", regr_type));
+            let value = float(aggregate(&batch, agg)?);
+            assert!(
+                (value - expected).abs() < 1e-9,
+                "{:?}: {}",
+                regr_type,
+                value
+            );
+        }
+
+        // a single value has no sample variance
+        let mut acc = RegrAccumulator::new(RegrType::VarSamp);
+        acc.update(&[ScalarValue::Int96Decimal(Some(15), 1)])?;
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(None));
+        acc.regr_type = RegrType::VarPop;
+        assert_eq!(acc.evaluate()?, ScalarValue::from(0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn regr_merge() -> Result<()> {
+        let mut all = RegrAccumulator::new(RegrType::Slope);
+        let mut partial = RegrAccumulator::new(RegrType::Slope);
+        let mut acc = RegrAccumulator::new(RegrType::Slope);
+        for (i, (y, x)) in [(3.0, 1.0), (5.0, 2.0), (8.0, 4.0), (1.0, 7.0)]
+            .iter()
+            .enumerate()
+        {
+            all.push(*y, *x);
+            if i % 2 == 0 {
+                partial.push(*y, *x);
+            } else {
+                acc.push(*y, *x);
+            }
+        }
+        acc.merge(&RegrAccumulator::new(RegrType::Slope).state()?)?;
+        acc.merge(&partial.state()?)?;
+        assert_eq!(acc.count, 4);
+        assert!((float(acc.evaluate()?) - float(all.evaluate()?)).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn regr_undefined() -> Result<()> {
+        let mut acc = RegrAccumulator::new(RegrType::Slope);
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(None));
+        // a single point, or a vertical line
+        acc.update(&[ScalarValue::from(1.0), ScalarValue::from(2.0)])?;
+        acc.update(&[ScalarValue::from(3.0), ScalarValue::from(2.0)])?;
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(None));
+        acc.regr_type = RegrType::R2;
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(None));
+
+        let mut acc = RegrAccumulator::new(RegrType::R2);
+        acc.update(&[ScalarValue::from(1.0), ScalarValue::from(2.0)])?;
+        acc.update(&[ScalarValue::from(1.0), ScalarValue::from(3.0)])?;
+        acc.update(&[ScalarValue::Float64(None), ScalarValue::from(4.0)])?;
+        assert_eq!(acc.evaluate()?, ScalarValue::from(1.0));
+        assert_eq!(acc.count, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,339 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the exact continuous percentile of sorted values
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::{ArrayRef, Float64Array};
+use arrow::compute::{self, total_cmp_64};
+use arrow::datatypes::{DataType, Field};
+
+use super::format_state_name;
+use smallvec::{smallvec, SmallVec};
+
+/// The exact continuous percentile of the values of an expression, as computed by
+/// PERCENTILE_CONT, for values that are expected to be sorted in ascending order.
+///
+/// The values are counted in a single pass, as runs of equal values, rather than
+/// kept one by one or summarized by a digest. The physical planner uses it for
+/// PERCENTILE_CONT when the input is known to sort the values of each group in
+/// ascending order. Unsorted values give the same result, sorting the runs when
+/// evaluated.
+#[derive(Debug)]
+pub struct SortedPercentile {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    fraction: f64,
+}
+
+impl SortedPercentile {
+    /// Create a new aggregate function of the `fraction` percentile, between 0 and 1,
+    /// of the values of `expr`, whose type is Float64
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        fraction: f64,
+        name: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            expr,
+            fraction,
+        }
+    }
+}
+
+impl AggregateExpr for SortedPercentile {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Float64, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        let list =
+            |data_type| DataType::List(Box::new(Field::new("item", data_type, true)));
+        Ok(vec![
+            Field::new(
+                &format_state_name(&self.name, "values"),
+                list(DataType::Float64),
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "counts"),
+                list(DataType::UInt64),
+                true,
+            ),
+        ])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(SortedPercentileAccumulator {
+            runs: vec![],
+            sorted: true,
+            fraction: self.fraction,
+        }))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A value and its number of consecutive occurrences
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    value: f64,
+    count: u64,
+}
+
+#[derive(Debug)]
+struct SortedPercentileAccumulator {
+    runs: Vec<Run>,
+    /// whether the runs are in ascending order of their values
+    sorted: bool,
+    fraction: f64,
+}
+
+impl SortedPercentileAccumulator {
+    fn push(&mut self, value: f64, count: u64) {
+        if let Some(last) = self.runs.last_mut() {
+            match total_cmp_64(value, last.value) {
+                Ordering::Equal => {
+                    last.count += count;
+                    return;
+                }
+                Ordering::Less => self.sorted = false,
+                Ordering::Greater => {}
+            }
+        }
+        self.runs.push(Run { value, count });
+    }
+
+    /// The runs in ascending order of their distinct values
+    fn sorted_runs(&self) -> Vec<Run> {
+        if self.sorted {
+            return self.runs.clone();
+        }
+        let mut runs = self.runs.clone();
+        runs.sort_by(|l, r| total_cmp_64(l.value, r.value));
+        runs.dedup_by(|run, previous| {
+            let equal = total_cmp_64(run.value, previous.value) == Ordering::Equal;
+            if equal {
+                previous.count += run.count;
+            }
+            equal
+        });
+        runs
+    }
+}
+
+impl Accumulator for SortedPercentileAccumulator {
+    fn reset(&mut self) {
+        self.runs.clear();
+        self.sorted = true;
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        let runs = self.sorted_runs();
+        if runs.is_empty() {
+            return Ok(smallvec![
+                ScalarValue::List(None, Box::new(DataType::Float64)),
+                ScalarValue::List(None, Box::new(DataType::UInt64)),
+            ]);
+        }
+        let values = runs.iter().map(|r| ScalarValue::Float64(Some(r.value)));
+        let counts = runs.iter().map(|r| ScalarValue::UInt64(Some(r.count)));
+        Ok(smallvec![
+            ScalarValue::List(
+                Some(Box::new(values.collect())),
+                Box::new(DataType::Float64)
+            ),
+            ScalarValue::List(
+                Some(Box::new(counts.collect())),
+                Box::new(DataType::UInt64)
+            ),
+        ])
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        match &values[0] {
+            ScalarValue::Float64(Some(value)) => self.push(*value, 1),
+            ScalarValue::Float64(None) => {}
+            value => {
+                return Err(DataFusionError::Internal(format!(
+                    "Sorted percentile is not expected to receive a scalar {:?}",
+                    value
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let values = compute::cast(&values[0], &DataType::Float64)?;
+        let values = values.as_any().downcast_ref::<Float64Array>().unwrap();
+        for value in values.iter().flatten() {
+            self.push(value, 1);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        match states {
+            [ScalarValue::List(Some(values), _), ScalarValue::List(Some(counts), _)]
+                if values.len() == counts.len() =>
+            {
+                for (value, count) in values.iter().zip(counts.iter()) {
+                    match (value, count) {
+                        (ScalarValue::Float64(Some(v)), ScalarValue::UInt64(Some(c))) => {
+                            self.push(*v, *c)
+                        }
+                        _ => {
+                            return Err(DataFusionError::Internal(format!(
+                                "Unexpected run {:?} of sorted percentile",
+                                (value, count)
+                            )))
+                        }
+                    }
+                }
+                Ok(())
+            }
+            // partial aggregate without any input rows
+            [ScalarValue::List(None, _), ScalarValue::List(None, _)] => Ok(()),
+            _ => Err(DataFusionError::Internal(format!(
+                "Unexpected state {:?} of sorted percentile",
+                states
+            ))),
+        }
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let runs = self.sorted_runs();
+        let n = runs.iter().map(|r| r.count).sum::<u64>();
+        if n == 0 {
+            return Ok(ScalarValue::Float64(None));
+        }
+        // the value at a position of the sorted values, counting the runs before it
+        let value_at = |position: u64| {
+            let mut before = 0;
+            for run in &runs {
+                before += run.count;
+                if position < before {
+                    return run.value;
+                }
+            }
+            runs[runs.len() - 1].value
+        };
+        let position = self.fraction * (n - 1) as f64;
+        let (lower, upper) = (position.floor(), position.ceil());
+        let lower_value = value_at(lower as u64);
+        let upper_value = value_at(upper as u64);
+        Ok(ScalarValue::Float64(Some(
+            lower_value + (position - lower) * (upper_value - lower_value),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::RecordBatch;
+
+    fn sorted_percentile(values: Vec<Option<f64>>, fraction: f64) -> Result<ScalarValue> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Float64, true)]);
+        let array: ArrayRef = Arc::new(Float64Array::from(values));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![array])?;
+        let agg = SortedPercentile::new(col("a", &schema)?, fraction, "bla");
+        aggregate(&batch, Arc::new(agg))
+    }
+
+    #[test]
+    fn sorted_percentile_cont() -> Result<()> {
+        let values = vec![Some(1.0), Some(2.0), Some(2.0), None, Some(2.0), Some(4.0)];
+        assert_eq!(
+            sorted_percentile(values.clone(), 0.5)?,
+            ScalarValue::Float64(Some(2.0))
+        );
+        assert_eq!(
+            sorted_percentile(values.clone(), 0.875)?,
+            ScalarValue::Float64(Some(3.0))
+        );
+        assert_eq!(
+            sorted_percentile(values, 0.0)?,
+            ScalarValue::Float64(Some(1.0))
+        );
+        assert_eq!(
+            sorted_percentile(vec![None], 0.5)?,
+            ScalarValue::Float64(None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unsorted_percentile_cont() -> Result<()> {
+        let values = vec![Some(4.0), Some(2.0), Some(1.0), Some(2.0), Some(2.0)];
+        assert_eq!(
+            sorted_percentile(values, 0.875)?,
+            ScalarValue::Float64(Some(3.0))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn merge_sorted_percentile_runs() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Float64, true)]);
+        let agg = SortedPercentile::new(col("a", &schema)?, 0.5, "bla");
+        let partial = |values: Vec<f64>| -> Result<_> {
+            let mut acc = agg.create_accumulator()?;
+            acc.update_batch(&[Arc::new(Float64Array::from(values)) as ArrayRef])?;
+            acc.state()
+        };
+        let mut acc = agg.create_accumulator()?;
+        acc.merge(&partial(vec![3.0, 3.0, 5.0])?)?;
+        acc.merge(&partial(vec![])?)?;
+        acc.merge(&partial(vec![1.0, 3.0])?)?;
+        assert_eq!(acc.evaluate()?, ScalarValue::Float64(Some(3.0)));
+        assert_eq!(
+            acc.state()?[1],
+            ScalarValue::List(
+                Some(Box::new(vec![
+                    ScalarValue::UInt64(Some(1)),
+                    ScalarValue::UInt64(Some(3)),
+                    ScalarValue::UInt64(Some(1)),
+                ])),
+                Box::new(DataType::UInt64)
+            )
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,369 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines physical expressions that can evaluated at runtime during query execution
+
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::ArrayRef;
+use arrow::compute::{lexsort_to_indices, SortColumn, SortOptions};
+use arrow::datatypes::{DataType, Field, Schema};
+
+use super::{format_state_name, PhysicalSortExpr};
+use smallvec::SmallVec;
+
+/// STRING_AGG aggregate expression
+/// Concatenates the non-null values of the given expression, separated by a
+/// delimiter, optionally in the order given by `ORDER BY` expressions.
+#[derive(Debug)]
+pub struct StringAgg {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    delimiter: String,
+    order_by: Vec<PhysicalSortExpr>,
+    order_by_types: Vec<DataType>,
+}
+
+impl StringAgg {
+    /// Create a new STRING_AGG aggregate function
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        delimiter: impl Into<String>,
+        name: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            expr,
+            delimiter: delimiter.into(),
+            order_by: vec![],
+            order_by_types: vec![],
+        }
+    }
+
+    /// Concatenate the values in the given order rather than in the order of the
+    /// input rows (`STRING_AGG(expr, delimiter ORDER BY ...)`)
+    pub fn with_order_by(
+        mut self,
+        order_by: Vec<PhysicalSortExpr>,
+        input_schema: &Schema,
+    ) -> Result<Self> {
+        self.order_by_types = order_by
+            .iter()
+            .map(|e| e.expr.data_type(input_schema))
+            .collect::<Result<_>>()?;
+        self.order_by = order_by;
+        Ok(self)
+    }
+}
+
+impl AggregateExpr for StringAgg {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, DataType::Utf8, true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        let mut fields = vec![Field::new(
+            &format_state_name(&self.name, "string_agg"),
+            list_type(&DataType::Utf8),
+            true,
+        )];
+        for (i, data_type) in self.order_by_types.iter().enumerate() {
+            fields.push(Field::new(
+                &format_state_name(&self.name, &format!("string_agg_key[{}]", i)),
+                list_type(data_type),
+                true,
+            ));
+        }
+        Ok(fields)
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        let mut exprs = vec![self.expr.clone()];
+        exprs.extend(self.order_by.iter().map(|e| e.expr.clone()));
+        exprs
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(StringAggAccumulator {
+            delimiter: self.delimiter.clone(),
+            values: vec![],
+            keys: vec![vec![]; self.order_by.len()],
+            key_types: self.order_by_types.clone(),
+            sort_options: self.order_by.iter().map(|e| e.options).collect(),
+        }))
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn list_type(data_type: &DataType) -> DataType {
+    DataType::List(Box::new(Field::new("item", data_type.clone(), true)))
+}
+
+#[derive(Debug)]
+struct StringAggAccumulator {
+    delimiter: String,
+    values: Vec<String>,
+    /// The values of the ORDER BY expressions for each of `values`, one vector per
+    /// expression
+    keys: Vec<Vec<ScalarValue>>,
+    key_types: Vec<DataType>,
+    sort_options: Vec<SortOptions>,
+}
+
+impl StringAggAccumulator {
+    /// Adds the value unless it is null, `values` are the value followed by its keys
+    fn push(&mut self, values: &[ScalarValue]) -> Result<()> {
+        match &values[0] {
+            ScalarValue::Utf8(Some(value)) => {
+                self.values.push(value.clone());
+                for (keys, key) in self.keys.iter_mut().zip(&values[1..]) {
+                    keys.push(key.clone());
+                }
+                Ok(())
+            }
+            ScalarValue::Utf8(None) => Ok(()),
+            value => Err(DataFusionError::Internal(format!(
+                "Unexpected STRING_AGG value {:?}",
+                value
+            ))),
+        }
+    }
+
+    fn ordered_values(&self) -> Result<Vec<&String>> {
+        if self.keys.is_empty() {
+            return Ok(self.values.iter().collect());
+        }
+        let columns = self
+            .keys
+            .iter()
+            .zip(&self.sort_options)
+            .map(|(keys, options)| {
+                Ok(SortColumn {
+                    values: ScalarValue::iter_to_array(keys.iter().cloned())?,
+                    options: Some(*options),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let indices = lexsort_to_indices(&columns, None)?;
+        Ok(indices
+            .values()
+            .iter()
+            .map(|i| &self.values[*i as usize])
+            .collect())
+    }
+}
+
+impl Accumulator for StringAggAccumulator {
+    fn reset(&mut self) {
+        self.values.clear();
+        for keys in &mut self.keys {
+            keys.clear();
+        }
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let array = &values[0];
+        if array.null_count() == array.len() {
+            return Ok(());
+        }
+        for index in 0..array.len() {
+            if array.is_null(index) {
+                continue;
+            }
+            let row = values
+                .iter()
+                .map(|array| ScalarValue::try_from_array(array, index))
+                .collect::<Result<Vec<_>>>()?;
+            self.push(&row)?;
+        }
+        Ok(())
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        self.push(values)
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        let lists = states
+            .iter()
+            .map(|state| match state {
+                ScalarValue::List(values, _) => Ok(values),
+                state => Err(DataFusionError::Internal(format!(
+                    "Unexpected accumulator state {:?}",
+                    state
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        match lists[0] {
+            Some(values) => {
+                for index in 0..values.len() {
+                    let row = lists
+                        .iter()
+                        .map(|list| match list {
+                            Some(list) => Ok(list[index].clone()),
+                            None => Err(DataFusionError::Internal(
+                                "STRING_AGG state without keys".to_string(),
+                            )),
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    self.push(&row)?;
+                }
+                Ok(())
+            }
+            // partial aggregate without any input rows
+            None => Ok(()),
+        }
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        let list = |values: Vec<ScalarValue>, data_type: &DataType| {
+            // as in ARRAY_AGG, no rows are a NULL list rather than an empty one
+            let values = if values.is_empty() {
+                None
+            } else {
+                Some(Box::new(values))
+            };
+            ScalarValue::List(values, Box::new(data_type.clone()))
+        };
+        let mut state = SmallVec::new();
+        state.push(list(
+            self.values
+                .iter()
+                .map(|v| ScalarValue::Utf8(Some(v.clone())))
+                .collect(),
+            &DataType::Utf8,
+        ));
+        for (keys, data_type) in self.keys.iter().zip(&self.key_types) {
+            state.push(list(keys.clone(), data_type));
+        }
+        Ok(state)
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        // as in Postgres, aggregating no values produces NULL rather than ''
+        if self.values.is_empty() {
+            return Ok(ScalarValue::Utf8(None));
+        }
+        let values = self.ordered_values()?;
+        let mut result = String::new();
+        for (i, value) in values.into_iter().enumerate() {
+            if i != 0 {
+                result.push_str(&self.delimiter);
+            }
+            result.push_str(value);
+        }
+        Ok(ScalarValue::Utf8(Some(result)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::record_batch::RecordBatch;
+
+    fn string_agg(order_by: Vec<(&str, SortOptions)>) -> Result<ScalarValue> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Utf8, true),
+            Field::new("b", DataType::Int32, true),
+        ]);
+        let a: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("x"),
+            None,
+            Some("y"),
+            Some("z"),
+        ]));
+        let b: ArrayRef =
+            Arc::new(Int32Array::from(vec![Some(2), Some(1), None, Some(1)]));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![a, b])?;
+        let order_by = order_by
+            .into_iter()
+            .map(|(name, options)| {
+                Ok(PhysicalSortExpr {
+                    expr: col(name, &schema)?,
+                    options,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let agg = Arc::new(
+            StringAgg::new(col("a", &schema)?, ", ", "bla")
+                .with_order_by(order_by, &schema)?,
+        );
+        aggregate(&batch, agg)
+    }
+
+    #[test]
+    fn string_agg_in_input_order() -> Result<()> {
+        assert_eq!(string_agg(vec![])?, ScalarValue::from("x, y, z"));
+        Ok(())
+    }
+
+    #[test]
+    fn string_agg_order_by() -> Result<()> {
+        let asc = SortOptions {
+            descending: false,
+            nulls_first: false,
+        };
+        assert_eq!(string_agg(vec![("b", asc)])?, ScalarValue::from("z, x, y"));
+        let desc = SortOptions {
+            descending: true,
+            nulls_first: true,
+        };
+        assert_eq!(
+            string_agg(vec![("b", desc), ("a", desc)])?,
+            ScalarValue::from("y, x, z")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn string_agg_merge() -> Result<()> {
+        let new_accumulator = || StringAggAccumulator {
+            delimiter: "-".to_string(),
+            values: vec![],
+            keys: vec![vec![]],
+            key_types: vec![DataType::Int32],
+            sort_options: vec![SortOptions::default()],
+        };
+        let mut partial = new_accumulator();
+        partial.update(&[ScalarValue::from("b"), ScalarValue::Int32(Some(2))])?;
+        partial.update(&[ScalarValue::Utf8(None), ScalarValue::Int32(Some(0))])?;
+        partial.update(&[ScalarValue::from("a"), ScalarValue::Int32(Some(1))])?;
+
+        let mut acc = new_accumulator();
+        assert_eq!(acc.evaluate()?, ScalarValue::Utf8(None));
+        acc.merge(&new_accumulator().state()?)?;
+        acc.update(&[ScalarValue::from("c"), ScalarValue::Int32(Some(3))])?;
+        acc.merge(&partial.state()?)?;
+        assert_eq!(acc.evaluate()?, ScalarValue::from("a-b-c"));
+        Ok(())
+    }
+}
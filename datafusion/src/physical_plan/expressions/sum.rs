@@ -18,7 +18,7 @@
 //! Defines physical expressions that can evaluated at runtime during query execution
 
 use std::any::Any;
-use std::convert::TryFrom;
+use std::convert::{identity, TryFrom};
 use std::sync::Arc;
 
 use crate::error::{DataFusionError, Result};
@@ -30,43 +30,50 @@ use arrow::compute;
 use arrow::datatypes::DataType;
 use arrow::{
     array::{
-        ArrayRef, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array,
-        Int64Decimal0Array, Int64Decimal10Array, Int64Decimal1Array, Int64Decimal2Array,
-        Int64Decimal3Array, Int64Decimal4Array, Int64Decimal5Array, Int8Array,
-        Int96Array, Int96Decimal0Array, Int96Decimal10Array, Int96Decimal1Array,
-        Int96Decimal2Array, Int96Decimal3Array, Int96Decimal4Array, Int96Decimal5Array,
-        UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+        ArrayRef, Float64Array, Int16Array, Int32Array, Int64Array, Int64Decimal0Array,
+        Int64Decimal10Array, Int64Decimal1Array, Int64Decimal2Array, Int64Decimal3Array,
+        Int64Decimal4Array, Int64Decimal5Array, Int8Array, Int96Array,
+        Int96Decimal0Array, Int96Decimal10Array, Int96Decimal1Array, Int96Decimal2Array,
+        Int96Decimal3Array, Int96Decimal4Array, Int96Decimal5Array, UInt16Array,
+        UInt32Array, UInt64Array, UInt8Array,
     },
     datatypes::Field,
 };
 
+use super::binary::fits_int96;
+use super::compensated_sum::{compensated_state_fields, CompensatedSumAccumulator};
 use super::format_state_name;
 use smallvec::smallvec;
 use smallvec::SmallVec;
 
 /// SUM aggregate expression
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Sum {
     name: String,
     data_type: DataType,
     expr: Arc<dyn PhysicalExpr>,
     nullable: bool,
+    overflow_check: bool,
+    compensated: bool,
 }
 
-/// function return type of a sum
+/// function return type of a sum, wider than the type of the values when it has a
+/// wider counterpart, so that the sums of many values do not overflow or lose
+/// precision
 pub fn sum_return_type(arg_type: &DataType) -> Result<DataType> {
     match arg_type {
         DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
             Ok(DataType::Int64)
         }
         DataType::Int96 => Ok(DataType::Int96),
-        DataType::Int64Decimal(scale) => Ok(DataType::Int64Decimal(*scale)),
-        DataType::Int96Decimal(scale) => Ok(DataType::Int96Decimal(*scale)),
+        DataType::Int64Decimal(scale) | DataType::Int96Decimal(scale) => {
+            Ok(DataType::Int96Decimal(*scale))
+        }
         DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => {
             Ok(DataType::UInt64)
         }
-        DataType::Float32 => Ok(DataType::Float32),
-        DataType::Float64 => Ok(DataType::Float64),
+        DataType::Float32 | DataType::Float64 => Ok(DataType::Float64),
+        DataType::Interval(_) => Ok(arg_type.clone()),
         other => Err(DataFusionError::Plan(format!(
             "SUM does not support type \"{:?}\"",
             other
@@ -86,8 +93,24 @@ impl Sum {
             expr,
             data_type,
             nullable: true,
+            overflow_check: false,
+            compensated: false,
         }
     }
+
+    /// Whether sums of integers and decimals fail with an error rather than wrap when
+    /// they overflow their type
+    pub fn with_overflow_check(mut self, overflow_check: bool) -> Self {
+        self.overflow_check = overflow_check;
+        self
+    }
+
+    /// Whether sums of floats are computed with a compensated summation, whose result
+    /// does not depend on the batches and the partitions of the values
+    pub fn with_compensated(mut self, compensated: bool) -> Self {
+        self.compensated = compensated;
+        self
+    }
 }
 
 impl AggregateExpr for Sum {
@@ -105,6 +128,9 @@ impl AggregateExpr for Sum {
     }
 
     fn state_fields(&self) -> Result<Vec<Field>> {
+        if self.compensated {
+            return Ok(compensated_state_fields(&self.name));
+        }
         Ok(vec![Field::new(
             &format_state_name(&self.name, "sum"),
             self.data_type.clone(),
@@ -117,7 +143,13 @@ impl AggregateExpr for Sum {
     }
 
     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
-        Ok(Box::new(SumAccumulator::try_new(&self.data_type)?))
+        if self.compensated {
+            return Ok(Box::new(CompensatedSumAccumulator::new(false)));
+        }
+        Ok(Box::new(SumAccumulator::try_new(
+            &self.data_type,
+            self.overflow_check,
+        )?))
     }
 
     fn uses_groups_accumulator(&self) -> bool {
@@ -129,10 +161,18 @@ impl AggregateExpr for Sum {
     fn create_groups_accumulator(
         &self,
     ) -> arrow::error::Result<Option<Box<dyn GroupsAccumulator>>> {
+        if self.compensated {
+            return Ok(Some(Box::new(GroupsAccumulatorFlatAdapter::<
+                CompensatedSumAccumulator,
+            >::new(|| {
+                Ok(CompensatedSumAccumulator::new(false))
+            }))));
+        }
         let data_type = self.data_type.clone();
+        let overflow_check = self.overflow_check;
         Ok(Some(Box::new(
             GroupsAccumulatorFlatAdapter::<SumAccumulator>::new(move || {
-                SumAccumulator::try_new(&data_type)
+                SumAccumulator::try_new(&data_type, overflow_check)
             }),
         )))
     }
@@ -145,24 +185,35 @@ impl AggregateExpr for Sum {
 #[derive(Debug)]
 struct SumAccumulator {
     sum: ScalarValue,
+    overflow_check: bool,
 }
 
 impl SumAccumulator {
     /// new sum accumulator
-    pub fn try_new(data_type: &DataType) -> Result<Self> {
+    pub fn try_new(data_type: &DataType, overflow_check: bool) -> Result<Self> {
         Ok(Self {
             sum: ScalarValue::try_from(data_type)?,
+            overflow_check,
         })
     }
+
+    /// Whether the sum is checked, i.e. checks are enabled and it is an integer or a
+    /// decimal
+    fn is_checked(&self) -> bool {
+        self.overflow_check
+            && matches!(
+                self.sum,
+                ScalarValue::Int64(_)
+                    | ScalarValue::UInt64(_)
+                    | ScalarValue::Int96(_)
+                    | ScalarValue::Int64Decimal(..)
+                    | ScalarValue::Int96Decimal(..)
+            )
+    }
 }
 
 // returns the new value after sum with the new values, taking nullability into account
 macro_rules! typed_sum_delta_batch {
-    ($VALUES:expr, $ARRAYTYPE:ident, Int64Decimal, $SCALE:expr) => {{
-        let array = $VALUES.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
-        let delta = compute::sum(array);
-        ScalarValue::Int64Decimal(delta, $SCALE)
-    }};
     ($VALUES:expr, $ARRAYTYPE:ident, Int96Decimal, $SCALE:expr) => {{
         let array = $VALUES.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
         let delta = compute::sum(array);
@@ -175,33 +226,27 @@ macro_rules! typed_sum_delta_batch {
     }};
 }
 
-// sums the array and returns a ScalarValue of its corresponding type.
+// sums the array and returns a ScalarValue of the type of its SUM: the integers
+// narrower than 64 bits are summed as 64 bits integers, Float32 as Float64 and 64 bits
+// decimals as 96 bits decimals, so that they do not wrap.
 pub(super) fn sum_batch(values: &ArrayRef) -> Result<ScalarValue> {
+    let values = &match values.data_type() {
+        DataType::Int8 | DataType::Int16 | DataType::Int32 => {
+            compute::cast(values, &DataType::Int64)?
+        }
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 => {
+            compute::cast(values, &DataType::UInt64)?
+        }
+        DataType::Float32 => compute::cast(values, &DataType::Float64)?,
+        _ => values.clone(),
+    };
     Ok(match values.data_type() {
         DataType::Float64 => typed_sum_delta_batch!(values, Float64Array, Float64),
-        DataType::Float32 => typed_sum_delta_batch!(values, Float32Array, Float32),
         DataType::Int64 => typed_sum_delta_batch!(values, Int64Array, Int64),
         DataType::Int96 => typed_sum_delta_batch!(values, Int96Array, Int96),
-        DataType::Int64Decimal(0) => {
-            typed_sum_delta_batch!(values, Int64Decimal0Array, Int64Decimal, 0)
-        }
-        DataType::Int64Decimal(1) => {
-            typed_sum_delta_batch!(values, Int64Decimal1Array, Int64Decimal, 1)
-        }
-        DataType::Int64Decimal(2) => {
-            typed_sum_delta_batch!(values, Int64Decimal2Array, Int64Decimal, 2)
-        }
-        DataType::Int64Decimal(3) => {
-            typed_sum_delta_batch!(values, Int64Decimal3Array, Int64Decimal, 3)
-        }
-        DataType::Int64Decimal(4) => {
-            typed_sum_delta_batch!(values, Int64Decimal4Array, Int64Decimal, 4)
-        }
-        DataType::Int64Decimal(5) => {
-            typed_sum_delta_batch!(values, Int64Decimal5Array, Int64Decimal, 5)
-        }
-        DataType::Int64Decimal(10) => {
-            typed_sum_delta_batch!(values, Int64Decimal10Array, Int64Decimal, 10)
+        // the unscaled values are summed as i128
+        DataType::Int64Decimal(scale) => {
+            ScalarValue::Int96Decimal(checked_sum_batch(values)?, *scale)
         }
         DataType::Int96Decimal(0) => {
             typed_sum_delta_batch!(values, Int96Decimal0Array, Int96Decimal, 0)
@@ -224,13 +269,7 @@ pub(super) fn sum_batch(values: &ArrayRef) -> Result<ScalarValue> {
         DataType::Int96Decimal(10) => {
             typed_sum_delta_batch!(values, Int96Decimal10Array, Int96Decimal, 10)
         }
-        DataType::Int32 => typed_sum_delta_batch!(values, Int32Array, Int32),
-        DataType::Int16 => typed_sum_delta_batch!(values, Int16Array, Int16),
-        DataType::Int8 => typed_sum_delta_batch!(values, Int8Array, Int8),
         DataType::UInt64 => typed_sum_delta_batch!(values, UInt64Array, UInt64),
-        DataType::UInt32 => typed_sum_delta_batch!(values, UInt32Array, UInt32),
-        DataType::UInt16 => typed_sum_delta_batch!(values, UInt16Array, UInt16),
-        DataType::UInt8 => typed_sum_delta_batch!(values, UInt8Array, UInt8),
         e => {
             return Err(DataFusionError::Internal(format!(
                 "Sum is not expected to receive the type {:?}",
@@ -240,6 +279,148 @@ pub(super) fn sum_batch(values: &ArrayRef) -> Result<ScalarValue> {
     })
 }
 
+// returns the sum of the values of an integer or decimal array as i128, or an error
+// when it overflows
+macro_rules! typed_checked_sum_batch {
+    ($VALUES:expr, $ARRAYTYPE:ident) => {{
+        typed_checked_sum_batch!($VALUES, $ARRAYTYPE, i128::from)
+    }};
+    ($VALUES:expr, $ARRAYTYPE:ident, $TO_I128:expr) => {{
+        let array = $VALUES.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
+        array
+            .iter()
+            .flatten()
+            .try_fold(None, |sum: Option<i128>, v| {
+                sum.unwrap_or(0).checked_add($TO_I128(v)).map(Some)
+            })
+            .ok_or_else(|| sum_overflow($VALUES.data_type()))?
+    }};
+}
+
+fn sum_overflow(data_type: &DataType) -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "Arithmetic overflow: SUM is out of range of {:?}",
+        data_type
+    ))
+}
+
+// sums the values of an integer or decimal array, as their unscaled value for
+// decimals, failing on overflow.
+fn checked_sum_batch(values: &ArrayRef) -> Result<Option<i128>> {
+    Ok(match values.data_type() {
+        DataType::Int8 => typed_checked_sum_batch!(values, Int8Array),
+        DataType::Int16 => typed_checked_sum_batch!(values, Int16Array),
+        DataType::Int32 => typed_checked_sum_batch!(values, Int32Array),
+        DataType::Int64 => typed_checked_sum_batch!(values, Int64Array),
+        DataType::Int96 => typed_checked_sum_batch!(values, Int96Array, identity),
+        DataType::UInt8 => typed_checked_sum_batch!(values, UInt8Array),
+        DataType::UInt16 => typed_checked_sum_batch!(values, UInt16Array),
+        DataType::UInt32 => typed_checked_sum_batch!(values, UInt32Array),
+        DataType::UInt64 => typed_checked_sum_batch!(values, UInt64Array),
+        DataType::Int64Decimal(0) => typed_checked_sum_batch!(values, Int64Decimal0Array),
+        DataType::Int64Decimal(1) => typed_checked_sum_batch!(values, Int64Decimal1Array),
+        DataType::Int64Decimal(2) => typed_checked_sum_batch!(values, Int64Decimal2Array),
+        DataType::Int64Decimal(3) => typed_checked_sum_batch!(values, Int64Decimal3Array),
+        DataType::Int64Decimal(4) => typed_checked_sum_batch!(values, Int64Decimal4Array),
+        DataType::Int64Decimal(5) => typed_checked_sum_batch!(values, Int64Decimal5Array),
+        DataType::Int64Decimal(10) => {
+            typed_checked_sum_batch!(values, Int64Decimal10Array)
+        }
+        DataType::Int96Decimal(0) => {
+            typed_checked_sum_batch!(values, Int96Decimal0Array, identity)
+        }
+        DataType::Int96Decimal(1) => {
+            typed_checked_sum_batch!(values, Int96Decimal1Array, identity)
+        }
+        DataType::Int96Decimal(2) => {
+            typed_checked_sum_batch!(values, Int96Decimal2Array, identity)
+        }
+        DataType::Int96Decimal(3) => {
+            typed_checked_sum_batch!(values, Int96Decimal3Array, identity)
+        }
+        DataType::Int96Decimal(4) => {
+            typed_checked_sum_batch!(values, Int96Decimal4Array, identity)
+        }
+        DataType::Int96Decimal(5) => {
+            typed_checked_sum_batch!(values, Int96Decimal5Array, identity)
+        }
+        DataType::Int96Decimal(10) => {
+            typed_checked_sum_batch!(values, Int96Decimal10Array, identity)
+        }
+        e => {
+            return Err(DataFusionError::Internal(format!(
+                "Checked sum is not expected to receive the type {:?}",
+                e
+            )))
+        }
+    })
+}
+
+// the unscaled value of an integer or decimal scalar, as i128
+fn scalar_to_i128(value: &ScalarValue) -> Result<Option<i128>> {
+    Ok(match value {
+        ScalarValue::Int8(v) => v.map(i128::from),
+        ScalarValue::Int16(v) => v.map(i128::from),
+        ScalarValue::Int32(v) => v.map(i128::from),
+        ScalarValue::Int64(v) => v.map(i128::from),
+        ScalarValue::Int96(v) => *v,
+        ScalarValue::UInt8(v) => v.map(i128::from),
+        ScalarValue::UInt16(v) => v.map(i128::from),
+        ScalarValue::UInt32(v) => v.map(i128::from),
+        ScalarValue::UInt64(v) => v.map(i128::from),
+        ScalarValue::Int64Decimal(v, _) => v.map(i128::from),
+        ScalarValue::Int96Decimal(v, _) => *v,
+        e => {
+            return Err(DataFusionError::Internal(format!(
+                "Checked sum is not expected to receive a scalar {:?}",
+                e
+            )))
+        }
+    })
+}
+
+// returns the sum of a SUM of integers or decimals and an unscaled delta, failing
+// when it does not fit the type of the SUM rather than wrapping.
+fn checked_sum(lhs: &ScalarValue, delta: Option<i128>) -> Result<ScalarValue> {
+    let delta = match delta {
+        Some(delta) => delta,
+        None => return Ok(lhs.clone()),
+    };
+    let overflow = || sum_overflow(&lhs.get_datatype());
+    let add = |value: Option<i128>| {
+        value
+            .map_or(Some(delta), |v| v.checked_add(delta))
+            .ok_or_else(overflow)
+    };
+    let add_int96 =
+        |value: Option<i128>| Some(add(value)?).filter(fits_int96).ok_or_else(overflow);
+    Ok(match lhs {
+        ScalarValue::Int64(v) => {
+            let sum = add(v.map(i128::from))?;
+            ScalarValue::Int64(Some(i64::try_from(sum).map_err(|_| overflow())?))
+        }
+        ScalarValue::UInt64(v) => {
+            let sum = add(v.map(i128::from))?;
+            ScalarValue::UInt64(Some(u64::try_from(sum).map_err(|_| overflow())?))
+        }
+        ScalarValue::Int96(v) => ScalarValue::Int96(Some(add_int96(*v)?)),
+        ScalarValue::Int64Decimal(v, scale) => {
+            let sum = add(v.map(i128::from))?;
+            let sum = i64::try_from(sum).map_err(|_| overflow())?;
+            ScalarValue::Int64Decimal(Some(sum), *scale)
+        }
+        ScalarValue::Int96Decimal(v, scale) => {
+            ScalarValue::Int96Decimal(Some(add_int96(*v)?), *scale)
+        }
+        e => {
+            return Err(DataFusionError::Internal(format!(
+                "Checked sum is not expected to receive a scalar {:?}",
+                e
+            )))
+        }
+    })
+}
+
 // returns the sum of two scalar values, including coercion into $TYPE.
 macro_rules! typed_sum {
     ($OLD_VALUE:expr, $DELTA:expr, Int64Decimal, $TYPE:ident, $SCALE:expr) => {{
@@ -364,6 +545,19 @@ pub(super) fn sum(lhs: &ScalarValue, rhs: &ScalarValue) -> Result<ScalarValue> {
             }
             typed_sum!(lhs, rhs, Int96Decimal, i128, *l_scale)
         }
+        // 64 bits decimals are summed as 96 bits decimals
+        (
+            ScalarValue::Int96Decimal(lhs, l_scale),
+            ScalarValue::Int64Decimal(rhs, r_scale),
+        ) => {
+            if l_scale != r_scale {
+                return Err(DataFusionError::Internal(format!(
+                    "Scale doesn't match: {} and {}",
+                    l_scale, r_scale
+                )));
+            }
+            typed_sum!(lhs, rhs, Int96Decimal, i128, *l_scale)
+        }
         e => {
             return Err(DataFusionError::Internal(format!(
                 "Sum is not expected to receive a scalar {:?}",
@@ -381,13 +575,21 @@ impl Accumulator for SumAccumulator {
 
     fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
         let values = &values[0];
-        self.sum = sum(&self.sum, &sum_batch(values)?)?;
+        self.sum = if self.is_checked() {
+            checked_sum(&self.sum, checked_sum_batch(values)?)?
+        } else {
+            sum(&self.sum, &sum_batch(values)?)?
+        };
         Ok(())
     }
 
     fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
         // sum(v1, v2, v3) = v1 + v2 + v3
-        self.sum = sum(&self.sum, &values[0])?;
+        self.sum = if self.is_checked() {
+            checked_sum(&self.sum, scalar_to_i128(&values[0])?)?
+        } else {
+            sum(&self.sum, &values[0])?
+        };
         Ok(())
     }
 
@@ -415,6 +617,7 @@ mod tests {
     use super::*;
     use crate::physical_plan::expressions::col;
     use crate::{error::Result, generic_test_op};
+    use arrow::array::Float32Array;
     use arrow::datatypes::*;
     use arrow::record_batch::RecordBatch;
 
@@ -481,8 +684,8 @@ mod tests {
             a,
             DataType::Float32,
             Sum,
-            ScalarValue::from(15_f32),
-            DataType::Float32
+            ScalarValue::from(15_f64),
+            DataType::Float64
         )
     }
 
@@ -499,6 +702,68 @@ mod tests {
         )
     }
 
+    #[test]
+    fn sum_i16_in_i64() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int16Array::from(vec![i16::MAX, i16::MAX]));
+        generic_test_op!(
+            a,
+            DataType::Int16,
+            Sum,
+            ScalarValue::from(65534i64),
+            DataType::Int64
+        )
+    }
+
+    #[test]
+    fn sum_int64_decimal_in_int96_decimal() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int64Decimal2Array::from(vec![
+            Some(i64::MAX),
+            None,
+            Some(1),
+        ]));
+        generic_test_op!(
+            a,
+            DataType::Int64Decimal(2),
+            Sum,
+            ScalarValue::Int96Decimal(Some(i64::MAX as i128 + 1), 2),
+            DataType::Int96Decimal(2)
+        )
+    }
+
+    #[test]
+    fn sum_overflow_check() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, false)]);
+        let a = Int64Array::from(vec![i64::MAX, 1, -2]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(a)])?;
+        let sum = Sum::new(col("a", &schema)?, "bla", DataType::Int64);
+
+        // the sum of a batch is only checked against the range of the SUM at its end
+        let checked = Arc::new(sum.clone().with_overflow_check(true));
+        assert_eq!(
+            aggregate(&batch, checked)?,
+            ScalarValue::Int64(Some(i64::MAX - 1))
+        );
+
+        let a = Int64Array::from(vec![i64::MAX, 1]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(a)])?;
+        let checked = Arc::new(sum.with_overflow_check(true));
+        let err = aggregate(&batch, checked).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Arithmetic overflow: SUM is out of range of Int64"
+        );
+
+        let mut accum = SumAccumulator::try_new(&DataType::Int64, true)?;
+        accum.update(&[ScalarValue::Int64(Some(i64::MIN))])?;
+        assert!(accum.merge(&[ScalarValue::Int64(Some(-1))]).is_err());
+
+        // Int96 overflows at 2^95, far below the range of its native i128
+        let mut accum = SumAccumulator::try_new(&DataType::Int96, true)?;
+        accum.update(&[ScalarValue::Int96(Some((1 << 95) - 1))])?;
+        assert!(accum.update(&[ScalarValue::Int96(Some(1))]).is_err());
+        Ok(())
+    }
+
     fn aggregate(
         batch: &RecordBatch,
         agg: Arc<dyn AggregateExpr>,
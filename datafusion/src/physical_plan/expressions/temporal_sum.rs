@@ -0,0 +1,436 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the SUM and AVG aggregate expressions of intervals and the AVG of timestamps
+
+use std::any::Any;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::datatypes::{DataType, Field, IntervalUnit, TimeUnit};
+
+use super::format_state_name;
+use smallvec::smallvec;
+use smallvec::SmallVec;
+
+const MILLIS_PER_DAY: i128 = 24 * 3_600 * 1_000;
+
+/// SUM or AVG aggregate expression of intervals, or AVG of timestamps, whose result
+/// has the type of the values. The average of timestamps is the timestamp of their
+/// average epoch.
+///
+/// The values are summed exactly, as 96-bit integers: the months of year-month
+/// intervals, the days and the milliseconds of day-time intervals apart, and the
+/// epochs of timestamps. The average is rounded to the unit of the values, the
+/// fractions of days of day-time intervals being milliseconds.
+#[derive(Debug)]
+pub struct TemporalSum {
+    name: String,
+    data_type: DataType,
+    expr: Arc<dyn PhysicalExpr>,
+    average: bool,
+}
+
+impl TemporalSum {
+    /// Create a new SUM aggregate function of intervals
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            data_type,
+            expr,
+            average: false,
+        }
+    }
+
+    /// Create a new AVG aggregate function of intervals or timestamps
+    pub fn new_avg(
+        expr: Arc<dyn PhysicalExpr>,
+        name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        Self {
+            average: true,
+            ..Self::new(expr, name, data_type)
+        }
+    }
+}
+
+impl AggregateExpr for TemporalSum {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        let mut fields = vec![
+            Field::new(
+                &format_state_name(&self.name, "count"),
+                DataType::UInt64,
+                true,
+            ),
+            Field::new(&format_state_name(&self.name, "sum"), DataType::Int96, true),
+        ];
+        if is_day_time(&self.data_type) {
+            fields.push(Field::new(
+                &format_state_name(&self.name, "sum_millis"),
+                DataType::Int96,
+                true,
+            ));
+        }
+        Ok(fields)
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(TemporalSumAccumulator::try_new(
+            &self.data_type,
+            self.average,
+        )?))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn is_day_time(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Interval(IntervalUnit::DayTime))
+}
+
+#[derive(Debug)]
+struct TemporalSumAccumulator {
+    data_type: DataType,
+    average: bool,
+    count: u64,
+    // the months of year-month intervals, the days of day-time intervals or the
+    // epochs of timestamps
+    sum: i128,
+    // the milliseconds of day-time intervals
+    sum_millis: i128,
+}
+
+impl TemporalSumAccumulator {
+    fn try_new(data_type: &DataType, average: bool) -> Result<Self> {
+        match data_type {
+            DataType::Interval(_) => {}
+            DataType::Timestamp(_, None) if average => {}
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "{} of temporal values does not support {:?}",
+                    if average { "AVG" } else { "SUM" },
+                    other
+                )))
+            }
+        }
+        Ok(Self {
+            data_type: data_type.clone(),
+            average,
+            count: 0,
+            sum: 0,
+            sum_millis: 0,
+        })
+    }
+
+    /// The result of the aggregate, or an error if it is out of the range of its type
+    fn result(&self, sum: i128, sum_millis: i128) -> Result<ScalarValue> {
+        let overflow = || {
+            DataFusionError::Execution(format!(
+                "Arithmetic overflow: {} is out of range of {:?}",
+                if self.average { "AVG" } else { "SUM" },
+                self.data_type
+            ))
+        };
+        let epoch = || i64::try_from(sum).map_err(|_| overflow());
+        Ok(match self.data_type {
+            DataType::Interval(IntervalUnit::YearMonth) => {
+                ScalarValue::IntervalYearMonth(Some(
+                    i32::try_from(sum).map_err(|_| overflow())?,
+                ))
+            }
+            DataType::Interval(IntervalUnit::DayTime) => ScalarValue::IntervalDayTime(
+                Some(day_time(sum, sum_millis).ok_or_else(overflow)?),
+            ),
+            DataType::Timestamp(TimeUnit::Second, _) => {
+                ScalarValue::TimestampSecond(Some(epoch()?))
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
+                ScalarValue::TimestampMillisecond(Some(epoch()?))
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                ScalarValue::TimestampMicrosecond(Some(epoch()?))
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+                ScalarValue::TimestampNanosecond(Some(epoch()?))
+            }
+            _ => unreachable!(),
+        })
+    }
+}
+
+/// The parts summed of `value`, none if it is NULL
+fn value_parts(value: &ScalarValue) -> Result<Option<(i128, i128)>> {
+    Ok(match value {
+        ScalarValue::IntervalYearMonth(v) => v.map(|v| (i128::from(v), 0)),
+        ScalarValue::IntervalDayTime(v) => v.map(|v| {
+            // negative intervals are stored as the negated positive interval
+            let days = v.signum() * (v.abs() >> 32);
+            let millis = v.signum() * (v.abs() & 0xFFFF_FFFF);
+            (i128::from(days), i128::from(millis))
+        }),
+        ScalarValue::TimestampSecond(v)
+        | ScalarValue::TimestampMillisecond(v)
+        | ScalarValue::TimestampMicrosecond(v)
+        | ScalarValue::TimestampNanosecond(v) => v.map(|v| (i128::from(v), 0)),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Unexpected value {:?} of temporal sum",
+                other
+            )))
+        }
+    })
+}
+
+fn state_value(state: &ScalarValue) -> Result<i128> {
+    match state {
+        ScalarValue::UInt64(v) => Ok(i128::from(v.unwrap_or(0))),
+        ScalarValue::Int96(v) => Ok(v.unwrap_or(0)),
+        other => Err(DataFusionError::Internal(format!(
+            "Unexpected state {:?} of temporal sum",
+            other
+        ))),
+    }
+}
+
+/// `n / d` rounded half away from zero, `d` being positive
+fn div_round(n: i128, d: i128) -> i128 {
+    let (q, r) = (n / d, n % d);
+    if 2 * r.abs() >= d {
+        q + n.signum()
+    } else {
+        q
+    }
+}
+
+/// Packs days and milliseconds into an `IntervalDayTime` value, none if it is out of
+/// range. The whole days are borrowed when the signs of the parts differ, as both
+/// must have the sign of the interval, and the milliseconds that do not fit in their
+/// 32 bits are carried to the days.
+fn day_time(days: i128, millis: i128) -> Option<i64> {
+    let (mut days, mut millis) = (days, millis);
+    if days.signum() * millis.signum() < 0 {
+        let total = days * MILLIS_PER_DAY + millis;
+        days = total / MILLIS_PER_DAY;
+        millis = total % MILLIS_PER_DAY;
+    }
+    if millis.abs() > i128::from(u32::MAX) {
+        days += millis / MILLIS_PER_DAY;
+        millis %= MILLIS_PER_DAY;
+    }
+    if days.abs() > i128::from(i32::MAX) {
+        return None;
+    }
+    let sign = if days < 0 || millis < 0 { -1 } else { 1 };
+    Some(sign * (((days.abs() as i64) << 32) | millis.abs() as i64))
+}
+
+impl Accumulator for TemporalSumAccumulator {
+    fn reset(&mut self) {
+        self.count = 0;
+        self.sum = 0;
+        self.sum_millis = 0;
+    }
+
+    fn state(&self) -> Result<SmallVec<[ScalarValue; 2]>> {
+        let mut state = smallvec![
+            ScalarValue::UInt64(Some(self.count)),
+            ScalarValue::Int96(Some(self.sum)),
+        ];
+        if is_day_time(&self.data_type) {
+            state.push(ScalarValue::Int96(Some(self.sum_millis)));
+        }
+        Ok(state)
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        if let Some((sum, millis)) = value_parts(&values[0])? {
+            self.count += 1;
+            self.sum += sum;
+            self.sum_millis += millis;
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        self.count += state_value(&states[0])? as u64;
+        self.sum += state_value(&states[1])?;
+        if let Some(millis) = states.get(2) {
+            self.sum_millis += state_value(millis)?;
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        if self.count == 0 {
+            return ScalarValue::try_from(&self.data_type);
+        }
+        if !self.average {
+            return self.result(self.sum, self.sum_millis);
+        }
+        let count = i128::from(self.count);
+        if is_day_time(&self.data_type) {
+            // the remainder of the days is averaged with the milliseconds
+            let millis = self.sum_millis + self.sum % count * MILLIS_PER_DAY;
+            self.result(self.sum / count, div_round(millis, count))
+        } else {
+            self.result(div_round(self.sum, count), 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use arrow::array::{
+        ArrayRef, IntervalDayTimeArray, IntervalYearMonthArray, TimestampNanosecondArray,
+    };
+    use arrow::datatypes::Schema;
+    use arrow::record_batch::RecordBatch;
+
+    const HOUR: i64 = 3_600_000;
+
+    fn temporal_sum(a: ArrayRef, average: bool) -> Result<ScalarValue> {
+        let data_type = a.data_type().clone();
+        let schema = Schema::new(vec![Field::new("a", data_type.clone(), true)]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![a])?;
+        let expr = col("a", &schema)?;
+        let agg = match average {
+            true => TemporalSum::new_avg(expr, "bla", data_type),
+            false => TemporalSum::new(expr, "bla", data_type),
+        };
+        aggregate(&batch, Arc::new(agg))
+    }
+
+    #[test]
+    fn avg_timestamps() -> Result<()> {
+        let a: ArrayRef = Arc::new(TimestampNanosecondArray::from_opt_vec(
+            vec![Some(1_000), None, Some(2_000), Some(4_000)],
+            None,
+        ));
+        assert_eq!(
+            temporal_sum(a, true)?,
+            ScalarValue::TimestampNanosecond(Some(2_333))
+        );
+
+        let a: ArrayRef = Arc::new(TimestampNanosecondArray::from_opt_vec(
+            vec![Some(i64::MAX), Some(i64::MAX - 2)],
+            None,
+        ));
+        assert_eq!(
+            temporal_sum(a, true)?,
+            ScalarValue::TimestampNanosecond(Some(i64::MAX - 1))
+        );
+
+        let a: ArrayRef =
+            Arc::new(TimestampNanosecondArray::from_opt_vec(vec![None], None));
+        assert_eq!(
+            temporal_sum(a, true)?,
+            ScalarValue::TimestampNanosecond(None)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sum_day_time_intervals() -> Result<()> {
+        // 1 day 12 hours, 1 day 18 hours and -2 hours
+        let a: ArrayRef = Arc::new(IntervalDayTimeArray::from(vec![
+            Some((1 << 32) | 12 * HOUR),
+            Some((1 << 32) | 18 * HOUR),
+            None,
+            Some(-2 * HOUR),
+        ]));
+        assert_eq!(
+            temporal_sum(a.clone(), false)?,
+            ScalarValue::IntervalDayTime(Some((2 << 32) | 28 * HOUR))
+        );
+        // 2 days 28 hours / 3, the hours are not carried to the days
+        assert_eq!(
+            temporal_sum(a, true)?,
+            ScalarValue::IntervalDayTime(Some(25 * HOUR + 1_200_000))
+        );
+
+        // -1 day and 2 hours
+        let a: ArrayRef = Arc::new(IntervalDayTimeArray::from(vec![
+            Some(-(1 << 32)),
+            Some(2 * HOUR),
+        ]));
+        assert_eq!(
+            temporal_sum(a, false)?,
+            ScalarValue::IntervalDayTime(Some(-22 * HOUR))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sum_year_month_intervals() -> Result<()> {
+        let a: ArrayRef =
+            Arc::new(IntervalYearMonthArray::from(vec![Some(1), Some(2), None]));
+        assert_eq!(
+            temporal_sum(a.clone(), false)?,
+            ScalarValue::IntervalYearMonth(Some(3))
+        );
+        assert_eq!(
+            temporal_sum(a, true)?,
+            ScalarValue::IntervalYearMonth(Some(2))
+        );
+
+        let a: ArrayRef = Arc::new(IntervalYearMonthArray::from(vec![i32::MAX, 1]));
+        let err = temporal_sum(a, false).unwrap_err().to_string();
+        assert!(err.contains("Arithmetic overflow: SUM"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_temporal_sum() -> Result<()> {
+        let data_type = DataType::Interval(IntervalUnit::DayTime);
+        let mut acc = TemporalSumAccumulator::try_new(&data_type, true)?;
+        acc.update(&[ScalarValue::IntervalDayTime(Some(1 << 32))])?;
+        let mut other = TemporalSumAccumulator::try_new(&data_type, true)?;
+        other.update(&[ScalarValue::IntervalDayTime(Some(2 << 32))])?;
+        acc.merge(&other.state()?)?;
+        assert_eq!(
+            acc.evaluate()?,
+            ScalarValue::IntervalDayTime(Some((1 << 32) | 12 * HOUR))
+        );
+        Ok(())
+    }
+}
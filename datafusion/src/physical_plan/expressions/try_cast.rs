@@ -19,16 +19,21 @@ use std::any::Any;
 use std::fmt;
 use std::sync::Arc;
 
-use super::ColumnarValue;
+use super::{cast_array_in_zone, ColumnarValue};
+use crate::cube_ext::temporal::Zone;
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::PhysicalExpr;
 use crate::scalar::ScalarValue;
+use arrow::array::{new_null_array, Array};
 use arrow::compute;
-use arrow::compute::kernels;
+use arrow::compute::CastOptions;
 use arrow::datatypes::{DataType, Schema};
 use arrow::record_batch::RecordBatch;
 use compute::can_cast_types;
 
+/// Options of the cast kernel that produce NULL for values that cannot be cast
+const TRY_CAST_OPTIONS: CastOptions = CastOptions { safe: true };
+
 /// TRY_CAST expression casts an expression to a specific data type and retuns NULL on invalid cast
 #[derive(Debug)]
 pub struct TryCastExpr {
@@ -36,12 +41,25 @@ pub struct TryCastExpr {
     expr: Arc<dyn PhysicalExpr>,
     /// The data type to cast to
     cast_type: DataType,
+    /// Time zone of strings without an offset cast to timestamps
+    time_zone: Zone,
 }
 
 impl TryCastExpr {
     /// Create a new CastExpr
     pub fn new(expr: Arc<dyn PhysicalExpr>, cast_type: DataType) -> Self {
-        Self { expr, cast_type }
+        Self {
+            expr,
+            cast_type,
+            time_zone: Zone::utc(),
+        }
+    }
+
+    /// Interpret strings without an offset cast to timestamps as local times of
+    /// `time_zone` instead of UTC
+    pub fn with_time_zone(mut self, time_zone: Zone) -> Self {
+        self.time_zone = time_zone;
+        self
     }
 
     /// The expression to cast
@@ -75,16 +93,34 @@ impl PhysicalExpr for TryCastExpr {
         Ok(true)
     }
 
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        self.expr.column_indices()
+    }
+
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let value = self.expr.evaluate(batch)?;
         match value {
-            ColumnarValue::Array(array) => Ok(ColumnarValue::Array(kernels::cast::cast(
+            // untyped NULLs can be cast to any type
+            ColumnarValue::Array(array) if array.data_type() == &DataType::Null => Ok(
+                ColumnarValue::Array(new_null_array(&self.cast_type, array.len())),
+            ),
+            ColumnarValue::Scalar(ScalarValue::Null) => Ok(ColumnarValue::Scalar(
+                ScalarValue::try_from(&self.cast_type)?,
+            )),
+            ColumnarValue::Array(array) => Ok(ColumnarValue::Array(cast_array_in_zone(
                 &array,
                 &self.cast_type,
+                &TRY_CAST_OPTIONS,
+                &self.time_zone,
             )?)),
             ColumnarValue::Scalar(scalar) => {
                 let scalar_array = scalar.to_array();
-                let cast_array = kernels::cast::cast(&scalar_array, &self.cast_type)?;
+                let cast_array = cast_array_in_zone(
+                    &scalar_array,
+                    &self.cast_type,
+                    &TRY_CAST_OPTIONS,
+                    &self.time_zone,
+                )?;
                 let cast_scalar = ScalarValue::try_from_array(&cast_array, 0)?;
                 Ok(ColumnarValue::Scalar(cast_scalar))
             }
@@ -100,12 +136,26 @@ pub fn try_cast(
     expr: Arc<dyn PhysicalExpr>,
     input_schema: &Schema,
     cast_type: DataType,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    try_cast_in_zone(expr, input_schema, cast_type, Zone::utc())
+}
+
+/// Return a PhysicalExpression representing `expr` casted to `cast_type` like
+/// [try_cast], where strings without an offset cast to timestamps are local times of
+/// `time_zone`
+pub fn try_cast_in_zone(
+    expr: Arc<dyn PhysicalExpr>,
+    input_schema: &Schema,
+    cast_type: DataType,
+    time_zone: Zone,
 ) -> Result<Arc<dyn PhysicalExpr>> {
     let expr_type = expr.data_type(input_schema)?;
     if expr_type == cast_type {
         Ok(expr.clone())
-    } else if can_cast_types(&expr_type, &cast_type) {
-        Ok(Arc::new(TryCastExpr::new(expr, cast_type)))
+    } else if expr_type == DataType::Null || can_cast_types(&expr_type, &cast_type) {
+        Ok(Arc::new(
+            TryCastExpr::new(expr, cast_type).with_time_zone(time_zone),
+        ))
     } else {
         Err(DataFusionError::Internal(format!(
             "Unsupported CAST from {:?} to {:?}",
@@ -36,10 +36,6 @@ use arrow::record_batch::RecordBatch;
 
 use async_trait::async_trait;
 
-use crate::logical_plan::Operator;
-use crate::physical_plan::expressions::{
-    BinaryExpr, CastExpr, Column, Literal, NotExpr, TryCastExpr,
-};
 use futures::stream::{Stream, StreamExt};
 
 /// FilterExec evaluates a boolean predicate against all input batches to determine which rows to
@@ -119,19 +115,7 @@ impl ExecutionPlan for FilterExec {
     }
 
     fn output_hints(&self) -> OptimizerHints {
-        let inputs_hints = self.input.output_hints();
-
-        let mut single_value_columns = inputs_hints.single_value_columns;
-        for c in extract_single_value_columns(self.predicate.as_ref()) {
-            single_value_columns.push(c.index());
-        }
-        single_value_columns.sort_unstable();
-        single_value_columns.dedup();
-
-        OptimizerHints {
-            sort_order: inputs_hints.sort_order,
-            single_value_columns,
-        }
+        self.input.output_hints().filter(self.predicate.as_ref())
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
@@ -155,59 +139,6 @@ impl ExecutionPlan for FilterExec {
     }
 }
 
-fn extract_single_value_columns(predicate: &dyn PhysicalExpr) -> Vec<&Column> {
-    let mut columns = Vec::new();
-    extract_single_value_columns_impl(predicate, &mut columns);
-    columns
-}
-
-fn extract_single_value_columns_impl<'a>(
-    predicate: &'a dyn PhysicalExpr,
-    out: &mut Vec<&'a Column>,
-) {
-    // TODO: more sophisticated expressions.
-    let is_constant = |mut e: &dyn PhysicalExpr| loop {
-        if e.as_any().is::<Literal>() {
-            return true;
-        } else if let Some(c) = e.as_any().downcast_ref::<CastExpr>() {
-            e = c.expr().as_ref();
-        } else if let Some(c) = e.as_any().downcast_ref::<TryCastExpr>() {
-            e = c.expr().as_ref();
-        } else {
-            return false;
-        }
-    };
-
-    let predicate = predicate.as_any();
-    if let Some(binary) = predicate.downcast_ref::<BinaryExpr>() {
-        match binary.op() {
-            Operator::And => {
-                extract_single_value_columns_impl(binary.left().as_ref(), out);
-                extract_single_value_columns_impl(binary.right().as_ref(), out);
-            }
-            Operator::Eq => {
-                let mut left = binary.left();
-                let mut right = binary.right();
-                if !left.as_any().is::<Column>() {
-                    std::mem::swap(&mut left, &mut right);
-                }
-                let left = left.as_any().downcast_ref::<Column>();
-                if left.is_some() && is_constant(right.as_ref()) {
-                    out.push(left.unwrap());
-                }
-            }
-            _ => {}
-        }
-    } else if predicate.is::<Column>() {
-        out.push(predicate.downcast_ref::<Column>().unwrap());
-    } else if predicate.is::<NotExpr>() {
-        let inner = predicate.downcast_ref::<NotExpr>().unwrap().arg();
-        if inner.as_any().is::<Column>() {
-            out.push(inner.as_any().downcast_ref::<Column>().unwrap());
-        }
-    }
-}
-
 /// The FilterExec streams wraps the input iterator and applies the predicate expression to
 /// determine which rows to include in its output batches
 struct FilterExecStream {
@@ -33,12 +33,17 @@ use super::{
     type_coercion::{coerce, data_types},
     ColumnarValue, PhysicalExpr,
 };
+use crate::cube_ext::temporal::DatePart;
 use crate::execution::context::ExecutionContextState;
 use crate::physical_plan::array_expressions;
 use crate::physical_plan::datetime_expressions;
+#[cfg(feature = "encoding_expressions")]
+use crate::physical_plan::encoding_expressions;
 use crate::physical_plan::expressions::{
-    cast_column, nullif_func, DEFAULT_DATAFUSION_CAST_OPTIONS, SUPPORTED_NULLIF_TYPES,
+    cast_column, nullif_func, Literal, DEFAULT_DATAFUSION_CAST_OPTIONS,
+    SUPPORTED_NULLIF_TYPES,
 };
+use crate::physical_plan::inet_expressions;
 use crate::physical_plan::math_expressions;
 use crate::physical_plan::string_expressions;
 use crate::{
@@ -68,6 +73,9 @@ pub enum Signature {
     // A function such as `array` is `VariadicEqual`
     // The first argument decides the type used for coercion
     VariadicEqual,
+    /// arbitrary number of arguments of arbitrary types
+    // A function such as `count(distinct a, b)` is `VariadicAny`
+    VariadicAny,
     /// fixed number of arguments of an arbitrary but equal type out of a list of valid types
     // A function of one argument of f64 is `Uniform(1, vec![DataType::Float64])`
     // A function of one argument of f64 or f32 is `Uniform(1, vec![DataType::Float32, DataType::Float64])`
@@ -80,6 +88,22 @@ pub enum Signature {
     OneOf(Vec<Signature>),
 }
 
+/// A function's volatility, which defines the optimizations that may be applied to its calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Volatility {
+    /// Always returns the same result for the same arguments, e.g. `abs`. Calls with
+    /// constant arguments can be evaluated at planning time and repeated calls can be
+    /// deduplicated, although there is no common subexpression elimination yet.
+    Immutable,
+    /// Returns the same result for the same arguments within a single query, e.g. `now`.
+    /// Calls are bound once per query at planning time.
+    Stable,
+    /// May return a different result on every evaluation, e.g. `random`. Calls are never
+    /// folded, deduplicated or moved to a place where they are evaluated a different
+    /// number of times.
+    Volatile,
+}
+
 /// Scalar function
 ///
 /// The Fn param is the wrapped function but be aware that the function will
@@ -139,6 +163,8 @@ pub enum BuiltinScalarFunction {
     // string functions
     /// construct an array from columns
     Array,
+    /// array_to_string
+    ArrayToString,
     /// ascii
     Ascii,
     /// bit_length
@@ -149,20 +175,50 @@ pub enum BuiltinScalarFunction {
     CharacterLength,
     /// chr
     Chr,
+    /// cidr_contains
+    CidrContains,
+    /// collation_key
+    CollationKey,
+    /// crc32
+    CRC32,
     /// concat
     Concat,
     /// concat_ws
     ConcatWithSeparator,
+    /// connection_id
+    ConnectionId,
     /// convert_tz
     ConvertTz,
+    /// current_date
+    CurrentDate,
+    /// current_time
+    CurrentTime,
+    /// database
+    Database,
     /// date_part
     DatePart,
     /// date_trunc
     DateTrunc,
+    /// decode
+    Decode,
+    /// encode
+    Encode,
+    /// from_hex
+    FromHex,
+    /// inet_aton
+    InetAton,
+    /// inet_ntoa
+    InetNtoa,
     /// initcap
     InitCap,
+    /// isoweek
+    IsoWeek,
     /// left
     Left,
+    /// localtime
+    LocalTime,
+    /// localtimestamp
+    LocalTimestamp,
     /// lpad
     Lpad,
     /// lower
@@ -191,6 +247,10 @@ pub enum BuiltinScalarFunction {
     Rpad,
     /// rtrim
     Rtrim,
+    /// sha1
+    SHA1,
+    /// sha2
+    SHA2,
     /// sha224
     SHA224,
     /// sha256
@@ -203,6 +263,8 @@ pub enum BuiltinScalarFunction {
     SplitPart,
     /// starts_with
     StartsWith,
+    /// string_to_array
+    StringToArray,
     /// strpos
     Strpos,
     /// substr
@@ -225,8 +287,20 @@ pub enum BuiltinScalarFunction {
     Trim,
     /// upper
     Upper,
+    /// user
+    User,
     /// regexp_match
     RegexpMatch,
+    /// uuid
+    Uuid,
+    /// version
+    Version,
+    /// week
+    Week,
+    /// xxhash64
+    XXHash64,
+    /// yearweek
+    YearWeek,
 }
 
 impl BuiltinScalarFunction {
@@ -235,15 +309,97 @@ impl BuiltinScalarFunction {
     fn supports_zero_argument(&self) -> bool {
         matches!(
             self,
-            BuiltinScalarFunction::Random | BuiltinScalarFunction::Now
+            BuiltinScalarFunction::Random
+                | BuiltinScalarFunction::Now
+                | BuiltinScalarFunction::CurrentDate
+                | BuiltinScalarFunction::CurrentTime
+                | BuiltinScalarFunction::LocalTime
+                | BuiltinScalarFunction::LocalTimestamp
+                | BuiltinScalarFunction::Uuid
+                | BuiltinScalarFunction::ConnectionId
+                | BuiltinScalarFunction::Database
+                | BuiltinScalarFunction::User
+                | BuiltinScalarFunction::Version
+        )
+    }
+
+    /// Whether the function returns information about the session, e.g. `version()`,
+    /// which is bound from the configuration of the session at plan time.
+    pub fn is_session_information(&self) -> bool {
+        matches!(
+            self,
+            BuiltinScalarFunction::ConnectionId
+                | BuiltinScalarFunction::Database
+                | BuiltinScalarFunction::User
+                | BuiltinScalarFunction::Version
         )
     }
+
+    /// Returns the [Volatility] of the function.
+    pub fn volatility(&self) -> Volatility {
+        match self {
+            BuiltinScalarFunction::Random | BuiltinScalarFunction::Uuid => {
+                Volatility::Volatile
+            }
+            BuiltinScalarFunction::Now
+            | BuiltinScalarFunction::CurrentDate
+            | BuiltinScalarFunction::CurrentTime
+            | BuiltinScalarFunction::LocalTime
+            | BuiltinScalarFunction::LocalTimestamp
+            | BuiltinScalarFunction::ConnectionId
+            | BuiltinScalarFunction::Database
+            | BuiltinScalarFunction::User
+            | BuiltinScalarFunction::Version => Volatility::Stable,
+            _ => Volatility::Immutable,
+        }
+    }
+
+    /// Whether the result can be null, given whether each argument can be null
+    pub fn nullable(&self, args_nullable: &[bool]) -> bool {
+        match self {
+            BuiltinScalarFunction::Random
+            | BuiltinScalarFunction::Uuid
+            | BuiltinScalarFunction::Now
+            | BuiltinScalarFunction::CurrentDate
+            | BuiltinScalarFunction::CurrentTime
+            | BuiltinScalarFunction::LocalTime
+            | BuiltinScalarFunction::LocalTimestamp
+            // skips the null arguments
+            | BuiltinScalarFunction::Concat => false,
+            // only null for null arguments
+            BuiltinScalarFunction::Abs
+            | BuiltinScalarFunction::Ceil
+            | BuiltinScalarFunction::Floor
+            | BuiltinScalarFunction::Round
+            | BuiltinScalarFunction::Signum
+            | BuiltinScalarFunction::Trunc
+            | BuiltinScalarFunction::BitLength
+            | BuiltinScalarFunction::CharacterLength
+            | BuiltinScalarFunction::OctetLength
+            | BuiltinScalarFunction::Lower
+            | BuiltinScalarFunction::Upper
+            | BuiltinScalarFunction::Btrim
+            | BuiltinScalarFunction::Ltrim
+            | BuiltinScalarFunction::Rtrim
+            | BuiltinScalarFunction::Trim
+            | BuiltinScalarFunction::Reverse
+            | BuiltinScalarFunction::MD5 => args_nullable.contains(&true),
+            _ => true,
+        }
+    }
 }
 
 impl fmt::Display for BuiltinScalarFunction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // lowercase of the debug.
-        write!(f, "{}", format!("{:?}", self).to_lowercase())
+        match self {
+            // niladic functions keep their SQL names, which are also used to find
+            // them when they are evaluated
+            BuiltinScalarFunction::CurrentDate => write!(f, "current_date"),
+            BuiltinScalarFunction::CurrentTime => write!(f, "current_time"),
+            BuiltinScalarFunction::ConnectionId => write!(f, "connection_id"),
+            // lowercase of the debug.
+            _ => write!(f, "{}", format!("{:?}", self).to_lowercase()),
+        }
     }
 }
 
@@ -273,6 +429,7 @@ impl FromStr for BuiltinScalarFunction {
 
             // string functions
             "array" => BuiltinScalarFunction::Array,
+            "array_to_string" | "array_join" => BuiltinScalarFunction::ArrayToString,
             "ascii" => BuiltinScalarFunction::Ascii,
             "bit_length" => BuiltinScalarFunction::BitLength,
             "btrim" => BuiltinScalarFunction::Btrim,
@@ -280,13 +437,29 @@ impl FromStr for BuiltinScalarFunction {
             "character_length" => BuiltinScalarFunction::CharacterLength,
             "concat" => BuiltinScalarFunction::Concat,
             "concat_ws" => BuiltinScalarFunction::ConcatWithSeparator,
+            "connection_id" => BuiltinScalarFunction::ConnectionId,
             "convert_tz" => BuiltinScalarFunction::ConvertTz,
+            "current_date" => BuiltinScalarFunction::CurrentDate,
+            "current_time" => BuiltinScalarFunction::CurrentTime,
+            "current_timestamp" => BuiltinScalarFunction::Now,
             "chr" => BuiltinScalarFunction::Chr,
+            "cidr_contains" => BuiltinScalarFunction::CidrContains,
+            "collation_key" => BuiltinScalarFunction::CollationKey,
+            "crc32" => BuiltinScalarFunction::CRC32,
+            "database" | "schema" => BuiltinScalarFunction::Database,
             "date_part" => BuiltinScalarFunction::DatePart,
             "date_trunc" => BuiltinScalarFunction::DateTrunc,
+            "decode" => BuiltinScalarFunction::Decode,
+            "encode" => BuiltinScalarFunction::Encode,
+            "from_hex" => BuiltinScalarFunction::FromHex,
+            "inet_aton" => BuiltinScalarFunction::InetAton,
+            "inet_ntoa" => BuiltinScalarFunction::InetNtoa,
             "initcap" => BuiltinScalarFunction::InitCap,
+            "isoweek" => BuiltinScalarFunction::IsoWeek,
             "left" => BuiltinScalarFunction::Left,
             "length" => BuiltinScalarFunction::CharacterLength,
+            "localtime" => BuiltinScalarFunction::LocalTime,
+            "localtimestamp" => BuiltinScalarFunction::LocalTimestamp,
             "lower" => BuiltinScalarFunction::Lower,
             "lpad" => BuiltinScalarFunction::Lpad,
             "ltrim" => BuiltinScalarFunction::Ltrim,
@@ -301,11 +474,14 @@ impl FromStr for BuiltinScalarFunction {
             "right" => BuiltinScalarFunction::Right,
             "rpad" => BuiltinScalarFunction::Rpad,
             "rtrim" => BuiltinScalarFunction::Rtrim,
+            "sha1" => BuiltinScalarFunction::SHA1,
+            "sha2" => BuiltinScalarFunction::SHA2,
             "sha224" => BuiltinScalarFunction::SHA224,
             "sha256" => BuiltinScalarFunction::SHA256,
             "sha384" => BuiltinScalarFunction::SHA384,
             "sha512" => BuiltinScalarFunction::SHA512,
             "split_part" => BuiltinScalarFunction::SplitPart,
+            "string_to_array" => BuiltinScalarFunction::StringToArray,
             "starts_with" => BuiltinScalarFunction::StartsWith,
             "strpos" => BuiltinScalarFunction::Strpos,
             "substr" => BuiltinScalarFunction::Substr,
@@ -318,7 +494,16 @@ impl FromStr for BuiltinScalarFunction {
             "translate" => BuiltinScalarFunction::Translate,
             "trim" => BuiltinScalarFunction::Trim,
             "upper" => BuiltinScalarFunction::Upper,
+            "user" | "current_user" | "session_user" | "system_user" => {
+                BuiltinScalarFunction::User
+            }
             "regexp_match" => BuiltinScalarFunction::RegexpMatch,
+            "uuid" => BuiltinScalarFunction::Uuid,
+            "gen_random_uuid" => BuiltinScalarFunction::Uuid,
+            "version" => BuiltinScalarFunction::Version,
+            "week" => BuiltinScalarFunction::Week,
+            "xxhash64" => BuiltinScalarFunction::XXHash64,
+            "yearweek" => BuiltinScalarFunction::YearWeek,
             _ => {
                 return Err(DataFusionError::Plan(format!(
                     "There is no built-in function named {}",
@@ -351,6 +536,18 @@ make_utf8_to_return_type!(utf8_to_str_type, DataType::LargeUtf8, DataType::Utf8)
 make_utf8_to_return_type!(utf8_to_int_type, DataType::Int64, DataType::Int32);
 make_utf8_to_return_type!(utf8_to_binary_type, DataType::Binary, DataType::Binary);
 
+/// Whether `date_part` can extract parts of values of type `t`
+fn is_date_part_input(t: &DataType) -> bool {
+    match t {
+        DataType::Null
+        | DataType::Date32
+        | DataType::Date64
+        | DataType::Timestamp(_, _) => true,
+        DataType::Dictionary(_, value_type) => is_date_part_input(value_type),
+        _ => false,
+    }
+}
+
 /// Returns the datatype of the scalar function
 pub fn return_type(
     fun: &BuiltinScalarFunction,
@@ -359,8 +556,9 @@ pub fn return_type(
     // Note that this function *must* return the same type that the respective physical expression returns
     // or the execution panics.
 
-    // verify that this is a valid set of data types for this function
-    data_types(arg_types, &signature(fun))?;
+    // verify that this is a valid set of data types for this function, the return type
+    // is the one of the arguments coerced to them
+    let arg_types = &data_types(arg_types, &signature(fun))?;
 
     // the return type of the built in function.
     // Some built-in functions' return type depends on the incoming type.
@@ -376,20 +574,56 @@ pub fn return_type(
             utf8_to_int_type(&arg_types[0], "character_length")
         }
         BuiltinScalarFunction::Chr => Ok(DataType::Utf8),
+        BuiltinScalarFunction::CRC32 => Ok(DataType::Int64),
         BuiltinScalarFunction::Concat => Ok(DataType::Utf8),
         BuiltinScalarFunction::ConcatWithSeparator => Ok(DataType::Utf8),
         BuiltinScalarFunction::ConvertTz => {
             Ok(DataType::Timestamp(TimeUnit::Nanosecond, None))
         }
-        BuiltinScalarFunction::DatePart => Ok(DataType::Int32),
+        BuiltinScalarFunction::DatePart => match (&arg_types[0], &arg_types[1]) {
+            // the type of the epoch differs, see [return_type_of_args]
+            (DataType::Utf8, t) if is_date_part_input(t) => Ok(DataType::Int32),
+            (part, t) => Err(DataFusionError::Plan(format!(
+                "The date_part function expects a Utf8 part and dates or timestamps, \
+                 got {:?} and {:?}",
+                part, t
+            ))),
+        },
+        BuiltinScalarFunction::IsoWeek
+        | BuiltinScalarFunction::Week
+        | BuiltinScalarFunction::YearWeek => Ok(DataType::Int32),
         BuiltinScalarFunction::DateTrunc => {
             Ok(DataType::Timestamp(TimeUnit::Nanosecond, None))
         }
+        #[cfg(feature = "encoding_expressions")]
+        BuiltinScalarFunction::Encode => {
+            Ok(encoding_expressions::encode_return_type(&arg_types[0]))
+        }
+        #[cfg(feature = "encoding_expressions")]
+        BuiltinScalarFunction::Decode | BuiltinScalarFunction::FromHex => {
+            Ok(encoding_expressions::decode_return_type(&arg_types[0]))
+        }
+        #[cfg(not(feature = "encoding_expressions"))]
+        BuiltinScalarFunction::Encode
+        | BuiltinScalarFunction::Decode
+        | BuiltinScalarFunction::FromHex => Err(DataFusionError::Internal(format!(
+            "function {} requires compilation with feature flag: encoding_expressions.",
+            fun
+        ))),
+        BuiltinScalarFunction::CidrContains => Ok(DataType::Boolean),
+        BuiltinScalarFunction::CollationKey => {
+            utf8_to_str_type(&arg_types[0], "collation_key")
+        }
+        BuiltinScalarFunction::InetAton => Ok(DataType::Int64),
+        BuiltinScalarFunction::InetNtoa => Ok(DataType::Utf8),
         BuiltinScalarFunction::InitCap => utf8_to_str_type(&arg_types[0], "initcap"),
         BuiltinScalarFunction::Left => utf8_to_str_type(&arg_types[0], "left"),
         BuiltinScalarFunction::Lower => utf8_to_str_type(&arg_types[0], "lower"),
         BuiltinScalarFunction::Lpad => utf8_to_str_type(&arg_types[0], "lpad"),
         BuiltinScalarFunction::Ltrim => utf8_to_str_type(&arg_types[0], "ltrim"),
+        BuiltinScalarFunction::MD5 if arg_types[0] == DataType::Binary => {
+            Ok(DataType::Utf8)
+        }
         BuiltinScalarFunction::MD5 => utf8_to_str_type(&arg_types[0], "md5"),
         BuiltinScalarFunction::NullIf => {
             // NULLIF has two args and they might get coerced, get a preview of this
@@ -400,6 +634,12 @@ pub fn return_type(
             utf8_to_int_type(&arg_types[0], "octet_length")
         }
         BuiltinScalarFunction::Random => Ok(DataType::Float64),
+        BuiltinScalarFunction::Uuid => Ok(DataType::Utf8),
+        BuiltinScalarFunction::Database
+        | BuiltinScalarFunction::User
+        | BuiltinScalarFunction::Version => Ok(DataType::Utf8),
+        BuiltinScalarFunction::ConnectionId => Ok(DataType::UInt64),
+        BuiltinScalarFunction::XXHash64 => Ok(DataType::Int64),
         BuiltinScalarFunction::RegexpReplace => {
             utf8_to_str_type(&arg_types[0], "regex_replace")
         }
@@ -409,11 +649,42 @@ pub fn return_type(
         BuiltinScalarFunction::Right => utf8_to_str_type(&arg_types[0], "right"),
         BuiltinScalarFunction::Rpad => utf8_to_str_type(&arg_types[0], "rpad"),
         BuiltinScalarFunction::Rtrim => utf8_to_str_type(&arg_types[0], "rtrimp"),
+        BuiltinScalarFunction::SHA1 | BuiltinScalarFunction::SHA2 => Ok(DataType::Utf8),
+        BuiltinScalarFunction::SHA224
+        | BuiltinScalarFunction::SHA256
+        | BuiltinScalarFunction::SHA384
+        | BuiltinScalarFunction::SHA512
+            if arg_types[0] == DataType::Binary =>
+        {
+            Ok(DataType::Binary)
+        }
         BuiltinScalarFunction::SHA224 => utf8_to_binary_type(&arg_types[0], "sha224"),
         BuiltinScalarFunction::SHA256 => utf8_to_binary_type(&arg_types[0], "sha256"),
         BuiltinScalarFunction::SHA384 => utf8_to_binary_type(&arg_types[0], "sha384"),
         BuiltinScalarFunction::SHA512 => utf8_to_binary_type(&arg_types[0], "sha512"),
         BuiltinScalarFunction::SplitPart => utf8_to_str_type(&arg_types[0], "split_part"),
+        BuiltinScalarFunction::StringToArray => Ok(DataType::List(Box::new(Field::new(
+            "item",
+            DataType::Utf8,
+            true,
+        )))),
+        BuiltinScalarFunction::ArrayToString => {
+            let is_string = |t: &DataType| {
+                matches!(t, DataType::Utf8 | DataType::LargeUtf8 | DataType::Null)
+            };
+            match &arg_types[0] {
+                DataType::List(_) | DataType::FixedSizeList(_, _)
+                    if arg_types[1..].iter().all(is_string) =>
+                {
+                    Ok(DataType::Utf8)
+                }
+                _ => Err(DataFusionError::Plan(format!(
+                    "The array_to_string function expects a list and string \
+                     delimiters, got {:?}",
+                    arg_types
+                ))),
+            }
+        }
         BuiltinScalarFunction::StartsWith => Ok(DataType::Boolean),
         BuiltinScalarFunction::Strpos => utf8_to_int_type(&arg_types[0], "strpos"),
         BuiltinScalarFunction::Substr => utf8_to_str_type(&arg_types[0], "substr"),
@@ -421,6 +692,8 @@ pub fn return_type(
             DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
                 DataType::Utf8
             }
+            DataType::Binary => DataType::Utf8,
+            DataType::LargeBinary => DataType::LargeUtf8,
             _ => {
                 // this error is internal as `data_types` should have captured this.
                 return Err(DataFusionError::Internal(
@@ -440,7 +713,13 @@ pub fn return_type(
         BuiltinScalarFunction::ToTimestampSeconds => {
             Ok(DataType::Timestamp(TimeUnit::Second, None))
         }
-        BuiltinScalarFunction::Now => Ok(DataType::Timestamp(TimeUnit::Nanosecond, None)),
+        BuiltinScalarFunction::Now | BuiltinScalarFunction::LocalTimestamp => {
+            Ok(DataType::Timestamp(TimeUnit::Nanosecond, None))
+        }
+        BuiltinScalarFunction::CurrentDate => Ok(DataType::Date32),
+        BuiltinScalarFunction::CurrentTime | BuiltinScalarFunction::LocalTime => {
+            Ok(DataType::Time64(TimeUnit::Nanosecond))
+        }
         BuiltinScalarFunction::Translate => utf8_to_str_type(&arg_types[0], "translate"),
         BuiltinScalarFunction::Trim => utf8_to_str_type(&arg_types[0], "trim"),
         BuiltinScalarFunction::Upper => utf8_to_str_type(&arg_types[0], "upper"),
@@ -491,6 +770,27 @@ pub fn return_type(
     }
 }
 
+/// Returns the datatype of the scalar function applied to arguments of `arg_types`,
+/// where `literals` holds the values of the constant arguments. Only the type of
+/// `date_part` depends on them: the epoch is an Int64, as it overflows an Int32 after
+/// 2038, while the other parts are Int32.
+pub fn return_type_of_args(
+    fun: &BuiltinScalarFunction,
+    arg_types: &[DataType],
+    literals: &[Option<&ScalarValue>],
+) -> Result<DataType> {
+    let data_type = return_type(fun, arg_types)?;
+    match (fun, literals.first()) {
+        (BuiltinScalarFunction::DatePart, Some(Some(ScalarValue::Utf8(Some(part))))) => {
+            Ok(part
+                .parse::<DatePart>()
+                .map(|part| part.data_type())
+                .unwrap_or(data_type))
+        }
+        _ => Ok(data_type),
+    }
+}
+
 #[cfg(feature = "crypto_expressions")]
 macro_rules! invoke_if_crypto_expressions_feature_flag {
     ($FUNC:ident, $NAME:expr) => {{
@@ -511,6 +811,25 @@ macro_rules! invoke_if_crypto_expressions_feature_flag {
     };
 }
 
+#[cfg(feature = "encoding_expressions")]
+macro_rules! invoke_if_encoding_expressions_feature_flag {
+    ($FUNC:ident, $NAME:expr) => {
+        encoding_expressions::$FUNC
+    };
+}
+
+#[cfg(not(feature = "encoding_expressions"))]
+macro_rules! invoke_if_encoding_expressions_feature_flag {
+    ($FUNC:ident, $NAME:expr) => {
+        |_: &[ColumnarValue]| -> Result<ColumnarValue> {
+            Err(DataFusionError::Internal(format!(
+                "function {} requires compilation with feature flag: encoding_expressions.",
+                $NAME
+            )))
+        }
+    };
+}
+
 #[cfg(feature = "regex_expressions")]
 macro_rules! invoke_if_regex_expressions_feature_flag {
     ($FUNC:ident, $T:tt, $NAME:expr) => {{
@@ -570,7 +889,17 @@ pub fn create_physical_fun(
         BuiltinScalarFunction::Ln => Arc::new(math_expressions::ln),
         BuiltinScalarFunction::Log10 => Arc::new(math_expressions::log10),
         BuiltinScalarFunction::Log2 => Arc::new(math_expressions::log2),
-        BuiltinScalarFunction::Random => Arc::new(math_expressions::random),
+        BuiltinScalarFunction::Random => {
+            math_expressions::make_random(ctx_state.config.random_seed())
+        }
+        BuiltinScalarFunction::Uuid => {
+            math_expressions::make_uuid(ctx_state.config.random_seed())
+        }
+        // information functions
+        BuiltinScalarFunction::ConnectionId
+        | BuiltinScalarFunction::Database
+        | BuiltinScalarFunction::User
+        | BuiltinScalarFunction::Version => create_session_info_fun(fun, ctx_state)?,
         BuiltinScalarFunction::Round => Arc::new(math_expressions::round),
         BuiltinScalarFunction::Signum => Arc::new(math_expressions::signum),
         BuiltinScalarFunction::Sin => Arc::new(math_expressions::sin),
@@ -579,6 +908,12 @@ pub fn create_physical_fun(
         BuiltinScalarFunction::Trunc => Arc::new(math_expressions::trunc),
         // string functions
         BuiltinScalarFunction::Array => Arc::new(array_expressions::array),
+        BuiltinScalarFunction::ArrayToString => {
+            make_scalar_function(array_expressions::array_to_string)
+        }
+        BuiltinScalarFunction::StringToArray => {
+            make_scalar_function(array_expressions::string_to_array)
+        }
         BuiltinScalarFunction::Ascii => Arc::new(|args| match args[0].data_type() {
             DataType::Utf8 => {
                 make_scalar_function(string_expressions::ascii::<i32>)(args)
@@ -646,17 +981,103 @@ pub fn create_physical_fun(
         BuiltinScalarFunction::ConcatWithSeparator => {
             Arc::new(|args| make_scalar_function(string_expressions::concat_ws)(args))
         }
-        BuiltinScalarFunction::DatePart => Arc::new(datetime_expressions::date_part),
+        BuiltinScalarFunction::DatePart => {
+            let time_zone = ctx_state.execution_props.time_zone;
+            Arc::new(move |args| {
+                datetime_expressions::date_part_in_zone(args, &time_zone)
+            })
+        }
         BuiltinScalarFunction::DateTrunc => Arc::new(datetime_expressions::date_trunc),
-        BuiltinScalarFunction::Now => {
-            // bind value for now at plan time
-            Arc::new(datetime_expressions::make_now(
-                ctx_state.execution_props.query_execution_start_time,
-            ))
+        BuiltinScalarFunction::IsoWeek => {
+            let time_zone = ctx_state.execution_props.time_zone;
+            make_scalar_function(move |args: &[ArrayRef]| {
+                datetime_expressions::iso_week(args, &time_zone)
+            })
+        }
+        BuiltinScalarFunction::Week => {
+            let time_zone = ctx_state.execution_props.time_zone;
+            make_scalar_function(move |args: &[ArrayRef]| {
+                datetime_expressions::week(args, &time_zone)
+            })
+        }
+        BuiltinScalarFunction::YearWeek => {
+            let time_zone = ctx_state.execution_props.time_zone;
+            make_scalar_function(move |args: &[ArrayRef]| {
+                datetime_expressions::year_week(args, &time_zone)
+            })
+        }
+        BuiltinScalarFunction::Decode => Arc::new(
+            invoke_if_encoding_expressions_feature_flag!(decode, "decode"),
+        ),
+        BuiltinScalarFunction::Encode => Arc::new(
+            invoke_if_encoding_expressions_feature_flag!(encode, "encode"),
+        ),
+        BuiltinScalarFunction::FromHex => Arc::new(
+            invoke_if_encoding_expressions_feature_flag!(from_hex, "from_hex"),
+        ),
+        BuiltinScalarFunction::Now
+        | BuiltinScalarFunction::CurrentDate
+        | BuiltinScalarFunction::CurrentTime
+        | BuiltinScalarFunction::LocalTime
+        | BuiltinScalarFunction::LocalTimestamp => {
+            create_current_time_fun(fun, ctx_state, None)?
         }
         BuiltinScalarFunction::ConvertTz => {
             Arc::new(|args| make_scalar_function(datetime_expressions::convert_tz)(args))
         }
+        BuiltinScalarFunction::CidrContains => {
+            Arc::new(|args| match args[0].data_type() {
+                DataType::Utf8 => {
+                    make_scalar_function(inet_expressions::cidr_contains::<i32>)(args)
+                }
+                DataType::LargeUtf8 => {
+                    make_scalar_function(inet_expressions::cidr_contains::<i64>)(args)
+                }
+                other => Err(DataFusionError::Internal(format!(
+                    "Unsupported data type {:?} for function cidr_contains",
+                    other,
+                ))),
+            })
+        }
+        BuiltinScalarFunction::CollationKey => {
+            Arc::new(|args| match args[0].data_type() {
+                DataType::Utf8 => {
+                    let func = invoke_if_unicode_expressions_feature_flag!(
+                        collation_key,
+                        i32,
+                        "collation_key"
+                    );
+                    make_scalar_function(func)(args)
+                }
+                DataType::LargeUtf8 => {
+                    let func = invoke_if_unicode_expressions_feature_flag!(
+                        collation_key,
+                        i64,
+                        "collation_key"
+                    );
+                    make_scalar_function(func)(args)
+                }
+                other => Err(DataFusionError::Internal(format!(
+                    "Unsupported data type {:?} for function collation_key",
+                    other,
+                ))),
+            })
+        }
+        BuiltinScalarFunction::InetAton => Arc::new(|args| match args[0].data_type() {
+            DataType::Utf8 => {
+                make_scalar_function(inet_expressions::inet_aton::<i32>)(args)
+            }
+            DataType::LargeUtf8 => {
+                make_scalar_function(inet_expressions::inet_aton::<i64>)(args)
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "Unsupported data type {:?} for function inet_aton",
+                other,
+            ))),
+        }),
+        BuiltinScalarFunction::InetNtoa => {
+            Arc::new(|args| make_scalar_function(inet_expressions::inet_ntoa)(args))
+        }
         BuiltinScalarFunction::InitCap => Arc::new(|args| match args[0].data_type() {
             DataType::Utf8 => {
                 make_scalar_function(string_expressions::initcap::<i32>)(args)
@@ -713,6 +1134,12 @@ pub fn create_physical_fun(
         BuiltinScalarFunction::MD5 => {
             Arc::new(invoke_if_crypto_expressions_feature_flag!(md5, "md5"))
         }
+        BuiltinScalarFunction::CRC32 => {
+            Arc::new(invoke_if_crypto_expressions_feature_flag!(crc32, "crc32"))
+        }
+        BuiltinScalarFunction::XXHash64 => Arc::new(
+            invoke_if_crypto_expressions_feature_flag!(xxhash64, "xxhash64"),
+        ),
         BuiltinScalarFunction::NullIf => Arc::new(nullif_func),
         BuiltinScalarFunction::OctetLength => Arc::new(|args| match &args[0] {
             ColumnarValue::Array(v) => Ok(ColumnarValue::Array(length(v.as_ref())?)),
@@ -856,6 +1283,12 @@ pub fn create_physical_fun(
                 other,
             ))),
         }),
+        BuiltinScalarFunction::SHA1 => {
+            Arc::new(invoke_if_crypto_expressions_feature_flag!(sha1, "sha1"))
+        }
+        BuiltinScalarFunction::SHA2 => {
+            Arc::new(invoke_if_crypto_expressions_feature_flag!(sha2, "sha2"))
+        }
         BuiltinScalarFunction::SHA224 => {
             Arc::new(invoke_if_crypto_expressions_feature_flag!(sha224, "sha224"))
         }
@@ -933,6 +1366,9 @@ pub fn create_physical_fun(
             DataType::Int64 => {
                 make_scalar_function(string_expressions::to_hex::<Int64Type>)(args)
             }
+            DataType::Binary | DataType::LargeBinary => {
+                invoke_if_encoding_expressions_feature_flag!(to_hex, "to_hex")(args)
+            }
             other => Err(DataFusionError::Internal(format!(
                 "Unsupported data type {:?} for function to_hex",
                 other,
@@ -990,107 +1426,229 @@ pub fn create_physical_expr(
     input_schema: &Schema,
     ctx_state: &ExecutionContextState,
 ) -> Result<Arc<dyn PhysicalExpr>> {
+    let mut args = coerce(args, input_schema, &signature(fun))?;
+
     let fun_expr: ScalarFunctionImplementation = match fun {
         // These functions need args and input schema to pick an implementation
         // Unlike the string functions, which actually figure out the function to use with each array,
         // here we return either a cast fn or string timestamp translation based on the expression data type
         // so we don't have to pay a per-array/batch cost.
-        BuiltinScalarFunction::ToTimestamp => {
-            Arc::new(match args[0].data_type(input_schema) {
+        BuiltinScalarFunction::ToTimestamp => match args[0].data_type(input_schema) {
+            Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
+                Arc::new(|col_values: &[ColumnarValue]| {
+                    cast_column(
+                        &col_values[0],
+                        &DataType::Timestamp(TimeUnit::Nanosecond, None),
+                        &DEFAULT_DATAFUSION_CAST_OPTIONS,
+                    )
+                })
+            }
+            Ok(DataType::Utf8) => {
+                let time_zone = ctx_state.execution_props.time_zone;
+                Arc::new(move |args: &[ColumnarValue]| {
+                    datetime_expressions::to_timestamp_in_zone(
+                        args,
+                        &TimeUnit::Nanosecond,
+                        &time_zone,
+                    )
+                })
+            }
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unsupported data type {:?} for function to_timestamp",
+                    other,
+                )))
+            }
+        },
+        BuiltinScalarFunction::ToTimestampMillis => match args[0].data_type(input_schema)
+        {
+            Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
+                Arc::new(|col_values: &[ColumnarValue]| {
+                    cast_column(
+                        &col_values[0],
+                        &DataType::Timestamp(TimeUnit::Millisecond, None),
+                        &DEFAULT_DATAFUSION_CAST_OPTIONS,
+                    )
+                })
+            }
+            Ok(DataType::Utf8) => {
+                let time_zone = ctx_state.execution_props.time_zone;
+                Arc::new(move |args: &[ColumnarValue]| {
+                    datetime_expressions::to_timestamp_in_zone(
+                        args,
+                        &TimeUnit::Millisecond,
+                        &time_zone,
+                    )
+                })
+            }
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unsupported data type {:?} for function to_timestamp_millis",
+                    other,
+                )))
+            }
+        },
+        BuiltinScalarFunction::ToTimestampMicros => match args[0].data_type(input_schema)
+        {
+            Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
+                Arc::new(|col_values: &[ColumnarValue]| {
+                    cast_column(
+                        &col_values[0],
+                        &DataType::Timestamp(TimeUnit::Microsecond, None),
+                        &DEFAULT_DATAFUSION_CAST_OPTIONS,
+                    )
+                })
+            }
+            Ok(DataType::Utf8) => {
+                let time_zone = ctx_state.execution_props.time_zone;
+                Arc::new(move |args: &[ColumnarValue]| {
+                    datetime_expressions::to_timestamp_in_zone(
+                        args,
+                        &TimeUnit::Microsecond,
+                        &time_zone,
+                    )
+                })
+            }
+            other => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unsupported data type {:?} for function to_timestamp_micros",
+                    other,
+                )))
+            }
+        },
+        BuiltinScalarFunction::ToTimestampSeconds => {
+            match args[0].data_type(input_schema) {
                 Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
-                    |col_values: &[ColumnarValue]| {
+                    Arc::new(|col_values: &[ColumnarValue]| {
                         cast_column(
                             &col_values[0],
-                            &DataType::Timestamp(TimeUnit::Nanosecond, None),
+                            &DataType::Timestamp(TimeUnit::Second, None),
                             &DEFAULT_DATAFUSION_CAST_OPTIONS,
                         )
-                    }
-                }
-                Ok(DataType::Utf8) => datetime_expressions::to_timestamp,
-                other => {
-                    return Err(DataFusionError::Internal(format!(
-                        "Unsupported data type {:?} for function to_timestamp",
-                        other,
-                    )))
+                    })
                 }
-            })
-        }
-        BuiltinScalarFunction::ToTimestampMillis => {
-            Arc::new(match args[0].data_type(input_schema) {
-                Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
-                    |col_values: &[ColumnarValue]| {
-                        cast_column(
-                            &col_values[0],
-                            &DataType::Timestamp(TimeUnit::Millisecond, None),
-                            &DEFAULT_DATAFUSION_CAST_OPTIONS,
+                Ok(DataType::Utf8) => {
+                    let time_zone = ctx_state.execution_props.time_zone;
+                    Arc::new(move |args: &[ColumnarValue]| {
+                        datetime_expressions::to_timestamp_in_zone(
+                            args,
+                            &TimeUnit::Second,
+                            &time_zone,
                         )
-                    }
+                    })
                 }
-                Ok(DataType::Utf8) => datetime_expressions::to_timestamp_millis,
                 other => {
                     return Err(DataFusionError::Internal(format!(
-                        "Unsupported data type {:?} for function to_timestamp_millis",
+                        "Unsupported data type {:?} for function to_timestamp_seconds",
                         other,
                     )))
                 }
-            })
+            }
         }
-        BuiltinScalarFunction::ToTimestampMicros => {
-            Arc::new(match args[0].data_type(input_schema) {
-                Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
-                    |col_values: &[ColumnarValue]| {
-                        cast_column(
-                            &col_values[0],
-                            &DataType::Timestamp(TimeUnit::Microsecond, None),
-                            &DEFAULT_DATAFUSION_CAST_OPTIONS,
-                        )
-                    }
-                }
-                Ok(DataType::Utf8) => datetime_expressions::to_timestamp_micros,
-                other => {
-                    return Err(DataFusionError::Internal(format!(
-                        "Unsupported data type {:?} for function to_timestamp_micros",
-                        other,
+        // The precision of the current time is bound at plan time, so these get no args
+        BuiltinScalarFunction::Now
+        | BuiltinScalarFunction::CurrentTime
+        | BuiltinScalarFunction::LocalTime
+        | BuiltinScalarFunction::LocalTimestamp
+            if !args.is_empty() =>
+        {
+            let precision = match args[0].as_any().downcast_ref::<Literal>() {
+                Some(literal) => datetime_expressions::time_precision(literal.value())?,
+                None => {
+                    return Err(DataFusionError::Plan(format!(
+                        "Precision of {} must be a constant",
+                        fun
                     )))
                 }
-            })
+            };
+            args.clear();
+            create_current_time_fun(fun, ctx_state, Some(precision))?
         }
-        BuiltinScalarFunction::ToTimestampSeconds => Arc::new({
-            match args[0].data_type(input_schema) {
-                Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
-                    |col_values: &[ColumnarValue]| {
-                        cast_column(
-                            &col_values[0],
-                            &DataType::Timestamp(TimeUnit::Second, None),
-                            &DEFAULT_DATAFUSION_CAST_OPTIONS,
-                        )
-                    }
-                }
-                Ok(DataType::Utf8) => datetime_expressions::to_timestamp_seconds,
-                other => {
-                    return Err(DataFusionError::Internal(format!(
-                        "Unsupported data type {:?} for function to_timestamp_seconds",
-                        other,
-                    )))
-                }
-            }
-        }),
         // These don't need args and input schema
         _ => create_physical_fun(fun, ctx_state)?,
     };
-    let args = coerce(args, input_schema, &signature(fun))?;
 
     let arg_types = args
         .iter()
         .map(|e| e.data_type(input_schema))
         .collect::<Result<Vec<_>>>()?;
+    let literals = args
+        .iter()
+        .map(|e| e.as_any().downcast_ref::<Literal>().map(Literal::value))
+        .collect::<Vec<_>>();
 
-    Ok(Arc::new(ScalarFunctionExpr::new(
-        &format!("{}", fun),
-        fun_expr,
-        args,
-        &return_type(fun, &arg_types)?,
-    )))
+    Ok(Arc::new(
+        ScalarFunctionExpr::new(
+            &format!("{}", fun),
+            fun_expr,
+            args,
+            &return_type_of_args(fun, &arg_types, &literals)?,
+        )
+        .with_builtin(fun.clone()),
+    ))
+}
+
+/// Create the implementation of `now()` and the other current time functions, whose
+/// value is bound at plan time with `precision` fractional digits of a second
+fn create_current_time_fun(
+    fun: &BuiltinScalarFunction,
+    ctx_state: &ExecutionContextState,
+    precision: Option<u32>,
+) -> Result<ScalarFunctionImplementation> {
+    let now_ts = ctx_state.execution_props.query_execution_start_time;
+    let time_zone = ctx_state.execution_props.time_zone;
+    Ok(match fun {
+        BuiltinScalarFunction::Now => {
+            Arc::new(datetime_expressions::make_now(now_ts, precision))
+        }
+        BuiltinScalarFunction::LocalTimestamp => Arc::new(
+            datetime_expressions::make_local_timestamp(now_ts, time_zone, precision),
+        ),
+        BuiltinScalarFunction::CurrentDate => {
+            Arc::new(datetime_expressions::make_current_date(now_ts, time_zone))
+        }
+        BuiltinScalarFunction::CurrentTime | BuiltinScalarFunction::LocalTime => {
+            Arc::new(datetime_expressions::make_current_time(
+                now_ts, time_zone, precision,
+            ))
+        }
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "{} is not a current time function",
+                other
+            )))
+        }
+    })
+}
+
+/// Create the implementation of the MySQL information functions, whose values are
+/// bound from the configuration of the session at plan time
+fn create_session_info_fun(
+    fun: &BuiltinScalarFunction,
+    ctx_state: &ExecutionContextState,
+) -> Result<ScalarFunctionImplementation> {
+    let config = &ctx_state.config;
+    let value = match fun {
+        BuiltinScalarFunction::ConnectionId => {
+            ScalarValue::UInt64(config.connection_id())
+        }
+        BuiltinScalarFunction::Database => {
+            ScalarValue::Utf8(Some(config.default_schema().to_string()))
+        }
+        BuiltinScalarFunction::User => ScalarValue::Utf8(config.session_user()),
+        BuiltinScalarFunction::Version => {
+            ScalarValue::Utf8(Some(config.server_version()))
+        }
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "{} is not an information function",
+                other
+            )))
+        }
+    };
+    Ok(Arc::new(move |_: &[ColumnarValue]| {
+        Ok(ColumnarValue::Scalar(value.clone()))
+    }))
 }
 
 /// the signatures supported by the function `fun`.
@@ -1102,6 +1660,14 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
         BuiltinScalarFunction::Array => {
             Signature::Variadic(array_expressions::SUPPORTED_ARRAY_TYPES.to_vec())
         }
+        // the list types are checked by `return_type`
+        BuiltinScalarFunction::ArrayToString => {
+            Signature::OneOf(vec![Signature::Any(2), Signature::Any(3)])
+        }
+        BuiltinScalarFunction::StringToArray => Signature::OneOf(vec![
+            Signature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+            Signature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Utf8]),
+        ]),
         BuiltinScalarFunction::Concat | BuiltinScalarFunction::ConcatWithSeparator => {
             Signature::Variadic(vec![DataType::Utf8])
         }
@@ -1110,26 +1676,63 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
         | BuiltinScalarFunction::CharacterLength
         | BuiltinScalarFunction::InitCap
         | BuiltinScalarFunction::Lower
-        | BuiltinScalarFunction::MD5
         | BuiltinScalarFunction::OctetLength
         | BuiltinScalarFunction::Reverse
-        | BuiltinScalarFunction::SHA224
-        | BuiltinScalarFunction::SHA256
-        | BuiltinScalarFunction::SHA384
-        | BuiltinScalarFunction::SHA512
         | BuiltinScalarFunction::Trim
         | BuiltinScalarFunction::Upper => {
             Signature::Uniform(1, vec![DataType::Utf8, DataType::LargeUtf8])
         }
+        BuiltinScalarFunction::MD5
+        | BuiltinScalarFunction::SHA1
+        | BuiltinScalarFunction::SHA224
+        | BuiltinScalarFunction::SHA256
+        | BuiltinScalarFunction::SHA384
+        | BuiltinScalarFunction::SHA512
+        | BuiltinScalarFunction::CRC32
+        | BuiltinScalarFunction::XXHash64 => Signature::Uniform(
+            1,
+            vec![DataType::Utf8, DataType::LargeUtf8, DataType::Binary],
+        ),
+        BuiltinScalarFunction::SHA2 => Signature::OneOf(vec![
+            Signature::Exact(vec![DataType::Utf8, DataType::Int64]),
+            Signature::Exact(vec![DataType::LargeUtf8, DataType::Int64]),
+            Signature::Exact(vec![DataType::Binary, DataType::Int64]),
+        ]),
         BuiltinScalarFunction::Btrim
         | BuiltinScalarFunction::Ltrim
         | BuiltinScalarFunction::Rtrim => Signature::OneOf(vec![
             Signature::Exact(vec![DataType::Utf8]),
             Signature::Exact(vec![DataType::Utf8, DataType::Utf8]),
         ]),
-        BuiltinScalarFunction::Chr | BuiltinScalarFunction::ToHex => {
+        BuiltinScalarFunction::Chr | BuiltinScalarFunction::InetNtoa => {
             Signature::Uniform(1, vec![DataType::Int64])
         }
+        BuiltinScalarFunction::InetAton => {
+            Signature::Uniform(1, vec![DataType::Utf8, DataType::LargeUtf8])
+        }
+        BuiltinScalarFunction::CidrContains => Signature::OneOf(vec![
+            Signature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+            Signature::Exact(vec![DataType::LargeUtf8, DataType::LargeUtf8]),
+        ]),
+        BuiltinScalarFunction::CollationKey => Signature::OneOf(vec![
+            Signature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+            Signature::Exact(vec![DataType::LargeUtf8, DataType::Utf8]),
+        ]),
+        BuiltinScalarFunction::ToHex => Signature::Uniform(
+            1,
+            vec![DataType::Int64, DataType::Binary, DataType::LargeBinary],
+        ),
+        BuiltinScalarFunction::FromHex => {
+            Signature::Uniform(1, vec![DataType::Utf8, DataType::LargeUtf8])
+        }
+        BuiltinScalarFunction::Encode | BuiltinScalarFunction::Decode => {
+            Signature::OneOf(vec![
+                Signature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                Signature::Exact(vec![DataType::LargeUtf8, DataType::Utf8]),
+                Signature::Exact(vec![DataType::Binary, DataType::Utf8]),
+                Signature::Exact(vec![DataType::LargeBinary, DataType::Utf8]),
+            ])
+        }
         BuiltinScalarFunction::Lpad | BuiltinScalarFunction::Rpad => {
             Signature::OneOf(vec![
                 Signature::Exact(vec![DataType::Utf8, DataType::Int64]),
@@ -1206,26 +1809,24 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
             DataType::Utf8,
             DataType::Timestamp(TimeUnit::Nanosecond, None),
         ]),
-        BuiltinScalarFunction::DatePart => Signature::OneOf(vec![
-            Signature::Exact(vec![DataType::Utf8, DataType::Date32]),
-            Signature::Exact(vec![DataType::Utf8, DataType::Date64]),
-            Signature::Exact(vec![
-                DataType::Utf8,
-                DataType::Timestamp(TimeUnit::Second, None),
-            ]),
-            Signature::Exact(vec![
-                DataType::Utf8,
-                DataType::Timestamp(TimeUnit::Microsecond, None),
-            ]),
-            Signature::Exact(vec![
-                DataType::Utf8,
-                DataType::Timestamp(TimeUnit::Millisecond, None),
-            ]),
-            Signature::Exact(vec![
-                DataType::Utf8,
-                DataType::Timestamp(TimeUnit::Nanosecond, None),
-            ]),
+        // the types are checked by `return_type` as timestamps may be in any time zone
+        // and dictionary-encoded
+        BuiltinScalarFunction::DatePart => Signature::Any(2),
+        BuiltinScalarFunction::IsoWeek => Signature::OneOf(vec![
+            Signature::Exact(vec![DataType::Timestamp(TimeUnit::Nanosecond, None)]),
+            Signature::Exact(vec![DataType::Date32]),
         ]),
+        BuiltinScalarFunction::Week | BuiltinScalarFunction::YearWeek => {
+            Signature::OneOf(vec![
+                Signature::Exact(vec![DataType::Timestamp(TimeUnit::Nanosecond, None)]),
+                Signature::Exact(vec![
+                    DataType::Timestamp(TimeUnit::Nanosecond, None),
+                    DataType::Int64,
+                ]),
+                Signature::Exact(vec![DataType::Date32]),
+                Signature::Exact(vec![DataType::Date32, DataType::Int64]),
+            ])
+        }
         BuiltinScalarFunction::SplitPart => Signature::OneOf(vec![
             Signature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Int64]),
             Signature::Exact(vec![DataType::LargeUtf8, DataType::Utf8, DataType::Int64]),
@@ -1279,7 +1880,20 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
             Signature::Exact(vec![DataType::Utf8, DataType::Utf8, DataType::Utf8]),
             Signature::Exact(vec![DataType::LargeUtf8, DataType::Utf8, DataType::Utf8]),
         ]),
-        BuiltinScalarFunction::Random => Signature::Exact(vec![]),
+        BuiltinScalarFunction::Random
+        | BuiltinScalarFunction::Uuid
+        | BuiltinScalarFunction::CurrentDate
+        | BuiltinScalarFunction::ConnectionId
+        | BuiltinScalarFunction::Database
+        | BuiltinScalarFunction::User
+        | BuiltinScalarFunction::Version => Signature::Exact(vec![]),
+        BuiltinScalarFunction::Now
+        | BuiltinScalarFunction::CurrentTime
+        | BuiltinScalarFunction::LocalTime
+        | BuiltinScalarFunction::LocalTimestamp => Signature::OneOf(vec![
+            Signature::Exact(vec![]),
+            Signature::Exact(vec![DataType::Int64]),
+        ]),
         // math expressions expect 1 argument of type f64 or f32
         // priority is given to f64 because e.g. `sqrt(1i32)` is in IR (real numbers) and thus we
         // return the best approximation for it (in f64).
@@ -1295,6 +1909,7 @@ pub struct ScalarFunctionExpr {
     name: String,
     args: Vec<Arc<dyn PhysicalExpr>>,
     return_type: DataType,
+    builtin: Option<BuiltinScalarFunction>,
 }
 
 impl Debug for ScalarFunctionExpr {
@@ -1321,9 +1936,17 @@ impl ScalarFunctionExpr {
             name: name.to_owned(),
             args,
             return_type: return_type.clone(),
+            builtin: None,
         }
     }
 
+    /// The expression implementing the built-in `fun`, whose results are known to be
+    /// non-null for some arguments
+    pub fn with_builtin(mut self, fun: BuiltinScalarFunction) -> Self {
+        self.builtin = Some(fun);
+        self
+    }
+
     /// Get the scalar function implementation
     pub fn fun(&self) -> &ScalarFunctionImplementation {
         &self.fun
@@ -1381,8 +2004,22 @@ impl PhysicalExpr for ScalarFunctionExpr {
         Ok(self.return_type.clone())
     }
 
-    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
-        Ok(true)
+    fn nullable(&self, input_schema: &Schema) -> Result<bool> {
+        match &self.builtin {
+            Some(fun) => {
+                let args_nullable = self
+                    .args
+                    .iter()
+                    .map(|e| e.nullable(input_schema))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(fun.nullable(&args_nullable))
+            }
+            None => Ok(true),
+        }
+    }
+
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        crate::physical_plan::column_indices(&self.args)
     }
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
@@ -1456,7 +2093,7 @@ mod tests {
     use arrow::{
         array::{
             Array, ArrayRef, BinaryArray, BooleanArray, FixedSizeListArray, Float32Array,
-            Float64Array, Int32Array, StringArray, UInt32Array, UInt64Array,
+            Float64Array, Int32Array, Int64Array, StringArray, UInt32Array, UInt64Array,
         },
         datatypes::Field,
         record_batch::RecordBatch,
@@ -1708,6 +2345,56 @@ mod tests {
             Int32,
             Int32Array
         );
+        #[cfg(feature = "unicode_expressions")]
+        test_function!(
+            CollationKey,
+            &[
+                lit(ScalarValue::Utf8(Some("Éclair".to_string()))),
+                lit(ScalarValue::Utf8(Some(
+                    "case_accent_insensitive".to_string()
+                ))),
+            ],
+            Ok(Some("eclair")),
+            &str,
+            Utf8,
+            StringArray
+        );
+        #[cfg(feature = "unicode_expressions")]
+        test_function!(
+            CollationKey,
+            &[
+                lit(ScalarValue::Utf8(Some("Éclair".to_string()))),
+                lit(ScalarValue::Utf8(Some("accent_insensitive".to_string()))),
+            ],
+            Ok(Some("Eclair")),
+            &str,
+            Utf8,
+            StringArray
+        );
+        #[cfg(feature = "unicode_expressions")]
+        test_function!(
+            CollationKey,
+            &[
+                lit(ScalarValue::Utf8(Some("Éclair".to_string()))),
+                lit(ScalarValue::Utf8(Some("binary".to_string()))),
+            ],
+            Ok(Some("Éclair")),
+            &str,
+            Utf8,
+            StringArray
+        );
+        #[cfg(feature = "unicode_expressions")]
+        test_function!(
+            CollationKey,
+            &[
+                lit(ScalarValue::Utf8(Some("Éclair".to_string()))),
+                lit(ScalarValue::Utf8(None)),
+            ],
+            Ok(None),
+            &str,
+            Utf8,
+            StringArray
+        );
         test_function!(
             Chr,
             &[lit(ScalarValue::Int64(Some(128175)))],
@@ -2948,6 +3635,77 @@ mod tests {
             StringArray
         );
         #[cfg(feature = "crypto_expressions")]
+        test_function!(
+            MD5,
+            &[lit(ScalarValue::Binary(Some(b"tom".to_vec())))],
+            Ok(Some("34b7da764b21d298ef307d04d8152dc5")),
+            &str,
+            Utf8,
+            StringArray
+        );
+        #[cfg(feature = "crypto_expressions")]
+        test_function!(
+            SHA1,
+            &[lit(ScalarValue::Utf8(Some("tom".to_string())))],
+            Ok(Some("96835dd8bfa718bd6447ccc87af89ae1675daeca")),
+            &str,
+            Utf8,
+            StringArray
+        );
+        #[cfg(feature = "crypto_expressions")]
+        test_function!(
+            SHA2,
+            &[
+                lit(ScalarValue::Utf8(Some("tom".to_string()))),
+                lit(ScalarValue::Int64(Some(256)))
+            ],
+            Ok(Some(
+                "e1608f75c5d7813f3d4031cb30bfb786507d98137538ff8e128a6ff74e84e643"
+            )),
+            &str,
+            Utf8,
+            StringArray
+        );
+        #[cfg(feature = "crypto_expressions")]
+        test_function!(
+            SHA2,
+            &[
+                lit(ScalarValue::Utf8(Some("tom".to_string()))),
+                lit(ScalarValue::Int64(Some(100)))
+            ],
+            Ok(None),
+            &str,
+            Utf8,
+            StringArray
+        );
+        #[cfg(feature = "crypto_expressions")]
+        test_function!(
+            CRC32,
+            &[lit(ScalarValue::Utf8(Some("tom".to_string())))],
+            Ok(Some(2111795987)),
+            i64,
+            Int64,
+            Int64Array
+        );
+        #[cfg(feature = "crypto_expressions")]
+        test_function!(
+            XXHash64,
+            &[lit(ScalarValue::Binary(Some(b"tom".to_vec())))],
+            Ok(Some(-3637207724263670644)),
+            i64,
+            Int64,
+            Int64Array
+        );
+        #[cfg(feature = "crypto_expressions")]
+        test_function!(
+            XXHash64,
+            &[lit(ScalarValue::Utf8(None))],
+            Ok(None),
+            i64,
+            Int64,
+            Int64Array
+        );
+        #[cfg(feature = "crypto_expressions")]
         test_function!(
             SHA224,
             &[lit(ScalarValue::Utf8(Some("tom".to_string())))],
@@ -3619,6 +4377,14 @@ mod tests {
             Utf8,
             StringArray
         );
+        test_function!(
+            Upper,
+            &[lit(ScalarValue::Utf8(Some("éclair ß".to_string())))],
+            Ok(Some("ÉCLAIR SS")),
+            &str,
+            Utf8,
+            StringArray
+        );
         test_function!(
             Upper,
             &[lit(ScalarValue::Utf8(Some("UPPER".to_string())))],
@@ -50,6 +50,10 @@ pub enum GroupByScalar {
     Int64Decimal(i64, u8),
     Int96Decimal(i128, u8),
     Date32(i32),
+    /// The elements of a list, NULL elements are [GroupByScalar::Null]
+    List(Vec<GroupByScalar>),
+    /// The values of the fields of a struct, in the order of the fields
+    Struct(Vec<GroupByScalar>),
 }
 
 impl TryFrom<&ScalarValue> for GroupByScalar {
@@ -87,7 +91,14 @@ impl TryFrom<&ScalarValue> for GroupByScalar {
             }
             ScalarValue::Utf8(Some(v)) => GroupByScalar::Utf8(v.clone()),
             ScalarValue::LargeUtf8(Some(v)) => GroupByScalar::LargeUtf8(v.clone()),
-            ScalarValue::Float32(None)
+            ScalarValue::List(Some(values), _) => GroupByScalar::List(
+                values
+                    .iter()
+                    .map(GroupByScalar::try_from)
+                    .collect::<Result<_>>()?,
+            ),
+            ScalarValue::Null
+            | ScalarValue::Float32(None)
             | ScalarValue::Float64(None)
             | ScalarValue::Boolean(None)
             | ScalarValue::Int8(None)
@@ -104,7 +115,8 @@ impl TryFrom<&ScalarValue> for GroupByScalar {
             | ScalarValue::Int96Decimal(None, _)
             | ScalarValue::TimestampMillisecond(None)
             | ScalarValue::TimestampMicrosecond(None)
-            | ScalarValue::TimestampNanosecond(None) => GroupByScalar::Null,
+            | ScalarValue::TimestampNanosecond(None)
+            | ScalarValue::List(None, _) => GroupByScalar::Null,
             v => {
                 return Err(DataFusionError::Internal(format!(
                     "Cannot convert a ScalarValue with associated DataType {:?}",
@@ -153,8 +165,27 @@ impl GroupByScalar {
                 ScalarValue::TimestampNanosecond(Some(*v))
             }
             GroupByScalar::Date32(v) => ScalarValue::Date32(Some(*v)),
+            GroupByScalar::List(values) => {
+                let item_type = match ty {
+                    DataType::List(item) | DataType::FixedSizeList(item, _) => {
+                        item.data_type()
+                    }
+                    _ => panic!("list GROUP BY value of type {:?}", ty),
+                };
+                ScalarValue::List(
+                    Some(Box::new(
+                        values.iter().map(|v| v.to_scalar(item_type)).collect(),
+                    )),
+                    Box::new(item_type.clone()),
+                )
+            }
+            // there are no struct scalars, see `NestedGroupByBuilder`
+            GroupByScalar::Struct(_) => {
+                panic!("struct GROUP BY values can not be converted to ScalarValue")
+            }
         };
-        debug_assert_eq!(&r.get_datatype(), ty);
+        // list scalars do not keep the name and nullability of their items
+        debug_assert!(matches!(r, ScalarValue::List(..)) || &r.get_datatype() == ty);
         r
     }
 }
@@ -227,6 +258,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_list_scalar() {
+        let list = |values: Option<Vec<ScalarValue>>| {
+            ScalarValue::List(values.map(Box::new), Box::new(DataType::Utf8))
+        };
+        let a = list(Some(vec![ScalarValue::from("x"), ScalarValue::Utf8(None)]));
+        let b = list(Some(vec![ScalarValue::from("x")]));
+        let group_a = GroupByScalar::try_from(&a).unwrap();
+        assert_eq!(
+            group_a,
+            GroupByScalar::List(vec![
+                GroupByScalar::Utf8("x".to_string()),
+                GroupByScalar::Null
+            ])
+        );
+        assert_ne!(group_a, GroupByScalar::try_from(&b).unwrap());
+        assert_eq!(
+            GroupByScalar::try_from(&list(None)).unwrap(),
+            GroupByScalar::Null
+        );
+        assert_eq!(group_a.to_scalar(&a.get_datatype()), a);
+    }
+
     #[test]
     fn size_of_group_by_scalar() {
         assert_eq!(std::mem::size_of::<GroupByScalar>(), 32);
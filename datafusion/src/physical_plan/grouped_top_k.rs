@@ -0,0 +1,464 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the grouped TOP K plan, which keeps the first rows of each group of its
+//! input in a sort order
+
+use std::any::Any;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use arrow::array::{ArrayRef, UInt32Array, UInt64Array};
+use arrow::compute::{lexicographical_partition_ranges, take};
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt};
+use hashbrown::HashMap;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::{Column, PhysicalSortExpr};
+use crate::physical_plan::sort::sort_batch;
+use crate::physical_plan::{
+    common, DisplayFormatType, Distribution, ExecutionPlan, OptimizerHints, Partitioning,
+    RecordBatchStream, SQLMetric, SendableRecordBatchStream,
+};
+
+/// Keeps the first `k` rows of each group of rows with equal values of the first
+/// `partition_len` sort expressions, in the order of the remaining ones, and numbers
+/// them from 1. The output is sorted on all the sort expressions, its first column
+/// is the row numbers and the input columns follow, as in the output of a ROW_NUMBER
+/// window over the sorted input.
+///
+/// The rows that cannot be among the first ones of their group are periodically
+/// discarded, so that about twice as many rows as the output are buffered at a time
+/// rather than the whole input.
+#[derive(Debug)]
+pub struct GroupedTopKExec {
+    input: Arc<dyn ExecutionPlan>,
+    expr: Vec<PhysicalSortExpr>,
+    /// Number of leading sort expressions the rows are grouped on
+    partition_len: usize,
+    /// Maximum number of rows of each group
+    k: usize,
+    schema: SchemaRef,
+    preserve_partitioning: bool,
+    output_rows: Arc<SQLMetric>,
+    sort_time_nanos: Arc<SQLMetric>,
+}
+
+impl GroupedTopKExec {
+    /// Create a new plan keeping the first `k` rows of the groups of `input` on the
+    /// first `partition_len` expressions of `expr`, whose numbers are the `row_number`
+    /// column of the output
+    pub fn try_new(
+        expr: Vec<PhysicalSortExpr>,
+        partition_len: usize,
+        k: usize,
+        row_number: Field,
+        input: Arc<dyn ExecutionPlan>,
+        preserve_partitioning: bool,
+    ) -> Result<Self> {
+        if expr.len() < partition_len {
+            return Err(DataFusionError::Internal(format!(
+                "GroupedTopKExec partition length {} is out of range for {} sort expressions",
+                partition_len,
+                expr.len()
+            )));
+        }
+        let input_schema = input.schema();
+        let mut fields = Vec::with_capacity(input_schema.fields().len() + 1);
+        fields.push(row_number);
+        fields.extend_from_slice(input_schema.fields());
+        Ok(Self {
+            input,
+            expr,
+            partition_len,
+            k,
+            schema: Arc::new(Schema::new(fields)),
+            preserve_partitioning,
+            output_rows: SQLMetric::counter(),
+            sort_time_nanos: SQLMetric::time_nanos(),
+        })
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// Sort expressions
+    pub fn expr(&self) -> &[PhysicalSortExpr] {
+        &self.expr
+    }
+
+    /// Number of leading sort expressions the rows are grouped on
+    pub fn partition_len(&self) -> usize {
+        self.partition_len
+    }
+
+    /// Maximum number of rows of each group
+    pub fn k(&self) -> usize {
+        self.k
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for GroupedTopKExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        if self.preserve_partitioning {
+            self.input.output_partitioning()
+        } else {
+            Partitioning::UnknownPartitioning(1)
+        }
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        if self.preserve_partitioning {
+            Distribution::UnspecifiedDistribution
+        } else {
+            Distribution::SinglePartition
+        }
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(GroupedTopKExec::try_new(
+                self.expr.clone(),
+                self.partition_len,
+                self.k,
+                self.schema.field(0).clone(),
+                children[0].clone(),
+                self.preserve_partitioning,
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "GroupedTopKExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if !self.preserve_partitioning {
+            if 0 != partition {
+                return Err(DataFusionError::Internal(format!(
+                    "GroupedTopKExec invalid partition {}",
+                    partition
+                )));
+            }
+            if 1 != self.input.output_partitioning().partition_count() {
+                return Err(DataFusionError::Internal(
+                    "GroupedTopKExec requires a single input partition".to_owned(),
+                ));
+            }
+        }
+
+        let input = self.input.execute(partition).await?;
+        Ok(Box::pin(GroupedTopKStream {
+            input,
+            schema: self.schema.clone(),
+            expr: self.expr.clone(),
+            partition_len: self.partition_len,
+            k: self.k,
+            buffered: vec![],
+            num_rows: 0,
+            kept_rows: 0,
+            finished: false,
+            output_rows: self.output_rows.clone(),
+            sort_time: self.sort_time_nanos.clone(),
+        }))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                let expr: Vec<String> = self.expr.iter().map(|e| e.to_string()).collect();
+                write!(
+                    f,
+                    "GroupedTopKExec: [{}], partition_len={}, k={}",
+                    expr.join(","),
+                    self.partition_len,
+                    self.k
+                )
+            }
+        }
+    }
+
+    fn metrics(&self) -> HashMap<String, SQLMetric> {
+        let mut metrics = HashMap::new();
+        metrics.insert("outputRows".to_owned(), (*self.output_rows).clone());
+        metrics.insert("sortTime".to_owned(), (*self.sort_time_nanos).clone());
+        metrics
+    }
+
+    fn output_hints(&self) -> OptimizerHints {
+        // the input columns follow the row numbers
+        let mut order = Vec::with_capacity(self.expr.len());
+        for s in &self.expr {
+            match s.expr.as_any().downcast_ref::<Column>() {
+                Some(column) => order.push(column.index() + 1),
+                None => break,
+            }
+        }
+        let input_hints = self.input.output_hints();
+        OptimizerHints {
+            sort_order: Some(order),
+            single_value_columns: input_hints
+                .single_value_columns
+                .iter()
+                .map(|c| c + 1)
+                .collect(),
+        }
+    }
+}
+
+struct GroupedTopKStream {
+    input: SendableRecordBatchStream,
+    schema: SchemaRef,
+    expr: Vec<PhysicalSortExpr>,
+    partition_len: usize,
+    k: usize,
+    /// Rows that may be among the first ones of their group
+    buffered: Vec<RecordBatch>,
+    /// Number of buffered rows
+    num_rows: usize,
+    /// Number of rows kept by the last discard
+    kept_rows: usize,
+    finished: bool,
+    output_rows: Arc<SQLMetric>,
+    sort_time: Arc<SQLMetric>,
+}
+
+impl GroupedTopKStream {
+    /// Sorts the buffered rows and only keeps the first `k` rows of each group,
+    /// returning the ranges of the groups in the kept rows
+    fn discard(&mut self) -> ArrowResult<Vec<Range<usize>>> {
+        let now = Instant::now();
+        let schema = self.input.schema();
+        let batch = match common::combine_batches(&self.buffered, schema.clone())? {
+            Some(batch) if batch.num_rows() != 0 => batch,
+            _ => {
+                self.buffered.clear();
+                self.num_rows = 0;
+                return Ok(vec![]);
+            }
+        };
+        let batch = sort_batch(batch, schema.clone(), &self.expr, 0, None)?;
+        let partition = self.expr[..self.partition_len]
+            .iter()
+            .map(|e| e.evaluate_to_sort_column(&batch))
+            .collect::<Result<Vec<_>>>()
+            .map_err(DataFusionError::into_arrow_external_error)?;
+        let groups = if partition.is_empty() {
+            vec![0..batch.num_rows()]
+        } else {
+            lexicographical_partition_ranges(&partition)?.collect()
+        };
+
+        let mut indices = Vec::with_capacity(batch.num_rows());
+        let mut ranges = Vec::with_capacity(groups.len());
+        for group in groups {
+            let end = group.end.min(group.start + self.k);
+            ranges.push(indices.len()..indices.len() + end - group.start);
+            indices.extend((group.start..end).map(|i| i as u32));
+        }
+        let indices = UInt32Array::from(indices);
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|c| take(c.as_ref(), &indices, None))
+            .collect::<ArrowResult<Vec<_>>>()?;
+        let kept = RecordBatch::try_new(schema, columns)?;
+        self.num_rows = kept.num_rows();
+        self.kept_rows = kept.num_rows();
+        self.buffered = vec![kept];
+        self.sort_time.add(now.elapsed().as_nanos() as usize);
+        Ok(ranges)
+    }
+
+    fn push(&mut self, batch: RecordBatch) -> ArrowResult<()> {
+        if batch.num_rows() == 0 {
+            return Ok(());
+        }
+        self.num_rows += batch.num_rows();
+        self.buffered.push(batch);
+        if self.num_rows >= self.kept_rows.max(self.k).saturating_mul(2) {
+            self.discard()?;
+        }
+        Ok(())
+    }
+
+    /// The first rows of each group, preceded by their numbers
+    fn output(&mut self) -> ArrowResult<RecordBatch> {
+        let ranges = self.discard()?;
+        let batch = match self.buffered.pop() {
+            Some(batch) => batch,
+            None => return Ok(RecordBatch::new_empty(self.schema.clone())),
+        };
+        let row_numbers = ranges.iter().flat_map(|r| 1..=r.len() as u64);
+        let mut columns: Vec<ArrayRef> =
+            vec![Arc::new(UInt64Array::from_iter_values(row_numbers))];
+        columns.extend_from_slice(batch.columns());
+        self.output_rows.add(batch.num_rows());
+        RecordBatch::try_new(self.schema.clone(), columns)
+    }
+}
+
+impl Stream for GroupedTopKStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.finished {
+                return Poll::Ready(None);
+            }
+            let result = match futures::ready!(self.input.poll_next_unpin(cx)) {
+                Some(Ok(batch)) => match self.push(batch) {
+                    Ok(()) => continue,
+                    Err(e) => Err(e),
+                },
+                Some(Err(e)) => Err(e),
+                None => self.output(),
+            };
+            self.finished = true;
+            return Poll::Ready(Some(result));
+        }
+    }
+}
+
+impl RecordBatchStream for GroupedTopKStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_batches_eq;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::sort::SortOptions;
+    use crate::test::build_table_i32;
+    use arrow::datatypes::DataType;
+
+    async fn grouped_top_k(partition_len: usize, k: usize) -> Result<Vec<RecordBatch>> {
+        let batches = vec![
+            build_table_i32(
+                ("a", &vec![1, 2, 1]),
+                ("b", &vec![5, 4, 3]),
+                ("c", &vec![1, 2, 3]),
+            ),
+            build_table_i32(
+                ("a", &vec![2, 1, 1]),
+                ("b", &vec![2, 1, 0]),
+                ("c", &vec![4, 5, 6]),
+            ),
+        ];
+        let schema = batches[0].schema();
+        let input: Arc<dyn ExecutionPlan> =
+            Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None)?);
+        let descending = SortOptions {
+            descending: true,
+            nulls_first: true,
+        };
+        let top_k = GroupedTopKExec::try_new(
+            vec![
+                PhysicalSortExpr {
+                    expr: col("a", &schema)?,
+                    options: SortOptions::default(),
+                },
+                PhysicalSortExpr {
+                    expr: col("b", &schema)?,
+                    options: descending,
+                },
+            ],
+            partition_len,
+            k,
+            Field::new("rn", DataType::UInt64, false),
+            input,
+            false,
+        )?;
+        collect(Arc::new(top_k)).await
+    }
+
+    #[tokio::test]
+    async fn first_rows_of_groups() -> Result<()> {
+        let expected = vec![
+            "+----+---+---+---+",
+            "| rn | a | b | c |",
+            "+----+---+---+---+",
+            "| 1  | 1 | 5 | 1 |",
+            "| 2  | 1 | 3 | 3 |",
+            "| 1  | 2 | 4 | 2 |",
+            "| 2  | 2 | 2 | 4 |",
+            "+----+---+---+---+",
+        ];
+        assert_batches_eq!(expected, &grouped_top_k(1, 2).await?);
+
+        // a single group of all the rows
+        let expected = vec![
+            "+----+---+---+---+",
+            "| rn | a | b | c |",
+            "+----+---+---+---+",
+            "| 1  | 1 | 5 | 1 |",
+            "+----+---+---+---+",
+        ];
+        assert_batches_eq!(expected, &grouped_top_k(0, 1).await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn groups_smaller_than_k() -> Result<()> {
+        let batches = grouped_top_k(1, 10).await?;
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 6);
+        let row_numbers = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(row_numbers.values(), &[1, 2, 3, 4, 1, 2]);
+        Ok(())
+    }
+}
@@ -66,12 +66,17 @@ use hashbrown::HashMap;
 use pin_project_lite::pin_project;
 
 use arrow::array::{
-    ArrayBuilder, BinaryBuilder, LargeStringArray, StringBuilder,
-    TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+    ArrayBuilder, ArrayData, BinaryBuilder, FixedSizeListArray, LargeStringArray,
+    ListArray, StringBuilder, StructArray, TimestampMicrosecondArray,
+    TimestampMillisecondArray, TimestampNanosecondArray,
 };
+use arrow::buffer::Buffer;
+use arrow::datatypes::ToByteSlice;
 use async_trait::async_trait;
 
+use super::array_expressions::list_values_and_offsets;
 use super::groups_accumulator::GroupsAccumulator;
+use super::selection::{execute_selected, SelectedBatch, SendableSelectedBatchStream};
 use super::{
     expressions::Column, group_scalar::GroupByScalar, RecordBatchStream,
     SendableRecordBatchStream,
@@ -180,6 +185,9 @@ impl HashAggregateExec {
         input: Arc<dyn ExecutionPlan>,
         input_schema: SchemaRef,
     ) -> Result<Self> {
+        for (expr, _) in &group_expr {
+            check_group_by_type(&expr.data_type(&input.schema())?)?;
+        }
         let schema = create_schema(&input.schema(), &group_expr, &aggr_expr, mode)?;
 
         let schema = Arc::new(schema);
@@ -274,11 +282,11 @@ impl ExecutionPlan for HashAggregateExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        self.input.output_partitioning()
+        // the group columns come first in the output
+        self.input.output_partitioning().project(&self.group_expr)
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        let input = self.input.execute(partition).await?;
         let group_expr = self.group_expr.iter().map(|x| x.0.clone()).collect();
 
         if self.group_expr.is_empty() {
@@ -286,9 +294,10 @@ impl ExecutionPlan for HashAggregateExec {
                 self.mode,
                 self.schema.clone(),
                 self.aggr_expr.clone(),
-                input,
+                execute_selected(&self.input, partition).await?,
             )))
         } else {
+            let input = self.input.execute(partition).await?;
             Ok(Box::pin(GroupedHashAggregateStream::new(
                 self.strategy,
                 self.mode,
@@ -641,10 +650,56 @@ fn dictionary_create_key_for_col<K: ArrowDictionaryKeyType>(
     create_key_for_col(dict_col.values(), values_index, vec)
 }
 
+/// The maximum number of List and Struct types nested in the type of a GROUP BY
+/// expression, which bounds the recursion of the key machinery
+const MAX_GROUP_BY_NESTING: usize = 3;
+
+/// Checks that the List and Struct values of `data_type` can be used as GROUP BY keys
+fn check_group_by_type(data_type: &DataType) -> Result<()> {
+    fn check(data_type: &DataType, depth: usize) -> Result<()> {
+        let children = match data_type {
+            DataType::List(item) | DataType::FixedSizeList(item, _) => {
+                vec![item.data_type()]
+            }
+            DataType::Struct(fields) => fields.iter().map(|f| f.data_type()).collect(),
+            DataType::Dictionary(..) if depth > 0 => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported GROUP BY type {} nested in a list or struct",
+                    data_type
+                )))
+            }
+            _ => return Ok(()),
+        };
+        if depth == MAX_GROUP_BY_NESTING {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Unsupported GROUP BY type {}, lists and structs can only be nested {} levels deep",
+                data_type, MAX_GROUP_BY_NESTING
+            )));
+        }
+        children
+            .into_iter()
+            .try_for_each(|child| check(child, depth + 1))
+    }
+    check(data_type, 0)
+}
+
+/// Appends the key bytes for `col[row]`, an element of a list or a field of a
+/// struct, preceded by whether it is valid
+fn create_nested_key_for_col(col: &ArrayRef, row: usize, vec: &mut KeyVec) -> Result<()> {
+    if col.is_null(row) {
+        vec.push(0);
+        return Ok(());
+    }
+    vec.push(1);
+    create_key_for_col(col, row, vec)
+}
+
 /// Appends a sequence of [u8] bytes for the value in `col[row]` to
 /// `vec` to be used as a key into the hash map
 fn create_key_for_col(col: &ArrayRef, row: usize, vec: &mut KeyVec) -> Result<()> {
     match col.data_type() {
+        // every value is NULL, they are all the same key
+        DataType::Null => {}
         DataType::Boolean => {
             let array = col.as_any().downcast_ref::<BooleanArray>().unwrap();
             vec.extend_from_slice(&[array.value(row) as u8]);
@@ -822,6 +877,25 @@ fn create_key_for_col(col: &ArrayRef, row: usize, vec: &mut KeyVec) -> Result<()
             )))
             }
         },
+        DataType::List(_) | DataType::FixedSizeList(..) => {
+            // tell a NULL list from an empty one
+            vec.push(col.is_valid(row) as u8);
+            let (values, offsets) = list_values_and_offsets(col)?;
+            let (start, end) = (offsets[row] as usize, offsets[row + 1] as usize);
+            // store the size
+            vec.extend_from_slice(&(end - start).to_le_bytes());
+            // store the elements
+            for i in start..end {
+                create_nested_key_for_col(&values, i, vec)?;
+            }
+        }
+        DataType::Struct(_) => {
+            vec.push(col.is_valid(row) as u8);
+            let array = col.as_any().downcast_ref::<StructArray>().unwrap();
+            for column in array.columns() {
+                create_nested_key_for_col(column, row, vec)?;
+            }
+        }
         _ => {
             // This is internal because we should have caught this before.
             return Err(DataFusionError::Internal(format!(
@@ -1105,7 +1179,7 @@ async fn compute_hash_aggregate(
     mode: AggregateMode,
     schema: SchemaRef,
     aggr_expr: Vec<Arc<dyn AggregateExpr>>,
-    mut input: SendableRecordBatchStream,
+    mut input: SendableSelectedBatchStream,
 ) -> ArrowResult<RecordBatch> {
     let mut accumulators = create_accumulators(&aggr_expr)
         .map_err(DataFusionError::into_arrow_external_error)?;
@@ -1133,7 +1207,7 @@ impl HashAggregateStream {
         mode: AggregateMode,
         schema: SchemaRef,
         aggr_expr: Vec<Arc<dyn AggregateExpr>>,
-        input: SendableRecordBatchStream,
+        input: SendableSelectedBatchStream,
     ) -> Self {
         let (tx, rx) = futures::channel::oneshot::channel();
 
@@ -1151,7 +1225,7 @@ impl HashAggregateStream {
 
 fn aggregate_batch(
     mode: &AggregateMode,
-    batch: &RecordBatch,
+    batch: &SelectedBatch,
     accumulators: &mut [AccumulatorItem],
     expressions: &[Vec<Arc<dyn PhysicalExpr>>],
 ) -> Result<()> {
@@ -1167,8 +1241,7 @@ fn aggregate_batch(
             // 1.2
             let values = &expr
                 .iter()
-                .map(|e| e.evaluate(batch))
-                .map(|r| r.map(|v| v.into_array(batch.num_rows())))
+                .map(|e| batch.evaluate(e.as_ref()))
                 .collect::<Result<Vec<_>>>()?;
 
             // 1.3
@@ -1297,6 +1370,18 @@ pub fn write_group_result_row(
     let add_key_columns = key_columns.is_empty();
     for i in 0..group_by_values.len() {
         match &group_by_values[i] {
+            v if is_nested(key_fields[i].data_type()) => {
+                if add_key_columns {
+                    key_columns.push(Box::new(NestedGroupByBuilder::new(
+                        key_fields[i].data_type().clone(),
+                    )));
+                }
+                key_columns[i]
+                    .as_any_mut()
+                    .downcast_mut::<NestedGroupByBuilder>()
+                    .unwrap()
+                    .append(v.clone());
+            }
             // Optimization to avoid allocation on conversion to ScalarValue.
             GroupByScalar::Utf8(str) => {
                 if add_key_columns {
@@ -1335,6 +1420,18 @@ pub fn write_group_result_row_with_groups_accumulator(
     let add_key_columns = key_columns.is_empty();
     for i in 0..group_by_values.len() {
         match &group_by_values[i] {
+            v if is_nested(key_fields[i].data_type()) => {
+                if add_key_columns {
+                    key_columns.push(Box::new(NestedGroupByBuilder::new(
+                        key_fields[i].data_type().clone(),
+                    )));
+                }
+                key_columns[i]
+                    .as_any_mut()
+                    .downcast_mut::<NestedGroupByBuilder>()
+                    .unwrap()
+                    .append(v.clone());
+            }
             // Optimization to avoid allocation on conversion to ScalarValue.
             GroupByScalar::Utf8(str) => {
                 if add_key_columns {
@@ -1408,6 +1505,135 @@ pub fn create_accumulation_state(
     Ok(AccumulationState::new(groups_accumulators))
 }
 
+fn is_nested(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::List(_) | DataType::FixedSizeList(..) | DataType::Struct(_)
+    )
+}
+
+/// Builds the arrays of List and Struct GROUP BY values, as there is no
+/// [ScalarValue] for structs and list scalars only support primitive items
+struct NestedGroupByBuilder {
+    data_type: DataType,
+    values: Vec<GroupByScalar>,
+}
+
+impl NestedGroupByBuilder {
+    fn new(data_type: DataType) -> Self {
+        Self {
+            data_type,
+            values: Vec::new(),
+        }
+    }
+
+    fn append(&mut self, value: GroupByScalar) {
+        self.values.push(value)
+    }
+}
+
+impl ArrayBuilder for NestedGroupByBuilder {
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let values = std::mem::take(&mut self.values);
+        let values = values.iter().collect::<Vec<_>>();
+        nested_group_by_array(&values, &self.data_type)
+            .expect("GROUP BY values do not match their type")
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+/// Creates an array of `data_type` with the GROUP BY `values`
+fn nested_group_by_array(
+    values: &[&GroupByScalar],
+    data_type: &DataType,
+) -> Result<ArrayRef> {
+    match data_type {
+        DataType::List(item) | DataType::FixedSizeList(item, _) => {
+            let mut items = Vec::new();
+            let mut offsets = Vec::with_capacity(values.len() + 1);
+            let mut valid = Vec::with_capacity(values.len());
+            offsets.push(0i32);
+            for v in values {
+                match v {
+                    GroupByScalar::List(vs) => {
+                        items.extend(vs.iter());
+                        valid.push(true);
+                    }
+                    _ => {
+                        // NULL fixed size lists still have their elements
+                        if let DataType::FixedSizeList(_, size) = data_type {
+                            items.extend((0..*size).map(|_| &GroupByScalar::Null));
+                        }
+                        valid.push(false);
+                    }
+                }
+                offsets.push(items.len() as i32);
+            }
+            let items = nested_group_by_array(&items, item.data_type())?;
+            let nulls = BooleanArray::from(valid);
+            let data = ArrayData::builder(data_type.clone())
+                .len(values.len())
+                .add_child_data(items.data().clone())
+                .null_bit_buffer(nulls.values().clone());
+            Ok(match data_type {
+                DataType::List(_) => Arc::new(ListArray::from(
+                    data.add_buffer(Buffer::from(offsets.to_byte_slice()))
+                        .build(),
+                )),
+                _ => Arc::new(FixedSizeListArray::from(data.build())),
+            })
+        }
+        DataType::Struct(fields) => {
+            let mut data = ArrayData::builder(data_type.clone()).len(values.len());
+            for (i, field) in fields.iter().enumerate() {
+                let children = values
+                    .iter()
+                    .map(|v| match v {
+                        GroupByScalar::Struct(vs) => &vs[i],
+                        _ => &GroupByScalar::Null,
+                    })
+                    .collect::<Vec<_>>();
+                let child = nested_group_by_array(&children, field.data_type())?;
+                data = data.add_child_data(child.data().clone());
+            }
+            let nulls = values
+                .iter()
+                .map(|v| matches!(v, GroupByScalar::Struct(_)))
+                .collect::<Vec<_>>();
+            let data = data
+                .null_bit_buffer(BooleanArray::from(nulls).values().clone())
+                .build();
+            Ok(Arc::new(StructArray::from(data)))
+        }
+        _ => {
+            let mut builder = create_builder(&ScalarValue::try_from(data_type)?);
+            for v in values {
+                append_value(&mut *builder, &v.to_scalar(data_type))?;
+            }
+            Ok(builder.finish())
+        }
+    }
+}
+
 #[allow(unused_variables)]
 pub(crate) fn create_builder(s: &ScalarValue) -> Box<dyn ArrayBuilder> {
     macro_rules! create_list_builder {
@@ -1650,7 +1876,7 @@ fn dictionary_create_group_by_value<K: ArrowDictionaryKeyType>(
 
 /// Extract the value in `col[row]` as a GroupByScalar
 pub(crate) fn create_group_by_value(col: &ArrayRef, row: usize) -> Result<GroupByScalar> {
-    if col.is_null(row) {
+    if col.is_null(row) || col.data_type() == &DataType::Null {
         return Ok(GroupByScalar::Null);
     }
     match col.data_type() {
@@ -1805,6 +2031,22 @@ pub(crate) fn create_group_by_value(col: &ArrayRef, row: usize) -> Result<GroupB
             let array = col.as_any().downcast_ref::<Int96Decimal10Array>().unwrap();
             Ok(GroupByScalar::Int96Decimal(array.value(row), 10))
         }
+        DataType::List(_) | DataType::FixedSizeList(..) => {
+            let (values, offsets) = list_values_and_offsets(col)?;
+            let values = (offsets[row] as usize..offsets[row + 1] as usize)
+                .map(|i| create_group_by_value(&values, i))
+                .collect::<Result<_>>()?;
+            Ok(GroupByScalar::List(values))
+        }
+        DataType::Struct(_) => {
+            let array = col.as_any().downcast_ref::<StructArray>().unwrap();
+            let values = array
+                .columns()
+                .into_iter()
+                .map(|column| create_group_by_value(column, row))
+                .collect::<Result<_>>()?;
+            Ok(GroupByScalar::Struct(values))
+        }
         _ => Err(DataFusionError::NotImplemented(format!(
             "Unsupported GROUP BY type {}",
             col.data_type(),
@@ -2082,4 +2324,126 @@ mod tests {
 
         check_aggregates(input).await
     }
+
+    #[tokio::test]
+    async fn aggregate_nested_group_by() -> Result<()> {
+        use crate::physical_plan::expressions::Count;
+        use crate::physical_plan::memory::MemoryExec;
+        use arrow::array::{Int32Builder, ListBuilder, StringBuilder};
+
+        let rows = vec![
+            Some(vec!["a", "b"]),
+            Some(vec!["a"]),
+            Some(vec!["a", "b"]),
+            None,
+            Some(vec![]),
+            Some(vec!["a", "b"]),
+        ];
+        let mut tags = ListBuilder::new(StringBuilder::new(0));
+        for row in &rows {
+            if let Some(values) = row {
+                for v in values {
+                    tags.values().append_value(v)?;
+                }
+            }
+            tags.append(row.is_some())?;
+        }
+        let mut x = Int32Builder::new(0);
+        let mut y = StringBuilder::new(0);
+        for i in 0..6 {
+            if i < 3 {
+                x.append_value(1)?;
+                y.append_value("p")?;
+            } else {
+                x.append_value(2)?;
+                y.append_null()?;
+            }
+        }
+        let s = StructArray::from(vec![
+            (
+                Field::new("x", DataType::Int32, true),
+                Arc::new(x.finish()) as ArrayRef,
+            ),
+            (
+                Field::new("y", DataType::Utf8, true),
+                Arc::new(y.finish()) as ArrayRef,
+            ),
+        ]);
+        let tags: ArrayRef = Arc::new(tags.finish());
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("tags", tags.data_type().clone(), true),
+            Field::new("s", s.data_type().clone(), true),
+        ]));
+        let batch =
+            RecordBatch::try_new(schema.clone(), vec![tags.clone(), Arc::new(s)])?;
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None)?);
+
+        let groups: Vec<(Arc<dyn PhysicalExpr>, String)> = vec![
+            (col("tags", &schema)?, "tags".to_string()),
+            (col("s", &schema)?, "s".to_string()),
+        ];
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Count::new(
+            col("tags", &schema)?,
+            "COUNT(tags)",
+            DataType::UInt64,
+        ))];
+        let aggregate = HashAggregateExec::try_new(
+            AggregateStrategy::Hash,
+            None,
+            AggregateMode::Full,
+            groups,
+            aggregates,
+            input,
+            schema.clone(),
+        )?;
+        let result = common::collect(aggregate.execute(0).await?).await?;
+        assert_eq!(result.len(), 1);
+        let batch = &result[0];
+        assert_eq!(batch.schema().field(0).data_type(), tags.data_type());
+
+        let mut groups = (0..batch.num_rows())
+            .map(|row| {
+                (0..batch.num_columns())
+                    .map(|i| create_group_by_value(batch.column(i), row))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        groups.sort();
+
+        let list = |values: &[&str]| {
+            GroupByScalar::List(
+                values
+                    .iter()
+                    .map(|v| GroupByScalar::Utf8(v.to_string()))
+                    .collect(),
+            )
+        };
+        let p = GroupByScalar::Struct(vec![
+            GroupByScalar::Int32(1),
+            GroupByScalar::Utf8("p".to_string()),
+        ]);
+        let q = GroupByScalar::Struct(vec![GroupByScalar::Int32(2), GroupByScalar::Null]);
+        let mut expected = vec![
+            vec![list(&["a", "b"]), p.clone(), GroupByScalar::UInt64(2)],
+            vec![list(&["a"]), p, GroupByScalar::UInt64(1)],
+            vec![GroupByScalar::Null, q.clone(), GroupByScalar::UInt64(0)],
+            vec![list(&[]), q.clone(), GroupByScalar::UInt64(1)],
+            vec![list(&["a", "b"]), q, GroupByScalar::UInt64(1)],
+        ];
+        expected.sort();
+        assert_eq!(groups, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn nested_group_by_limits() {
+        let list =
+            |data_type| DataType::List(Box::new(Field::new("item", data_type, true)));
+        let dictionary =
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        assert!(check_group_by_type(&dictionary).is_ok());
+        assert!(check_group_by_type(&list(list(list(DataType::Int64)))).is_ok());
+        assert!(check_group_by_type(&list(list(list(list(DataType::Int64))))).is_err());
+        assert!(check_group_by_type(&list(dictionary)).is_err());
+    }
 }
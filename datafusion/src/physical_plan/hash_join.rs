@@ -18,15 +18,18 @@
 //! Defines the join plan for executing partitions in parallel and then joining the results
 //! into a set of partitions.
 
-use ahash::CallHasher;
 use ahash::RandomState;
 
 use arrow::{
     array::{
         ArrayData, ArrayRef, BooleanArray, Date32Array, Date64Array, Float32Array,
-        Float64Array, LargeStringArray, PrimitiveArray, TimestampMicrosecondArray,
-        TimestampMillisecondArray, TimestampNanosecondArray, UInt32BufferBuilder,
-        UInt32Builder, UInt64BufferBuilder, UInt64Builder,
+        Float64Array, Int64Decimal0Array, Int64Decimal10Array, Int64Decimal1Array,
+        Int64Decimal2Array, Int64Decimal3Array, Int64Decimal4Array, Int64Decimal5Array,
+        Int96Decimal0Array, Int96Decimal10Array, Int96Decimal1Array, Int96Decimal2Array,
+        Int96Decimal3Array, Int96Decimal4Array, Int96Decimal5Array, LargeStringArray,
+        PrimitiveArray, TimestampMicrosecondArray, TimestampMillisecondArray,
+        TimestampNanosecondArray, UInt32BufferBuilder, UInt32Builder,
+        UInt64BufferBuilder, UInt64Builder,
     },
     compute,
     datatypes::{TimeUnit, UInt32Type, UInt64Type},
@@ -53,6 +56,7 @@ use arrow::array::{
 };
 
 use super::expressions::Column;
+pub use super::hash_utils::create_hashes;
 use super::{
     coalesce_partitions::CoalescePartitionsExec,
     hash_utils::{build_join_schema, check_join_is_valid, JoinOn},
@@ -61,7 +65,7 @@ use crate::error::{DataFusionError, Result};
 use crate::logical_plan::JoinType;
 
 use super::{
-    DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+    DisplayFormatType, ExecutionPlan, OptimizerHints, Partitioning, RecordBatchStream,
     SendableRecordBatchStream,
 };
 use crate::physical_plan::coalesce_batches::concat_batches;
@@ -274,7 +278,80 @@ impl ExecutionPlan for HashJoinExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        self.right.output_partitioning()
+        match (self.right.output_partitioning(), &self.join_type) {
+            // The right columns follow the left ones in the output. Outer joins on the left
+            // produce rows with nulls instead of the right columns, which may end up in any
+            // partition.
+            (Partitioning::Hash(exprs, n), JoinType::Inner | JoinType::Right) => {
+                let left_len = self.left.schema().fields().len();
+                let exprs = exprs
+                    .iter()
+                    .map(|e| {
+                        e.as_any().downcast_ref::<Column>().map(|c| {
+                            Arc::new(Column::new(c.name(), left_len + c.index()))
+                                as Arc<dyn PhysicalExpr>
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>();
+                match exprs {
+                    Some(exprs) => Partitioning::Hash(exprs, n),
+                    None => Partitioning::UnknownPartitioning(n),
+                }
+            }
+            (Partitioning::Hash(_, n), _) => Partitioning::UnknownPartitioning(n),
+            (partitioning, _) => partitioning,
+        }
+    }
+
+    fn output_hints(&self) -> OptimizerHints {
+        // Output rows are produced while streaming the probe (right) side and keep its
+        // order. Only inner and right joins are order-preserving: other join types emit
+        // unmatched build rows after the whole probe side. Note that `JoinSelection` turns
+        // a `LEFT JOIN` of a large table with a smaller one into a right join.
+        if !matches!(self.join_type, JoinType::Inner | JoinType::Right) {
+            return OptimizerHints::default();
+        }
+        let right_hints = self.right.output_hints();
+        let left_hints = self.left.output_hints();
+        let column_indices = match self.column_indices_from_schema() {
+            Ok(indices) => indices,
+            Err(_) => return OptimizerHints::default(),
+        };
+
+        let mut right_to_output = vec![None; self.right.schema().fields().len()];
+        let mut single_value_columns = Vec::new();
+        for (out_i, c) in column_indices.iter().enumerate() {
+            if !c.is_left {
+                right_to_output[c.index] = Some(out_i);
+                if right_hints.single_value_columns.contains(&c.index) {
+                    single_value_columns.push(out_i);
+                }
+            } else if self.join_type == JoinType::Inner
+                && left_hints.single_value_columns.contains(&c.index)
+            {
+                // every output row matches some build row
+                single_value_columns.push(out_i);
+            }
+        }
+
+        let mut sort_order = Vec::new();
+        if let Some(in_so) = right_hints.sort_order {
+            for in_col in in_so {
+                match right_to_output[in_col] {
+                    Some(out_col) => sort_order.push(out_col),
+                    None => break,
+                }
+            }
+        }
+
+        OptimizerHints {
+            single_value_columns,
+            sort_order: if sort_order.is_empty() {
+                None
+            } else {
+                Some(sort_order)
+            },
+        }
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
@@ -790,13 +867,6 @@ impl BuildHasher for IdHashBuilder {
     }
 }
 
-// Combines two hashes into one hash
-#[inline]
-fn combine_hashes(l: u64, r: u64) -> u64 {
-    let hash = (17 * 37u64).wrapping_add(l);
-    hash.wrapping_mul(37).wrapping_add(r)
-}
-
 macro_rules! equal_rows_elem {
     ($array_type:ident, $l: ident, $r: ident, $left: ident, $right: ident) => {{
         let left_array = $l.as_any().downcast_ref::<$array_type>().unwrap();
@@ -832,363 +902,83 @@ fn equal_rows(
             DataType::UInt16 => equal_rows_elem!(UInt16Array, l, r, left, right),
             DataType::UInt32 => equal_rows_elem!(UInt32Array, l, r, left, right),
             DataType::UInt64 => equal_rows_elem!(UInt64Array, l, r, left, right),
-            DataType::Timestamp(_, None) => {
-                equal_rows_elem!(Int64Array, l, r, left, right)
-            }
-            DataType::Utf8 => equal_rows_elem!(StringArray, l, r, left, right),
-            DataType::LargeUtf8 => equal_rows_elem!(LargeStringArray, l, r, left, right),
-            _ => {
-                // This is internal because we should have caught this before.
-                err = Some(Err(DataFusionError::Internal(
-                    "Unsupported data type in hasher".to_string(),
-                )));
-                false
-            }
-        });
-
-    err.unwrap_or(Ok(res))
-}
-
-macro_rules! hash_array {
-    ($array_type:ident, $column: ident, $ty: ident, $hashes: ident, $random_state: ident, $multi_col: ident) => {
-        let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
-        if array.null_count() == 0 {
-            if $multi_col {
-                for (i, hash) in $hashes.iter_mut().enumerate() {
-                    *hash = combine_hashes(
-                        $ty::get_hash(&array.value(i), $random_state),
-                        *hash,
-                    );
-                }
-            } else {
-                for (i, hash) in $hashes.iter_mut().enumerate() {
-                    *hash = $ty::get_hash(&array.value(i), $random_state);
-                }
-            }
-        } else {
-            if $multi_col {
-                for (i, hash) in $hashes.iter_mut().enumerate() {
-                    if !array.is_null(i) {
-                        *hash = combine_hashes(
-                            $ty::get_hash(&array.value(i), $random_state),
-                            *hash,
-                        );
-                    }
-                }
-            } else {
-                for (i, hash) in $hashes.iter_mut().enumerate() {
-                    if !array.is_null(i) {
-                        *hash = $ty::get_hash(&array.value(i), $random_state);
-                    }
-                }
-            }
-        }
-    };
-}
-
-macro_rules! hash_array_primitive {
-    ($array_type:ident, $column: ident, $ty: ident, $hashes: ident, $random_state: ident, $multi_col: ident) => {
-        let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
-        let values = array.values();
-
-        if array.null_count() == 0 {
-            if $multi_col {
-                for (hash, value) in $hashes.iter_mut().zip(values.iter()) {
-                    *hash = combine_hashes($ty::get_hash(value, $random_state), *hash);
-                }
-            } else {
-                for (hash, value) in $hashes.iter_mut().zip(values.iter()) {
-                    *hash = $ty::get_hash(value, $random_state)
-                }
-            }
-        } else {
-            if $multi_col {
-                for (i, (hash, value)) in
-                    $hashes.iter_mut().zip(values.iter()).enumerate()
-                {
-                    if !array.is_null(i) {
-                        *hash =
-                            combine_hashes($ty::get_hash(value, $random_state), *hash);
-                    }
-                }
-            } else {
-                for (i, (hash, value)) in
-                    $hashes.iter_mut().zip(values.iter()).enumerate()
-                {
-                    if !array.is_null(i) {
-                        *hash = $ty::get_hash(value, $random_state);
-                    }
-                }
-            }
-        }
-    };
-}
-
-macro_rules! hash_array_float {
-    ($array_type:ident, $column: ident, $ty: ident, $hashes: ident, $random_state: ident, $multi_col: ident) => {
-        let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
-        let values = array.values();
-
-        if array.null_count() == 0 {
-            if $multi_col {
-                for (hash, value) in $hashes.iter_mut().zip(values.iter()) {
-                    *hash = combine_hashes(
-                        $ty::get_hash(
-                            &$ty::from_le_bytes(value.to_le_bytes()),
-                            $random_state,
-                        ),
-                        *hash,
-                    );
-                }
-            } else {
-                for (hash, value) in $hashes.iter_mut().zip(values.iter()) {
-                    *hash = $ty::get_hash(
-                        &$ty::from_le_bytes(value.to_le_bytes()),
-                        $random_state,
-                    )
-                }
+            DataType::Float32 => equal_rows_elem!(Float32Array, l, r, left, right),
+            DataType::Float64 => equal_rows_elem!(Float64Array, l, r, left, right),
+            DataType::Timestamp(TimeUnit::Millisecond, None) => {
+                equal_rows_elem!(TimestampMillisecondArray, l, r, left, right)
             }
-        } else {
-            if $multi_col {
-                for (i, (hash, value)) in
-                    $hashes.iter_mut().zip(values.iter()).enumerate()
-                {
-                    if !array.is_null(i) {
-                        *hash = combine_hashes(
-                            $ty::get_hash(
-                                &$ty::from_le_bytes(value.to_le_bytes()),
-                                $random_state,
-                            ),
-                            *hash,
-                        );
-                    }
-                }
-            } else {
-                for (i, (hash, value)) in
-                    $hashes.iter_mut().zip(values.iter()).enumerate()
-                {
-                    if !array.is_null(i) {
-                        *hash = $ty::get_hash(
-                            &$ty::from_le_bytes(value.to_le_bytes()),
-                            $random_state,
-                        );
-                    }
-                }
+            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+                equal_rows_elem!(TimestampMicrosecondArray, l, r, left, right)
             }
-        }
-    };
-}
-
-/// Creates hash values for every element in the row based on the values in the columns
-pub fn create_hashes<'a>(
-    arrays: &[ArrayRef],
-    random_state: &RandomState,
-    hashes_buffer: &'a mut Vec<u64>,
-) -> Result<&'a mut Vec<u64>> {
-    // combine hashes with `combine_hashes` if we have more than 1 column
-    let multi_col = arrays.len() > 1;
-
-    for col in arrays {
-        match col.data_type() {
-            DataType::UInt8 => {
-                hash_array_primitive!(
-                    UInt8Array,
-                    col,
-                    u8,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+                equal_rows_elem!(TimestampNanosecondArray, l, r, left, right)
             }
-            DataType::UInt16 => {
-                hash_array_primitive!(
-                    UInt16Array,
-                    col,
-                    u16,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Date32 => equal_rows_elem!(Date32Array, l, r, left, right),
+            DataType::Date64 => equal_rows_elem!(Date64Array, l, r, left, right),
+            DataType::Utf8 => equal_rows_elem!(StringArray, l, r, left, right),
+            DataType::LargeUtf8 => equal_rows_elem!(LargeStringArray, l, r, left, right),
+            DataType::Int64Decimal(0) => {
+                equal_rows_elem!(Int64Decimal0Array, l, r, left, right)
             }
-            DataType::UInt32 => {
-                hash_array_primitive!(
-                    UInt32Array,
-                    col,
-                    u32,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int64Decimal(1) => {
+                equal_rows_elem!(Int64Decimal1Array, l, r, left, right)
             }
-            DataType::UInt64 => {
-                hash_array_primitive!(
-                    UInt64Array,
-                    col,
-                    u64,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int64Decimal(2) => {
+                equal_rows_elem!(Int64Decimal2Array, l, r, left, right)
             }
-            DataType::Int8 => {
-                hash_array_primitive!(
-                    Int8Array,
-                    col,
-                    i8,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int64Decimal(3) => {
+                equal_rows_elem!(Int64Decimal3Array, l, r, left, right)
             }
-            DataType::Int16 => {
-                hash_array_primitive!(
-                    Int16Array,
-                    col,
-                    i16,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int64Decimal(4) => {
+                equal_rows_elem!(Int64Decimal4Array, l, r, left, right)
             }
-            DataType::Int32 => {
-                hash_array_primitive!(
-                    Int32Array,
-                    col,
-                    i32,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int64Decimal(5) => {
+                equal_rows_elem!(Int64Decimal5Array, l, r, left, right)
             }
-            DataType::Int64 => {
-                hash_array_primitive!(
-                    Int64Array,
-                    col,
-                    i64,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int64Decimal(10) => {
+                equal_rows_elem!(Int64Decimal10Array, l, r, left, right)
             }
-            DataType::Int96 => {
-                hash_array_primitive!(
-                    Int96Array,
-                    col,
-                    i128,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int96Decimal(0) => {
+                equal_rows_elem!(Int96Decimal0Array, l, r, left, right)
             }
-            DataType::Float32 => {
-                hash_array_float!(
-                    Float32Array,
-                    col,
-                    u32,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int96Decimal(1) => {
+                equal_rows_elem!(Int96Decimal1Array, l, r, left, right)
             }
-            DataType::Float64 => {
-                hash_array_float!(
-                    Float64Array,
-                    col,
-                    u64,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int96Decimal(2) => {
+                equal_rows_elem!(Int96Decimal2Array, l, r, left, right)
             }
-            DataType::Timestamp(TimeUnit::Millisecond, None) => {
-                hash_array_primitive!(
-                    TimestampMillisecondArray,
-                    col,
-                    i64,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int96Decimal(3) => {
+                equal_rows_elem!(Int96Decimal3Array, l, r, left, right)
             }
-            DataType::Timestamp(TimeUnit::Microsecond, None) => {
-                hash_array_primitive!(
-                    TimestampMicrosecondArray,
-                    col,
-                    i64,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int96Decimal(4) => {
+                equal_rows_elem!(Int96Decimal4Array, l, r, left, right)
             }
-            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
-                hash_array_primitive!(
-                    TimestampNanosecondArray,
-                    col,
-                    i64,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int96Decimal(5) => {
+                equal_rows_elem!(Int96Decimal5Array, l, r, left, right)
             }
-            DataType::Date32 => {
-                hash_array_primitive!(
-                    Date32Array,
-                    col,
-                    i32,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Int96Decimal(10) => {
+                equal_rows_elem!(Int96Decimal10Array, l, r, left, right)
             }
-            DataType::Date64 => {
-                hash_array_primitive!(
-                    Date64Array,
-                    col,
-                    i64,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
-            }
-            DataType::Boolean => {
-                hash_array!(
-                    BooleanArray,
-                    col,
-                    u8,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
-            }
-            DataType::Utf8 => {
-                hash_array!(
-                    StringArray,
-                    col,
-                    str,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
-            }
-            DataType::LargeUtf8 => {
-                hash_array!(
-                    LargeStringArray,
-                    col,
-                    str,
-                    hashes_buffer,
-                    random_state,
-                    multi_col
-                );
+            DataType::Dictionary(..)
+            | DataType::List(_)
+            | DataType::FixedSizeList(..)
+            | DataType::Struct(_) => {
+                // compare the values rather than dictionary keys or list offsets
+                l.is_valid(left)
+                    && r.is_valid(right)
+                    && l.slice(left, 1).data() == r.slice(right, 1).data()
             }
             _ => {
                 // This is internal because we should have caught this before.
-                return Err(DataFusionError::Internal(
-                    "Unsupported data type in hasher".to_string(),
-                ));
+                err = Some(Err(DataFusionError::Internal(format!(
+                    "Unsupported data type in hasher {}",
+                    l.data_type()
+                ))));
+                false
             }
-        }
-    }
-    Ok(hashes_buffer)
+        });
+
+    err.unwrap_or(Ok(res))
 }
 
 // Produces a batch for left-side rows that have/have not been matched during the whole join
@@ -1331,12 +1121,17 @@ mod tests {
     use crate::{
         assert_batches_sorted_eq,
         physical_plan::{
-            common, expressions::Column, memory::MemoryExec, repartition::RepartitionExec,
+            common,
+            expressions::{Column, PhysicalSortExpr},
+            memory::MemoryExec,
+            repartition::RepartitionExec,
+            sort::SortExec,
         },
         test::{build_table_i32, columns},
     };
 
     use super::*;
+    use arrow::compute::SortOptions;
     use std::sync::Arc;
 
     fn build_table(
@@ -2127,18 +1922,67 @@ mod tests {
     }
 
     #[test]
-    fn create_hashes_for_float_arrays() -> Result<()> {
-        let f32_arr = Arc::new(Float32Array::from(vec![0.12, 0.5, 1f32, 444.7]));
-        let f64_arr = Arc::new(Float64Array::from(vec![0.12, 0.5, 1f64, 444.7]));
-
-        let random_state = RandomState::with_seeds(0, 0, 0, 0);
-        let hashes_buff = &mut vec![0; f32_arr.len()];
-        let hashes = create_hashes(&[f32_arr], &random_state, hashes_buff)?;
-        assert_eq!(hashes.len(), 4,);
+    fn join_preserves_probe_sort_order() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 7]),
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table(
+            ("a2", &vec![10, 20, 30]),
+            ("b2", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+        let right: Arc<dyn ExecutionPlan> = Arc::new(SortExec::try_new(
+            vec![
+                PhysicalSortExpr {
+                    expr: Arc::new(Column::new_with_schema("c2", &right.schema())?),
+                    options: SortOptions::default(),
+                },
+                PhysicalSortExpr {
+                    expr: Arc::new(Column::new_with_schema("a2", &right.schema())?),
+                    options: SortOptions::default(),
+                },
+            ],
+            right,
+        )?);
+        let on = vec![(
+            Column::new_with_schema("b1", &left.schema())?,
+            Column::new_with_schema("b2", &right.schema())?,
+        )];
 
-        let hashes = create_hashes(&[f64_arr], &random_state, hashes_buff)?;
-        assert_eq!(hashes.len(), 4,);
+        for join_type in &[JoinType::Inner, JoinType::Right] {
+            let join = join(left.clone(), right.clone(), on.clone(), join_type)?;
+            assert_eq!(join.output_hints().sort_order, Some(vec![5, 3]));
+        }
+        for join_type in &[JoinType::Left, JoinType::Full, JoinType::Semi] {
+            let join = join(left.clone(), right.clone(), on.clone(), join_type)?;
+            assert_eq!(join.output_hints().sort_order, None);
+        }
+        Ok(())
+    }
 
+    #[test]
+    fn equal_rows_for_dictionary_and_timestamp_keys() -> Result<()> {
+        use arrow::array::DictionaryArray;
+        use arrow::datatypes::Int8Type;
+
+        let left: DictionaryArray<Int8Type> = vec!["x", "y"].into_iter().collect();
+        let right: DictionaryArray<Int8Type> = vec!["y", "z", "x"].into_iter().collect();
+        let left: Vec<ArrayRef> = vec![Arc::new(left)];
+        let right: Vec<ArrayRef> = vec![Arc::new(right)];
+        assert!(equal_rows(0, 2, &left, &right)?);
+        assert!(equal_rows(1, 0, &left, &right)?);
+        assert!(!equal_rows(0, 1, &left, &right)?);
+
+        let left: Vec<ArrayRef> =
+            vec![Arc::new(TimestampMillisecondArray::from(vec![1, 2]))];
+        let right: Vec<ArrayRef> = vec![Arc::new(TimestampMillisecondArray::from(vec![
+            Some(2),
+            None,
+        ]))];
+        assert!(equal_rows(1, 0, &left, &right)?);
+        assert!(!equal_rows(0, 1, &left, &right)?);
         Ok(())
     }
 
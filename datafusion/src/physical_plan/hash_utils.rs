@@ -15,16 +15,31 @@
 // specific language governing permissions and limitations
 // under the License.
 
-//! Functionality used both on logical and physical plans
+//! Functionality used both on logical and physical plans, and the hashing of the
+//! values of join, repartitioning and aggregation keys
 
 use crate::error::{DataFusionError, Result};
 
 use std::collections::HashSet;
 
 use crate::logical_plan::JoinType;
+use crate::physical_plan::array_expressions::list_values_and_offsets;
 use crate::physical_plan::expressions::Column;
-use arrow::datatypes::Field;
-use arrow::datatypes::Schema;
+use ahash::{CallHasher, RandomState};
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, Date32Array, Date64Array, DictionaryArray,
+    Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int64Decimal0Array,
+    Int64Decimal10Array, Int64Decimal1Array, Int64Decimal2Array, Int64Decimal3Array,
+    Int64Decimal4Array, Int64Decimal5Array, Int8Array, Int96Array, Int96Decimal0Array,
+    Int96Decimal10Array, Int96Decimal1Array, Int96Decimal2Array, Int96Decimal3Array,
+    Int96Decimal4Array, Int96Decimal5Array, LargeStringArray, StringArray, StructArray,
+    TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+    UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+};
+use arrow::datatypes::{
+    ArrowDictionaryKeyType, ArrowNativeType, DataType, Field, Int16Type, Int32Type,
+    Int64Type, Int8Type, Schema, TimeUnit, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
 
 /// The on clause of the join, as vector of (left, right) columns.
 pub type JoinOn = Vec<(Column, Column)>;
@@ -89,23 +104,683 @@ fn check_join_set_is_valid(
 }
 
 /// Creates a schema for a join operation.
-/// The fields from the left side are first
+/// The fields from the left side are first. Fields of the side that can be
+/// missing a match in an outer join are nullable.
 pub fn build_join_schema(left: &Schema, right: &Schema, join_type: &JoinType) -> Schema {
     let fields: Vec<Field> = match join_type {
         JoinType::Inner | JoinType::Left | JoinType::Full | JoinType::Right => {
-            let left_fields = left.fields().iter();
-            let right_fields = right.fields().iter();
+            let left_fields = left
+                .fields()
+                .iter()
+                .map(|f| nullable_if(f, join_type.is_left_nullable()));
+            let right_fields = right
+                .fields()
+                .iter()
+                .map(|f| nullable_if(f, join_type.is_right_nullable()));
             // left then right
-            left_fields.chain(right_fields).cloned().collect()
+            left_fields.chain(right_fields).collect()
         }
         JoinType::Semi | JoinType::Anti => left.fields().clone(),
     };
     Schema::new(fields)
 }
 
+/// Returns a copy of `field` that is nullable if `nullable` is set
+fn nullable_if(field: &Field, nullable: bool) -> Field {
+    if nullable && !field.is_nullable() {
+        Field::new(field.name(), field.data_type().clone(), true)
+    } else {
+        field.clone()
+    }
+}
+
+// Combines two hashes into one hash
+#[inline]
+fn combine_hashes(l: u64, r: u64) -> u64 {
+    let hash = (17 * 37u64).wrapping_add(l);
+    hash.wrapping_mul(37).wrapping_add(r)
+}
+
+macro_rules! hash_array {
+    ($array_type:ident, $column: ident, $ty: ident, $hashes: ident, $random_state: ident, $multi_col: ident) => {
+        let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
+        if array.null_count() == 0 {
+            if $multi_col {
+                for (i, hash) in $hashes.iter_mut().enumerate() {
+                    *hash = combine_hashes(
+                        $ty::get_hash(&array.value(i), $random_state),
+                        *hash,
+                    );
+                }
+            } else {
+                for (i, hash) in $hashes.iter_mut().enumerate() {
+                    *hash = $ty::get_hash(&array.value(i), $random_state);
+                }
+            }
+        } else {
+            if $multi_col {
+                for (i, hash) in $hashes.iter_mut().enumerate() {
+                    if !array.is_null(i) {
+                        *hash = combine_hashes(
+                            $ty::get_hash(&array.value(i), $random_state),
+                            *hash,
+                        );
+                    }
+                }
+            } else {
+                for (i, hash) in $hashes.iter_mut().enumerate() {
+                    if !array.is_null(i) {
+                        *hash = $ty::get_hash(&array.value(i), $random_state);
+                    }
+                }
+            }
+        }
+    };
+}
+
+macro_rules! hash_array_primitive {
+    ($array_type:ident, $column: ident, $ty: ident, $hashes: ident, $random_state: ident, $multi_col: ident) => {
+        let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
+        let values = array.values();
+
+        if array.null_count() == 0 {
+            if $multi_col {
+                for (hash, value) in $hashes.iter_mut().zip(values.iter()) {
+                    *hash = combine_hashes($ty::get_hash(value, $random_state), *hash);
+                }
+            } else {
+                for (hash, value) in $hashes.iter_mut().zip(values.iter()) {
+                    *hash = $ty::get_hash(value, $random_state)
+                }
+            }
+        } else {
+            if $multi_col {
+                for (i, (hash, value)) in
+                    $hashes.iter_mut().zip(values.iter()).enumerate()
+                {
+                    if !array.is_null(i) {
+                        *hash =
+                            combine_hashes($ty::get_hash(value, $random_state), *hash);
+                    }
+                }
+            } else {
+                for (i, (hash, value)) in
+                    $hashes.iter_mut().zip(values.iter()).enumerate()
+                {
+                    if !array.is_null(i) {
+                        *hash = $ty::get_hash(value, $random_state);
+                    }
+                }
+            }
+        }
+    };
+}
+
+macro_rules! hash_array_float {
+    ($array_type:ident, $column: ident, $ty: ident, $hashes: ident, $random_state: ident, $multi_col: ident) => {
+        let array = $column.as_any().downcast_ref::<$array_type>().unwrap();
+        let values = array.values();
+
+        if array.null_count() == 0 {
+            if $multi_col {
+                for (hash, value) in $hashes.iter_mut().zip(values.iter()) {
+                    *hash = combine_hashes(
+                        $ty::get_hash(
+                            &$ty::from_le_bytes(value.to_le_bytes()),
+                            $random_state,
+                        ),
+                        *hash,
+                    );
+                }
+            } else {
+                for (hash, value) in $hashes.iter_mut().zip(values.iter()) {
+                    *hash = $ty::get_hash(
+                        &$ty::from_le_bytes(value.to_le_bytes()),
+                        $random_state,
+                    )
+                }
+            }
+        } else {
+            if $multi_col {
+                for (i, (hash, value)) in
+                    $hashes.iter_mut().zip(values.iter()).enumerate()
+                {
+                    if !array.is_null(i) {
+                        *hash = combine_hashes(
+                            $ty::get_hash(
+                                &$ty::from_le_bytes(value.to_le_bytes()),
+                                $random_state,
+                            ),
+                            *hash,
+                        );
+                    }
+                }
+            } else {
+                for (i, (hash, value)) in
+                    $hashes.iter_mut().zip(values.iter()).enumerate()
+                {
+                    if !array.is_null(i) {
+                        *hash = $ty::get_hash(
+                            &$ty::from_le_bytes(value.to_le_bytes()),
+                            $random_state,
+                        );
+                    }
+                }
+            }
+        }
+    };
+}
+
+/// Creates hash values for every element in the row based on the values in the columns
+pub fn create_hashes<'a>(
+    arrays: &[ArrayRef],
+    random_state: &RandomState,
+    hashes_buffer: &'a mut Vec<u64>,
+) -> Result<&'a mut Vec<u64>> {
+    // combine hashes with `combine_hashes` if we have more than 1 column
+    let multi_col = arrays.len() > 1;
+
+    for col in arrays {
+        match col.data_type() {
+            // all the values are NULL, which do not change the hashes
+            DataType::Null => {}
+            DataType::UInt8 => {
+                hash_array_primitive!(
+                    UInt8Array,
+                    col,
+                    u8,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::UInt16 => {
+                hash_array_primitive!(
+                    UInt16Array,
+                    col,
+                    u16,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::UInt32 => {
+                hash_array_primitive!(
+                    UInt32Array,
+                    col,
+                    u32,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::UInt64 => {
+                hash_array_primitive!(
+                    UInt64Array,
+                    col,
+                    u64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int8 => {
+                hash_array_primitive!(
+                    Int8Array,
+                    col,
+                    i8,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int16 => {
+                hash_array_primitive!(
+                    Int16Array,
+                    col,
+                    i16,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int32 => {
+                hash_array_primitive!(
+                    Int32Array,
+                    col,
+                    i32,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int64 => {
+                hash_array_primitive!(
+                    Int64Array,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int96 => {
+                hash_array_primitive!(
+                    Int96Array,
+                    col,
+                    i128,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Float32 => {
+                hash_array_float!(
+                    Float32Array,
+                    col,
+                    u32,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Float64 => {
+                hash_array_float!(
+                    Float64Array,
+                    col,
+                    u64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, None) => {
+                hash_array_primitive!(
+                    TimestampMillisecondArray,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+                hash_array_primitive!(
+                    TimestampMicrosecondArray,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, None) => {
+                hash_array_primitive!(
+                    TimestampNanosecondArray,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Date32 => {
+                hash_array_primitive!(
+                    Date32Array,
+                    col,
+                    i32,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Date64 => {
+                hash_array_primitive!(
+                    Date64Array,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Boolean => {
+                hash_array!(
+                    BooleanArray,
+                    col,
+                    u8,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Utf8 => {
+                hash_array!(
+                    StringArray,
+                    col,
+                    str,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::LargeUtf8 => {
+                hash_array!(
+                    LargeStringArray,
+                    col,
+                    str,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int64Decimal(0) => {
+                hash_array_primitive!(
+                    Int64Decimal0Array,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int64Decimal(1) => {
+                hash_array_primitive!(
+                    Int64Decimal1Array,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int64Decimal(2) => {
+                hash_array_primitive!(
+                    Int64Decimal2Array,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int64Decimal(3) => {
+                hash_array_primitive!(
+                    Int64Decimal3Array,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int64Decimal(4) => {
+                hash_array_primitive!(
+                    Int64Decimal4Array,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int64Decimal(5) => {
+                hash_array_primitive!(
+                    Int64Decimal5Array,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int64Decimal(10) => {
+                hash_array_primitive!(
+                    Int64Decimal10Array,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int96Decimal(0) => {
+                hash_array_primitive!(
+                    Int96Decimal0Array,
+                    col,
+                    i128,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int96Decimal(1) => {
+                hash_array_primitive!(
+                    Int96Decimal1Array,
+                    col,
+                    i128,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int96Decimal(2) => {
+                hash_array_primitive!(
+                    Int96Decimal2Array,
+                    col,
+                    i128,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int96Decimal(3) => {
+                hash_array_primitive!(
+                    Int96Decimal3Array,
+                    col,
+                    i128,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int96Decimal(4) => {
+                hash_array_primitive!(
+                    Int96Decimal4Array,
+                    col,
+                    i128,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int96Decimal(5) => {
+                hash_array_primitive!(
+                    Int96Decimal5Array,
+                    col,
+                    i128,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Int96Decimal(10) => {
+                hash_array_primitive!(
+                    Int96Decimal10Array,
+                    col,
+                    i128,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Dictionary(index_type, _) => match **index_type {
+                DataType::Int8 => hash_dictionary::<Int8Type>(
+                    col,
+                    random_state,
+                    hashes_buffer,
+                    multi_col,
+                )?,
+                DataType::Int16 => hash_dictionary::<Int16Type>(
+                    col,
+                    random_state,
+                    hashes_buffer,
+                    multi_col,
+                )?,
+                DataType::Int32 => hash_dictionary::<Int32Type>(
+                    col,
+                    random_state,
+                    hashes_buffer,
+                    multi_col,
+                )?,
+                DataType::Int64 => hash_dictionary::<Int64Type>(
+                    col,
+                    random_state,
+                    hashes_buffer,
+                    multi_col,
+                )?,
+                DataType::UInt8 => hash_dictionary::<UInt8Type>(
+                    col,
+                    random_state,
+                    hashes_buffer,
+                    multi_col,
+                )?,
+                DataType::UInt16 => hash_dictionary::<UInt16Type>(
+                    col,
+                    random_state,
+                    hashes_buffer,
+                    multi_col,
+                )?,
+                DataType::UInt32 => hash_dictionary::<UInt32Type>(
+                    col,
+                    random_state,
+                    hashes_buffer,
+                    multi_col,
+                )?,
+                DataType::UInt64 => hash_dictionary::<UInt64Type>(
+                    col,
+                    random_state,
+                    hashes_buffer,
+                    multi_col,
+                )?,
+                _ => {
+                    return Err(DataFusionError::Internal(format!(
+                        "Unsupported dictionary index type in hasher {}",
+                        col.data_type()
+                    )))
+                }
+            },
+            DataType::List(_) | DataType::FixedSizeList(..) => {
+                hash_list(col, random_state, hashes_buffer, multi_col)?;
+            }
+            DataType::Struct(_) => {
+                hash_struct(col, random_state, hashes_buffer, multi_col)?;
+            }
+            _ => {
+                // This is internal because we should have caught this before.
+                return Err(DataFusionError::Internal(format!(
+                    "Unsupported data type in hasher {}",
+                    col.data_type()
+                )));
+            }
+        }
+    }
+    Ok(hashes_buffer)
+}
+
+/// Sets `hash` to the hash `value` of a column, combined with the hashes of the
+/// previous columns when there are several
+#[inline]
+fn update_hash(hash: &mut u64, value: u64, multi_col: bool) {
+    *hash = if multi_col {
+        combine_hashes(value, *hash)
+    } else {
+        value
+    };
+}
+
+/// Hashes the values of a dictionary rather than its keys, so that equal values have
+/// equal hashes in arrays with different dictionaries
+fn hash_dictionary<K: ArrowDictionaryKeyType>(
+    array: &ArrayRef,
+    random_state: &RandomState,
+    hashes_buffer: &mut Vec<u64>,
+    multi_col: bool,
+) -> Result<()> {
+    let dict = array.as_any().downcast_ref::<DictionaryArray<K>>().unwrap();
+    let values = dict.values();
+    let mut values_hashes = vec![0; values.len()];
+    create_hashes(&[values.clone()], random_state, &mut values_hashes)?;
+
+    let keys = dict.keys();
+    for (i, hash) in hashes_buffer.iter_mut().enumerate() {
+        if dict.is_valid(i) {
+            let index = keys.value(i).to_usize().ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "Can not convert index to usize in dictionary of type {:?}",
+                    keys.data_type()
+                ))
+            })?;
+            update_hash(hash, values_hashes[index], multi_col);
+        }
+    }
+    Ok(())
+}
+
+/// Hashes a list by combining the hashes of its elements
+fn hash_list(
+    array: &ArrayRef,
+    random_state: &RandomState,
+    hashes_buffer: &mut Vec<u64>,
+    multi_col: bool,
+) -> Result<()> {
+    let (values, offsets) = list_values_and_offsets(array)?;
+    let mut values_hashes = vec![0; values.len()];
+    create_hashes(&[values], random_state, &mut values_hashes)?;
+
+    for (i, hash) in hashes_buffer.iter_mut().enumerate() {
+        if array.is_valid(i) {
+            let (start, end) = (offsets[i] as usize, offsets[i + 1] as usize);
+            // start from the length so that NULL elements still count
+            let list_hash = values_hashes[start..end]
+                .iter()
+                .fold((end - start) as u64, |h, v| combine_hashes(*v, h));
+            update_hash(hash, list_hash, multi_col);
+        }
+    }
+    Ok(())
+}
+
+/// Hashes a struct by combining the hashes of its fields
+fn hash_struct(
+    array: &ArrayRef,
+    random_state: &RandomState,
+    hashes_buffer: &mut Vec<u64>,
+    multi_col: bool,
+) -> Result<()> {
+    let structs = array.as_any().downcast_ref::<StructArray>().unwrap();
+    let fields = structs
+        .columns()
+        .into_iter()
+        .cloned()
+        .collect::<Vec<ArrayRef>>();
+    let mut struct_hashes = vec![0; array.len()];
+    // a single field is not combined, as a single column
+    create_hashes(&fields, random_state, &mut struct_hashes)?;
+
+    for (i, hash) in hashes_buffer.iter_mut().enumerate() {
+        if array.is_valid(i) {
+            update_hash(hash, struct_hashes[i], multi_col);
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use arrow::datatypes::DataType;
+    use std::sync::Arc;
 
     fn check(left: &[Column], right: &[Column], on: &[(Column, Column)]) -> Result<()> {
         let left = left
@@ -165,4 +840,103 @@ mod tests {
 
         assert!(check(&left, &right, on).is_ok());
     }
+
+    #[test]
+    fn join_schema_nullability() {
+        let left = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        let right = Schema::new(vec![Field::new("b", DataType::Int32, false)]);
+        let nullable = |join_type: JoinType| {
+            build_join_schema(&left, &right, &join_type)
+                .fields()
+                .iter()
+                .map(|f| f.is_nullable())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(nullable(JoinType::Inner), vec![false, false]);
+        assert_eq!(nullable(JoinType::Left), vec![false, true]);
+        assert_eq!(nullable(JoinType::Right), vec![true, false]);
+        assert_eq!(nullable(JoinType::Full), vec![true, true]);
+        assert_eq!(nullable(JoinType::Semi), vec![false]);
+        assert_eq!(nullable(JoinType::Anti), vec![false]);
+    }
+
+    fn hashes(arrays: &[ArrayRef]) -> Result<Vec<u64>> {
+        let random_state = RandomState::with_seeds(0, 0, 0, 0);
+        let mut hashes_buffer = vec![0; arrays[0].len()];
+        create_hashes(arrays, &random_state, &mut hashes_buffer)?;
+        Ok(hashes_buffer)
+    }
+
+    #[test]
+    fn create_hashes_for_float_arrays() -> Result<()> {
+        let f32_arr = Arc::new(Float32Array::from(vec![0.12, 0.5, 1f32, 444.7]));
+        let f64_arr = Arc::new(Float64Array::from(vec![0.12, 0.5, 1f64, 444.7]));
+
+        let random_state = RandomState::with_seeds(0, 0, 0, 0);
+        let hashes_buff = &mut vec![0; f32_arr.len()];
+        let hashes = create_hashes(&[f32_arr], &random_state, hashes_buff)?;
+        assert_eq!(hashes.len(), 4,);
+
+        let hashes = create_hashes(&[f64_arr], &random_state, hashes_buff)?;
+        assert_eq!(hashes.len(), 4,);
+
+        Ok(())
+    }
+
+    #[test]
+    fn create_hashes_for_dictionary_arrays() -> Result<()> {
+        let a: DictionaryArray<Int8Type> = vec!["x", "y", "x"].into_iter().collect();
+        let b: DictionaryArray<Int32Type> = vec!["y", "x", "z"].into_iter().collect();
+        let a = hashes(&[Arc::new(a)])?;
+        let b = hashes(&[Arc::new(b)])?;
+        // the hashes of the values, whatever their keys
+        let values = hashes(&[Arc::new(StringArray::from(vec!["x", "y", "x"]))])?;
+        assert_eq!(a, values);
+        assert_eq!(a[0], b[1]);
+        assert_eq!(a[1], b[0]);
+        assert_ne!(a[0], b[2]);
+        Ok(())
+    }
+
+    #[test]
+    fn create_hashes_for_decimal_arrays() -> Result<()> {
+        let a = Int64Decimal2Array::from(vec![Some(100), None, Some(100)]);
+        let hashes = hashes(&[Arc::new(a)])?;
+        assert_eq!(hashes[0], hashes[2]);
+        Ok(())
+    }
+
+    #[test]
+    fn create_hashes_for_nested_arrays() -> Result<()> {
+        use arrow::array::{ListBuilder, StringBuilder};
+
+        let mut builder = ListBuilder::new(StringBuilder::new(0));
+        for list in &[vec!["a", "b"], vec!["b", "a"], vec!["a"], vec!["a", "b"]] {
+            for v in list {
+                builder.values().append_value(v)?;
+            }
+            builder.append(true)?;
+        }
+        let lists: ArrayRef = Arc::new(builder.finish());
+        let list_hashes = hashes(&[lists.clone()])?;
+        assert_eq!(list_hashes[0], list_hashes[3]);
+        assert_ne!(list_hashes[0], list_hashes[1]);
+        assert_ne!(list_hashes[0], list_hashes[2]);
+        // the offsets of a slice do not change the hashes
+        assert_eq!(hashes(&[lists.slice(3, 1)])?[0], list_hashes[0]);
+
+        let ids: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3, 1]));
+        let tags = Field::new("tags", lists.data_type().clone(), false);
+        let structs: ArrayRef = Arc::new(StructArray::from(vec![
+            (Field::new("id", DataType::Int32, false), ids.clone()),
+            (tags, lists.clone()),
+        ]));
+        let struct_hashes = hashes(&[structs])?;
+        assert_eq!(struct_hashes[0], struct_hashes[3]);
+        assert_ne!(struct_hashes[0], struct_hashes[1]);
+        // a struct hashes as its fields in several columns
+        assert_eq!(struct_hashes, hashes(&[ids, lists])?);
+        Ok(())
+    }
 }
@@ -0,0 +1,558 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Higher-order functions, which apply a lambda to the elements of lists, such as
+//! `array_transform(list, x -> x + 1)`.
+//!
+//! The body of a lambda is planned against a schema made of its parameters only, so
+//! it can refer to its parameters and to literals but not to the columns of the
+//! input.
+
+use std::any::Any;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use super::array_expressions::list_values_and_offsets;
+use super::{ColumnarValue, PhysicalExpr};
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::{DFField, DFSchema, Expr};
+use arrow::array::{Array, ArrayData, ArrayRef, BooleanArray, ListArray, UInt32Array};
+use arrow::buffer::Buffer;
+use arrow::compute;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, ToByteSlice};
+use arrow::record_batch::RecordBatch;
+
+/// Enum of the higher-order functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HigherOrderFunction {
+    /// `array_transform(list, x -> expr)`: the list of the results of the lambda on
+    /// the elements of `list`
+    ArrayTransform,
+    /// `array_filter(list, x -> predicate)`: the elements of `list` for which the
+    /// lambda is true
+    ArrayFilter,
+    /// `array_reduce(list, initial, (acc, x) -> expr)`: folds the elements of `list`
+    /// with the lambda, starting from `initial`
+    ArrayReduce,
+}
+
+impl fmt::Display for HigherOrderFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HigherOrderFunction::ArrayTransform => write!(f, "array_transform"),
+            HigherOrderFunction::ArrayFilter => write!(f, "array_filter"),
+            HigherOrderFunction::ArrayReduce => write!(f, "array_reduce"),
+        }
+    }
+}
+
+impl FromStr for HigherOrderFunction {
+    type Err = DataFusionError;
+    fn from_str(name: &str) -> Result<HigherOrderFunction> {
+        Ok(match name.to_lowercase().as_str() {
+            "array_transform" => HigherOrderFunction::ArrayTransform,
+            "array_filter" => HigherOrderFunction::ArrayFilter,
+            "array_reduce" => HigherOrderFunction::ArrayReduce,
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "There is no higher-order function named {}",
+                    name
+                )))
+            }
+        })
+    }
+}
+
+impl HigherOrderFunction {
+    /// The types of the parameters of the lambda, given the types of the arguments
+    /// before it
+    pub fn lambda_param_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>> {
+        let expected_args = match self {
+            HigherOrderFunction::ArrayTransform | HigherOrderFunction::ArrayFilter => 1,
+            HigherOrderFunction::ArrayReduce => 2,
+        };
+        if arg_types.len() != expected_args {
+            return Err(DataFusionError::Plan(format!(
+                "The function {} expects {} arguments before its lambda, got {}",
+                self,
+                expected_args,
+                arg_types.len()
+            )));
+        }
+        let element_type = match &arg_types[0] {
+            DataType::List(field) | DataType::FixedSizeList(field, _) => {
+                field.data_type().clone()
+            }
+            other => {
+                return Err(DataFusionError::Plan(format!(
+                    "The function {} expects a list, got {:?}",
+                    self, other
+                )))
+            }
+        };
+        Ok(match self {
+            HigherOrderFunction::ArrayTransform | HigherOrderFunction::ArrayFilter => {
+                vec![element_type]
+            }
+            HigherOrderFunction::ArrayReduce => vec![arg_types[1].clone(), element_type],
+        })
+    }
+
+    /// The return type, given the types of the arguments before the lambda and the
+    /// type of the body of the lambda
+    pub fn return_type(
+        &self,
+        arg_types: &[DataType],
+        body_type: &DataType,
+    ) -> Result<DataType> {
+        match self {
+            HigherOrderFunction::ArrayTransform => Ok(DataType::List(Box::new(
+                Field::new("item", body_type.clone(), true),
+            ))),
+            HigherOrderFunction::ArrayFilter => match body_type {
+                DataType::Boolean => Ok(DataType::List(Box::new(Field::new(
+                    "item",
+                    self.lambda_param_types(arg_types)?[0].clone(),
+                    true,
+                )))),
+                other => Err(DataFusionError::Plan(format!(
+                    "The lambda of {} must return a boolean, got {:?}",
+                    self, other
+                ))),
+            },
+            // the results of the lambda are cast to the type of the initial value
+            HigherOrderFunction::ArrayReduce => {
+                if body_type == &arg_types[1]
+                    || compute::can_cast_types(body_type, &arg_types[1])
+                {
+                    Ok(arg_types[1].clone())
+                } else {
+                    Err(DataFusionError::Plan(format!(
+                        "The lambda of {} returns {:?}, which cannot be cast to the \
+                         type of the initial value {:?}",
+                        self, body_type, arg_types[1]
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// Splits the arguments of a higher-order function into the arguments before the
+/// lambda, and the parameters and body of the lambda, which must be the last argument
+pub fn split_lambda(args: &[Expr]) -> Result<(&[Expr], &[String], &Expr)> {
+    match args.split_last() {
+        Some((Expr::Lambda { params, body }, args)) => Ok((args, params, body)),
+        _ => Err(DataFusionError::Plan(
+            "The last argument of a higher-order function must be a lambda".to_string(),
+        )),
+    }
+}
+
+/// The schema against which the body of a lambda with `params` of `param_types` is
+/// planned
+pub fn lambda_schema(params: &[String], param_types: &[DataType]) -> Result<DFSchema> {
+    if params.len() != param_types.len() {
+        return Err(DataFusionError::Plan(format!(
+            "Expected a lambda with {} parameters, got {}",
+            param_types.len(),
+            params.len()
+        )));
+    }
+    DFSchema::new(
+        params
+            .iter()
+            .zip(param_types)
+            .map(|(name, data_type)| DFField::new(None, name, data_type.clone(), true))
+            .collect(),
+    )
+}
+
+/// The return type of the higher-order function `fun` called with `args` on `schema`
+pub fn return_type(
+    fun: &HigherOrderFunction,
+    args: &[Expr],
+    schema: &DFSchema,
+) -> Result<DataType> {
+    let (args, params, body) = split_lambda(args)?;
+    let arg_types = args
+        .iter()
+        .map(|e| e.get_type(schema))
+        .collect::<Result<Vec<_>>>()?;
+    let body_schema = lambda_schema(params, &fun.lambda_param_types(&arg_types)?)?;
+    fun.return_type(&arg_types, &body.get_type(&body_schema)?)
+}
+
+/// Creates a physical expression of the higher-order function `fun`, whose lambda
+/// `body` was planned against `body_schema`
+pub fn create_physical_expr(
+    fun: &HigherOrderFunction,
+    args: Vec<Arc<dyn PhysicalExpr>>,
+    body: Arc<dyn PhysicalExpr>,
+    body_schema: SchemaRef,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let arg_types = args
+        .iter()
+        .map(|e| e.data_type(input_schema))
+        .collect::<Result<Vec<_>>>()?;
+    let return_type = fun.return_type(&arg_types, &body.data_type(&body_schema)?)?;
+    Ok(Arc::new(HigherOrderFunctionExpr {
+        fun: *fun,
+        args,
+        body,
+        body_schema,
+        return_type,
+    }))
+}
+
+/// Physical expression of a higher-order function
+#[derive(Debug)]
+pub struct HigherOrderFunctionExpr {
+    fun: HigherOrderFunction,
+    args: Vec<Arc<dyn PhysicalExpr>>,
+    body: Arc<dyn PhysicalExpr>,
+    body_schema: SchemaRef,
+    return_type: DataType,
+}
+
+impl fmt::Display for HigherOrderFunctionExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}(", self.fun)?;
+        for arg in &self.args {
+            write!(f, "{}, ", arg)?;
+        }
+        let params = self
+            .body_schema
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect::<Vec<_>>();
+        write!(f, "({}) -> {})", params.join(", "), self.body)
+    }
+}
+
+/// The lists of an array of lists, as the ranges of their elements in `values`
+struct Lists {
+    values: ArrayRef,
+    offsets: Vec<i32>,
+    valid: Vec<bool>,
+}
+
+impl Lists {
+    fn try_new(array: &ArrayRef) -> Result<Self> {
+        let (values, offsets) = list_values_and_offsets(array)?;
+        Ok(Lists {
+            values,
+            offsets,
+            valid: (0..array.len()).map(|i| array.is_valid(i)).collect(),
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.valid.len()
+    }
+
+    /// The number of elements of the list `i`, 0 when it is null
+    fn list_len(&self, i: usize) -> usize {
+        if self.valid[i] {
+            (self.offsets[i + 1] - self.offsets[i]) as usize
+        } else {
+            0
+        }
+    }
+
+    /// An array of these lists with the elements at `offsets` of `values`
+    fn with_values(&self, values: ArrayRef, offsets: &[i32]) -> ArrayRef {
+        let field = Field::new("item", values.data_type().clone(), true);
+        let nulls = BooleanArray::from(self.valid.clone());
+        let data = ArrayData::builder(DataType::List(Box::new(field)))
+            .len(self.len())
+            .add_buffer(Buffer::from(offsets.to_byte_slice()))
+            .add_child_data(values.data().clone())
+            .null_bit_buffer(nulls.values().clone())
+            .build();
+        Arc::new(ListArray::from(data))
+    }
+}
+
+impl HigherOrderFunctionExpr {
+    /// Evaluates the lambda on the arrays of its parameters
+    fn apply(&self, params: Vec<ArrayRef>) -> Result<ArrayRef> {
+        let len = params[0].len();
+        let batch = RecordBatch::try_new(self.body_schema.clone(), params)?;
+        Ok(self.body.evaluate(&batch)?.into_array(len))
+    }
+
+    fn transform(&self, lists: &Lists) -> Result<ArrayRef> {
+        let results = self.apply(vec![lists.values.clone()])?;
+        Ok(lists.with_values(results, &lists.offsets))
+    }
+
+    fn filter(&self, lists: &Lists) -> Result<ArrayRef> {
+        let mask = self.apply(vec![lists.values.clone()])?;
+        let mask = mask
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "The lambda of {} returned {:?} instead of booleans",
+                    self.fun,
+                    mask.data_type()
+                ))
+            })?;
+        let mut indices = Vec::new();
+        let mut offsets = Vec::with_capacity(lists.len() + 1);
+        offsets.push(0);
+        for i in 0..lists.len() {
+            let start = lists.offsets[i] as usize;
+            for j in start..start + lists.list_len(i) {
+                if mask.is_valid(j) && mask.value(j) {
+                    indices.push(j as u32);
+                }
+            }
+            offsets.push(indices.len() as i32);
+        }
+        let values =
+            compute::take(lists.values.as_ref(), &UInt32Array::from(indices), None)?;
+        Ok(lists.with_values(values, &offsets))
+    }
+
+    /// Folds the lists element by element, evaluating the lambda on all the lists
+    /// that have an element at the current position at once
+    fn reduce(&self, lists: &Lists, initial: ArrayRef) -> Result<ArrayRef> {
+        let mut acc = initial;
+        for position in 0.. {
+            let rows = (0..lists.len())
+                .filter(|i| lists.list_len(*i) > position)
+                .map(|i| i as u32)
+                .collect::<Vec<_>>();
+            if rows.is_empty() {
+                break;
+            }
+            let elements = rows
+                .iter()
+                .map(|i| (lists.offsets[*i as usize] as usize + position) as u32)
+                .collect::<UInt32Array>();
+            let rows = UInt32Array::from(rows);
+            let acc_rows = compute::take(acc.as_ref(), &rows, None)?;
+            let elements = compute::take(lists.values.as_ref(), &elements, None)?;
+            let mut results = self.apply(vec![acc_rows, elements])?;
+            if results.data_type() != acc.data_type() {
+                results = compute::cast(&results, acc.data_type())?;
+            }
+
+            // replace the accumulators of the rows with the results
+            let merged = compute::concat(&[acc.as_ref(), results.as_ref()])?;
+            let mut indices = (0..lists.len() as u32).collect::<Vec<_>>();
+            for (result, row) in rows.values().iter().enumerate() {
+                indices[*row as usize] = (lists.len() + result) as u32;
+            }
+            acc = compute::take(merged.as_ref(), &UInt32Array::from(indices), None)?;
+        }
+
+        // the results of NULL lists are NULL
+        let indices = (0..lists.len())
+            .map(|i| lists.valid[i].then(|| i as u32))
+            .collect::<UInt32Array>();
+        Ok(compute::take(acc.as_ref(), &indices, None)?)
+    }
+}
+
+impl PhysicalExpr for HigherOrderFunctionExpr {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.return_type.clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        // the body of the lambda reads the elements of the lists, not the input
+        crate::physical_plan::column_indices(&self.args)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let args = self
+            .args
+            .iter()
+            .map(|e| Ok(e.evaluate(batch)?.into_array(batch.num_rows())))
+            .collect::<Result<Vec<_>>>()?;
+        let lists = Lists::try_new(&args[0])?;
+        let result = match self.fun {
+            HigherOrderFunction::ArrayTransform => self.transform(&lists)?,
+            HigherOrderFunction::ArrayFilter => self.filter(&lists)?,
+            HigherOrderFunction::ArrayReduce => self.reduce(&lists, args[1].clone())?,
+        };
+        Ok(ColumnarValue::Array(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, lambda, lit, Operator};
+    use crate::physical_plan::expressions::{
+        binary, col as physical_col, lit as physical_lit,
+    };
+    use crate::scalar::ScalarValue;
+    use arrow::array::Int64Array;
+    use arrow::datatypes::Int64Type;
+
+    fn lists() -> ArrayRef {
+        Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(vec![Some(1), Some(2), Some(3)]),
+            None,
+            Some(vec![]),
+            Some(vec![Some(4), None, Some(6)]),
+        ]))
+    }
+
+    fn evaluate(
+        fun: HigherOrderFunction,
+        initial: Option<ScalarValue>,
+        params: &[&str],
+        body: impl Fn(&Schema) -> Result<Arc<dyn PhysicalExpr>>,
+    ) -> Result<ArrayRef> {
+        let input = Schema::new(vec![Field::new("l", lists().data_type().clone(), true)]);
+        let batch = RecordBatch::try_new(Arc::new(input.clone()), vec![lists()])?;
+        let mut args = vec![physical_col("l", &input)?];
+        let mut arg_types = vec![lists().data_type().clone()];
+        if let Some(initial) = initial {
+            arg_types.push(initial.get_datatype());
+            args.push(physical_lit(initial));
+        }
+        let params = params.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        let body_schema: Schema =
+            lambda_schema(&params, &fun.lambda_param_types(&arg_types)?)?.into();
+        let body = body(&body_schema)?;
+        let expr = create_physical_expr(&fun, args, body, Arc::new(body_schema), &input)?;
+        Ok(expr.evaluate(&batch)?.into_array(batch.num_rows()))
+    }
+
+    fn to_vecs(array: &ArrayRef) -> Vec<Option<Vec<Option<i64>>>> {
+        let lists = array.as_any().downcast_ref::<ListArray>().unwrap();
+        (0..lists.len())
+            .map(|i| {
+                lists.is_valid(i).then(|| {
+                    let values = lists.value(i);
+                    let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+                    values.iter().collect()
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn array_transform() -> Result<()> {
+        let result = evaluate(HigherOrderFunction::ArrayTransform, None, &["x"], |s| {
+            binary(
+                physical_col("x", s)?,
+                Operator::Multiply,
+                physical_lit(ScalarValue::Int64(Some(10))),
+                s,
+            )
+        })?;
+        assert_eq!(
+            to_vecs(&result),
+            vec![
+                Some(vec![Some(10), Some(20), Some(30)]),
+                None,
+                Some(vec![]),
+                Some(vec![Some(40), None, Some(60)]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn array_filter() -> Result<()> {
+        let result = evaluate(HigherOrderFunction::ArrayFilter, None, &["x"], |s| {
+            binary(
+                physical_col("x", s)?,
+                Operator::NotEq,
+                physical_lit(ScalarValue::Int64(Some(2))),
+                s,
+            )
+        })?;
+        assert_eq!(
+            to_vecs(&result),
+            vec![
+                Some(vec![Some(1), Some(3)]),
+                None,
+                Some(vec![]),
+                Some(vec![Some(4), Some(6)]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn array_reduce() -> Result<()> {
+        let initial = ScalarValue::Int64(Some(100));
+        let result = evaluate(
+            HigherOrderFunction::ArrayReduce,
+            Some(initial),
+            &["acc", "x"],
+            |s| {
+                binary(
+                    physical_col("acc", s)?,
+                    Operator::Plus,
+                    physical_col("x", s)?,
+                    s,
+                )
+            },
+        )?;
+        let result = result.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(
+            result.iter().collect::<Vec<_>>(),
+            vec![Some(106), None, Some(100), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lambda_types() -> Result<()> {
+        let schema = DFSchema::new(vec![DFField::new(
+            None,
+            "l",
+            lists().data_type().clone(),
+            true,
+        )])?;
+        let transform = vec![col("l"), lambda(&["x"], col("x").eq(lit(1_i64)))];
+        assert_eq!(
+            return_type(&HigherOrderFunction::ArrayTransform, &transform, &schema)?,
+            DataType::List(Box::new(Field::new("item", DataType::Boolean, true)))
+        );
+        let filter = vec![col("l"), lambda(&["x"], col("x") + lit(1_i64))];
+        assert!(
+            return_type(&HigherOrderFunction::ArrayFilter, &filter, &schema).is_err()
+        );
+        let reduce = vec![col("l"), lit(0_i64), lambda(&["x"], col("x"))];
+        assert!(
+            return_type(&HigherOrderFunction::ArrayReduce, &reduce, &schema).is_err()
+        );
+        assert!(split_lambda(&[col("l")]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Derivation of the [OptimizerHints] of a plan from the hints of its input, shared by
+//! the built-in execution plans and available to user-defined ones.
+
+use std::sync::Arc;
+
+use crate::logical_plan::Operator;
+use crate::physical_plan::expressions::{
+    BinaryExpr, CastExpr, Column, Literal, NotExpr, TryCastExpr,
+};
+use crate::physical_plan::{OptimizerHints, PhysicalExpr};
+
+impl OptimizerHints {
+    /// Hints of the output of a projection with the input hints `self`. Only columns
+    /// projected as is keep their hints. The sort order is truncated at the first sort
+    /// key column that is not projected, unless it has a single value.
+    pub fn project(&self, expr: &[(Arc<dyn PhysicalExpr>, String)]) -> OptimizerHints {
+        if self == &OptimizerHints::default() {
+            return OptimizerHints::default();
+        }
+
+        let output_index = |input_index: usize| {
+            expr.iter().rposition(|(e, _)| {
+                e.as_any()
+                    .downcast_ref::<Column>()
+                    .map_or(false, |c| c.index() == input_index)
+            })
+        };
+        let single_value_columns = self
+            .single_value_columns
+            .iter()
+            .filter_map(|i| output_index(*i))
+            .collect();
+        let mut sort_order = Vec::new();
+        if let Some(input_order) = &self.sort_order {
+            for i in input_order {
+                if let Some(out) = output_index(*i) {
+                    sort_order.push(out);
+                } else if self.single_value_columns.contains(i) {
+                    continue;
+                } else {
+                    break;
+                }
+            }
+        }
+
+        OptimizerHints {
+            sort_order: if sort_order.is_empty() {
+                None
+            } else {
+                Some(sort_order)
+            },
+            single_value_columns,
+        }
+    }
+
+    /// Hints of the output of a filter with the input hints `self`. The order is kept and
+    /// columns compared for equality with constants by `predicate` have a single value.
+    pub fn filter(&self, predicate: &dyn PhysicalExpr) -> OptimizerHints {
+        let mut single_value_columns = self.single_value_columns.clone();
+        for c in extract_single_value_columns(predicate) {
+            single_value_columns.push(c.index());
+        }
+        single_value_columns.sort_unstable();
+        single_value_columns.dedup();
+
+        OptimizerHints {
+            sort_order: self.sort_order.clone(),
+            single_value_columns,
+        }
+    }
+
+    /// Hints of the output of a limit or offset with the input hints `self`, which
+    /// keeps all of them.
+    pub fn limit(&self) -> OptimizerHints {
+        self.clone()
+    }
+
+    /// Hints of the output of merging `input_partitions` partitions with the input hints
+    /// `self` into one. The sort order is only kept for a single input partition.
+    pub fn merge_partitions(&self, input_partitions: usize) -> OptimizerHints {
+        OptimizerHints {
+            sort_order: if input_partitions <= 1 {
+                self.sort_order.clone()
+            } else {
+                None
+            },
+            single_value_columns: self.single_value_columns.clone(),
+        }
+    }
+}
+
+fn extract_single_value_columns(predicate: &dyn PhysicalExpr) -> Vec<&Column> {
+    let mut columns = Vec::new();
+    extract_single_value_columns_impl(predicate, &mut columns);
+    columns
+}
+
+fn extract_single_value_columns_impl<'a>(
+    predicate: &'a dyn PhysicalExpr,
+    out: &mut Vec<&'a Column>,
+) {
+    // TODO: more sophisticated expressions.
+    let is_constant = |mut e: &dyn PhysicalExpr| loop {
+        if e.as_any().is::<Literal>() {
+            return true;
+        } else if let Some(c) = e.as_any().downcast_ref::<CastExpr>() {
+            e = c.expr().as_ref();
+        } else if let Some(c) = e.as_any().downcast_ref::<TryCastExpr>() {
+            e = c.expr().as_ref();
+        } else {
+            return false;
+        }
+    };
+
+    let predicate = predicate.as_any();
+    if let Some(binary) = predicate.downcast_ref::<BinaryExpr>() {
+        match binary.op() {
+            Operator::And => {
+                extract_single_value_columns_impl(binary.left().as_ref(), out);
+                extract_single_value_columns_impl(binary.right().as_ref(), out);
+            }
+            Operator::Eq => {
+                let mut left = binary.left();
+                let mut right = binary.right();
+                if !left.as_any().is::<Column>() {
+                    std::mem::swap(&mut left, &mut right);
+                }
+                let left = left.as_any().downcast_ref::<Column>();
+                if left.is_some() && is_constant(right.as_ref()) {
+                    out.push(left.unwrap());
+                }
+            }
+            _ => {}
+        }
+    } else if predicate.is::<Column>() {
+        out.push(predicate.downcast_ref::<Column>().unwrap());
+    } else if predicate.is::<NotExpr>() {
+        let inner = predicate.downcast_ref::<NotExpr>().unwrap().arg();
+        if inner.as_any().is::<Column>() {
+            out.push(inner.as_any().downcast_ref::<Column>().unwrap());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{binary, lit};
+    use crate::scalar::ScalarValue;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    fn column(name: &str, index: usize) -> Arc<dyn PhysicalExpr> {
+        Arc::new(Column::new(name, index))
+    }
+
+    #[test]
+    fn project_hints() {
+        let hints = OptimizerHints {
+            sort_order: Some(vec![0, 1, 2]),
+            single_value_columns: vec![1],
+        };
+        let expr = vec![
+            (column("c", 2), "c".to_string()),
+            (column("a", 0), "a".to_string()),
+        ];
+        assert_eq!(
+            hints.project(&expr),
+            OptimizerHints {
+                sort_order: Some(vec![1, 0]),
+                single_value_columns: vec![],
+            }
+        );
+
+        let expr = vec![(column("b", 1), "b".to_string())];
+        assert_eq!(
+            hints.project(&expr),
+            OptimizerHints {
+                sort_order: None,
+                single_value_columns: vec![0],
+            }
+        );
+    }
+
+    #[test]
+    fn filter_hints() -> crate::error::Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]);
+        let predicate = binary(
+            column("b", 1),
+            Operator::Eq,
+            lit(ScalarValue::Int32(Some(1))),
+            &schema,
+        )?;
+        let hints = OptimizerHints {
+            sort_order: Some(vec![0]),
+            single_value_columns: vec![],
+        };
+        assert_eq!(
+            hints.filter(predicate.as_ref()),
+            OptimizerHints {
+                sort_order: Some(vec![0]),
+                single_value_columns: vec![1],
+            }
+        );
+        assert_eq!(hints.merge_partitions(2).sort_order, None);
+        assert_eq!(hints.limit(), hints);
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Network address expressions
+
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use arrow::array::{
+    Array, ArrayRef, BooleanArray, GenericStringArray, Int64Array, StringArray,
+    StringOffsetSizeTrait,
+};
+
+macro_rules! downcast_arg {
+    ($ARG:expr, $NAME:expr, $ARRAY_TYPE:ty) => {{
+        $ARG.as_any().downcast_ref::<$ARRAY_TYPE>().ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "could not cast {} to {}",
+                $NAME,
+                std::any::type_name::<$ARRAY_TYPE>()
+            ))
+        })?
+    }};
+}
+
+/// Converts a dotted-quad IPv4 address to its numeric value.
+/// Only the four parts form is accepted, ignoring surrounding whitespace. Unlike
+/// MySQL, the short forms such as `127.1` are invalid addresses, which produce NULL.
+/// inet_aton('10.0.0.1') = 167772161
+pub fn inet_aton<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let string_array = downcast_arg!(args[0], "string", GenericStringArray<T>);
+
+    let result = string_array
+        .iter()
+        .map(|ip| {
+            ip.and_then(|ip| ip.trim().parse::<Ipv4Addr>().ok())
+                .map(|ip| u32::from(ip) as i64)
+        })
+        .collect::<Int64Array>();
+
+    Ok(Arc::new(result) as ArrayRef)
+}
+
+/// Converts the numeric value of an IPv4 address to its dotted-quad form.
+/// Values outside of the IPv4 range produce NULL, as in MySQL.
+/// inet_ntoa(167772161) = '10.0.0.1'
+pub fn inet_ntoa(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let integer_array = downcast_arg!(args[0], "integer", Int64Array);
+
+    let result = integer_array
+        .iter()
+        .map(|value| {
+            value
+                .filter(|value| (0..=u32::MAX as i64).contains(value))
+                .map(|value| Ipv4Addr::from(value as u32).to_string())
+        })
+        .collect::<StringArray>();
+
+    Ok(Arc::new(result) as ArrayRef)
+}
+
+/// A parsed network in CIDR notation, e.g. `10.0.0.0/8` or `2001:db8::/32`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Cidr {
+    network: u128,
+    prefix_len: u32,
+    is_ipv4: bool,
+}
+
+impl Cidr {
+    /// Parses `a.b.c.d/n` or `x:y::z/n`. A plain address is treated as a network
+    /// with a single host.
+    fn parse(cidr: &str) -> Result<Self> {
+        let invalid =
+            || DataFusionError::Execution(format!("Invalid CIDR notation '{}'", cidr));
+        let (addr, prefix_len) = match cidr.trim().split_once('/') {
+            Some((addr, len)) => (addr, Some(len.parse::<u32>().map_err(|_| invalid())?)),
+            None => (cidr.trim(), None),
+        };
+        let addr = addr.parse::<IpAddr>().map_err(|_| invalid())?;
+        let (bits, is_ipv4) = match addr {
+            IpAddr::V4(_) => (32, true),
+            IpAddr::V6(_) => (128, false),
+        };
+        let prefix_len = prefix_len.unwrap_or(bits);
+        if prefix_len > bits {
+            return Err(invalid());
+        }
+        Ok(Self {
+            network: address_bits(addr) & mask(prefix_len, bits),
+            prefix_len,
+            is_ipv4,
+        })
+    }
+
+    fn contains(&self, addr: IpAddr) -> bool {
+        let bits = match (addr, self.is_ipv4) {
+            (IpAddr::V4(_), true) => 32,
+            (IpAddr::V6(_), false) => 128,
+            _ => return false,
+        };
+        address_bits(addr) & mask(self.prefix_len, bits) == self.network
+    }
+}
+
+fn address_bits(addr: IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(addr) => u32::from(addr) as u128,
+        IpAddr::V6(addr) => u128::from(addr),
+    }
+}
+
+/// mask with the highest `prefix_len` bits of a `bits` wide address set
+fn mask(prefix_len: u32, bits: u32) -> u128 {
+    if prefix_len == 0 {
+        0
+    } else {
+        (u128::MAX << (128 - prefix_len)) >> (128 - bits)
+    }
+}
+
+/// Checks whether an IP address belongs to a network given in CIDR notation.
+/// Invalid addresses produce NULL, while invalid networks are an error.
+/// cidr_contains('10.1.2.3', '10.0.0.0/8') = true
+pub fn cidr_contains<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let ip_array = downcast_arg!(args[0], "ip", GenericStringArray<T>);
+    let cidr_array = downcast_arg!(args[1], "cidr", GenericStringArray<T>);
+
+    // the network is usually a literal, so avoid parsing it for every row
+    let mut last_cidr: Option<(&str, Cidr)> = None;
+    let result = ip_array
+        .iter()
+        .zip(cidr_array.iter())
+        .map(|(ip, cidr)| {
+            let (ip, cidr) = match (ip, cidr) {
+                (Some(ip), Some(cidr)) => (ip, cidr),
+                _ => return Ok(None),
+            };
+            let network = match last_cidr {
+                Some((text, network)) if text == cidr => network,
+                _ => {
+                    let network = Cidr::parse(cidr)?;
+                    last_cidr = Some((cidr, network));
+                    network
+                }
+            };
+            Ok(ip
+                .trim()
+                .parse::<IpAddr>()
+                .ok()
+                .map(|ip| network.contains(ip)))
+        })
+        .collect::<Result<BooleanArray>>()?;
+
+    Ok(Arc::new(result) as ArrayRef)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inet_aton_ntoa() -> Result<()> {
+        let input: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("10.0.0.1"),
+            Some("255.255.255.255"),
+            Some("not an ip"),
+            None,
+        ]));
+        let numbers = inet_aton::<i32>(&[input])?;
+        let expected: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(167772161),
+            Some(4294967295),
+            None,
+            None,
+        ]));
+        assert_eq!(&numbers, &expected);
+
+        let numbers: ArrayRef = Arc::new(Int64Array::from(vec![
+            Some(167772161),
+            Some(-1),
+            Some(4294967296),
+            None,
+        ]));
+        let strings = inet_ntoa(&[numbers])?;
+        let expected: ArrayRef =
+            Arc::new(StringArray::from(vec![Some("10.0.0.1"), None, None, None]));
+        assert_eq!(&strings, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cidr_contains() -> Result<()> {
+        let ips: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("10.1.2.3"),
+            Some("11.0.0.1"),
+            Some("2001:db8::1"),
+            Some("2001:db9::1"),
+            Some("10.0.0.1"),
+            Some("garbage"),
+            None,
+        ]));
+        let cidrs: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("10.0.0.0/8"),
+            Some("10.0.0.0/8"),
+            Some("2001:db8::/32"),
+            Some("2001:db8::/32"),
+            Some("0.0.0.0/0"),
+            Some("10.0.0.0/8"),
+            Some("10.0.0.0/8"),
+        ]));
+        let result = cidr_contains::<i32>(&[ips, cidrs])?;
+        let expected: ArrayRef = Arc::new(BooleanArray::from(vec![
+            Some(true),
+            Some(false),
+            Some(true),
+            Some(false),
+            Some(true),
+            None,
+            None,
+        ]));
+        assert_eq!(&result, &expected);
+
+        let ips: ArrayRef = Arc::new(StringArray::from(vec!["10.0.0.1"]));
+        let cidrs: ArrayRef = Arc::new(StringArray::from(vec!["10.0.0.0/33"]));
+        assert!(cidr_contains::<i32>(&[ips, cidrs]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the INSERT plan, which appends the rows of its input to a table
+
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+use arrow::array::UInt64Array;
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+
+use crate::catalog::schema::SchemaProvider;
+use crate::catalog::TableReference;
+use crate::datasource::TableProvider;
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::LogicalPlan;
+use crate::physical_plan::common::{self, SizedRecordBatchStream};
+use crate::physical_plan::{
+    DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
+    SendableRecordBatchStream,
+};
+
+/// Execution plan appending the rows of its input to a table with
+/// [`TableProvider::insert`]. It produces a single row with the number of rows
+/// inserted.
+pub struct InsertExec {
+    table_name: String,
+    table: Arc<dyn TableProvider>,
+    input: Arc<dyn ExecutionPlan>,
+    /// The schema the table is registered in once the rows are appended
+    create_in: Option<Arc<dyn SchemaProvider>>,
+}
+
+impl InsertExec {
+    /// Create a new InsertExec appending the rows of `input` to `table`. The input
+    /// has the columns of the table, in order.
+    pub fn new(
+        table_name: String,
+        table: Arc<dyn TableProvider>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Self {
+        Self {
+            table_name,
+            table,
+            input,
+            create_in: None,
+        }
+    }
+
+    /// Registers the table under its name in `schema` once the rows are appended,
+    /// for `CREATE TABLE ... AS`. Fails if a table of that name exists by then.
+    pub fn with_create_table(mut self, schema: Arc<dyn SchemaProvider>) -> Self {
+        self.create_in = Some(schema);
+        self
+    }
+
+    /// Name of the table
+    pub fn table_name(&self) -> &str {
+        &self.table_name
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+}
+
+impl fmt::Debug for InsertExec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("InsertExec")
+            .field("table_name", &self.table_name)
+            .field("input", &self.input)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for InsertExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        LogicalPlan::insert_schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::SinglePartition
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(InsertExec {
+                table_name: self.table_name.clone(),
+                table: self.table.clone(),
+                input: children[0].clone(),
+                create_in: self.create_in.clone(),
+            })),
+            _ => Err(DataFusionError::Internal(
+                "InsertExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "InsertExec invalid partition {}",
+                partition
+            )));
+        }
+        if self.input.output_partitioning().partition_count() != 1 {
+            return Err(DataFusionError::Internal(
+                "InsertExec requires a single input partition".to_string(),
+            ));
+        }
+
+        let batches = common::collect(self.input.execute(0).await?).await?;
+        let count = batches.iter().map(|b| b.num_rows() as u64).sum::<u64>();
+        self.table.insert(batches)?;
+        if let Some(schema) = &self.create_in {
+            let table_ref = TableReference::from(self.table_name.as_str());
+            let name = table_ref.table();
+            if schema.table(name).is_some() {
+                return Err(DataFusionError::Execution(format!(
+                    "Table '{}' already exists",
+                    self.table_name
+                )));
+            }
+            schema.register_table(name.to_string(), self.table.clone())?;
+        }
+
+        let batch = RecordBatch::try_new(
+            self.schema(),
+            vec![Arc::new(UInt64Array::from(vec![count]))],
+        )?;
+        Ok(Box::pin(SizedRecordBatchStream::new(
+            self.schema(),
+            vec![Arc::new(batch)],
+        )))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(f, "InsertExec: table={}", self.table_name)
+            }
+        }
+    }
+}
@@ -25,6 +25,7 @@ use std::task::{Context, Poll};
 use futures::stream::Stream;
 use futures::stream::StreamExt;
 
+use crate::datasource::datasource::Statistics;
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{
     DisplayFormatType, Distribution, ExecutionPlan, OptimizerHints, Partitioning,
@@ -105,7 +106,7 @@ impl ExecutionPlan for GlobalLimitExec {
     }
 
     fn output_hints(&self) -> OptimizerHints {
-        self.input.output_hints()
+        self.input.output_hints().limit()
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
@@ -128,6 +129,17 @@ impl ExecutionPlan for GlobalLimitExec {
         Ok(Box::pin(LimitStream::new(stream, self.limit)))
     }
 
+    fn statistics(&self) -> Statistics {
+        Statistics {
+            num_rows: self
+                .input
+                .statistics()
+                .num_rows
+                .map(|num_rows| num_rows.min(self.limit)),
+            ..Statistics::default()
+        }
+    }
+
     fn fmt_as(
         &self,
         t: DisplayFormatType,
@@ -202,7 +214,7 @@ impl ExecutionPlan for LocalLimitExec {
     }
 
     fn output_hints(&self) -> OptimizerHints {
-        self.input.output_hints()
+        self.input.output_hints().limit()
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
@@ -234,7 +246,7 @@ pub fn truncate_batch(batch: &RecordBatch, n: usize) -> RecordBatch {
 }
 
 /// A Limit stream limits the stream to up to `limit` rows.
-struct LimitStream {
+pub(crate) struct LimitStream {
     /// The maximum number of rows to produce
     limit: usize,
     /// The input to read from. This is set to None once the limit is
@@ -247,11 +259,12 @@ struct LimitStream {
 }
 
 impl LimitStream {
-    fn new(input: SendableRecordBatchStream, limit: usize) -> Self {
+    pub(crate) fn new(input: SendableRecordBatchStream, limit: usize) -> Self {
         let schema = input.schema();
         Self {
             limit,
-            input: Some(input),
+            // nothing to read for `LIMIT 0`
+            input: if limit == 0 { None } else { Some(input) },
             schema,
             current_len: 0,
         }
@@ -263,6 +276,11 @@ impl LimitStream {
             None
         } else if self.current_len + batch.num_rows() <= self.limit {
             self.current_len += batch.num_rows();
+            if self.current_len == self.limit {
+                // the limit is satisfied, there is no need to wait for the next poll
+                // to drop the input (and cancel any tasks still producing it)
+                self.input = None;
+            }
             Some(batch)
         } else {
             let batch_rows = self.limit - self.current_len;
@@ -308,6 +326,7 @@ mod tests {
     use crate::physical_plan::common;
     use crate::physical_plan::csv::{CsvExec, CsvReadOptions};
     use crate::test;
+    use crate::test::exec::PendingExec;
 
     #[tokio::test]
     async fn limit() -> Result<()> {
@@ -371,4 +390,53 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn limit_drops_input_when_satisfied() -> Result<()> {
+        let batches = vec![
+            test::make_partition(5),
+            test::make_partition(10),
+            test::make_partition(15),
+        ];
+        let input = test::exec::TestStream::new(batches);
+        let index = input.index();
+
+        // the first batch satisfies the limit exactly, the input must not be
+        // polled again
+        let limit_stream = LimitStream::new(Box::pin(input), 5);
+        let results = collect(Box::pin(limit_stream)).await?;
+        let num_rows: usize = results.into_iter().map(|b| b.num_rows()).sum();
+        assert_eq!(num_rows, 5);
+        assert_eq!(index.value(), 1);
+
+        let input = test::exec::TestStream::new(vec![test::make_partition(5)]);
+        let index = input.index();
+        let limit_stream = LimitStream::new(Box::pin(input), 0);
+        assert!(collect(Box::pin(limit_stream)).await?.is_empty());
+        assert_eq!(index.value(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn limit_cancels_partition_tasks() -> Result<()> {
+        // the partitions never finish, so the upstream tasks can only stop by
+        // being cancelled
+        let input = Arc::new(PendingExec::new(test::make_partition(1), 4));
+        let limit =
+            GlobalLimitExec::new(Arc::new(CoalescePartitionsExec::new(input.clone())), 1);
+
+        let batches = common::collect(limit.execute(0).await?).await?;
+        let row_count: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(row_count, 1);
+
+        // aborted tasks drop their streams asynchronously
+        for _ in 0..100 {
+            if input.open_streams() == 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(input.open_streams(), 0);
+        Ok(())
+    }
 }
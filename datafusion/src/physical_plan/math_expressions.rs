@@ -18,11 +18,13 @@
 //! Math expressions
 use super::{ColumnarValue, ScalarValue};
 use crate::error::{DataFusionError, Result};
-use arrow::array::{Float32Array, Float64Array};
+use crate::physical_plan::functions::ScalarFunctionImplementation;
+use arrow::array::{Float32Array, Float64Array, StringArray};
 use arrow::datatypes::DataType;
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 use std::iter;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 macro_rules! downcast_compute_op {
     ($ARRAY:expr, $NAME:expr, $FUNC:ident, $TYPE:ident) => {{
@@ -102,27 +104,94 @@ math_unary_function!("ln", ln);
 math_unary_function!("log2", log2);
 math_unary_function!("log10", log10);
 
+/// number of rows to generate for a function without parameters, which receives
+/// a null array with the length of the batch instead.
+fn zero_argument_len(name: &str, args: &[ColumnarValue]) -> Result<usize> {
+    match &args[0] {
+        ColumnarValue::Array(array) => Ok(array.len()),
+        _ => Err(DataFusionError::Internal(format!(
+            "Expect {} function to take no param",
+            name
+        ))),
+    }
+}
+
+fn random_values<R: Rng + ?Sized>(rng: &mut R, len: usize) -> ColumnarValue {
+    let values = iter::repeat_with(|| rng.gen_range(0.0..1.0)).take(len);
+    let array = Float64Array::from_iter_values(values);
+    ColumnarValue::Array(Arc::new(array))
+}
+
+/// generates a version 4 UUID as described in RFC 4122
+fn uuid_v4<R: Rng + ?Sized>(rng: &mut R) -> String {
+    let mut v: u128 = rng.gen();
+    // version 4
+    v = (v & !(0xf << 76)) | (0x4 << 76);
+    // variant 1
+    v = (v & !(0x3 << 62)) | (0x2 << 62);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        (v >> 96) as u32,
+        (v >> 80) as u16,
+        (v >> 64) as u16,
+        (v >> 48) as u16,
+        v & 0xffff_ffff_ffff
+    )
+}
+
+fn uuid_values<R: Rng + ?Sized>(rng: &mut R, len: usize) -> ColumnarValue {
+    let values = iter::repeat_with(|| Some(uuid_v4(rng))).take(len);
+    let array = values.collect::<StringArray>();
+    ColumnarValue::Array(Arc::new(array))
+}
+
 /// random SQL function
 pub fn random(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    let len: usize = match &args[0] {
-        ColumnarValue::Array(array) => array.len(),
-        _ => {
-            return Err(DataFusionError::Internal(
-                "Expect random function to take no param".to_string(),
-            ))
+    let len = zero_argument_len("random", args)?;
+    Ok(random_values(&mut thread_rng(), len))
+}
+
+/// uuid SQL function, also available as gen_random_uuid
+pub fn uuid(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let len = zero_argument_len("uuid", args)?;
+    Ok(uuid_values(&mut thread_rng(), len))
+}
+
+/// Creates the random SQL function. When a `seed` is given, all evaluations of the
+/// returned function draw from a single generator seeded with it, so a query
+/// executed in a single partition produces the same values on every run.
+pub fn make_random(seed: Option<u64>) -> ScalarFunctionImplementation {
+    match seed {
+        None => Arc::new(random),
+        Some(seed) => {
+            let rng = Mutex::new(StdRng::seed_from_u64(seed));
+            Arc::new(move |args: &[ColumnarValue]| {
+                let len = zero_argument_len("random", args)?;
+                Ok(random_values(&mut *rng.lock().unwrap(), len))
+            })
         }
-    };
-    let mut rng = thread_rng();
-    let values = iter::repeat_with(|| rng.gen_range(0.0..1.0)).take(len);
-    let array = Float64Array::from_iter_values(values);
-    Ok(ColumnarValue::Array(Arc::new(array)))
+    }
+}
+
+/// Creates the uuid SQL function, seeded the same way as [`make_random`].
+pub fn make_uuid(seed: Option<u64>) -> ScalarFunctionImplementation {
+    match seed {
+        None => Arc::new(uuid),
+        Some(seed) => {
+            let rng = Mutex::new(StdRng::seed_from_u64(seed));
+            Arc::new(move |args: &[ColumnarValue]| {
+                let len = zero_argument_len("uuid", args)?;
+                Ok(uuid_values(&mut *rng.lock().unwrap(), len))
+            })
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
-    use arrow::array::{Float64Array, NullArray};
+    use arrow::array::NullArray;
 
     #[test]
     fn test_random_expression() {
@@ -133,4 +202,41 @@ mod tests {
         assert_eq!(floats.len(), 1);
         assert!(0.0 <= floats.value(0) && floats.value(0) < 1.0);
     }
+
+    #[test]
+    fn test_uuid_expression() {
+        let args = vec![ColumnarValue::Array(Arc::new(NullArray::new(2)))];
+        let array = uuid(&args).expect("fail").into_array(2);
+        let uuids = array.as_any().downcast_ref::<StringArray>().expect("fail");
+
+        assert_eq!(uuids.len(), 2);
+        assert_ne!(uuids.value(0), uuids.value(1));
+        for i in 0..2 {
+            let value = uuids.value(i);
+            assert_eq!(value.len(), 36);
+            assert_eq!(&value[14..15], "4");
+            assert!(matches!(&value[19..20], "8" | "9" | "a" | "b"));
+        }
+    }
+
+    #[test]
+    fn test_seeded_random() {
+        let args = vec![ColumnarValue::Array(Arc::new(NullArray::new(3)))];
+        let eval = |f: ScalarFunctionImplementation| {
+            let first = f(&args).expect("fail").into_array(3);
+            let second = f(&args).expect("fail").into_array(3);
+            (first, second)
+        };
+
+        let (a1, a2) = eval(make_random(Some(42)));
+        let (b1, b2) = eval(make_random(Some(42)));
+        assert_eq!(&a1, &b1);
+        assert_eq!(&a2, &b2);
+        // the generator keeps advancing between batches
+        assert_ne!(&a1, &a2);
+
+        let (a1, _) = eval(make_uuid(Some(7)));
+        let (b1, _) = eval(make_uuid(Some(7)));
+        assert_eq!(&a1, &b1);
+    }
 }
@@ -26,6 +26,7 @@ use super::{
     DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
     SendableRecordBatchStream,
 };
+use crate::datasource::datasource::Statistics;
 use crate::error::{DataFusionError, Result};
 use arrow::datatypes::SchemaRef;
 use arrow::error::Result as ArrowResult;
@@ -97,6 +98,19 @@ impl ExecutionPlan for MemoryExec {
         )?))
     }
 
+    fn statistics(&self) -> Statistics {
+        let num_rows = self
+            .partitions
+            .iter()
+            .flatten()
+            .map(|batch| batch.num_rows())
+            .sum();
+        Statistics {
+            num_rows: Some(num_rows),
+            ..Statistics::default()
+        }
+    }
+
     fn fmt_as(
         &self,
         t: DisplayFormatType,
@@ -41,6 +41,7 @@ use crate::physical_plan::{ExecutionPlan, OptimizerHints};
 
 use super::SendableRecordBatchStream;
 use crate::cube_ext;
+use crate::physical_plan::common::AbortOnDropMany;
 use pin_project_lite::pin_project;
 use std::option::Option::None;
 
@@ -123,6 +124,7 @@ impl ExecutionPlan for MergeExec {
 
                 // spawn independent tasks whose resulting streams (of batches)
                 // are sent to the channel for consumption.
+                let mut join_handles = Vec::with_capacity(input_partitions);
                 for part_i in 0..input_partitions {
                     let input = self.input.clone();
                     let mut sender = sender.clone();
@@ -141,33 +143,31 @@ impl ExecutionPlan for MergeExec {
 
                         while let Some(item) = stream.next().await {
                             // If send fails, plan being torn down,
-                            // there is no place to send the error
-                            sender.send(item).await.ok();
+                            // stop pulling from the input
+                            if sender.send(item).await.is_err() {
+                                return;
+                            }
                         }
                     };
-                    cube_ext::spawn_mpsc_with_catch_unwind(task, sender_unwind);
+                    join_handles.push(cube_ext::spawn_mpsc_with_catch_unwind(
+                        task,
+                        sender_unwind,
+                    ));
                 }
 
                 Ok(Box::pin(MergeStream {
                     input: receiver,
                     schema: self.schema(),
+                    _drop_helper: AbortOnDropMany(join_handles),
                 }))
             }
         }
     }
 
     fn output_hints(&self) -> OptimizerHints {
-        let input_hints = self.input.output_hints();
-        let sort_order;
-        if self.input.output_partitioning().partition_count() <= 1 {
-            sort_order = input_hints.sort_order
-        } else {
-            sort_order = None
-        }
-        OptimizerHints {
-            sort_order,
-            single_value_columns: input_hints.single_value_columns,
-        }
+        self.input
+            .output_hints()
+            .merge_partitions(self.input.output_partitioning().partition_count())
     }
 }
 
@@ -176,6 +176,8 @@ pin_project! {
         schema: SchemaRef,
         #[pin]
         input: mpsc::Receiver<ArrowResult<RecordBatch>>,
+        // cancels the input tasks when the stream is dropped
+        _drop_helper: AbortOnDropMany<()>,
     }
 }
 
@@ -24,7 +24,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use futures::{Stream, StreamExt};
 
-use arrow::array::{ArrayRef, UInt32Array};
+use arrow::array::{build_compare, ArrayRef, DynComparator, UInt32Array, UInt32Builder};
 use arrow::datatypes::{Schema, SchemaRef};
 use arrow::error::{ArrowError, Result as ArrowResult};
 use arrow::record_batch::RecordBatch;
@@ -40,6 +40,7 @@ use crate::logical_plan::JoinType;
 use crate::physical_plan::expressions::Column;
 use arrow::compute::kernels::merge::{merge_join_indices, MergeJoinType};
 use arrow::compute::{concat, take};
+use std::cmp::Ordering;
 use std::task::Poll;
 
 /// join execution plan executes partitions in parallel and combines them into a set of
@@ -382,20 +383,40 @@ fn merge_join(
     right_cursor: usize,
     join_type: &JoinType,
 ) -> ArrowResult<((usize, bool), (usize, bool), RecordBatch)> {
+    let left_keys = on_left
+        .iter()
+        .map(|c| left.column(c.index()).clone())
+        .collect::<Vec<_>>();
+    let right_keys = on_right
+        .iter()
+        .map(|c| right.column(c.index()).clone())
+        .collect::<Vec<_>>();
+    if *join_type == JoinType::Full {
+        let (
+            (new_left_cursor, advance_left, left_indices),
+            (new_right_cursor, advance_right, right_indices),
+        ) = full_merge_join_indices(
+            &left_keys,
+            &right_keys,
+            left_cursor,
+            right_cursor,
+            last_left,
+            last_right,
+        )?;
+        let batch = take_joined(schema, left, right, &left_indices, &right_indices)?;
+        return Ok((
+            (new_left_cursor, advance_left),
+            (new_right_cursor, advance_right),
+            batch,
+        ));
+    }
+
     let (
         (new_left_cursor, advance_left, left_indices),
         (new_right_cursor, advance_right, right_indices),
     ) = merge_join_indices(
-        on_left
-            .iter()
-            .map(|c| left.column(c.index()).clone())
-            .collect::<Vec<_>>()
-            .as_slice(),
-        on_right
-            .iter()
-            .map(|c| right.column(c.index()).clone())
-            .collect::<Vec<_>>()
-            .as_slice(),
+        left_keys.as_slice(),
+        right_keys.as_slice(),
         left_cursor,
         right_cursor,
         last_left,
@@ -406,7 +427,8 @@ fn merge_join(
             JoinType::Right => MergeJoinType::Right,
             JoinType::Full | JoinType::Semi | JoinType::Anti => {
                 return Err(ArrowError::NotYetImplemented(
-                    "merge join supports only LEFT, RIGHT and INNER JOIN".to_string(),
+                    "merge join supports only LEFT, RIGHT, FULL and INNER JOIN"
+                        .to_string(),
                 ))
             }
         },
@@ -427,6 +449,129 @@ fn merge_join(
     ))
 }
 
+fn take_joined(
+    schema: SchemaRef,
+    left: &RecordBatch,
+    right: &RecordBatch,
+    left_indices: &UInt32Array,
+    right_indices: &UInt32Array,
+) -> ArrowResult<RecordBatch> {
+    let mut columns = Vec::with_capacity(left.columns().len() + right.columns().len());
+    for c in left.columns() {
+        columns.push(take(c.as_ref(), left_indices, None)?);
+    }
+    for c in right.columns() {
+        columns.push(take(c.as_ref(), right_indices, None)?);
+    }
+    RecordBatch::try_new(schema, columns)
+}
+
+/// Computes the rows of a FULL JOIN of key columns sorted in the same order, starting at
+/// the given cursors. Rows without a match on the other side get a null index for it.
+///
+/// Follows the protocol of [merge_join_indices]: returns the new cursor of each side and
+/// whether more data must be appended to that side to continue. The latter happens when
+/// a group of equal keys reaches the end of the batch that is not the last one.
+fn full_merge_join_indices(
+    left_keys: &[ArrayRef],
+    right_keys: &[ArrayRef],
+    left_cursor: usize,
+    right_cursor: usize,
+    last_left: bool,
+    last_right: bool,
+) -> ArrowResult<((usize, bool, UInt32Array), (usize, bool, UInt32Array))> {
+    let left_len = left_keys.first().map(|k| k.len()).unwrap_or(0);
+    let right_len = right_keys.first().map(|k| k.len()).unwrap_or(0);
+    let cross = comparators(left_keys, right_keys)?;
+    let left_self = comparators(left_keys, left_keys)?;
+    let right_self = comparators(right_keys, right_keys)?;
+    let has_null = |keys: &[ArrayRef], i: usize| keys.iter().any(|k| k.is_null(i));
+
+    let mut left_indices = UInt32Builder::new(0);
+    let mut right_indices = UInt32Builder::new(0);
+    let (mut l, mut r) = (left_cursor, right_cursor);
+    let (mut advance_left, mut advance_right) = (false, false);
+    loop {
+        let left_done = l == left_len;
+        let right_done = r == right_len;
+        if (left_done && !last_left) || (right_done && !last_right) {
+            // wait for the next batch on the exhausted side
+            break;
+        }
+        if left_done && right_done {
+            break;
+        }
+        // null keys never match
+        if right_done || (!left_done && has_null(left_keys, l)) {
+            left_indices.append_value(l as u32)?;
+            right_indices.append_null()?;
+            l += 1;
+            continue;
+        }
+        if left_done || has_null(right_keys, r) {
+            left_indices.append_null()?;
+            right_indices.append_value(r as u32)?;
+            r += 1;
+            continue;
+        }
+        match compare_rows(&cross, l, r) {
+            Ordering::Less => {
+                left_indices.append_value(l as u32)?;
+                right_indices.append_null()?;
+                l += 1;
+            }
+            Ordering::Greater => {
+                left_indices.append_null()?;
+                right_indices.append_value(r as u32)?;
+                r += 1;
+            }
+            Ordering::Equal => {
+                let left_end = (l + 1..left_len)
+                    .find(|i| compare_rows(&left_self, l, *i) != Ordering::Equal)
+                    .unwrap_or(left_len);
+                let right_end = (r + 1..right_len)
+                    .find(|i| compare_rows(&right_self, r, *i) != Ordering::Equal)
+                    .unwrap_or(right_len);
+                // the group may continue in the next batch
+                advance_left = left_end == left_len && !last_left;
+                advance_right = right_end == right_len && !last_right;
+                if advance_left || advance_right {
+                    break;
+                }
+                for li in l..left_end {
+                    for ri in r..right_end {
+                        left_indices.append_value(li as u32)?;
+                        right_indices.append_value(ri as u32)?;
+                    }
+                }
+                l = left_end;
+                r = right_end;
+            }
+        }
+    }
+    Ok((
+        (l, advance_left, left_indices.finish()),
+        (r, advance_right, right_indices.finish()),
+    ))
+}
+
+fn comparators(left: &[ArrayRef], right: &[ArrayRef]) -> ArrowResult<Vec<DynComparator>> {
+    left.iter()
+        .zip(right.iter())
+        .map(|(l, r)| build_compare(l.as_ref(), r.as_ref()))
+        .collect()
+}
+
+fn compare_rows(comparators: &[DynComparator], l: usize, r: usize) -> Ordering {
+    for c in comparators {
+        match c(l, r) {
+            Ordering::Equal => continue,
+            ordering => return ordering,
+        }
+    }
+    Ordering::Equal
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -864,6 +1009,68 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn full_join_multiple_batches() -> Result<()> {
+        let left_1 = build_table_i32_option(
+            ("a1", &vec![Some(0), Some(1), Some(2)]),
+            ("b1", &vec![None, Some(4), Some(5)]),
+            ("c1", &vec![Some(7), Some(8), Some(9)]),
+        );
+        let left_2 = build_table_i32_option(
+            ("a1", &vec![Some(3), Some(4)]),
+            ("b1", &vec![Some(5), Some(7)]),
+            ("c1", &vec![Some(10), Some(11)]),
+        );
+        let right_1 = build_table_i32_option(
+            ("a2", &vec![Some(10), Some(20)]),
+            ("b1", &vec![None, Some(3)]),
+            ("c2", &vec![Some(70), Some(80)]),
+        );
+        let right_2 = build_table_i32_option(
+            ("a2", &vec![Some(30), Some(40)]),
+            ("b1", &vec![Some(5), Some(6)]),
+            ("c2", &vec![Some(90), Some(100)]),
+        );
+        let schema_left = left_1.schema();
+        let schema = right_1.schema();
+
+        let left = Arc::new(
+            MemoryExec::try_new(&vec![vec![left_1], vec![left_2]], schema_left, None)
+                .unwrap(),
+        );
+        let right = Arc::new(
+            MemoryExec::try_new(&vec![vec![right_1], vec![right_2]], schema, None)
+                .unwrap(),
+        );
+
+        let on = &[("b1", "b1")];
+
+        let join = join_with_type(left, right, on, &JoinType::Full)?;
+        assert!(join.schema().fields().iter().all(|f| f.is_nullable()));
+
+        let stream = join.execute(0).await?;
+        let batches = common::collect(stream).await?;
+
+        let expected = vec![
+            "+----+----+----+----+----+-----+",
+            "| a1 | b1 | c1 | a2 | b1 | c2  |",
+            "+----+----+----+----+----+-----+",
+            "| 0  |    | 7  |    |    |     |",
+            "|    |    |    | 10 |    | 70  |",
+            "|    |    |    | 20 | 3  | 80  |",
+            "| 1  | 4  | 8  |    |    |     |",
+            "| 2  | 5  | 9  | 30 | 5  | 90  |",
+            "| 3  | 5  | 10 | 30 | 5  | 90  |",
+            "|    |    |    | 40 | 6  | 100 |",
+            "| 4  | 7  | 11 |    |    |     |",
+            "+----+----+----+----+----+-----+",
+        ];
+
+        assert_batches_eq!(expected, &batches);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn empty_right() -> Result<()> {
         let left_1 = build_table_i32_option(
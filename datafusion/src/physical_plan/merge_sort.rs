@@ -27,9 +27,7 @@ use futures::StreamExt;
 
 use arrow::array::{build_compare, ArrayRef, BooleanArray, DynComparator};
 pub use arrow::compute::SortOptions;
-use arrow::compute::{
-    filter_record_batch, lexsort_to_indices, take, SortColumn, TakeOptions,
-};
+use arrow::compute::{filter_record_batch, take, SortColumn, TakeOptions};
 use arrow::datatypes::SchemaRef;
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
@@ -41,6 +39,7 @@ use crate::physical_plan::{ExecutionPlan, OptimizerHints, Partitioning};
 use crate::cube_ext::util::{cmp_array_row_same_types, lexcmp_array_rows};
 use crate::physical_plan::expressions::Column;
 use crate::physical_plan::memory::MemoryStream;
+use crate::physical_plan::sort_key::lexsort_to_indices_with_keys;
 use arrow::array::{make_array, MutableArrayData};
 use async_trait::async_trait;
 use futures::future::join_all;
@@ -233,7 +232,7 @@ fn sort_batch(
             })
         })
         .collect::<ArrowResult<Vec<_>>>()?;
-    let indices = lexsort_to_indices(columns_to_sort.as_slice(), None)?;
+    let indices = lexsort_to_indices_with_keys(columns_to_sort.as_slice(), None)?;
 
     RecordBatch::try_new(
         schema.clone(),
@@ -20,13 +20,16 @@
 use self::{
     coalesce_partitions::CoalescePartitionsExec, display::DisplayableExecutionPlan,
 };
-use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::datasource::datasource::Statistics;
+use crate::physical_plan::expressions::{Column, PhysicalSortExpr};
 use crate::{
     error::{DataFusionError, Result},
     scalar::ScalarValue,
 };
+use arrow::array::{make_array, Array, BooleanArray, MutableArrayData};
 use arrow::compute::kernels::partition::lexicographical_partition_ranges;
 use arrow::compute::kernels::sort::{SortColumn, SortOptions};
+use arrow::compute::{filter, filter_record_batch};
 use arrow::datatypes::{DataType, Schema, SchemaRef};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
@@ -177,6 +180,12 @@ pub trait ExecutionPlan: Debug + Send + Sync {
         OptimizerHints::default()
     }
 
+    /// Returns the statistics of the output of this plan (summed over all partitions)
+    /// that are known before executing it. Nothing is known by default.
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+
     /// creates an iterator
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream>;
 
@@ -392,6 +401,33 @@ impl Partitioning {
             UnknownPartitioning(n) => *n,
         }
     }
+
+    /// Returns the partitioning of the output of `exprs` evaluated on the partitions of
+    /// this one. Hash partitioning is kept when all of its columns are projected.
+    pub fn project(&self, exprs: &[(Arc<dyn PhysicalExpr>, String)]) -> Partitioning {
+        let (hash_exprs, n) = match self {
+            Partitioning::Hash(hash_exprs, n) => (hash_exprs, *n),
+            other => return other.clone(),
+        };
+        let projected = hash_exprs
+            .iter()
+            .map(|e| {
+                let hashed = e.as_any().downcast_ref::<Column>()?;
+                exprs.iter().enumerate().find_map(|(i, (e, name))| {
+                    match e.as_any().downcast_ref::<Column>() {
+                        Some(c) if c.index() == hashed.index() => {
+                            Some(Arc::new(Column::new(name, i)) as Arc<dyn PhysicalExpr>)
+                        }
+                        _ => None,
+                    }
+                })
+            })
+            .collect::<Option<Vec<_>>>();
+        match projected {
+            Some(projected) => Partitioning::Hash(projected, n),
+            None => Partitioning::UnknownPartitioning(n),
+        }
+    }
 }
 
 /// Distribution schemes
@@ -445,6 +481,101 @@ pub trait PhysicalExpr: Send + Sync + Display + Debug {
     fn nullable(&self, input_schema: &Schema) -> Result<bool>;
     /// Evaluate an expression against a RecordBatch
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue>;
+    /// The indexes of the columns of the input that the expression reads, or `None`
+    /// when they are unknown
+    fn column_indices(&self) -> Option<Vec<usize>> {
+        None
+    }
+    /// Evaluate an expression only for the rows of a RecordBatch that are set in
+    /// `selection`. The result has a row for every row of the batch, the rows that are
+    /// not selected are NULL.
+    fn evaluate_selection(
+        &self,
+        batch: &RecordBatch,
+        selection: &BooleanArray,
+    ) -> Result<ColumnarValue> {
+        let num_selected = (0..selection.len())
+            .filter(|i| selection.is_valid(*i) && selection.value(*i))
+            .count();
+        if num_selected == batch.num_rows() {
+            return self.evaluate(batch);
+        }
+        let selected =
+            filter_columns(batch, selection, self.column_indices().as_deref())?;
+        match self.evaluate(&selected)? {
+            ColumnarValue::Array(array) => {
+                Ok(ColumnarValue::Array(scatter(selection, array.as_ref())))
+            }
+            scalar => Ok(scalar),
+        }
+    }
+}
+
+/// The union of the [`PhysicalExpr::column_indices`] of `exprs`, or `None` when those
+/// of one of them are unknown
+pub fn column_indices<'a>(
+    exprs: impl IntoIterator<Item = &'a Arc<dyn PhysicalExpr>>,
+) -> Option<Vec<usize>> {
+    let mut indices = vec![];
+    for expr in exprs {
+        indices.extend(expr.column_indices()?);
+    }
+    indices.sort_unstable();
+    indices.dedup();
+    Some(indices)
+}
+
+/// The rows of `batch` that are set in `selection`. Only the `columns` that an
+/// expression reads are copied, when they are known. The other columns are zero-copy
+/// slices of the right length, whose values are not the selected ones.
+pub(crate) fn filter_columns(
+    batch: &RecordBatch,
+    selection: &BooleanArray,
+    columns: Option<&[usize]>,
+) -> Result<RecordBatch> {
+    let columns = match columns {
+        Some(columns) => columns,
+        None => return Ok(filter_record_batch(batch, selection)?),
+    };
+    let num_selected = (0..selection.len())
+        .filter(|i| selection.is_valid(*i) && selection.value(*i))
+        .count();
+    let arrays = batch
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, array)| {
+            if columns.contains(&i) {
+                filter(array.as_ref(), selection)
+            } else {
+                Ok(array.slice(0, num_selected))
+            }
+        })
+        .collect::<ArrowResult<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(batch.schema(), arrays)?)
+}
+
+/// Spreads the values of `truthy` over the rows set in `mask`, in order, and makes the
+/// other rows NULL.
+fn scatter(mask: &BooleanArray, truthy: &dyn Array) -> ArrayRef {
+    let mut data = MutableArrayData::new(vec![truthy.data()], true, mask.len());
+    let mut taken = 0;
+    let mut i = 0;
+    while i < mask.len() {
+        let is_set = |i: usize| mask.is_valid(i) && mask.value(i);
+        let start = i;
+        let set = is_set(i);
+        while i < mask.len() && is_set(i) == set {
+            i += 1;
+        }
+        if set {
+            data.extend(0, taken, taken + i - start);
+            taken += i - start;
+        } else {
+            data.extend_nulls(i - start);
+        }
+    }
+    make_array(data.freeze())
 }
 
 /// An aggregate expression that:
@@ -527,6 +658,13 @@ pub trait WindowExpr: Send + Sync + Debug {
     /// evaluate the window function values against the batch
     fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef>;
 
+    /// Creates an evaluator of a single window partition split in consecutive chunks
+    /// that end at peer group boundaries, or `None` when the function needs all rows of
+    /// the partition at once.
+    fn create_chunk_evaluator(&self) -> Result<Option<Box<dyn WindowChunkEvaluator>>> {
+        Ok(None)
+    }
+
     /// evaluate the partition points given the sort columns; if the sort columns are
     /// empty then the result will be a single element vec of the whole column rows.
     fn evaluate_partition_points(
@@ -579,6 +717,13 @@ pub trait WindowExpr: Send + Sync + Debug {
     }
 }
 
+/// Evaluates a window function over the consecutive chunks of a window partition,
+/// carrying the state of the previous chunks over to the next one.
+pub trait WindowChunkEvaluator: Send + Debug {
+    /// Evaluates the window function on the rows of the next chunk
+    fn evaluate_chunk(&mut self, batch: &RecordBatch) -> Result<ArrayRef>;
+}
+
 /// An accumulator represents a stateful object that lives throughout the evaluation of multiple rows and
 /// generically accumulates values.
 ///
@@ -635,6 +780,7 @@ pub trait Accumulator: Send + Sync + Debug {
 }
 
 pub mod aggregates;
+pub mod analyze;
 pub mod array_expressions;
 pub mod coalesce_batches;
 pub mod coalesce_partitions;
@@ -647,17 +793,25 @@ pub mod datetime_expressions;
 pub mod display;
 pub mod distinct_expressions;
 pub mod empty;
+pub mod encoders;
+#[cfg(feature = "encoding_expressions")]
+pub mod encoding_expressions;
 pub mod explain;
 pub mod expressions;
 pub mod filter;
 pub mod functions;
 pub mod group_scalar;
+pub mod grouped_top_k;
 pub mod groups_accumulator;
 pub mod groups_accumulator_adapter;
 pub mod groups_accumulator_flat_adapter;
 pub mod hash_aggregate;
 pub mod hash_join;
 pub mod hash_utils;
+pub mod higher_order_functions;
+mod hints;
+pub mod inet_expressions;
+pub mod insert;
 pub mod json;
 pub mod limit;
 pub mod math_expressions;
@@ -666,13 +820,17 @@ pub mod merge;
 pub mod merge_join;
 pub mod merge_sort;
 pub mod parquet;
+pub mod partial_sort;
 pub mod planner;
+pub mod progress;
 pub mod projection;
 #[cfg(feature = "regex_expressions")]
 pub mod regex_expressions;
 pub mod repartition;
+pub mod selection;
 pub mod skip;
 pub mod sort;
+pub mod sort_key;
 pub mod sort_preserving_merge;
 mod sorted_aggregate;
 pub mod source;
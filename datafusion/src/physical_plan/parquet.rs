@@ -29,26 +29,30 @@ use crate::{
     logical_plan::{Column, Expr},
     physical_optimizer::pruning::{PruningPredicate, PruningStatistics},
     physical_plan::{
-        common, DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
-        SendableRecordBatchStream,
+        common, expressions::struct_field, DisplayFormatType, ExecutionPlan,
+        OptimizerHints, Partitioning, RecordBatchStream, SendableRecordBatchStream,
     },
     scalar::ScalarValue,
 };
 
 use arrow::{
-    array::ArrayRef,
-    datatypes::{Schema, SchemaRef},
+    array::{ArrayRef, BooleanArray},
+    compute::filter_record_batch,
+    datatypes::{DataType, Field, Schema, SchemaRef},
     error::{ArrowError, Result as ArrowResult},
     record_batch::RecordBatch,
 };
 use hashbrown::HashMap;
 use log::debug;
+use parquet::basic::Compression;
 use parquet::file::{
     footer,
     metadata::RowGroupMetaData,
+    properties::WriterProperties,
     reader::{FileReader, SerializedFileReader},
     statistics::Statistics as ParquetStatistics,
 };
+use parquet::schema::types::{ColumnPath, SchemaDescriptor};
 
 use fmt::Debug;
 use parquet::arrow::{ArrowReader, ParquetFileArrowReader};
@@ -67,6 +71,12 @@ use parquet::file::metadata::ParquetMetaData;
 use super::SQLMetric;
 
 /// Execution plan for scanning one or more Parquet partitions
+///
+/// A pruning predicate skips whole row groups whose min/max statistics show they have
+/// no matching rows, reported by the `numRowGroupsPruned` and `numRowsPruned`
+/// metrics. Pruning does not go below row groups: the Parquet reader in use reads
+/// neither column bloom filters nor page indexes, so a point lookup such as
+/// `WHERE id = 123` reads every row group whose `id` range contains 123.
 #[derive(Debug, Clone)]
 pub struct ParquetExec {
     /// Parquet partitions to read
@@ -75,6 +85,9 @@ pub struct ParquetExec {
     schema: SchemaRef,
     /// Projection for which columns to load
     projection: Vec<usize>,
+    /// Path of each projected column in the file schema, several names for a
+    /// shredded field
+    column_paths: Vec<Vec<String>>,
     /// Batch size
     batch_size: usize,
     /// Statistics for the data set (sum of statistics for all partitions)
@@ -87,6 +100,11 @@ pub struct ParquetExec {
     limit: Option<usize>,
     /// Creates readers for parquet files.
     metadata_cache: Arc<dyn ParquetMetadataCache>,
+    /// Indices of the columns of the file schema that the rows of each partition are
+    /// sorted on, if any
+    file_sort_order: Option<Vec<usize>>,
+    /// Rows to skip in the files read
+    deletion_vectors: Option<Arc<dyn DeletionVectorProvider>>,
 }
 
 /// Represents one partition of a Parquet data set and this currently means one Parquet file.
@@ -120,8 +138,50 @@ pub struct ParquetExecMetrics {
 struct ParquetPartitionMetrics {
     /// Numer of times the predicate could not be evaluated
     pub predicate_evaluation_errors: Arc<SQLMetric>,
-    /// Number of row groups pruned using
+    /// Number of row groups pruned using their statistics
     pub row_groups_pruned: Arc<SQLMetric>,
+    /// Number of rows in the pruned row groups
+    pub rows_pruned: Arc<SQLMetric>,
+    /// Number of rows skipped because of deletion vectors
+    pub rows_deleted: Arc<SQLMetric>,
+}
+
+/// Rows of a Parquet file that were deleted and must be skipped when it is read, e.g.
+/// rows replaced by an upsert that has not been compacted into the file yet
+#[derive(Debug, Clone, Default)]
+pub struct DeletionVector {
+    /// Sorted indices of the deleted rows within the file
+    rows: Vec<u64>,
+}
+
+impl DeletionVector {
+    /// Create a deletion vector of the rows with the given indices within the file
+    pub fn new(mut rows: Vec<u64>) -> Self {
+        rows.sort_unstable();
+        rows.dedup();
+        Self { rows }
+    }
+
+    /// Whether the row with index `row` within the file was deleted
+    pub fn contains(&self, row: u64) -> bool {
+        self.rows.binary_search(&row).is_ok()
+    }
+
+    /// Number of deleted rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether no rows were deleted
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// Supplies the deletion vectors of the files read by a [ParquetExec]
+pub trait DeletionVectorProvider: Debug + Sync + Send {
+    /// Returns the rows deleted from the file `filename`, if any
+    fn deletion_vector(&self, filename: &str) -> Result<Option<Arc<DeletionVector>>>;
 }
 
 /// Cache for Parquet Metadata
@@ -232,6 +292,88 @@ impl MetadataCacheFactory for BasicMetadataCacheFactory {
     }
 }
 
+/// Options for writing query results to Parquet files, see
+/// [ExecutionContext::write_parquet_with_options](crate::execution::context::ExecutionContext::write_parquet_with_options)
+#[derive(Debug, Clone, Default)]
+pub struct ParquetWriteOptions {
+    max_row_group_size: Option<usize>,
+    compression: Option<Compression>,
+    column_compression: Vec<(String, Compression)>,
+    column_dictionary_enabled: Vec<(String, bool)>,
+    sort_by: Vec<String>,
+}
+
+impl ParquetWriteOptions {
+    /// Create options with the default writer properties
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of rows in a row group
+    pub fn with_max_row_group_size(mut self, max_row_group_size: usize) -> Self {
+        self.max_row_group_size = Some(max_row_group_size);
+        self
+    }
+
+    /// Compression codec of all columns without a codec of their own
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Compression codec of the column `name`
+    pub fn with_column_compression(
+        mut self,
+        name: impl Into<String>,
+        compression: Compression,
+    ) -> Self {
+        self.column_compression.push((name.into(), compression));
+        self
+    }
+
+    /// Whether the values of the column `name` are dictionary encoded
+    pub fn with_column_dictionary_enabled(
+        mut self,
+        name: impl Into<String>,
+        enabled: bool,
+    ) -> Self {
+        self.column_dictionary_enabled.push((name.into(), enabled));
+        self
+    }
+
+    /// Sort the rows of each file by the columns `names` before writing them, so that
+    /// the statistics of the row groups can prune them when the files are read
+    pub fn with_sort_by(mut self, names: Vec<String>) -> Self {
+        self.sort_by = names;
+        self
+    }
+
+    /// Columns the rows of each file are sorted by
+    pub fn sort_by(&self) -> &[String] {
+        &self.sort_by
+    }
+
+    /// Properties of the Parquet writer
+    pub fn writer_properties(&self) -> WriterProperties {
+        let mut builder = WriterProperties::builder();
+        if let Some(max_row_group_size) = self.max_row_group_size {
+            builder = builder.set_max_row_group_size(max_row_group_size);
+        }
+        if let Some(compression) = self.compression {
+            builder = builder.set_compression(compression);
+        }
+        for (name, compression) in &self.column_compression {
+            builder = builder
+                .set_column_compression(ColumnPath::from(name.as_str()), *compression);
+        }
+        for (name, enabled) in &self.column_dictionary_enabled {
+            builder = builder
+                .set_column_dictionary_enabled(ColumnPath::from(name.as_str()), *enabled);
+        }
+        builder.build()
+    }
+}
+
 impl ParquetExec {
     /// Create a new Parquet reader execution plan based on the specified Parquet filename or
     /// directory containing Parquet files
@@ -346,7 +488,7 @@ impl ParquetExec {
                 let num_fields = schema.fields().len();
                 if schemas.is_empty() || schema != schemas[0] {
                     schemas.push(schema);
-                    null_counts = vec![0; num_fields]
+                    null_counts = vec![Some(0); num_fields]
                 }
                 for row_group_meta in meta_data.row_groups() {
                     num_rows += row_group_meta.num_rows();
@@ -354,13 +496,19 @@ impl ParquetExec {
 
                     // Currently assumes every Parquet file has same schema
                     // https://issues.apache.org/jira/browse/ARROW-11017
-                    let columns_null_counts = row_group_meta
-                        .columns()
-                        .iter()
-                        .flat_map(|c| c.statistics().map(|stats| stats.null_count()));
-
-                    for (i, cnt) in columns_null_counts.enumerate() {
-                        null_counts[i] += cnt
+                    let columns = row_group_meta.columns();
+                    if columns.len() == null_counts.len() {
+                        for (count, column) in null_counts.iter_mut().zip(columns) {
+                            *count = match (*count, column.statistics()) {
+                                (Some(count), Some(stats)) => {
+                                    Some(count + stats.null_count())
+                                }
+                                _ => None,
+                            };
+                        }
+                    } else {
+                        // nested fields are stored in several columns
+                        null_counts.iter_mut().for_each(|count| *count = None);
                     }
                     if limit.map(|x| num_rows >= x as i64).unwrap_or(false) {
                         limit_exhausted = true;
@@ -372,7 +520,7 @@ impl ParquetExec {
             let column_stats = null_counts
                 .iter()
                 .map(|null_count| ColumnStatistics {
-                    null_count: Some(*null_count as usize),
+                    null_count: null_count.map(|count| count as usize),
                     max_value: None,
                     min_value: None,
                     distinct_count: None,
@@ -469,12 +617,19 @@ impl ParquetExec {
             None => (0..schema.fields().len()).collect(),
         };
 
-        let projected_schema = Schema::new(
-            projection
-                .iter()
-                .map(|i| schema.field(*i).clone())
-                .collect(),
-        );
+        let shredded = shredded_fields(&schema);
+        let num_fields = schema.fields().len();
+        let (fields, column_paths): (Vec<Field>, Vec<Vec<String>>) = projection
+            .iter()
+            .map(|&i| match i.checked_sub(num_fields) {
+                None => {
+                    let field = schema.field(i);
+                    (field.clone(), vec![field.name().clone()])
+                }
+                Some(i) => shredded[i].clone(),
+            })
+            .unzip();
+        let projected_schema = Schema::new(fields);
 
         // sum the statistics
         let mut num_rows: Option<usize> = None;
@@ -493,7 +648,8 @@ impl ParquetExec {
                     x.iter().map(|c| c.null_count).collect();
                 has_null_counts = true;
 
-                for &i in projection.iter() {
+                // shredded fields have no statistics
+                for &i in projection.iter().filter(|&&i| i < num_fields) {
                     null_counts[i] = part_nulls[i].unwrap_or(0);
                 }
             }
@@ -523,21 +679,42 @@ impl ParquetExec {
             partitions,
             schema: Arc::new(projected_schema),
             projection,
+            column_paths,
             metrics,
             predicate_builder,
             batch_size,
             statistics,
             limit,
             metadata_cache,
+            file_sort_order: None,
+            deletion_vectors: None,
         }
     }
 
+    /// Declare that each file is sorted on the columns of the file schema with indices
+    /// `sort_order`, and that the files are ordered the same way. Partitions read
+    /// consecutive files in order, so their rows are sorted on these columns too.
+    pub fn with_file_sort_order(mut self, sort_order: Vec<usize>) -> Self {
+        self.file_sort_order = Some(sort_order);
+        self
+    }
+
+    /// Skip the rows of each file deleted according to `deletion_vectors`
+    pub fn with_deletion_vectors(
+        mut self,
+        deletion_vectors: Arc<dyn DeletionVectorProvider>,
+    ) -> Self {
+        self.deletion_vectors = Some(deletion_vectors);
+        self
+    }
+
     /// Parquet partitions to read
     pub fn partitions(&self) -> &[ParquetPartition] {
         &self.partitions
     }
 
-    /// Projection for which columns to load
+    /// Projection for which columns to load, indices of the fields of the
+    /// [shredded_schema] of the files
     pub fn projection(&self) -> &[usize] {
         &self.projection
     }
@@ -547,8 +724,9 @@ impl ParquetExec {
         self.batch_size
     }
 
-    /// Statistics for the data set (sum of statistics for all partitions)
-    pub fn statistics(&self) -> &Statistics {
+    /// Statistics for the data set (sum of statistics for all partitions), also
+    /// returned by [`ExecutionPlan::statistics`]
+    pub fn dataset_statistics(&self) -> &Statistics {
         &self.statistics
     }
 }
@@ -589,6 +767,8 @@ impl ParquetPartitionMetrics {
         Self {
             predicate_evaluation_errors: SQLMetric::counter(),
             row_groups_pruned: SQLMetric::counter(),
+            rows_pruned: SQLMetric::counter(),
+            rows_deleted: SQLMetric::counter(),
         }
     }
 }
@@ -614,6 +794,25 @@ impl ExecutionPlan for ParquetExec {
         Partitioning::UnknownPartitioning(self.partitions.len())
     }
 
+    fn output_hints(&self) -> OptimizerHints {
+        // the order is kept up to the first sort column that is not projected
+        let mut sort_order = Vec::new();
+        for c in self.file_sort_order.iter().flatten() {
+            match self.projection.iter().position(|p| p == c) {
+                Some(i) => sort_order.push(i),
+                None => break,
+            }
+        }
+        OptimizerHints {
+            sort_order: if sort_order.is_empty() {
+                None
+            } else {
+                Some(sort_order)
+            },
+            single_value_columns: Vec::new(),
+        }
+    }
+
     fn with_new_children(
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
@@ -639,24 +838,28 @@ impl ExecutionPlan for ParquetExec {
         let partition = &self.partitions[partition];
         let filenames = partition.filenames.clone();
         let metrics = partition.metrics.clone();
-        let projection = self.projection.clone();
+        let column_paths = self.column_paths.clone();
+        let schema = self.schema.clone();
         let predicate_builder = self.predicate_builder.clone();
         let batch_size = self.batch_size;
         let limit = self.limit;
         let tx_unwind = response_tx.clone();
         let metadata_cache = self.metadata_cache.clone();
+        let deletion_vectors = self.deletion_vectors.clone();
 
         cube_ext::spawn_blocking_mpsc_with_catch_unwind(
             move || {
                 if let Err(e) = read_files(
                     &filenames,
                     metrics,
-                    &projection,
+                    &column_paths,
+                    schema,
                     &predicate_builder,
                     batch_size,
                     response_tx,
                     limit,
                     metadata_cache,
+                    deletion_vectors,
                 ) {
                     println!("Parquet reader thread terminated due to error: {:?}", e);
                 }
@@ -670,6 +873,10 @@ impl ExecutionPlan for ParquetExec {
         }))
     }
 
+    fn statistics(&self) -> Statistics {
+        self.statistics.clone()
+    }
+
     fn fmt_as(
         &self,
         t: DisplayFormatType,
@@ -712,6 +919,14 @@ impl ExecutionPlan for ParquetExec {
                         format!("numRowGroupsPruned for {}", p.filenames.join(",")),
                         p.metrics.row_groups_pruned.as_ref().clone(),
                     ),
+                    (
+                        format!("numRowsPruned for {}", p.filenames.join(",")),
+                        p.metrics.rows_pruned.as_ref().clone(),
+                    ),
+                    (
+                        format!("numRowsDeleted for {}", p.filenames.join(",")),
+                        p.metrics.rows_deleted.as_ref().clone(),
+                    ),
                 ]
             })
             .chain(std::iter::once((
@@ -833,6 +1048,13 @@ fn build_row_group_predicate(
             // NB: false means don't scan row group
             let num_pruned = values.iter().filter(|&v| !v).count();
             metrics.row_groups_pruned.add(num_pruned);
+            let rows_pruned: i64 = row_group_metadata
+                .iter()
+                .zip(values.iter())
+                .filter(|(_, &v)| !v)
+                .map(|(meta, _)| meta.num_rows())
+                .sum();
+            metrics.rows_pruned.add(rows_pruned as usize);
             Box::new(move |_, i| values[i])
         }
         // stats filter array could not be built
@@ -847,35 +1069,77 @@ fn build_row_group_predicate(
 
 #[tracing::instrument(
     level = "trace",
-    skip(metrics, predicate_builder, response_tx, metadata_cache)
+    skip(
+        metrics,
+        schema,
+        predicate_builder,
+        response_tx,
+        metadata_cache,
+        deletion_vectors
+    )
 )]
 fn read_files(
     filenames: &[String],
     metrics: ParquetPartitionMetrics,
-    projection: &[usize],
+    column_paths: &[Vec<String>],
+    schema: SchemaRef,
     predicate_builder: &Option<PruningPredicate>,
     batch_size: usize,
     response_tx: Sender<ArrowResult<RecordBatch>>,
     limit: Option<usize>,
     metadata_cache: Arc<dyn ParquetMetadataCache>,
+    deletion_vectors: Option<Arc<dyn DeletionVectorProvider>>,
 ) -> Result<()> {
     let mut total_rows = 0;
+    let has_shredded_fields = column_paths.iter().any(|path| path.len() > 1);
     'outer: for filename in filenames {
         let mut file_reader = metadata_cache.file_reader(filename)?;
+        let row_groups = file_reader.metadata().row_groups();
+        let mut keep_row_groups = vec![true; row_groups.len()];
         if let Some(predicate_builder) = predicate_builder {
-            let row_group_predicate = build_row_group_predicate(
-                predicate_builder,
-                metrics.clone(),
-                file_reader.metadata().row_groups(),
-            );
-            file_reader.filter_row_groups(&row_group_predicate);
+            let row_group_predicate =
+                build_row_group_predicate(predicate_builder, metrics.clone(), row_groups);
+            for (i, row_group) in row_groups.iter().enumerate() {
+                keep_row_groups[i] = row_group_predicate(row_group, i);
+            }
         }
+        let mut deleted_rows = None;
+        if let Some(deletion_vectors) = &deletion_vectors {
+            if let Some(deleted) = deletion_vectors.deletion_vector(filename)? {
+                deleted_rows =
+                    Some(DeletedRows::new(deleted, row_groups, &keep_row_groups));
+            }
+        }
+        if predicate_builder.is_some() {
+            file_reader.filter_row_groups(&|_, i| keep_row_groups[i]);
+        }
+        let leaves = leaf_columns(
+            file_reader.metadata().file_metadata().schema_descr(),
+            column_paths,
+        );
         let mut arrow_reader = ParquetFileArrowReader::new(Arc::new(file_reader));
-        let mut batch_reader = arrow_reader
-            .get_record_reader_by_columns(projection.to_owned(), batch_size)?;
+        let mut batch_reader =
+            arrow_reader.get_record_reader_by_columns(leaves, batch_size)?;
         loop {
             let span = tracing::trace_span!("parquet read batch");
             let batch = span.in_scope(|| batch_reader.next());
+            let batch = match (batch, &mut deleted_rows) {
+                (Some(Ok(batch)), Some(deleted_rows)) => {
+                    let batch = deleted_rows.filter(&batch);
+                    if batch.is_ok() {
+                        metrics.rows_deleted.add(deleted_rows.last_deleted);
+                    }
+                    Some(batch)
+                }
+                (batch, _) => batch,
+            };
+            let batch = match batch {
+                Some(Ok(batch)) if has_shredded_fields => Some(
+                    shred_batch(&batch, column_paths, &schema)
+                        .map_err(DataFusionError::into_arrow_external_error),
+                ),
+                batch => batch,
+            };
             match batch {
                 Some(Ok(batch)) => {
                     total_rows += batch.num_rows();
@@ -915,6 +1179,148 @@ fn read_files(
     Ok(())
 }
 
+/// The fields of `schema` followed by its shredded fields: the fields nested in its
+/// struct columns, other than structs, named by their path such as `s.a.b`. A
+/// [ParquetExec] projecting a shredded field only reads the column chunks of its
+/// leaves rather than the whole struct column.
+pub fn shredded_schema(schema: &Schema) -> Schema {
+    let mut fields = schema.fields().clone();
+    fields.extend(shredded_fields(schema).into_iter().map(|(field, _)| field));
+    Schema::new(fields)
+}
+
+/// The shredded fields of `schema` with their paths, see [shredded_schema]
+fn shredded_fields(schema: &Schema) -> Vec<(Field, Vec<String>)> {
+    fn visit(
+        path: &mut Vec<String>,
+        data_type: &DataType,
+        shredded: &mut Vec<(Field, Vec<String>)>,
+    ) {
+        match data_type {
+            DataType::Struct(fields) => {
+                for field in fields {
+                    path.push(field.name().clone());
+                    visit(path, field.data_type(), shredded);
+                    path.pop();
+                }
+            }
+            // the columns of the schema themselves
+            _ if path.len() == 1 => {}
+            data_type => shredded.push((
+                // NULL when any of the structs the field is nested in is
+                Field::new(&path.join("."), data_type.clone(), true),
+                path.clone(),
+            )),
+        }
+    }
+    let mut shredded = vec![];
+    for field in schema.fields() {
+        visit(
+            &mut vec![field.name().clone()],
+            field.data_type(),
+            &mut shredded,
+        );
+    }
+    shredded
+}
+
+/// Indices of the leaf columns of `schema` in the columns at `column_paths`, nested
+/// columns are stored as one leaf column per field they contain
+fn leaf_columns(schema: &SchemaDescriptor, column_paths: &[Vec<String>]) -> Vec<usize> {
+    (0..schema.num_columns())
+        .filter(|&i| {
+            let parts = schema.column(i).path().parts();
+            column_paths.iter().any(|path| parts.starts_with(path))
+        })
+        .collect()
+}
+
+/// The columns at `column_paths` of a batch read from a file, which contains the
+/// struct columns the shredded fields are nested in, with only the leaves read
+fn shred_batch(
+    batch: &RecordBatch,
+    column_paths: &[Vec<String>],
+    schema: &SchemaRef,
+) -> Result<RecordBatch> {
+    let columns = column_paths
+        .iter()
+        .map(|path| {
+            let index = batch.schema().index_of(&path[0])?;
+            let mut column = batch.column(index).clone();
+            for name in &path[1..] {
+                column = struct_field(&column, name)?;
+            }
+            Ok(column)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// Removes the rows of a [DeletionVector] from the batches read from a file, which
+/// only contain the rows of the row groups that were not pruned
+struct DeletedRows {
+    deleted: Arc<DeletionVector>,
+    /// Index of the first row and number of rows of each row group that is read
+    row_groups: Vec<(u64, u64)>,
+    /// Current row group and offset of the next row read within it
+    position: (usize, u64),
+    /// Number of rows removed from the last batch
+    last_deleted: usize,
+}
+
+impl DeletedRows {
+    fn new(
+        deleted: Arc<DeletionVector>,
+        row_groups: &[RowGroupMetaData],
+        keep_row_groups: &[bool],
+    ) -> Self {
+        let mut start = 0;
+        let mut kept = Vec::new();
+        for (row_group, keep) in row_groups.iter().zip(keep_row_groups) {
+            let num_rows = row_group.num_rows() as u64;
+            if *keep {
+                kept.push((start, num_rows));
+            }
+            start += num_rows;
+        }
+        Self {
+            deleted,
+            row_groups: kept,
+            position: (0, 0),
+            last_deleted: 0,
+        }
+    }
+
+    fn filter(&mut self, batch: &RecordBatch) -> ArrowResult<RecordBatch> {
+        let mut keep = Vec::with_capacity(batch.num_rows());
+        for _ in 0..batch.num_rows() {
+            let (row_group, offset) = &mut self.position;
+            while *row_group < self.row_groups.len()
+                && *offset == self.row_groups[*row_group].1
+            {
+                *row_group += 1;
+                *offset = 0;
+            }
+            let row = match self.row_groups.get(*row_group) {
+                Some((start, _)) => start + *offset,
+                None => {
+                    return Err(ArrowError::ParquetError(
+                        "More rows read than in the row groups".to_string(),
+                    ))
+                }
+            };
+            *offset += 1;
+            keep.push(!self.deleted.contains(row));
+        }
+
+        self.last_deleted = keep.iter().filter(|k| !**k).count();
+        if self.last_deleted == 0 {
+            return Ok(batch.clone());
+        }
+        filter_record_batch(batch, &BooleanArray::from(keep))
+    }
+}
+
 fn split_files(filenames: &[String], n: usize) -> Vec<&[String]> {
     let mut chunk_size = filenames.len() / n;
     if filenames.len() % n > 0 {
@@ -1030,6 +1436,107 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn read_with_deletion_vectors() -> Result<()> {
+        use crate::logical_plan::{col, lit};
+        use crate::test::DeleteRows;
+        use arrow::array::Int32Array;
+        use parquet::arrow::ArrowWriter;
+
+        let tmp_dir = tempfile::TempDir::new()?;
+        let filename = format!("{}/a.parquet", tmp_dir.path().to_str().unwrap());
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![0, 1, 2, 3, 4, 5]))],
+        )?;
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(2)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(File::create(&filename)?, schema, Some(props))?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        // the first row group is pruned, rows are still numbered from the file start
+        let parquet_exec = ParquetExec::try_from_path(
+            &filename,
+            None,
+            Some(col("a").gt(lit(1))),
+            1024,
+            1,
+            None,
+        )?
+        .with_deletion_vectors(Arc::new(DeleteRows(vec![5, 2, 0])));
+        let batches = common::collect(parquet_exec.execute(0).await?).await?;
+        let expected = vec!["+---+", "| a |", "+---+", "| 3 |", "| 4 |", "+---+"];
+        crate::assert_batches_eq!(expected, &batches);
+
+        let metrics = parquet_exec.metrics();
+        let metric = |name: &str| metrics[&format!("{} for {}", name, filename)].value();
+        assert_eq!(metric("numRowGroupsPruned"), 1);
+        assert_eq!(metric("numRowsPruned"), 2);
+        assert_eq!(metric("numRowsDeleted"), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_shredded_fields() -> Result<()> {
+        use arrow::array::{Int32Array, StringArray, StructArray};
+        use parquet::arrow::ArrowWriter;
+
+        let tmp_dir = tempfile::TempDir::new()?;
+        let filename = format!("{}/a.parquet", tmp_dir.path().to_str().unwrap());
+        let t = StructArray::from(vec![(
+            Field::new("b", DataType::Utf8, true),
+            Arc::new(StringArray::from(vec![Some("x"), None])) as ArrayRef,
+        )]);
+        let s = StructArray::from(vec![
+            (
+                Field::new("a", DataType::Int32, false),
+                Arc::new(Int32Array::from(vec![10, 20])) as ArrayRef,
+            ),
+            (
+                Field::new("t", t.data_type().clone(), false),
+                Arc::new(t) as ArrayRef,
+            ),
+        ]);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("s", s.data_type().clone(), false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2])), Arc::new(s)],
+        )?;
+        let mut writer = ArrowWriter::try_new(File::create(&filename)?, schema, None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+
+        let shredded = shredded_schema(batch.schema().as_ref());
+        let names = shredded
+            .fields()
+            .iter()
+            .map(|f| f.name().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["id", "s", "s.a", "s.t.b"]);
+
+        let parquet_exec =
+            ParquetExec::try_from_path(&filename, Some(vec![3, 0]), None, 1024, 1, None)?;
+        assert_eq!(parquet_exec.schema().field(0), shredded.field(3));
+        let batches = common::collect(parquet_exec.execute(0).await?).await?;
+        let expected = vec![
+            "+-------+----+",
+            "| s.t.b | id |",
+            "+-------+----+",
+            "| x     | 1  |",
+            "|       | 2  |",
+            "+-------+----+",
+        ];
+        crate::assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
     #[test]
     fn row_group_predicate_builder_simple_expr() -> Result<()> {
         use crate::logical_plan::{col, lit};
@@ -0,0 +1,551 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the partial SORT plan, which sorts an input that is already sorted on a
+//! prefix of the sort key
+
+use std::any::Any;
+use std::cmp::Ordering;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use arrow::array::{build_compare, ArrayRef};
+use arrow::datatypes::SchemaRef;
+use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt};
+use hashbrown::HashMap;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::{Column, PhysicalSortExpr};
+use crate::physical_plan::sort::sort_batch;
+use crate::physical_plan::{
+    common, DisplayFormatType, Distribution, ExecutionPlan, OptimizerHints, Partitioning,
+    RecordBatchStream, SQLMetric, SendableRecordBatchStream,
+};
+
+/// Sorts an input that is already sorted on the first `prefix_len` sort expressions.
+/// Only the rows of each run with equal values of the prefix are sorted on the
+/// remaining expressions, so runs are produced as soon as they end instead of
+/// buffering the whole input.
+#[derive(Debug)]
+pub struct PartialSortExec {
+    input: Arc<dyn ExecutionPlan>,
+    expr: Vec<PhysicalSortExpr>,
+    /// Number of leading sort expressions the input is sorted on
+    prefix_len: usize,
+    preserve_partitioning: bool,
+    skip: usize,
+    fetch: Option<usize>,
+    output_rows: Arc<SQLMetric>,
+    sort_time_nanos: Arc<SQLMetric>,
+}
+
+impl PartialSortExec {
+    /// Create a new partial sort of `input` that is sorted on the first `prefix_len`
+    /// expressions of `expr`
+    pub fn try_new(
+        expr: Vec<PhysicalSortExpr>,
+        prefix_len: usize,
+        input: Arc<dyn ExecutionPlan>,
+        preserve_partitioning: bool,
+    ) -> Result<Self> {
+        if prefix_len == 0 || expr.len() < prefix_len {
+            return Err(DataFusionError::Internal(format!(
+                "PartialSortExec prefix length {} is out of range for {} sort expressions",
+                prefix_len,
+                expr.len()
+            )));
+        }
+        Ok(Self {
+            input,
+            expr,
+            prefix_len,
+            preserve_partitioning,
+            skip: 0,
+            fetch: None,
+            output_rows: SQLMetric::counter(),
+            sort_time_nanos: SQLMetric::time_nanos(),
+        })
+    }
+
+    /// Skip the first `skip` rows of the sorted output (`OFFSET`)
+    pub fn with_skip(mut self, skip: usize) -> Self {
+        self.skip = skip;
+        self
+    }
+
+    /// Only produce the first `fetch` rows (after skipping) of the sorted output
+    /// (`LIMIT`). Reading the input stops once they are produced.
+    pub fn with_fetch(mut self, fetch: Option<usize>) -> Self {
+        self.fetch = fetch;
+        self
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// Sort expressions
+    pub fn expr(&self) -> &[PhysicalSortExpr] {
+        &self.expr
+    }
+
+    /// Number of leading sort expressions the input is sorted on
+    pub fn prefix_len(&self) -> usize {
+        self.prefix_len
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for PartialSortExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        if self.preserve_partitioning {
+            self.input.output_partitioning()
+        } else {
+            Partitioning::UnknownPartitioning(1)
+        }
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        if self.preserve_partitioning {
+            Distribution::UnspecifiedDistribution
+        } else {
+            Distribution::SinglePartition
+        }
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(
+                PartialSortExec::try_new(
+                    self.expr.clone(),
+                    self.prefix_len,
+                    children[0].clone(),
+                    self.preserve_partitioning,
+                )?
+                .with_skip(self.skip)
+                .with_fetch(self.fetch),
+            )),
+            _ => Err(DataFusionError::Internal(
+                "PartialSortExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        if !self.preserve_partitioning {
+            if 0 != partition {
+                return Err(DataFusionError::Internal(format!(
+                    "PartialSortExec invalid partition {}",
+                    partition
+                )));
+            }
+            if 1 != self.input.output_partitioning().partition_count() {
+                return Err(DataFusionError::Internal(
+                    "PartialSortExec requires a single input partition".to_owned(),
+                ));
+            }
+        }
+
+        let input = self.input.execute(partition).await?;
+        Ok(Box::pin(PartialSortStream {
+            schema: input.schema(),
+            input,
+            expr: self.expr.clone(),
+            prefix_len: self.prefix_len,
+            to_skip: self.skip,
+            remaining: self.fetch,
+            buffered: vec![],
+            last_prefix: None,
+            finished: false,
+            output_rows: self.output_rows.clone(),
+            sort_time: self.sort_time_nanos.clone(),
+        }))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                let expr: Vec<String> = self.expr.iter().map(|e| e.to_string()).collect();
+                write!(
+                    f,
+                    "PartialSortExec: [{}], prefix_len={}",
+                    expr.join(","),
+                    self.prefix_len
+                )?;
+                if self.skip != 0 {
+                    write!(f, ", skip={}", self.skip)?;
+                }
+                if let Some(fetch) = self.fetch {
+                    write!(f, ", fetch={}", fetch)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn metrics(&self) -> HashMap<String, SQLMetric> {
+        let mut metrics = HashMap::new();
+        metrics.insert("outputRows".to_owned(), (*self.output_rows).clone());
+        metrics.insert("sortTime".to_owned(), (*self.sort_time_nanos).clone());
+        metrics
+    }
+
+    fn output_hints(&self) -> OptimizerHints {
+        let mut order = Vec::with_capacity(self.expr.len());
+        for s in &self.expr {
+            match s.expr.as_any().downcast_ref::<Column>() {
+                Some(column) => order.push(column.index()),
+                None => break,
+            }
+        }
+        OptimizerHints {
+            sort_order: Some(order),
+            single_value_columns: self.input.output_hints().single_value_columns,
+        }
+    }
+}
+
+struct PartialSortStream {
+    input: SendableRecordBatchStream,
+    schema: SchemaRef,
+    expr: Vec<PhysicalSortExpr>,
+    prefix_len: usize,
+    to_skip: usize,
+    remaining: Option<usize>,
+    /// Rows of the last run, which may continue in the next input batch
+    buffered: Vec<RecordBatch>,
+    /// Prefix of the last produced row, to check the input is sorted on it
+    last_prefix: Option<Vec<ArrayRef>>,
+    finished: bool,
+    output_rows: Arc<SQLMetric>,
+    sort_time: Arc<SQLMetric>,
+}
+
+impl PartialSortStream {
+    fn prefix(&self, batch: &RecordBatch) -> ArrowResult<Vec<ArrayRef>> {
+        self.expr[..self.prefix_len]
+            .iter()
+            .map(|e| Ok(e.expr.evaluate(batch)?.into_array(batch.num_rows())))
+            .collect::<Result<Vec<_>>>()
+            .map_err(DataFusionError::into_arrow_external_error)
+    }
+
+    fn cmp_prefix(
+        &self,
+        l: &[ArrayRef],
+        l_row: usize,
+        r: &[ArrayRef],
+        r_row: usize,
+    ) -> ArrowResult<Ordering> {
+        for ((l, r), e) in l.iter().zip(r.iter()).zip(self.expr.iter()) {
+            let o = match (l.is_valid(l_row), r.is_valid(r_row)) {
+                (false, false) => Ordering::Equal,
+                (false, true) if e.options.nulls_first => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (true, false) if e.options.nulls_first => Ordering::Greater,
+                (true, false) => Ordering::Less,
+                (true, true) => {
+                    let o = build_compare(l.as_ref(), r.as_ref())?(l_row, r_row);
+                    if e.options.descending {
+                        o.reverse()
+                    } else {
+                        o
+                    }
+                }
+            };
+            if o != Ordering::Equal {
+                return Ok(o);
+            }
+        }
+        Ok(Ordering::Equal)
+    }
+
+    /// Adds `batch` to the buffered rows and returns the rows of the runs that ended
+    fn push(&mut self, batch: RecordBatch) -> ArrowResult<Option<Vec<RecordBatch>>> {
+        let num_rows = batch.num_rows();
+        if num_rows == 0 {
+            return Ok(None);
+        }
+        let prefix = self.prefix(&batch)?;
+        // start of the last run of the batch
+        let mut start = num_rows - 1;
+        while start > 0
+            && self.cmp_prefix(&prefix, start - 1, &prefix, num_rows - 1)?
+                == Ordering::Equal
+        {
+            start -= 1;
+        }
+        if start == 0 {
+            let continues_run = match self.buffered.last() {
+                Some(last) => {
+                    let last_prefix = self.prefix(last)?;
+                    self.cmp_prefix(&last_prefix, last.num_rows() - 1, &prefix, 0)?
+                        == Ordering::Equal
+                }
+                None => true,
+            };
+            if continues_run {
+                self.buffered.push(batch);
+                return Ok(None);
+            }
+        }
+
+        let mut complete = std::mem::take(&mut self.buffered);
+        if start != 0 {
+            complete.push(slice_batch(&batch, 0, start)?);
+        }
+        self.buffered
+            .push(slice_batch(&batch, start, num_rows - start)?);
+        Ok(Some(complete))
+    }
+
+    /// Sorts the rows of complete runs, applying the skip and the fetch
+    fn sort(&mut self, batches: Vec<RecordBatch>) -> ArrowResult<Option<RecordBatch>> {
+        let now = Instant::now();
+        let batch = match common::combine_batches(&batches, self.schema.clone())? {
+            Some(batch) => batch,
+            None => return Ok(None),
+        };
+        let batch = sort_batch(batch, self.schema.clone(), &self.expr, 0, None)?;
+        self.sort_time.add(now.elapsed().as_nanos() as usize);
+
+        let prefix = self.prefix(&batch)?;
+        if let Some(last_prefix) = &self.last_prefix {
+            if self.cmp_prefix(last_prefix, 0, &prefix, 0)? == Ordering::Greater {
+                return Err(ArrowError::ComputeError(
+                    "PartialSortExec input is not sorted on the sort key prefix"
+                        .to_string(),
+                ));
+            }
+        }
+        let last = batch.num_rows() - 1;
+        self.last_prefix = Some(prefix.iter().map(|a| a.slice(last, 1)).collect());
+
+        let skip = self.to_skip.min(batch.num_rows());
+        self.to_skip -= skip;
+        let mut len = batch.num_rows() - skip;
+        if let Some(remaining) = &mut self.remaining {
+            len = len.min(*remaining);
+            *remaining -= len;
+            if *remaining == 0 {
+                self.finished = true;
+            }
+        }
+        if len == 0 {
+            return Ok(None);
+        }
+        self.output_rows.add(len);
+        Ok(Some(slice_batch(&batch, skip, len)?))
+    }
+}
+
+fn slice_batch(
+    batch: &RecordBatch,
+    offset: usize,
+    len: usize,
+) -> ArrowResult<RecordBatch> {
+    RecordBatch::try_new(
+        batch.schema(),
+        batch
+            .columns()
+            .iter()
+            .map(|c| c.slice(offset, len))
+            .collect(),
+    )
+}
+
+impl Stream for PartialSortStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.finished {
+                return Poll::Ready(None);
+            }
+            let complete = match self.input.poll_next_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(batch))) => match self.push(batch) {
+                    Ok(Some(complete)) => complete,
+                    Ok(None) => continue,
+                    Err(e) => return Poll::Ready(Some(Err(e))),
+                },
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => {
+                    self.finished = true;
+                    std::mem::take(&mut self.buffered)
+                }
+            };
+            match self.sort(complete) {
+                Ok(Some(batch)) => return Poll::Ready(Some(Ok(batch))),
+                Ok(None) => continue,
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+        }
+    }
+}
+
+impl RecordBatchStream for PartialSortStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::memory::MemoryExec;
+    use arrow::array::Int32Array;
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    fn batch(schema: &SchemaRef, a: Vec<i32>, b: Vec<i32>) -> RecordBatch {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(a)), Arc::new(Int32Array::from(b))],
+        )
+        .unwrap()
+    }
+
+    fn partial_sort(
+        batches: Vec<RecordBatch>,
+        schema: &SchemaRef,
+    ) -> Result<PartialSortExec> {
+        let expr = vec![
+            PhysicalSortExpr {
+                expr: col("a", schema)?,
+                options: SortOptions::default(),
+            },
+            PhysicalSortExpr {
+                expr: col("b", schema)?,
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: false,
+                },
+            },
+        ];
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None)?);
+        PartialSortExec::try_new(expr, 1, input, false)
+    }
+
+    fn values(batches: &[RecordBatch]) -> Vec<(i32, i32)> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let a = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int32Array>()
+                    .unwrap();
+                let b = batch
+                    .column(1)
+                    .as_any()
+                    .downcast_ref::<Int32Array>()
+                    .unwrap();
+                (0..batch.num_rows())
+                    .map(|i| (a.value(i), b.value(i)))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn sort_runs_across_batches() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let batches = vec![
+            batch(&schema, vec![1, 1, 2], vec![1, 3, 2]),
+            batch(&schema, vec![2, 2], vec![5, 1]),
+            batch(&schema, vec![2, 3, 3], vec![4, 1, 2]),
+        ];
+
+        let sort = Arc::new(partial_sort(batches.clone(), &schema)?);
+        let result = collect(sort.clone()).await?;
+        assert_eq!(
+            values(&result),
+            vec![
+                (1, 3),
+                (1, 1),
+                (2, 5),
+                (2, 4),
+                (2, 2),
+                (2, 1),
+                (3, 2),
+                (3, 1)
+            ]
+        );
+        // every run is produced as soon as it ends
+        assert_eq!(result.len(), 3);
+        assert_eq!(sort.metrics().get("outputRows").unwrap().value(), 8);
+
+        let sort = Arc::new(
+            partial_sort(batches, &schema)?
+                .with_skip(1)
+                .with_fetch(Some(3)),
+        );
+        let result = collect(sort).await?;
+        assert_eq!(values(&result), vec![(1, 1), (2, 5), (2, 4)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unsorted_prefix() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let batches = vec![
+            batch(&schema, vec![2, 2], vec![1, 2]),
+            batch(&schema, vec![1, 1], vec![1, 2]),
+        ];
+        let sort = Arc::new(partial_sort(batches, &schema)?);
+        let err = collect(sort).await.unwrap_err();
+        assert!(err.to_string().contains("not sorted"), "{}", err);
+        Ok(())
+    }
+}
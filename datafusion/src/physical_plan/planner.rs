@@ -21,23 +21,29 @@ use super::{
     aggregates, cross_join::CrossJoinExec, empty::EmptyExec, expressions::binary,
     functions, hash_join::PartitionMode, udaf, union::UnionExec, windows,
 };
+use crate::catalog::TableReference;
 use crate::cube_ext::alias::LogicalAliasPlanner;
 use crate::cube_ext::join::CrossJoinPlanner;
 use crate::cube_ext::joinagg::CrossJoinAggPlanner;
 use crate::execution::context::ExecutionContextState;
 use crate::logical_plan::{
-    unnormalize_cols, DFSchema, Expr, LogicalPlan, Operator,
+    unnormalize_cols, DFSchema, Expr, GetFieldAccess, JoinType, LogicalPlan, Operator,
     Partitioning as LogicalPartitioning, PlanType, ToStringifiedPlan,
     UserDefinedLogicalNode,
 };
 use crate::physical_optimizer::optimizer::PhysicalOptimizerRule;
+use crate::physical_plan::analyze::AnalyzeExec;
 use crate::physical_plan::explain::ExplainExec;
-use crate::physical_plan::expressions::{CaseExpr, Column, Literal, PhysicalSortExpr};
+use crate::physical_plan::expressions::{
+    union_coercion, CaseExpr, Column, Literal, PhysicalSortExpr,
+};
 use crate::physical_plan::filter::FilterExec;
+use crate::physical_plan::functions::Volatility;
 use crate::physical_plan::hash_aggregate::{
     AggregateMode, AggregateStrategy, HashAggregateExec,
 };
 use crate::physical_plan::hash_join::HashJoinExec;
+use crate::physical_plan::insert::InsertExec;
 use crate::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
 use crate::physical_plan::merge::MergeExec;
 use crate::physical_plan::merge_join::MergeJoinExec;
@@ -48,10 +54,11 @@ use crate::physical_plan::projection::ProjectionExec;
 use crate::physical_plan::repartition::RepartitionExec;
 use crate::physical_plan::skip::SkipExec;
 use crate::physical_plan::sort::SortExec;
+use crate::physical_plan::sort_preserving_merge::SortPreservingMergeExec;
 use crate::physical_plan::udf;
 use crate::physical_plan::windows::WindowAggExec;
 use crate::physical_plan::{expressions, ColumnarValue};
-use crate::physical_plan::{hash_utils, Partitioning};
+use crate::physical_plan::{hash_utils, higher_order_functions, Partitioning};
 use crate::physical_plan::{AggregateExpr, ExecutionPlan, PhysicalExpr, WindowExpr};
 use crate::scalar::ScalarValue;
 use crate::sql::utils::{generate_sort_key, window_expr_common_partition_keys};
@@ -61,7 +68,7 @@ use crate::{
     physical_plan::displayable,
 };
 use arrow::array::*;
-use arrow::compute::SortOptions;
+use arrow::compute::{CastOptions, SortOptions};
 use arrow::datatypes::Field;
 use arrow::datatypes::{Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
@@ -70,6 +77,7 @@ use expressions::col;
 use itertools::Itertools;
 use log::debug;
 
+use std::collections::HashSet;
 use std::sync::Arc;
 
 fn create_function_physical_name(
@@ -157,9 +165,49 @@ pub fn physical_name(e: &Expr, input_schema: &DFSchema) -> Result<String> {
             fun,
             distinct,
             args,
-            ..
+            ignore_nulls,
+            order_by,
+            filter,
         } => {
-            create_function_physical_name(&fun.to_string(), *distinct, args, input_schema)
+            let mut parts = vec![create_function_physical_name(
+                &fun.to_string(),
+                *distinct,
+                args,
+                input_schema,
+            )?];
+            if *ignore_nulls {
+                parts.push("IGNORE NULLS".to_string());
+            }
+            if !order_by.is_empty() {
+                let mut names = Vec::with_capacity(order_by.len());
+                for e in order_by {
+                    names.push(match e {
+                        Expr::Sort {
+                            expr,
+                            asc,
+                            nulls_first,
+                        } => format!(
+                            "{} {} NULLS {}",
+                            physical_name(expr, input_schema)?,
+                            if *asc { "ASC" } else { "DESC" },
+                            if *nulls_first { "FIRST" } else { "LAST" },
+                        ),
+                        e => physical_name(e, input_schema)?,
+                    });
+                }
+                if aggregates::is_ordered_set(fun) {
+                    parts.push(format!("WITHIN GROUP (ORDER BY {})", names.join(", ")));
+                } else {
+                    parts.push(format!("ORDER BY [{}]", names.join(", ")));
+                }
+            }
+            if let Some(filter) = filter {
+                parts.push(format!(
+                    "FILTER (WHERE {})",
+                    physical_name(filter, input_schema)?
+                ));
+            }
+            Ok(parts.join(" "))
         }
         Expr::AggregateUDF { fun, args } => {
             let mut names = Vec::with_capacity(args.len());
@@ -181,6 +229,31 @@ pub fn physical_name(e: &Expr, input_schema: &DFSchema) -> Result<String> {
                 Ok(format!("{} IN ({:?})", expr, list))
             }
         }
+        Expr::HigherOrderFunction { fun, args } => {
+            create_function_physical_name(&fun.to_string(), false, args, input_schema)
+        }
+        Expr::Lambda { params, body } => Ok(format!(
+            "({}) -> {}",
+            params.join(", "),
+            physical_name(body, input_schema)?
+        )),
+        Expr::GetIndexedField { expr, field } => {
+            let expr = physical_name(expr, input_schema)?;
+            match field {
+                GetFieldAccess::ListIndex { key } => {
+                    Ok(format!("{}[{}]", expr, physical_name(key, input_schema)?))
+                }
+                GetFieldAccess::ListRange { start, stop } => Ok(format!(
+                    "{}[{}:{}]",
+                    expr,
+                    physical_name(start, input_schema)?,
+                    physical_name(stop, input_schema)?
+                )),
+                GetFieldAccess::NamedStructField { name } => {
+                    Ok(format!("{}.{}", expr, name))
+                }
+            }
+        }
         other => Err(DataFusionError::NotImplemented(format!(
             "Cannot derive physical field name for logical expression {:?}",
             other
@@ -463,11 +536,14 @@ impl DefaultPhysicalPlanner {
                     })
                     .collect::<Result<Vec<_>>>()?;
 
-                Ok(Arc::new(WindowAggExec::try_new(
-                    window_expr,
-                    input_exec,
-                    physical_input_schema,
-                )?))
+                Ok(Arc::new(
+                    WindowAggExec::try_new(
+                        window_expr,
+                        input_exec,
+                        physical_input_schema,
+                    )?
+                    .with_memory_limit(ctx_state.config.memory_limit()),
+                ))
             }
             LogicalPlan::Aggregate {
                 input,
@@ -505,11 +581,50 @@ impl DefaultPhysicalPlanner {
                         )
                     })
                     .collect::<Result<Vec<_>>>()?;
+                let aggregates =
+                    with_sorted_percentiles(aggregates, input_exec.as_ref(), &groups);
 
                 //It's not obvious here, but "order" here is mapping from input "sort_on" into
                 //positions of "group by" columns
-                let (strategy, order) =
+                let (sorted_strategy, sort_order) =
                     compute_aggregation_strategy(input_exec.as_ref(), &groups);
+                // the cost model only chooses among the strategies that are correct
+                // for the input
+                let valid_strategies = if sorted_strategy == AggregateStrategy::Hash {
+                    vec![AggregateStrategy::Hash]
+                } else {
+                    vec![sorted_strategy, AggregateStrategy::Hash]
+                };
+                let strategy = ctx_state.config.cost_model().aggregate_strategy(
+                    input_exec.as_ref(),
+                    &groups,
+                    &valid_strategies,
+                );
+                if !valid_strategies.contains(&strategy) {
+                    return Err(DataFusionError::Internal(format!(
+                        "The cost model chose the {:?} aggregation strategy, which is \
+                         not valid for the input {:?}",
+                        strategy, input_exec
+                    )));
+                }
+                let order = if strategy == AggregateStrategy::InplaceSorted {
+                    sort_order
+                } else {
+                    None
+                };
+                if is_partitioned_on_groups(&input_exec.output_partitioning(), &groups) {
+                    // Each group is within a single partition, so aggregating every
+                    // partition on its own gives the final result.
+                    return Ok(Arc::new(HashAggregateExec::try_new(
+                        strategy,
+                        order,
+                        AggregateMode::Full,
+                        groups,
+                        aggregates,
+                        input_exec,
+                        physical_input_schema.clone(),
+                    )?));
+                }
                 // TODO: fix cubestore planning and re-enable.
                 if false && input_exec.output_partitioning().partition_count() == 1 {
                     // A single pass is enough for 1 partition.
@@ -555,16 +670,9 @@ impl DefaultPhysicalPlanner {
                     .map(|i| col(&groups[i].1, &initial_aggr.schema()))
                     .collect::<Result<_>>()?;
 
-                // TODO: dictionary type not yet supported in Hash Repartition
-                let contains_dict = groups
-                    .iter()
-                    .flat_map(|x| x.0.data_type(physical_input_schema.as_ref()))
-                    .any(|x| matches!(x, DataType::Dictionary(_, _)));
-
                 let can_repartition = !groups.is_empty()
                     && ctx_state.config.concurrency > 1
                     && ctx_state.config.repartition_aggregations
-                    && !contains_dict
                     && strategy == AggregateStrategy::Hash;
 
                 let (initial_aggr, next_partition_mode): (
@@ -680,9 +788,12 @@ impl DefaultPhysicalPlanner {
 
                 let merge_node: Arc<dyn ExecutionPlan> =
                     if sorted_on.iter().all(|on| on.is_some()) && sorted_on.len() == 1 {
+                        let columns = sorted_on[0].as_ref().unwrap().clone();
+                        let physical_plans =
+                            coerce_sort_columns(physical_plans, &columns)?;
                         Arc::new(MergeSortExec::try_new(
                             Arc::new(UnionExec::new(physical_plans)),
-                            sorted_on[0].as_ref().unwrap().clone(),
+                            columns,
                         )?)
                     } else {
                         Arc::new(MergeExec::new(Arc::new(UnionExec::new(physical_plans))))
@@ -721,34 +832,7 @@ impl DefaultPhysicalPlanner {
                 )?))
             }
             LogicalPlan::Sort { expr, input, .. } => {
-                let physical_input = self.create_initial_plan(input, ctx_state)?;
-                let input_schema = physical_input.as_ref().schema();
-                let input_dfschema = input.as_ref().schema();
-
-                let sort_expr = expr
-                    .iter()
-                    .map(|e| match e {
-                        Expr::Sort {
-                            expr,
-                            asc,
-                            nulls_first,
-                        } => self.create_physical_sort_expr(
-                            expr,
-                            input_dfschema,
-                            &input_schema,
-                            SortOptions {
-                                descending: !*asc,
-                                nulls_first: *nulls_first,
-                            },
-                            ctx_state,
-                        ),
-                        _ => Err(DataFusionError::Plan(
-                            "Sort only accepts sort expressions".to_string(),
-                        )),
-                    })
-                    .collect::<Result<Vec<_>>>()?;
-
-                Ok(Arc::new(SortExec::try_new(sort_expr, physical_input)?))
+                self.create_sort_plan(expr, input, 0, None, ctx_state)
             }
             LogicalPlan::Join {
                 left,
@@ -798,12 +882,12 @@ impl DefaultPhysicalPlanner {
                     } else {
                         physical_right
                     };
-                    Ok(Arc::new(MergeJoinExec::try_new(
+                    merge_join_with_coerced_keys(
                         left_to_join,
                         right_to_join,
-                        &keys,
-                        &join_type,
-                    )?))
+                        keys,
+                        join_type,
+                    )
                 } else {
                     if ctx_state.config.concurrency > 1
                         && ctx_state.config.repartition_joins
@@ -863,6 +947,30 @@ impl DefaultPhysicalPlanner {
             ))),
             LogicalPlan::Limit { input, n, .. } => {
                 let limit = *n;
+                // push LIMIT and OFFSET into the sort, if there is one
+                match input.as_ref() {
+                    LogicalPlan::Sort { expr, input } => {
+                        return self.create_sort_plan(
+                            expr,
+                            input,
+                            0,
+                            Some(limit),
+                            ctx_state,
+                        );
+                    }
+                    LogicalPlan::Skip { input, n: skip } => {
+                        if let LogicalPlan::Sort { expr, input } = input.as_ref() {
+                            return self.create_sort_plan(
+                                expr,
+                                input,
+                                *skip,
+                                Some(limit),
+                                ctx_state,
+                            );
+                        }
+                    }
+                    _ => {}
+                }
                 let input = self.create_initial_plan(input, ctx_state)?;
 
                 // GlobalLimitExec requires a single partition for input
@@ -878,6 +986,9 @@ impl DefaultPhysicalPlanner {
             }
             LogicalPlan::Skip { input, n, .. } => {
                 let skip = *n;
+                if let LogicalPlan::Sort { expr, input } = input.as_ref() {
+                    return self.create_sort_plan(expr, input, skip, None, ctx_state);
+                }
                 let input = self.create_physical_plan(input, ctx_state)?;
 
                 Ok(Arc::new(SkipExec::new(input, skip)))
@@ -891,6 +1002,57 @@ impl DefaultPhysicalPlanner {
                     "Unsupported logical plan: CreateExternalTable".to_string(),
                 ))
             }
+            LogicalPlan::CreateMemoryTable { .. } => {
+                // Like "CREATE EXTERNAL TABLE", the table is registered by the
+                // context, which fills it with an insert plan
+                Err(DataFusionError::Internal(
+                    "Unsupported logical plan: CreateMemoryTable".to_string(),
+                ))
+            }
+            LogicalPlan::Insert {
+                table_name,
+                table,
+                input,
+                create_table,
+                ..
+            } => {
+                let input = self.create_initial_plan(input, ctx_state)?;
+                let insert = InsertExec::new(table_name.clone(), table.clone(), input);
+                if *create_table {
+                    let table_ref: TableReference = table_name.as_str().into();
+                    let schema_provider = ctx_state.schema_for_ref(table_ref)?;
+                    Ok(Arc::new(insert.with_create_table(schema_provider)))
+                } else {
+                    Ok(Arc::new(insert))
+                }
+            }
+            LogicalPlan::Analyze {
+                table_name, table, ..
+            } => {
+                let table_ref: TableReference = table_name.as_str().into();
+                let schema_provider = ctx_state.schema_for_ref(table_ref)?;
+                // the statistics are stored by replacing the table in its schema
+                if schema_provider.table(table_ref.table()).is_none() {
+                    return Err(DataFusionError::Plan(format!(
+                        "Table or CTE with name '{}' not found",
+                        table_name
+                    )));
+                }
+                let input = table.scan(&None, batch_size, &[], None)?;
+                Ok(Arc::new(AnalyzeExec::new(
+                    table_ref.table().to_string(),
+                    table.clone(),
+                    schema_provider,
+                    input,
+                )))
+            }
+            LogicalPlan::SetVariable { .. } => {
+                // Configuration changes are applied by the context, there is
+                // nothing to execute.
+                Err(DataFusionError::Internal(
+                    "Unsupported logical plan: SetVariable".to_string(),
+                ))
+            }
             LogicalPlan::Explain { .. } => Err(DataFusionError::Internal(
                 "Unsupported logical plan: Explain must be root of the plan".to_string(),
             )),
@@ -1061,10 +1223,17 @@ impl DefaultPhysicalPlanner {
                     input_schema,
                     ctx_state,
                 )?;
-                self.evaluate_constants(
-                    binary(lhs.clone(), *op, rhs.clone(), input_schema)?,
-                    vec![lhs, rhs],
-                )
+                let expr = if ctx_state.config.arithmetic_overflow().is_checked() {
+                    expressions::checked_binary(
+                        lhs.clone(),
+                        *op,
+                        rhs.clone(),
+                        input_schema,
+                    )?
+                } else {
+                    binary(lhs.clone(), *op, rhs.clone(), input_schema)?
+                };
+                self.evaluate_constants(expr, vec![lhs, rhs])
             }
             Expr::Case {
                 expr,
@@ -1139,8 +1308,17 @@ impl DefaultPhysicalPlanner {
                     input_schema,
                     ctx_state,
                 )?;
+                let cast_options = CastOptions {
+                    safe: ctx_state.config.lenient_cast(),
+                };
                 self.evaluate_constants(
-                    expressions::cast(input.clone(), input_schema, data_type.clone())?,
+                    expressions::cast_in_zone(
+                        input.clone(),
+                        input_schema,
+                        data_type.clone(),
+                        cast_options,
+                        ctx_state.execution_props.time_zone,
+                    )?,
                     vec![input],
                 )
             }
@@ -1152,10 +1330,11 @@ impl DefaultPhysicalPlanner {
                     ctx_state,
                 )?;
                 self.evaluate_constants(
-                    expressions::try_cast(
+                    expressions::try_cast_in_zone(
                         input.clone(),
                         input_schema,
                         data_type.clone(),
+                        ctx_state.execution_props.time_zone,
                     )?,
                     vec![input],
                 )
@@ -1218,7 +1397,8 @@ impl DefaultPhysicalPlanner {
                     })
                     .collect::<Result<Vec<_>>>()?;
 
-                self.evaluate_constants(
+                self.evaluate_constants_if(
+                    fun.volatility() != Volatility::Volatile,
                     functions::create_physical_expr(
                         fun,
                         &physical_args,
@@ -1239,11 +1419,13 @@ impl DefaultPhysicalPlanner {
                     )?);
                 }
 
-                self.evaluate_constants(
-                    udf::create_physical_expr(
+                self.evaluate_constants_if(
+                    fun.volatility != Volatility::Volatile,
+                    udf::create_statement_physical_expr(
                         fun.clone().as_ref(),
                         &physical_args,
                         input_schema,
+                        &ctx_state.execution_props,
                     )?,
                     physical_args,
                 )
@@ -1292,7 +1474,8 @@ impl DefaultPhysicalPlanner {
                 list,
                 negated,
             } => match expr.as_ref() {
-                Expr::Literal(ScalarValue::Utf8(None)) => {
+                Expr::Literal(ScalarValue::Null)
+                | Expr::Literal(ScalarValue::Utf8(None)) => {
                     Ok(expressions::lit(ScalarValue::Boolean(None)))
                 }
                 _ => {
@@ -1326,10 +1509,12 @@ impl DefaultPhysicalPlanner {
 
                                 if list_expr_data_type == value_expr_data_type {
                                     Ok(list_expr)
-                                } else if can_cast_types(
-                                    &list_expr_data_type,
-                                    &value_expr_data_type,
-                                ) {
+                                } else if list_expr_data_type == DataType::Null
+                                    || can_cast_types(
+                                        &list_expr_data_type,
+                                        &value_expr_data_type,
+                                    )
+                                {
                                     expressions::cast(
                                         list_expr,
                                         input_schema,
@@ -1348,6 +1533,66 @@ impl DefaultPhysicalPlanner {
                     expressions::in_list(value_expr, list_exprs, negated)
                 }
             },
+            Expr::HigherOrderFunction { fun, args } => {
+                let (args, params, body) = higher_order_functions::split_lambda(args)?;
+                let arg_types = args
+                    .iter()
+                    .map(|e| e.get_type(input_dfschema))
+                    .collect::<Result<Vec<_>>>()?;
+                let physical_args = args
+                    .iter()
+                    .map(|e| {
+                        self.create_physical_expr(
+                            e,
+                            input_dfschema,
+                            input_schema,
+                            ctx_state,
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let body_dfschema = higher_order_functions::lambda_schema(
+                    params,
+                    &fun.lambda_param_types(&arg_types)?,
+                )?;
+                let body_schema: Schema = (&body_dfschema).into();
+                let body = self.create_physical_expr(
+                    body,
+                    &body_dfschema,
+                    &body_schema,
+                    ctx_state,
+                )?;
+                higher_order_functions::create_physical_expr(
+                    fun,
+                    physical_args,
+                    body,
+                    Arc::new(body_schema),
+                    input_schema,
+                )
+            }
+            Expr::GetIndexedField { expr, field } => {
+                let create_physical_expr = |e: &Expr| {
+                    self.create_physical_expr(e, input_dfschema, input_schema, ctx_state)
+                };
+                let expr = create_physical_expr(expr)?;
+                match field {
+                    GetFieldAccess::ListIndex { key } => expressions::get_indexed_field(
+                        expr,
+                        create_physical_expr(key)?,
+                        input_schema,
+                    ),
+                    GetFieldAccess::ListRange { start, stop } => {
+                        expressions::get_list_range(
+                            expr,
+                            create_physical_expr(start)?,
+                            create_physical_expr(stop)?,
+                            input_schema,
+                        )
+                    }
+                    GetFieldAccess::NamedStructField { name } => {
+                        expressions::get_struct_field(expr, name, input_schema)
+                    }
+                }
+            }
             other => Err(DataFusionError::NotImplemented(format!(
                 "Physical plan does not support logical expression {:?}",
                 other
@@ -1360,9 +1605,21 @@ impl DefaultPhysicalPlanner {
         res_expr: Arc<dyn PhysicalExpr>,
         inputs: Vec<Arc<dyn PhysicalExpr>>,
     ) -> Result<Arc<dyn PhysicalExpr>> {
-        if inputs
-            .iter()
-            .all(|i| i.as_any().downcast_ref::<Literal>().is_some())
+        self.evaluate_constants_if(true, res_expr, inputs)
+    }
+
+    /// Evaluates `res_expr` at planning time when its `inputs` are literals, unless
+    /// `foldable` is false, e.g. for the calls of volatile functions
+    fn evaluate_constants_if(
+        &self,
+        foldable: bool,
+        res_expr: Arc<dyn PhysicalExpr>,
+        inputs: Vec<Arc<dyn PhysicalExpr>>,
+    ) -> Result<Arc<dyn PhysicalExpr>> {
+        if foldable
+            && inputs
+                .iter()
+                .all(|i| i.as_any().downcast_ref::<Literal>().is_some())
         {
             Ok(evaluate_const(res_expr)?)
         } else {
@@ -1491,7 +1748,9 @@ impl DefaultPhysicalPlanner {
                 fun,
                 distinct,
                 args,
-                ..
+                ignore_nulls,
+                order_by,
+                filter,
             } => {
                 let args = args
                     .iter()
@@ -1504,13 +1763,87 @@ impl DefaultPhysicalPlanner {
                         )
                     })
                     .collect::<Result<Vec<_>>>()?;
-                aggregates::create_aggregate_expr(
+                let order_by = order_by
+                    .iter()
+                    .map(|e| match e {
+                        Expr::Sort {
+                            expr,
+                            asc,
+                            nulls_first,
+                        } => self.create_physical_sort_expr(
+                            expr,
+                            logical_input_schema,
+                            physical_input_schema,
+                            SortOptions {
+                                descending: !*asc,
+                                nulls_first: *nulls_first,
+                            },
+                            ctx_state,
+                        ),
+                        _ => Err(DataFusionError::Plan(
+                            "Sort only accepts sort expressions".to_string(),
+                        )),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let aggregate = aggregates::create_aggregate_expr(
                     fun,
                     *distinct,
+                    *ignore_nulls,
                     &args,
+                    &order_by,
                     physical_input_schema,
                     name,
-                )
+                )?;
+                // SUM of integers and decimals fails on overflow in the checked modes
+                let checked = ctx_state.config.arithmetic_overflow().is_checked();
+                let sum = aggregate.as_any().downcast_ref::<expressions::Sum>();
+                let aggregate: Arc<dyn AggregateExpr> = match sum {
+                    Some(sum) if checked => {
+                        Arc::new(sum.clone().with_overflow_check(true))
+                    }
+                    _ => aggregate,
+                };
+                // SUM and AVG of floats may be compensated
+                let compensated = ctx_state.config.compensated_float_sum()
+                    && match args.first() {
+                        Some(arg) => matches!(
+                            arg.data_type(physical_input_schema)?,
+                            DataType::Float32 | DataType::Float64
+                        ),
+                        None => false,
+                    };
+                let any = aggregate.as_any();
+                let aggregate: Arc<dyn AggregateExpr> = if !compensated {
+                    aggregate
+                } else if let Some(sum) = any.downcast_ref::<expressions::Sum>() {
+                    Arc::new(sum.clone().with_compensated(true))
+                } else if let Some(avg) = any.downcast_ref::<expressions::Avg>() {
+                    Arc::new(avg.clone().with_compensated(true))
+                } else {
+                    aggregate
+                };
+                match filter {
+                    Some(filter) => {
+                        let predicate = self.create_physical_expr(
+                            filter,
+                            logical_input_schema,
+                            physical_input_schema,
+                            ctx_state,
+                        )?;
+                        let data_type = predicate.data_type(physical_input_schema)?;
+                        if data_type != DataType::Boolean {
+                            return Err(DataFusionError::Plan(format!(
+                                "FILTER predicate of {} must be boolean, got {:?}",
+                                physical_name(e, logical_input_schema)?,
+                                data_type
+                            )));
+                        }
+                        Ok(Arc::new(expressions::AggregateFilter::new(
+                            aggregate, predicate,
+                        )))
+                    }
+                    None => Ok(aggregate),
+                }
             }
             Expr::AggregateUDF { fun, args, .. } => {
                 let args = args
@@ -1557,6 +1890,73 @@ impl DefaultPhysicalPlanner {
         )
     }
 
+    /// Create a physical plan for `LogicalPlan::Sort` that skips the first `skip` rows
+    /// and returns at most `fetch` of the remaining ones.
+    ///
+    /// When the input has several partitions and there is a `fetch`, each partition
+    /// is sorted separately keeping only `skip + fetch` rows and the results are
+    /// merged, so that the whole input is never buffered in one place.
+    fn create_sort_plan(
+        &self,
+        expr: &[Expr],
+        input: &LogicalPlan,
+        skip: usize,
+        fetch: Option<usize>,
+        ctx_state: &ExecutionContextState,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let physical_input = self.create_initial_plan(input, ctx_state)?;
+        let input_schema = physical_input.as_ref().schema();
+        let input_dfschema = input.schema();
+
+        let sort_expr = expr
+            .iter()
+            .map(|e| match e {
+                Expr::Sort {
+                    expr,
+                    asc,
+                    nulls_first,
+                } => self.create_physical_sort_expr(
+                    expr,
+                    input_dfschema,
+                    &input_schema,
+                    SortOptions {
+                        descending: !*asc,
+                        nulls_first: *nulls_first,
+                    },
+                    ctx_state,
+                ),
+                _ => Err(DataFusionError::Plan(
+                    "Sort only accepts sort expressions".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        match fetch {
+            Some(fetch) if physical_input.output_partitioning().partition_count() > 1 => {
+                let sort = SortExec::new_with_partitioning(
+                    sort_expr.clone(),
+                    physical_input,
+                    true,
+                )
+                .with_fetch(Some(skip + fetch));
+                Ok(Arc::new(
+                    SortPreservingMergeExec::new(
+                        sort_expr,
+                        Arc::new(sort),
+                        ctx_state.config.batch_size,
+                    )
+                    .with_skip(skip)
+                    .with_fetch(Some(fetch)),
+                ))
+            }
+            _ => Ok(Arc::new(
+                SortExec::try_new(sort_expr, physical_input)?
+                    .with_skip(skip)
+                    .with_fetch(fetch),
+            )),
+        }
+    }
+
     /// Create a physical sort expression from a logical expression
     pub fn create_physical_sort_expr(
         &self,
@@ -1663,6 +2063,29 @@ pub fn evaluate_const(expr: Arc<dyn PhysicalExpr>) -> Result<Arc<dyn PhysicalExp
     Ok(Arc::new(Literal::new(scalar)))
 }
 
+/// Returns true when `partitioning` hashes the input on a subset of the group keys, which
+/// puts all the rows of each group into the same partition.
+fn is_partitioned_on_groups(
+    partitioning: &Partitioning,
+    groups: &[(Arc<dyn PhysicalExpr>, String)],
+) -> bool {
+    let hash_exprs = match partitioning {
+        Partitioning::Hash(exprs, _) if !exprs.is_empty() => exprs,
+        _ => return false,
+    };
+    let group_columns = groups
+        .iter()
+        .filter_map(|(e, _)| e.as_any().downcast_ref::<Column>())
+        .map(|c| c.index())
+        .collect::<HashSet<_>>();
+    hash_exprs
+        .iter()
+        .all(|e| match e.as_any().downcast_ref::<Column>() {
+            Some(c) => group_columns.contains(&c.index()),
+            None => false,
+        })
+}
+
 /// Returns the most efficient aggregation strategy for the given input.
 pub fn compute_aggregation_strategy(
     input: &dyn ExecutionPlan,
@@ -1746,6 +2169,287 @@ fn input_sorted_by_group_key(
     true
 }
 
+/// Replaces the PERCENTILE_CONT of `aggregates` whose values arrive in ascending order
+/// in each group, according to the output hints of `input` and the sort producing
+/// them, with a [expressions::SortedPercentile] that computes them in a single pass.
+/// APPROX_PERCENTILE_CONT is left alone, its digest does not give the exact result.
+fn with_sorted_percentiles(
+    aggregates: Vec<Arc<dyn AggregateExpr>>,
+    input: &dyn ExecutionPlan,
+    group_key: &[(Arc<dyn PhysicalExpr>, String)],
+) -> Vec<Arc<dyn AggregateExpr>> {
+    aggregates
+        .into_iter()
+        .map(|aggregate| {
+            let fraction = match continuous_percentile_fraction(aggregate.as_ref()) {
+                Some(fraction) => fraction,
+                None => return aggregate,
+            };
+            let expr = aggregate.expressions()[0].clone();
+            if !sorted_in_groups(input, group_key, &expr) {
+                return aggregate;
+            }
+            Arc::new(expressions::SortedPercentile::new(
+                expr,
+                fraction,
+                aggregate.name(),
+            ))
+        })
+        .collect()
+}
+
+/// The fraction of the ascending values of `aggregate`, if it is a PERCENTILE_CONT
+fn continuous_percentile_fraction(aggregate: &dyn AggregateExpr) -> Option<f64> {
+    let percentile = aggregate
+        .as_any()
+        .downcast_ref::<expressions::Percentile>()?;
+    match percentile.percentile_type() {
+        expressions::PercentileType::Continuous if percentile.options().descending => {
+            Some(1.0 - percentile.fraction())
+        }
+        expressions::PercentileType::Continuous => Some(percentile.fraction()),
+        expressions::PercentileType::Discrete => None,
+    }
+}
+
+/// Whether the values of `expr`, a column or an order preserving cast of one, are in
+/// ascending order in each group of `group_key`, which is the case when the sort key
+/// of `input` only has group or single value columns before it and the sort of the
+/// column is known to be ascending
+fn sorted_in_groups(
+    input: &dyn ExecutionPlan,
+    group_key: &[(Arc<dyn PhysicalExpr>, String)],
+    expr: &Arc<dyn PhysicalExpr>,
+) -> bool {
+    let schema = input.schema();
+    let mut expr = expr;
+    while let Some(cast) = expr.as_any().downcast_ref::<expressions::CastExpr>() {
+        match cast.expr().data_type(&schema) {
+            Ok(from) if preserves_order(&from, cast.cast_type()) => expr = cast.expr(),
+            _ => return false,
+        }
+    }
+    let column = match expr.as_any().downcast_ref::<Column>() {
+        Some(column) => column.index(),
+        None => return false,
+    };
+    let hints = input.output_hints();
+    let group_columns = group_key
+        .iter()
+        .filter_map(|(g, _)| g.as_any().downcast_ref::<Column>())
+        .map(|c| c.index())
+        .collect::<Vec<_>>();
+    for i in hints.sort_order.iter().flatten() {
+        if *i == column {
+            return sort_options(input, column).map_or(false, |o| !o.descending);
+        }
+        if !group_columns.contains(i) && !hints.single_value_columns.contains(i) {
+            return false;
+        }
+    }
+    false
+}
+
+/// The options of the sort of the column `column` of `input`, if it is known: the
+/// output hints do not tell the direction, it is found in the [SortExec] ordering the
+/// rows, through the operators that keep its columns and their order
+fn sort_options(input: &dyn ExecutionPlan, column: usize) -> Option<SortOptions> {
+    if let Some(sort) = input.as_any().downcast_ref::<SortExec>() {
+        return sort
+            .expr()
+            .iter()
+            .find(|s| {
+                let c = s.expr.as_any().downcast_ref::<Column>();
+                c.map_or(false, |c| c.index() == column)
+            })
+            .map(|s| s.options);
+    }
+    match input.children().as_slice() {
+        [child]
+            if child.schema() == input.schema()
+                && child.output_hints().sort_order == input.output_hints().sort_order =>
+        {
+            sort_options(child.as_ref(), column)
+        }
+        _ => None,
+    }
+}
+
+/// Whether casting from `from` to `to` keeps the order of the values, the casts of
+/// numbers to floats. Casts of strings to numbers, for example, do not.
+fn preserves_order(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+    match (from, to) {
+        (from, to) if from == to => true,
+        (
+            Int8 | Int16 | Int32 | Int64 | Int96 | UInt8 | UInt16 | UInt32 | UInt64
+            | Float32 | Int64Decimal(_) | Int96Decimal(_),
+            Float64,
+        ) => true,
+        _ => false,
+    }
+}
+
+/// The types the sort columns of `inputs` are cast to, so that the rows of all inputs
+/// can be compared when merging them. Only widening casts, which keep the order of
+/// the values, are used. Returns an error if the types of a column can not be compared.
+fn sort_column_types(inputs: &[(SchemaRef, Vec<Column>)]) -> Result<Vec<DataType>> {
+    let (first_schema, first_columns) = &inputs[0];
+    first_columns
+        .iter()
+        .enumerate()
+        .map(|(k, c)| {
+            let mut target_type = first_schema.field(c.index()).data_type().clone();
+            for (schema, columns) in &inputs[1..] {
+                let input_type = schema.field(columns[k].index()).data_type().clone();
+                target_type =
+                    union_coercion(&target_type, &input_type).ok_or_else(|| {
+                        DataFusionError::Plan(format!(
+                            "Cannot order by column {} of types {:?} and {:?}",
+                            c.name(),
+                            target_type,
+                            input_type
+                        ))
+                    })?;
+            }
+            Ok(target_type)
+        })
+        .collect()
+}
+
+/// Casts the sort `columns` of `inputs` to a common type, see [sort_column_types]
+fn coerce_sort_columns(
+    inputs: Vec<Arc<dyn ExecutionPlan>>,
+    columns: &[Column],
+) -> Result<Vec<Arc<dyn ExecutionPlan>>> {
+    let target_types = sort_column_types(
+        &inputs
+            .iter()
+            .map(|input| (input.schema(), columns.to_vec()))
+            .collect::<Vec<_>>(),
+    )?;
+
+    inputs
+        .into_iter()
+        .map(|input| {
+            let schema = input.schema();
+            let needs_cast = columns
+                .iter()
+                .zip(target_types.iter())
+                .any(|(c, t)| schema.field(c.index()).data_type() != t);
+            if !needs_cast {
+                return Ok(input);
+            }
+            let expr = schema
+                .fields()
+                .iter()
+                .enumerate()
+                .map(|(i, f)| {
+                    let column: Arc<dyn PhysicalExpr> =
+                        Arc::new(Column::new(f.name(), i));
+                    let expr = match columns.iter().position(|c| c.index() == i) {
+                        Some(k) => {
+                            expressions::cast(column, &schema, target_types[k].clone())?
+                        }
+                        None => column,
+                    };
+                    Ok((expr, f.name().to_string()))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Arc::new(ProjectionExec::try_new(expr, input)?) as Arc<dyn ExecutionPlan>)
+        })
+        .collect()
+}
+
+/// Creates a merge join of the sorted `left` and `right` inputs. Keys of different
+/// types are cast to a common type, see [sort_column_types], in additional columns
+/// that are removed after the join, so that the output keeps the types of the inputs.
+fn merge_join_with_coerced_keys(
+    left: Arc<dyn ExecutionPlan>,
+    right: Arc<dyn ExecutionPlan>,
+    on: &hash_utils::JoinOn,
+    join_type: &JoinType,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let (left_keys, right_keys): (Vec<Column>, Vec<Column>) = on.iter().cloned().unzip();
+    let left_schema = left.schema();
+    let right_schema = right.schema();
+    let types = sort_column_types(&[
+        (left_schema.clone(), left_keys.clone()),
+        (right_schema.clone(), right_keys.clone()),
+    ])?;
+    let has_type = |schema: &SchemaRef, keys: &[Column]| {
+        keys.iter()
+            .zip(types.iter())
+            .all(|(c, t)| schema.field(c.index()).data_type() == t)
+    };
+    if has_type(&left_schema, &left_keys) && has_type(&right_schema, &right_keys) {
+        return Ok(Arc::new(MergeJoinExec::try_new(
+            left, right, on, join_type,
+        )?));
+    }
+
+    let (left, left_keys) = with_cast_keys(left, &left_keys, &types)?;
+    let (right, right_keys) = with_cast_keys(right, &right_keys, &types)?;
+    let right_offset = left.schema().fields().len();
+    let on = left_keys.into_iter().zip(right_keys).collect::<Vec<_>>();
+    let join: Arc<dyn ExecutionPlan> =
+        Arc::new(MergeJoinExec::try_new(left, right, &on, join_type)?);
+
+    let join_schema = join.schema();
+    let mut columns = (0..left_schema.fields().len()).collect::<Vec<_>>();
+    // semi and anti joins only return the columns of the left input
+    if join_schema.fields().len() > right_offset {
+        columns.extend(right_offset..right_offset + right_schema.fields().len());
+    }
+    let expr = columns
+        .into_iter()
+        .map(|i| {
+            let name = join_schema.field(i).name();
+            (
+                Arc::new(Column::new(name, i)) as Arc<dyn PhysicalExpr>,
+                name.to_string(),
+            )
+        })
+        .collect();
+    Ok(Arc::new(ProjectionExec::try_new(expr, join)?))
+}
+
+/// Appends the `keys` of `input` that are not of the key `types` as columns cast to
+/// them. Returns the new input with the columns of all keys.
+fn with_cast_keys(
+    input: Arc<dyn ExecutionPlan>,
+    keys: &[Column],
+    types: &[DataType],
+) -> Result<(Arc<dyn ExecutionPlan>, Vec<Column>)> {
+    let schema = input.schema();
+    let mut expr = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            (
+                Arc::new(Column::new(f.name(), i)) as Arc<dyn PhysicalExpr>,
+                f.name().to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut cast_keys = Vec::with_capacity(keys.len());
+    for (k, (key, data_type)) in keys.iter().zip(types.iter()).enumerate() {
+        if schema.field(key.index()).data_type() == data_type {
+            cast_keys.push(key.clone());
+            continue;
+        }
+        let name = format!("__merge_join_key_{}", k);
+        cast_keys.push(Column::new(&name, expr.len()));
+        let column = Arc::new(key.clone());
+        expr.push((expressions::cast(column, &schema, data_type.clone())?, name));
+    }
+    if cast_keys.iter().zip(keys.iter()).all(|(c, k)| c == k) {
+        return Ok((input, cast_keys));
+    }
+    Ok((Arc::new(ProjectionExec::try_new(expr, input)?), cast_keys))
+}
+
 fn tuple_err<T, R>(value: (Result<T>, Result<R>)) -> Result<(T, R)> {
     match value {
         (Ok(e), Ok(e1)) => Ok((e, e1)),
@@ -1758,12 +2462,15 @@ fn tuple_err<T, R>(value: (Result<T>, Result<R>)) -> Result<(T, R)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution::context::ExecutionConfig;
     use crate::logical_plan::{and, DFField, DFSchema, DFSchemaRef};
-    use crate::physical_plan::OptimizerHints;
+    use crate::physical_optimizer::cost::{CostModel, DefaultCostModel};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::{common, OptimizerHints};
     use crate::physical_plan::{csv::CsvReadOptions, expressions, Partitioning};
     use crate::scalar::ScalarValue;
     use crate::{
-        logical_plan::{col, lit, sum, LogicalPlanBuilder},
+        logical_plan::{col, lit, percentile_cont, sum, LogicalPlanBuilder},
         physical_plan::SendableRecordBatchStream,
     };
     use arrow::datatypes::{DataType, Field, SchemaRef};
@@ -1999,6 +2706,92 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn coerce_sort_columns_of_union_inputs() -> Result<()> {
+        let input = |data_type: DataType| -> Result<Arc<dyn ExecutionPlan>> {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("a", data_type, true),
+                Field::new("b", DataType::Utf8, true),
+            ]));
+            Ok(Arc::new(MemoryExec::try_new(&[], schema, None)?))
+        };
+        let columns = vec![Column::new("a", 0)];
+
+        let inputs = coerce_sort_columns(
+            vec![input(DataType::Int32)?, input(DataType::Int64)?],
+            &columns,
+        )?;
+        for input in &inputs {
+            assert_eq!(input.schema().field(0).data_type(), &DataType::Int64);
+            assert_eq!(input.schema().field(1).data_type(), &DataType::Utf8);
+        }
+        assert!(inputs[0]
+            .as_any()
+            .downcast_ref::<ProjectionExec>()
+            .is_some());
+        assert!(inputs[1].as_any().downcast_ref::<MemoryExec>().is_some());
+
+        let dictionary =
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let inputs = coerce_sort_columns(
+            vec![input(DataType::Utf8)?, input(dictionary)?],
+            &columns,
+        )?;
+        assert_eq!(inputs[1].schema().field(0).data_type(), &DataType::Utf8);
+
+        let err = coerce_sort_columns(
+            vec![input(DataType::Int32)?, input(DataType::Binary)?],
+            &columns,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Cannot order by column a"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn merge_join_keys_of_different_types() -> Result<()> {
+        let input = |name: &str,
+                     data_type: DataType,
+                     values: ArrayRef|
+         -> Result<Arc<dyn ExecutionPlan>> {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new(name, data_type, false),
+                Field::new(&format!("{}_name", name), DataType::Utf8, false),
+            ]));
+            let names = Arc::new(StringArray::from(vec!["x"; values.len()]));
+            let batch = RecordBatch::try_new(schema.clone(), vec![values, names])?;
+            Ok(Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None)?))
+        };
+        let left = input(
+            "a",
+            DataType::Int32,
+            Arc::new(Int32Array::from(vec![1, 2, 4])),
+        )?;
+        let right = input(
+            "b",
+            DataType::Int64,
+            Arc::new(Int64Array::from(vec![2, 3, 4])),
+        )?;
+        let on = vec![(Column::new("a", 0), Column::new("b", 0))];
+
+        let join = merge_join_with_coerced_keys(left, right, &on, &JoinType::Inner)?;
+        let schema = join.schema();
+        assert_eq!(schema.fields().len(), 4);
+        assert_eq!(schema.field(0).data_type(), &DataType::Int32);
+        assert_eq!(schema.field(2).data_type(), &DataType::Int64);
+        let batches = common::collect(join.execute(0).await?).await?;
+        let expected = vec![
+            "+---+--------+---+--------+",
+            "| a | a_name | b | b_name |",
+            "+---+--------+---+--------+",
+            "| 2 | x      | 2 | x      |",
+            "| 4 | x      | 4 | x      |",
+            "+---+--------+---+--------+",
+        ];
+        crate::assert_batches_eq!(expected, &batches);
+        Ok(())
+    }
+
     #[test]
     fn hash_agg_input_schema() -> Result<()> {
         let testdata = crate::test_util::arrow_test_data();
@@ -2042,6 +2835,86 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn cost_model_chooses_among_valid_aggregate_strategies() -> Result<()> {
+        #[derive(Debug)]
+        struct AlwaysSorted {}
+        impl CostModel for AlwaysSorted {
+            fn swap_join_inputs(
+                &self,
+                left: &dyn ExecutionPlan,
+                right: &dyn ExecutionPlan,
+            ) -> bool {
+                DefaultCostModel::new().swap_join_inputs(left, right)
+            }
+
+            fn collect_build_side(
+                &self,
+                build: &dyn ExecutionPlan,
+                config: &ExecutionConfig,
+            ) -> bool {
+                DefaultCostModel::new().collect_build_side(build, config)
+            }
+
+            fn repartition(
+                &self,
+                plan: &dyn ExecutionPlan,
+                config: &ExecutionConfig,
+            ) -> bool {
+                DefaultCostModel::new().repartition(plan, config)
+            }
+
+            fn aggregate_strategy(
+                &self,
+                _: &dyn ExecutionPlan,
+                _: &[(Arc<dyn PhysicalExpr>, String)],
+                _: &[AggregateStrategy],
+            ) -> AggregateStrategy {
+                AggregateStrategy::InplaceSorted
+            }
+        }
+
+        let testdata = crate::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let logical_plan = LogicalPlanBuilder::scan_csv(path, options, None)?
+            .aggregate(vec![col("c1")], vec![sum(col("c2"))])?
+            .build()?;
+
+        let mut ctx_state = make_ctx_state();
+        ctx_state.config = ctx_state.config.with_cost_model(Arc::new(AlwaysSorted {}));
+        let err = DefaultPhysicalPlanner::default()
+            .create_physical_plan(&logical_plan, &ctx_state)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("The cost model chose the InplaceSorted aggregation strategy"));
+        Ok(())
+    }
+
+    #[test]
+    fn hash_agg_on_input_partitioned_by_group_keys() -> Result<()> {
+        let testdata = crate::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let logical_plan = LogicalPlanBuilder::scan_csv(path, options, None)?
+            .repartition(LogicalPartitioning::Hash(vec![col("c1")], 4))?
+            .aggregate(vec![col("c1"), col("c2")], vec![sum(col("c3"))])?
+            .build()?;
+
+        let execution_plan = plan(&logical_plan)?;
+        let hash_agg = execution_plan
+            .as_any()
+            .downcast_ref::<HashAggregateExec>()
+            .expect("hash aggregate");
+        assert_eq!(AggregateMode::Full, *hash_agg.mode());
+        assert!(hash_agg.input().as_any().is::<RepartitionExec>());
+        assert_eq!(4, execution_plan.output_partitioning().partition_count());
+
+        Ok(())
+    }
+
     #[test]
     fn hash_agg_aggregation_strategy_with_nongrouped_single_value_columns_in_sort_key(
     ) -> Result<()> {
@@ -2108,6 +2981,71 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn sorted_percentiles() -> Result<()> {
+        let testdata = crate::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let approx_percentile_cont = |expr: Expr| Expr::AggregateFunction {
+            fun: aggregates::AggregateFunction::ApproxPercentileCont,
+            distinct: false,
+            ignore_nulls: false,
+            args: vec![expr, lit(0.25)],
+            order_by: vec![],
+            filter: None,
+        };
+        let logical_plan = LogicalPlanBuilder::scan_csv(&path, options, None)?
+            .sort(vec![col("c1").sort(true, true), col("c3").sort(true, true)])?
+            .aggregate(
+                vec![col("c1")],
+                vec![
+                    approx_percentile_cont(col("c3")),
+                    percentile_cont(lit(0.25), col("c3").sort(false, true)),
+                    approx_percentile_cont(col("c4")),
+                ],
+            )?
+            .build()?;
+
+        let execution_plan = plan(&logical_plan)?;
+        let hash_agg = execution_plan
+            .as_any()
+            .downcast_ref::<HashAggregateExec>()
+            .expect("hash aggregate");
+        let aggr_expr = hash_agg.aggr_expr();
+        // the values of c3 are sorted in each group of c1, not the ones of c4, and the
+        // approximate percentiles are kept
+        assert!(aggr_expr[0].as_any().is::<expressions::ApproxPercentile>());
+        assert!(aggr_expr[1].as_any().is::<expressions::SortedPercentile>());
+        assert!(aggr_expr[2].as_any().is::<expressions::ApproxPercentile>());
+
+        // the values of c3 are in descending order in each group
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let logical_plan = LogicalPlanBuilder::scan_csv(path, options, None)?
+            .sort(vec![
+                col("c1").sort(true, true),
+                col("c3").sort(false, true),
+            ])?
+            .aggregate(
+                vec![col("c1")],
+                vec![percentile_cont(lit(0.25), col("c3").sort(true, true))],
+            )?
+            .build()?;
+        let execution_plan = plan(&logical_plan)?;
+        let hash_agg = execution_plan
+            .as_any()
+            .downcast_ref::<HashAggregateExec>()
+            .expect("hash aggregate");
+        assert!(hash_agg.aggr_expr()[0]
+            .as_any()
+            .is::<expressions::Percentile>());
+
+        assert!(preserves_order(&DataType::Int32, &DataType::Float64));
+        assert!(!preserves_order(&DataType::Utf8, &DataType::Float64));
+        assert!(!preserves_order(&DataType::Float64, &DataType::Int32));
+        Ok(())
+    }
+
     #[test]
     fn test_explain() {
         let schema = Schema::new(vec![Field::new("id", DataType::Int32, false)]);
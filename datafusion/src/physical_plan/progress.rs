@@ -0,0 +1,437 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Reports the progress of the execution of a physical plan, the rows produced by each
+//! of its operators and the partitions they completed, while it is executed.
+//!
+//! The counters of the operators are always up to date, while the events are only
+//! kept until the next one: a consumer that does not keep up with them only receives
+//! the latest event, and one that only reads the counters does not buffer any.
+//!
+//! ```no_run
+//! # use datafusion::error::Result;
+//! # use datafusion::physical_plan::{collect, progress, ExecutionPlan};
+//! # use futures::StreamExt;
+//! # use std::sync::Arc;
+//! # async fn f(plan: Arc<dyn ExecutionPlan>) -> Result<()> {
+//! let (plan, mut progress) = progress::instrument(plan)?;
+//! tokio::spawn(async move {
+//!     while let Some(event) = progress.next().await {
+//!         println!("{:?}, {:.0}% done", event, 100.0 * progress.completed_fraction());
+//!     }
+//! });
+//! let batches = collect(plan).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::any::Any;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::datasource::datasource::Statistics;
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{
+    DisplayFormatType, ExecutionPlan, OptimizerHints, Partitioning, RecordBatchStream,
+    SQLMetric, SendableRecordBatchStream,
+};
+
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use tokio::sync::watch;
+
+/// An event about the progress of an operator of an instrumented plan. Operators are
+/// identified by their index in [ProgressReceiver::operators].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// A partition of an operator produced a batch of `rows` rows
+    BatchProduced {
+        /// The index of the operator
+        operator: usize,
+        /// The partition of the operator that produced the batch
+        partition: usize,
+        /// The number of rows of the batch
+        rows: usize,
+    },
+    /// A partition of an operator produced all of its batches
+    PartitionCompleted {
+        /// The index of the operator
+        operator: usize,
+        /// The completed partition
+        partition: usize,
+    },
+}
+
+/// The progress of an operator of an instrumented plan, updated while it is executed
+#[derive(Debug)]
+pub struct OperatorProgress {
+    description: String,
+    depth: usize,
+    partitions: usize,
+    output_rows: Arc<SQLMetric>,
+    completed_partitions: Arc<SQLMetric>,
+}
+
+impl OperatorProgress {
+    /// The description of the operator, as displayed by `EXPLAIN`
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The depth of the operator in the plan, 0 for its root
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The number of output partitions of the operator
+    pub fn partitions(&self) -> usize {
+        self.partitions
+    }
+
+    /// The number of rows produced by the operator so far
+    pub fn output_rows(&self) -> usize {
+        self.output_rows.value()
+    }
+
+    /// The number of partitions of the operator that produced all of their batches
+    pub fn completed_partitions(&self) -> usize {
+        self.completed_partitions.value()
+    }
+}
+
+/// Receives the progress of the execution of a plan instrumented by [instrument]. As a
+/// [Stream] of [ProgressEvent], it returns the latest event when a new one occurred
+/// since the last poll, skipping the events in between, and it ends when the
+/// instrumented plan and the streams of its partitions are dropped.
+pub struct ProgressReceiver {
+    operators: Vec<OperatorProgress>,
+    events: BoxStream<'static, ProgressEvent>,
+}
+
+impl ProgressReceiver {
+    /// The operators of the instrumented plan, in the order of a depth first walk of the
+    /// plan starting from its root
+    pub fn operators(&self) -> &[OperatorProgress] {
+        &self.operators
+    }
+
+    /// The fraction of the partitions of all the operators that are completed, between
+    /// 0 and 1. Operators are weighted by their number of partitions only, as the number
+    /// of rows they produce is not known in advance.
+    pub fn completed_fraction(&self) -> f64 {
+        let (completed, total) =
+            self.operators.iter().fold((0, 0), |(completed, total), o| {
+                // some operators execute the partitions of their input more than once
+                let completed_partitions = o.completed_partitions().min(o.partitions);
+                (completed + completed_partitions, total + o.partitions)
+            });
+        if total == 0 {
+            1.0
+        } else {
+            completed as f64 / total as f64
+        }
+    }
+}
+
+impl Stream for ProgressReceiver {
+    type Item = ProgressEvent;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+impl fmt::Debug for ProgressReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressReceiver")
+            .field("operators", &self.operators)
+            .finish()
+    }
+}
+
+/// Returns `plan` instrumented to report the progress of each of its operators to the
+/// returned [ProgressReceiver] when it is executed. The instrumented plan should not be
+/// optimized further, as each of its operators is wrapped in a node reporting its
+/// progress.
+///
+/// The execution is cancelled by dropping the streams of its partitions, for instance
+/// when the progress shows it will take too long.
+pub fn instrument(
+    plan: Arc<dyn ExecutionPlan>,
+) -> Result<(Arc<dyn ExecutionPlan>, ProgressReceiver)> {
+    let (sender, receiver) = watch::channel(None);
+    let mut operators = Vec::new();
+    let plan = instrument_plan(plan, 0, &mut operators, &Arc::new(sender))?;
+    // the channel only keeps the latest event
+    let events = futures::stream::unfold(receiver, |mut receiver| async move {
+        receiver.changed().await.ok()?;
+        let event = receiver.borrow().clone()?;
+        Some((event, receiver))
+    });
+    Ok((
+        plan,
+        ProgressReceiver {
+            operators,
+            events: events.boxed(),
+        },
+    ))
+}
+
+type ProgressSender = Arc<watch::Sender<Option<ProgressEvent>>>;
+
+fn instrument_plan(
+    plan: Arc<dyn ExecutionPlan>,
+    depth: usize,
+    operators: &mut Vec<OperatorProgress>,
+    sender: &ProgressSender,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let operator = operators.len();
+    let output_rows = SQLMetric::counter();
+    let completed_partitions = SQLMetric::counter();
+    operators.push(OperatorProgress {
+        description: OneLine(plan.as_ref()).to_string(),
+        depth,
+        partitions: plan.output_partitioning().partition_count(),
+        output_rows: output_rows.clone(),
+        completed_partitions: completed_partitions.clone(),
+    });
+
+    let children = plan
+        .children()
+        .into_iter()
+        .map(|child| instrument_plan(child, depth + 1, operators, sender))
+        .collect::<Result<Vec<_>>>()?;
+    let input = if children.is_empty() {
+        plan
+    } else {
+        plan.with_new_children(children)?
+    };
+    Ok(Arc::new(ProgressExec {
+        input,
+        operator,
+        output_rows,
+        completed_partitions,
+        sender: sender.clone(),
+    }))
+}
+
+/// Displays an operator without its children
+struct OneLine<'a>(&'a dyn ExecutionPlan);
+
+impl<'a> fmt::Display for OneLine<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_as(DisplayFormatType::Default, f)
+    }
+}
+
+/// Reports the progress of its input, which it returns unchanged
+#[derive(Debug)]
+struct ProgressExec {
+    input: Arc<dyn ExecutionPlan>,
+    operator: usize,
+    output_rows: Arc<SQLMetric>,
+    completed_partitions: Arc<SQLMetric>,
+    sender: ProgressSender,
+}
+
+#[async_trait]
+impl ExecutionPlan for ProgressExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(ProgressExec {
+                input: children[0].clone(),
+                operator: self.operator,
+                output_rows: self.output_rows.clone(),
+                completed_partitions: self.completed_partitions.clone(),
+                sender: self.sender.clone(),
+            })),
+            _ => Err(DataFusionError::Internal(
+                "ProgressExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn output_hints(&self) -> OptimizerHints {
+        self.input.output_hints()
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
+    }
+
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        Ok(Box::pin(ProgressStream {
+            input: self.input.execute(partition).await?,
+            operator: self.operator,
+            partition,
+            output_rows: self.output_rows.clone(),
+            completed_partitions: self.completed_partitions.clone(),
+            sender: self.sender.clone(),
+            is_completed: false,
+        }))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(f, "ProgressExec: operator={}", self.operator)
+            }
+        }
+    }
+}
+
+struct ProgressStream {
+    input: SendableRecordBatchStream,
+    operator: usize,
+    partition: usize,
+    output_rows: Arc<SQLMetric>,
+    completed_partitions: Arc<SQLMetric>,
+    sender: ProgressSender,
+    is_completed: bool,
+}
+
+impl ProgressStream {
+    fn report(&self, event: ProgressEvent) {
+        // the progress is not needed anymore when the receiver is dropped
+        self.sender.send(Some(event)).ok();
+    }
+}
+
+impl Stream for ProgressStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let poll = self.input.poll_next_unpin(cx);
+        match &poll {
+            Poll::Ready(Some(Ok(batch))) => {
+                self.output_rows.add(batch.num_rows());
+                self.report(ProgressEvent::BatchProduced {
+                    operator: self.operator,
+                    partition: self.partition,
+                    rows: batch.num_rows(),
+                });
+            }
+            Poll::Ready(None) if !self.is_completed => {
+                self.is_completed = true;
+                self.completed_partitions.add(1);
+                self.report(ProgressEvent::PartitionCompleted {
+                    operator: self.operator,
+                    partition: self.partition,
+                });
+            }
+            _ => {}
+        }
+        poll
+    }
+}
+
+impl RecordBatchStream for ProgressStream {
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::memory::MemoryExec;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    fn batch(schema: &SchemaRef, values: Vec<i32>) -> Result<RecordBatch> {
+        Ok(RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(values))],
+        )?)
+    }
+
+    #[tokio::test]
+    async fn report_progress() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let partitions = vec![
+            vec![batch(&schema, vec![1, 2])?, batch(&schema, vec![3])?],
+            vec![batch(&schema, vec![4, 5, 6])?],
+        ];
+        let memory = MemoryExec::try_new(&partitions, schema, None)?;
+        let plan = Arc::new(CoalescePartitionsExec::new(Arc::new(memory)));
+
+        let (plan, progress) = instrument(plan)?;
+        let descriptions = progress
+            .operators()
+            .iter()
+            .map(|o| (o.description(), o.depth(), o.partitions()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            descriptions,
+            vec![
+                ("CoalescePartitionsExec", 0, 1),
+                ("MemoryExec: partitions=2, partition_sizes=[2, 1]", 1, 2)
+            ]
+        );
+        assert_eq!(progress.completed_fraction(), 0.0);
+
+        let batches = collect(plan).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 6);
+        assert_eq!(progress.operators()[0].output_rows(), 6);
+        assert_eq!(progress.operators()[1].output_rows(), 6);
+        assert_eq!(progress.operators()[1].completed_partitions(), 2);
+        assert_eq!(progress.completed_fraction(), 1.0);
+
+        // the events that were not received in time are skipped
+        let events = progress.collect::<Vec<_>>().await;
+        assert_eq!(
+            events,
+            vec![ProgressEvent::PartitionCompleted {
+                operator: 0,
+                partition: 0
+            }]
+        );
+        Ok(())
+    }
+}
@@ -25,6 +25,7 @@ use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+use crate::datasource::datasource::Statistics;
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{
     DisplayFormatType, ExecutionPlan, OptimizerHints, Partitioning, PhysicalExpr,
@@ -33,10 +34,13 @@ use arrow::datatypes::{Field, Schema, SchemaRef};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 
+use super::coalesce_batches::coalesce_stream;
+use super::selection::{
+    coalesced_filter, execute_selected, SelectedBatch, SendableSelectedBatchStream,
+};
 use super::{RecordBatchStream, SendableRecordBatchStream};
 use async_trait::async_trait;
 
-use crate::physical_plan::expressions::Column;
 use futures::stream::Stream;
 use futures::stream::StreamExt;
 
@@ -108,7 +112,7 @@ impl ExecutionPlan for ProjectionExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        self.input.output_partitioning()
+        self.input.output_partitioning().project(&self.expr)
     }
 
     fn with_new_children(
@@ -127,56 +131,26 @@ impl ExecutionPlan for ProjectionExec {
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        Ok(Box::pin(ProjectionStream {
+        let stream = Box::pin(ProjectionStream {
             schema: self.schema.clone(),
             expr: self.expr.iter().map(|x| x.0.clone()).collect(),
-            input: self.input.execute(partition).await?,
-        }))
+            input: execute_selected(&self.input, partition).await?,
+        });
+        // the filter below is evaluated here, so its batches are coalesced after projection
+        Ok(match coalesced_filter(&self.input) {
+            Some(target_batch_size) => coalesce_stream(stream, target_batch_size),
+            None => stream,
+        })
     }
 
     fn output_hints(&self) -> OptimizerHints {
-        let input_hints = self.input.output_hints();
-        if input_hints == OptimizerHints::default() {
-            return OptimizerHints::default();
-        }
-
-        let input_schema = self.input.schema();
-        let mut input_to_output = vec![None; input_schema.fields().len()];
-        for out_i in 0..self.expr.len() {
-            let column;
-            if let Some(c) = self.expr[out_i].0.as_any().downcast_ref::<Column>() {
-                column = c;
-            } else {
-                continue;
-            }
-            input_to_output[column.index()] = Some(out_i);
-        }
+        self.input.output_hints().project(&self.expr)
+    }
 
-        let single_value_columns = input_hints
-            .single_value_columns
-            .iter()
-            .filter_map(|i| input_to_output[*i])
-            .collect();
-        let mut sort_order = Vec::new();
-        if let Some(in_so) = input_hints.sort_order {
-            for in_col in in_so {
-                if let Some(out_col) = input_to_output[in_col] {
-                    sort_order.push(out_col);
-                } else if input_hints.single_value_columns.contains(&in_col) {
-                    continue;
-                } else {
-                    break;
-                }
-            }
-        };
-
-        OptimizerHints {
-            single_value_columns,
-            sort_order: if sort_order.is_empty() {
-                None
-            } else {
-                Some(sort_order)
-            },
+    fn statistics(&self) -> Statistics {
+        Statistics {
+            num_rows: self.input.statistics().num_rows,
+            ..Statistics::default()
         }
     }
 
@@ -207,14 +181,13 @@ impl ExecutionPlan for ProjectionExec {
 }
 
 fn batch_project(
-    batch: &RecordBatch,
+    batch: &SelectedBatch,
     expressions: &[Arc<dyn PhysicalExpr>],
     schema: &SchemaRef,
 ) -> ArrowResult<RecordBatch> {
     expressions
         .iter()
-        .map(|expr| expr.evaluate(batch))
-        .map(|r| r.map(|v| v.into_array(batch.num_rows())))
+        .map(|expr| batch.evaluate(expr.as_ref()))
         .collect::<Result<Vec<_>>>()
         .map_or_else(
             |e| Err(DataFusionError::into_arrow_external_error(e)),
@@ -226,7 +199,7 @@ fn batch_project(
 struct ProjectionStream {
     schema: SchemaRef,
     expr: Vec<Arc<dyn PhysicalExpr>>,
-    input: SendableRecordBatchStream,
+    input: SendableSelectedBatchStream,
 }
 
 impl Stream for ProjectionStream {
@@ -238,7 +211,8 @@ impl Stream for ProjectionStream {
     ) -> Poll<Option<Self::Item>> {
         self.input.poll_next_unpin(cx).map(|x| match x {
             Some(Ok(batch)) => Some(batch_project(&batch, &self.expr, &self.schema)),
-            other => other,
+            Some(Err(e)) => Some(Err(e)),
+            None => None,
         })
     }
 
@@ -24,6 +24,7 @@ use std::task::{Context, Poll};
 use std::time::Instant;
 use std::{any::Any, vec};
 
+use crate::datasource::datasource::Statistics;
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{DisplayFormatType, ExecutionPlan, Partitioning, SQLMetric};
 use arrow::record_batch::RecordBatch;
@@ -31,7 +32,7 @@ use arrow::{array::Array, error::Result as ArrowResult};
 use arrow::{compute::take, datatypes::SchemaRef};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
-use super::{hash_join::create_hashes, RecordBatchStream, SendableRecordBatchStream};
+use super::{hash_utils::create_hashes, RecordBatchStream, SendableRecordBatchStream};
 use async_trait::async_trait;
 
 use futures::stream::Stream;
@@ -200,6 +201,10 @@ impl ExecutionPlan for RepartitionExec {
         }))
     }
 
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
+    }
+
     fn metrics(&self) -> HashMap<String, SQLMetric> {
         self.metrics.to_hashmap()
     }
@@ -0,0 +1,280 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Selection vectors let the operators on top of a [`FilterExec`] consume its input
+//! batches together with the rows the predicate keeps, instead of a filtered copy of every
+//! column. Expressions are evaluated on the selected rows of the columns they read only,
+//! so that they neither waste work on the discarded rows nor fail on them, e.g. with a
+//! division by zero that the filter guards against. A batch is compacted right away when
+//! the predicate keeps few of its rows, as every expression would then copy them.
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, BooleanArray};
+use arrow::compute::filter_record_batch;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use futures::{Stream, StreamExt};
+
+use super::coalesce_batches::CoalesceBatchesExec;
+use super::filter::FilterExec;
+use super::{filter_columns, ColumnarValue, ExecutionPlan, PhysicalExpr};
+use crate::error::{DataFusionError, Result};
+use crate::scalar::ScalarValue;
+
+/// The fraction of the rows of a batch below which [`SelectedBatch::filter`] compacts it
+pub const COMPACT_SELECTIVITY: f64 = 0.5;
+
+/// A record batch with a selection vector, which marks the rows of the batch that are
+/// part of the result. Without a selection vector, all the rows are.
+#[derive(Debug, Clone)]
+pub struct SelectedBatch {
+    batch: RecordBatch,
+    selection: Option<BooleanArray>,
+    num_selected: usize,
+}
+
+impl SelectedBatch {
+    /// Create a batch with all of its rows selected
+    pub fn new(batch: RecordBatch) -> Self {
+        let num_selected = batch.num_rows();
+        Self {
+            batch,
+            selection: None,
+            num_selected,
+        }
+    }
+
+    /// The underlying batch, including the rows that are not selected
+    pub fn batch(&self) -> &RecordBatch {
+        &self.batch
+    }
+
+    /// The selection vector, which has no nulls. `None` when every row is selected.
+    pub fn selection(&self) -> Option<&BooleanArray> {
+        self.selection.as_ref()
+    }
+
+    /// The number of selected rows
+    pub fn num_rows(&self) -> usize {
+        self.num_selected
+    }
+
+    /// Deselect the rows for which `predicate` is not true. The predicate is only
+    /// evaluated for the rows that are still selected.
+    pub fn filter(self, predicate: &dyn PhysicalExpr) -> Result<Self> {
+        let num_rows = self.batch.num_rows();
+        let mask = match &self.selection {
+            None => predicate.evaluate(&self.batch)?,
+            Some(selection) => predicate.evaluate_selection(&self.batch, selection)?,
+        };
+        let selection = match mask {
+            ColumnarValue::Scalar(ScalarValue::Boolean(Some(true))) => return Ok(self),
+            ColumnarValue::Scalar(ScalarValue::Boolean(_)) => vec![false; num_rows],
+            ColumnarValue::Array(mask) => {
+                let mask = mask
+                    .as_any()
+                    .downcast_ref::<BooleanArray>()
+                    .ok_or_else(non_boolean_predicate)?;
+                (0..num_rows)
+                    .map(|i| {
+                        mask.is_valid(i)
+                            && mask.value(i)
+                            && self.selection.as_ref().map_or(true, |s| s.value(i))
+                    })
+                    .collect()
+            }
+            ColumnarValue::Scalar(_) => return Err(non_boolean_predicate()),
+        };
+
+        let num_selected = selection.iter().filter(|s| **s).count();
+        let selection = BooleanArray::from(selection);
+        if num_selected == num_rows {
+            Ok(Self::new(self.batch))
+        } else if (num_selected as f64) < COMPACT_SELECTIVITY * num_rows as f64 {
+            Ok(Self::new(filter_record_batch(&self.batch, &selection)?))
+        } else {
+            Ok(Self {
+                batch: self.batch,
+                selection: Some(selection),
+                num_selected,
+            })
+        }
+    }
+
+    /// Evaluate `expr` for the selected rows. The rows that are not selected are not
+    /// evaluated.
+    pub fn evaluate(&self, expr: &dyn PhysicalExpr) -> Result<ArrayRef> {
+        match &self.selection {
+            None => Ok(expr
+                .evaluate(&self.batch)?
+                .into_array(self.batch.num_rows())),
+            Some(selection) => {
+                let selected = filter_columns(
+                    &self.batch,
+                    selection,
+                    expr.column_indices().as_deref(),
+                )?;
+                Ok(expr.evaluate(&selected)?.into_array(self.num_selected))
+            }
+        }
+    }
+
+    /// Materialize the selected rows
+    pub fn into_batch(self) -> Result<RecordBatch> {
+        match &self.selection {
+            None => Ok(self.batch),
+            Some(selection) => Ok(filter_record_batch(&self.batch, selection)?),
+        }
+    }
+}
+
+fn non_boolean_predicate() -> DataFusionError {
+    DataFusionError::Internal(
+        "Filter predicate evaluated to non-boolean value".to_string(),
+    )
+}
+
+/// Trait for a stream of batches with selection vectors
+pub type SendableSelectedBatchStream =
+    Pin<Box<dyn Stream<Item = ArrowResult<SelectedBatch>> + Send>>;
+
+/// Returns the target batch size of `plan` when it coalesces the output of a filter.
+/// The filtered batches need no coalescing when the filter is deferred.
+pub(crate) fn coalesced_filter(plan: &Arc<dyn ExecutionPlan>) -> Option<usize> {
+    let coalesce = plan.as_any().downcast_ref::<CoalesceBatchesExec>()?;
+    if coalesce.input().as_any().is::<FilterExec>() {
+        Some(coalesce.target_batch_size())
+    } else {
+        None
+    }
+}
+
+/// Execute a partition of `plan`, deferring the filters on its top to selection vectors
+pub async fn execute_selected(
+    plan: &Arc<dyn ExecutionPlan>,
+    partition: usize,
+) -> Result<SendableSelectedBatchStream> {
+    let mut input = match plan.as_any().downcast_ref::<CoalesceBatchesExec>() {
+        Some(coalesce) if coalesce.input().as_any().is::<FilterExec>() => {
+            coalesce.input()
+        }
+        _ => plan,
+    };
+    let mut predicates = Vec::new();
+    while let Some(filter) = input.as_any().downcast_ref::<FilterExec>() {
+        predicates.push(filter.predicate().clone());
+        input = filter.input();
+    }
+    // the innermost filter is applied first
+    predicates.reverse();
+
+    let stream = input.execute(partition).await?;
+    Ok(Box::pin(stream.map(move |batch| {
+        predicates
+            .iter()
+            .try_fold(SelectedBatch::new(batch?), |selected, predicate| {
+                selected.filter(predicate.as_ref())
+            })
+            .map_err(DataFusionError::into_arrow_external_error)
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::Operator;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::expressions::{binary, col, lit};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::projection::ProjectionExec;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    fn int_batch(values: Vec<i32>) -> Result<RecordBatch> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        Ok(RecordBatch::try_new(
+            Arc::new(schema),
+            vec![Arc::new(Int32Array::from(values))],
+        )?)
+    }
+
+    fn a_gt(value: i32, schema: &Schema) -> Result<Arc<dyn PhysicalExpr>> {
+        binary(
+            col("a", schema)?,
+            Operator::Gt,
+            lit(ScalarValue::Int32(Some(value))),
+            schema,
+        )
+    }
+
+    #[test]
+    fn filter_is_lazy_until_selectivity_is_low() -> Result<()> {
+        let batch = int_batch((0..10).collect())?;
+        let schema = batch.schema();
+
+        let selected = SelectedBatch::new(batch).filter(a_gt(2, &schema)?.as_ref())?;
+        assert_eq!(7, selected.num_rows());
+        assert_eq!(10, selected.batch().num_rows());
+        let a = selected.evaluate(col("a", &schema)?.as_ref())?;
+        assert_eq!(a.as_ref(), &Int32Array::from((3..10).collect::<Vec<_>>()));
+
+        let selected = selected.filter(a_gt(6, &schema)?.as_ref())?;
+        assert_eq!(3, selected.num_rows());
+        assert_eq!(3, selected.batch().num_rows());
+        assert!(selected.selection().is_none());
+
+        let batch = selected.into_batch()?;
+        assert_eq!(batch.column(0).as_ref(), &Int32Array::from(vec![7, 8, 9]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn project_filtered_input() -> Result<()> {
+        let batch = int_batch(vec![5, 1, 7, 3, 9])?;
+        let schema = batch.schema();
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None)?);
+        let filter = Arc::new(FilterExec::try_new(
+            a_gt(1, &schema)?,
+            Arc::new(FilterExec::try_new(a_gt(4, &schema)?, input)?),
+        )?);
+
+        let plan: Arc<dyn ExecutionPlan> = filter.clone();
+        let selected = execute_selected(&plan, 0)
+            .await?
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<ArrowResult<Vec<_>>>()?;
+        assert_eq!(1, selected.len());
+        assert_eq!(3, selected[0].num_rows());
+        assert!(selected[0].selection().is_some());
+
+        let projection = Arc::new(ProjectionExec::try_new(
+            vec![(col("a", &schema)?, "a".to_string())],
+            filter,
+        )?);
+        let batches = collect(projection).await?;
+        assert_eq!(1, batches.len());
+        assert_eq!(
+            batches[0].column(0).as_ref(),
+            &Int32Array::from(vec![5, 7, 9])
+        );
+        Ok(())
+    }
+}
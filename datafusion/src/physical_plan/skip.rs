@@ -99,7 +99,7 @@ impl ExecutionPlan for SkipExec {
     }
 
     fn output_hints(&self) -> OptimizerHints {
-        self.input.output_hints()
+        self.input.output_hints().limit()
     }
 
     async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
@@ -122,7 +122,7 @@ impl ExecutionPlan for SkipExec {
 }
 
 /// A Skip stream skips first `skip` rows.
-struct SkipStream {
+pub(crate) struct SkipStream {
     to_skip: usize,
     input: SendableRecordBatchStream,
     // the current count
@@ -130,7 +130,7 @@ struct SkipStream {
 }
 
 impl SkipStream {
-    fn new(input: SendableRecordBatchStream, to_skip: usize) -> Self {
+    pub(crate) fn new(input: SendableRecordBatchStream, to_skip: usize) -> Self {
         Self {
             to_skip,
             input,
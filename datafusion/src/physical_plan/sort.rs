@@ -20,6 +20,7 @@
 use crate::cube_ext;
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::expressions::{Column, PhysicalSortExpr};
+use crate::physical_plan::sort_key::lexsort_to_indices_with_keys;
 use crate::physical_plan::{
     common, DisplayFormatType, Distribution, ExecutionPlan, Partitioning, SQLMetric,
 };
@@ -27,14 +28,18 @@ use crate::physical_plan::{
     OptimizerHints, RecordBatchStream, SendableRecordBatchStream,
 };
 pub use arrow::compute::SortOptions;
-use arrow::compute::{lexsort_to_indices, take, SortColumn, TakeOptions};
+use arrow::compute::{take, SortColumn, TakeOptions};
 use arrow::datatypes::SchemaRef;
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
-use arrow::{array::ArrayRef, error::ArrowError};
+use arrow::{
+    array::{ArrayRef, UInt32Array},
+    error::ArrowError,
+};
 use async_trait::async_trait;
 use futures::stream::Stream;
 use futures::Future;
+use futures::StreamExt;
 use hashbrown::HashMap;
 use pin_project_lite::pin_project;
 use std::any::Any;
@@ -56,6 +61,10 @@ pub struct SortExec {
     sort_time_nanos: Arc<SQLMetric>,
     /// Preserve partitions of input plan
     preserve_partitioning: bool,
+    /// Number of leading rows of the sorted output to skip
+    skip: usize,
+    /// Maximum number of rows to return after skipping, all rows if `None`
+    fetch: Option<usize>,
 }
 
 impl SortExec {
@@ -78,11 +87,43 @@ impl SortExec {
             expr,
             input,
             preserve_partitioning,
+            skip: 0,
+            fetch: None,
             output_rows: SQLMetric::counter(),
             sort_time_nanos: SQLMetric::time_nanos(),
         }
     }
 
+    /// Skip the first `skip` rows of the sorted output (`OFFSET`).
+    /// Applies to every partition when the partitioning is preserved.
+    pub fn with_skip(mut self, skip: usize) -> Self {
+        self.skip = skip;
+        self
+    }
+
+    /// Only produce the first `fetch` rows (after skipping) of the sorted output
+    /// (`LIMIT`). The sort then keeps only the rows it may return in memory
+    /// instead of buffering its whole input.
+    pub fn with_fetch(mut self, fetch: Option<usize>) -> Self {
+        self.fetch = fetch;
+        self
+    }
+
+    /// Number of leading rows skipped
+    pub fn skip(&self) -> usize {
+        self.skip
+    }
+
+    /// Maximum number of rows returned, if any
+    pub fn fetch(&self) -> Option<usize> {
+        self.fetch
+    }
+
+    /// Whether the partitioning of the input plan is preserved
+    pub fn preserve_partitioning(&self) -> bool {
+        self.preserve_partitioning
+    }
+
     /// Input schema
     pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
         &self.input
@@ -131,10 +172,15 @@ impl ExecutionPlan for SortExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(SortExec::try_new(
-                self.expr.clone(),
-                children[0].clone(),
-            )?)),
+            1 => Ok(Arc::new(
+                SortExec::new_with_partitioning(
+                    self.expr.clone(),
+                    children[0].clone(),
+                    self.preserve_partitioning,
+                )
+                .with_skip(self.skip)
+                .with_fetch(self.fetch),
+            )),
             _ => Err(DataFusionError::Internal(
                 "SortExec wrong number of children".to_string(),
             )),
@@ -163,6 +209,8 @@ impl ExecutionPlan for SortExec {
         Ok(Box::pin(SortStream::new(
             input,
             self.expr.clone(),
+            self.skip,
+            self.fetch,
             self.output_rows.clone(),
             self.sort_time_nanos.clone(),
         )))
@@ -176,7 +224,14 @@ impl ExecutionPlan for SortExec {
         match t {
             DisplayFormatType::Default => {
                 let expr: Vec<String> = self.expr.iter().map(|e| e.to_string()).collect();
-                write!(f, "SortExec: [{}]", expr.join(","))
+                write!(f, "SortExec: [{}]", expr.join(","))?;
+                if self.skip != 0 {
+                    write!(f, ", skip={}", self.skip)?;
+                }
+                if let Some(fetch) = self.fetch {
+                    write!(f, ", fetch={}", fetch)?;
+                }
+                Ok(())
             }
         }
     }
@@ -216,21 +271,29 @@ impl ExecutionPlan for SortExec {
     }
 }
 
+/// Sorts `batch`, returning at most `fetch` rows after skipping the first `skip` ones
 #[tracing::instrument(level = "trace", skip(batch, schema, expr))]
-fn sort_batch(
+pub(crate) fn sort_batch(
     batch: RecordBatch,
     schema: SchemaRef,
     expr: &[PhysicalSortExpr],
+    skip: usize,
+    fetch: Option<usize>,
 ) -> ArrowResult<RecordBatch> {
-    // TODO: pushup the limit expression to sort
-    let indices = lexsort_to_indices(
+    let indices = lexsort_to_indices_with_keys(
         &expr
             .iter()
             .map(|e| e.evaluate_to_sort_column(&batch))
             .collect::<Result<Vec<SortColumn>>>()
             .map_err(DataFusionError::into_arrow_external_error)?,
-        None,
+        fetch.map(|fetch| skip + fetch),
     )?;
+    let indices = if skip == 0 {
+        indices
+    } else {
+        let start = skip.min(indices.len());
+        UInt32Array::from(indices.values()[start..].to_vec())
+    };
 
     // reorder all rows based on sorted indices
     RecordBatch::try_new(
@@ -253,6 +316,38 @@ fn sort_batch(
     )
 }
 
+/// Collects the input, periodically discarding the rows that cannot be among the
+/// first `k` rows of the sorted output, so that at most about `2 * k` rows are
+/// buffered at a time.
+async fn collect_top_k(
+    mut input: SendableRecordBatchStream,
+    expr: &[PhysicalSortExpr],
+    k: usize,
+    sort_time: &SQLMetric,
+) -> ArrowResult<Vec<RecordBatch>> {
+    let schema = input.schema();
+    let mut batches = vec![];
+    let mut num_rows = 0;
+    if k == 0 {
+        return Ok(batches);
+    }
+    while let Some(batch) = input.next().await {
+        let batch = batch?;
+        num_rows += batch.num_rows();
+        batches.push(batch);
+        if num_rows >= 2 * k {
+            let now = Instant::now();
+            let combined = common::combine_batches(&batches, schema.clone())?
+                .expect("batches are not empty");
+            let top = sort_batch(combined, schema.clone(), expr, 0, Some(k))?;
+            num_rows = top.num_rows();
+            batches = vec![top];
+            sort_time.add(now.elapsed().as_nanos() as usize);
+        }
+    }
+    Ok(batches)
+}
+
 pin_project! {
     /// stream for sort plan
     struct SortStream {
@@ -268,6 +363,8 @@ impl SortStream {
     fn new(
         input: SendableRecordBatchStream,
         expr: Vec<PhysicalSortExpr>,
+        skip: usize,
+        fetch: Option<usize>,
         output_rows: Arc<SQLMetric>,
         sort_time: Arc<SQLMetric>,
     ) -> Self {
@@ -275,20 +372,23 @@ impl SortStream {
         let schema = input.schema();
         let task = async move {
             let schema = input.schema();
-            common::collect(input)
-                .await
-                .map_err(DataFusionError::into_arrow_external_error)
-                .and_then(move |batches| {
-                    let now = Instant::now();
-                    // combine all record batches into one for each column
-                    let combined = common::combine_batches(&batches, schema.clone())?;
-                    // sort combined record batch
-                    let result = combined
-                        .map(|batch| sort_batch(batch, schema, &expr))
-                        .transpose()?;
-                    sort_time.add(now.elapsed().as_nanos() as usize);
-                    Ok(result)
-                })
+            let batches = match fetch {
+                None => common::collect(input)
+                    .await
+                    .map_err(DataFusionError::into_arrow_external_error)?,
+                Some(fetch) => {
+                    collect_top_k(input, &expr, skip + fetch, &sort_time).await?
+                }
+            };
+            let now = Instant::now();
+            // combine all record batches into one for each column
+            let combined = common::combine_batches(&batches, schema.clone())?;
+            // sort combined record batch
+            let result = combined
+                .map(|batch| sort_batch(batch, schema, &expr, skip, fetch))
+                .transpose()?;
+            sort_time.add(now.elapsed().as_nanos() as usize);
+            Ok::<_, ArrowError>(result)
         };
         cube_ext::spawn_oneshot_with_catch_unwind(task, tx);
 
@@ -512,4 +612,38 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_sort_skip_fetch() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        // enough small batches for the buffered rows to be compacted several times
+        let batches = (0..5)
+            .map(|i| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from(vec![19 - i, i + 5, 14 - i, i]))],
+                )
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+
+        let sort_exec = Arc::new(
+            SortExec::try_new(
+                vec![PhysicalSortExpr {
+                    expr: col("a", &schema)?,
+                    options: SortOptions::default(),
+                }],
+                Arc::new(MemoryExec::try_new(&[batches], schema, None)?),
+            )?
+            .with_skip(2)
+            .with_fetch(Some(3)),
+        );
+
+        let result: Vec<RecordBatch> = collect(sort_exec.clone()).await?;
+        assert_eq!(sort_exec.metrics().get("outputRows").unwrap().value(), 3);
+        assert_eq!(result.len(), 1);
+        let a = as_primitive_array::<Int32Type>(result[0].column(0));
+        assert_eq!(a.values(), &[2, 3, 4]);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,355 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Normalized sort keys: the values of the sort columns of each row encoded into bytes
+//! that compare with `memcmp` in the sort order, so that sorting on several columns
+//! compares a single byte string per row instead of each column in turn.
+
+use arrow::array::{
+    Array, ArrayRef, BinaryArray, BooleanArray, LargeBinaryArray, LargeStringArray,
+    PrimitiveArray, StringArray, UInt32Array,
+};
+use arrow::compute::{lexsort_to_indices, SortColumn, SortOptions};
+use arrow::datatypes::*;
+use arrow::error::Result as ArrowResult;
+
+/// Marks a NULL sorted before all values
+const NULL_FIRST: u8 = 0x00;
+/// Marks a non-NULL value
+const VALID: u8 = 0x01;
+/// Marks a NULL sorted after all values
+const NULL_LAST: u8 = 0xFF;
+
+/// The normalized sort keys of the rows of a batch
+#[derive(Debug)]
+pub struct SortKeys {
+    rows: Vec<Vec<u8>>,
+}
+
+impl SortKeys {
+    /// Encodes the sort keys of `columns`, or returns `None` if the type of one of them
+    /// is not supported
+    pub fn try_new(columns: &[SortColumn]) -> Option<Self> {
+        let num_rows = columns.first().map_or(0, |c| c.values.len());
+        let mut rows = vec![Vec::new(); num_rows];
+        for column in columns {
+            let options = column.options.unwrap_or_default();
+            if !encode_column(&column.values, options, &mut rows) {
+                return None;
+            }
+        }
+        Some(Self { rows })
+    }
+
+    /// The key of the row `i`
+    pub fn row(&self, i: usize) -> &[u8] {
+        &self.rows[i]
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether there are no rows
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// The indices of the rows in the sort order, truncated to `limit` rows
+    pub fn sort_to_indices(&self, limit: Option<usize>) -> UInt32Array {
+        let mut indices = (0..self.rows.len() as u32).collect::<Vec<_>>();
+        let compare =
+            |a: &u32, b: &u32| self.rows[*a as usize].cmp(&self.rows[*b as usize]);
+        match limit {
+            Some(limit) if limit < indices.len() => {
+                if limit > 0 {
+                    indices.select_nth_unstable_by(limit - 1, compare);
+                }
+                indices.truncate(limit);
+            }
+            _ => {}
+        }
+        indices.sort_unstable_by(compare);
+        UInt32Array::from(indices)
+    }
+}
+
+/// Sorts the rows of `columns` like [lexsort_to_indices], comparing normalized sort
+/// keys when there are several columns of supported types
+pub fn lexsort_to_indices_with_keys(
+    columns: &[SortColumn],
+    limit: Option<usize>,
+) -> ArrowResult<UInt32Array> {
+    if columns.len() > 1 {
+        if let Some(keys) = SortKeys::try_new(columns) {
+            return Ok(keys.sort_to_indices(limit));
+        }
+    }
+    lexsort_to_indices(columns, limit)
+}
+
+/// Appends the encoded values of `array` to the keys of the rows
+fn encode_column(array: &ArrayRef, options: SortOptions, rows: &mut [Vec<u8>]) -> bool {
+    macro_rules! primitive {
+        ($T:ty) => {
+            encode_primitive::<$T>(array, options, rows)
+        };
+    }
+    match array.data_type() {
+        DataType::Boolean => {
+            let array = array.as_any().downcast_ref::<BooleanArray>().unwrap();
+            encode_values(array, options, rows, |i, key| {
+                key.push(array.value(i) as u8)
+            });
+        }
+        DataType::Int8 => primitive!(Int8Type),
+        DataType::Int16 => primitive!(Int16Type),
+        DataType::Int32 => primitive!(Int32Type),
+        DataType::Int64 => primitive!(Int64Type),
+        DataType::UInt8 => primitive!(UInt8Type),
+        DataType::UInt16 => primitive!(UInt16Type),
+        DataType::UInt32 => primitive!(UInt32Type),
+        DataType::UInt64 => primitive!(UInt64Type),
+        DataType::Float32 => primitive!(Float32Type),
+        DataType::Float64 => primitive!(Float64Type),
+        DataType::Date32 => primitive!(Date32Type),
+        DataType::Date64 => primitive!(Date64Type),
+        DataType::Timestamp(TimeUnit::Second, _) => primitive!(TimestampSecondType),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            primitive!(TimestampMillisecondType)
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            primitive!(TimestampMicrosecondType)
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            primitive!(TimestampNanosecondType)
+        }
+        DataType::Utf8 => {
+            let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+            encode_values(array, options, rows, |i, key| {
+                encode_bytes(array.value(i).as_bytes(), key)
+            });
+        }
+        DataType::LargeUtf8 => {
+            let array = array.as_any().downcast_ref::<LargeStringArray>().unwrap();
+            encode_values(array, options, rows, |i, key| {
+                encode_bytes(array.value(i).as_bytes(), key)
+            });
+        }
+        DataType::Binary => {
+            let array = array.as_any().downcast_ref::<BinaryArray>().unwrap();
+            encode_values(array, options, rows, |i, key| {
+                encode_bytes(array.value(i), key)
+            });
+        }
+        DataType::LargeBinary => {
+            let array = array.as_any().downcast_ref::<LargeBinaryArray>().unwrap();
+            encode_values(array, options, rows, |i, key| {
+                encode_bytes(array.value(i), key)
+            });
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Appends the NULL marker or the encoded value of each row, inverting the bytes of
+/// values for descending orders. NULLs are placed independently of the direction.
+fn encode_values(
+    array: &dyn Array,
+    options: SortOptions,
+    rows: &mut [Vec<u8>],
+    encode: impl Fn(usize, &mut Vec<u8>),
+) {
+    for (i, key) in rows.iter_mut().enumerate() {
+        if array.is_null(i) {
+            key.push(if options.nulls_first {
+                NULL_FIRST
+            } else {
+                NULL_LAST
+            });
+            continue;
+        }
+        key.push(VALID);
+        let start = key.len();
+        encode(i, key);
+        if options.descending {
+            for b in &mut key[start..] {
+                *b = !*b;
+            }
+        }
+    }
+}
+
+fn encode_primitive<T>(array: &ArrayRef, options: SortOptions, rows: &mut [Vec<u8>])
+where
+    T: ArrowPrimitiveType,
+    T::Native: KeyEncode,
+{
+    let array = array.as_any().downcast_ref::<PrimitiveArray<T>>().unwrap();
+    encode_values(array, options, rows, |i, key| array.value(i).encode(key));
+}
+
+/// Byte-wise escaping so that no encoded value is a prefix of another: `0x00` is
+/// written as `0x00 0x01` and the value is terminated by `0x00 0x00`
+fn encode_bytes(value: &[u8], key: &mut Vec<u8>) {
+    for b in value {
+        key.push(*b);
+        if *b == 0 {
+            key.push(1);
+        }
+    }
+    key.extend_from_slice(&[0, 0]);
+}
+
+/// Encoding of native values into big-endian bytes that compare like the values
+trait KeyEncode {
+    fn encode(self, key: &mut Vec<u8>);
+}
+
+macro_rules! key_encode_unsigned {
+    ($($T:ty),*) => {
+        $(impl KeyEncode for $T {
+            fn encode(self, key: &mut Vec<u8>) {
+                key.extend_from_slice(&self.to_be_bytes());
+            }
+        })*
+    };
+}
+
+macro_rules! key_encode_signed {
+    ($($T:ty),*) => {
+        $(impl KeyEncode for $T {
+            fn encode(self, key: &mut Vec<u8>) {
+                // flipping the sign bit orders negative values before positive ones
+                let mut bytes = self.to_be_bytes();
+                bytes[0] ^= 0x80;
+                key.extend_from_slice(&bytes);
+            }
+        })*
+    };
+}
+
+macro_rules! key_encode_float {
+    ($($T:ty),*) => {
+        $(impl KeyEncode for $T {
+            fn encode(self, key: &mut Vec<u8>) {
+                // negative values have all bits inverted, as a larger magnitude makes
+                // them smaller, and positive values have the sign bit set
+                let mut bytes = self.to_be_bytes();
+                if bytes[0] & 0x80 != 0 {
+                    for b in &mut bytes {
+                        *b = !*b;
+                    }
+                } else {
+                    bytes[0] |= 0x80;
+                }
+                key.extend_from_slice(&bytes);
+            }
+        })*
+    };
+}
+
+key_encode_unsigned!(u8, u16, u32, u64);
+key_encode_signed!(i8, i16, i32, i64);
+key_encode_float!(f32, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Float64Array, Int32Array};
+    use arrow::compute::take;
+    use std::sync::Arc;
+
+    fn sort_column(values: ArrayRef, descending: bool, nulls_first: bool) -> SortColumn {
+        SortColumn {
+            values,
+            options: Some(SortOptions {
+                descending,
+                nulls_first,
+            }),
+        }
+    }
+
+    /// Checks that the keys sort the columns like `lexsort_to_indices`. Rows with equal
+    /// keys may be in any order, so the sorted columns are compared.
+    fn assert_sorts_like_lexsort(columns: &[SortColumn], limit: Option<usize>) {
+        let keys = SortKeys::try_new(columns).unwrap();
+        let actual = keys.sort_to_indices(limit);
+        let expected = lexsort_to_indices(columns, limit).unwrap();
+        for c in columns {
+            assert_eq!(
+                take(c.values.as_ref(), &actual, None).unwrap().data(),
+                take(c.values.as_ref(), &expected, None).unwrap().data(),
+            );
+        }
+    }
+
+    #[test]
+    fn sort_like_lexsort() {
+        let ints: ArrayRef = Arc::new(Int32Array::from(vec![
+            Some(2),
+            None,
+            Some(-1),
+            Some(2),
+            Some(i32::MIN),
+            Some(0),
+            Some(i32::MAX),
+            None,
+        ]));
+        let strings: ArrayRef = Arc::new(StringArray::from(vec![
+            Some("b"),
+            Some("a\0"),
+            Some(""),
+            Some("a"),
+            None,
+            Some("ab"),
+            Some("a"),
+            Some("a"),
+        ]));
+        let floats: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(-1.5),
+            Some(0.0),
+            None,
+            Some(2.5),
+            Some(-100.0),
+            Some(1e10),
+            Some(f64::MIN),
+            Some(3.0),
+        ]));
+        for &descending in &[false, true] {
+            for &nulls_first in &[false, true] {
+                let columns = vec![
+                    sort_column(ints.clone(), descending, nulls_first),
+                    sort_column(strings.clone(), !descending, nulls_first),
+                    sort_column(floats.clone(), descending, !nulls_first),
+                ];
+                assert_sorts_like_lexsort(&columns, None);
+                assert_sorts_like_lexsort(&columns, Some(3));
+                assert_sorts_like_lexsort(&columns[1..], None);
+                assert_sorts_like_lexsort(&columns[2..], Some(1));
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_types() {
+        let nulls: ArrayRef = Arc::new(arrow::array::NullArray::new(1));
+        assert!(SortKeys::try_new(&[sort_column(nulls, false, false)]).is_none());
+    }
+}
@@ -40,9 +40,12 @@ use hashbrown::HashMap;
 
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{
-    common::spawn_execution, expressions::PhysicalSortExpr, DisplayFormatType,
-    Distribution, ExecutionPlan, Partitioning, PhysicalExpr, RecordBatchStream,
-    SendableRecordBatchStream,
+    common::{spawn_execution, AbortOnDropMany},
+    expressions::PhysicalSortExpr,
+    limit::LimitStream,
+    skip::SkipStream,
+    DisplayFormatType, Distribution, ExecutionPlan, Partitioning, PhysicalExpr,
+    RecordBatchStream, SendableRecordBatchStream,
 };
 
 /// Sort preserving merge execution plan
@@ -59,6 +62,10 @@ pub struct SortPreservingMergeExec {
     expr: Vec<PhysicalSortExpr>,
     /// The target size of yielded batches
     target_batch_size: usize,
+    /// Number of leading rows of the merged output to skip
+    skip: usize,
+    /// Maximum number of rows to return after skipping, all rows if `None`
+    fetch: Option<usize>,
 }
 
 impl SortPreservingMergeExec {
@@ -72,6 +79,47 @@ impl SortPreservingMergeExec {
             input,
             expr,
             target_batch_size,
+            skip: 0,
+            fetch: None,
+        }
+    }
+
+    /// Skip the first `skip` rows of the merged output (`OFFSET`)
+    pub fn with_skip(mut self, skip: usize) -> Self {
+        self.skip = skip;
+        self
+    }
+
+    /// Only produce the first `fetch` rows (after skipping) of the merged output
+    /// (`LIMIT`). The inputs are cancelled as soon as these rows are produced.
+    pub fn with_fetch(mut self, fetch: Option<usize>) -> Self {
+        self.fetch = fetch;
+        self
+    }
+
+    /// Number of leading rows skipped
+    pub fn skip(&self) -> usize {
+        self.skip
+    }
+
+    /// Maximum number of rows returned, if any
+    pub fn fetch(&self) -> Option<usize> {
+        self.fetch
+    }
+
+    /// Applies `skip` and `fetch` to the merged stream
+    fn skip_and_fetch(
+        &self,
+        stream: SendableRecordBatchStream,
+    ) -> SendableRecordBatchStream {
+        let stream: SendableRecordBatchStream = if self.skip == 0 {
+            stream
+        } else {
+            Box::pin(SkipStream::new(stream, self.skip))
+        };
+        match self.fetch {
+            Some(fetch) => Box::pin(LimitStream::new(stream, fetch)),
+            None => stream,
         }
     }
 
@@ -115,11 +163,15 @@ impl ExecutionPlan for SortPreservingMergeExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(SortPreservingMergeExec::new(
-                self.expr.clone(),
-                children[0].clone(),
-                self.target_batch_size,
-            ))),
+            1 => Ok(Arc::new(
+                SortPreservingMergeExec::new(
+                    self.expr.clone(),
+                    children[0].clone(),
+                    self.target_batch_size,
+                )
+                .with_skip(self.skip)
+                .with_fetch(self.fetch),
+            )),
             _ => Err(DataFusionError::Internal(
                 "SortPreservingMergeExec wrong number of children".to_string(),
             )),
@@ -142,24 +194,26 @@ impl ExecutionPlan for SortPreservingMergeExec {
             )),
             1 => {
                 // bypass if there is only one partition to merge
-                self.input.execute(0).await
+                Ok(self.skip_and_fetch(self.input.execute(0).await?))
             }
             _ => {
-                let streams = (0..input_partitions)
+                let (streams, join_handles) = (0..input_partitions)
                     .into_iter()
                     .map(|part_i| {
                         let (sender, receiver) = mpsc::channel(1);
-                        spawn_execution(self.input.clone(), sender, part_i);
-                        receiver
+                        let join_handle =
+                            spawn_execution(self.input.clone(), sender, part_i);
+                        (receiver, join_handle)
                     })
-                    .collect();
+                    .unzip();
 
-                Ok(Box::pin(SortPreservingMergeStream::new(
+                Ok(self.skip_and_fetch(Box::pin(SortPreservingMergeStream::new(
                     streams,
+                    AbortOnDropMany(join_handles),
                     self.schema(),
                     &self.expr,
                     self.target_batch_size,
-                )))
+                ))))
             }
         }
     }
@@ -172,7 +226,14 @@ impl ExecutionPlan for SortPreservingMergeExec {
         match t {
             DisplayFormatType::Default => {
                 let expr: Vec<String> = self.expr.iter().map(|e| e.to_string()).collect();
-                write!(f, "SortPreservingMergeExec: [{}]", expr.join(","))
+                write!(f, "SortPreservingMergeExec: [{}]", expr.join(","))?;
+                if self.skip != 0 {
+                    write!(f, ", skip={}", self.skip)?;
+                }
+                if let Some(fetch) = self.fetch {
+                    write!(f, ", fetch={}", fetch)?;
+                }
+                Ok(())
             }
         }
     }
@@ -325,6 +386,8 @@ struct SortPreservingMergeStream {
     schema: SchemaRef,
     /// The sorted input streams to merge together
     streams: Vec<mpsc::Receiver<ArrowResult<RecordBatch>>>,
+    /// Cancels the tasks feeding `streams` when this stream is dropped
+    _drop_helper: AbortOnDropMany<()>,
     /// For each input stream maintain a dequeue of SortKeyCursor
     ///
     /// Exhausted cursors will be popped off the front once all
@@ -348,6 +411,7 @@ struct SortPreservingMergeStream {
 impl SortPreservingMergeStream {
     fn new(
         streams: Vec<mpsc::Receiver<ArrowResult<RecordBatch>>>,
+        drop_helper: AbortOnDropMany<()>,
         schema: SchemaRef,
         expressions: &[PhysicalSortExpr],
         target_batch_size: usize,
@@ -361,6 +425,7 @@ impl SortPreservingMergeStream {
             schema,
             cursors,
             streams,
+            _drop_helper: drop_helper,
             column_expressions: expressions.iter().map(|x| x.expr.clone()).collect(),
             sort_options: expressions.iter().map(|x| x.options).collect(),
             target_batch_size,
@@ -825,6 +890,32 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn test_merge_skip_fetch() {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![1, 3, 5, 7]));
+        let b1 = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![2, 4, 6, 8]));
+        let b2 = RecordBatch::try_from_iter(vec![("a", a)]).unwrap();
+        let schema = b1.schema();
+
+        let sort = vec![PhysicalSortExpr {
+            expr: col("a", &schema).unwrap(),
+            options: Default::default(),
+        }];
+        let exec = MemoryExec::try_new(&[vec![b1], vec![b2]], schema, None).unwrap();
+        let merge = Arc::new(
+            SortPreservingMergeExec::new(sort, Arc::new(exec), 1024)
+                .with_skip(2)
+                .with_fetch(Some(3)),
+        );
+
+        let collected = collect(merge).await.unwrap();
+        assert_batches_eq!(
+            &["+---+", "| a |", "+---+", "| 3 |", "| 4 |", "| 5 |", "+---+",],
+            collected.as_slice()
+        );
+    }
+
     async fn _test_merge(partitions: &[Vec<RecordBatch>], exp: &[&str]) {
         let schema = partitions[0][0].schema();
         let sort = vec![
@@ -1151,6 +1242,7 @@ mod tests {
 
         let merge_stream = SortPreservingMergeStream::new(
             streams,
+            AbortOnDropMany(vec![]),
             batches.schema(),
             sort.as_slice(),
             1024,
@@ -22,6 +22,7 @@
 //! String expressions
 
 use std::any::type_name;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::{
@@ -398,10 +399,11 @@ pub fn initcap<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef>
     Ok(Arc::new(result) as ArrayRef)
 }
 
-/// Converts the string to all lower case.
+/// Converts the string to all lower case, using the Unicode case mappings.
 /// lower('TOM') = 'tom'
+/// lower('ÉCLAIR') = 'éclair'
 pub fn lower(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    handle(args, |string| string.to_ascii_lowercase(), "lower")
+    handle(args, |string| string.to_lowercase(), "lower")
 }
 
 /// Removes the longest string containing only characters in characters (a space by default) from the start of string.
@@ -588,8 +590,60 @@ where
     Ok(Arc::new(result) as ArrayRef)
 }
 
-/// Converts the string to all upper case.
+/// Converts the string to all upper case, using the Unicode case mappings.
 /// upper('tom') = 'TOM'
+/// upper('éclair') = 'ÉCLAIR'
 pub fn upper(args: &[ColumnarValue]) -> Result<ColumnarValue> {
-    handle(args, |string| string.to_ascii_uppercase(), "upper")
+    handle(args, |string| string.to_uppercase(), "upper")
+}
+
+/// Rules for comparing strings. Strings are compared by their bytes unless a
+/// collation is specified with `COLLATE` or the `datafusion.execution.collation`
+/// option, in which case they are compared by their `collation_key`.
+///
+/// The session option applies to the comparisons `=`, `<>`, `<`, `<=`, `>`, `>=`,
+/// `BETWEEN` and `IN (...)`, including those of `JOIN ... ON`, to `ORDER BY`, and to
+/// the keys of GROUP BY and `SELECT DISTINCT`, whose groups show the smallest of their
+/// original strings. GROUPING SETS, `JOIN ... USING`, MIN and MAX compare bytes, unless
+/// their operands are `COLLATE`d, in which case they operate on the collation keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collation {
+    /// Compares the bytes of the strings
+    Binary,
+    /// Ignores the case of letters, 'A' equals 'a'
+    CaseInsensitive,
+    /// Ignores diacritics, 'é' equals 'e'
+    AccentInsensitive,
+    /// Ignores both the case of letters and diacritics, 'É' equals 'e'
+    CaseAccentInsensitive,
+}
+
+impl Collation {
+    /// The name of the collation, as accepted by `COLLATE`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Collation::Binary => "binary",
+            Collation::CaseInsensitive => "case_insensitive",
+            Collation::AccentInsensitive => "accent_insensitive",
+            Collation::CaseAccentInsensitive => "case_accent_insensitive",
+        }
+    }
+}
+
+impl FromStr for Collation {
+    type Err = DataFusionError;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_lowercase().as_str() {
+            "binary" => Ok(Collation::Binary),
+            "case_insensitive" => Ok(Collation::CaseInsensitive),
+            "accent_insensitive" => Ok(Collation::AccentInsensitive),
+            "case_accent_insensitive" => Ok(Collation::CaseAccentInsensitive),
+            _ => Err(DataFusionError::Plan(format!(
+                "Unknown collation '{}', expected one of binary, case_insensitive, \
+                 accent_insensitive, case_accent_insensitive",
+                name
+            ))),
+        }
+    }
 }
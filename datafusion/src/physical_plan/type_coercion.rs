@@ -112,12 +112,15 @@ fn get_valid_types(
             .map(|valid_type| (0..*number).map(|_| valid_type.clone()).collect())
             .collect(),
         Signature::VariadicEqual => {
-            // one entry with the same len as current_types, whose type is `current_types[0]`.
-            vec![current_types
+            // one entry with the same len as current_types, whose type is the type of
+            // the first argument that is not an untyped NULL.
+            let data_type = current_types
                 .iter()
-                .map(|_| current_types[0].clone())
-                .collect()]
+                .find(|t| **t != DataType::Null)
+                .unwrap_or(&current_types[0]);
+            vec![current_types.iter().map(|_| data_type.clone()).collect()]
         }
+        Signature::VariadicAny => vec![current_types.to_vec()],
         Signature::Exact(valid_types) => vec![valid_types.clone()],
         Signature::Any(number) => {
             if current_types.len() != *number {
@@ -173,6 +176,10 @@ fn maybe_data_types(
 /// See the module level documentation for more detail on coercion.
 pub fn can_coerce_from(type_into: &DataType, type_from: &DataType) -> bool {
     use self::DataType::*;
+    // an untyped NULL takes any type
+    if type_from == &Null {
+        return true;
+    }
     match type_into {
         Int8 => matches!(type_from, Int8),
         Int16 => matches!(type_from, Int8 | Int16 | UInt8),
@@ -337,6 +344,24 @@ mod tests {
                 Signature::Any(1),
                 vec![DataType::Float32],
             )?,
+            // null -> f64
+            case(
+                vec![DataType::Null],
+                Signature::Uniform(1, vec![DataType::Float64]),
+                vec![DataType::Float64],
+            )?,
+            // null -> type of the first argument that is not null
+            case(
+                vec![DataType::Null, DataType::Int64, DataType::Null],
+                Signature::VariadicEqual,
+                vec![DataType::Int64, DataType::Int64, DataType::Int64],
+            )?,
+            // any types are kept
+            case(
+                vec![DataType::Utf8, DataType::Boolean, DataType::Int8],
+                Signature::VariadicAny,
+                vec![DataType::Utf8, DataType::Boolean, DataType::Int8],
+            )?,
         ];
 
         for case in cases {
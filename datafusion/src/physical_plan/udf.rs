@@ -21,11 +21,13 @@ use fmt::{Debug, Formatter};
 use std::fmt;
 
 use crate::error::Result;
+use crate::execution::context::ExecutionProps;
 use crate::{logical_plan::Expr, physical_plan::PhysicalExpr};
 
 use super::{
     functions::{
         ReturnTypeFunction, ScalarFunctionExpr, ScalarFunctionImplementation, Signature,
+        Volatility,
     },
     type_coercion::coerce,
 };
@@ -33,6 +35,11 @@ use super::{
 use arrow::datatypes::Schema;
 use std::sync::Arc;
 
+/// Creates the implementation of a UDF for a statement from the properties of its
+/// execution, e.g. to use the start time of the statement as `now()` does
+pub type StatementFunctionFactory =
+    Arc<dyn Fn(&ExecutionProps) -> Result<ScalarFunctionImplementation> + Send + Sync>;
+
 /// Logical representation of a UDF.
 #[derive(Clone)]
 pub struct ScalarUDF {
@@ -51,6 +58,11 @@ pub struct ScalarUDF {
     /// the batch's row count (so that the generative zero-argument function can know
     /// the result array size).
     pub fun: ScalarFunctionImplementation,
+    /// volatility, [Volatility::Immutable] unless specified otherwise
+    pub volatility: Volatility,
+    /// Creates the implementation used instead of `fun` for every statement, see
+    /// [ScalarUDF::with_statement_function]
+    pub statement_fun: Option<StatementFunctionFactory>,
 }
 
 impl Debug for ScalarUDF {
@@ -58,6 +70,7 @@ impl Debug for ScalarUDF {
         f.debug_struct("ScalarUDF")
             .field("name", &self.name)
             .field("signature", &self.signature)
+            .field("volatility", &self.volatility)
             .field("fun", &"<FUNC>")
             .finish()
     }
@@ -82,9 +95,28 @@ impl ScalarUDF {
             signature: signature.clone(),
             return_type: return_type.clone(),
             fun: fun.clone(),
+            volatility: Volatility::Immutable,
+            statement_fun: None,
         }
     }
 
+    /// Sets the volatility of the UDF. Optimizer rules use it to decide whether calls
+    /// may be folded, deduplicated or pushed down.
+    pub fn with_volatility(mut self, volatility: Volatility) -> Self {
+        self.volatility = volatility;
+        self
+    }
+
+    /// Binds the UDF to the properties of each statement calling it at planning time,
+    /// like the current time functions, e.g. to read a snapshot of external data as
+    /// of [ExecutionProps::query_execution_start_time]. `factory` creates the
+    /// implementation used by the statement. The UDF becomes [Volatility::Stable].
+    pub fn with_statement_function(mut self, factory: StatementFunctionFactory) -> Self {
+        self.statement_fun = Some(factory);
+        self.volatility = Volatility::Stable;
+        self
+    }
+
     /// creates a logical expression with a call of the UDF
     /// This utility allows using the UDF without requiring access to the registry.
     pub fn call(&self, args: Vec<Expr>) -> Expr {
@@ -97,10 +129,23 @@ impl ScalarUDF {
 
 /// Create a physical expression of the UDF.
 /// This function errors when `args`' can't be coerced to a valid argument type of the UDF.
+/// A UDF bound to each statement is bound to an execution starting now.
 pub fn create_physical_expr(
     fun: &ScalarUDF,
     args: &[Arc<dyn PhysicalExpr>],
     input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    create_statement_physical_expr(fun, args, input_schema, &ExecutionProps::new())
+}
+
+/// Create a physical expression of the UDF for a statement executed with
+/// `execution_props`, see [ScalarUDF::with_statement_function].
+/// This function errors when `args`' can't be coerced to a valid argument type of the UDF.
+pub fn create_statement_physical_expr(
+    fun: &ScalarUDF,
+    args: &[Arc<dyn PhysicalExpr>],
+    input_schema: &Schema,
+    execution_props: &ExecutionProps,
 ) -> Result<Arc<dyn PhysicalExpr>> {
     // coerce
     let args = coerce(args, input_schema, &fun.signature)?;
@@ -110,9 +155,14 @@ pub fn create_physical_expr(
         .map(|e| e.data_type(input_schema))
         .collect::<Result<Vec<_>>>()?;
 
+    let implementation = match &fun.statement_fun {
+        Some(factory) => factory(execution_props)?,
+        None => fun.fun.clone(),
+    };
+
     Ok(Arc::new(ScalarFunctionExpr::new(
         &fun.name,
-        fun.fun.clone(),
+        implementation,
         args,
         (fun.return_type)(&arg_types)?.as_ref(),
     )))
@@ -23,16 +23,21 @@
 
 use std::any::type_name;
 use std::cmp::Ordering;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::error::{DataFusionError, Result};
+use crate::physical_plan::string_expressions::Collation;
 use arrow::{
     array::{
-        ArrayRef, GenericStringArray, Int64Array, PrimitiveArray, StringOffsetSizeTrait,
+        ArrayRef, GenericStringArray, Int64Array, PrimitiveArray, StringArray,
+        StringOffsetSizeTrait,
     },
     datatypes::{ArrowNativeType, ArrowPrimitiveType},
 };
 use hashbrown::HashMap;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 use unicode_segmentation::UnicodeSegmentation;
 
 macro_rules! downcast_string_arg {
@@ -61,7 +66,7 @@ macro_rules! downcast_arg {
     }};
 }
 
-/// Returns number of characters in the string.
+/// Returns number of characters (Unicode scalar values) in the string.
 /// character_length('josé') = 4
 pub fn character_length<T: ArrowPrimitiveType>(args: &[ArrayRef]) -> Result<ArrayRef>
 where
@@ -78,9 +83,8 @@ where
         .iter()
         .map(|string| {
             string.map(|string: &str| {
-                T::Native::from_usize(string.graphemes(true).count()).expect(
-                    "should not fail as graphemes.count will always return integer",
-                )
+                T::Native::from_usize(string.chars().count())
+                    .expect("should not fail as chars.count will always return integer")
             })
         })
         .collect::<PrimitiveArray<T>>();
@@ -409,6 +413,7 @@ where
 }
 
 /// Extracts the substring of string starting at the start'th character, and extending for count characters if that is specified. (Same as substring(string from start for count).)
+/// Characters are Unicode scalar values, as in Postgres.
 /// substr('alphabet', 3) = 'phabet'
 /// substr('alphabet', 3, 2) = 'ph'
 pub fn substr<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
@@ -425,13 +430,8 @@ pub fn substr<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
                         if start <= 0 {
                             Some(string.to_string())
                         } else {
-                            let graphemes = string.graphemes(true).collect::<Vec<&str>>();
                             let start_pos = start as usize - 1;
-                            if graphemes.len() < start_pos {
-                                Some("".to_string())
-                            } else {
-                                Some(graphemes[start_pos..].concat())
-                            }
+                            Some(string.chars().skip(start_pos).collect::<String>())
                         }
                     }
                     _ => None,
@@ -458,19 +458,14 @@ pub fn substr<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
                         } else if start <= 0 {
                             Ok(Some(string.to_string()))
                         } else {
-                            let graphemes = string.graphemes(true).collect::<Vec<&str>>();
                             let start_pos = start as usize - 1;
-                            let count_usize = count as usize;
-                            if graphemes.len() < start_pos {
-                                Ok(Some("".to_string()))
-                            } else if graphemes.len() < start_pos + count_usize {
-                                Ok(Some(graphemes[start_pos..].concat()))
-                            } else {
-                                Ok(Some(
-                                    graphemes[start_pos..start_pos + count_usize]
-                                        .concat(),
-                                ))
-                            }
+                            Ok(Some(
+                                string
+                                    .chars()
+                                    .skip(start_pos)
+                                    .take(count as usize)
+                                    .collect::<String>(),
+                            ))
                         }
                     }
                     _ => Ok(None),
@@ -486,6 +481,57 @@ pub fn substr<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
     }
 }
 
+/// Returns a key for comparing the string under the given collation: strings that are
+/// equal under the collation have equal keys.
+/// collation_key('Éclair', 'case_accent_insensitive') = 'eclair'
+pub fn collation_key<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let string_array = downcast_string_arg!(args[0], "string", T);
+    let collation_array = downcast_arg!(args[1], "collation", StringArray);
+
+    let mut collation: Option<(&str, Collation)> = None;
+    let result = string_array
+        .iter()
+        .zip(collation_array.iter())
+        .map(|(string, name)| match (string, name) {
+            (Some(string), Some(name)) => {
+                let c = match collation {
+                    Some((last, c)) if last == name => c,
+                    _ => {
+                        let c = Collation::from_str(name).map_err(|e| match e {
+                            DataFusionError::Plan(msg) => DataFusionError::Execution(msg),
+                            e => e,
+                        })?;
+                        collation = Some((name, c));
+                        c
+                    }
+                };
+                Ok(Some(fold_string(string, c)))
+            }
+            _ => Ok(None),
+        })
+        .collect::<Result<GenericStringArray<T>>>()?;
+
+    Ok(Arc::new(result) as ArrayRef)
+}
+
+fn fold_string(string: &str, collation: Collation) -> String {
+    match collation {
+        Collation::Binary => string.to_string(),
+        Collation::CaseInsensitive => string.to_lowercase(),
+        Collation::AccentInsensitive => strip_accents(string),
+        Collation::CaseAccentInsensitive => strip_accents(&string.to_lowercase()),
+    }
+}
+
+/// Decomposes the characters and removes the combining marks, e.g. 'é' becomes 'e'
+fn strip_accents(string: &str) -> String {
+    string
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .nfc()
+        .collect()
+}
+
 /// Replaces each character in string that matches a character in the from set with the corresponding character in the to set. If from is longer than to, occurrences of the extra characters in from are deleted.
 /// translate('12345', '143', 'ax') = 'a2x5'
 pub fn translate<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
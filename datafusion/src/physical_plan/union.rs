@@ -21,28 +21,61 @@
 
 //! The Union operator combines multiple inputs with the same schema
 
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use std::{any::Any, sync::Arc};
 
-use super::{ExecutionPlan, Partitioning, SendableRecordBatchStream};
+use super::{ExecutionPlan, Partitioning, RecordBatchStream, SendableRecordBatchStream};
 use crate::error::Result;
 use crate::physical_plan::OptimizerHints;
-use arrow::datatypes::SchemaRef;
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
+use futures::stream::{Stream, StreamExt};
 
 /// UNION ALL execution plan
 #[derive(Debug)]
 pub struct UnionExec {
     /// Input execution plan
     inputs: Vec<Arc<dyn ExecutionPlan>>,
+    /// The schema of the first input, with the columns that are nullable in any input
+    /// nullable
+    schema: SchemaRef,
 }
 
 impl UnionExec {
     /// Create a new UnionExec
     pub fn new(inputs: Vec<Arc<dyn ExecutionPlan>>) -> Self {
-        UnionExec { inputs }
+        let schema = union_schema(&inputs);
+        UnionExec { inputs, schema }
     }
 }
 
+fn union_schema(inputs: &[Arc<dyn ExecutionPlan>]) -> SchemaRef {
+    let first = inputs[0].schema();
+    let fields = first
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let nullable = inputs.iter().any(|input| {
+                input
+                    .schema()
+                    .fields()
+                    .get(i)
+                    .map_or(false, |f| f.is_nullable())
+            });
+            if nullable && !f.is_nullable() {
+                Field::new(f.name(), f.data_type().clone(), true)
+            } else {
+                f.clone()
+            }
+        })
+        .collect();
+    Arc::new(Schema::new_with_metadata(fields, first.metadata().clone()))
+}
+
 #[async_trait]
 impl ExecutionPlan for UnionExec {
     /// Return a reference to Any that can be used for downcasting
@@ -51,7 +84,7 @@ impl ExecutionPlan for UnionExec {
     }
 
     fn schema(&self) -> SchemaRef {
-        self.inputs[0].schema()
+        self.schema.clone()
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
@@ -83,7 +116,14 @@ impl ExecutionPlan for UnionExec {
         for input in self.inputs.iter() {
             // Calculate whether partition belongs to the current partition
             if partition < input.output_partitioning().partition_count() {
-                return input.execute(partition).await;
+                let stream = input.execute(partition).await?;
+                if input.schema() == self.schema {
+                    return Ok(stream);
+                }
+                return Ok(Box::pin(UnionInputStream {
+                    schema: self.schema.clone(),
+                    input: stream,
+                }));
             } else {
                 partition -= input.output_partitioning().partition_count();
             }
@@ -111,6 +151,39 @@ impl ExecutionPlan for UnionExec {
     }
 }
 
+/// The batches of an input of the union, with the schema of the union
+struct UnionInputStream {
+    schema: SchemaRef,
+    input: SendableRecordBatchStream,
+}
+
+impl Stream for UnionInputStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.input.poll_next_unpin(cx).map(|x| match x {
+            Some(Ok(batch)) => Some(RecordBatch::try_new(
+                self.schema.clone(),
+                batch.columns().to_vec(),
+            )),
+            other => other,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl RecordBatchStream for UnionInputStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
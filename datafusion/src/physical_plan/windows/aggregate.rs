@@ -21,9 +21,10 @@ use crate::error::{DataFusionError, Result};
 use crate::logical_plan::window_frames::{WindowFrame, WindowFrameUnits};
 use crate::physical_plan::windows::find_ranges_in_range;
 use crate::physical_plan::{
-    expressions::PhysicalSortExpr, Accumulator, AggregateExpr, PhysicalExpr, WindowExpr,
+    expressions::PhysicalSortExpr, Accumulator, AggregateExpr, PhysicalExpr,
+    WindowChunkEvaluator, WindowExpr,
 };
-use arrow::compute::concat;
+use arrow::compute::{concat, lexicographical_partition_ranges};
 use arrow::record_batch::RecordBatch;
 use arrow::{array::ArrayRef, datatypes::Field};
 use std::any::Any;
@@ -149,6 +150,47 @@ impl WindowExpr for AggregateWindowExpr {
             WindowFrameUnits::Groups => self.group_based_evaluate(batch),
         }
     }
+
+    /// running aggregates over the peer groups of an ordered partition only need the
+    /// accumulator state of the previous chunks
+    fn create_chunk_evaluator(&self) -> Result<Option<Box<dyn WindowChunkEvaluator>>> {
+        if self.evaluation_mode() != WindowFrameUnits::Range || self.order_by.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Box::new(AggregateWindowChunkEvaluator {
+            expressions: self.expressions(),
+            order_by: self.order_by.clone(),
+            accumulator: self.create_accumulator()?,
+        })))
+    }
+}
+
+/// Evaluates a running aggregate over the chunks of an ordered window partition
+#[derive(Debug)]
+struct AggregateWindowChunkEvaluator {
+    expressions: Vec<Arc<dyn PhysicalExpr>>,
+    order_by: Vec<PhysicalSortExpr>,
+    accumulator: AggregateWindowAccumulator,
+}
+
+impl WindowChunkEvaluator for AggregateWindowChunkEvaluator {
+    fn evaluate_chunk(&mut self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let values = self
+            .expressions
+            .iter()
+            .map(|e| e.evaluate(batch).map(|v| v.into_array(batch.num_rows())))
+            .collect::<Result<Vec<_>>>()?;
+        let order_by_columns = self
+            .order_by
+            .iter()
+            .map(|e| e.evaluate_to_sort_column(batch))
+            .collect::<Result<Vec<_>>>()?;
+        let results = lexicographical_partition_ranges(&order_by_columns)?
+            .map(|range| self.accumulator.scan_peers(&values, &range))
+            .collect::<Result<Vec<_>>>()?;
+        let results = results.iter().map(|i| i.as_ref()).collect::<Vec<_>>();
+        concat(&results).map_err(DataFusionError::ArrowError)
+    }
 }
 
 /// Aggregate window accumulator utilizes the accumulator from aggregation and do a accumulative sum
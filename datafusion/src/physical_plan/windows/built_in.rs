@@ -22,12 +22,14 @@ use crate::logical_plan::window_frames::WindowFrame;
 use crate::physical_plan::{
     expressions::PhysicalSortExpr,
     window_functions::{BuiltInWindowFunction, BuiltInWindowFunctionExpr},
-    PhysicalExpr, WindowExpr,
+    PhysicalExpr, WindowChunkEvaluator, WindowExpr,
 };
-use arrow::compute::concat;
+use arrow::array::UInt64Array;
+use arrow::compute::{concat, lexicographical_partition_ranges};
 use arrow::record_batch::RecordBatch;
 use arrow::{array::ArrayRef, datatypes::Field};
 use std::any::Any;
+use std::iter;
 use std::sync::Arc;
 
 /// A window expr that takes the form of a built in window function
@@ -57,6 +59,11 @@ impl BuiltInWindowExpr {
             window_frame,
         }
     }
+
+    /// The built-in window function
+    pub fn fun(&self) -> &BuiltInWindowFunction {
+        &self.fun
+    }
 }
 
 impl WindowExpr for BuiltInWindowExpr {
@@ -100,4 +107,68 @@ impl WindowExpr for BuiltInWindowExpr {
         let results = results.iter().map(|i| i.as_ref()).collect::<Vec<_>>();
         concat(&results).map_err(DataFusionError::ArrowError)
     }
+
+    /// row numbers and ranks only depend on the number of rows and peer groups of the
+    /// previous chunks
+    fn create_chunk_evaluator(&self) -> Result<Option<Box<dyn WindowChunkEvaluator>>> {
+        match self.fun {
+            BuiltInWindowFunction::RowNumber
+            | BuiltInWindowFunction::Rank
+            | BuiltInWindowFunction::DenseRank => {
+                Ok(Some(Box::new(BuiltInWindowChunkEvaluator {
+                    fun: self.fun.clone(),
+                    order_by: self.order_by.clone(),
+                    rows: 0,
+                    peer_groups: 0,
+                })))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Evaluates row numbers and ranks over the chunks of a window partition
+#[derive(Debug)]
+struct BuiltInWindowChunkEvaluator {
+    fun: BuiltInWindowFunction,
+    order_by: Vec<PhysicalSortExpr>,
+    /// Number of rows of the previous chunks
+    rows: u64,
+    /// Number of peer groups of the previous chunks
+    peer_groups: u64,
+}
+
+impl WindowChunkEvaluator for BuiltInWindowChunkEvaluator {
+    fn evaluate_chunk(&mut self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let num_rows = batch.num_rows() as u64;
+        if self.fun == BuiltInWindowFunction::RowNumber {
+            let result =
+                UInt64Array::from_iter_values(self.rows + 1..=self.rows + num_rows);
+            self.rows += num_rows;
+            return Ok(Arc::new(result));
+        }
+        let order_by_columns = self
+            .order_by
+            .iter()
+            .map(|e| e.evaluate_to_sort_column(batch))
+            .collect::<Result<Vec<_>>>()?;
+        let peers = if order_by_columns.is_empty() {
+            vec![0..batch.num_rows()]
+        } else {
+            lexicographical_partition_ranges(&order_by_columns)?.collect()
+        };
+        let dense = self.fun == BuiltInWindowFunction::DenseRank;
+        let mut values = Vec::with_capacity(batch.num_rows());
+        for range in peers {
+            let rank = if dense {
+                self.peer_groups + 1
+            } else {
+                self.rows + range.start as u64 + 1
+            };
+            values.extend(iter::repeat(rank).take(range.end - range.start));
+            self.peer_groups += 1;
+        }
+        self.rows += num_rows;
+        Ok(Arc::new(UInt64Array::from(values)))
+    }
 }
@@ -57,7 +57,15 @@ pub fn create_window_expr(
 ) -> Result<Arc<dyn WindowExpr>> {
     Ok(match fun {
         WindowFunction::AggregateFunction(fun) => Arc::new(AggregateWindowExpr::new(
-            aggregates::create_aggregate_expr(fun, false, args, input_schema, name)?,
+            aggregates::create_aggregate_expr(
+                fun,
+                false,
+                false,
+                args,
+                &[],
+                input_schema,
+                name,
+            )?,
             partition_by,
             order_by,
             window_frame,
@@ -176,9 +184,13 @@ mod tests {
     use crate::physical_plan::collect;
     use crate::physical_plan::csv::{CsvExec, CsvReadOptions};
     use crate::physical_plan::expressions::col;
-    use crate::test;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::test::{self, build_table_i32};
     use arrow::array::*;
-    use arrow::datatypes::SchemaRef;
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{
+        ArrowPrimitiveType, DataType, Field, Int64Type, SchemaRef, UInt64Type,
+    };
     use arrow::record_batch::RecordBatch;
 
     fn create_test_schema(partitions: usize) -> Result<(Arc<CsvExec>, SchemaRef)> {
@@ -255,4 +267,243 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn window_function_decimal() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "d",
+            DataType::Int64Decimal(2),
+            true,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int64Decimal2Array::from(vec![
+                Some(150),
+                None,
+                Some(250),
+            ]))],
+        )?;
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None)?);
+
+        let window_exec = Arc::new(WindowAggExec::try_new(
+            vec![
+                create_window_expr(
+                    &WindowFunction::AggregateFunction(AggregateFunction::Sum),
+                    "sum".to_owned(),
+                    &[col("d", &schema)?],
+                    &[],
+                    &[],
+                    Some(WindowFrame::default()),
+                    schema.as_ref(),
+                )?,
+                create_window_expr(
+                    &WindowFunction::AggregateFunction(AggregateFunction::Avg),
+                    "avg".to_owned(),
+                    &[col("d", &schema)?],
+                    &[],
+                    &[],
+                    Some(WindowFrame::default()),
+                    schema.as_ref(),
+                )?,
+            ],
+            input,
+            schema.clone(),
+        )?);
+
+        let result: Vec<RecordBatch> = collect(window_exec).await?;
+        let columns = result[0].columns();
+
+        // sums and averages are 96 bits decimals of the same scale
+        let sum: &Int96Decimal2Array = as_primitive_array(&columns[0]);
+        assert_eq!(sum.value(0), 400);
+        assert_eq!(sum.value(2), 400);
+
+        let avg: &Int96Decimal2Array = as_primitive_array(&columns[1]);
+        assert_eq!(avg.value(0), 200);
+        assert_eq!(avg.value(2), 200);
+
+        Ok(())
+    }
+
+    fn partitioned_sum(
+        batches: Vec<RecordBatch>,
+        memory_limit: Option<usize>,
+    ) -> Result<Arc<WindowAggExec>> {
+        let schema = batches[0].schema();
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None)?);
+        Ok(Arc::new(
+            WindowAggExec::try_new(
+                vec![create_window_expr(
+                    &WindowFunction::AggregateFunction(AggregateFunction::Sum),
+                    "sum".to_owned(),
+                    &[col("b", &schema)?],
+                    &[col("a", &schema)?],
+                    &[],
+                    Some(WindowFrame::default()),
+                    schema.as_ref(),
+                )?],
+                input,
+                schema.clone(),
+            )?
+            .with_memory_limit(memory_limit),
+        ))
+    }
+
+    #[tokio::test]
+    async fn window_function_streams_partitions() -> Result<()> {
+        let batches = vec![
+            build_table_i32(
+                ("a", &vec![1, 1, 2]),
+                ("b", &vec![1, 2, 3]),
+                ("c", &vec![0; 3]),
+            ),
+            build_table_i32(("a", &vec![2, 2]), ("b", &vec![4, 5]), ("c", &vec![0; 2])),
+            build_table_i32(
+                ("a", &vec![3, 4, 4]),
+                ("b", &vec![6, 7, 8]),
+                ("c", &vec![0; 3]),
+            ),
+        ];
+        let result = collect(partitioned_sum(batches, None)?).await?;
+
+        // each partition is returned once a later one starts in the input
+        let sums = result
+            .iter()
+            .map(|batch| {
+                let sum: &Int64Array = as_primitive_array(batch.column(0));
+                sum.values().to_vec()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sums, vec![vec![3, 3], vec![12, 12, 12, 6], vec![15, 15]]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn window_function_memory_limit() -> Result<()> {
+        let batches = vec![build_table_i32(
+            ("a", &vec![1, 1, 2]),
+            ("b", &vec![1, 2, 3]),
+            ("c", &vec![0; 3]),
+        )];
+        let err = collect(partitioned_sum(batches, Some(1))?)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("exceeds the memory limit of 1 bytes"),
+            "{}",
+            err
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn window_function_memory_limit_chunks() -> Result<()> {
+        let batches = vec![
+            build_table_i32(("a", &vec![1]), ("b", &vec![1]), ("c", &vec![10])),
+            build_table_i32(("a", &vec![1]), ("b", &vec![2]), ("c", &vec![20])),
+            build_table_i32(("a", &vec![1]), ("b", &vec![2]), ("c", &vec![30])),
+            build_table_i32(("a", &vec![1]), ("b", &vec![3]), ("c", &vec![40])),
+            build_table_i32(
+                ("a", &vec![1, 2, 3]),
+                ("b", &vec![4, 1, 1]),
+                ("c", &vec![1, 5, 7]),
+            ),
+        ];
+        // two buffered batches fit in the limit, three do not
+        let batch_size = batches[0]
+            .columns()
+            .iter()
+            .map(|c| c.get_array_memory_size())
+            .sum::<usize>();
+        let schema = batches[0].schema();
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None)?);
+        let partition_by = vec![col("a", &schema)?];
+        let order_by = vec![PhysicalSortExpr {
+            expr: col("b", &schema)?,
+            options: SortOptions::default(),
+        }];
+        let window_expr =
+            |fun: WindowFunction, name: &str, args: &[Arc<dyn PhysicalExpr>]| {
+                create_window_expr(
+                    &fun,
+                    name.to_owned(),
+                    args,
+                    &partition_by,
+                    &order_by,
+                    None,
+                    schema.as_ref(),
+                )
+            };
+        let window_exec = Arc::new(
+            WindowAggExec::try_new(
+                vec![
+                    window_expr(
+                        WindowFunction::AggregateFunction(AggregateFunction::Sum),
+                        "sum",
+                        &[col("c", &schema)?],
+                    )?,
+                    window_expr(
+                        WindowFunction::BuiltInWindowFunction(
+                            BuiltInWindowFunction::RowNumber,
+                        ),
+                        "row_number",
+                        &[],
+                    )?,
+                    window_expr(
+                        WindowFunction::BuiltInWindowFunction(
+                            BuiltInWindowFunction::Rank,
+                        ),
+                        "rank",
+                        &[],
+                    )?,
+                    window_expr(
+                        WindowFunction::BuiltInWindowFunction(
+                            BuiltInWindowFunction::DenseRank,
+                        ),
+                        "dense_rank",
+                        &[],
+                    )?,
+                ],
+                input,
+                schema.clone(),
+            )?
+            .with_memory_limit(Some(batch_size * 5 / 2)),
+        );
+
+        let result = collect(window_exec).await?;
+        // the first peer group of the partition is returned before the partition ends
+        assert_eq!(result[0].num_rows(), 1);
+
+        assert_eq!(
+            column_values::<Int64Type>(&result, 0),
+            vec![10, 60, 60, 100, 101, 5, 7]
+        );
+        assert_eq!(
+            column_values::<UInt64Type>(&result, 1),
+            vec![1, 2, 3, 4, 5, 1, 1]
+        );
+        assert_eq!(
+            column_values::<UInt64Type>(&result, 2),
+            vec![1, 2, 2, 4, 5, 1, 1]
+        );
+        assert_eq!(
+            column_values::<UInt64Type>(&result, 3),
+            vec![1, 2, 2, 3, 4, 1, 1]
+        );
+        Ok(())
+    }
+
+    fn column_values<T: ArrowPrimitiveType>(
+        batches: &[RecordBatch],
+        i: usize,
+    ) -> Vec<T::Native> {
+        batches
+            .iter()
+            .flat_map(|batch| {
+                let array: &PrimitiveArray<T> = as_primitive_array(batch.column(i));
+                array.values().to_vec()
+            })
+            .collect()
+    }
 }
@@ -20,18 +20,17 @@
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{
     common, Distribution, ExecutionPlan, Partitioning, RecordBatchStream,
-    SendableRecordBatchStream, WindowExpr,
+    SendableRecordBatchStream, WindowChunkEvaluator, WindowExpr,
 };
 use arrow::{
     array::ArrayRef,
+    compute::{concat, lexicographical_partition_ranges, SortColumn},
     datatypes::{Schema, SchemaRef},
-    error::{ArrowError, Result as ArrowResult},
+    error::Result as ArrowResult,
     record_batch::RecordBatch,
 };
 use async_trait::async_trait;
-use futures::stream::Stream;
-use futures::Future;
-use pin_project_lite::pin_project;
+use futures::stream::{Stream, StreamExt};
 use std::any::Any;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -48,6 +47,8 @@ pub struct WindowAggExec {
     schema: SchemaRef,
     /// Schema before the window
     input_schema: SchemaRef,
+    /// Maximum number of bytes of input buffered for the partitions being evaluated
+    memory_limit: Option<usize>,
 }
 
 impl WindowAggExec {
@@ -64,9 +65,24 @@ impl WindowAggExec {
             window_expr,
             schema,
             input_schema,
+            memory_limit: None,
         })
     }
 
+    /// Limit the input buffered for a window partition, or for all rows without
+    /// PARTITION BY, to `limit` bytes. Partitions over the limit are evaluated in chunks
+    /// when all window functions support it, e.g. running aggregates with ORDER BY,
+    /// ROW_NUMBER, RANK and DENSE_RANK, and fail the execution otherwise.
+    pub fn with_memory_limit(mut self, limit: Option<usize>) -> Self {
+        self.memory_limit = limit;
+        self
+    }
+
+    /// Maximum number of bytes of input buffered by the execution, if any
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
     /// Window expressions
     pub fn window_expr(&self) -> &[Arc<dyn WindowExpr>] {
         &self.window_expr
@@ -123,11 +139,14 @@ impl ExecutionPlan for WindowAggExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(WindowAggExec::try_new(
-                self.window_expr.clone(),
-                children[0].clone(),
-                self.input_schema.clone(),
-            )?)),
+            1 => Ok(Arc::new(
+                WindowAggExec::try_new(
+                    self.window_expr.clone(),
+                    children[0].clone(),
+                    self.input_schema.clone(),
+                )?
+                .with_memory_limit(self.memory_limit),
+            )),
             _ => Err(DataFusionError::Internal(
                 "WindowAggExec wrong number of children".to_owned(),
             )),
@@ -140,6 +159,7 @@ impl ExecutionPlan for WindowAggExec {
             self.schema.clone(),
             self.window_expr.clone(),
             input,
+            self.memory_limit,
         ));
         Ok(stream)
     }
@@ -159,7 +179,7 @@ fn create_schema(
 
 /// Compute the window aggregate columns
 fn compute_window_aggregates(
-    window_expr: Vec<Arc<dyn WindowExpr>>,
+    window_expr: &[Arc<dyn WindowExpr>],
     batch: &RecordBatch,
 ) -> Result<Vec<ArrayRef>> {
     window_expr
@@ -168,14 +188,71 @@ fn compute_window_aggregates(
         .collect()
 }
 
-pin_project! {
-  /// stream for window aggregation plan
-  pub struct WindowAggStream {
-      schema: SchemaRef,
-      #[pin]
-      output: futures::channel::oneshot::Receiver<ArrowResult<RecordBatch>>,
-      finished: bool,
-  }
+/// Compute the window aggregate columns when the first partition of `batch` continues
+/// the chunks already evaluated by `evaluators`
+fn compute_window_aggregates_after_chunks(
+    window_expr: &[Arc<dyn WindowExpr>],
+    evaluators: Vec<Box<dyn WindowChunkEvaluator>>,
+    batch: &RecordBatch,
+) -> Result<Vec<ArrayRef>> {
+    let partition_columns = match window_expr.first() {
+        Some(expr) => expr.partition_columns(batch)?,
+        None => vec![],
+    };
+    let end = if partition_columns.is_empty() {
+        batch.num_rows()
+    } else {
+        lexicographical_partition_ranges(&partition_columns)?
+            .next()
+            .map_or(0, |r| r.end)
+    };
+    let head = batch.slice(0, end);
+    let head_columns = evaluators
+        .into_iter()
+        .map(|mut evaluator| evaluator.evaluate_chunk(&head))
+        .collect::<Result<Vec<_>>>()?;
+    if end == batch.num_rows() {
+        return Ok(head_columns);
+    }
+    let tail = batch.slice(end, batch.num_rows() - end);
+    let tail_columns = compute_window_aggregates(window_expr, &tail)?;
+    head_columns
+        .iter()
+        .zip(tail_columns.iter())
+        .map(|(head, tail)| Ok(concat(&[head.as_ref(), tail.as_ref()])?))
+        .collect()
+}
+
+/// Approximate number of bytes used by the arrays of `batch`
+fn batch_memory_size(batch: &RecordBatch) -> usize {
+    batch
+        .columns()
+        .iter()
+        .map(|c| c.get_array_memory_size())
+        .sum()
+}
+
+/// Stream for window aggregation plan.
+///
+/// The input is sorted on the partition keys, so the rows of the window partitions
+/// that are complete are evaluated and returned as soon as a later partition starts,
+/// and only the partitions that may continue in the next input batch are buffered.
+/// When the buffered rows exceed the memory limit, the complete peer groups of the
+/// buffered partition are evaluated as a chunk and released.
+pub struct WindowAggStream {
+    schema: SchemaRef,
+    input: SendableRecordBatchStream,
+    window_expr: Vec<Arc<dyn WindowExpr>>,
+    /// Input batches of the partitions that are not known to be complete
+    buffer: Vec<RecordBatch>,
+    /// Partition columns of the last buffered row
+    last_key: Option<Vec<ArrayRef>>,
+    /// Bytes used by the buffered batches
+    buffer_size: usize,
+    memory_limit: Option<usize>,
+    /// Evaluators of the buffered partition, once its first chunk has been returned
+    chunk_evaluators: Option<Vec<Box<dyn WindowChunkEvaluator>>>,
+    finished: bool,
 }
 
 impl WindowAggStream {
@@ -184,70 +261,226 @@ impl WindowAggStream {
         schema: SchemaRef,
         window_expr: Vec<Arc<dyn WindowExpr>>,
         input: SendableRecordBatchStream,
+        memory_limit: Option<usize>,
     ) -> Self {
-        let (tx, rx) = futures::channel::oneshot::channel();
-        let schema_clone = schema.clone();
-        tokio::spawn(async move {
-            let schema = schema_clone.clone();
-            let result = WindowAggStream::process(input, window_expr, schema).await;
-            tx.send(result)
-        });
-
         Self {
-            output: rx,
-            finished: false,
             schema,
+            input,
+            window_expr,
+            buffer: Vec::new(),
+            last_key: None,
+            buffer_size: 0,
+            memory_limit,
+            chunk_evaluators: None,
+            finished: false,
         }
     }
 
-    async fn process(
-        input: SendableRecordBatchStream,
-        window_expr: Vec<Arc<dyn WindowExpr>>,
-        schema: SchemaRef,
-    ) -> ArrowResult<RecordBatch> {
-        let input_schema = input.schema();
-        let batches = common::collect(input)
-            .await
-            .map_err(DataFusionError::into_arrow_external_error)?;
-        let batch = common::combine_batches(&batches, input_schema.clone())?;
+    fn partition_columns(&self, batch: &RecordBatch) -> Result<Vec<SortColumn>> {
+        match self.window_expr.first() {
+            Some(expr) => expr.partition_columns(batch),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Number of leading rows of `batch` that belong to the buffered partitions or to
+    /// partitions that end within `batch`, or `None` if all partitions seen so far may
+    /// continue in the next batch
+    fn complete_rows(&self, batch: &RecordBatch) -> Result<Option<usize>> {
+        let columns = self.partition_columns(batch)?;
+        if columns.is_empty() {
+            return Ok(None);
+        }
+        let last_start = lexicographical_partition_ranges(&columns)?
+            .last()
+            .map_or(0, |r| r.start);
+        if last_start != 0 {
+            return Ok(Some(last_start));
+        }
+        // the batch has a single partition, which either continues the last buffered
+        // partition or starts a new one
+        let last_key = match &self.last_key {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+        let boundary = last_key
+            .iter()
+            .zip(columns.iter())
+            .map(|(last, column)| {
+                let values =
+                    concat(&[last.as_ref(), column.values.slice(0, 1).as_ref()])?;
+                Ok(SortColumn {
+                    values,
+                    options: column.options,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let starts_new_partition =
+            lexicographical_partition_ranges(&boundary)?.count() > 1;
+        Ok(if starts_new_partition { Some(0) } else { None })
+    }
+
+    /// Adds `batch` to the buffered rows and returns the output of the partitions
+    /// that are complete, if any
+    fn push_batch(&mut self, batch: RecordBatch) -> Result<Option<RecordBatch>> {
+        let num_rows = batch.num_rows();
+        if num_rows == 0 {
+            return Ok(None);
+        }
+        let mut output = match self.complete_rows(&batch)? {
+            Some(complete) => {
+                let mut complete_batches = std::mem::take(&mut self.buffer);
+                if complete > 0 {
+                    complete_batches.push(batch.slice(0, complete));
+                }
+                self.buffer.push(batch.slice(complete, num_rows - complete));
+                Some(self.compute_output(&complete_batches)?)
+            }
+            None => {
+                self.buffer.push(batch);
+                None
+            }
+        };
+
+        let last = self.buffer.last().unwrap();
+        self.last_key = Some(
+            self.partition_columns(last)?
+                .into_iter()
+                .map(|c| c.values.slice(c.values.len() - 1, 1))
+                .collect(),
+        );
+        self.buffer_size = self.buffer.iter().map(batch_memory_size).sum();
+        if let Some(limit) = self.memory_limit {
+            if self.buffer_size > limit {
+                if let Some(chunk) = self.compute_chunk()? {
+                    output = match output {
+                        Some(output) => common::combine_batches(
+                            &[output, chunk],
+                            self.schema.clone(),
+                        )?,
+                        None => Some(chunk),
+                    };
+                }
+            }
+            if self.buffer_size > limit {
+                return Err(DataFusionError::Execution(format!(
+                    "Window partition of {} bytes exceeds the memory limit of {} bytes",
+                    self.buffer_size, limit
+                )));
+            }
+        }
+        Ok(output)
+    }
+
+    /// Evaluates the buffered rows up to the last peer group, which may continue in
+    /// the next batch, and keeps a copy of the last peer group only. Returns `None` if
+    /// some window function needs the whole partition or there is a single peer group.
+    fn compute_chunk(&mut self) -> Result<Option<RecordBatch>> {
+        let batch = match common::combine_batches(&self.buffer, self.input.schema())? {
+            Some(batch) => batch,
+            None => return Ok(None),
+        };
+        let mut end = batch.num_rows();
+        for expr in &self.window_expr {
+            let columns = expr.sort_columns(&batch)?;
+            let last_start = if columns.is_empty() {
+                0
+            } else {
+                lexicographical_partition_ranges(&columns)?
+                    .last()
+                    .map_or(0, |r| r.start)
+            };
+            end = end.min(last_start);
+        }
+        if end == 0 {
+            return Ok(None);
+        }
+        if self.chunk_evaluators.is_none() {
+            self.chunk_evaluators = self
+                .window_expr
+                .iter()
+                .map(|expr| expr.create_chunk_evaluator())
+                .collect::<Result<Option<Vec<_>>>>()?;
+        }
+        let evaluators = match &mut self.chunk_evaluators {
+            Some(evaluators) => evaluators,
+            None => return Ok(None),
+        };
+
+        let chunk = batch.slice(0, end);
+        let mut columns = evaluators
+            .iter_mut()
+            .map(|e| e.evaluate_chunk(&chunk))
+            .collect::<Result<Vec<_>>>()?;
+        columns.extend_from_slice(chunk.columns());
+
+        // copy the rest so that the memory of the evaluated rows is released
+        let rest = batch.slice(end, batch.num_rows() - end);
+        let rest = common::combine_batches(&[rest], self.input.schema())?.unwrap();
+        self.buffer_size = batch_memory_size(&rest);
+        self.buffer = vec![rest];
+        Ok(Some(RecordBatch::try_new(self.schema.clone(), columns)?))
+    }
+
+    /// Evaluates the window functions on the rows of complete partitions
+    fn compute_output(&mut self, batches: &[RecordBatch]) -> Result<RecordBatch> {
+        let batch = common::combine_batches(batches, self.input.schema())?;
         if let Some(batch) = batch {
             // calculate window cols
-            let mut columns = compute_window_aggregates(window_expr, &batch)
-                .map_err(DataFusionError::into_arrow_external_error)?;
+            let mut columns = match self.chunk_evaluators.take() {
+                // the leading rows end the partition that was evaluated in chunks
+                Some(evaluators) => compute_window_aggregates_after_chunks(
+                    &self.window_expr,
+                    evaluators,
+                    &batch,
+                )?,
+                None => compute_window_aggregates(&self.window_expr, &batch)?,
+            };
             // combine with the original cols
             // note the setup of window aggregates is that they newly calculated window
             // expressions are always prepended to the columns
             columns.extend_from_slice(batch.columns());
-            RecordBatch::try_new(schema, columns)
+            Ok(RecordBatch::try_new(self.schema.clone(), columns)?)
         } else {
-            Ok(RecordBatch::new_empty(schema))
+            Ok(RecordBatch::new_empty(self.schema.clone()))
         }
     }
+
+    fn finish(&mut self) -> Result<RecordBatch> {
+        let batches = std::mem::take(&mut self.buffer);
+        self.buffer_size = 0;
+        self.compute_output(&batches)
+    }
 }
 
 impl Stream for WindowAggStream {
     type Item = ArrowResult<RecordBatch>;
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.finished {
-            return Poll::Ready(None);
-        }
-
-        // is the output ready?
-        let this = self.project();
-        let output_poll = this.output.poll(cx);
-
-        match output_poll {
-            Poll::Ready(result) => {
-                *this.finished = true;
-                // check for error in receiving channel and unwrap actual result
-                let result = match result {
-                    Err(e) => Some(Err(ArrowError::ExternalError(Box::new(e)))), // error receiving
-                    Ok(result) => Some(result),
-                };
-                Poll::Ready(result)
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.finished {
+                return Poll::Ready(None);
+            }
+            let result = match futures::ready!(self.input.poll_next_unpin(cx)) {
+                Some(Ok(batch)) => match self.push_batch(batch) {
+                    Ok(None) => continue,
+                    Ok(Some(output)) => Ok(output),
+                    Err(e) => Err(e.into_arrow_external_error()),
+                },
+                Some(Err(e)) => Err(e),
+                None => {
+                    self.finished = true;
+                    self.finish()
+                        .map_err(DataFusionError::into_arrow_external_error)
+                }
+            };
+            if result.is_err() {
+                self.finished = true;
             }
-            Poll::Pending => Poll::Pending,
+            return Poll::Ready(Some(result));
         }
     }
 }
@@ -28,10 +28,11 @@
 pub use crate::dataframe::DataFrame;
 pub use crate::execution::context::{ExecutionConfig, ExecutionContext};
 pub use crate::logical_plan::{
-    array, ascii, avg, bit_length, btrim, character_length, chr, col, concat, concat_ws,
-    count, create_udf, in_list, initcap, left, length, lit, lower, lpad, ltrim, max, md5,
-    min, now, octet_length, random, regexp_replace, repeat, replace, reverse, right,
-    rpad, rtrim, sha224, sha256, sha384, sha512, split_part, starts_with, strpos, substr,
-    sum, to_hex, translate, trim, upper, Column, JoinType, Partitioning,
+    any_value, array, array_agg, ascii, avg, bit_length, btrim, character_length, chr,
+    col, concat, concat_ws, count, crc32, create_udf, from_hex, in_list, initcap, left,
+    length, lit, lower, lpad, ltrim, max, md5, min, now, octet_length, random,
+    regexp_replace, repeat, replace, reverse, right, rpad, rtrim, sha1, sha2, sha224,
+    sha256, sha384, sha512, split_part, starts_with, strpos, substr, sum, to_hex,
+    translate, trim, upper, xxhash64, Column, JoinType, Partitioning,
 };
 pub use crate::physical_plan::csv::CsvReadOptions;
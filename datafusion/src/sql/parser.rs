@@ -20,7 +20,10 @@
 //! Declares a SQL parser based on sqlparser that handles custom formats that we need.
 
 use sqlparser::{
-    ast::{ColumnDef, ColumnOptionDef, Statement as SQLStatement, TableConstraint},
+    ast::{
+        ColumnDef, ColumnOptionDef, Expr as SQLExpr, Query, Statement as SQLStatement,
+        TableConstraint,
+    },
     dialect::{keywords::Keyword, Dialect, GenericDialect},
     parser::{Parser, ParserError},
     tokenizer::{Token, Tokenizer},
@@ -76,6 +79,37 @@ pub struct CreateExternalTable {
     pub location: String,
 }
 
+/// DataFusion extension DDL for `CREATE TABLE`, which creates an in-memory table
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateMemoryTable {
+    /// Table name
+    pub name: String,
+    /// Column definitions, including their `DEFAULT` values
+    pub columns: Vec<ColumnDef>,
+    /// Expressions of the columns declared `GENERATED ALWAYS AS (<expr>)`
+    pub generated_columns: Vec<(String, SQLExpr)>,
+    /// Whether `IF NOT EXISTS` was specified
+    pub if_not_exists: bool,
+    /// Query providing the rows of `CREATE TABLE ... AS <query>`
+    pub query: Option<Box<Query>>,
+}
+
+/// DataFusion extension statement `SET <variable> = <value>`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetVariable {
+    /// Fully qualified option name, e.g. `datafusion.execution.batch_size`
+    pub variable: String,
+    /// Textual value of the option
+    pub value: String,
+}
+
+/// DataFusion extension DDL for `ANALYZE`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzeTable {
+    /// Table name
+    pub table_name: String,
+}
+
 /// DataFusion Statement representations.
 ///
 /// Tokens parsed by `DFParser` are converted into these values.
@@ -85,6 +119,12 @@ pub enum Statement {
     Statement(SQLStatement),
     /// Extension: `CREATE EXTERNAL TABLE`
     CreateExternalTable(CreateExternalTable),
+    /// Extension: `CREATE TABLE` of an in-memory table
+    CreateMemoryTable(CreateMemoryTable),
+    /// Extension: `SET [SESSION | LOCAL] <variable> { = | TO } <value>`
+    SetVariable(SetVariable),
+    /// Extension: `ANALYZE [TABLE] <table>`
+    AnalyzeTable(AnalyzeTable),
 }
 
 /// SQL Parser
@@ -162,6 +202,14 @@ impl<'a> DFParser<'a> {
                         // use custom parsing
                         self.parse_create()
                     }
+                    Keyword::SET => {
+                        self.parser.next_token();
+                        self.parse_set_variable()
+                    }
+                    Keyword::ANALYZE => {
+                        self.parser.next_token();
+                        self.parse_analyze()
+                    }
                     _ => {
                         // use the native parser
                         Ok(Statement::Statement(self.parser.parse_statement()?))
@@ -179,28 +227,36 @@ impl<'a> DFParser<'a> {
     pub fn parse_create(&mut self) -> Result<Statement, ParserError> {
         if self.parser.parse_keyword(Keyword::EXTERNAL) {
             self.parse_create_external_table()
+        } else if self.parser.parse_keyword(Keyword::TABLE) {
+            self.parse_create_memory_table()
         } else {
             Ok(Statement::Statement(self.parser.parse_create()?))
         }
     }
 
     // This is a copy of the equivalent implementation in sqlparser.
+    #[allow(clippy::type_complexity)]
     fn parse_columns(
         &mut self,
-    ) -> Result<(Vec<ColumnDef>, Vec<TableConstraint>), ParserError> {
+    ) -> Result<(Vec<ColumnDef>, Vec<TableConstraint>, Vec<(String, SQLExpr)>), ParserError>
+    {
         let mut columns = vec![];
         let mut constraints = vec![];
+        let mut generated_columns = vec![];
         if !self.parser.consume_token(&Token::LParen)
             || self.parser.consume_token(&Token::RParen)
         {
-            return Ok((columns, constraints));
+            return Ok((columns, constraints, generated_columns));
         }
 
         loop {
             if let Some(constraint) = self.parser.parse_optional_table_constraint()? {
                 constraints.push(constraint);
             } else if let Token::Word(_) = self.parser.peek_token() {
-                let column_def = self.parse_column_def()?;
+                let (column_def, generated) = self.parse_column_def()?;
+                if let Some(expr) = generated {
+                    generated_columns.push((column_def.name.value.clone(), expr));
+                }
                 columns.push(column_def);
             } else {
                 return self.expected(
@@ -220,10 +276,12 @@ impl<'a> DFParser<'a> {
             }
         }
 
-        Ok((columns, constraints))
+        Ok((columns, constraints, generated_columns))
     }
 
-    fn parse_column_def(&mut self) -> Result<ColumnDef, ParserError> {
+    /// Parses a column definition and the expression of `GENERATED ALWAYS AS (<expr>)`,
+    /// which is not supported by sqlparser
+    fn parse_column_def(&mut self) -> Result<(ColumnDef, Option<SQLExpr>), ParserError> {
         let name = self.parser.parse_identifier()?;
         let data_type = self.parser.parse_data_type()?;
         let collation = if self.parser.parse_keyword(Keyword::COLLATE) {
@@ -232,8 +290,19 @@ impl<'a> DFParser<'a> {
             None
         };
         let mut options = vec![];
+        let mut generated = None;
         loop {
-            if self.parser.parse_keyword(Keyword::CONSTRAINT) {
+            if self.consume_word("GENERATED") {
+                if !self.consume_word("ALWAYS") {
+                    return self.expected("ALWAYS", self.parser.peek_token());
+                }
+                self.parser.expect_keyword(Keyword::AS)?;
+                self.parser.expect_token(&Token::LParen)?;
+                generated = Some(self.parser.parse_expr()?);
+                self.parser.expect_token(&Token::RParen)?;
+                // generated columns are always computed on insert
+                self.consume_word("STORED");
+            } else if self.parser.parse_keyword(Keyword::CONSTRAINT) {
                 let name = Some(self.parser.parse_identifier()?);
                 if let Some(option) = self.parser.parse_optional_column_option()? {
                     options.push(ColumnOptionDef { name, option });
@@ -249,18 +318,21 @@ impl<'a> DFParser<'a> {
                 break;
             };
         }
-        Ok(ColumnDef {
-            name,
-            data_type,
-            collation,
-            options,
-        })
+        Ok((
+            ColumnDef {
+                name,
+                data_type,
+                collation,
+                options,
+            },
+            generated,
+        ))
     }
 
     fn parse_create_external_table(&mut self) -> Result<Statement, ParserError> {
         self.parser.expect_keyword(Keyword::TABLE)?;
         let table_name = self.parser.parse_object_name()?;
-        let (columns, _) = self.parse_columns()?;
+        let (columns, _, _) = self.parse_columns()?;
         self.parser
             .expect_keywords(&[Keyword::STORED, Keyword::AS])?;
 
@@ -282,6 +354,69 @@ impl<'a> DFParser<'a> {
         Ok(Statement::CreateExternalTable(create))
     }
 
+    /// Parse `CREATE TABLE`, the `CREATE TABLE` keywords have already been consumed
+    fn parse_create_memory_table(&mut self) -> Result<Statement, ParserError> {
+        let if_not_exists =
+            self.parser
+                .parse_keywords(&[Keyword::IF, Keyword::NOT, Keyword::EXISTS]);
+        let table_name = self.parser.parse_object_name()?;
+        let (columns, _, generated_columns) = self.parse_columns()?;
+        let query = if self.parser.parse_keyword(Keyword::AS) {
+            Some(Box::new(self.parser.parse_query()?))
+        } else {
+            None
+        };
+
+        Ok(Statement::CreateMemoryTable(CreateMemoryTable {
+            name: table_name.to_string(),
+            columns,
+            generated_columns,
+            if_not_exists,
+            query,
+        }))
+    }
+
+    /// Parse a configuration change, the `SET` keyword has already been consumed
+    fn parse_set_variable(&mut self) -> Result<Statement, ParserError> {
+        // scope modifiers are accepted for compatibility, all settings are
+        // applied to the whole context
+        if !self.consume_word("SESSION") {
+            self.consume_word("LOCAL");
+        }
+
+        let variable = self.parser.parse_object_name()?.to_string();
+        if !self.parser.consume_token(&Token::Eq) && !self.consume_word("TO") {
+            return self.expected("'=' or TO", self.parser.peek_token());
+        }
+
+        let value = match self.parser.next_token() {
+            Token::SingleQuotedString(s) => s,
+            Token::Word(w) => w.value,
+            Token::EOF => return self.expected("a value", Token::EOF),
+            other => other.to_string(),
+        };
+
+        Ok(Statement::SetVariable(SetVariable { variable, value }))
+    }
+
+    /// Parse `ANALYZE`, the `ANALYZE` keyword has already been consumed
+    fn parse_analyze(&mut self) -> Result<Statement, ParserError> {
+        self.parser.parse_keyword(Keyword::TABLE);
+        let table_name = self.parser.parse_object_name()?.to_string();
+        Ok(Statement::AnalyzeTable(AnalyzeTable { table_name }))
+    }
+
+    /// Consumes the next token if it is a word matching `expected`, ignoring case
+    fn consume_word(&mut self, expected: &str) -> bool {
+        match self.parser.peek_token() {
+            Token::Word(w) if w.value.eq_ignore_ascii_case(expected) => {
+                self.parser.next_token();
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Parses the set of valid formats
     fn parse_file_format(&mut self) -> Result<FileType, ParserError> {
         match self.parser.next_token() {
@@ -397,4 +532,68 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn set_variable() -> Result<(), ParserError> {
+        let expected = Statement::SetVariable(SetVariable {
+            variable: "datafusion.execution.batch_size".into(),
+            value: "1024".into(),
+        });
+        expect_parse_ok(
+            "SET datafusion.execution.batch_size = 1024",
+            expected.clone(),
+        )?;
+        expect_parse_ok(
+            "SET SESSION datafusion.execution.batch_size TO '1024'",
+            expected,
+        )?;
+
+        let sql = "SET datafusion.execution.parquet_pruning false";
+        expect_parse_error(sql, "Expected '=' or TO");
+
+        Ok(())
+    }
+
+    #[test]
+    fn analyze_table() -> Result<(), ParserError> {
+        let expected = Statement::AnalyzeTable(AnalyzeTable {
+            table_name: "public.t".into(),
+        });
+        expect_parse_ok("ANALYZE public.t", expected.clone())?;
+        expect_parse_ok("ANALYZE TABLE public.t", expected)?;
+        Ok(())
+    }
+
+    #[test]
+    fn create_memory_table() -> Result<(), ParserError> {
+        let sql =
+            "CREATE TABLE t (a INT DEFAULT 0, b INT GENERATED ALWAYS AS (a + 1) STORED)";
+        let statements = DFParser::parse_sql(sql)?;
+        match &statements[0] {
+            Statement::CreateMemoryTable(create) => {
+                assert_eq!(create.name, "t");
+                assert_eq!(create.columns[0].options[0].option.to_string(), "DEFAULT 0");
+                assert!(create.columns[1].options.is_empty());
+                assert_eq!(create.generated_columns.len(), 1);
+                assert_eq!(create.generated_columns[0].0, "b");
+                assert_eq!(create.generated_columns[0].1.to_string(), "a + 1");
+                assert!(!create.if_not_exists);
+                assert!(create.query.is_none());
+            }
+            other => panic!("Unexpected statement {:?}", other),
+        }
+
+        let sql = "CREATE TABLE IF NOT EXISTS t AS SELECT 1";
+        match &DFParser::parse_sql(sql)?[0] {
+            Statement::CreateMemoryTable(create) => {
+                assert!(create.if_not_exists);
+                assert!(create.columns.is_empty());
+                assert_eq!(create.query.as_ref().unwrap().to_string(), "SELECT 1");
+            }
+            other => panic!("Unexpected statement {:?}", other),
+        }
+
+        expect_parse_error("CREATE TABLE t (a INT GENERATED AS (1))", "Expected ALWAYS");
+        Ok(())
+    }
 }
@@ -25,25 +25,34 @@ use std::{convert::TryInto, vec};
 use super::{
     parser::DFParser,
     utils::{
-        can_columns_satisfy_exprs, expr_as_column_expr, extract_aliases,
-        find_aggregate_exprs, find_column_exprs, find_window_exprs,
-        group_window_expr_by_sort_keys, rebase_expr, resolve_aliases_to_exprs,
-        resolve_positions_to_exprs,
+        can_columns_satisfy_exprs, clone_with_replacement, cube_sets,
+        expr_as_column_expr, extract_aliases, find_aggregate_exprs, find_column_exprs,
+        find_window_exprs, group_window_expr_by_sort_keys, grouping_id,
+        grouping_set_function, is_grouping, rebase_expr, resolve_aliases_to_exprs,
+        resolve_positions_to_exprs, rollup_sets,
     },
 };
+use crate::arrow_print::NonFiniteFloats;
 use crate::catalog::TableReference;
 use crate::cube_ext::alias::LogicalAlias;
 use crate::cube_ext::join::contains_table_scan;
-use crate::datasource::TableProvider;
+use crate::cube_ext::temporal::Zone;
+use crate::datasource::analyzed::AnalyzedTable;
+use crate::datasource::{MemTable, TableProvider};
 use crate::logical_plan::window_frames::{
     check_window_bound_order, WindowFrame, WindowFrameBound, WindowFrameUnits,
 };
 use crate::logical_plan::Expr::Alias;
 use crate::logical_plan::{
-    and, builder::expand_wildcard, col, lit, normalize_col, union_with_alias, Column,
-    DFSchema, Expr, LogicalPlan, LogicalPlanBuilder, Operator, PlanType, ToDFSchema,
-    ToStringifiedPlan,
+    and, builder::expand_wildcard, col, collate, lit, min, normalize_col, replace_col,
+    union_distinct_with_alias, union_with_alias, Column, DFSchema, Expr, LogicalPlan,
+    LogicalPlanBuilder, Operator, PlanType, ToDFSchema, ToStringifiedPlan,
 };
+use crate::optimizer::utils::expr_to_columns;
+use crate::physical_plan::expressions::{
+    binary_operator_data_type, promoted_type, union_coercion, ArithmeticOverflow,
+};
+use crate::physical_plan::string_expressions::Collation;
 use crate::prelude::JoinType;
 use crate::scalar::ScalarValue;
 use crate::sql::utils::find_rolling_aggregate_exprs;
@@ -51,12 +60,27 @@ use crate::{
     error::{DataFusionError, Result},
     physical_plan::udaf::AggregateUDF,
 };
+use crate::{
+    execution::config::{
+        parse_function_search_path, ConfigOptions, OPT_ARITHMETIC_OVERFLOW,
+        OPT_COLLATION, OPT_FUNCTION_SEARCH_PATH, OPT_NON_FINITE_FLOATS, OPT_TIME_ZONE,
+    },
+    logical_plan::DFSchemaRef,
+};
 use crate::{
     physical_plan::udf::ScalarUDF,
-    physical_plan::{aggregates, functions, window_functions},
-    sql::parser::{CreateExternalTable, FileType, Statement as DFStatement},
+    physical_plan::{
+        aggregates, functions, higher_order_functions::HigherOrderFunction,
+        window_functions,
+    },
+    sql::parser::{
+        AnalyzeTable, CreateExternalTable, CreateMemoryTable, FileType, SetVariable,
+        Statement as DFStatement,
+    },
 };
+use arrow::array::{ArrayRef, StringArray};
 use arrow::datatypes::*;
+use arrow::record_batch::RecordBatch;
 use hashbrown::HashMap;
 use itertools::Itertools;
 use sqlparser::ast::{
@@ -78,6 +102,10 @@ pub trait ContextProvider {
     fn get_function_meta(&self, name: &str) -> Option<Arc<ScalarUDF>>;
     /// Getter for a UDAF description
     fn get_aggregate_meta(&self, name: &str) -> Option<Arc<AggregateUDF>>;
+    /// Getter for the runtime configuration, used to answer `SHOW` statements
+    fn get_config_options(&self) -> Option<ConfigOptions> {
+        None
+    }
 }
 
 /// SQL query planner
@@ -91,6 +119,10 @@ const DEFAULT_NULLS_FIRST: bool = false;
 #[cfg(not(feature = "default_nulls_last"))]
 const DEFAULT_NULLS_FIRST: bool = true;
 
+/// The largest number of grouping sets of a GROUP BY, every set aggregates the input
+/// of the query once more
+const MAX_GROUPING_SETS: usize = 4096;
+
 impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     /// Create a new query planner
     pub fn new(schema_provider: &'a S) -> Self {
@@ -101,6 +133,9 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     pub fn statement_to_plan(&self, statement: &DFStatement) -> Result<LogicalPlan> {
         match statement {
             DFStatement::CreateExternalTable(s) => self.external_table_to_plan(s),
+            DFStatement::CreateMemoryTable(s) => self.memory_table_to_plan(s),
+            DFStatement::SetVariable(s) => self.set_variable_to_plan(s),
+            DFStatement::AnalyzeTable(s) => self.analyze_table_to_plan(s),
             DFStatement::Statement(s) => self.sql_statement_to_plan(s),
         }
     }
@@ -114,6 +149,12 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 analyze: _,
             } => self.explain_statement_to_plan(*verbose, statement),
             Statement::Query(query) => self.query_to_plan(query),
+            Statement::Insert {
+                table_name,
+                columns,
+                source,
+                ..
+            } => self.insert_to_plan(table_name, columns, source),
             Statement::ShowVariable { variable } => self.show_variable_to_plan(variable),
             Statement::ShowColumns {
                 extended,
@@ -191,8 +232,13 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                     let right_plan = self.set_expr_to_plan(right.as_ref(), None, ctes)?;
                     union_with_alias(left_plan, right_plan, alias)
                 }
+                (SetOperator::Union, false) => {
+                    let left_plan = self.set_expr_to_plan(left.as_ref(), None, ctes)?;
+                    let right_plan = self.set_expr_to_plan(right.as_ref(), None, ctes)?;
+                    union_distinct_with_alias(left_plan, right_plan, alias)
+                }
                 _ => Err(DataFusionError::NotImplemented(format!(
-                    "Only UNION ALL is supported, found {}",
+                    "Only UNION and UNION ALL are supported, found {}",
                     op
                 ))),
             },
@@ -248,6 +294,268 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         })
     }
 
+    /// Generate a logical plan from a CREATE TABLE statement
+    pub fn memory_table_to_plan(
+        &self,
+        statement: &CreateMemoryTable,
+    ) -> Result<LogicalPlan> {
+        let CreateMemoryTable {
+            name,
+            columns,
+            generated_columns,
+            if_not_exists,
+            query,
+        } = statement;
+
+        let input = match query {
+            Some(_) if !columns.is_empty() => {
+                return Err(DataFusionError::NotImplemented(
+                    "Column definitions are not supported with CREATE TABLE ... AS"
+                        .to_string(),
+                ))
+            }
+            Some(query) => Some(Arc::new(self.query_to_plan(query)?)),
+            None => None,
+        };
+        let schema: Schema = match &input {
+            Some(input) => input.schema().as_ref().clone().into(),
+            None => {
+                // unlike external tables, columns are nullable unless declared NOT NULL
+                let mut fields = Vec::with_capacity(columns.len());
+                for column in columns {
+                    let allow_null = !column
+                        .options
+                        .iter()
+                        .any(|x| x.option == ColumnOption::NotNull);
+                    fields.push(Field::new(
+                        &column.name.value,
+                        self.make_data_type(&column.data_type)?,
+                        allow_null,
+                    ));
+                }
+                Schema::new(fields)
+            }
+        };
+        let schema = schema.to_dfschema_ref()?;
+
+        let mut column_defaults = Vec::new();
+        for column in columns {
+            for option in &column.options {
+                if let ColumnOption::Default(expr) = &option.option {
+                    column_defaults.push((
+                        column.name.value.clone(),
+                        self.sql_to_rex(expr, &DFSchema::empty())?,
+                    ));
+                }
+            }
+        }
+
+        let generated_columns = generated_columns
+            .iter()
+            .map(|(column, expr)| {
+                let expr = self.sql_to_rex(expr, &schema)?;
+                let mut referenced = HashSet::new();
+                expr_to_columns(&expr, &mut referenced)?;
+                for c in referenced {
+                    if generated_columns.iter().any(|(g, _)| g == &c.name) {
+                        return Err(DataFusionError::Plan(format!(
+                            "Generated column {} cannot refer to generated column {}",
+                            column, c.name
+                        )));
+                    }
+                }
+                Ok((column.clone(), expr))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(LogicalPlan::CreateMemoryTable {
+            name: name.clone(),
+            schema,
+            column_defaults,
+            generated_columns,
+            if_not_exists: *if_not_exists,
+            input,
+        })
+    }
+
+    /// Generate a logical plan from an INSERT statement. The rows of `source` get the
+    /// default values of the omitted columns and the values of the generated columns
+    /// of in-memory tables.
+    fn insert_to_plan(
+        &self,
+        table_name: &ObjectName,
+        columns: &[Ident],
+        source: &Query,
+    ) -> Result<LogicalPlan> {
+        let name = table_name.to_string();
+        let table = self
+            .schema_provider
+            .get_table_provider(table_name.try_into()?)
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "Table or CTE with name '{}' not found",
+                    name
+                ))
+            })?;
+        let table_schema = table.schema();
+        let memory_table = match table.as_any().downcast_ref::<AnalyzedTable>() {
+            Some(analyzed) => analyzed.source().as_any().downcast_ref::<MemTable>(),
+            None => table.as_any().downcast_ref::<MemTable>(),
+        };
+        let generated =
+            |column: &str| memory_table.and_then(|t| t.generated_column(column));
+
+        let target_columns = if columns.is_empty() {
+            table_schema
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .filter(|c| generated(c.as_str()).is_none())
+                .collect::<Vec<_>>()
+        } else {
+            columns.iter().map(|c| c.value.clone()).collect()
+        };
+        for column in &target_columns {
+            if table_schema.field_with_name(column).is_err() {
+                return Err(DataFusionError::Plan(format!(
+                    "Column {} not found in table {}",
+                    column, name
+                )));
+            }
+            if generated(column.as_str()).is_some() {
+                return Err(DataFusionError::Plan(format!(
+                    "Cannot insert into generated column {}",
+                    column
+                )));
+            }
+        }
+
+        let source = self.query_to_plan(source)?;
+        let source_fields = source.schema().fields().clone();
+        if source_fields.len() != target_columns.len() {
+            return Err(DataFusionError::Plan(format!(
+                "INSERT has {} target columns but the query produces {} columns",
+                target_columns.len(),
+                source_fields.len()
+            )));
+        }
+        // name the columns of the query after the columns they are inserted into
+        let plan = LogicalPlanBuilder::from(source)
+            .project(
+                source_fields
+                    .iter()
+                    .zip(target_columns.iter())
+                    .map(|(f, c)| Expr::Column(f.qualified_column()).alias(c)),
+            )?
+            .build()?;
+
+        // complete the omitted columns, then compute the generated ones from them
+        let column_expr =
+            |expr: Expr, field: &Field, plan: &LogicalPlan| -> Result<Expr> {
+                let expr = if &expr.get_type(plan.schema())? == field.data_type() {
+                    expr
+                } else {
+                    Expr::Cast {
+                        expr: Box::new(expr),
+                        data_type: field.data_type().clone(),
+                    }
+                };
+                Ok(match expr {
+                    Expr::Column(_) => expr,
+                    _ => expr.alias(field.name()),
+                })
+            };
+        let mut stored = Vec::new();
+        for field in table_schema.fields() {
+            let column = field.name();
+            if generated(column.as_str()).is_some() {
+                continue;
+            }
+            let expr = if target_columns.contains(column) {
+                col(column)
+            } else if let Some(default) =
+                memory_table.and_then(|t| t.column_default(column))
+            {
+                default.clone()
+            } else if field.is_nullable() {
+                Expr::Literal(ScalarValue::try_from(field.data_type())?)
+            } else {
+                return Err(DataFusionError::Plan(format!(
+                    "Column {} of table {} is not nullable and has no default value",
+                    column, name
+                )));
+            };
+            stored.push(column_expr(expr, field, &plan)?);
+        }
+        let plan = LogicalPlanBuilder::from(plan).project(stored)?.build()?;
+
+        let mut all = Vec::new();
+        for field in table_schema.fields() {
+            let column = field.name();
+            all.push(match generated(column.as_str()) {
+                Some(expr) => column_expr(expr.clone(), field, &plan)?,
+                None => col(column),
+            });
+        }
+        let plan = LogicalPlanBuilder::from(plan).project(all)?.build()?;
+
+        Ok(LogicalPlan::Insert {
+            table_name: name,
+            table,
+            input: Arc::new(plan),
+            create_table: false,
+            schema: LogicalPlan::insert_schema().to_dfschema_ref()?,
+        })
+    }
+
+    /// Generate a logical plan from a SET statement
+    pub fn set_variable_to_plan(&self, statement: &SetVariable) -> Result<LogicalPlan> {
+        let SetVariable { variable, value } = statement;
+
+        // validate the value early so that typos are reported at planning time
+        if let Some(mut options) = self.schema_provider.get_config_options() {
+            options.set_str(variable, value)?;
+        }
+        if variable.eq_ignore_ascii_case(OPT_COLLATION) {
+            Collation::from_str(value)?;
+        }
+        if variable.eq_ignore_ascii_case(OPT_TIME_ZONE) {
+            Zone::parse(value)?;
+        }
+        if variable.eq_ignore_ascii_case(OPT_ARITHMETIC_OVERFLOW) {
+            ArithmeticOverflow::from_str(value)?;
+        }
+        if variable.eq_ignore_ascii_case(OPT_NON_FINITE_FLOATS) {
+            NonFiniteFloats::from_str(value)?;
+        }
+
+        Ok(LogicalPlan::SetVariable {
+            variable: variable.to_lowercase(),
+            value: value.clone(),
+            schema: DFSchemaRef::new(DFSchema::empty()),
+        })
+    }
+
+    /// Generate a logical plan from an ANALYZE statement
+    pub fn analyze_table_to_plan(&self, statement: &AnalyzeTable) -> Result<LogicalPlan> {
+        let AnalyzeTable { table_name } = statement;
+        let table = self
+            .schema_provider
+            .get_table_provider(table_name.as_str().into())
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "Table or CTE with name '{}' not found",
+                    table_name
+                ))
+            })?;
+
+        Ok(LogicalPlan::Analyze {
+            table_name: table_name.clone(),
+            table,
+            schema: LogicalPlan::analyze_schema().to_dfschema_ref()?,
+        })
+    }
+
     /// Generate a plan for EXPLAIN ... that will print out a plan
     ///
     pub fn explain_statement_to_plan(
@@ -384,7 +692,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     ) -> Result<LogicalPlan> {
         match constraint {
             JoinConstraint::On(sql_expr) => {
-                let mut keys: Vec<(Column, Column)> = vec![];
+                let mut keys: Vec<(Expr, Expr)> = vec![];
                 let left_schema = left.schema();
                 let right_schema = right.schema();
                 let join_schema = left_schema.join(right_schema)?;
@@ -402,7 +710,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                     right_schema,
                     &mut keys,
                     &mut filter,
-                );
+                )?;
                 if !filter.is_empty() && !contains_table_scan(&left) {
                     // Complex condition, try cross join. We still prefer to **not** allow cross
                     // joins in general case to avoid abysmal performance.
@@ -412,17 +720,33 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                         .skewed_left_cross_join(right, &expr)?
                         .build();
                 }
-                let (left_keys, right_keys): (Vec<Column>, Vec<Column>) =
+
+                // Keys that are not plain columns are computed by projections on top of
+                // the inputs and removed from the output after the join.
+                let output_fields = match join_type {
+                    JoinType::Semi | JoinType::Anti => left_schema.fields(),
+                    _ => join_schema.fields(),
+                };
+                let output_columns = output_fields
+                    .iter()
+                    .map(|f| Expr::Column(f.qualified_column()))
+                    .collect::<Vec<_>>();
+                let (left_keys, right_keys): (Vec<Expr>, Vec<Expr>) =
                     keys.into_iter().unzip();
+                let (left, left_keys, left_projected) =
+                    project_join_keys(left, left_keys, "__join_key_l")?;
+                let (right, right_keys, right_projected) =
+                    project_join_keys(right.clone(), right_keys, "__join_key_r")?;
+
                 // return the logical plan representing the join
                 let join = LogicalPlanBuilder::from(left).join(
-                    right,
+                    &right,
                     join_type,
                     (left_keys, right_keys),
                 )?;
 
-                if filter.is_empty() {
-                    join.build()
+                let join = if filter.is_empty() {
+                    join
                 } else if join_type == JoinType::Inner {
                     join.filter(
                         filter
@@ -430,12 +754,17 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                             .skip(1)
                             .fold(filter[0].clone(), |acc, e| acc.and(e.clone())),
                     )?
-                    .build()
                 } else {
-                    Err(DataFusionError::NotImplemented(format!(
+                    return Err(DataFusionError::NotImplemented(format!(
                         "Unsupported expressions in {:?} JOIN: {:?}",
                         join_type, filter
-                    )))
+                    )));
+                };
+
+                if left_projected || right_projected {
+                    join.project(output_columns)?.build()
+                } else {
+                    join.build()
                 }
             }
             JoinConstraint::Using(idents) => {
@@ -549,7 +878,14 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     ) -> Result<LogicalPlan> {
         let plans = self.plan_from_tables(&select.from, ctes)?;
 
-        let plan = match &select.selection {
+        // `IN (subquery)` conjuncts of the predicate are planned as semi joins
+        let mut in_subqueries = vec![];
+        let selection = select
+            .selection
+            .as_ref()
+            .and_then(|p| split_in_subqueries(p, &mut in_subqueries));
+
+        let plan = match &selection {
             Some(predicate_expr) => {
                 // build join schema
                 let mut fields = vec![];
@@ -619,7 +955,15 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 }
             }
         };
-        let plan = plan?;
+        let mut plan = plan?;
+
+        for (expr, subquery) in &in_subqueries {
+            let expr = self.sql_to_rex(expr, plan.schema())?;
+            let subquery = self.query_to_plan_with_alias(subquery, None, ctes)?;
+            plan = LogicalPlanBuilder::from(plan)
+                .filter_in_subquery(vec![expr], &subquery)?
+                .build()?;
+        }
 
         // The SELECT expressions, with wildcards expanded.
         let select_exprs = self.prepare_select_exprs(&plan, &select.projection)?;
@@ -637,9 +981,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             .having
             .as_ref()
             .map::<Result<Expr>, _>(|having_expr| {
-                // CubeStore: the schema does not see aliases from expression. We do this to support
-                // queries of the form `SELECT sum(n) as n … HAVING sum(n) < 10`, sent by CubeJS.
-                let mut having_expr = self.sql_to_rex(having_expr, plan.schema())?;
+                let having_expr = self.sql_to_rex(having_expr, &combined_schema)?;
                 // This step "dereferences" any aliases in the HAVING clause.
                 //
                 // This is how we support queries with HAVING expressions that
@@ -655,10 +997,22 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 //   SELECT c1 AS m FROM t HAVING c1 > 10;
                 //   SELECT c1, MAX(c2) AS m FROM t GROUP BY c1 HAVING MAX(c2) > 10;
                 //
-                if false {
-                    // Disabled in CubeStore.
-                    having_expr = resolve_aliases_to_exprs(&having_expr, &alias_map)?
-                }
+                // The columns of the input take precedence over the aliases. CubeStore
+                // relies on this for queries of the form
+                // `SELECT sum(n) as n … HAVING sum(n) < 10`, sent by CubeJS.
+                let having_aliases = alias_map
+                    .iter()
+                    .filter(|(name, _)| {
+                        !plan.schema().fields().iter().any(|f| f.name() == *name)
+                    })
+                    .map(|(name, expr)| (name.clone(), expr.clone()))
+                    .collect();
+                let having_expr =
+                    resolve_aliases_to_exprs(&having_expr, &having_aliases)?;
+                self.validate_schema_satisfies_exprs(
+                    plan.schema(),
+                    &[having_expr.clone()],
+                )?;
                 normalize_col(having_expr, &plan)
             })
             .transpose()?;
@@ -674,23 +1028,69 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         // All of the aggregate expressions (deduplicated).
         let aggr_exprs = find_aggregate_exprs(&aggr_expr_haystack);
 
-        let group_by_exprs = select
-            .group_by
-            .iter()
-            .map(|e| {
-                let group_by_expr = self.sql_expr_to_logical_expr(e, &combined_schema)?;
-                let group_by_expr = resolve_aliases_to_exprs(&group_by_expr, &alias_map)?;
-                let group_by_expr =
-                    resolve_positions_to_exprs(&group_by_expr, &select_exprs)
-                        .unwrap_or(group_by_expr);
-                let group_by_expr = normalize_col(group_by_expr, &projected_plan)?;
-                self.validate_schema_satisfies_exprs(
-                    plan.schema(),
-                    &[group_by_expr.clone()],
-                )?;
-                Ok(group_by_expr)
-            })
-            .collect::<Result<Vec<Expr>>>()?;
+        let group_by_expr = |e: &SQLExpr| -> Result<Expr> {
+            let group_by_expr = self.sql_expr_to_logical_expr(e, &combined_schema)?;
+            let group_by_expr = resolve_aliases_to_exprs(&group_by_expr, &alias_map)?;
+            let group_by_expr = resolve_positions_to_exprs(&group_by_expr, &select_exprs)
+                .unwrap_or(group_by_expr);
+            let group_by_expr = normalize_col(group_by_expr, &projected_plan)?;
+            self.validate_schema_satisfies_exprs(
+                plan.schema(),
+                &[group_by_expr.clone()],
+            )?;
+            Ok(group_by_expr)
+        };
+
+        // the sets of keys to group by, `ROLLUP(a, b)` and `CUBE(a, b)` items stand for
+        // several sets that are combined with the ones of the other items
+        let mut grouping_sets: Vec<Vec<Expr>> = vec![vec![]];
+        for e in &select.group_by {
+            let item_sets = match grouping_set_function(e) {
+                Some((name, args)) => {
+                    let keys = args
+                        .iter()
+                        .map(|arg| match arg {
+                            FunctionArg::Named { arg, .. }
+                            | FunctionArg::Unnamed(arg) => group_by_expr(arg),
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    let item_sets = if name == "rollup" {
+                        keys.len() + 1
+                    } else {
+                        1usize.checked_shl(keys.len() as u32).unwrap_or(usize::MAX)
+                    };
+                    let sets = grouping_sets.len().saturating_mul(item_sets);
+                    if sets > MAX_GROUPING_SETS {
+                        return Err(DataFusionError::Plan(format!(
+                            "GROUP BY has {} grouping sets, at most {} are supported",
+                            sets, MAX_GROUPING_SETS
+                        )));
+                    }
+                    if name == "rollup" {
+                        rollup_sets(&keys)
+                    } else {
+                        cube_sets(&keys)
+                    }
+                }
+                None => vec![vec![group_by_expr(e)?]],
+            };
+            grouping_sets = grouping_sets
+                .iter()
+                .flat_map(|set| {
+                    item_sets.iter().map(move |item| {
+                        let mut set = set.clone();
+                        for e in item {
+                            if !set.contains(e) {
+                                set.push(e.clone());
+                            }
+                        }
+                        set
+                    })
+                })
+                .collect();
+        }
+        // the first set has all the keys
+        let group_by_exprs = grouping_sets[0].clone();
 
         // CubeStore extension: rolling window
         let rolling_aggs = find_rolling_aggregate_exprs(&select_exprs);
@@ -779,37 +1179,46 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             }
         };
 
-        let (plan, select_exprs_post_aggr, having_expr_post_aggr_opt) = if !group_by_exprs
-            .is_empty()
-            || !aggr_exprs.is_empty()
-        {
-            self.aggregate(
-                plan,
-                &select_exprs,
-                &having_expr_opt,
-                group_by_exprs,
-                aggr_exprs,
-            )?
-        } else {
-            if let Some(having_expr) = &having_expr_opt {
-                let available_columns = select_exprs
-                    .iter()
-                    .map(|expr| expr_as_column_expr(expr, &plan))
-                    .collect::<Result<Vec<Expr>>>()?;
-
-                // Ensure the HAVING expression is using only columns
-                // provided by the SELECT.
-                if !can_columns_satisfy_exprs(&available_columns, &[having_expr.clone()])?
-                {
-                    return Err(DataFusionError::Plan(
-                        "Having references column(s) not provided by the select"
-                            .to_owned(),
-                    ));
+        let (plan, select_exprs_post_aggr, having_expr_post_aggr_opt) =
+            if grouping_sets.len() > 1 || aggr_exprs.iter().any(is_grouping) {
+                self.grouping_sets_aggregate(
+                    plan,
+                    &select_exprs,
+                    &having_expr_opt,
+                    group_by_exprs,
+                    grouping_sets,
+                    aggr_exprs,
+                )?
+            } else if !group_by_exprs.is_empty() || !aggr_exprs.is_empty() {
+                self.collated_aggregate(
+                    plan,
+                    &select_exprs,
+                    &having_expr_opt,
+                    group_by_exprs,
+                    aggr_exprs,
+                )?
+            } else {
+                if let Some(having_expr) = &having_expr_opt {
+                    let available_columns = select_exprs
+                        .iter()
+                        .map(|expr| expr_as_column_expr(expr, &plan))
+                        .collect::<Result<Vec<Expr>>>()?;
+
+                    // Ensure the HAVING expression is using only columns
+                    // provided by the SELECT.
+                    if !can_columns_satisfy_exprs(
+                        &available_columns,
+                        &[having_expr.clone()],
+                    )? {
+                        return Err(DataFusionError::Plan(
+                            "Having references column(s) not provided by the select"
+                                .to_owned(),
+                        ));
+                    }
                 }
-            }
 
-            (plan, select_exprs, having_expr_opt)
-        };
+                (plan, select_exprs, having_expr_opt)
+            };
 
         let plan = if let Some(having_expr_post_aggr) = having_expr_post_aggr_opt {
             LogicalPlanBuilder::from(plan)
@@ -829,9 +1238,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         };
 
         let plan = if select.distinct {
-            return LogicalPlanBuilder::from(plan)
-                .aggregate(select_exprs_post_aggr, vec![])?
-                .build();
+            return self.distinct(plan, select_exprs_post_aggr);
         } else {
             plan
         };
@@ -875,6 +1282,77 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
     /// Wrap a plan in a window
     fn window(&self, input: LogicalPlan, window_exprs: Vec<Expr>) -> Result<LogicalPlan> {
+        // the partition and sort keys that are not columns are computed by a hidden
+        // projection below the windows, so that they are evaluated once
+        let mut key_exprs: Vec<Expr> = vec![];
+        for e in &window_exprs {
+            if let Expr::WindowFunction {
+                partition_by,
+                order_by,
+                ..
+            } = e
+            {
+                let sort_exprs = order_by.iter().map(|e| match e {
+                    Expr::Sort { expr, .. } => expr.as_ref(),
+                    e => e,
+                });
+                for key in partition_by.iter().chain(sort_exprs) {
+                    if !matches!(key, Expr::Column(_) | Expr::Literal(_))
+                        && !key_exprs.contains(key)
+                    {
+                        key_exprs.push(key.clone());
+                    }
+                }
+            }
+        }
+        if key_exprs.is_empty() {
+            return self.window_groups(input, window_exprs);
+        }
+
+        let input_columns = input
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| Expr::Column(f.qualified_column()))
+            .collect::<Vec<_>>();
+        let mut hidden_exprs = input_columns.clone();
+        for e in &key_exprs {
+            hidden_exprs.push(e.clone().alias(&e.name(input.schema())?));
+        }
+        let hidden_projection = LogicalPlanBuilder::from(input.clone())
+            .project(hidden_exprs)?
+            .build()?;
+
+        let keyed_window_exprs = window_exprs
+            .iter()
+            .map(|e| rebase_expr(e, &key_exprs, &input))
+            .collect::<Result<Vec<_>>>()?;
+        let plan = self.window_groups(hidden_projection, keyed_window_exprs.clone())?;
+
+        // remove the hidden columns and restore the names of the window expressions
+        let mut cleanup_exprs = vec![];
+        for (keyed, e) in keyed_window_exprs.iter().zip(&window_exprs) {
+            let name = e.name(input.schema())?;
+            let column = expr_as_column_expr(keyed, &plan)?;
+            cleanup_exprs.push(if column.name(plan.schema())? == name {
+                column
+            } else {
+                column.alias(&name)
+            });
+        }
+        cleanup_exprs.extend(input_columns);
+        LogicalPlanBuilder::from(plan)
+            .project(cleanup_exprs)?
+            .build()
+    }
+
+    /// Wrap a plan in the windows of the expressions, nesting the ones with the same
+    /// sort keys in the same window
+    fn window_groups(
+        &self,
+        input: LogicalPlan,
+        window_exprs: Vec<Expr>,
+    ) -> Result<LogicalPlan> {
         let mut plan = input;
         let mut groups = group_window_expr_by_sort_keys(&window_exprs)?;
         // sort by sort_key len descending, so that more deeply sorted plans gets nested further
@@ -957,6 +1435,252 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         Ok((plan, select_exprs_post_aggr, having_expr_post_aggr_opt))
     }
 
+    /// Wrap a plan in an aggregate whose string keys are compared under the session
+    /// collation. Each group shows the smallest original value of such a key, in
+    /// bytes, which replaces the key in the SELECT and HAVING expressions.
+    fn collated_aggregate(
+        &self,
+        input: LogicalPlan,
+        select_exprs: &[Expr],
+        having_expr_opt: &Option<Expr>,
+        group_by_exprs: Vec<Expr>,
+        mut aggr_exprs: Vec<Expr>,
+    ) -> Result<(LogicalPlan, Vec<Expr>, Option<Expr>)> {
+        let collation = self.session_collation()?;
+        let schema = input.schema().clone();
+        let mut collated_group_by_exprs = Vec::with_capacity(group_by_exprs.len());
+        let mut representatives = vec![];
+        for e in group_by_exprs {
+            if collation != Collation::Binary
+                && explicit_collation(&e)?.is_none()
+                && is_string_expr(&e, &schema)
+            {
+                let representative = min(e.clone());
+                if !aggr_exprs.contains(&representative) {
+                    aggr_exprs.push(representative.clone());
+                }
+                collated_group_by_exprs.push(collate(e.clone(), collation));
+                representatives.push((e, representative));
+            } else {
+                collated_group_by_exprs.push(e);
+            }
+        }
+        if representatives.is_empty() {
+            return self.aggregate(
+                input,
+                select_exprs,
+                having_expr_opt,
+                collated_group_by_exprs,
+                aggr_exprs,
+            );
+        }
+
+        let represent = |expr: &Expr| {
+            clone_with_replacement(expr, &|nested| {
+                Ok(match nested {
+                    // aggregates are computed from the original values
+                    Expr::AggregateFunction { .. } | Expr::AggregateUDF { .. } => {
+                        Some(nested.clone())
+                    }
+                    _ => representatives
+                        .iter()
+                        .find(|(key, _)| key == nested)
+                        .map(|(_, representative)| representative.clone()),
+                })
+            })
+        };
+        let select_exprs = select_exprs
+            .iter()
+            .map(|e| {
+                let represented = represent(e)?;
+                Ok(match e {
+                    _ if represented == *e => represented,
+                    Expr::Alias(..) => represented,
+                    Expr::Column(c) => represented.alias(&c.name),
+                    _ => represented.alias(&e.name(&schema)?),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let having_expr_opt = having_expr_opt.as_ref().map(represent).transpose()?;
+        self.aggregate(
+            input,
+            &select_exprs,
+            &having_expr_opt,
+            collated_group_by_exprs,
+            aggr_exprs,
+        )
+    }
+
+    /// Wrap a plan in the aggregate of SELECT DISTINCT, which compares the string
+    /// expressions under the session collation and shows the smallest original value,
+    /// in bytes, of each
+    fn distinct(
+        &self,
+        input: LogicalPlan,
+        select_exprs: Vec<Expr>,
+    ) -> Result<LogicalPlan> {
+        let collation = self.session_collation()?;
+        let schema = input.schema().clone();
+        let mut group_by_exprs = Vec::with_capacity(select_exprs.len());
+        let mut aggr_exprs = vec![];
+        let mut projection = Vec::with_capacity(select_exprs.len());
+        for e in select_exprs {
+            let (value, name) = match &e {
+                Expr::Alias(value, name) => (value.as_ref(), name.clone()),
+                Expr::Column(c) => (&e, c.name.clone()),
+                _ => (&e, e.name(&schema)?),
+            };
+            if collation != Collation::Binary
+                && explicit_collation(value)?.is_none()
+                && is_string_expr(value, &schema)
+            {
+                group_by_exprs.push(collate(value.clone(), collation));
+                let representative = min(value.clone());
+                aggr_exprs.push(representative.clone());
+                projection.push(representative.alias(&name));
+            } else {
+                group_by_exprs.push(e.clone());
+                projection.push(e);
+            }
+        }
+        if aggr_exprs.is_empty() {
+            return LogicalPlanBuilder::from(input)
+                .aggregate(group_by_exprs, vec![])?
+                .build();
+        }
+
+        let aggr_projection_exprs = group_by_exprs
+            .iter()
+            .chain(aggr_exprs.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        let projection = projection
+            .iter()
+            .map(|e| rebase_expr(e, &aggr_projection_exprs, &input))
+            .collect::<Result<Vec<_>>>()?;
+        LogicalPlanBuilder::from(input)
+            .aggregate(group_by_exprs, aggr_exprs)?
+            .project(projection)?
+            .build()
+    }
+
+    /// Wrap a plan in the union of the aggregates of the grouping sets, the keys that
+    /// are not in a set are null in the rows of its aggregate. Every set aggregates
+    /// `input` on its own, a query with n sets computes its input n times, hence the
+    /// limit of [MAX_GROUPING_SETS].
+    fn grouping_sets_aggregate(
+        &self,
+        input: LogicalPlan,
+        select_exprs: &[Expr],
+        having_expr_opt: &Option<Expr>,
+        group_by_exprs: Vec<Expr>,
+        grouping_sets: Vec<Vec<Expr>>,
+        aggr_exprs: Vec<Expr>,
+    ) -> Result<(LogicalPlan, Vec<Expr>, Option<Expr>)> {
+        for e in aggr_exprs.iter().filter(|e| is_grouping(e)) {
+            if let Expr::AggregateFunction { args, .. } = e {
+                if args.is_empty() || args.iter().any(|a| !group_by_exprs.contains(a)) {
+                    return Err(DataFusionError::Plan(format!(
+                        "Arguments of {:?} must be GROUP BY expressions",
+                        e
+                    )));
+                }
+            }
+        }
+
+        // the aggregate by all the keys gives the columns of the union
+        let (plan, select_exprs_post_aggr, having_expr_post_aggr_opt) = self.aggregate(
+            input.clone(),
+            select_exprs,
+            having_expr_opt,
+            group_by_exprs.clone(),
+            aggr_exprs.clone(),
+        )?;
+        let schema = plan.schema().clone();
+
+        // the union removes the qualifiers of the columns, they are named by their
+        // qualified names instead
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|f| {
+                let column = f.qualified_column();
+                let renamed = Column::from_name(column.flat_name());
+                (column, renamed)
+            })
+            .collect::<Vec<_>>();
+        let rename = |e: Expr| {
+            let columns = columns
+                .iter()
+                .map(|(c, renamed)| (c, renamed))
+                .collect::<std::collections::HashMap<_, _>>();
+            replace_col(e, &columns)
+        };
+
+        let mut union: Option<LogicalPlan> = None;
+        // the union takes the nullability of the columns of its first input, which is
+        // the aggregate with the fewest keys
+        for set in grouping_sets.iter().rev() {
+            // GROUPING is not aggregated, it is the same for all the rows of a set
+            let set_aggr_exprs = aggr_exprs
+                .iter()
+                .filter(|e| !is_grouping(e))
+                .cloned()
+                .collect::<Vec<_>>();
+            let aggregate = LogicalPlanBuilder::from(input.clone())
+                .aggregate(set.clone(), set_aggr_exprs)?
+                .build()?;
+            let mut exprs = vec![];
+            for (i, field) in schema.fields().iter().enumerate() {
+                let name = field.qualified_column().flat_name();
+                let e = match group_by_exprs.get(i) {
+                    Some(key) if !set.contains(key) => {
+                        Expr::Literal(ScalarValue::try_from(field.data_type())?)
+                    }
+                    Some(key) => expr_as_column_expr(key, &input)?,
+                    None => match &aggr_exprs[i - group_by_exprs.len()] {
+                        Expr::AggregateFunction {
+                            fun: aggregates::AggregateFunction::Grouping,
+                            args,
+                            ..
+                        } => Expr::Literal(ScalarValue::Int32(Some(grouping_id(
+                            args, set,
+                        )))),
+                        aggr_expr => expr_as_column_expr(aggr_expr, &input)?,
+                    },
+                };
+                exprs.push(match &e {
+                    Expr::Column(c) if c.relation.is_none() && c.name == name => e,
+                    _ => e.alias(&name),
+                });
+            }
+            let branch = LogicalPlanBuilder::from(aggregate)
+                .project(exprs)?
+                .build()?;
+            union = Some(match union {
+                Some(union) => union_with_alias(union, branch, None)?,
+                None => branch,
+            });
+        }
+        let plan = union.ok_or_else(|| {
+            DataFusionError::Internal("Grouping sets without sets".to_string())
+        })?;
+
+        let select_exprs_post_aggr = select_exprs_post_aggr
+            .into_iter()
+            .map(|e| match &e {
+                // keep the names of the selected keys
+                Expr::Column(c) if c.relation.is_some() => {
+                    Ok(rename(e.clone())?.alias(&c.name))
+                }
+                _ => rename(e),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let having_expr_post_aggr_opt =
+            having_expr_post_aggr_opt.map(rename).transpose()?;
+        Ok((plan, select_exprs_post_aggr, having_expr_post_aggr_opt))
+    }
+
     /// Return a plan that skips first [count] rows
     fn skip_rows(
         &self,
@@ -1040,6 +1764,15 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             }
             _ => self.sql_expr_to_logical_expr(&e.expr, schema)?,
         };
+        let session_collation = self.session_collation()?;
+        let expr = if session_collation != Collation::Binary
+            && explicit_collation(&expr)?.is_none()
+            && is_string_expr(&expr, schema)
+        {
+            collate(expr, session_collation)
+        } else {
+            expr
+        };
         Ok(Expr::Sort {
             expr: Box::new(expr),
             // by default asc
@@ -1049,6 +1782,119 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         })
     }
 
+    /// The collation set with the `datafusion.execution.collation` option
+    fn session_collation(&self) -> Result<Collation> {
+        match self
+            .schema_provider
+            .get_config_options()
+            .and_then(|options| options.get_string(OPT_COLLATION))
+        {
+            Some(name) => Collation::from_str(&name),
+            None => Ok(Collation::Binary),
+        }
+    }
+
+    /// Handling of the arithmetic that overflows, from the configuration of the session
+    fn session_arithmetic_overflow(&self) -> Result<ArithmeticOverflow> {
+        match self
+            .schema_provider
+            .get_config_options()
+            .and_then(|options| options.get_string(OPT_ARITHMETIC_OVERFLOW))
+        {
+            Some(name) => ArithmeticOverflow::from_str(&name),
+            None => Ok(ArithmeticOverflow::Unchecked),
+        }
+    }
+
+    /// Casts the operands of `+`, `-` or `*` of integers or decimals to the wider type
+    /// of their result, see [promoted_type], when the session promotes arithmetic
+    fn promote_arithmetic(
+        &self,
+        left: Expr,
+        op: Operator,
+        right: Expr,
+        schema: &DFSchema,
+    ) -> Result<(Expr, Expr)> {
+        if self.session_arithmetic_overflow()? != ArithmeticOverflow::Promote
+            || !matches!(op, Operator::Plus | Operator::Minus | Operator::Multiply)
+        {
+            return Ok((left, right));
+        }
+        let data_type = binary_operator_data_type(
+            &left.get_type(schema)?,
+            &op,
+            &right.get_type(schema)?,
+        );
+        match data_type.ok().as_ref().and_then(promoted_type) {
+            Some(data_type) => Ok((
+                left.cast_to(&data_type, schema)?,
+                right.cast_to(&data_type, schema)?,
+            )),
+            None => Ok((left, right)),
+        }
+    }
+
+    /// Casts the argument of SUM of integers or decimals to the wider type of its
+    /// result, see [promoted_type], when the session promotes arithmetic
+    fn promote_sum(&self, args: Vec<Expr>, schema: &DFSchema) -> Result<Vec<Expr>> {
+        if self.session_arithmetic_overflow()? != ArithmeticOverflow::Promote {
+            return Ok(args);
+        }
+        args.into_iter()
+            .map(|arg| {
+                let data_type = aggregates::return_type(
+                    &aggregates::AggregateFunction::Sum,
+                    &[arg.get_type(schema)?],
+                );
+                match data_type.ok().as_ref().and_then(promoted_type) {
+                    Some(data_type) => arg.cast_to(&data_type, schema),
+                    None => Ok(arg),
+                }
+            })
+            .collect()
+    }
+
+    /// Compares string operands, e.g. of `=`, `BETWEEN` or `IN`, under the collation
+    /// given with `COLLATE` on any of them or, if there is none, under the session
+    /// collation.
+    fn collate_comparison(
+        &self,
+        operands: Vec<Expr>,
+        schema: &DFSchema,
+    ) -> Result<Vec<Expr>> {
+        let mut collation = None;
+        for operand in &operands {
+            match (collation, explicit_collation(operand)?) {
+                (Some(c), Some(other)) if c != other => {
+                    return Err(DataFusionError::Plan(format!(
+                        "Cannot compare strings with different collations {} and {}",
+                        c.name(),
+                        other.name()
+                    )))
+                }
+                (None, Some(c)) => collation = Some(c),
+                _ => {}
+            }
+        }
+        let collation = match collation {
+            Some(c) => c,
+            None => self.session_collation()?,
+        };
+        if collation == Collation::Binary {
+            return Ok(operands);
+        }
+        operands
+            .into_iter()
+            .map(|e| {
+                if explicit_collation(&e)?.is_none() && is_string_expr(&e, schema) {
+                    Ok(collate(e, collation))
+                } else {
+                    Ok(e)
+                }
+            })
+            .collect()
+    }
+
     /// Validate the schema provides all of the columns referenced in the expressions.
     fn validate_schema_satisfies_exprs(
         &self,
@@ -1130,7 +1976,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
             SQLExpr::Value(Value::Boolean(n)) => Ok(lit(*n)),
 
-            SQLExpr::Value(Value::Null) => Ok(Expr::Literal(ScalarValue::Utf8(None))),
+            SQLExpr::Value(Value::Null) => Ok(Expr::Literal(ScalarValue::Null)),
             SQLExpr::Extract { field, expr } => Ok(Expr::ScalarFunction {
                 fun: functions::BuiltinScalarFunction::DatePart,
                 args: vec![
@@ -1157,6 +2003,8 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 if id.value.starts_with('@') {
                     let var_names = vec![id.value.clone()];
                     Ok(Expr::ScalarVariable(var_names))
+                } else if let Some(fun) = niladic_function(id, schema) {
+                    Ok(Expr::ScalarFunction { fun, args: vec![] })
                 } else {
                     // create a column expression based on raw user input, this column will be
                     // normalized with qualifer later by the SQL planner.
@@ -1170,18 +2018,35 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                     var_names.push(id.value.clone());
                 }
                 if &var_names[0][0..1] == "@" {
-                    Ok(Expr::ScalarVariable(var_names))
-                } else if var_names.len() == 2 {
-                    // table.column identifier
-                    let name = var_names.pop().unwrap();
-                    let relation = Some(var_names.pop().unwrap());
-                    Ok(Expr::Column(Column { relation, name }))
-                } else {
-                    Ok(Expr::Column(Column {
-                        relation: Some(var_names[0].to_string()),
-                        name: var_names[1].to_string(),
-                    }))
+                    return Ok(Expr::ScalarVariable(var_names));
                 }
+                // `table.column.field...`, or `column.field...` when the first name is
+                // a struct column rather than a table
+                let is_qualified = schema
+                    .field_with_qualified_name(&var_names[0], &var_names[1])
+                    .is_ok();
+                let is_struct = |name: &str| {
+                    matches!(
+                        schema.field_with_unqualified_name(name),
+                        Ok(field) if matches!(field.data_type(), DataType::Struct(_))
+                    )
+                };
+                let (column, fields) = if !is_qualified && is_struct(&var_names[0]) {
+                    let column = Column {
+                        relation: None,
+                        name: var_names[0].clone(),
+                    };
+                    (column, &var_names[1..])
+                } else {
+                    let column = Column {
+                        relation: Some(var_names[0].clone()),
+                        name: var_names[1].clone(),
+                    };
+                    (column, &var_names[2..])
+                };
+                Ok(fields
+                    .iter()
+                    .fold(Expr::Column(column), |expr, name| expr.field(name)))
             }
 
             SQLExpr::Wildcard => Ok(Expr::Wildcard),
@@ -1288,24 +2153,37 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 ref negated,
                 ref low,
                 ref high,
-            } => Ok(Expr::Between {
-                expr: Box::new(self.sql_expr_to_logical_expr(expr, schema)?),
-                negated: *negated,
-                low: Box::new(self.sql_expr_to_logical_expr(low, schema)?),
-                high: Box::new(self.sql_expr_to_logical_expr(high, schema)?),
-            }),
+            } => {
+                let operands = vec![
+                    self.sql_expr_to_logical_expr(expr, schema)?,
+                    self.sql_expr_to_logical_expr(low, schema)?,
+                    self.sql_expr_to_logical_expr(high, schema)?,
+                ];
+                let mut operands = self.collate_comparison(operands, schema)?;
+                let high = operands.pop().unwrap();
+                let low = operands.pop().unwrap();
+                let expr = operands.pop().unwrap();
+                Ok(Expr::Between {
+                    expr: Box::new(expr),
+                    negated: *negated,
+                    low: Box::new(low),
+                    high: Box::new(high),
+                })
+            }
 
             SQLExpr::InList {
                 ref expr,
                 ref list,
                 ref negated,
             } => {
-                let list_expr = list
-                    .iter()
+                let operands = std::iter::once(expr.as_ref())
+                    .chain(list.iter())
                     .map(|e| self.sql_expr_to_logical_expr(e, schema))
                     .collect::<Result<Vec<_>>>()?;
+                let mut operands = self.collate_comparison(operands, schema)?;
+                let list_expr = operands.split_off(1);
                 Ok(Expr::InList {
-                    expr: Box::new(self.sql_expr_to_logical_expr(expr, schema)?),
+                    expr: Box::new(operands.pop().unwrap()),
                     list: list_expr,
                     negated: *negated,
                 })
@@ -1340,128 +2218,44 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                     ))),
                 }?;
 
+                let left = self.sql_expr_to_logical_expr(left, schema)?;
+                let right = self.sql_expr_to_logical_expr(right, schema)?;
+                let (left, right) = match operator {
+                    Operator::Eq
+                    | Operator::NotEq
+                    | Operator::Lt
+                    | Operator::LtEq
+                    | Operator::Gt
+                    | Operator::GtEq => {
+                        let mut operands =
+                            self.collate_comparison(vec![left, right], schema)?;
+                        let right = operands.pop().unwrap();
+                        (operands.pop().unwrap(), right)
+                    }
+                    _ => self.promote_arithmetic(left, operator, right, schema)?,
+                };
                 Ok(Expr::BinaryExpr {
-                    left: Box::new(self.sql_expr_to_logical_expr(left, schema)?),
+                    left: Box::new(left),
                     op: operator,
-                    right: Box::new(self.sql_expr_to_logical_expr(right, schema)?),
+                    right: Box::new(right),
                 })
             }
 
-            SQLExpr::Function(function) => {
-                let name = if function.name.0.len() > 1 {
-                    // DF doesn't handle compound identifiers
-                    // (e.g. "foo.bar") for function names yet
-                    function.name.to_string()
-                } else {
-                    // if there is a quote style, then don't normalize
-                    // the name, otherwise normalize to lowercase
-                    let ident = &function.name.0[0];
-                    match ident.quote_style {
-                        Some(_) => ident.value.clone(),
-                        None => ident.value.to_ascii_lowercase(),
-                    }
-                };
-
-                // first, scalar built-in
-                if let Ok(fun) = functions::BuiltinScalarFunction::from_str(&name) {
-                    let args = self.function_args_to_expr(function, schema)?;
-
-                    return Ok(Expr::ScalarFunction { fun, args });
-                };
-
-                // then, window function
-                if let Some(window) = &function.over {
-                    let partition_by = window
-                        .partition_by
-                        .iter()
-                        .map(|e| self.sql_expr_to_logical_expr(e, schema))
-                        .collect::<Result<Vec<_>>>()?;
-                    let order_by = window
-                        .order_by
-                        .iter()
-                        .map(|e| self.order_by_to_sort_expr(e, schema, false))
-                        .collect::<Result<Vec<_>>>()?;
-                    let window_frame = window
-                        .window_frame
-                        .as_ref()
-                        .map(|window_frame| {
-                            let window_frame: WindowFrame = window_frame.clone().try_into()?;
-                            if WindowFrameUnits::Range == window_frame.units
-                                && order_by.len() != 1
-                            {
-                                Err(DataFusionError::Plan(format!(
-                                    "With window frame of type RANGE, the order by expression must be of length 1, got {}", order_by.len())))
-                            } else {
-                                Ok(window_frame)
-                            }
-
-                        })
-                        .transpose()?;
-                    let fun = window_functions::WindowFunction::from_str(&name)?;
-                    match fun {
-                        window_functions::WindowFunction::AggregateFunction(
-                            aggregate_fun,
-                        ) => {
-                            return Ok(Expr::WindowFunction {
-                                fun: window_functions::WindowFunction::AggregateFunction(
-                                    aggregate_fun.clone(),
-                                ),
-                                args: self.aggregate_fn_to_expr(
-                                    &aggregate_fun,
-                                    function,
-                                    schema,
-                                )?,
-                                partition_by,
-                                order_by,
-                                window_frame,
-                            });
-                        }
-                        window_functions::WindowFunction::BuiltInWindowFunction(
-                            window_fun,
-                        ) => {
-                            return Ok(Expr::WindowFunction {
-                                fun: window_functions::WindowFunction::BuiltInWindowFunction(
-                                    window_fun,
-                                ),
-                                args:self.function_args_to_expr(function, schema)?,
-                                partition_by,
-                                order_by,
-                                window_frame,
-                            });
-                        }
-                    }
-                }
-
-                // next, aggregate built-ins
-                if let Ok(fun) = aggregates::AggregateFunction::from_str(&name) {
-                    let args = self.aggregate_fn_to_expr(&fun, function, schema)?;
-                    return Ok(Expr::AggregateFunction {
-                        fun,
-                        distinct: function.distinct,
-                        args,
-                    });
-                };
-
-                // finally, user-defined functions (UDF) and UDAF
-                match self.schema_provider.get_function_meta(&name) {
-                    Some(fm) => {
-                        let args = self.function_args_to_expr(function, schema)?;
-
-                        Ok(Expr::ScalarUDF { fun: fm, args })
-                    }
-                    None => match self.schema_provider.get_aggregate_meta(&name) {
-                        Some(fm) => {
-                            let args = self.function_args_to_expr(function, schema)?;
-                            Ok(Expr::AggregateUDF { fun: fm, args })
-                        }
-                        _ => Err(DataFusionError::Plan(format!(
-                            "Invalid function '{}'",
-                            name
-                        ))),
-                    },
-                }
+            SQLExpr::Collate { expr, collation } => {
+                let name = collation
+                    .0
+                    .iter()
+                    .map(|i| i.value.as_str())
+                    .collect::<Vec<_>>()
+                    .join(".");
+                Ok(collate(
+                    self.sql_expr_to_logical_expr(expr, schema)?,
+                    Collation::from_str(&name)?,
+                ))
             }
 
+            SQLExpr::Function(function) => self.sql_function_to_expr(function, schema),
+
             SQLExpr::Nested(e) => self.sql_expr_to_logical_expr(e, schema),
 
             SQLExpr::Rolling {
@@ -1512,6 +2306,215 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         }
     }
 
+    /// Plans the call of a function
+    fn sql_function_to_expr(
+        &self,
+        function: &sqlparser::ast::Function,
+        schema: &DFSchema,
+    ) -> Result<Expr> {
+        // if there is a quote style, then don't normalize
+        // the name, otherwise normalize to lowercase
+        let mut parts = function
+            .name
+            .0
+            .iter()
+            .map(|ident| match ident.quote_style {
+                Some(_) => ident.value.clone(),
+                None => ident.value.to_ascii_lowercase(),
+            })
+            .collect::<Vec<_>>();
+        let name = parts.pop().unwrap();
+        let namespace = if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("."))
+        };
+
+        // functions registered under a namespace, e.g. `pg_catalog.version()`.
+        // The built-in functions are also found in the namespaces of the
+        // search path.
+        if let Some(namespace) = &namespace {
+            let qualified_name = format!("{}.{}", namespace, name);
+            if let Some(expr) = self.udf_to_expr(&qualified_name, function, schema)? {
+                return Ok(expr);
+            }
+            if !self.function_search_path().contains(namespace) {
+                return Err(DataFusionError::Plan(format!(
+                    "Invalid function '{}'",
+                    qualified_name
+                )));
+            }
+        } else if function.over.is_none() {
+            // user-defined functions (UDF) and UDAF take precedence over the built-in
+            // functions of the same name, they are also searched for in the
+            // namespaces of the search path
+            if let Some(expr) = self.udf_to_expr(&name, function, schema)? {
+                return Ok(expr);
+            }
+            for namespace in self.function_search_path() {
+                let qualified_name = format!("{}.{}", namespace, name);
+                if let Some(expr) = self.udf_to_expr(&qualified_name, function, schema)? {
+                    return Ok(expr);
+                }
+            }
+        }
+
+        // conditional functions of other dialects are planned as CASE
+        if let Some(expr) = self.conditional_fn_to_case(&name, function, schema)? {
+            return Ok(expr);
+        }
+
+        // higher-order functions, whose last argument is a lambda
+        if let Ok(fun) = HigherOrderFunction::from_str(&name) {
+            // the SQL parser does not support lambdas yet
+            return Err(DataFusionError::Plan(format!(
+                "{} takes a lambda, which is only available through the DataFrame API",
+                fun
+            )));
+        }
+
+        // first, scalar built-in
+        if let Ok(fun) = functions::BuiltinScalarFunction::from_str(&name) {
+            let args = self.function_args_to_expr(function, schema)?;
+
+            return Ok(Expr::ScalarFunction { fun, args });
+        };
+
+        // then, window function
+        if let Some(window) = &function.over {
+            let partition_by = window
+                .partition_by
+                .iter()
+                .map(|e| self.sql_expr_to_logical_expr(e, schema))
+                .collect::<Result<Vec<_>>>()?;
+            let order_by = window
+                .order_by
+                .iter()
+                .map(|e| self.order_by_to_sort_expr(e, schema, false))
+                .collect::<Result<Vec<_>>>()?;
+            let window_frame = window
+                .window_frame
+                .as_ref()
+                .map(|window_frame| {
+                    let window_frame: WindowFrame = window_frame.clone().try_into()?;
+                    if WindowFrameUnits::Range == window_frame.units
+                        && order_by.len() != 1
+                    {
+                        Err(DataFusionError::Plan(format!(
+                            "With window frame of type RANGE, the order by expression must be of length 1, got {}", order_by.len())))
+                    } else {
+                        Ok(window_frame)
+                    }
+
+                })
+                .transpose()?;
+            let fun = window_functions::WindowFunction::from_str(&name)?;
+            match fun {
+                window_functions::WindowFunction::AggregateFunction(aggregate_fun) => {
+                    return Ok(Expr::WindowFunction {
+                        fun: window_functions::WindowFunction::AggregateFunction(
+                            aggregate_fun.clone(),
+                        ),
+                        args: self.aggregate_fn_to_expr(
+                            &aggregate_fun,
+                            function,
+                            schema,
+                        )?,
+                        partition_by,
+                        order_by,
+                        window_frame,
+                    });
+                }
+                window_functions::WindowFunction::BuiltInWindowFunction(window_fun) => {
+                    return Ok(Expr::WindowFunction {
+                        fun: window_functions::WindowFunction::BuiltInWindowFunction(
+                            window_fun,
+                        ),
+                        args: self.function_args_to_expr(function, schema)?,
+                        partition_by,
+                        order_by,
+                        window_frame,
+                    });
+                }
+            }
+        }
+
+        // next, aggregate built-ins
+        if let Ok(fun) = aggregates::AggregateFunction::from_str(&name) {
+            if aggregates::is_ordered_set(&fun) {
+                // the SQL parser does not support the WITHIN GROUP clause yet
+                return Err(DataFusionError::Plan(format!(
+                    "{} requires WITHIN GROUP (ORDER BY ...), which is only \
+                     available through the DataFrame API",
+                    fun
+                )));
+            }
+            if aggregates::requires_order_by(&fun) {
+                // nor the ORDER BY clause of aggregate calls
+                return Err(DataFusionError::Plan(format!(
+                    "{} requires ORDER BY, which is only available through the \
+                     DataFrame API",
+                    fun
+                )));
+            }
+            let mut args = self.aggregate_fn_to_expr(&fun, function, schema)?;
+            if fun == aggregates::AggregateFunction::Sum {
+                args = self.promote_sum(args, schema)?;
+            }
+            if fun == aggregates::AggregateFunction::Count
+                && !function.distinct
+                && args.len() > 1
+            {
+                return Err(DataFusionError::Plan(
+                    "COUNT of several expressions is only valid with DISTINCT"
+                        .to_string(),
+                ));
+            }
+            return Ok(Expr::AggregateFunction {
+                fun,
+                distinct: function.distinct,
+                ignore_nulls: false,
+                args,
+                order_by: vec![],
+                filter: None,
+            });
+        };
+
+        Err(DataFusionError::Plan(format!(
+            "Invalid function '{}'",
+            namespace.map_or_else(|| name.clone(), |ns| format!("{}.{}", ns, name))
+        )))
+    }
+
+    /// Plans the call of the user-defined function or aggregate registered as `name`,
+    /// if there is one
+    fn udf_to_expr(
+        &self,
+        name: &str,
+        function: &sqlparser::ast::Function,
+        schema: &DFSchema,
+    ) -> Result<Option<Expr>> {
+        if let Some(fun) = self.schema_provider.get_function_meta(name) {
+            let args = self.function_args_to_expr(function, schema)?;
+            return Ok(Some(Expr::ScalarUDF { fun, args }));
+        }
+        if let Some(fun) = self.schema_provider.get_aggregate_meta(name) {
+            let args = self.function_args_to_expr(function, schema)?;
+            return Ok(Some(Expr::AggregateUDF { fun, args }));
+        }
+        Ok(None)
+    }
+
+    /// The namespaces set with the `datafusion.sql.function_search_path` option
+    fn function_search_path(&self) -> Vec<String> {
+        self.schema_provider
+            .get_config_options()
+            .unwrap_or_default()
+            .get_string(OPT_FUNCTION_SEARCH_PATH)
+            .map(|path| parse_function_search_path(&path))
+            .unwrap_or_default()
+    }
+
     fn function_args_to_expr(
         &self,
         function: &sqlparser::ast::Function,
@@ -1524,6 +2527,117 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             .collect::<Result<Vec<Expr>>>()
     }
 
+    /// Plans `IFNULL`, `NVL`, `NVL2` and Oracle's `DECODE` as CASE expressions.
+    /// Returns `None` for any other function, including the two argument `DECODE`,
+    /// which is the binary decoding function.
+    fn conditional_fn_to_case(
+        &self,
+        name: &str,
+        function: &sqlparser::ast::Function,
+        schema: &DFSchema,
+    ) -> Result<Option<Expr>> {
+        let min_args = match name {
+            "ifnull" | "nvl" => 2,
+            "nvl2" => 3,
+            "decode" if function.args.len() >= 3 => 3,
+            _ => return Ok(None),
+        };
+        let mut args = self.function_args_to_expr(function, schema)?;
+        if args.len() < min_args || (name != "decode" && args.len() != min_args) {
+            return Err(DataFusionError::Plan(format!(
+                "Function '{}' expects {} arguments, got {}",
+                name,
+                min_args,
+                args.len()
+            )));
+        }
+
+        let (when_then_expr, else_expr) = match name {
+            "ifnull" | "nvl" => {
+                let fallback = args.pop().unwrap();
+                let value = args.pop().unwrap();
+                (
+                    vec![(Box::new(value.clone().is_not_null()), Box::new(value))],
+                    Some(fallback),
+                )
+            }
+            "nvl2" => {
+                let if_null = args.pop().unwrap();
+                let if_not_null = args.pop().unwrap();
+                let value = args.pop().unwrap();
+                (
+                    vec![(Box::new(value.is_not_null()), Box::new(if_not_null))],
+                    Some(if_null),
+                )
+            }
+            _ => {
+                // DECODE(expr, search1, result1, ..., [default])
+                let value = args.remove(0);
+                let default = if args.len() % 2 == 1 {
+                    args.pop()
+                } else {
+                    None
+                };
+                let when_then_expr = args
+                    .chunks(2)
+                    .map(|pair| {
+                        let (search, result) = (pair[0].clone(), pair[1].clone());
+                        // unlike '=', DECODE considers two nulls to be equal
+                        let matches = match search {
+                            Expr::Literal(ref v) if v.is_null() => {
+                                value.clone().is_null()
+                            }
+                            Expr::Literal(_) => value.clone().eq(search),
+                            _ => value
+                                .clone()
+                                .eq(search.clone())
+                                .or(value.clone().is_null().and(search.is_null())),
+                        };
+                        (Box::new(matches), Box::new(result))
+                    })
+                    .collect();
+                (when_then_expr, default)
+            }
+        };
+
+        // like the inputs of a UNION, the results are cast to their common type
+        let mut data_type = DataType::Null;
+        for result in when_then_expr
+            .iter()
+            .map(|(_, then)| then.as_ref())
+            .chain(else_expr.iter())
+        {
+            let result_type = result.get_type(schema)?;
+            data_type = union_coercion(&data_type, &result_type).ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "Function '{}' cannot return both {:?} and {:?}",
+                    name, data_type, result_type
+                ))
+            })?;
+        }
+        let cast = |expr: Expr| -> Result<Expr> {
+            Ok(if expr.get_type(schema)? == data_type {
+                expr
+            } else {
+                Expr::Cast {
+                    expr: Box::new(expr),
+                    data_type: data_type.clone(),
+                }
+            })
+        };
+        let when_then_expr = when_then_expr
+            .into_iter()
+            .map(|(when, then)| Ok((when, Box::new(cast(*then)?))))
+            .collect::<Result<Vec<_>>>()?;
+        let else_expr = else_expr.map(cast).transpose()?;
+
+        Ok(Some(Expr::Case {
+            expr: None,
+            when_then_expr,
+            else_expr: else_expr.map(Box::new),
+        }))
+    }
+
     fn aggregate_fn_to_expr(
         &self,
         fun: &aggregates::AggregateFunction,
@@ -1614,7 +2728,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 }
             };
 
-            if interval_period > (i32::MAX as f32) {
+            if interval_period.abs() > (i32::MAX as f32) {
                 return Err(DataFusionError::NotImplemented(format!(
                     "Interval field value out of range: {:?}",
                     value
@@ -1622,23 +2736,47 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             }
 
             match interval_type.to_lowercase().as_str() {
-                "year" => Ok(align_interval_parts(interval_period * 12_f32, 0.0, 0.0)),
-                "quarter" => Ok(align_interval_parts(interval_period * 3_f32, 0.0, 0.0)),
-                "month" => Ok(align_interval_parts(interval_period, 0.0, 0.0)),
-                "week" | "weeks" => Ok(align_interval_parts(
+                "millennium" | "millennia" | "millenniums" | "mil" | "mils" => {
+                    Ok(align_interval_parts(interval_period * 12_000_f32, 0.0, 0.0))
+                }
+                "century" | "centuries" | "c" => {
+                    Ok(align_interval_parts(interval_period * 1_200_f32, 0.0, 0.0))
+                }
+                "decade" | "decades" | "dec" | "decs" => {
+                    Ok(align_interval_parts(interval_period * 120_f32, 0.0, 0.0))
+                }
+                "year" | "years" | "yr" | "yrs" | "y" => {
+                    Ok(align_interval_parts(interval_period * 12_f32, 0.0, 0.0))
+                }
+                "quarter" | "quarters" | "qtr" => {
+                    Ok(align_interval_parts(interval_period * 3_f32, 0.0, 0.0))
+                }
+                "month" | "months" | "mon" | "mons" => {
+                    Ok(align_interval_parts(interval_period, 0.0, 0.0))
+                }
+                "week" | "weeks" | "w" => Ok(align_interval_parts(
                     0.0,
                     interval_period * DAYS_PER_WEEK,
                     0.0,
                 )),
-                "day" | "days" => Ok(align_interval_parts(0.0, interval_period, 0.0)),
-                "hour" | "hours" => {
+                "day" | "days" | "d" => {
+                    Ok(align_interval_parts(0.0, interval_period, 0.0))
+                }
+                "hour" | "hours" | "hr" | "hrs" | "h" => {
                     Ok((0, 0, interval_period * SECONDS_PER_HOUR * MILLIS_PER_SECOND))
                 }
-                "minutes" | "minute" => {
+                "minutes" | "minute" | "mins" | "min" | "m" => {
                     Ok((0, 0, interval_period * 60_f32 * MILLIS_PER_SECOND))
                 }
-                "seconds" | "second" => Ok((0, 0, interval_period * MILLIS_PER_SECOND)),
-                "milliseconds" | "millisecond" => Ok((0, 0, interval_period)),
+                "seconds" | "second" | "secs" | "sec" | "s" => {
+                    Ok((0, 0, interval_period * MILLIS_PER_SECOND))
+                }
+                "milliseconds" | "millisecond" | "msecs" | "msec" | "ms" => {
+                    Ok((0, 0, interval_period))
+                }
+                "microseconds" | "microsecond" | "usecs" | "usec" | "us" => {
+                    Ok((0, 0, interval_period / MILLIS_PER_SECOND))
+                }
                 _ => Err(DataFusionError::NotImplemented(format!(
                     "Invalid input syntax for type interval: {:?}",
                     value
@@ -1646,34 +2784,79 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             }
         };
 
+        // Time of day part in the `[-]HH:MM[:SS[.fff]]` format, e.g. INTERVAL '1 day 04:05:06'
+        let calculate_from_time = |time_str: &str| -> Result<(i32, i32, f32)> {
+            let invalid = || {
+                DataFusionError::SQL(ParserError(format!(
+                    "Unsupported Interval Expression with value {:?}",
+                    value
+                )))
+            };
+            let (sign, time_str) = match time_str.strip_prefix('-') {
+                Some(time_str) => (-1_f64, time_str),
+                None => (1_f64, time_str.strip_prefix('+').unwrap_or(time_str)),
+            };
+            let mut parts = time_str.split(':');
+            let hours = parts.next().and_then(|p| u32::from_str(p).ok());
+            let minutes = parts.next().and_then(|p| u32::from_str(p).ok());
+            let seconds = match parts.next() {
+                Some(p) => f64::from_str(p).ok().filter(|s| *s >= 0.0 && *s < 60.0),
+                None => Some(0.0),
+            };
+            match (hours, minutes, seconds, parts.next()) {
+                (Some(hours), Some(minutes), Some(seconds), None) if minutes < 60 => {
+                    let millis = ((hours as f64 * 60.0 + minutes as f64) * 60.0
+                        + seconds)
+                        * 1_000.0;
+                    Ok((0, 0, (sign * millis) as f32))
+                }
+                _ => Err(invalid()),
+            }
+        };
+
         let mut result_month: i64 = 0;
         let mut result_days: i64 = 0;
         let mut result_millis: i64 = 0;
 
-        let mut parts = value.split_whitespace();
+        let mut parts = value.split_whitespace().peekable();
+        // Postgres style `INTERVAL '1 day ago'` negates the whole interval
+        let mut ago = false;
 
-        loop {
-            let interval_period_str = parts.next();
-            if interval_period_str.is_none() {
+        while let Some(interval_period_str) = parts.next() {
+            if interval_period_str.eq_ignore_ascii_case("ago") && parts.peek().is_none() {
+                ago = true;
                 break;
             }
 
-            let leading_field = leading_field
-                .as_ref()
-                .map(|dt| dt.to_string())
-                .unwrap_or_else(|| "second".to_string());
-
-            let unit = parts
-                .next()
-                .map(|part| part.to_string())
-                .unwrap_or(leading_field);
+            let (diff_month, diff_days, diff_millis) = if interval_period_str
+                .contains(':')
+            {
+                calculate_from_time(interval_period_str)?
+            } else {
+                // A number without a unit is measured in the leading field, if any
+                let unit = match parts.peek() {
+                    Some(part) if part.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                        if part.eq_ignore_ascii_case("ago") {
+                            None
+                        } else {
+                            parts.next().map(|part| part.to_string())
+                        }
+                    }
+                    _ => None,
+                };
+                let unit = unit.unwrap_or_else(|| {
+                    leading_field
+                        .as_ref()
+                        .map(|dt| dt.to_string())
+                        .unwrap_or_else(|| "second".to_string())
+                });
 
-            let (diff_month, diff_days, diff_millis) =
-                calculate_from_part(interval_period_str.unwrap(), &unit)?;
+                calculate_from_part(interval_period_str, &unit)?
+            };
 
             result_month += diff_month as i64;
 
-            if result_month > (i32::MAX as i64) {
+            if result_month.abs() > (i32::MAX as i64) {
                 return Err(DataFusionError::NotImplemented(format!(
                     "Interval field value out of range: {:?}",
                     value
@@ -1682,7 +2865,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
             result_days += diff_days as i64;
 
-            if result_days > (i32::MAX as i64) {
+            if result_days.abs() > (i32::MAX as i64) {
                 return Err(DataFusionError::NotImplemented(format!(
                     "Interval field value out of range: {:?}",
                     value
@@ -1691,7 +2874,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
             result_millis += diff_millis as i64;
 
-            if result_millis > (i32::MAX as i64) {
+            if result_millis.abs() > (i32::MAX as i64) {
                 return Err(DataFusionError::NotImplemented(format!(
                     "Interval field value out of range: {:?}",
                     value
@@ -1699,6 +2882,12 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             }
         }
 
+        if ago {
+            result_month = -result_month;
+            result_days = -result_days;
+            result_millis = -result_millis;
+        }
+
         // Interval is tricky thing
         // 1 day is not 24 hours because timezones, 1 year != 365/364! 30 days != 1 month
         // The true way to store and calculate intervals is to store it as it defined
@@ -1718,8 +2907,9 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             ))));
         }
 
-        let result: i64 = (result_days << 32) | result_millis;
-        Ok(Expr::Literal(ScalarValue::IntervalDayTime(Some(result))))
+        Ok(Expr::Literal(ScalarValue::IntervalDayTime(Some(
+            interval_day_time(result_days, result_millis),
+        ))))
     }
 
     fn show_variable_to_plan(&self, variable: &[Ident]) -> Result<LogicalPlan> {
@@ -1737,10 +2927,47 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 ))
             }
         } else {
-            Err(DataFusionError::NotImplemented(format!(
-                "SHOW {} not implemented. Supported syntax: SHOW <TABLES>",
-                variable
-            )))
+            let options = self.schema_provider.get_config_options();
+            let settings = match options {
+                Some(options) if variable.eq_ignore_ascii_case("all") => {
+                    options.options().clone().into_iter().collect::<Vec<_>>()
+                }
+                Some(options) => match options.get(&variable) {
+                    Some(value) => vec![(variable.to_lowercase(), value)],
+                    None => vec![],
+                },
+                None => vec![],
+            };
+            if settings.is_empty() {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "SHOW {} not implemented. Supported syntax: SHOW <TABLES | ALL | option name>",
+                    variable
+                )));
+            }
+
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("name", DataType::Utf8, false),
+                Field::new("setting", DataType::Utf8, true),
+            ]));
+            let names = StringArray::from(
+                settings.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(),
+            );
+            let values = settings
+                .iter()
+                .map(|(_, v)| {
+                    if v.is_null() {
+                        None
+                    } else {
+                        Some(v.to_string())
+                    }
+                })
+                .collect::<StringArray>();
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(names) as ArrayRef, Arc::new(values)],
+            )?;
+
+            LogicalPlanBuilder::scan_memory(vec![vec![batch]], schema, None)?.build()
         }
     }
 
@@ -1813,6 +3040,58 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     }
 }
 
+/// Packs days and milliseconds into an `IntervalDayTime` value. Negative intervals are
+/// stored as the negated positive interval, so that both parts must have the same sign:
+/// whole days are borrowed when they differ, e.g. `1 day -1 hour` becomes `23 hours`.
+fn interval_day_time(days: i64, millis: i64) -> i64 {
+    const MILLIS_PER_DAY: i64 = 24 * 3_600 * 1_000;
+    let (days, millis) = if days.signum() * millis.signum() < 0 {
+        let total = days * MILLIS_PER_DAY + millis;
+        (total / MILLIS_PER_DAY, total % MILLIS_PER_DAY)
+    } else {
+        (days, millis)
+    };
+    let sign = if days < 0 || millis < 0 { -1 } else { 1 };
+    sign * ((days.abs() << 32) | millis.abs())
+}
+
+/// Moves the `<expr> IN (<subquery>)` conjuncts of a WHERE predicate to `in_subqueries`
+/// and returns the rest of the predicate, if any
+fn split_in_subqueries(
+    predicate: &SQLExpr,
+    in_subqueries: &mut Vec<(SQLExpr, Query)>,
+) -> Option<SQLExpr> {
+    match predicate {
+        SQLExpr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            let left = split_in_subqueries(left, in_subqueries);
+            let right = split_in_subqueries(right, in_subqueries);
+            match (left, right) {
+                (Some(left), Some(right)) => Some(SQLExpr::BinaryOp {
+                    left: Box::new(left),
+                    op: BinaryOperator::And,
+                    right: Box::new(right),
+                }),
+                (Some(e), None) | (None, Some(e)) => Some(e),
+                (None, None) => None,
+            }
+        }
+        SQLExpr::Nested(e) => split_in_subqueries(e, in_subqueries),
+        SQLExpr::InSubquery {
+            expr,
+            subquery,
+            negated: false,
+        } => {
+            in_subqueries.push((expr.as_ref().clone(), subquery.as_ref().clone()));
+            None
+        }
+        other => Some(other.clone()),
+    }
+}
+
 /// Remove join expressions from a filter expression
 fn remove_join_expressions(
     expr: &Expr,
@@ -1856,17 +3135,20 @@ fn remove_join_expressions(
 /// foo = bar => accum=[(foo, bar)] accum_filter=[]
 /// foo = bar AND bar = baz => accum=[(foo, bar), (bar, baz)] accum_filter=[]
 /// foo = bar AND baz > 1 => accum=[(foo, bar)] accum_filter=[baz > 1]
+/// foo + 1 = lower(bar) => accum=[(foo + 1, lower(bar))] accum_filter=[]
 ///
 /// foo = bar
 /// foo = bar AND bar = baz AND ...
 ///
+/// Both sides of an equality may be arbitrary expressions as long as each of
+/// them only references columns of one input. Keys are ordered as (left, right).
 fn extract_join_keys(
     expr: &Expr,
     ls: &DFSchema,
     rs: &DFSchema,
-    accum: &mut Vec<(Column, Column)>,
+    accum: &mut Vec<(Expr, Expr)>,
     accum_filter: &mut Vec<Expr>,
-) {
+) -> Result<()> {
     match expr {
         Expr::BinaryExpr { left, op, right } => match op {
             Operator::Eq => match (left.as_ref(), right.as_ref()) {
@@ -1881,15 +3163,21 @@ fn extract_join_keys(
                         std::mem::swap(&mut lc, &mut rc)
                     }
 
-                    accum.push((lc, rc));
+                    accum.push((Expr::Column(lc), Expr::Column(rc)));
                 }
-                _other => {
-                    accum_filter.push(expr.clone());
+                (l, r) => {
+                    if references_only(l, ls)? && references_only(r, rs)? {
+                        accum.push((l.clone(), r.clone()));
+                    } else if references_only(l, rs)? && references_only(r, ls)? {
+                        accum.push((r.clone(), l.clone()));
+                    } else {
+                        accum_filter.push(expr.clone());
+                    }
                 }
             },
             Operator::And => {
-                extract_join_keys(left, ls, rs, accum, accum_filter);
-                extract_join_keys(right, ls, rs, accum, accum_filter);
+                extract_join_keys(left, ls, rs, accum, accum_filter)?;
+                extract_join_keys(right, ls, rs, accum, accum_filter)?;
             }
             _other => {
                 accum_filter.push(expr.clone());
@@ -1899,6 +3187,94 @@ fn extract_join_keys(
             accum_filter.push(expr.clone());
         }
     }
+    Ok(())
+}
+
+/// Whether `expr` references at least one column and all of its columns come from `schema`
+fn references_only(expr: &Expr, schema: &DFSchema) -> Result<bool> {
+    let mut columns = HashSet::new();
+    expr_to_columns(expr, &mut columns)?;
+    Ok(
+        !columns.is_empty()
+            && columns.iter().all(|c| schema.field_from_column(c).is_ok()),
+    )
+}
+
+/// Adds a projection computing the non-column join `keys` on top of `plan`.
+/// Returns the new input, the columns to join on and whether a projection was added.
+fn project_join_keys(
+    plan: LogicalPlan,
+    keys: Vec<Expr>,
+    prefix: &str,
+) -> Result<(LogicalPlan, Vec<Column>, bool)> {
+    let mut key_exprs = vec![];
+    let mut columns = Vec::with_capacity(keys.len());
+    for (i, key) in keys.into_iter().enumerate() {
+        match key {
+            Expr::Column(c) => columns.push(c),
+            key => {
+                let name = format!("{}_{}", prefix, i);
+                key_exprs.push(key.alias(&name));
+                columns.push(Column::from_name(name));
+            }
+        }
+    }
+    if key_exprs.is_empty() {
+        return Ok((plan, columns, false));
+    }
+
+    let projection = plan
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| Expr::Column(f.qualified_column()))
+        .chain(key_exprs)
+        .collect::<Vec<_>>();
+    let plan = LogicalPlanBuilder::from(plan)
+        .project(projection)?
+        .build()?;
+    Ok((plan, columns, true))
+}
+
+/// The collation of `expr` if it is `COLLATE`d explicitly
+fn explicit_collation(expr: &Expr) -> Result<Option<Collation>> {
+    match expr {
+        Expr::ScalarFunction {
+            fun: functions::BuiltinScalarFunction::CollationKey,
+            args,
+        } => match args.get(1) {
+            Some(Expr::Literal(ScalarValue::Utf8(Some(name)))) => {
+                Ok(Some(Collation::from_str(name)?))
+            }
+            _ => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// The SQL functions that are called without parentheses, e.g. `current_date`, unless
+/// `id` is quoted or names a column of `schema`
+fn niladic_function(
+    id: &Ident,
+    schema: &DFSchema,
+) -> Option<functions::BuiltinScalarFunction> {
+    if id.quote_style.is_some()
+        || !schema.fields_with_unqualified_name(&id.value).is_empty()
+    {
+        return None;
+    }
+    match id.value.to_ascii_lowercase().as_str() {
+        name @ ("current_date" | "current_time" | "current_timestamp" | "localtime"
+        | "localtimestamp") => functions::BuiltinScalarFunction::from_str(name).ok(),
+        _ => None,
+    }
+}
+
+fn is_string_expr(expr: &Expr, schema: &DFSchema) -> bool {
+    matches!(
+        expr.get_type(schema),
+        Ok(DataType::Utf8) | Ok(DataType::LargeUtf8)
+    )
 }
 
 /// Extract join keys from a WHERE clause
@@ -2267,7 +3643,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "fails in CubeStore fork"]
     fn select_aggregate_aliased_with_having_referencing_aggregate_by_its_alias() {
         let sql = "SELECT MAX(age) as max_age
                    FROM person
@@ -2337,7 +3712,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "fails in CubeStore fork"]
     fn select_aggregate_with_group_by_with_having_using_column_by_alias() {
         let sql = "SELECT first_name AS fn, MAX(age)
                    FROM person
@@ -2351,7 +3725,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "fails in CubeStore fork"]
     fn select_aggregate_with_group_by_with_having_using_columns_with_and_without_their_aliases(
     ) {
         let sql = "SELECT first_name AS fn, MAX(age) AS max_age
@@ -2418,7 +3791,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "fails in CubeStore fork"]
     fn select_aggregate_aliased_with_group_by_with_having_referencing_aggregate_by_its_alias(
     ) {
         let sql = "SELECT first_name, MAX(age) AS max_age
@@ -2433,7 +3805,19 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "fails in CubeStore fork"]
+    fn select_aggregate_aliased_as_column_with_having_referencing_the_column() {
+        let sql = "SELECT first_name, MAX(age) AS age
+                   FROM person
+                   GROUP BY first_name
+                   HAVING MAX(age) > 100 AND MIN(age) < 10";
+        let expected = "Projection: #person.first_name, #MAX(person.age) AS age\
+                        \n  Filter: #MAX(person.age) Gt Int64(100) And #MIN(person.age) Lt Int64(10)\
+                        \n    Aggregate: groupBy=[[#person.first_name]], aggr=[[MAX(#person.age), MIN(#person.age)]]\
+                        \n      TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
     fn select_aggregate_compound_aliased_with_group_by_with_having_referencing_compound_aggregate_by_its_alias(
     ) {
         let sql = "SELECT first_name, MAX(age) + 1 AS max_age_plus_one
@@ -2518,6 +3902,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn select_string_agg() {
+        quick_test(
+            "SELECT string_agg(first_name, ',') FROM person",
+            "Projection: #STRING_AGG(person.first_name,Utf8(\",\"))\
+            \n  Aggregate: groupBy=[[]], aggr=[[STRING_AGG(#person.first_name, Utf8(\",\"))]]\
+            \n    TableScan: person projection=None",
+        );
+    }
+
+    #[test]
+    fn select_regr() {
+        quick_test(
+            "SELECT regr_slope(salary, age) FROM person",
+            "Projection: #REGR_SLOPE(person.salary,person.age)\
+            \n  Aggregate: groupBy=[[]], aggr=[[REGR_SLOPE(#person.salary, #person.age)]]\
+            \n    TableScan: person projection=None",
+        );
+    }
+
+    #[test]
+    fn select_count_distinct_columns() {
+        quick_test(
+            "SELECT COUNT(DISTINCT first_name, last_name) FROM person",
+            "Projection: #COUNT(DISTINCT person.first_name,person.last_name)\
+            \n  Aggregate: groupBy=[[]], aggr=[[COUNT(DISTINCT #person.first_name, #person.last_name)]]\
+            \n    TableScan: person projection=None",
+        );
+
+        let sql = "SELECT COUNT(first_name, last_name) FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert!(matches!(
+            err,
+            DataFusionError::Plan(msg) if msg.contains("only valid with DISTINCT"),
+        ));
+
+        let sql = "SELECT COUNT() FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert!(matches!(
+            err,
+            DataFusionError::Plan(msg) if msg == "COUNT requires at least one argument",
+        ));
+    }
+
+    #[test]
+    fn select_percentile_without_within_group() {
+        let sql = "SELECT percentile_cont(0.5) FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert!(matches!(
+            err,
+            DataFusionError::Plan(msg) if msg.contains("PERCENTILE_CONT requires WITHIN GROUP"),
+        ));
+    }
+
+    #[test]
+    fn select_first_last() {
+        let sql = "SELECT first(age) FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert!(matches!(
+            err,
+            DataFusionError::Plan(msg) if msg.contains("FIRST requires ORDER BY"),
+        ));
+    }
+
+    #[test]
+    fn select_struct_fields() {
+        quick_test(
+            "SELECT payload.user.name, events.payload.kind FROM events \
+            WHERE payload.kind = 'click'",
+            "Projection: #events.payload.user.name, #events.payload.kind\
+            \n  Filter: #events.payload.kind Eq Utf8(\"click\")\
+            \n    TableScan: events projection=None",
+        );
+    }
+
     #[test]
     fn test_sum_aggregate() {
         quick_test(
@@ -2658,6 +4117,76 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn select_interval_literals() {
+        let day_time = |days: i64, millis: i64| {
+            Expr::Literal(ScalarValue::IntervalDayTime(Some((days << 32) | millis)))
+        };
+        let cases = vec![
+            (
+                "'1 year 2 months'",
+                Expr::Literal(ScalarValue::IntervalYearMonth(Some(14))),
+            ),
+            (
+                "'2 years 3 mons'",
+                Expr::Literal(ScalarValue::IntervalYearMonth(Some(27))),
+            ),
+            (
+                "'1 decade'",
+                Expr::Literal(ScalarValue::IntervalYearMonth(Some(120))),
+            ),
+            (
+                "'-1 year'",
+                Expr::Literal(ScalarValue::IntervalYearMonth(Some(-12))),
+            ),
+            (
+                "'1 year ago'",
+                Expr::Literal(ScalarValue::IntervalYearMonth(Some(-12))),
+            ),
+            ("'3 days 04:05:06'", day_time(3, 14_706_000)),
+            ("'04:05'", day_time(0, 14_700_000)),
+            ("'00:00:01.5'", day_time(0, 1_500)),
+            ("'90' SECOND", day_time(0, 90_000)),
+            ("'90' MINUTE", day_time(0, 5_400_000)),
+            ("'2 hrs 30 mins 10 secs'", day_time(0, 9_010_000)),
+            ("'1 d 1 h 1 m 1 s 1 ms'", day_time(1, 3_661_001)),
+            ("'1 day -1 hour'", day_time(0, 82_800_000)),
+            (
+                "'-1 day'",
+                Expr::Literal(ScalarValue::IntervalDayTime(Some(-(1 << 32)))),
+            ),
+            (
+                "'-1 day -04:00:00'",
+                Expr::Literal(ScalarValue::IntervalDayTime(Some(
+                    -((1 << 32) | 14_400_000),
+                ))),
+            ),
+            (
+                "'1 day 2 hours ago'",
+                Expr::Literal(ScalarValue::IntervalDayTime(Some(
+                    -((1 << 32) | 7_200_000),
+                ))),
+            ),
+        ];
+        for (interval, expected) in cases {
+            let sql = format!("SELECT INTERVAL {}", interval);
+            match logical_plan(&sql).unwrap() {
+                LogicalPlan::Projection { expr, .. } => {
+                    assert_eq!(expr, vec![expected], "{}", sql)
+                }
+                plan => panic!("unexpected plan {:?}", plan),
+            }
+        }
+    }
+
+    #[test]
+    fn select_invalid_interval() {
+        for interval in &["'1 fortnight'", "'04:65'", "'1:2:3:4'", "'1 day 04:05:xx'"] {
+            let sql = format!("SELECT INTERVAL {}", interval);
+            assert!(logical_plan(&sql).is_err(), "{}", sql);
+        }
+    }
+
     #[test]
     fn select_simple_aggregate_with_groupby_and_column_is_in_aggregate_and_groupby() {
         quick_test(
@@ -2684,6 +4213,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn select_aggregate_with_groupby_key_and_rollup() {
+        quick_test(
+            "SELECT order_id, customer_id, SUM(qty) FROM orders GROUP BY order_id, ROLLUP(customer_id)",
+            "Projection: #orders.order_id AS order_id, #orders.customer_id AS customer_id, #SUM(orders.qty)\
+             \n  Union\
+             \n    Projection: #orders.order_id AS orders.order_id, UInt32(NULL) AS orders.customer_id, #SUM(orders.qty)\
+             \n      Aggregate: groupBy=[[#orders.order_id]], aggr=[[SUM(#orders.qty)]]\
+             \n        TableScan: orders projection=None\
+             \n    Projection: #orders.order_id AS orders.order_id, #orders.customer_id AS orders.customer_id, #SUM(orders.qty)\
+             \n      Aggregate: groupBy=[[#orders.order_id, #orders.customer_id]], aggr=[[SUM(#orders.qty)]]\
+             \n        TableScan: orders projection=None",
+        );
+    }
+
+    #[test]
+    fn select_grouping_with_rollup() {
+        quick_test(
+            "SELECT order_id, customer_id, GROUPING(customer_id), SUM(qty) FROM orders GROUP BY order_id, ROLLUP(customer_id)",
+            "Projection: #orders.order_id AS order_id, #orders.customer_id AS customer_id, #GROUPING(orders.customer_id), #SUM(orders.qty)\
+             \n  Union\
+             \n    Projection: #orders.order_id AS orders.order_id, UInt32(NULL) AS orders.customer_id, Int32(1) AS GROUPING(orders.customer_id), #SUM(orders.qty)\
+             \n      Aggregate: groupBy=[[#orders.order_id]], aggr=[[SUM(#orders.qty)]]\
+             \n        TableScan: orders projection=None\
+             \n    Projection: #orders.order_id AS orders.order_id, #orders.customer_id AS orders.customer_id, Int32(0) AS GROUPING(orders.customer_id), #SUM(orders.qty)\
+             \n      Aggregate: groupBy=[[#orders.order_id, #orders.customer_id]], aggr=[[SUM(#orders.qty)]]\
+             \n        TableScan: orders projection=None",
+        );
+    }
+
+    #[test]
+    fn select_grouping_of_non_key() {
+        let sql = "SELECT GROUPING(qty) FROM orders GROUP BY ROLLUP(customer_id)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Arguments of GROUPING(#orders.qty) must be GROUP BY expressions\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn select_aggregate_with_too_many_grouping_sets() {
+        let sql = "SELECT SUM(age) FROM person \
+                   GROUP BY CUBE(id, first_name, last_name, age, state, salary), \
+                   CUBE(id, first_name, last_name, age, state, salary, birth_date)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"GROUP BY has 8192 grouping sets, at most 4096 are supported\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn select_simple_aggregate_with_groupby_position_out_of_range() {
         let sql = "SELECT state, MIN(age) FROM person GROUP BY 0";
@@ -2875,6 +4456,32 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn select_namespaced_function() {
+        let expected = "Projection: pg_catalog.version()\
+                        \n  EmptyRelation";
+        quick_test("SELECT pg_catalog.version()", expected);
+        // found in the search path, before the built-in function of the same name
+        quick_test("SELECT version()", expected);
+
+        // built-in functions are found in the namespaces of the search path
+        quick_test(
+            "SELECT pg_catalog.upper(first_name) FROM person",
+            "Projection: upper(#person.first_name)\
+            \n  TableScan: person projection=None",
+        );
+    }
+
+    #[test]
+    fn select_function_of_unknown_namespace() {
+        let sql = "SELECT foo.upper(first_name) FROM person";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Invalid function 'foo.upper'\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn select_where_nullif_division() {
         let sql = "SELECT c3/(c4+c5) \
@@ -2885,6 +4492,38 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn select_ifnull_nvl_nvl2() {
+        let sql = "SELECT ifnull(age, 0), nvl(first_name, 'x'), nvl2(age, 'y', 'n') FROM person";
+        let expected = "Projection: CASE WHEN #person.age IS NOT NULL THEN CAST(#person.age AS Int64) ELSE Int64(0) END, \
+            CASE WHEN #person.first_name IS NOT NULL THEN #person.first_name ELSE Utf8(\"x\") END, \
+            CASE WHEN #person.age IS NOT NULL THEN Utf8(\"y\") ELSE Utf8(\"n\") END\
+            \n  TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_decode() {
+        let sql = "SELECT decode(state, 'CA', 1, NULL, 2, last_name, 3, 0), decode(age, 1, 'one') FROM person";
+        let expected = "Projection: CASE WHEN #person.state Eq Utf8(\"CA\") THEN Int64(1) \
+            WHEN #person.state IS NULL THEN Int64(2) \
+            WHEN #person.state Eq #person.last_name Or #person.state IS NULL And #person.last_name IS NULL THEN Int64(3) \
+            ELSE Int64(0) END, \
+            CASE WHEN #person.age Eq Int64(1) THEN Utf8(\"one\") END\
+            \n  TableScan: person projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn select_conditional_function_wrong_arity() {
+        let err = logical_plan("SELECT nvl2(age, 1) FROM person")
+            .expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Function 'nvl2' expects 3 arguments, got 2\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn select_where_with_negative_operator() {
         let sql = "SELECT c3 FROM aggregate_test_100 WHERE c3 > -0.1 AND -c4 > 0";
@@ -2903,6 +4542,20 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn select_where_in_subquery() {
+        let sql = "SELECT first_name FROM person \
+                   WHERE age > 20 AND id IN (SELECT customer_id FROM orders)";
+        let expected = "Projection: #person.first_name\
+            \n  Join: #person.id = #__in_subquery_0\
+            \n    Filter: #person.age Gt Int64(20)\
+            \n      TableScan: person projection=None\
+            \n    Projection: #orders.customer_id AS __in_subquery_0\
+            \n      Projection: #orders.customer_id\
+            \n        TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
     #[test]
     fn select_order_by() {
         let sql = "SELECT id FROM person ORDER BY id";
@@ -3130,6 +4783,34 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn project_wildcard_on_joins_with_using() {
+        let sql = "SELECT * \
+            FROM lineitem \
+            JOIN lineitem AS l2 USING (l_item_id) \
+            JOIN lineitem AS l3 USING (l_item_id)";
+        let plan = format!("{:?}", logical_plan(sql).unwrap());
+        assert_eq!(
+            "Projection: #lineitem.item_id, #lineitem.l_description, #lineitem.price, \
+            #l2.item_id, #l2.l_item_id, #l2.l_description, #l2.price, \
+            #l3.item_id, #l3.l_description, #l3.price",
+            plan.lines().next().unwrap()
+        );
+    }
+
+    #[test]
+    fn project_wildcard_ignores_using_in_subquery() {
+        let sql = "SELECT * \
+            FROM (SELECT l2.price FROM lineitem JOIN lineitem AS l2 USING (l_item_id)) AS sub \
+            CROSS JOIN lineitem";
+        let plan = format!("{:?}", logical_plan(sql).unwrap());
+        assert_eq!(
+            "Projection: #sub.price, #lineitem.item_id, #lineitem.l_item_id, \
+            #lineitem.l_description, #lineitem.price",
+            plan.lines().next().unwrap()
+        );
+    }
+
     #[test]
     fn equijoin_explicit_syntax_3_tables() {
         let sql = "SELECT id, order_id, l_description \
@@ -3168,6 +4849,22 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn union_distinct() {
+        let sql = "SELECT order_id from orders UNION SELECT order_id FROM orders \
+                   UNION ALL SELECT order_id FROM orders";
+        let expected = "Union\
+            \n  Aggregate: groupBy=[[#order_id]], aggr=[[]]\
+            \n    Union\
+            \n      Projection: #orders.order_id\
+            \n        TableScan: orders projection=None\
+            \n      Projection: #orders.order_id\
+            \n        TableScan: orders projection=None\
+            \n  Projection: #orders.order_id\
+            \n    TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
     #[test]
     fn union_4_combined_in_one() {
         let sql = "SELECT order_id from orders
@@ -3300,6 +4997,18 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn over_order_by_expression() {
+        let sql = "SELECT order_id, MAX(qty) OVER (ORDER BY qty * 2) from orders";
+        let expected = "\
+        Projection: #orders.order_id, #MAX(orders.qty) ORDER BY [#orders.qty Multiply Int64(2) ASC NULLS FIRST]\
+        \n  Projection: #MAX(orders.qty) ORDER BY [#orders.qty Multiply Int64(2) ASC NULLS FIRST], #orders.order_id, #orders.customer_id, #orders.item_id, #orders.o_item_id, #orders.qty, #orders.price, #orders.delivered\
+        \n    WindowAggr: windowExpr=[[MAX(#orders.qty) ORDER BY [#orders.qty Multiply Int64(2) ASC NULLS FIRST]]]\
+        \n      Projection: #orders.order_id, #orders.customer_id, #orders.item_id, #orders.o_item_id, #orders.qty, #orders.price, #orders.delivered, #orders.qty Multiply Int64(2) AS orders.qty Multiply Int64(2)\
+        \n        TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
     #[test]
     fn over_order_by_with_window_frame_double_end() {
         let sql = "SELECT order_id, MAX(qty) OVER (ORDER BY order_id ROWS BETWEEN 3 PRECEDING and 3 FOLLOWING), MIN(qty) OVER (ORDER BY order_id DESC) from orders";
@@ -3555,11 +5264,11 @@ mod tests {
     }
 
     #[test]
-    fn only_union_all_supported() {
+    fn only_union_supported() {
         let sql = "SELECT order_id from orders EXCEPT SELECT order_id FROM orders";
         let err = logical_plan(sql).expect_err("query should have failed");
         assert_eq!(
-            "NotImplemented(\"Only UNION ALL is supported, found EXCEPT\")",
+            "NotImplemented(\"Only UNION and UNION ALL are supported, found EXCEPT\")",
             format!("{:?}", err)
         );
     }
@@ -3615,6 +5324,17 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn analyze_table() {
+        quick_test("ANALYZE TABLE person", "Analyze: \"person\"");
+
+        let err = logical_plan("ANALYZE unknown").expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Table or CTE with name 'unknown' not found\")",
+            format!("{:?}", err)
+        );
+    }
+
     fn logical_plan(sql: &str) -> Result<LogicalPlan> {
         let planner = SqlToRel::new(&MockContextProvider {});
         let result = DFParser::parse_sql(sql);
@@ -3695,6 +5415,25 @@ mod tests {
                     Field::new("c12", DataType::Float64, false),
                     Field::new("c13", DataType::Utf8, false),
                 ])),
+                "events" => Some(Schema::new(vec![
+                    Field::new("id", DataType::UInt32, false),
+                    Field::new(
+                        "payload",
+                        DataType::Struct(vec![
+                            Field::new(
+                                "user",
+                                DataType::Struct(vec![Field::new(
+                                    "name",
+                                    DataType::Utf8,
+                                    true,
+                                )]),
+                                true,
+                            ),
+                            Field::new("kind", DataType::Utf8, true),
+                        ]),
+                        true,
+                    ),
+                ])),
                 _ => None,
             };
             schema.map(|s| -> Arc<dyn TableProvider> {
@@ -3712,12 +5451,18 @@ mod tests {
                     Arc::new(DataType::Float64),
                     f,
                 ))),
+                "pg_catalog.version" => Some(Arc::new(create_udf(
+                    "pg_catalog.version",
+                    vec![],
+                    Arc::new(DataType::Utf8),
+                    f,
+                ))),
                 _ => None,
             }
         }
 
         fn get_aggregate_meta(&self, _name: &str) -> Option<Arc<AggregateUDF>> {
-            unimplemented!()
+            None
         }
     }
 }
@@ -17,12 +17,14 @@
 
 //! SQL Utility Functions
 
-use crate::logical_plan::{Expr, LogicalPlan};
+use crate::logical_plan::{Expr, GetFieldAccess, LogicalPlan};
+use crate::physical_plan::aggregates::AggregateFunction;
 use crate::scalar::ScalarValue;
 use crate::{
     error::{DataFusionError, Result},
     logical_plan::{Column, ExpressionVisitor, Recursion},
 };
+use sqlparser::ast::{Expr as SQLExpr, FunctionArg};
 use std::collections::HashMap;
 
 /// Collect all deeply nested `Expr::AggregateFunction` and
@@ -233,6 +235,9 @@ where
                 fun,
                 args,
                 distinct,
+                ignore_nulls,
+                order_by,
+                filter,
             } => Ok(Expr::AggregateFunction {
                 fun: fun.clone(),
                 args: args
@@ -240,6 +245,17 @@ where
                     .map(|e| clone_with_replacement(e, replacement_fn))
                     .collect::<Result<Vec<Expr>>>()?,
                 distinct: *distinct,
+                ignore_nulls: *ignore_nulls,
+                order_by: order_by
+                    .iter()
+                    .map(|e| clone_with_replacement(e, replacement_fn))
+                    .collect::<Result<Vec<Expr>>>()?,
+                filter: match filter {
+                    Some(filter) => {
+                        Some(Box::new(clone_with_replacement(filter, replacement_fn)?))
+                    }
+                    None => None,
+                },
             }),
             Expr::WindowFunction {
                 fun,
@@ -395,6 +411,35 @@ where
                 end: end_bound.clone(),
                 offset: *offset,
             }),
+            Expr::HigherOrderFunction { fun, args } => Ok(Expr::HigherOrderFunction {
+                fun: *fun,
+                args: args
+                    .iter()
+                    .map(|e| clone_with_replacement(e, replacement_fn))
+                    .collect::<Result<Vec<Expr>>>()?,
+            }),
+            // the columns of the body are the parameters of the lambda
+            Expr::Lambda { .. } => Ok(expr.clone()),
+            Expr::GetIndexedField { expr, field } => Ok(Expr::GetIndexedField {
+                expr: Box::new(clone_with_replacement(expr, replacement_fn)?),
+                field: match field {
+                    GetFieldAccess::ListIndex { key } => GetFieldAccess::ListIndex {
+                        key: Box::new(clone_with_replacement(key, replacement_fn)?),
+                    },
+                    GetFieldAccess::ListRange { start, stop } => {
+                        GetFieldAccess::ListRange {
+                            start: Box::new(clone_with_replacement(
+                                start,
+                                replacement_fn,
+                            )?),
+                            stop: Box::new(clone_with_replacement(stop, replacement_fn)?),
+                        }
+                    }
+                    GetFieldAccess::NamedStructField { name } => {
+                        GetFieldAccess::NamedStructField { name: name.clone() }
+                    }
+                },
+            }),
             Expr::Wildcard => Ok(Expr::Wildcard),
         },
     }
@@ -456,6 +501,67 @@ pub(crate) fn resolve_aliases_to_exprs(
     })
 }
 
+/// The name and the arguments of a `ROLLUP(...)` or `CUBE(...)` item of GROUP BY, if
+/// it is one
+pub(crate) fn grouping_set_function(expr: &SQLExpr) -> Option<(String, &[FunctionArg])> {
+    match expr {
+        SQLExpr::Function(function)
+            if function.name.0.len() == 1
+                && function.name.0[0].quote_style.is_none()
+                && function.over.is_none() =>
+        {
+            let name = function.name.0[0].value.to_ascii_lowercase();
+            if name == "rollup" || name == "cube" {
+                Some((name, function.args.as_slice()))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The grouping sets of `ROLLUP(keys)`: all the keys, then all the keys but the last
+/// one and so on, down to no keys
+pub(crate) fn rollup_sets(keys: &[Expr]) -> Vec<Vec<Expr>> {
+    (0..=keys.len()).rev().map(|n| keys[..n].to_vec()).collect()
+}
+
+/// The grouping sets of `CUBE(keys)`: all the subsets of the keys, starting with all
+/// the keys and ending with no keys
+pub(crate) fn cube_sets(keys: &[Expr]) -> Vec<Vec<Expr>> {
+    let subsets = 1usize << keys.len();
+    (0..subsets)
+        .rev()
+        .map(|mask| {
+            keys.iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << (keys.len() - 1 - i)) != 0)
+                .map(|(_, key)| key.clone())
+                .collect()
+        })
+        .collect()
+}
+
+/// Whether `expr` is a call to GROUPING, which tells the grouping set of a row
+pub(crate) fn is_grouping(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::AggregateFunction {
+            fun: AggregateFunction::Grouping,
+            ..
+        }
+    )
+}
+
+/// The value of `GROUPING(args)` in the rows of the grouping set `set`: a bit for each
+/// argument, the first one being the most significant, that is set when the argument
+/// is not a key of the set
+pub(crate) fn grouping_id(args: &[Expr], set: &[Expr]) -> i32 {
+    args.iter()
+        .fold(0, |id, arg| (id << 1) | (!set.contains(arg) as i32))
+}
+
 type WindowSortKey = Vec<Expr>;
 
 /// Generate a sort key for a given window expr's partition_by and order_bu expr
@@ -643,6 +749,24 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_grouping_sets() {
+        let keys = vec![col("a"), col("b")];
+        assert_eq!(
+            rollup_sets(&keys),
+            vec![vec![col("a"), col("b")], vec![col("a")], vec![]]
+        );
+        assert_eq!(
+            cube_sets(&keys),
+            vec![
+                vec![col("a"), col("b")],
+                vec![col("a")],
+                vec![col("b")],
+                vec![]
+            ]
+        );
+    }
+
     #[test]
     fn test_find_sort_exprs() -> Result<()> {
         let exprs = &[
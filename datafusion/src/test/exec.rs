@@ -316,6 +316,112 @@ impl ExecutionPlan for BarrierExec {
     }
 }
 
+/// A mock execution plan that produces a single batch per partition and then
+/// never finishes. Used to check that consumers cancel their inputs.
+#[derive(Debug)]
+pub struct PendingExec {
+    schema: SchemaRef,
+    batch: Option<RecordBatch>,
+    partitions: usize,
+    /// cloned into every stream, so that live streams can be counted
+    stream_refs: Arc<()>,
+}
+
+impl PendingExec {
+    /// Create a new exec with some number of partitions
+    pub fn new(batch: RecordBatch, partitions: usize) -> Self {
+        Self {
+            schema: batch.schema(),
+            batch: Some(batch),
+            partitions,
+            stream_refs: Arc::new(()),
+        }
+    }
+
+    /// Create a new exec whose partitions never produce any batch
+    pub fn without_batches(schema: SchemaRef, partitions: usize) -> Self {
+        Self {
+            schema,
+            batch: None,
+            partitions,
+            stream_refs: Arc::new(()),
+        }
+    }
+
+    /// Number of streams created by `execute` that were not dropped yet
+    pub fn open_streams(&self) -> usize {
+        Arc::strong_count(&self.stream_refs) - 1
+    }
+}
+
+#[async_trait]
+impl ExecutionPlan for PendingExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.partitions)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        unimplemented!()
+    }
+
+    /// Returns a stream which yields the batch, if any, and then stays pending forever
+    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+        assert!(partition < self.partitions);
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        if let Some(batch) = &self.batch {
+            tx.send(Ok(batch.clone())).await.ok();
+        }
+        Ok(Box::pin(PendingStream {
+            schema: self.schema(),
+            inner: ReceiverStream::new(rx),
+            _sender: tx,
+            _stream_ref: self.stream_refs.clone(),
+        }))
+    }
+}
+
+/// Stream returned by [PendingExec], the kept sender prevents it from finishing
+#[derive(Debug)]
+struct PendingStream {
+    schema: SchemaRef,
+    inner: ReceiverStream<ArrowResult<RecordBatch>>,
+    _sender: tokio::sync::mpsc::Sender<ArrowResult<RecordBatch>>,
+    _stream_ref: Arc<()>,
+}
+
+impl Stream for PendingStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl RecordBatchStream for PendingStream {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+}
+
 /// A mock execution plan that errors on a call to execute
 #[derive(Debug)]
 pub struct ErrorExec {
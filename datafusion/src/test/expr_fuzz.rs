@@ -0,0 +1,377 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Random expressions and batches for cross-checking the vectorized evaluation of
+//! physical expressions against a row by row interpreter
+
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BooleanArray, Int64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Operator;
+use crate::physical_plan::expressions::{binary, col, is_not_null, is_null, lit, not};
+use crate::physical_plan::PhysicalExpr;
+use crate::scalar::ScalarValue;
+
+/// Integer values are kept in `-MAX_VALUE..=MAX_VALUE` so that expressions up to
+/// `MAX_DEPTH` deep cannot overflow
+const MAX_VALUE: i64 = 100;
+const MAX_DEPTH: usize = 3;
+const MAX_ROWS: usize = 16;
+
+/// An expression over the columns of [ExprFuzzer::schema]
+#[derive(Debug, Clone)]
+pub enum FuzzExpr {
+    /// Column with the given index
+    Column(usize),
+    /// Int64 literal
+    Int(Option<i64>),
+    /// Boolean literal
+    Bool(Option<bool>),
+    /// Binary operation
+    Binary(Box<FuzzExpr>, Operator, Box<FuzzExpr>),
+    /// NOT
+    Not(Box<FuzzExpr>),
+    /// IS NULL
+    IsNull(Box<FuzzExpr>),
+    /// IS NOT NULL
+    IsNotNull(Box<FuzzExpr>),
+}
+
+impl FuzzExpr {
+    /// Build the physical expression
+    pub fn to_physical(&self, schema: &Schema) -> Result<Arc<dyn PhysicalExpr>> {
+        match self {
+            FuzzExpr::Column(i) => col(schema.field(*i).name(), schema),
+            FuzzExpr::Int(v) => Ok(lit(ScalarValue::Int64(*v))),
+            FuzzExpr::Bool(v) => Ok(lit(ScalarValue::Boolean(*v))),
+            FuzzExpr::Binary(l, op, r) => {
+                binary(l.to_physical(schema)?, *op, r.to_physical(schema)?, schema)
+            }
+            FuzzExpr::Not(e) => not(e.to_physical(schema)?, schema),
+            FuzzExpr::IsNull(e) => is_null(e.to_physical(schema)?),
+            FuzzExpr::IsNotNull(e) => is_not_null(e.to_physical(schema)?),
+        }
+    }
+
+    /// Evaluate the expression on the row `row` of `batch`
+    pub fn evaluate_row(&self, batch: &RecordBatch, row: usize) -> Result<ScalarValue> {
+        Ok(match self {
+            FuzzExpr::Column(i) => ScalarValue::try_from_array(batch.column(*i), row)?,
+            FuzzExpr::Int(v) => ScalarValue::Int64(*v),
+            FuzzExpr::Bool(v) => ScalarValue::Boolean(*v),
+            FuzzExpr::Binary(l, op, r) => {
+                let l = l.evaluate_row(batch, row)?;
+                let r = r.evaluate_row(batch, row)?;
+                evaluate_binary(l, *op, r)?
+            }
+            FuzzExpr::Not(e) => match e.evaluate_row(batch, row)? {
+                ScalarValue::Boolean(v) => ScalarValue::Boolean(v.map(|v| !v)),
+                v => return Err(unexpected(&v)),
+            },
+            FuzzExpr::IsNull(e) => {
+                ScalarValue::Boolean(Some(e.evaluate_row(batch, row)?.is_null()))
+            }
+            FuzzExpr::IsNotNull(e) => {
+                ScalarValue::Boolean(Some(!e.evaluate_row(batch, row)?.is_null()))
+            }
+        })
+    }
+}
+
+fn unexpected(value: &ScalarValue) -> DataFusionError {
+    DataFusionError::Internal(format!("Unexpected value {:?}", value))
+}
+
+fn evaluate_binary(l: ScalarValue, op: Operator, r: ScalarValue) -> Result<ScalarValue> {
+    use ScalarValue::{Boolean, Int64};
+    Ok(match (l, r) {
+        (Boolean(l), Boolean(r)) => match op {
+            // three-valued logic: NULL unless the other operand decides the result
+            Operator::And => match (l, r) {
+                (Some(false), _) | (_, Some(false)) => Boolean(Some(false)),
+                (Some(true), Some(true)) => Boolean(Some(true)),
+                _ => Boolean(None),
+            },
+            Operator::Or => match (l, r) {
+                (Some(true), _) | (_, Some(true)) => Boolean(Some(true)),
+                (Some(false), Some(false)) => Boolean(Some(false)),
+                _ => Boolean(None),
+            },
+            Operator::Eq => Boolean(l.zip(r).map(|(l, r)| l == r)),
+            Operator::NotEq => Boolean(l.zip(r).map(|(l, r)| l != r)),
+            _ => {
+                return Err(DataFusionError::Internal(format!(
+                    "Unexpected boolean operator {}",
+                    op
+                )))
+            }
+        },
+        (Int64(l), Int64(r)) => {
+            let (l, r) = match (l, r) {
+                (Some(l), Some(r)) => (l, r),
+                _ if is_comparison(op) => return Ok(Boolean(None)),
+                _ => return Ok(Int64(None)),
+            };
+            match op {
+                Operator::Plus => Int64(Some(l + r)),
+                Operator::Minus => Int64(Some(l - r)),
+                Operator::Multiply => Int64(Some(l * r)),
+                Operator::Divide => Int64(Some(l / r)),
+                Operator::Modulus => Int64(Some(l % r)),
+                Operator::Eq => Boolean(Some(l == r)),
+                Operator::NotEq => Boolean(Some(l != r)),
+                Operator::Lt => Boolean(Some(l < r)),
+                Operator::LtEq => Boolean(Some(l <= r)),
+                Operator::Gt => Boolean(Some(l > r)),
+                Operator::GtEq => Boolean(Some(l >= r)),
+                _ => {
+                    return Err(DataFusionError::Internal(format!(
+                        "Unexpected integer operator {}",
+                        op
+                    )))
+                }
+            }
+        }
+        (l, _) => return Err(unexpected(&l)),
+    })
+}
+
+fn is_comparison(op: Operator) -> bool {
+    matches!(
+        op,
+        Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+    )
+}
+
+/// Generates random expressions and batches from a seed, so that failures can be
+/// reproduced
+pub struct ExprFuzzer {
+    rng: StdRng,
+    schema: SchemaRef,
+}
+
+impl ExprFuzzer {
+    /// Create a fuzzer generating values from `seed`
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            schema: Arc::new(Schema::new(vec![
+                Field::new("a", DataType::Int64, true),
+                Field::new("b", DataType::Int64, true),
+                Field::new("c", DataType::Boolean, true),
+            ])),
+        }
+    }
+
+    /// The schema of the generated batches
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// A batch of up to `MAX_ROWS` rows, possibly empty, with some NULLs
+    pub fn batch(&mut self) -> Result<RecordBatch> {
+        let num_rows = self.rng.gen_range(0..=MAX_ROWS);
+        let null_probability = self.rng.gen_range(0.0..0.5);
+        let mut ints = || -> ArrayRef {
+            Arc::new(
+                (0..num_rows)
+                    .map(|_| {
+                        if self.rng.gen_bool(null_probability) {
+                            None
+                        } else {
+                            Some(self.rng.gen_range(-MAX_VALUE..=MAX_VALUE))
+                        }
+                    })
+                    .collect::<Int64Array>(),
+            )
+        };
+        let a = ints();
+        let b = ints();
+        let c: ArrayRef = Arc::new(
+            (0..num_rows)
+                .map(|_| {
+                    if self.rng.gen_bool(null_probability) {
+                        None
+                    } else {
+                        Some(self.rng.gen_bool(0.5))
+                    }
+                })
+                .collect::<BooleanArray>(),
+        );
+        Ok(RecordBatch::try_new(self.schema.clone(), vec![a, b, c])?)
+    }
+
+    /// A random boolean expression
+    pub fn bool_expr(&mut self) -> FuzzExpr {
+        self.gen_bool(MAX_DEPTH)
+    }
+
+    /// A random Int64 expression
+    pub fn int_expr(&mut self) -> FuzzExpr {
+        self.gen_int(MAX_DEPTH)
+    }
+
+    fn gen_int(&mut self, depth: usize) -> FuzzExpr {
+        let choice = if depth == 0 {
+            self.rng.gen_range(0..2)
+        } else {
+            self.rng.gen_range(0..4)
+        };
+        match choice {
+            0 => FuzzExpr::Column(self.rng.gen_range(0..2)),
+            1 => FuzzExpr::Int(if self.rng.gen_bool(0.1) {
+                None
+            } else {
+                Some(self.rng.gen_range(-MAX_VALUE..=MAX_VALUE))
+            }),
+            2 => {
+                let op = [Operator::Plus, Operator::Minus, Operator::Multiply]
+                    [self.rng.gen_range(0..3)];
+                FuzzExpr::Binary(
+                    Box::new(self.gen_int(depth - 1)),
+                    op,
+                    Box::new(self.gen_int(depth - 1)),
+                )
+            }
+            _ => {
+                // divisors are non-zero literals, division by zero is an error
+                let op = [Operator::Divide, Operator::Modulus][self.rng.gen_range(0..2)];
+                let mut divisor = self.rng.gen_range(1..=MAX_VALUE);
+                if self.rng.gen_bool(0.5) {
+                    divisor = -divisor;
+                }
+                FuzzExpr::Binary(
+                    Box::new(self.gen_int(depth - 1)),
+                    op,
+                    Box::new(FuzzExpr::Int(Some(divisor))),
+                )
+            }
+        }
+    }
+
+    fn gen_bool(&mut self, depth: usize) -> FuzzExpr {
+        let choice = if depth == 0 {
+            self.rng.gen_range(0..2)
+        } else {
+            self.rng.gen_range(0..6)
+        };
+        match choice {
+            0 => FuzzExpr::Column(2),
+            1 => FuzzExpr::Bool(match self.rng.gen_range(0..3) {
+                0 => None,
+                1 => Some(false),
+                _ => Some(true),
+            }),
+            2 => {
+                let op = [
+                    Operator::Eq,
+                    Operator::NotEq,
+                    Operator::Lt,
+                    Operator::LtEq,
+                    Operator::Gt,
+                    Operator::GtEq,
+                ][self.rng.gen_range(0..6)];
+                FuzzExpr::Binary(
+                    Box::new(self.gen_int(depth - 1)),
+                    op,
+                    Box::new(self.gen_int(depth - 1)),
+                )
+            }
+            3 => {
+                let op = [Operator::And, Operator::Or, Operator::Eq, Operator::NotEq]
+                    [self.rng.gen_range(0..4)];
+                FuzzExpr::Binary(
+                    Box::new(self.gen_bool(depth - 1)),
+                    op,
+                    Box::new(self.gen_bool(depth - 1)),
+                )
+            }
+            4 => FuzzExpr::Not(Box::new(self.gen_bool(depth - 1))),
+            _ => {
+                let arg = if self.rng.gen_bool(0.5) {
+                    self.gen_int(depth - 1)
+                } else {
+                    self.gen_bool(depth - 1)
+                };
+                if self.rng.gen_bool(0.5) {
+                    FuzzExpr::IsNull(Box::new(arg))
+                } else {
+                    FuzzExpr::IsNotNull(Box::new(arg))
+                }
+            }
+        }
+    }
+}
+
+/// Checks that the vectorized evaluation of `expr` on `batch` matches its row by row
+/// evaluation, with an error describing the mismatch otherwise
+pub fn check_expr(expr: &FuzzExpr, batch: &RecordBatch) -> Result<()> {
+    let physical = expr.to_physical(&batch.schema())?;
+    let result = physical.evaluate(batch)?.into_array(batch.num_rows());
+    if result.len() != batch.num_rows() {
+        return Err(DataFusionError::Execution(format!(
+            "{} produced {} rows for {} input rows",
+            physical,
+            result.len(),
+            batch.num_rows()
+        )));
+    }
+    for row in 0..batch.num_rows() {
+        let actual = ScalarValue::try_from_array(&result, row)?;
+        let expected = expr.evaluate_row(batch, row)?;
+        if actual != expected {
+            return Err(DataFusionError::Execution(format!(
+                "{} is {:?} instead of {:?} for row {} of\n{}",
+                physical,
+                actual,
+                expected,
+                row,
+                arrow::util::pretty::pretty_format_batches(&[batch.clone()])?
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzz_expressions() -> Result<()> {
+        let mut fuzzer = ExprFuzzer::new(0);
+        for _ in 0..1000 {
+            let batch = fuzzer.batch()?;
+            let expr = fuzzer.int_expr();
+            check_expr(&expr, &batch)?;
+            let expr = fuzzer.bool_expr();
+            check_expr(&expr, &batch)?;
+        }
+        Ok(())
+    }
+}
@@ -20,6 +20,7 @@
 use crate::datasource::{MemTable, TableProvider};
 use crate::error::Result;
 use crate::logical_plan::{LogicalPlan, LogicalPlanBuilder};
+use crate::physical_plan::parquet::{DeletionVector, DeletionVectorProvider};
 use array::{
     Array, ArrayRef, StringArray, TimestampMicrosecondArray, TimestampMillisecondArray,
     TimestampNanosecondArray, TimestampSecondArray,
@@ -343,6 +344,17 @@ pub fn make_timestamps() -> RecordBatch {
     .unwrap()
 }
 
+/// Deletes the rows with the given indices from every file read
+#[derive(Debug)]
+pub struct DeleteRows(pub Vec<u64>);
+
+impl DeletionVectorProvider for DeleteRows {
+    fn deletion_vector(&self, _: &str) -> Result<Option<Arc<DeletionVector>>> {
+        Ok(Some(Arc::new(DeletionVector::new(self.0.clone()))))
+    }
+}
+
 pub mod exec;
+pub mod expr_fuzz;
 pub mod user_defined;
 pub mod variable;
@@ -17,13 +17,19 @@
 
 use std::sync::Arc;
 
-use arrow::datatypes::{DataType, Field, Schema};
+use arrow::datatypes::{DataType, Field, Int64Type, Schema};
 use arrow::{
-    array::{Int32Array, StringArray},
+    array::{
+        Array, Float64Array, Int32Array, Int64Array, ListArray, StringArray, UInt64Array,
+    },
     record_batch::RecordBatch,
 };
 
 use datafusion::error::Result;
+use datafusion::logical_plan::{
+    array_filter, array_reduce, array_transform, col, count, lambda, lit,
+    percentile_cont, percentile_disc, sum,
+};
 use datafusion::{datasource::MemTable, prelude::JoinType};
 
 use datafusion::execution::context::ExecutionContext;
@@ -77,3 +83,249 @@ async fn join() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn higher_order_functions() -> Result<()> {
+    let lists = ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+        Some(vec![Some(1), Some(2), Some(3)]),
+        None,
+        Some(vec![Some(4), Some(5)]),
+    ]);
+    let schema = Arc::new(Schema::new(vec![Field::new(
+        "l",
+        lists.data_type().clone(),
+        true,
+    )]));
+    let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(lists)])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+
+    let df = ctx.table("t")?.select(vec![
+        array_transform(col("l"), lambda(&["x"], col("x") * lit(2_i64))).alias("doubled"),
+        array_filter(col("l"), lambda(&["x"], col("x").gt(lit(1_i64)))).alias("big"),
+        array_reduce(
+            col("l"),
+            lit(0_i64),
+            lambda(&["acc", "x"], col("acc") + col("x")),
+        )
+        .alias("sum"),
+    ])?;
+    let batches = df.collect().await?;
+
+    let lists = |i: usize| -> Vec<Option<Vec<Option<i64>>>> {
+        let lists = batches[0]
+            .column(i)
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap();
+        (0..lists.len())
+            .map(|row| {
+                lists.is_valid(row).then(|| {
+                    let values = lists.value(row);
+                    let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+                    values.iter().collect()
+                })
+            })
+            .collect()
+    };
+    assert_eq!(
+        lists(0),
+        vec![
+            Some(vec![Some(2), Some(4), Some(6)]),
+            None,
+            Some(vec![Some(8), Some(10)])
+        ]
+    );
+    assert_eq!(
+        lists(1),
+        vec![
+            Some(vec![Some(2), Some(3)]),
+            None,
+            Some(vec![Some(4), Some(5)])
+        ]
+    );
+    let sums = batches[0]
+        .column(2)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(
+        sums.iter().collect::<Vec<_>>(),
+        vec![Some(6), None, Some(9)]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn percentiles() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("g", DataType::Utf8, false),
+        Field::new("x", DataType::Int32, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "a", "b", "a", "a", "b"])),
+            Arc::new(Int32Array::from(vec![
+                Some(1),
+                Some(4),
+                Some(10),
+                Some(2),
+                Some(3),
+                None,
+            ])),
+        ],
+    )?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+
+    let df = ctx
+        .table("t")?
+        .aggregate(
+            vec![col("g")],
+            vec![
+                percentile_cont(lit(0.5), col("x").sort(true, false)).alias("cont"),
+                percentile_disc(lit(0.5), col("x").sort(true, false)).alias("disc"),
+                percentile_disc(lit(0.25), col("x").sort(false, false)).alias("desc"),
+            ],
+        )?
+        .sort(vec![col("g").sort(true, false)])?;
+    let batches = df.collect().await?;
+
+    let cont = batches[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(cont.iter().collect::<Vec<_>>(), vec![Some(2.5), Some(10.0)]);
+    let disc = |i: usize| {
+        let array = batches[0]
+            .column(i)
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap();
+        array.iter().collect::<Vec<_>>()
+    };
+    assert_eq!(disc(2), vec![Some(2), Some(10)]);
+    assert_eq!(disc(3), vec![Some(4), Some(10)]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn filtered_aggregates() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("g", DataType::Utf8, false),
+        Field::new("x", DataType::Int32, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "a", "b", "a", "a", "b"])),
+            Arc::new(Int32Array::from(vec![
+                Some(1),
+                Some(4),
+                Some(10),
+                Some(2),
+                Some(3),
+                None,
+            ])),
+        ],
+    )?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+
+    // the rows whose predicate is NULL are not aggregated
+    let df = ctx
+        .table("t")?
+        .aggregate(
+            vec![col("g")],
+            vec![
+                sum(col("x")).filter(col("x").gt(lit(1)))?.alias("s"),
+                count(lit(1_u8)).filter(col("x").gt(lit(2)))?.alias("c"),
+            ],
+        )?
+        .sort(vec![col("g").sort(true, false)])?;
+    let batches = df.collect().await?;
+
+    let s = batches[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(s.iter().collect::<Vec<_>>(), vec![Some(9), Some(10)]);
+    let c = batches[0]
+        .column(2)
+        .as_any()
+        .downcast_ref::<UInt64Array>()
+        .unwrap();
+    assert_eq!(c.iter().collect::<Vec<_>>(), vec![Some(2), Some(1)]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn get_indexed_field() -> Result<()> {
+    let lists = ListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+        Some(vec![Some(1), Some(2), Some(3)]),
+        None,
+        Some(vec![Some(4), Some(5)]),
+    ]);
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("l", lists.data_type().clone(), true),
+        Field::new("i", DataType::Int32, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(lists), Arc::new(Int32Array::from(vec![3, 1, 3]))],
+    )?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+
+    let df = ctx.table("t")?.select(vec![
+        col("l").index(lit(1_i64)),
+        col("l").index(col("i")).alias("dynamic"),
+        col("l").index(lit(-1_i64)).alias("last"),
+        col("l").slice(lit(2_i64), lit(-1_i64)).alias("tail"),
+    ])?;
+    assert_eq!(df.schema().field(0).name(), "t.l[Int64(1)]");
+    assert_eq!(df.schema().field(1).data_type(), &DataType::Int64);
+    let batches = df.collect().await?;
+
+    let values = |i: usize| {
+        batches[0]
+            .column(i)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(values(0), vec![Some(1), None, Some(4)]);
+    assert_eq!(values(1), vec![Some(3), None, None]);
+    assert_eq!(values(2), vec![Some(3), None, Some(5)]);
+    let tails = batches[0]
+        .column(3)
+        .as_any()
+        .downcast_ref::<ListArray>()
+        .unwrap();
+    let tails = (0..tails.len())
+        .map(|row| {
+            tails.is_valid(row).then(|| {
+                let values = tails.value(row);
+                let values = values.as_any().downcast_ref::<Int64Array>().unwrap();
+                values.iter().collect::<Vec<_>>()
+            })
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tails,
+        vec![Some(vec![Some(2), Some(3)]), None, Some(vec![Some(5)])]
+    );
+    Ok(())
+}
@@ -295,6 +295,7 @@ fn prune_int32_eq() {
             // This should prune out groups without error
             assert_eq!(output.predicate_evaluation_errors(), Some(0));
             assert_eq!(output.row_groups_pruned(), Some(3));
+            assert_eq!(output.rows_pruned(), Some(15));
             assert_eq!(output.result_rows, 1, "{}", output.description());
         })
 }
@@ -582,6 +583,11 @@ impl TestOutput {
         self.metric_value("numRowGroupsPruned for PARQUET_FILE")
     }
 
+    /// The number of rows in the pruned row groups
+    fn rows_pruned(&self) -> Option<usize> {
+        self.metric_value("numRowsPruned for PARQUET_FILE")
+    }
+
     fn description(&self) -> String {
         let metrics = self
             .metrics
@@ -40,7 +40,11 @@ use arrow::{
 
 use datafusion::assert_batches_eq;
 use datafusion::assert_batches_sorted_eq;
-use datafusion::logical_plan::LogicalPlan;
+use datafusion::logical_plan::{
+    array_agg_ignore_nulls, array_agg_limit, first, last, string_agg, JoinType,
+    LogicalPlan,
+};
+use datafusion::physical_plan::string_expressions::Collation;
 use datafusion::prelude::*;
 use datafusion::{
     datasource::{csv::CsvReadOptions, MemTable},
@@ -905,6 +909,31 @@ async fn csv_query_avg_multi_batch() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn csv_query_compensated_float_sum() -> Result<()> {
+    let sql = "SELECT avg(c12), sum(c12), sum(c11) FROM aggregate_test_100";
+    let mut results = vec![];
+    for batch_size in [1, 7, 100] {
+        let config = ExecutionConfig::new()
+            .with_batch_size(batch_size)
+            .with_compensated_float_sum(true);
+        let mut ctx = ExecutionContext::with_config(config);
+        register_aggregate_csv(&mut ctx)?;
+        results.push(execute(&mut ctx, sql).await);
+    }
+    // the results do not depend on the batch size
+    assert_eq!(results[0], results[1]);
+    assert_eq!(results[0], results[2]);
+    let avg = results[0][0][0].parse::<f64>().unwrap();
+    assert!((avg - 0.5089725).abs() < 0.01);
+
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    ctx.sql("SET datafusion.execution.compensated_float_sum = true")?;
+    assert_eq!(execute(&mut ctx, sql).await, results[0]);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_nullif_divide_by_0() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -928,6 +957,379 @@ async fn csv_query_nullif_divide_by_0() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_array_agg() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Int32, true)]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(Int32Array::from(vec![Some(1), None, Some(3)]))],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+
+    let list_values = |batches: &[RecordBatch]| {
+        let list = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .unwrap()
+            .value(0);
+        list.as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>()
+    };
+
+    let actual = execute_to_batches(&mut ctx, "SELECT array_agg(c1) FROM test").await;
+    assert_eq!(actual[0].schema().field(0).name(), "ARRAY_AGG(c1)");
+    assert_eq!(list_values(&actual), vec![Some(1), None, Some(3)]);
+
+    let actual = ctx
+        .table("test")?
+        .aggregate(vec![], vec![array_agg_ignore_nulls(col("c1"))])?
+        .collect()
+        .await?;
+    assert!(actual[0].schema().field(0).name().ends_with("IGNORE NULLS"));
+    assert_eq!(list_values(&actual), vec![Some(1), Some(3)]);
+
+    let actual = execute_to_batches(&mut ctx, "SELECT array_agg(c1, 2) FROM test").await;
+    assert_eq!(list_values(&actual), vec![Some(1), None]);
+
+    let order_by = vec![col("c1").sort(false, false)];
+    let actual = ctx
+        .table("test")?
+        .aggregate(vec![], vec![array_agg_limit(col("c1"), order_by, 2)])?
+        .collect()
+        .await?;
+    assert_eq!(list_values(&actual), vec![Some(3), Some(1)]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_any_value() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("c1", DataType::Utf8, false),
+        Field::new("c2", DataType::Int32, true),
+    ]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "a", "b", "b"])),
+            Arc::new(Int32Array::from(vec![None, Some(1), None, None])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+
+    let sql = "SELECT c1, any_value(c2) FROM test GROUP BY c1 ORDER BY c1";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["a", "1"], vec!["b", "NULL"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_group_by_list() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Utf8, true)]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(StringArray::from(vec![
+            Some("a,b"),
+            Some("b"),
+            None,
+            Some("a,b"),
+            Some("b,a"),
+        ]))],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+
+    let sql = "SELECT array_to_string(tags, '|') AS t, n \
+        FROM (SELECT tags, COUNT(*) AS n \
+        FROM (SELECT string_to_array(c1, ',') AS tags FROM test) GROUP BY tags) \
+        ORDER BY t NULLS LAST";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["a|b", "2"],
+        vec!["b", "1"],
+        vec!["b|a", "1"],
+        vec!["NULL", "1"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT array_to_string(tags, '|') AS t \
+        FROM (SELECT DISTINCT string_to_array(c1, ',') AS tags FROM test) \
+        ORDER BY t NULLS LAST";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["a|b"], vec!["b"], vec!["b|a"], vec!["NULL"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_regr() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("y", DataType::Int32, true),
+        Field::new("x", DataType::Int64, true),
+    ]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![
+                Some(3),
+                Some(5),
+                Some(7),
+                Some(9),
+                Some(100),
+            ])),
+            Arc::new(Int64Array::from(vec![
+                Some(1),
+                Some(2),
+                Some(3),
+                Some(4),
+                None,
+            ])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+
+    // y = 2x + 1, the row without x is ignored
+    let sql = "SELECT regr_slope(y, x), regr_intercept(y, x), regr_r2(y, x), \
+               regr_count(y, x), regr_avgx(y, x), regr_avgy(y, x), \
+               regr_sxx(y, x), regr_syy(y, x), regr_sxy(y, x) FROM test";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["2", "1", "1", "4", "2.5", "6", "5", "20", "10"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_moments_of_decimals() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("y", DataType::Int32, true),
+        Field::new("x", DataType::Int64Decimal(2), true),
+    ]));
+    let x = vec![2, 4, 4, 4, 5, 5, 7, 9];
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(
+                x.iter().map(|x| 2 * x + 1).collect::<Vec<i32>>(),
+            )),
+            Arc::new(Int64Decimal2Array::from(
+                x.iter().map(|x| *x as i64 * 100).collect::<Vec<_>>(),
+            )),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+
+    // the decimals are 2.00, 4.00, 4.00, 4.00, 5.00, 5.00, 7.00 and 9.00
+    let sql = "SELECT var_pop(x), variance(x), stddev_pop(x), covar_pop(y, x), \
+               corr(y, x), regr_slope(y, x) FROM test";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["4", "4.571428571428571", "2", "8", "1", "2"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_approx_percentile_cont() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("g", DataType::Utf8, false),
+        Field::new("v", DataType::Int32, true),
+    ]));
+    let partition = |groups: Vec<&str>, values: Vec<Option<i32>>| {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(groups)),
+                Arc::new(Int32Array::from(values)),
+            ],
+        )
+    };
+    let table = MemTable::try_new(
+        schema.clone(),
+        vec![
+            vec![partition(
+                vec!["a", "b", "a"],
+                vec![Some(1), Some(2), Some(3)],
+            )?],
+            vec![partition(
+                vec!["b", "a", "b", "a"],
+                vec![Some(4), Some(5), Some(6), None],
+            )?],
+        ],
+    )?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+
+    let sql = "SELECT g, approx_percentile_cont(v, 0.5) FROM test GROUP BY g ORDER BY g";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual, vec![vec!["a", "3"], vec!["b", "4"]]);
+
+    // the digests of the groups are merged by a separate query
+    let sql = "SELECT g, approx_percentile_cont_digest(v) AS digest FROM test GROUP BY g";
+    let digests = execute_to_batches(&mut ctx, sql).await;
+    assert_eq!(digests[0].schema().field(1).data_type(), &DataType::Binary);
+    let table = MemTable::try_new(digests[0].schema(), vec![digests])?;
+    ctx.register_table("digests", Arc::new(table))?;
+    let sql = "SELECT approx_percentile_cont_merge(digest, 0), \
+               approx_percentile_cont_merge(digest, 0.5), \
+               approx_percentile_cont_merge(digest, 1) FROM digests";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual, vec![vec!["1", "3.5", "6"]]);
+
+    // merging the digests without a fraction gives their merged digest
+    let sql = "SELECT approx_percentile_cont_merge(digest) AS digest FROM digests";
+    let merged = execute_to_batches(&mut ctx, sql).await;
+    let table = MemTable::try_new(merged[0].schema(), vec![merged])?;
+    ctx.register_table("merged", Arc::new(table))?;
+    let sql = "SELECT approx_percentile_cont_merge(digest, 0.5) FROM merged";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual, vec![vec!["3.5"]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_string_agg() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("c1", DataType::Utf8, true),
+        Field::new("c2", DataType::Int32, false),
+    ]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec![
+                Some("a"),
+                Some("b"),
+                None,
+                Some("c"),
+                Some("d"),
+                None,
+            ])),
+            Arc::new(Int32Array::from(vec![1, 1, 2, 2, 1, 3])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    // a single partition keeps the order of the input rows
+    let mut ctx =
+        ExecutionContext::with_config(ExecutionConfig::new().with_concurrency(1));
+    ctx.register_table("test", Arc::new(table))?;
+
+    let sql = "SELECT c2, string_agg(c1, ', ') AS s FROM test \
+               WHERE c1 <> 'b' OR c1 IS NULL GROUP BY c2";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+------+",
+        "| c2 | s    |",
+        "+----+------+",
+        "| 1  | a, d |",
+        "| 2  | c    |",
+        "| 3  |      |",
+        "+----+------+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    let actual = ctx
+        .table("test")?
+        .aggregate(
+            vec![col("c2")],
+            vec![
+                string_agg(col("c1"), lit("-"), vec![col("c1").sort(false, true)])
+                    .alias("s"),
+            ],
+        )?
+        .collect()
+        .await?;
+    let expected = vec![
+        "+----+-------+",
+        "| c2 | s     |",
+        "+----+-------+",
+        "| 1  | d-b-a |",
+        "| 2  | c     |",
+        "| 3  |       |",
+        "+----+-------+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_first_last() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("g", DataType::Utf8, false),
+        Field::new("ts", DataType::Int64, false),
+        Field::new("v", DataType::Int32, true),
+    ]));
+    let partition = |g: Vec<&str>, ts: Vec<i64>, v: Vec<Option<i32>>| {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(g)),
+                Arc::new(Int64Array::from(ts)),
+                Arc::new(Int32Array::from(v)),
+            ],
+        )
+    };
+    let table = MemTable::try_new(
+        schema.clone(),
+        vec![
+            vec![partition(
+                vec!["a", "b", "a"],
+                vec![3, 1, 1],
+                vec![Some(30), Some(10), Some(11)],
+            )?],
+            vec![partition(vec!["a", "b"], vec![2, 5], vec![Some(20), None])?],
+        ],
+    )?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+
+    // the rows of each group are in both partitions, whose partial states are merged
+    let ts = || vec![col("ts").sort(true, false)];
+    let actual = ctx
+        .table("test")?
+        .aggregate(
+            vec![col("g")],
+            vec![
+                first(col("v"), ts()).alias("earliest"),
+                last(col("v"), ts()).alias("latest"),
+            ],
+        )?
+        .collect()
+        .await?;
+    let expected = vec![
+        "+---+----------+--------+",
+        "| g | earliest | latest |",
+        "+---+----------+--------+",
+        "| a | 11       | 30     |",
+        "| b | 10       |        |",
+        "+---+----------+--------+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    // the first row is arbitrary without ORDER BY
+    let err = ctx
+        .table("test")?
+        .aggregate(vec![col("g")], vec![first(col("v"), vec![])])?
+        .collect()
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error during planning: FIRST requires ORDER BY"
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_count() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -1042,6 +1444,70 @@ async fn csv_query_window_with_order_by() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_top_n_per_group() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("g", DataType::Utf8, false),
+        Field::new("score", DataType::Int64, false),
+        Field::new("player", DataType::Utf8, false),
+    ]));
+    let partition = |g: Vec<&str>, score: Vec<i64>, player: Vec<&str>| {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(g)),
+                Arc::new(Int64Array::from(score)),
+                Arc::new(StringArray::from(player)),
+            ],
+        )
+    };
+    let table = MemTable::try_new(
+        schema.clone(),
+        vec![
+            vec![partition(
+                vec!["a", "b", "a"],
+                vec![10, 7, 30],
+                vec!["p1", "p2", "p3"],
+            )?],
+            vec![partition(
+                vec!["a", "b", "a", "b"],
+                vec![20, 5, 5, 9],
+                vec!["p4", "p5", "p6", "p7"],
+            )?],
+        ],
+    )?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("scores", Arc::new(table))?;
+
+    let sql = "SELECT g, player, rn FROM (\
+               SELECT g, player, row_number() OVER (PARTITION BY g ORDER BY score DESC) AS rn \
+               FROM scores) AS t \
+               WHERE rn <= 2";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let physical_plan = ctx.create_physical_plan(&plan)?;
+    let displayed = format!("{}", displayable(physical_plan.as_ref()).indent());
+    assert!(
+        displayed.contains("GroupedTopKExec") && !displayed.contains("WindowAggExec"),
+        "{}",
+        displayed
+    );
+
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+--------+----+",
+        "| g | player | rn |",
+        "+---+--------+----+",
+        "| a | p3     | 1  |",
+        "| a | p4     | 2  |",
+        "| b | p2     | 2  |",
+        "| b | p7     | 1  |",
+        "+---+--------+----+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_group_by_int_count() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -1325,6 +1791,43 @@ async fn union_all() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn union_all_coerces_types() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let sql = "SELECT * FROM (SELECT CAST(1 AS INT) AS x, 'a' AS y \
+               UNION ALL SELECT CAST(2 AS BIGINT) AS x, 'b' AS y \
+               UNION ALL SELECT 2.5 AS x, 'c' AS y) ORDER BY x";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["1", "a"], vec!["2", "b"], vec!["2.5", "c"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT 1 AS x UNION ALL SELECT 'a' AS x";
+    let err = ctx.create_logical_plan(sql).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error during planning: UNION can not combine the column x of type Utf8 with Int64"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn csv_union_distinct() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let sql = "SELECT * FROM (SELECT 1 AS x, 'a' AS y \
+               UNION SELECT 2 AS x, 'b' AS y \
+               UNION SELECT 1 AS x, 'a' AS y \
+               UNION ALL SELECT 2 AS x, 'b' AS y) ORDER BY x";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["1", "a"], vec!["2", "b"], vec!["2", "b"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT c1 FROM aggregate_test_100 UNION SELECT c1 FROM aggregate_test_100";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual.len(), 5);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_union_all() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -1582,6 +2085,40 @@ async fn csv_query_limit_zero() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_order_by_limit_offset() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Int32, false)]));
+    let partition = |values: Vec<i32>| {
+        RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(values))])
+            .map(|batch| vec![batch])
+    };
+    let table = MemTable::try_new(
+        schema.clone(),
+        vec![
+            partition(vec![9, 1, 7, 3])?,
+            partition(vec![8, 2, 6, 4, 0])?,
+        ],
+    )?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+
+    let sql = "SELECT c1 FROM test ORDER BY c1 DESC LIMIT 3 OFFSET 2";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["7"], vec!["6"], vec!["4"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT c1 FROM test ORDER BY c1 LIMIT 2 OFFSET 6";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["7"], vec!["8"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT c1 FROM test ORDER BY c1 OFFSET 7";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["8"], vec!["9"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_create_external_table() {
     let mut ctx = ExecutionContext::new();
@@ -1629,14 +2166,69 @@ async fn csv_query_external_table_sum() {
 }
 
 #[tokio::test]
-async fn csv_query_count_star() {
+async fn query_arithmetic_overflow() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("c1", DataType::Int16, false),
+        Field::new("c2", DataType::Int64, false),
+    ]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int16Array::from(vec![30000, 30000])),
+            Arc::new(Int64Array::from(vec![i64::MAX, 1])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
     let mut ctx = ExecutionContext::new();
-    register_aggregate_csv_by_sql(&mut ctx).await;
-    let sql = "SELECT COUNT(*) FROM aggregate_test_100";
-    let actual = execute(&mut ctx, sql).await;
-    let expected = vec![vec!["100"]];
-    assert_eq!(expected, actual);
-}
+    ctx.register_table("test", Arc::new(table))?;
+
+    // SUM of small integers is computed in its 64 bits result type
+    let actual = execute(&mut ctx, "SELECT SUM(c1) FROM test").await;
+    assert_eq!(vec![vec!["60000"]], actual);
+
+    ctx.sql("SET datafusion.execution.arithmetic_overflow = 'error'")?;
+    for (sql, expected) in [
+        (
+            "SELECT SUM(c2) FROM test",
+            "Arithmetic overflow: SUM is out of range of Int64",
+        ),
+        (
+            "SELECT c1 + c1 FROM test",
+            "Arithmetic overflow: 30000 + 30000 is out of range of Int16",
+        ),
+    ] {
+        let err = ctx.sql(sql)?.collect().await.unwrap_err();
+        assert_eq!(err.to_string(), format!("Execution error: {}", expected));
+    }
+
+    ctx.sql("SET datafusion.execution.arithmetic_overflow = 'promote'")?;
+    let sql = "SELECT c1 + c1, c2 * 2 FROM test";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["60000", "18446744073709551614"], vec!["60000", "2"]];
+    assert_eq!(expected, actual);
+    let actual = execute(&mut ctx, "SELECT SUM(c2) FROM test").await;
+    assert_eq!(vec![vec!["9223372036854775808"]], actual);
+
+    let err = ctx
+        .sql("SET datafusion.execution.arithmetic_overflow = 'saturate'")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error during planning: Unknown arithmetic overflow handling 'saturate', \
+         expected one of unchecked, error, promote"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn csv_query_count_star() {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv_by_sql(&mut ctx).await;
+    let sql = "SELECT COUNT(*) FROM aggregate_test_100";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["100"]];
+    assert_eq!(expected, actual);
+}
 
 #[tokio::test]
 async fn csv_query_count_one() {
@@ -1772,6 +2364,37 @@ async fn equijoin_and_other_condition() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn equijoin_on_expressions() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let equivalent_sql = [
+        "SELECT t1_id, t1_name, t2_name FROM t1 JOIN t2 ON t1_id + 11 = t2_id + 0 ORDER BY t1_id",
+        "SELECT t1_id, t1_name, t2_name FROM t1 JOIN t2 ON t2_id + 0 = t1_id + 11 ORDER BY t1_id",
+        "SELECT t1_id, t1_name, t2_name FROM t1 JOIN t2 ON t2_id * 1 = t1.t1_id + 11 AND t1_name = t1_name ORDER BY t1_id",
+    ];
+    let expected = vec![
+        vec!["11", "a", "y"],
+        vec!["33", "c", "x"],
+        vec!["44", "d", "w"],
+    ];
+    for sql in equivalent_sql.iter() {
+        let actual = execute(&mut ctx, sql).await;
+        assert_eq!(expected, actual);
+    }
+
+    // expression keys are computed below the join instead of filtering a cross join
+    let plan = ctx.create_logical_plan(equivalent_sql[0])?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = format!("{:?}", plan);
+    assert!(
+        plan.contains("Join: #__join_key_l_0 = #__join_key_r_0"),
+        "{}",
+        plan
+    );
+    assert!(!plan.contains("CrossJoin"), "{}", plan);
+    Ok(())
+}
+
 #[tokio::test]
 async fn equijoin_and_unsupported_condition() -> Result<()> {
     let ctx = create_join_context("t1_id", "t2_id")?;
@@ -2779,6 +3402,26 @@ async fn query_array() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_filtered_rows_are_not_evaluated() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![Field::new("c", DataType::Int32, false)]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(Int32Array::from(vec![1, 0, 2, 5, 10]))],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", Arc::new(table))?;
+    // most rows pass the filter, so it is deferred to the projection and aggregation
+    let actual = execute(&mut ctx, "SELECT 10 / c FROM t WHERE c <> 0").await;
+    let expected = vec![vec!["10"], vec!["5"], vec!["2"], vec!["1"]];
+    assert_eq!(expected, actual);
+    let actual = execute(&mut ctx, "SELECT SUM(10 / c) FROM t WHERE c <> 0").await;
+    assert_eq!(vec![vec!["18"]], actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_sum_cast() {
     let mut ctx = ExecutionContext::new();
@@ -2833,6 +3476,25 @@ async fn like() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_avg_timestamp() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("ts_data", make_timestamp_nano_table()?)?;
+    ctx.register_table("ts_secs", make_timestamp_table::<TimestampSecondType>()?)?;
+
+    let sql = "SELECT AVG(ts) FROM ts_data";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["2020-09-08 12:42:29.190855"]], actual);
+
+    let sql = "SELECT AVG(ts) FROM ts_secs WHERE value > 1";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["2020-09-08 12:12:29"]], actual);
+
+    let sql = "SELECT SUM(ts) FROM ts_data";
+    assert!(ctx.create_logical_plan(sql).is_err());
+    Ok(())
+}
+
 fn make_timestamp_table<A>() -> Result<Arc<MemTable>>
 where
     A: ArrowTimestampType,
@@ -2934,6 +3596,52 @@ async fn to_timestamp_seconds() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn timestamps_of_different_units() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("ts_secs", make_timestamp_table::<TimestampSecondType>()?)?;
+    ctx.register_table(
+        "ts_millis",
+        make_timestamp_table::<TimestampMillisecondType>()?,
+    )?;
+    ctx.register_table("ts_nanos", make_timestamp_nano_table()?)?;
+
+    let sql = "SELECT COUNT(*) FROM ts_secs, ts_millis WHERE ts_secs.ts < ts_millis.ts";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["6"]], actual);
+
+    let sql = "SELECT s.value, n.value FROM ts_secs s \
+               JOIN (SELECT date_trunc('second', ts) AS ts, value FROM ts_nanos) n \
+               ON s.ts = n.ts ORDER BY s.value";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["1", "1"], vec!["2", "2"], vec!["3", "3"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn timestamps_out_of_nanosecond_range() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let sql = "SELECT to_timestamp_seconds('3000-01-01T00:00:00Z'), \
+               to_timestamp_micros('1500-06-01 12:00:00')";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["3000-01-01 00:00:00", "1500-06-01 12:00:00"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT to_timestamp('3000-01-01T00:00:00Z')";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = ctx.create_physical_plan(&plan)?;
+    let err = collect(plan).await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("out of the range of Nanosecond timestamps"),
+        "{}",
+        err
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn count_distinct_timestamps() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -3022,6 +3730,92 @@ async fn query_count_distinct() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_grouping_with_cube() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("c1", DataType::Utf8, false),
+        Field::new("c2", DataType::Int32, false),
+        Field::new("c3", DataType::Int64, false),
+    ]));
+
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "a", "b"])),
+            Arc::new(Int32Array::from(vec![1, 2, 1])),
+            Arc::new(Int64Array::from(vec![10, 20, 30])),
+        ],
+    )?;
+
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+    let sql = "SELECT c1, c2, GROUPING(c1, c2) AS g, SUM(c3) FROM test \
+               GROUP BY CUBE(c1, c2) ORDER BY g, c1, c2";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["a", "1", "0", "10"],
+        vec!["a", "2", "0", "20"],
+        vec!["b", "1", "0", "30"],
+        vec!["a", "NULL", "1", "30"],
+        vec!["b", "NULL", "1", "30"],
+        vec!["NULL", "1", "2", "40"],
+        vec!["NULL", "2", "2", "20"],
+        vec!["NULL", "NULL", "3", "60"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_count_distinct_columns() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("c1", DataType::Int32, true),
+        Field::new("c2", DataType::Utf8, true),
+        Field::new("c3", DataType::Utf8, false),
+    ]));
+
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![
+                Some(1),
+                Some(1),
+                Some(1),
+                None,
+                Some(2),
+                Some(2),
+            ])),
+            Arc::new(StringArray::from(vec![
+                Some("a"),
+                Some("a"),
+                Some("b"),
+                Some("a"),
+                None,
+                Some("a"),
+            ])),
+            Arc::new(StringArray::from(vec!["x", "y", "x", "x", "y", "y"])),
+        ],
+    )?;
+
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+    // rows with a NULL in any of the columns are not counted
+    let sql = "SELECT COUNT(DISTINCT c1, c2) FROM test";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["3".to_string()]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT c3, COUNT(DISTINCT c1, c2) FROM test GROUP BY c3 ORDER BY c3";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["x", "2"], vec!["y", "2"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn query_on_string_dictionary() -> Result<()> {
     // Test to ensure DataFusion can operate on dictionary types
@@ -3328,6 +4122,59 @@ async fn test_boolean_expressions() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_null_expressions() -> Result<()> {
+    test_expression!("NULL + 1", "NULL");
+    test_expression!("2.5 * NULL", "NULL");
+    test_expression!("1 < NULL", "NULL");
+    test_expression!("NULL = NULL", "NULL");
+    test_expression!("NULL AND false", "false");
+    test_expression!("NULL OR true", "true");
+    test_expression!("NOT NULL", "NULL");
+    test_expression!("NULL IS NULL", "true");
+    test_expression!("CAST(NULL AS INT) IS NULL", "true");
+    test_expression!("CASE WHEN 1 > 2 THEN NULL ELSE 3 END", "3");
+    test_expression!("1 IN (NULL, 2)", "NULL");
+    test_expression!("upper(NULL)", "NULL");
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_null_literal_types() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Float64, true)]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(Float64Array::from(vec![Some(1.0), Some(2.0)]))],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+    let sql = "SELECT NULL + c1 AS a, c1 = NULL AS b, \
+               CASE WHEN c1 > 1 THEN NULL ELSE c1 END AS c FROM test";
+    let plan = ctx.create_logical_plan(sql)?;
+    let types = plan
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.data_type().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        vec![DataType::Float64, DataType::Boolean, DataType::Float64]
+    );
+
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["NULL", "NULL", "1"], vec!["NULL", "NULL", "NULL"]];
+    assert_eq!(expected, actual);
+
+    // an untyped NULL GROUP BY key
+    let sql = "SELECT n, COUNT(*) FROM (SELECT NULL AS n, c1 FROM test) GROUP BY n";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["NULL", "2"]], actual);
+    Ok(())
+}
+
 #[tokio::test]
 #[cfg_attr(not(feature = "crypto_expressions"), ignore)]
 // #[ignore = "Scalar binary arrives later in commits"] // TODO: try it out
@@ -3359,6 +4206,48 @@ async fn test_crypto_expressions() -> Result<()> {
     test_expression!("sha512('tom')", "6e1b9b3fe840680e37051f7ad5e959d6f39ad0f8885d855166f55c659469d3c8b78118c44a2a49c72ddb481cd6d8731034e11cc030070ba843a90b3495cb8d3e");
     test_expression!("sha512('')", "cf83e1357eefb8bdf1542850d66d8007d620e4050b5715dc83f4a921d36ce9ce47d0d13c5d85f2b0ff8318d2877eec2f63b931bd47417a81a538327af927da3e");
     test_expression!("sha512(NULL)", "NULL");
+    test_expression!("sha1('tom')", "96835dd8bfa718bd6447ccc87af89ae1675daeca");
+    test_expression!("sha1(NULL)", "NULL");
+    test_expression!(
+        "sha2('tom', 256)",
+        "e1608f75c5d7813f3d4031cb30bfb786507d98137538ff8e128a6ff74e84e643"
+    );
+    test_expression!(
+        "sha2('tom', 0)",
+        "e1608f75c5d7813f3d4031cb30bfb786507d98137538ff8e128a6ff74e84e643"
+    );
+    test_expression!("sha2('tom', 1)", "NULL");
+    test_expression!("crc32('tom')", "2111795987");
+    test_expression!("crc32(NULL)", "NULL");
+    test_expression!("xxhash64('tom')", "-3637207724263670644");
+    test_expression!("xxhash64('')", "-1205034819632174695");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_inet_expressions() -> Result<()> {
+    test_expression!("inet_aton('10.0.0.1')", "167772161");
+    test_expression!("inet_aton('10.0.0')", "NULL");
+    test_expression!("inet_ntoa(167772161)", "10.0.0.1");
+    test_expression!("inet_ntoa(inet_aton('192.168.1.20'))", "192.168.1.20");
+    test_expression!("cidr_contains('10.1.2.3', '10.0.0.0/8')", "true");
+    test_expression!("cidr_contains('192.168.1.1', '10.0.0.0/8')", "false");
+    test_expression!("cidr_contains('2001:db8::1', '2001:db8::/32')", "true");
+    test_expression!("cidr_contains(NULL, '10.0.0.0/8')", "NULL");
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg_attr(not(feature = "encoding_expressions"), ignore)]
+async fn test_encoding_expressions() -> Result<()> {
+    test_expression!("encode('tom', 'base64')", "dG9t");
+    test_expression!("encode('tom', 'hex')", "746f6d");
+    test_expression!("encode(NULL, 'hex')", "NULL");
+    test_expression!("encode(decode('dG9t', 'base64'), 'hex')", "746f6d");
+    test_expression!("encode(decode('746F6D', 'hex'), 'base64')", "dG9t");
+    test_expression!("to_hex(from_hex('746f6d'))", "746f6d");
+    test_expression!("from_hex(NULL)", "NULL");
+    test_expression!("to_hex(255)", "ff");
     Ok(())
 }
 
@@ -3501,6 +4390,30 @@ async fn test_interval_expressions() -> Result<()> {
         "interval '2' year",
         "2 years 0 mons 0 days 0 hours 0 mins 0.00 secs"
     );
+    test_expression!(
+        "interval '1 year 2 months'",
+        "1 years 2 mons 0 days 0 hours 0 mins 0.00 secs"
+    );
+    test_expression!(
+        "interval '3 days 04:05:06'",
+        "0 years 0 mons 3 days 4 hours 5 mins 6.00 secs"
+    );
+    test_expression!(
+        "interval '04:05:06.5'",
+        "0 years 0 mons 0 days 4 hours 5 mins 6.500 secs"
+    );
+    test_expression!(
+        "interval '90' second",
+        "0 years 0 mons 0 days 0 hours 1 mins 30.00 secs"
+    );
+    test_expression!(
+        "interval '1 day -1 hour'",
+        "0 years 0 mons 0 days 23 hours 0 mins 0.00 secs"
+    );
+    test_expression!(
+        "interval '2 hrs 30 mins'",
+        "0 years 0 mons 0 days 2 hours 30 mins 0.00 secs"
+    );
     Ok(())
 }
 
@@ -3580,6 +4493,62 @@ async fn test_string_expressions() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_string_array_conversions() -> Result<()> {
+    test_expression!(
+        "array_to_string(string_to_array('a,b,,c', ','), '|')",
+        "a|b||c"
+    );
+    test_expression!("array_join(string_to_array('xyz', NULL), '-')", "x-y-z");
+    test_expression!(
+        "array_to_string(string_to_array('a,*,c', ',', '*'), ',')",
+        "a,c"
+    );
+    test_expression!(
+        "array_to_string(string_to_array('a,*,c', ',', '*'), ',', 'N')",
+        "a,N,c"
+    );
+    test_expression!("array_to_string(string_to_array('', ','), ',')", "");
+    test_expression!("string_to_array(NULL, ',')", "NULL");
+    test_expression!("array_to_string(string_to_array('a,b', ','), NULL)", "NULL");
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_higher_order_functions() -> Result<()> {
+    let ctx = ExecutionContext::new();
+    let err = ctx
+        .create_logical_plan("SELECT array_transform(array(1, 2), 1)")
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error during planning: array_transform takes a lambda, which is only \
+         available through the DataFrame API"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_conditional_functions() -> Result<()> {
+    test_expression!("ifnull('a', 'b')", "a");
+    test_expression!("nvl(CAST(NULL AS VARCHAR), 'b')", "b");
+    test_expression!("nvl2('a', 'y', 'n')", "y");
+    test_expression!("nvl2(CAST(NULL AS INT), 'y', 'n')", "n");
+    // the results are cast to their common type
+    test_expression!("nvl(CAST(NULL AS INT), 0)", "0");
+    test_expression!("nvl(CAST(1 AS INT), 0)", "1");
+    test_expression!("ifnull(CAST(NULL AS INT), 2.5)", "2.5");
+    test_expression!("nvl2('a', 1, CAST(2 AS SMALLINT))", "1");
+    test_expression!("decode('b', 'a', 'x', 'b', 'y', 'z')", "y");
+    test_expression!("decode('c', 'a', 'x', 'b', 'y', 'z')", "z");
+    test_expression!("decode('c', 'a', 'x')", "NULL");
+    test_expression!(
+        "decode(CAST(NULL AS VARCHAR), 'a', 'x', NULL, 'null', 'z')",
+        "null"
+    );
+    Ok(())
+}
+
 #[tokio::test]
 #[cfg_attr(not(feature = "unicode_expressions"), ignore)]
 async fn test_unicode_expressions() -> Result<()> {
@@ -3651,6 +4620,15 @@ async fn test_unicode_expressions() -> Result<()> {
     test_expression!("substr('alphabet', 3, 20)", "phabet");
     test_expression!("substr('alphabet', CAST(NULL AS int), 20)", "NULL");
     test_expression!("substr('alphabet', 3, CAST(NULL AS int))", "NULL");
+    test_expression!("substr('joséésoj', 4, 2)", "éé");
+    test_expression!("substr('joséésoj', 5)", "ésoj");
+    test_expression!("upper('éclair')", "ÉCLAIR");
+    test_expression!("lower('ÉCLAIR')", "éclair");
+    test_expression!(
+        "collation_key('Éclair', 'case_accent_insensitive')",
+        "eclair"
+    );
+    test_expression!("collation_key('Éclair', 'case_insensitive')", "éclair");
     test_expression!("translate('12345', '143', 'ax')", "a2x5");
     test_expression!("translate(NULL, '143', 'ax')", "NULL");
     test_expression!("translate('12345', NULL, 'ax')", "NULL");
@@ -3658,6 +4636,99 @@ async fn test_unicode_expressions() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+#[cfg_attr(not(feature = "unicode_expressions"), ignore)]
+async fn test_collation() -> Result<()> {
+    let table = || -> Result<Arc<MemTable>> {
+        let schema = Arc::new(Schema::new(vec![Field::new("c", DataType::Utf8, false)]));
+        let data = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from(vec![
+                "b", "Éclair", "a", "B", "eclair", "A",
+            ]))],
+        )?;
+        Ok(Arc::new(MemTable::try_new(schema, vec![vec![data]])?))
+    };
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", table()?)?;
+
+    let sql =
+        "SELECT c FROM t WHERE c COLLATE case_accent_insensitive = 'ECLAIR' ORDER BY c";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["eclair"], vec!["Éclair"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT c COLLATE case_insensitive, COUNT(*) FROM t \
+        GROUP BY c COLLATE case_insensitive ORDER BY 1";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["a", "2"],
+        vec!["b", "2"],
+        vec!["eclair", "1"],
+        vec!["éclair", "1"],
+    ];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT c FROM t WHERE c IN ('a', 'B', 'eclair') ORDER BY c";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["B"], vec!["a"], vec!["eclair"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT c FROM t WHERE c COLLATE binary = c COLLATE case_insensitive";
+    assert!(ctx.create_logical_plan(sql).is_err());
+
+    // the session collation applies to comparisons, including IN, BETWEEN and join
+    // conditions, ORDER BY, GROUP BY and DISTINCT
+    let config = ExecutionConfig::new().with_collation(Collation::CaseInsensitive);
+    let mut ctx = ExecutionContext::with_config(config);
+    ctx.register_table("t", table()?)?;
+
+    let sql = "SELECT c FROM t WHERE c IN ('a', 'B', 'eclair') \
+        ORDER BY c, c COLLATE binary";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["A"], vec!["a"], vec!["B"], vec!["b"], vec!["eclair"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT COUNT(*) FROM t WHERE c = 'ÉCLAIR'";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["1"]], actual);
+
+    let sql = "SELECT COUNT(*) FROM t WHERE c BETWEEN 'A' AND 'b'";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["4"]], actual);
+
+    ctx.register_table("u", table()?)?;
+    let sql = "SELECT COUNT(*) FROM t JOIN u ON t.c = u.c";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["10"]], actual);
+
+    // groups show their smallest original value
+    let sql = "SELECT c, COUNT(*) FROM t GROUP BY c HAVING COUNT(c) > 0 ORDER BY c";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["A", "2"],
+        vec!["B", "2"],
+        vec!["eclair", "1"],
+        vec!["Éclair", "1"],
+    ];
+    assert_eq!(expected, actual);
+
+    let results =
+        execute_to_batches(&mut ctx, "SELECT DISTINCT c FROM t ORDER BY c").await;
+    let expected = vec![
+        "+--------+",
+        "| c      |",
+        "+--------+",
+        "| A      |",
+        "| B      |",
+        "| eclair |",
+        "| Éclair |",
+        "+--------+",
+    ];
+    assert_batches_eq!(expected, &results);
+    Ok(())
+}
+
 #[tokio::test]
 #[cfg_attr(not(feature = "regex_expressions"), ignore)]
 async fn test_regex_expressions() -> Result<()> {
@@ -3707,6 +4778,59 @@ async fn test_extract_date_part() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_extract_day_and_epoch_parts() -> Result<()> {
+    test_expression!("date_part('doy', CAST('2020-12-31' AS DATE))", "366");
+    test_expression!("date_part('dow', to_timestamp('2021-01-03T10:00:00'))", "0");
+    test_expression!("date_part('DOW', CAST('2021-01-09' AS DATE))", "6");
+    test_expression!(
+        "date_part('epoch', to_timestamp('2020-09-08T12:00:00+00:00'))",
+        "1599566400"
+    );
+    test_expression!("date_part('epoch', CAST('1969-12-31' AS DATE))", "-86400");
+    test_expression!(
+        "date_part('epoch', to_timestamp('2040-01-01T00:00:00+00:00'))",
+        "2208988800"
+    );
+    test_expression!("date_part('doy', NULL)", "NULL");
+
+    // only the epoch is an Int64, the other parts remain Int32
+    let mut ctx = ExecutionContext::new();
+    let sql = "SELECT date_part('hour', to_timestamp('2040-01-01T00:00:00')), \
+               date_part('EPOCH', to_timestamp('2040-01-01T00:00:00'))";
+    let batches = execute_to_batches(&mut ctx, sql).await;
+    let schema = batches[0].schema();
+    assert_eq!(schema.field(0).data_type(), &DataType::Int32);
+    assert_eq!(schema.field(1).data_type(), &DataType::Int64);
+
+    test_expression!("date_part('quarter', CAST('2020-08-31' AS DATE))", "3");
+    test_expression!("date_part('isodow', CAST('2021-01-03' AS DATE))", "7");
+    test_expression!("date_part('isoyear', CAST('2021-01-03' AS DATE))", "2020");
+    test_expression!(
+        "date_part('minute', to_timestamp('2020-09-08T12:34:56'))",
+        "34"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_week_functions() -> Result<()> {
+    test_expression!("week(to_timestamp('2008-02-20T10:00:00'))", "7");
+    test_expression!("week(to_timestamp('2008-02-20T10:00:00'), 1)", "8");
+    test_expression!("week(CAST('2000-01-01' AS DATE), 2)", "52");
+    test_expression!("week(CAST('2000-01-01' AS DATE), NULL)", "NULL");
+    test_expression!("yearweek(CAST('1987-01-01' AS DATE))", "198652");
+    test_expression!("yearweek(CAST('2024-12-30' AS DATE), 3)", "202501");
+    test_expression!("isoweek(to_timestamp('2021-01-03T10:00:00'))", "53");
+    test_expression!("date_part('week', CAST('2021-01-03' AS DATE))", "53");
+    test_expression!("date_part('isoweek', CAST('2021-01-04' AS DATE))", "1");
+    test_expression!(
+        "date_part('yearweek', to_timestamp('2024-12-30T10:00:00'))",
+        "202452"
+    );
+    Ok(())
+}
+
 #[tokio::test]
 // #[ignore = "Naive IN list or implementation"] // TODO: try it out
 async fn test_in_list_scalar() -> Result<()> {
@@ -3865,6 +4989,37 @@ async fn test_cast_expressions() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_lenient_cast() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Utf8, true)]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(StringArray::from(vec![
+            Some("1"),
+            Some("x"),
+            None,
+        ]))],
+    )?;
+    let sql = "SELECT CAST(c1 AS INT) FROM test";
+
+    let mut ctx = ExecutionContext::new();
+    let table = MemTable::try_new(schema.clone(), vec![vec![data.clone()]])?;
+    ctx.register_table("test", Arc::new(table))?;
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = ctx.create_physical_plan(&plan)?;
+    let err = collect(plan).await.unwrap_err().to_string();
+    assert!(err.contains("row 1: 'x'"), "{}", err);
+
+    let mut ctx =
+        ExecutionContext::with_config(ExecutionConfig::new().with_lenient_cast(true));
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    ctx.register_table("test", Arc::new(table))?;
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual, vec![vec!["1"], vec!["NULL"], vec!["NULL"]]);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_current_timestamp_expressions() -> Result<()> {
     let t1 = chrono::Utc::now().timestamp();
@@ -3912,6 +5067,35 @@ async fn test_current_timestamp_expressions_non_optimized() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_current_date_and_time_expressions() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    ctx.sql("SET datafusion.execution.time_zone = '+05:00'")?;
+    let sql =
+        "SELECT current_date, localtimestamp(0), now(0), current_time, localtime(3)";
+    let actual = execute(&mut ctx, sql).await;
+    let row = &actual[0];
+
+    let parse = |s: &str| {
+        chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f").unwrap()
+    };
+    let local = parse(&row[1]);
+    assert_eq!(local - parse(&row[2]), chrono::Duration::hours(5));
+    assert_eq!(row[0], local.date().to_string());
+    for time in &row[3..] {
+        let time = chrono::NaiveTime::parse_from_str(time, "%H:%M:%S%.f").unwrap();
+        let diff = (time - local.time()).num_milliseconds().abs();
+        assert!(diff <= 1000 || diff >= 86_399_000, "{} {}", time, local);
+    }
+
+    let err = ctx
+        .create_logical_plan("SELECT now(10)")
+        .and_then(|plan| ctx.optimize(&plan))
+        .unwrap_err();
+    assert!(err.to_string().contains("between 0 and 9"), "{}", err);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_random_expression() -> Result<()> {
     let mut ctx = create_ctx()?;
@@ -3923,6 +5107,37 @@ async fn test_random_expression() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_uuid_expression() -> Result<()> {
+    let mut ctx = create_ctx()?;
+    let sql = "SELECT uuid() u1, gen_random_uuid() u2";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual[0][0].len(), 36);
+    assert_eq!(actual[0][1].len(), 36);
+    assert_ne!(actual[0][0], actual[0][1]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_seeded_random_expressions() -> Result<()> {
+    let sql = "SELECT random() r, uuid() u FROM aggregate_test_100";
+    let mut results = vec![];
+    for _ in 0..2 {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .with_concurrency(1)
+                .with_random_seed(42),
+        );
+        register_aggregate_csv(&mut ctx)?;
+        results.push(execute(&mut ctx, sql).await);
+    }
+    assert_eq!(results[0].len(), 100);
+    assert_eq!(results[0], results[1]);
+    // values are generated per row rather than folded to a constant
+    assert_ne!(results[0][0], results[0][1]);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_cast_expressions_error() -> Result<()> {
     // sin(utf8) should error
@@ -4005,18 +5220,17 @@ async fn test_physical_plan_display_indent() {
 
     let physical_plan = ctx.create_physical_plan(&plan).unwrap();
     let expected = vec![
-        "GlobalLimitExec: limit=10",
-        "  SortExec: [the_min@2 DESC]",
-        "    CoalescePartitionsExec",
-        "      ProjectionExec: expr=[c1@0 as c1, MAX(aggregate_test_100.c12)@1 as MAX(c12), MIN(aggregate_test_100.c12)@2 as the_min]",
-        "        HashAggregateExec: mode=FinalPartitioned, gby=[c1@0 as c1], aggr=[MAX(c12), MIN(c12)]",
-        "          CoalesceBatchesExec: target_batch_size=4096",
-        "            RepartitionExec: partitioning=Hash([Column { name: \"c1\", index: 0 }], 3)",
-        "              HashAggregateExec: mode=Partial, gby=[c1@0 as c1], aggr=[MAX(c12), MIN(c12)]",
-        "                CoalesceBatchesExec: target_batch_size=4096",
-        "                  FilterExec: c12@1 < CAST(10 AS Float64)",
-        "                    RepartitionExec: partitioning=RoundRobinBatch(3)",
-        "                      CsvExec: source=Path(ARROW_TEST_DATA/csv/aggregate_test_100.csv: [ARROW_TEST_DATA/csv/aggregate_test_100.csv]), has_header=true",
+        "SortPreservingMergeExec: [the_min@2 DESC], fetch=10",
+        "  SortExec: [the_min@2 DESC], fetch=10",
+        "    ProjectionExec: expr=[c1@0 as c1, MAX(aggregate_test_100.c12)@1 as MAX(c12), MIN(aggregate_test_100.c12)@2 as the_min]",
+        "      HashAggregateExec: mode=FinalPartitioned, gby=[c1@0 as c1], aggr=[MAX(c12), MIN(c12)]",
+        "        CoalesceBatchesExec: target_batch_size=4096",
+        "          RepartitionExec: partitioning=Hash([Column { name: \"c1\", index: 0 }], 3)",
+        "            HashAggregateExec: mode=Partial, gby=[c1@0 as c1], aggr=[MAX(c12), MIN(c12)]",
+        "              CoalesceBatchesExec: target_batch_size=4096",
+        "                FilterExec: c12@1 < CAST(10 AS Float64)",
+        "                  RepartitionExec: partitioning=RoundRobinBatch(3)",
+        "                    CsvExec: source=Path(ARROW_TEST_DATA/csv/aggregate_test_100.csv: [ARROW_TEST_DATA/csv/aggregate_test_100.csv]), has_header=true",
     ];
 
     let data_path = datafusion::test_util::arrow_test_data();
@@ -4096,6 +5310,163 @@ async fn test_aggregation_with_bad_arguments() -> Result<()> {
     Ok(())
 }
 
+/// Executes `sql` with the logical optimizer batch `batch` disabled and then enabled,
+/// checks that both return the same rows and returns the two optimized plans
+async fn execute_with_optimizer_batch(
+    batch: &str,
+    sql: &str,
+) -> Result<(LogicalPlan, LogicalPlan)> {
+    let mut plans = vec![];
+    let mut results = vec![];
+    for enabled in [false, true] {
+        let config = ExecutionConfig::new().with_optimizer_batch_enabled(batch, enabled);
+        let mut ctx = ExecutionContext::with_config(config);
+        register_aggregate_csv(&mut ctx)?;
+        let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+        plans.push(plan);
+        results.push(execute(&mut ctx, sql).await);
+    }
+    assert_eq!(results[0], results[1], "batch {} changed the result", batch);
+    let with = plans.pop().unwrap();
+    let without = plans.pop().unwrap();
+    Ok((without, with))
+}
+
+/// The type of the first join of `plan`, if any
+fn join_type(plan: &LogicalPlan) -> Option<JoinType> {
+    match plan {
+        LogicalPlan::Join { join_type, .. } => Some(*join_type),
+        plan => plan.inputs().into_iter().find_map(join_type),
+    }
+}
+
+#[tokio::test]
+async fn optimizer_column_pruning() -> Result<()> {
+    let sql = "SELECT c1, COUNT(*) FROM aggregate_test_100 WHERE c3 > 0 \
+               GROUP BY c1 ORDER BY c1";
+    let (without, with) = execute_with_optimizer_batch("column_pruning", sql).await?;
+    let (without, with) = (format!("{:?}", without), format!("{:?}", with));
+    // c3 is only needed by the filter and no longer reaches the aggregation
+    let pruned = "Projection: #aggregate_test_100.c1\n";
+    assert!(!without.contains(pruned), "{}", without);
+    assert!(with.contains(pruned), "{}", with);
+    Ok(())
+}
+
+#[tokio::test]
+async fn optimizer_merge_projections() -> Result<()> {
+    let sql = "SELECT x + 1 AS y \
+               FROM (SELECT c2 * 2 AS x FROM aggregate_test_100) AS t ORDER BY y";
+    let (without, with) = execute_with_optimizer_batch("merge_projections", sql).await?;
+    let (without, with) = (format!("{:?}", without), format!("{:?}", with));
+    assert_eq!(without.matches("Projection:").count(), 2, "{}", without);
+    assert_eq!(with.matches("Projection:").count(), 1, "{}", with);
+    Ok(())
+}
+
+#[tokio::test]
+async fn optimizer_join_elimination() -> Result<()> {
+    // the filter removes the rows the join pads with nulls
+    let sql = "SELECT a.c1, b.c2 FROM aggregate_test_100 AS a \
+               LEFT JOIN aggregate_test_100 AS b ON a.c9 = b.c9 \
+               WHERE b.c3 > 0 ORDER BY a.c1, b.c2";
+    let (without, with) = execute_with_optimizer_batch("join_elimination", sql).await?;
+    assert_eq!(join_type(&without), Some(JoinType::Left), "{:?}", without);
+    assert_eq!(join_type(&with), Some(JoinType::Inner), "{:?}", with);
+
+    // the right input has one row per key and none of its columns is used
+    let sql = "SELECT a.c1, a.c2 FROM aggregate_test_100 AS a \
+               LEFT JOIN (SELECT c1, MAX(c2) AS m FROM aggregate_test_100 GROUP BY c1) AS b \
+               ON a.c1 = b.c1 ORDER BY a.c1, a.c2";
+    let (without, with) = execute_with_optimizer_batch("join_elimination", sql).await?;
+    assert_eq!(join_type(&without), Some(JoinType::Left), "{:?}", without);
+    assert_eq!(join_type(&with), None, "{:?}", with);
+    Ok(())
+}
+
+#[tokio::test]
+async fn optimizer_merge_aggregates() -> Result<()> {
+    let sql = "SELECT c1, SUM(s), MAX(m) \
+               FROM (SELECT c1, c2, SUM(c4) AS s, MAX(c3) AS m \
+                     FROM aggregate_test_100 GROUP BY c1, c2) AS t \
+               GROUP BY c1 ORDER BY c1";
+    let (without, with) = execute_with_optimizer_batch("merge_aggregates", sql).await?;
+    let (without, with) = (format!("{:?}", without), format!("{:?}", with));
+    assert_eq!(without.matches("Aggregate:").count(), 2, "{}", without);
+    assert_eq!(with.matches("Aggregate:").count(), 1, "{}", with);
+    Ok(())
+}
+
+#[tokio::test]
+async fn optimizer_filter_elimination_after_insert() -> Result<()> {
+    let config =
+        ExecutionConfig::new().with_optimizer_batch_enabled("filter_elimination", true);
+    let mut ctx = ExecutionContext::with_config(config);
+    for sql in [
+        "CREATE TABLE t (a INT, b VARCHAR)",
+        "INSERT INTO t SELECT 1, 'x'",
+        "ANALYZE t",
+        // the analyzed maximum of a is now stale and must not eliminate the filter
+        "INSERT INTO t SELECT 100, 'z'",
+    ] {
+        ctx.sql(sql)?.collect().await?;
+    }
+    let actual = execute(&mut ctx, "SELECT * FROM t WHERE a > 50").await;
+    assert_eq!(actual, vec![vec!["100", "z"]]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn optimizer_predicate_inference() -> Result<()> {
+    let sql = "SELECT a.c1, b.c1 FROM aggregate_test_100 AS a \
+               JOIN aggregate_test_100 AS b ON a.c2 = b.c2 \
+               WHERE a.c2 > 3 ORDER BY a.c1, b.c1";
+    let (without, with) =
+        execute_with_optimizer_batch("predicate_inference", sql).await?;
+    let (without, with) = (format!("{:?}", without), format!("{:?}", with));
+    // the filter on a.c2 also holds for b.c2 and is pushed down to b
+    assert!(!without.contains("Filter: #b.c2"), "{}", without);
+    assert!(with.contains("Filter: #b.c2"), "{}", with);
+    Ok(())
+}
+
+#[tokio::test]
+async fn physical_nullability_matches_logical() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv(&mut ctx)?;
+    let queries = [
+        (
+            "SELECT abs(c3), upper(c1), random(), CASE WHEN c3 > 0 THEN c3 END, \
+             CASE WHEN c3 > 0 THEN c3 ELSE c3 END, sqrt(c12) FROM aggregate_test_100",
+            vec![false, false, false, true, false, true],
+        ),
+        (
+            "SELECT c1 FROM aggregate_test_100 UNION ALL \
+             SELECT CASE WHEN c3 > 0 THEN c1 END AS c1 FROM aggregate_test_100",
+            vec![true],
+        ),
+    ];
+    for (sql, expected) in queries {
+        let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+        let logical = plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.is_nullable())
+            .collect::<Vec<_>>();
+        let physical = ctx
+            .create_physical_plan(&plan)?
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.is_nullable())
+            .collect::<Vec<_>>();
+        assert_eq!(logical, expected, "{}", sql);
+        assert_eq!(physical, expected, "{}", sql);
+    }
+    Ok(())
+}
+
 // Normalizes parts of an explain plan that vary from run to run (such as path)
 fn normalize_for_explain(s: &str) -> String {
     // Convert things like /Users/alamb/Software/arrow/testing/data/csv/aggregate_test_100.csv